@@ -17,6 +17,12 @@ pub enum AnonCryptAlg {
     A256gcmEcdhEsA256kw,
 }
 
+/// Algorithms for authenticated encryption
+///
+/// ECDH-1PU key wrapping requires a compactly committing content encryption algorithm
+/// (draft-madden-jose-ecdh-1pu-04, section 2.1), so only A256CBC-HS512 is offered.
+/// A256GCM and XC20P are not committing and are rejected for authcrypt when packing
+/// and unpacking, they can only be used with anoncrypt.
 #[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub enum AuthCryptAlg {
     /// AES256-CBC + HMAC-SHA512 with a 512 bit key content encryption,
@@ -30,4 +36,6 @@ pub enum SignAlg {
     EdDSA,
     ES256,
     ES256K,
+    ES384,
+    ES512,
 }
@@ -4,11 +4,17 @@
 use crate::{
     error::{Error, ErrorKind, Result, ResultExt, ToResult, err_msg},
     jwk::FromJwkValue,
-    utils::crypto::{AsKnownKeyPair, AsKnownKeyPairSecret, KnownKeyAlg, KnownKeyPair},
+    utils::{
+        crypto::{AsKnownKeyPair, AsKnownKeyPairSecret, KnownKeyAlg, KnownKeyPair},
+        p521::P521KeyPair,
+    },
 };
 use affinidi_secrets_resolver::secrets::{Secret, SecretMaterial, SecretType};
 use askar_crypto::{
-    alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, x25519::X25519KeyPair},
+    alg::{
+        ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair,
+        x25519::X25519KeyPair,
+    },
     repr::{KeyPublicBytes, KeySecretBytes},
 };
 use base64::prelude::*;
@@ -117,6 +123,8 @@ impl AsKnownKeyPair for DIDVerificationMethod {
         match &jwk.params {
             Params::EC(ec) => match ec.curve.clone().unwrap_or("".to_string()).as_str() {
                 "P-256" => KnownKeyAlg::P256,
+                "P-384" => KnownKeyAlg::P384,
+                "P-521" => KnownKeyAlg::P521,
                 "secp256k1" => KnownKeyAlg::K256,
                 _ => KnownKeyAlg::Unsupported,
             },
@@ -137,6 +145,12 @@ impl AsKnownKeyPair for DIDVerificationMethod {
                     "P-256" => P256KeyPair::from_jwk_value(&jwk_value)
                         .kind(ErrorKind::Malformed, "Unable parse jwk")
                         .map(KnownKeyPair::P256),
+                    "P-384" => P384KeyPair::from_jwk_value(&jwk_value)
+                        .kind(ErrorKind::Malformed, "Unable parse jwk")
+                        .map(KnownKeyPair::P384),
+                    "P-521" => P521KeyPair::from_jwk_value(&jwk_value)
+                        .kind(ErrorKind::Malformed, "Unable parse jwk")
+                        .map(KnownKeyPair::P521),
                     "secp256k1" => K256KeyPair::from_jwk_value(&jwk_value)
                         .kind(ErrorKind::Malformed, "Unable parse jwk")
                         .map(KnownKeyPair::K256),
@@ -183,6 +197,8 @@ impl AsKnownKeyPairSecret for Secret {
                 },
             ) => match (value["kty"].as_str(), value["crv"].as_str()) {
                 (Some(kty), Some(crv)) if kty == "EC" && crv == "P-256" => KnownKeyAlg::P256,
+                (Some(kty), Some(crv)) if kty == "EC" && crv == "P-384" => KnownKeyAlg::P384,
+                (Some(kty), Some(crv)) if kty == "EC" && crv == "P-521" => KnownKeyAlg::P521,
                 (Some(kty), Some(crv)) if kty == "EC" && crv == "secp256k1" => KnownKeyAlg::K256,
                 (Some(kty), Some(crv)) if kty == "OKP" && crv == "Ed25519" => KnownKeyAlg::Ed25519,
                 (Some(kty), Some(crv)) if kty == "OKP" && crv == "X25519" => KnownKeyAlg::X25519,
@@ -229,6 +245,16 @@ impl AsKnownKeyPairSecret for Secret {
                         .kind(ErrorKind::Malformed, "Unable parse jwk")
                         .map(KnownKeyPair::P256)
                 }
+                (Some(kty), Some(crv)) if kty == "EC" && crv == "P-384" => {
+                    P384KeyPair::from_jwk_value(value)
+                        .kind(ErrorKind::Malformed, "Unable parse jwk")
+                        .map(KnownKeyPair::P384)
+                }
+                (Some(kty), Some(crv)) if kty == "EC" && crv == "P-521" => {
+                    P521KeyPair::from_jwk_value(value)
+                        .kind(ErrorKind::Malformed, "Unable parse jwk")
+                        .map(KnownKeyPair::P521)
+                }
                 (Some(kty), Some(crv)) if kty == "EC" && crv == "secp256k1" => {
                     K256KeyPair::from_jwk_value(value)
                         .kind(ErrorKind::Malformed, "Unable parse jwk")
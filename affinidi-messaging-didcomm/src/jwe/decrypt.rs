@@ -24,6 +24,8 @@ impl ParsedJWE {
         KE: KeyExchange + KeyGen + ToJwkValue + FromJwkValue,
         KW: KeyWrap + FromKeyDerivation,
     {
+        self.protected.alg.check_enc(&self.protected.enc)?;
+
         let (skid, skey) = match sender {
             Some((skid, skey)) => (Some(skid), Some(skey)),
             None => (None, None),
@@ -107,6 +109,7 @@ mod tests {
             aes::{A128Kw, A256CbcHs512, A256Gcm, A256Kw, AesKey},
             chacha20::{Chacha20Key, XC20P},
             p256::P256KeyPair,
+            p384::P384KeyPair,
             x25519::X25519KeyPair,
         },
         encrypt::KeyAeadInPlace,
@@ -118,7 +121,10 @@ mod tests {
         error::{Error, ErrorKind},
         jwe::{self, test_support::*},
         jwk::{FromJwkValue, ToJwkValue},
-        utils::crypto::{JoseKDF, KeyWrap},
+        utils::{
+            crypto::{JoseKDF, KeyWrap},
+            p521::P521KeyPair,
+        },
     };
 
     #[test]
@@ -303,7 +309,61 @@ mod tests {
             PAYLOAD,
         );
 
-        // TODO: P-384 and P-521 support after solving https://github.com/hyperledger/aries-askar/issues/10
+        _decrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            None,
+            (BOB_KID_P384_1, BOB_KEY_P384_1),
+            MSG_ANONCRYPT_P384_A256GSM,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            None,
+            (BOB_KID_P384_2, BOB_KEY_P384_2),
+            MSG_ANONCRYPT_P384_A256GSM,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            None,
+            (BOB_KID_P521_1, BOB_KEY_P521_1),
+            MSG_ANONCRYPT_P521_A256GSM,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            None,
+            (BOB_KID_P521_2, BOB_KEY_P521_2),
+            MSG_ANONCRYPT_P521_A256GSM,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P384_1, ALICE_PKEY_P384_1)),
+            (BOB_KID_P384_1, BOB_KEY_P384_1),
+            MSG_AUTHCRYPT_P384_A256CBC,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P384_1, ALICE_PKEY_P384_1)),
+            (BOB_KID_P384_2, BOB_KEY_P384_2),
+            MSG_AUTHCRYPT_P384_A256CBC,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P521_1, ALICE_PKEY_P521_1)),
+            (BOB_KID_P521_1, BOB_KEY_P521_1),
+            MSG_AUTHCRYPT_P521_A256CBC,
+            PAYLOAD,
+        );
+
+        _decrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P521_1, ALICE_PKEY_P521_1)),
+            (BOB_KID_P521_2, BOB_KEY_P521_2),
+            MSG_AUTHCRYPT_P521_A256CBC,
+            PAYLOAD,
+        );
 
         fn _decrypt_works<CE, KDF, KE, KW>(
             sender: Option<(&str, &str)>,
@@ -372,6 +432,23 @@ mod tests {
         assert_eq!(format!("{}", err), "Invalid state: Wrong skid used");
     }
 
+    #[test]
+    fn decrypt_works_authcrypt_incompatible_enc() {
+        let res = _decrypt::<AesKey<A256Gcm>, Ecdh1PU<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P384_1, ALICE_PKEY_P384_1)),
+            (BOB_KID_P384_1, BOB_KEY_P384_1),
+            MSG_AUTHCRYPT_P384_A256GCM,
+        );
+
+        let err = res.expect_err("res is ok");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        assert_eq!(
+            format!("{}", err),
+            "Unsupported crypto or method: ECDH-1PU+A256KW can't be used with A256GCM content encryption"
+        );
+    }
+
     #[test]
     fn decrypt_works_recipient_not_found() {
         let res = _decrypt::<
@@ -1549,6 +1626,121 @@ mod tests {
     }
     "#;
 
+    const MSG_ANONCRYPT_P384_A256GSM: &str = r#"
+    {
+        "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLWVuY3J5cHRlZCtqc29uIiwiYWxnIjoiRUNESC1FUytBMjU2S1ciLCJlbmMiOiJBMjU2R0NNIiwiYXB2IjoiTEpBOUVva3M1dGFtVUZWQmFsTXdCaEo2RGtEY0o4SEs0U2xYWldxRHFubyIsImVwayI6eyJjcnYiOiJQLTM4NCIsImt0eSI6IkVDIiwieCI6ImdvaUNXMTRoWU1jQ0hlMkdCajJoeG4zT2o0RnFiTmlmVV9xVFptNW0zZTN6WWFxdm9INnhBbEpqdHc4ZEpYM0siLCJ5Ijoic3ZRa08wRWJVTGYzcXJqd2NxT3IzaThMa0FETHo1LXE3aFNKa0ZFWHowUERzaXV6N2tTVmpGeTVyVnI5V3RLeSJ9fQ",
+        "recipients":[
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-1"
+                    },
+                    "encrypted_key":"oilwQkIYZlIyNKQLXBn2npaw6CzNY5VSM-dM9ODWax-LbJYzCRxUYA"
+                },
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-2"
+                    },
+                    "encrypted_key":"pOX4l5MoVlgKuwPnL3o2bLNDl6T3dCwrLNcPAVzagemROWoJZeAWgw"
+                }
+        ],
+        "iv":"5uP-0mHI7ykWCn4C",
+        "ciphertext":"RYfy9KZgDlIzDafCTJKZsEFe3B3WTxiava4TTmP43IBhRrg4DYl2cMLOGeOVMwCeKX5W_lnDipeyZAjeVQ8x6aDcmp_W7gCeIvFqAdBmhHucf4dpiLmEP71d9KeKMy5ZDjA1vFa0MEiQOu8ZdOfXF2RkBZnHczf9CSXTnbhobFu-ya2uQ3KQlk-7GLEYzbxlh7Yd2JgHDarzADwXsIVgBzwLTZ15Wr1rTcYzp_i5pjwc0Thk2n4zcQ1QHqiBiz9p-TtSckvPoAxiq5Qjr7ujJdqdwkbvb5I4SY3l9MZ5nO0a1Ursnq0my9CIZmCYjiARnVXTvMRdtG2-vG_p4vTJ3A0zYYt3nLfOImmmqrnErcBkwuEn1LEp",
+        "tag":"vEju4oC562uuL9HFlUCozQ"
+    }
+    "#;
+
+    const MSG_ANONCRYPT_P521_A256GSM: &str = r#"
+    {
+        "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLWVuY3J5cHRlZCtqc29uIiwiYWxnIjoiRUNESC1FUytBMjU2S1ciLCJlbmMiOiJBMjU2R0NNIiwiYXB2IjoiR09lbzc2eW02TkNnOVdXTUVZZlcwZVZEVDU2Njh6RWhsMnVBSVctRS1IRSIsImVwayI6eyJjcnYiOiJQLTUyMSIsImt0eSI6IkVDIiwieCI6IkFBUl84VG1DWGdzcWM4aC1sdXg0dVpyYnIxd1R5S3ZoRk91dE1XSDY5M0w4Tm1xMVVsVFloT1pqbXU3Smx2bnhvM0VDSlZEcTZoektqQmJhMS1UdXZBVHYiLCJ5IjoiQWRoM3NIOXJWQlRlSzRvNjdOWFg1SFlmVG1UUnVwZXZQQ202cURKUFg2N3dMc1FzZnJjQmpEQlZCVEVocDJqYUtZMGh4WWFiUXoteFczYkJGOVpJM2Y0dCJ9fQ",
+        "recipients":[
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p521-1"
+                    },
+                    "encrypted_key":"2vMEyZICw5nAE7AiXGk2vFTFLxWX1YP1gnvW7s0N70B44t6cIw6t-g"
+                },
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p521-2"
+                    },
+                    "encrypted_key":"03GlYhrVo-nrjCNUZyYsyEz3yU3tvNnvBkeK8iH4lKk39sa3rwOu5w"
+                }
+        ],
+        "iv":"8cR34Fj9B-UcnZGR",
+        "ciphertext":"_N_P95Rub-wIsoITerlLYg7-S8EjG9s3n3gzYQImiPTXaeN0WUMsGEs79x-Ic9-cofz2Sx2p53lXpikH23V0h3q67BL74hRsyc5ehhQP8epp5Tyr3aeAldPiOq2byVwhNIuy0uyibIOOqo3Wf1qlaPaURkZr_lKIF1VLLD698vKtRho-ZGNtAbLH4QnmGO7_FTXLcVp34PQKerXuxdeiOrkvnsyVhtXnzm1-JT1vJM2wwDkTmPgSY8TuAyRUCxngIVgIChtoPRee-dOylS9p7oTM-q8SnWUjh7l3b2zkYXuCeOrgBCWL4LOrhbovU62gEeZA7S6WbW0siWKFZIutJjOBo7gM4KGRzXEd9K4x4yn286A6t-YJ",
+        "tag":"W2kt1_bSEYbFC_E6yK7OEw"
+    }
+    "#;
+
+    const MSG_AUTHCRYPT_P384_A256CBC: &str = r#"
+    {
+        "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLWVuY3J5cHRlZCtqc29uIiwiYWxnIjoiRUNESC0xUFUrQTI1NktXIiwiZW5jIjoiQTI1NkNCQy1IUzUxMiIsInNraWQiOiJkaWQ6ZXhhbXBsZTphbGljZSNrZXktcDM4NC0xIiwiYXB1IjoiWkdsa09tVjRZVzF3YkdVNllXeHBZMlVqYTJWNUxYQXpPRFF0TVEiLCJhcHYiOiJMSkE5RW9rczV0YW1VRlZCYWxNd0JoSjZEa0RjSjhISzRTbFhaV3FEcW5vIiwiZXBrIjp7ImNydiI6IlAtMzg0Iiwia3R5IjoiRUMiLCJ4IjoiTnBmd0IxZ0h6OXdYODNUYW9Ta0ktMDBkSTlPY214UWNhWGhuRlZFelc2WXkzM3JHQWxjdDIzV3lSM0lnYlRJNSIsInkiOiJoUkJoT1JhWmRQNEVLNHpEXzhhY0JrQ25JbHowMjdVUjRmb094Z0xXRmRUSzJRVDRwVDlUQ0Q3N19OODZIbVlSIn19",
+        "recipients":[
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-1"
+                    },
+                    "encrypted_key":"D7xqN3ERLiTpCJBHL4PSGD83m82NKvPjlmeInTbN2fE53iuB4q34i8sNATpAfSDJOqqnrPSm9igTgYMaAU2o39NzlvDn_JIA"
+                },
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-2"
+                    },
+                    "encrypted_key":"pzhxiH2OiekAFRLZeGC9LYZSTzI7oQ9L5QBzF1Drhz6IKvqyQzsgtl_-nCJvsrWQqnWlmFzliWf5Bw3qDHCb3GXHLkOZj73G"
+                }
+        ],
+        "iv":"LIpDMFt5LAcR9eXNNtwerg",
+        "ciphertext":"zoteYEDDl9s5bSxMROdAZ4CiZ56TArtgquP-N2vhMZYB3-J1qfGsbGZfkhzgec9RQHVX8kwNjJ8y72DqYYMFrFmt9d2PNBNC0jlfFTBAaUaLF2L5QHoYYfzdNoEsj3Ae4XlOlFPXMbZhw8eFhO6YnpsfUsVxUesx097qyQACF6KqzrCfq3S0GBkre-rAGslMEZREN3ycLs59IGRZ5cZI3dfD4N8Ah-olAbHh2qyMxTmFs4cNxtG2XTLOi4-xHG_u31zBUgQYdSHFcw3RzJT-DEbD0jk0nrGDWnjy8Jn3EcEeB52Svo3SPNL_xDHf5LE8-kj3biZOPW3oPrxQ75BYHgScrpHCRZTCOkvt87fPJSQCL3vvTCeMBdtXausTr4FF",
+        "tag":"EYIUkzuaEKNTCy5V6_p1ORgf9a7n2HVLeQJY1hNy2rQ"
+    }
+    "#;
+
+    const MSG_AUTHCRYPT_P521_A256CBC: &str = r#"
+    {
+        "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLWVuY3J5cHRlZCtqc29uIiwiYWxnIjoiRUNESC0xUFUrQTI1NktXIiwiZW5jIjoiQTI1NkNCQy1IUzUxMiIsInNraWQiOiJkaWQ6ZXhhbXBsZTphbGljZSNrZXktcDUyMS0xIiwiYXB1IjoiWkdsa09tVjRZVzF3YkdVNllXeHBZMlVqYTJWNUxYQTFNakV0TVEiLCJhcHYiOiJHT2VvNzZ5bTZOQ2c5V1dNRVlmVzBlVkRUNTY2OHpFaGwydUFJVy1FLUhFIiwiZXBrIjp7ImNydiI6IlAtNTIxIiwia3R5IjoiRUMiLCJ4IjoiQUtSZ3FPMW14QkR1cXlOamQ0YmlXdUhUY2RVM2lYUHFhbFBrcXJCYnA3R0xZNDZHRjBfd1ptanZpdi1NM0hPVWE5bzlUYjMtZy10OEZXM1U0dGY3ekVxSiIsInkiOiJBZkdfbEdzN3dXN3VTN1EzVU5TVEY0LUVSQzFaVnI1OTRFdFd5R2JxeVlsOHg2ZmUzRmY0aTRsVkFTUTkwd2ZMLUhBT0tYS2lvWDlEQnp0dlBSd2VyLUs1In19",
+        "recipients":[
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p521-1"
+                    },
+                    "encrypted_key":"k_GXrn9gOTVDLO77UfqXPU0ArFe5UCWiPvybWTcoRexhqIPN4bWyMd6CsF8qc-3nk3p9HtPh-lS7tVDe0-TO4XqrY-hID4xy"
+                },
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p521-2"
+                    },
+                    "encrypted_key":"S0h2e8x7-HC52bxp5sZ0KOHEODTcDSnKkN6pH_pPGsQemMciorCDZtuY8oKbwGotV1P-cyqqoz7UTVfsOgoEicLMXrWEXrSm"
+                }
+        ],
+        "iv":"jZT9LhuNhBGB3pB8zRHdZA",
+        "ciphertext":"0G36DZeAG_qv0w4JRBcWc5jNDsjMs0fsiGGyC7M8Uy1n65iUV1dmE6eYOpJA43N956u9I3wquvb7QNYseawMD6qaRpG6j7vVHzfo0lRk1awCafP_lLg5fsB5ZYN5Ivr9xDLuumNvJQLlc38o0T3L_auj_5JtK1RPgqqOn0hp-y-EEnM_xZgtLa0M6RXtpDgDJshgPdN4AIFqX0rGI7KG21ZEFTx7chqXz4vQQdSWwQVf-X0H8XD3ovKtjvdlcegDfluph30Zqwl9pR43z7h9_rcozm-1mOFGMGY_kDKL-SVYqrXLDn40nTs4b3Nmj_BuFhNNchQ5W2ZdQKWXxisY6ivEb7E5H5JhR-Jn_XjFtI5_Vt46pwJaEHeKXZF7Q889",
+        "tag":"V0ZAbhKgCgILpQrFzPDHj-To-AN0N7tvghI_hhzazo0"
+    }
+    "#;
+
+    const MSG_AUTHCRYPT_P384_A256GCM: &str = r#"
+    {
+        "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLWVuY3J5cHRlZCtqc29uIiwiYWxnIjoiRUNESC0xUFUrQTI1NktXIiwiZW5jIjoiQTI1NkdDTSIsInNraWQiOiJkaWQ6ZXhhbXBsZTphbGljZSNrZXktcDM4NC0xIiwiYXB1IjoiWkdsa09tVjRZVzF3YkdVNllXeHBZMlVqYTJWNUxYQXpPRFF0TVEiLCJhcHYiOiJMSkE5RW9rczV0YW1VRlZCYWxNd0JoSjZEa0RjSjhISzRTbFhaV3FEcW5vIiwiZXBrIjp7ImNydiI6IlAtMzg0Iiwia3R5IjoiRUMiLCJ4IjoiSnYtdGdGWXR3cFY2dEMwM096OXBxS19xTXNWazBVWEdmYmJOZm5jN1oyMVNHUWRtRG9nTlFWSVY1cWF5eElHYiIsInkiOiI4SVJweGthdlZLOU1QbTV4b1JQS3BhR3haRVVqa2F5Q1BWNU9HSXZsRG9TVnBWcVN4bmtjSmlsbTBZUmdTMndhIn19",
+        "recipients":[
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-1"
+                    },
+                    "encrypted_key":"ii_yUeUNp7Jt-562G-5noCy25uz4Rggk1D8twPviaw0PmeoE4lEwSg"
+                },
+                {
+                    "header":{
+                        "kid":"did:example:bob#key-p384-2"
+                    },
+                    "encrypted_key":"f_vMDL23ERpjeVaOzCAYrVzGFTsJPOhqBz6vrtC2VEliA79DM2fMgQ"
+                }
+        ],
+        "iv":"auJM8dRpNsVmLrpF",
+        "ciphertext":"-xfu9AF9vOfif2-cs2VZQQNP1Ra_VXi3HzukP5pMsZAhM17WvfvZa3B_L7nu1DeKRQRhrunEHX3qTWTEk2SLR_SLikNTj6PxHXhGtk3chsD1jydPfxte7TiWIwTUrMaNvIdXpHLnDAphv1sN1oTjYj4vFTu6ykw5GBotxfANFM7V-pA11-Y04vBcUTG02sDpWQsneX7oigSdgSyQtJCpUXQgs5SoQkkYm7MB7HKFN7gZDepUcSvXtII7JnpUYQGcnWnLXh1_6DICFzXKztBpJcZ1rZv4yac13YVJ5aQecupH4A3zSxIaza21g-jHUWdbzjh3cFhDFS_-Y4Zqvn1t-CEquyRqFdICc_E1Q6Gp56Xj0qgOJltJ",
+        "tag":"j8vJ4b9d0ot70jcbDxIe9A"
+    }
+    "#;
+
     const MSG_AUTHCRYPT_X25519_A256CBC: &str = r#"
     {
         "ciphertext":"MJezmxJ8DzUB01rMjiW6JViSaUhsZBhMvYtezkhmwts1qXWtDB63i4-FHZP6cJSyCI7eU-gqH8lBXO_UVuviWIqnIUrTRLaumanZ4q1dNKAnxNL-dHmb3coOqSvy3ZZn6W17lsVudjw7hUUpMbeMbQ5W8GokK9ZCGaaWnqAzd1ZcuGXDuemWeA8BerQsfQw_IQm-aUKancldedHSGrOjVWgozVL97MH966j3i9CJc3k9jS9xDuE0owoWVZa7SxTmhl1PDetmzLnYIIIt-peJtNYGdpd-FcYxIFycQNRUoFEr77h4GBTLbC-vqbQHJC1vW4O2LEKhnhOAVlGyDYkNbA4DSL-LMwKxenQXRARsKSIMn7z-ZIqTE-VCNj9vbtgR",
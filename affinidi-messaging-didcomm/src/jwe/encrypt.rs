@@ -28,6 +28,8 @@ where
     KE: KeyExchange + KeyGen + ToJwkValue,
     KW: KeyWrap + FromKeyDerivation,
 {
+    alg.check_enc(&enc)?;
+
     let (skid, skey) = match sender {
         Some((skid, skey)) => (Some(skid.to_string()), Some(skey)),
         None => (None, None),
@@ -162,12 +164,13 @@ mod tests {
             aes::{A256CbcHs512, A256Gcm, A256Kw, AesKey},
             chacha20::{Chacha20Key, XC20P},
             p256::P256KeyPair,
+            p384::P384KeyPair,
             x25519::X25519KeyPair,
         },
         encrypt::{KeyAeadInPlace, KeyAeadMeta},
         jwk::FromJwk,
         kdf::{FromKeyDerivation, KeyExchange, ecdh_1pu::Ecdh1PU, ecdh_es::EcdhEs},
-        repr::{KeyGen, KeyPublicBytes, KeySecretBytes, ToPublicBytes, ToSecretBytes},
+        repr::{KeyGen, KeySecretBytes, ToSecretBytes},
     };
 
     use crate::{
@@ -178,7 +181,10 @@ mod tests {
             test_support::*,
         },
         jwk::{FromJwkValue, ToJwkValue},
-        utils::crypto::{JoseKDF, KeyWrap},
+        utils::{
+            crypto::{JoseKDF, KeyWrap},
+            p521::P521KeyPair,
+        },
     };
 
     #[test]
@@ -362,7 +368,86 @@ mod tests {
             Algorithm::Other("otherAlg".to_owned()),
             EncAlgorithm::A256Gcm,
         );
-        // TODO: P-384 and P-521 support after solving https://github.com/hyperledger/aries-askar/issues/10
+
+        _encrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P384_1, ALICE_KEY_P384_1, ALICE_PKEY_P384_1)),
+            &[
+                (BOB_KID_P384_1, BOB_KEY_P384_1, BOB_PKEY_P384_1),
+                (BOB_KID_P384_2, BOB_KEY_P384_2, BOB_PKEY_P384_2),
+            ],
+            Algorithm::Ecdh1puA256kw,
+            EncAlgorithm::A256cbcHs512,
+        );
+
+        _encrypt_works::<AesKey<A256CbcHs512>, EcdhEs<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P384_1, BOB_KEY_P384_1, BOB_PKEY_P384_1),
+                (BOB_KID_P384_2, BOB_KEY_P384_2, BOB_PKEY_P384_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::A256cbcHs512,
+        );
+
+        _encrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P384_1, BOB_KEY_P384_1, BOB_PKEY_P384_1),
+                (BOB_KID_P384_2, BOB_KEY_P384_2, BOB_PKEY_P384_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::A256Gcm,
+        );
+
+        _encrypt_works::<Chacha20Key<XC20P>, EcdhEs<'_, P384KeyPair>, P384KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P384_1, BOB_KEY_P384_1, BOB_PKEY_P384_1),
+                (BOB_KID_P384_2, BOB_KEY_P384_2, BOB_PKEY_P384_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::Xc20P,
+        );
+
+        _encrypt_works::<AesKey<A256CbcHs512>, Ecdh1PU<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            Some((ALICE_KID_P521_1, ALICE_KEY_P521_1, ALICE_PKEY_P521_1)),
+            &[
+                (BOB_KID_P521_1, BOB_KEY_P521_1, BOB_PKEY_P521_1),
+                (BOB_KID_P521_2, BOB_KEY_P521_2, BOB_PKEY_P521_2),
+            ],
+            Algorithm::Ecdh1puA256kw,
+            EncAlgorithm::A256cbcHs512,
+        );
+
+        _encrypt_works::<AesKey<A256CbcHs512>, EcdhEs<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P521_1, BOB_KEY_P521_1, BOB_PKEY_P521_1),
+                (BOB_KID_P521_2, BOB_KEY_P521_2, BOB_PKEY_P521_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::A256cbcHs512,
+        );
+
+        _encrypt_works::<AesKey<A256Gcm>, EcdhEs<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P521_1, BOB_KEY_P521_1, BOB_PKEY_P521_1),
+                (BOB_KID_P521_2, BOB_KEY_P521_2, BOB_PKEY_P521_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::A256Gcm,
+        );
+
+        _encrypt_works::<Chacha20Key<XC20P>, EcdhEs<'_, P521KeyPair>, P521KeyPair, AesKey<A256Kw>>(
+            None,
+            &[
+                (BOB_KID_P521_1, BOB_KEY_P521_1, BOB_PKEY_P521_1),
+                (BOB_KID_P521_2, BOB_KEY_P521_2, BOB_PKEY_P521_2),
+            ],
+            Algorithm::EcdhEsA256kw,
+            EncAlgorithm::Xc20P,
+        );
 
         fn _encrypt_works<CE, KDF, KE, KW>(
             alice: Option<(&str, &str, &str)>,
@@ -372,7 +457,7 @@ mod tests {
         ) where
             CE: KeyAeadInPlace + KeyAeadMeta + KeyGen + ToSecretBytes + KeySecretBytes,
             KDF: JoseKDF<KE, KW>,
-            KE: KeyExchange + KeyGen + ToJwkValue + FromJwkValue + ToPublicBytes + KeyPublicBytes,
+            KE: KeyExchange + KeyGen + ToJwkValue + FromJwkValue,
             KW: KeyWrap + FromKeyDerivation,
         {
             let alice = alice.map(|a| {
@@ -461,4 +546,37 @@ mod tests {
             "Invalid state: Unable derive kw: Invalid state: No sender key for ecdh-1pu: No sender key for ecdh-1pu"
         );
     }
+
+    #[test]
+    fn encrypt_works_authcrypt_incompatible_enc() {
+        _encrypt_works_authcrypt_incompatible_enc::<AesKey<A256Gcm>>(
+            EncAlgorithm::A256Gcm,
+            "Unsupported crypto or method: ECDH-1PU+A256KW can't be used with A256GCM content encryption",
+        );
+
+        _encrypt_works_authcrypt_incompatible_enc::<Chacha20Key<XC20P>>(
+            EncAlgorithm::Xc20P,
+            "Unsupported crypto or method: ECDH-1PU+A256KW can't be used with XC20P content encryption",
+        );
+
+        fn _encrypt_works_authcrypt_incompatible_enc<CE>(enc: EncAlgorithm, exp_err: &str)
+        where
+            CE: KeyAeadInPlace + KeyAeadMeta + KeyGen + ToSecretBytes,
+        {
+            let alice_key = X25519KeyPair::from_jwk(ALICE_KEY_X25519_1).expect("unable from_jwk");
+            let bob_pkey = X25519KeyPair::from_jwk(BOB_PKEY_X25519_1).expect("unable from_jwk");
+
+            let res = jwe::encrypt::<CE, Ecdh1PU<'_, X25519KeyPair>, X25519KeyPair, AesKey<A256Kw>>(
+                "Some plaintext.".as_bytes(),
+                Algorithm::Ecdh1puA256kw,
+                enc,
+                Some((ALICE_KID_X25519_1, &alice_key)),
+                &[(BOB_KID_X25519_1, &bob_pkey)],
+            );
+
+            let err = res.expect_err("res is ok");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+            assert_eq!(format!("{}", err), exp_err);
+        }
+    }
 }
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 use serde_json::Value;
 
+use crate::error::{ErrorKind, Result, err_msg};
+
 /// Subset of JWE in generic json serialization form used for authcrypt
 /// and anoncrypt message types.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -95,6 +97,25 @@ impl Algorithm {
             Algorithm::Other(s) => s,
         }
     }
+
+    /// Checks that the content encryption algorithm can be used with this key agreement.
+    ///
+    /// ECDH-1PU with key wrapping feeds the authentication tag into the key derivation and
+    /// requires a compactly committing AEAD (draft-madden-jose-ecdh-1pu-04, section 2.1).
+    /// A256GCM and XC20P aren't committing, so DIDComm authcrypt only allows A256CBC-HS512.
+    pub(crate) fn check_enc(&self, enc: &EncAlgorithm) -> Result<()> {
+        match (self, enc) {
+            (Algorithm::Ecdh1puA256kw, EncAlgorithm::A256cbcHs512) => Ok(()),
+            (Algorithm::Ecdh1puA256kw, enc) => Err(err_msg(
+                ErrorKind::Unsupported,
+                format!(
+                    "ECDH-1PU+A256KW can't be used with {} content encryption",
+                    enc
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 /// Represents possible values for `enc` header.
@@ -172,4 +193,33 @@ mod tests {
 
         assert_eq!(enc_alg, EncAlgorithm::Other("Unknown 2".into()));
     }
+
+    #[test]
+    fn algorithm_check_enc_works() {
+        let alg = Algorithm::Ecdh1puA256kw;
+        alg.check_enc(&EncAlgorithm::A256cbcHs512)
+            .expect("unable check enc.");
+
+        for enc in [EncAlgorithm::A256Gcm, EncAlgorithm::Xc20P] {
+            let err = alg.check_enc(&enc).expect_err("res is ok");
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+        }
+
+        let err = alg
+            .check_enc(&EncAlgorithm::A256Gcm)
+            .expect_err("res is ok");
+        assert_eq!(
+            format!("{}", err),
+            "Unsupported crypto or method: ECDH-1PU+A256KW can't be used with A256GCM content encryption"
+        );
+
+        let alg = Algorithm::EcdhEsA256kw;
+        for enc in [
+            EncAlgorithm::A256cbcHs512,
+            EncAlgorithm::A256Gcm,
+            EncAlgorithm::Xc20P,
+        ] {
+            alg.check_enc(&enc).expect("unable check enc.");
+        }
+    }
 }
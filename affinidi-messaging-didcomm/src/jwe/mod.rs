@@ -60,6 +60,40 @@ pub(crate) mod test_support {
         "y":"SxYgE7CmEJYi7IDhgK5jI4ZiajO8jPRZDldVhqFpYoo"
     }"#;
 
+    pub(crate) const ALICE_KID_P384_1: &str = "did:example:alice#key-p384-1";
+
+    pub(crate) const ALICE_KEY_P384_1: &str = r#"{
+        "kty":"EC",
+        "d":"9upIPvgM9BjDOLtiTNs_sWjx6sSMiBVPwFoobWax_J9Al0pnXrKwZLB458g4BPKu",
+        "crv":"P-384",
+        "x":"S6cF3kOgdRAMj_loXOYaA5IW4liuDHPUX_N7uGq70OfDvBZhUsdiHXHFYB6eU_cA",
+        "y":"E2zwXQ3lqJDuHuEInBTq4Ewr-fQCgBsYBG5guZR07S7dRVfyJ-eV8uTN6wrKJVyX"
+    }"#;
+
+    pub(crate) const ALICE_PKEY_P384_1: &str = r#"{
+        "kty":"EC",
+        "crv":"P-384",
+        "x":"S6cF3kOgdRAMj_loXOYaA5IW4liuDHPUX_N7uGq70OfDvBZhUsdiHXHFYB6eU_cA",
+        "y":"E2zwXQ3lqJDuHuEInBTq4Ewr-fQCgBsYBG5guZR07S7dRVfyJ-eV8uTN6wrKJVyX"
+    }"#;
+
+    pub(crate) const ALICE_KID_P521_1: &str = "did:example:alice#key-p521-1";
+
+    pub(crate) const ALICE_KEY_P521_1: &str = r#"{
+        "kty":"EC",
+        "d":"AQCQKE7rZpxPnX9RgjXxeywrAMp1fJsyFe4cir1gWj-8t8xWaM_E2qBkTTzyjbRBu-JPXHe_auT850iYmE34SkWi",
+        "crv":"P-521",
+        "x":"AHBEVPRhAv-WHDEvxVM9S0px9WxxwHL641Pemgk9sDdxvli9VpKCBdra5gg_4kupBDhz__AlaBgKOC_15J2Byptz",
+        "y":"AciGcHJCD_yMikQvlmqpkBbVqqbg93mMVcgvXBYAQPP-u9AF7adybwZrNfHWCKAQwGF9ugd0Zhg7mLMEszIONFRk"
+    }"#;
+
+    pub(crate) const ALICE_PKEY_P521_1: &str = r#"{
+        "kty":"EC",
+        "crv":"P-521",
+        "x":"AHBEVPRhAv-WHDEvxVM9S0px9WxxwHL641Pemgk9sDdxvli9VpKCBdra5gg_4kupBDhz__AlaBgKOC_15J2Byptz",
+        "y":"AciGcHJCD_yMikQvlmqpkBbVqqbg93mMVcgvXBYAQPP-u9AF7adybwZrNfHWCKAQwGF9ugd0Zhg7mLMEszIONFRk"
+    }"#;
+
     pub(crate) const BOB_KID_X25519_1: &str = "did:example:bob#key-x25519-1";
 
     pub(crate) const BOB_KEY_X25519_1: &str = r#"{
@@ -138,4 +172,72 @@ pub(crate) mod test_support {
         "x":"n0yBsGrwGZup9ywKhzD4KoORGicilzIUyfcXb1CSwe0",
         "y":"ov0buZJ8GHzV128jmCw1CaFbajZoFFmiJDbMrceCXIw"
     }"#;
+
+    pub(crate) const BOB_KID_P384_1: &str = "did:example:bob#key-p384-1";
+
+    pub(crate) const BOB_KEY_P384_1: &str = r#"{
+        "kty":"EC",
+        "d":"ajqcWbYA0UDBKfAhkSkeiVjMMt8l-5rcknvEv9t_Os6M8s-HisdywvNCX4CGd_xY",
+        "crv":"P-384",
+        "x":"MvnE_OwKoTcJVfHyTX-DLSRhhNwlu5LNoQ5UWD9Jmgtdxp_kpjsMuTTBnxg5RF_Y",
+        "y":"X_3HJBcKFQEG35PZbEOBn8u9_z8V1F9V1Kv-Vh0aSzmH-y9aOuDJUE3D4Hvmi5l7"
+    }"#;
+
+    pub(crate) const BOB_PKEY_P384_1: &str = r#"{
+        "kty":"EC",
+        "crv":"P-384",
+        "x":"MvnE_OwKoTcJVfHyTX-DLSRhhNwlu5LNoQ5UWD9Jmgtdxp_kpjsMuTTBnxg5RF_Y",
+        "y":"X_3HJBcKFQEG35PZbEOBn8u9_z8V1F9V1Kv-Vh0aSzmH-y9aOuDJUE3D4Hvmi5l7"
+    }"#;
+
+    pub(crate) const BOB_KID_P384_2: &str = "did:example:bob#key-p384-2";
+
+    pub(crate) const BOB_KEY_P384_2: &str = r#"{
+        "kty":"EC",
+        "d":"OiwhRotK188BtbQy0XBO8PljSKYI6CCD-nE_ZUzK7o81tk3imDOuQ-jrSWaIkI-T",
+        "crv":"P-384",
+        "x":"2x3HOTvR8e-Tu6U4UqMd1wUWsNXMD0RgIunZTMcZsS-zWOwDgsrhYVHmv3k_DjV3",
+        "y":"W9LLaBjlWYcXUxOf6ECSfcXKaC3-K9z4hCoP0PS87Q_4ExMgIwxVCXUEB6nf0GDd"
+    }"#;
+
+    pub(crate) const BOB_PKEY_P384_2: &str = r#"{
+        "kty":"EC",
+        "crv":"P-384",
+        "x":"2x3HOTvR8e-Tu6U4UqMd1wUWsNXMD0RgIunZTMcZsS-zWOwDgsrhYVHmv3k_DjV3",
+        "y":"W9LLaBjlWYcXUxOf6ECSfcXKaC3-K9z4hCoP0PS87Q_4ExMgIwxVCXUEB6nf0GDd"
+    }"#;
+
+    pub(crate) const BOB_KID_P521_1: &str = "did:example:bob#key-p521-1";
+
+    pub(crate) const BOB_KEY_P521_1: &str = r#"{
+        "kty":"EC",
+        "d":"AV5ocjvy7PkPgNrSuvCxtG70NMj6iTabvvjSLbsdd8OdI9HlXYlFR7RdBbgLUTruvaIRhjEAE9gNTH6rWUIdfuj6",
+        "crv":"P-521",
+        "x":"Af9O5THFENlqQbh2Ehipt1Yf4gAd9RCa3QzPktfcgUIFADMc4kAaYVViTaDOuvVS2vMS1KZe0D5kXedSXPQ3QbHi",
+        "y":"ATZVigRQ7UdGsQ9j-omyff6JIeeUv3CBWYsZ0l6x3C_SYqhqVV7dEG-TafCCNiIxs8qeUiXQ8cHWVclqkH4Lo1qH"
+    }"#;
+
+    pub(crate) const BOB_PKEY_P521_1: &str = r#"{
+        "kty":"EC",
+        "crv":"P-521",
+        "x":"Af9O5THFENlqQbh2Ehipt1Yf4gAd9RCa3QzPktfcgUIFADMc4kAaYVViTaDOuvVS2vMS1KZe0D5kXedSXPQ3QbHi",
+        "y":"ATZVigRQ7UdGsQ9j-omyff6JIeeUv3CBWYsZ0l6x3C_SYqhqVV7dEG-TafCCNiIxs8qeUiXQ8cHWVclqkH4Lo1qH"
+    }"#;
+
+    pub(crate) const BOB_KID_P521_2: &str = "did:example:bob#key-p521-2";
+
+    pub(crate) const BOB_KEY_P521_2: &str = r#"{
+        "kty":"EC",
+        "d":"ABixMEZHsyT7SRw-lY5HxdNOofTZLlwBHwPEJ3spEMC2sWN1RZQylZuvoyOBGJnPxg4-H_iVhNWf_OtgYODrYhCk",
+        "crv":"P-521",
+        "x":"ATp_WxCfIK_SriBoStmA0QrJc2pUR1djpen0VdpmogtnKxJbitiPq-HJXYXDKriXfVnkrl2i952MsIOMfD2j0Ots",
+        "y":"AEJipR0Dc-aBZYDqN51SKHYSWs9hM58SmRY1MxgXANgZrPaq1EeGMGOjkbLMEJtBThdjXhkS5VlXMkF0cYhZELiH"
+    }"#;
+
+    pub(crate) const BOB_PKEY_P521_2: &str = r#"{
+        "kty":"EC",
+        "crv":"P-521",
+        "x":"ATp_WxCfIK_SriBoStmA0QrJc2pUR1djpen0VdpmogtnKxJbitiPq-HJXYXDKriXfVnkrl2i952MsIOMfD2j0Ots",
+        "y":"AEJipR0Dc-aBZYDqN51SKHYSWs9hM58SmRY1MxgXANgZrPaq1EeGMGOjkbLMEJtBThdjXhkS5VlXMkF0cYhZELiH"
+    }"#;
 }
@@ -69,8 +69,11 @@ impl Jwe {
 }
 
 impl ParsedJWE {
-    /// Verifies that apv and apu filled according DID Comm specification.
+    /// Verifies that apv and apu filled according DID Comm specification
+    /// and that the content encryption is allowed for the key agreement.
     pub(crate) fn verify_didcomm(self) -> Result<Self> {
+        self.protected.alg.check_enc(&self.protected.enc)?;
+
         let did_comm_apv = {
             let mut kids = self
                 .jwe
@@ -1,6 +1,12 @@
-use crate::error::{Error, ErrorKind, Result, ResultExt};
+use crate::{
+    error::{Error, ErrorKind, Result, ResultExt},
+    utils::p521::P521KeyPair,
+};
 use askar_crypto::{
-    alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, x25519::X25519KeyPair},
+    alg::{
+        ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair,
+        x25519::X25519KeyPair,
+    },
     jwk::{FromJwk, ToJwk},
 };
 use serde_json::Value;
@@ -40,11 +46,15 @@ impl FromJwkValue for Ed25519KeyPair {}
 impl FromJwkValue for P256KeyPair {}
 impl FromJwkValue for X25519KeyPair {}
 impl FromJwkValue for K256KeyPair {}
+impl FromJwkValue for P384KeyPair {}
+impl FromJwkValue for P521KeyPair {}
 
 impl ToJwkValue for Ed25519KeyPair {}
 impl ToJwkValue for P256KeyPair {}
 impl ToJwkValue for X25519KeyPair {}
 impl ToJwkValue for K256KeyPair {}
+impl ToJwkValue for P384KeyPair {}
+impl ToJwkValue for P521KeyPair {}
 
 #[cfg(test)]
 mod tests {
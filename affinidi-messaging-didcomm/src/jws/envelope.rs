@@ -1,8 +1,13 @@
+use std::any::TypeId;
+
 use askar_crypto::sign::SignatureType;
 use serde::{Deserialize, Serialize};
 use serde_enum_str::{Deserialize_enum_str, Serialize_enum_str};
 
-use crate::error::{ErrorKind, Result, err_msg};
+use crate::{
+    error::{ErrorKind, Result, err_msg},
+    utils::p521::P521KeyPair,
+};
 
 /// Subset of JWS in generic json serialization used for signed message type.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -75,16 +80,27 @@ pub enum Algorithm {
     #[serde(rename = "ES256K")]
     Es256K,
 
+    #[serde(rename = "ES384")]
+    Es384,
+
+    #[serde(rename = "ES512")]
+    Es512,
+
     #[serde(other)]
     Other(String),
 }
 
 impl Algorithm {
-    pub(crate) fn sig_type(&self) -> Result<SignatureType> {
+    /// askar has no ES512 signature type, so `None` is returned for ES512 and
+    /// P-521 keys sign and verify ES512 by default.
+    /// Check [`Algorithm::supports_key`] before using the result
+    pub(crate) fn sig_type(&self) -> Result<Option<SignatureType>> {
         let sig_type = match self {
-            Algorithm::EdDSA => SignatureType::EdDSA,
-            Algorithm::Es256 => SignatureType::ES256,
-            Algorithm::Es256K => SignatureType::ES256K,
+            Algorithm::EdDSA => Some(SignatureType::EdDSA),
+            Algorithm::Es256 => Some(SignatureType::ES256),
+            Algorithm::Es256K => Some(SignatureType::ES256K),
+            Algorithm::Es384 => Some(SignatureType::ES384),
+            Algorithm::Es512 => None,
             Algorithm::Other(_) => Err(err_msg(
                 ErrorKind::Unsupported,
                 "Unsupported signature type",
@@ -93,6 +109,12 @@ impl Algorithm {
 
         Ok(sig_type)
     }
+
+    /// Other keys treat a `None` signature type as their own default, so ES512
+    /// is only allowed for [`P521KeyPair`]
+    pub(crate) fn supports_key<Key: 'static>(&self) -> bool {
+        *self != Algorithm::Es512 || TypeId::of::<Key>() == TypeId::of::<P521KeyPair>()
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +135,14 @@ mod tests {
         let alg = serde_json::to_string(&alg).expect("Unable serialize");
         assert_eq!(alg, "\"ES256K\"");
 
+        let alg = Algorithm::Es384;
+        let alg = serde_json::to_string(&alg).expect("Unable serialize");
+        assert_eq!(alg, "\"ES384\"");
+
+        let alg = Algorithm::Es512;
+        let alg = serde_json::to_string(&alg).expect("Unable serialize");
+        assert_eq!(alg, "\"ES512\"");
+
         let alg = Algorithm::Other("Unknown".into());
         let alg = serde_json::to_string(&alg).expect("Unable serialize");
         assert_eq!(alg, "\"Unknown\"");
@@ -136,6 +166,14 @@ mod tests {
 
         assert_eq!(alg, Algorithm::Es256K);
 
+        let alg: Algorithm = serde_json::from_str("\"ES384\"").expect("Unable deserialize");
+
+        assert_eq!(alg, Algorithm::Es384);
+
+        let alg: Algorithm = serde_json::from_str("\"ES512\"").expect("Unable deserialize");
+
+        assert_eq!(alg, Algorithm::Es512);
+
         let alg: Algorithm = serde_json::from_str("\"Unknown\"").expect("Unable deserialize");
         assert_eq!(alg, Algorithm::Other("Unknown".into()));
 
@@ -2,11 +2,11 @@ use askar_crypto::sign::KeySign;
 use base64::prelude::*;
 
 use crate::{
-    error::{Error, ErrorKind, Result, ResultExt},
+    error::{Error, ErrorKind, Result, ResultExt, err_msg},
     jws::envelope::{Algorithm, CompactHeader, Header, Jws, ProtectedHeader, Signature},
 };
 
-pub(crate) fn sign<Key: KeySign>(
+pub(crate) fn sign<Key: KeySign + 'static>(
     payload: &[u8],
     signer: (&str, &Key),
     alg: Algorithm,
@@ -15,6 +15,13 @@ pub(crate) fn sign<Key: KeySign>(
 
     let sig_type = alg.sig_type()?;
 
+    if !alg.supports_key::<Key>() {
+        Err(err_msg(
+            ErrorKind::InvalidState,
+            "Unable create signature: Unsupported signature type",
+        ))?;
+    }
+
     let protected = {
         let protected = ProtectedHeader {
             typ: "application/didcomm-signed+json".into(),
@@ -36,7 +43,7 @@ pub(crate) fn sign<Key: KeySign>(
         let sign_input = format!("{}.{}", protected, payload);
 
         let signature = key
-            .create_signature(sign_input.as_bytes(), Some(sig_type))
+            .create_signature(sign_input.as_bytes(), sig_type)
             .map_err(|err| {
                 Error::msg(
                     ErrorKind::InvalidState,
@@ -63,7 +70,7 @@ pub(crate) fn sign<Key: KeySign>(
     Ok(jws)
 }
 
-pub(crate) fn sign_compact<Key: KeySign>(
+pub(crate) fn sign_compact<Key: KeySign + 'static>(
     payload: &[u8],
     signer: (&str, &Key),
     typ: &str,
@@ -73,6 +80,13 @@ pub(crate) fn sign_compact<Key: KeySign>(
 
     let sig_type = alg.sig_type()?;
 
+    if !alg.supports_key::<Key>() {
+        Err(err_msg(
+            ErrorKind::InvalidState,
+            "Unable create signature: Unsupported signature type",
+        ))?;
+    }
+
     let header = {
         let header = CompactHeader {
             typ: typ.into(),
@@ -95,7 +109,7 @@ pub(crate) fn sign_compact<Key: KeySign>(
         let sign_input = format!("{}.{}", header, payload);
 
         let signature = key
-            .create_signature(sign_input.as_bytes(), Some(sig_type))
+            .create_signature(sign_input.as_bytes(), sig_type)
             .map_err(|err| {
                 Error::msg(
                     ErrorKind::InvalidState,
@@ -114,7 +128,7 @@ pub(crate) fn sign_compact<Key: KeySign>(
 #[cfg(test)]
 mod tests {
     use askar_crypto::{
-        alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair},
+        alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair},
         jwk::FromJwk,
         sign::{KeySigVerify, KeySign},
     };
@@ -123,6 +137,7 @@ mod tests {
     use crate::{
         error::{ErrorKind, Result},
         jws::{self, envelope::Algorithm},
+        utils::p521::P521KeyPair,
     };
 
     #[test]
@@ -151,7 +166,23 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_works<K: FromJwk + KeySign + KeySigVerify>(
+        _sign_works::<P384KeyPair>(
+            ALICE_KID_P384,
+            ALICE_KEY_P384,
+            ALICE_PKEY_P384,
+            Algorithm::Es384,
+            PAYLOAD,
+        );
+
+        _sign_works::<P521KeyPair>(
+            ALICE_KID_P521,
+            ALICE_KEY_P521,
+            ALICE_PKEY_P521,
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        fn _sign_works<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             pkey: &str,
@@ -238,7 +269,49 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_works_incompatible_alg<K: FromJwk + KeySign + KeySigVerify>(
+        _sign_works_incompatible_alg::<P384KeyPair>(
+            ALICE_KID_P384,
+            ALICE_KEY_P384,
+            Algorithm::Es256,
+            PAYLOAD,
+        );
+
+        _sign_works_incompatible_alg::<P521KeyPair>(
+            ALICE_KID_P521,
+            ALICE_KEY_P521,
+            Algorithm::Es384,
+            PAYLOAD,
+        );
+
+        _sign_works_incompatible_alg::<P256KeyPair>(
+            ALICE_KID_P256,
+            ALICE_KEY_P256,
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        _sign_works_incompatible_alg::<P384KeyPair>(
+            ALICE_KID_P384,
+            ALICE_KEY_P384,
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        _sign_works_incompatible_alg::<K256KeyPair>(
+            ALICE_KID_K256,
+            ALICE_KEY_K256,
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        _sign_works_incompatible_alg::<Ed25519KeyPair>(
+            ALICE_KID_ED25519,
+            ALICE_KEY_ED25519,
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        fn _sign_works_incompatible_alg<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             alg: Algorithm,
@@ -279,7 +352,7 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_works_unknown_alg<K: FromJwk + KeySign + KeySigVerify>(
+        fn _sign_works_unknown_alg<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             alg: Algorithm,
@@ -326,7 +399,25 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_compact_works<K: FromJwk + KeySign + KeySigVerify>(
+        _sign_compact_works::<P384KeyPair>(
+            ALICE_KID_P384,
+            ALICE_KEY_P384,
+            ALICE_PKEY_P384,
+            "example-typ-4",
+            Algorithm::Es384,
+            PAYLOAD,
+        );
+
+        _sign_compact_works::<P521KeyPair>(
+            ALICE_KID_P521,
+            ALICE_KEY_P521,
+            ALICE_PKEY_P521,
+            "example-typ-5",
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        fn _sign_compact_works<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             pkey: &str,
@@ -419,7 +510,23 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_compact_works_incompatible_alg<K: FromJwk + KeySign + KeySigVerify>(
+        _sign_compact_works_incompatible_alg::<P256KeyPair>(
+            ALICE_KID_P256,
+            ALICE_KEY_P256,
+            "example-typ-1",
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        _sign_compact_works_incompatible_alg::<P384KeyPair>(
+            ALICE_KID_P384,
+            ALICE_KEY_P384,
+            "example-typ-1",
+            Algorithm::Es512,
+            PAYLOAD,
+        );
+
+        fn _sign_compact_works_incompatible_alg<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             typ: &str,
@@ -464,7 +571,7 @@ mod tests {
             PAYLOAD,
         );
 
-        fn _sign_compact_works_unknown_alg<K: FromJwk + KeySign + KeySigVerify>(
+        fn _sign_compact_works_unknown_alg<K: FromJwk + KeySign + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             typ: &str,
@@ -483,7 +590,7 @@ mod tests {
         }
     }
 
-    fn _sign<K: FromJwk + KeySign>(
+    fn _sign<K: FromJwk + KeySign + 'static>(
         kid: &str,
         key: &str,
         alg: Algorithm,
@@ -493,7 +600,7 @@ mod tests {
         jws::sign(payload.as_bytes(), (kid, &key), alg.clone())
     }
 
-    fn _sign_compact<K: FromJwk + KeySign>(
+    fn _sign_compact<K: FromJwk + KeySign + 'static>(
         kid: &str,
         key: &str,
         typ: &str,
@@ -566,5 +673,47 @@ mod tests {
     }
     "#;
 
+    const ALICE_KID_P384: &str = "did:example:alice#key-4";
+
+    const ALICE_KEY_P384: &str = r#"
+    {
+        "kty":"EC",
+        "d":"2eNsbSAPV2NWsapVbAsw8Bypt-ZnVmPtBVyjmcqa1mlgYyr6MsGo0MYzQ_d-9htB",
+        "crv":"P-384",
+        "x":"s8d7ooKzV4WBQC6musgaKwb-XnPpMfLfvqJ1LzaL_cR4o5W2BWKIIBdXo4ZXmAIp",
+        "y":"MpfI9PpFGg-oXWU0bk-970gaitT1cfQmGOi3NV0NeLW75Dvg9o8JpwdQdTWtLDot"
+    }
+    "#;
+
+    const ALICE_PKEY_P384: &str = r#"
+    {
+        "kty":"EC",
+        "crv":"P-384",
+        "x":"s8d7ooKzV4WBQC6musgaKwb-XnPpMfLfvqJ1LzaL_cR4o5W2BWKIIBdXo4ZXmAIp",
+        "y":"MpfI9PpFGg-oXWU0bk-970gaitT1cfQmGOi3NV0NeLW75Dvg9o8JpwdQdTWtLDot"
+    }
+    "#;
+
+    const ALICE_KID_P521: &str = "did:example:alice#key-5";
+
+    const ALICE_KEY_P521: &str = r#"
+    {
+        "kty":"EC",
+        "d":"AbmUHWz52fa3PHkz6rl1JbpNSI-i73oEGTfuxiK2B4IwGXhiTbYNdGDrvH3e7HRmjNOQnNaVIfmB8mgcE6p1aZBD",
+        "crv":"P-521",
+        "x":"AWzEo8MAtw4QQhzuly2GogJvjuHhimGPlcDpFpFaCKyV86cN3-l49bre7cT3EOf2P5KO1U-8qd-fFPS76c-lGqqf",
+        "y":"AVzdIw--MrY7QzHBdpH4uslPHMimaRn5IovvnqqrmHv33_TYpXI-uvZYrHlECoDs1IG9fcVZvs_f5BvQj7m8XgKF"
+    }
+    "#;
+
+    const ALICE_PKEY_P521: &str = r#"
+    {
+        "kty":"EC",
+        "crv":"P-521",
+        "x":"AWzEo8MAtw4QQhzuly2GogJvjuHhimGPlcDpFpFaCKyV86cN3-l49bre7cT3EOf2P5KO1U-8qd-fFPS76c-lGqqf",
+        "y":"AVzdIw--MrY7QzHBdpH4uslPHMimaRn5IovvnqqrmHv33_TYpXI-uvZYrHlECoDs1IG9fcVZvs_f5BvQj7m8XgKF"
+    }
+    "#;
+
     const PAYLOAD: &str = r#"{"id":"1234567890","typ":"application/didcomm-plain+json","type":"http://example.com/protocols/lets_do_lunch/1.0/proposal","from":"did:example:alice","to":["did:example:bob"],"created_time":1516269022,"expires_time":1516385931,"body":{"messagespecificattribute":"and its value"}}"#;
 }
@@ -7,7 +7,7 @@ use crate::{
 use base64::prelude::*;
 
 impl ParsedJWS {
-    pub(crate) fn verify<Key: KeySigVerify>(&self, signer: (&str, &Key)) -> Result<bool> {
+    pub(crate) fn verify<Key: KeySigVerify + 'static>(&self, signer: (&str, &Key)) -> Result<bool> {
         let (kid, key) = signer;
 
         let (i, signature) = self
@@ -24,6 +24,14 @@ impl ParsedJWS {
             .ok_or_else(|| err_msg(ErrorKind::InvalidState, "Invalid protected header index"))?;
 
         let sig_type = protected.alg.sig_type()?;
+
+        if !protected.alg.supports_key::<Key>() {
+            Err(err_msg(
+                ErrorKind::Malformed,
+                "Unable verify signature: Unsupported signature type",
+            ))?;
+        }
+
         let sign_input = format!("{}.{}", signature.protected, self.jws.payload);
 
         let signature = BASE64_URL_SAFE_NO_PAD
@@ -31,7 +39,7 @@ impl ParsedJWS {
             .kind(ErrorKind::Malformed, "Unable decode signature")?;
 
         let valid = key
-            .verify_signature(sign_input.as_bytes(), &signature, Some(sig_type))
+            .verify_signature(sign_input.as_bytes(), &signature, sig_type)
             .map_err(|err| {
                 Error::msg(
                     ErrorKind::Malformed,
@@ -44,8 +52,16 @@ impl ParsedJWS {
 }
 
 impl ParsedCompactJWS {
-    pub(crate) fn verify<Key: KeySigVerify>(&self, key: &Key) -> Result<bool> {
+    pub(crate) fn verify<Key: KeySigVerify + 'static>(&self, key: &Key) -> Result<bool> {
         let sig_type = self.parsed_header.alg.sig_type()?;
+
+        if !self.parsed_header.alg.supports_key::<Key>() {
+            Err(err_msg(
+                ErrorKind::Malformed,
+                "Unable verify signature: Unsupported signature type",
+            ))?;
+        }
+
         let sign_input = format!("{}.{}", self.header, self.payload);
 
         let signature = BASE64_URL_SAFE_NO_PAD
@@ -53,7 +69,7 @@ impl ParsedCompactJWS {
             .kind(ErrorKind::Malformed, "Unable decode signature")?;
 
         let valid = key
-            .verify_signature(sign_input.as_bytes(), &signature, Some(sig_type))
+            .verify_signature(sign_input.as_bytes(), &signature, sig_type)
             .map_err(|err| {
                 Error::msg(
                     ErrorKind::Malformed,
@@ -68,7 +84,7 @@ impl ParsedCompactJWS {
 #[cfg(test)]
 mod tests {
     use askar_crypto::{
-        alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair},
+        alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair},
         jwk::FromJwk,
         sign::KeySigVerify,
     };
@@ -76,6 +92,7 @@ mod tests {
     use crate::{
         error::{Error, ErrorKind},
         jws,
+        utils::p521::P521KeyPair,
     };
 
     #[test]
@@ -83,8 +100,10 @@ mod tests {
         _verify_works::<Ed25519KeyPair>(ALICE_KID_ED25519, ALICE_PKEY_ED25519, ALICE_MSG_ED25519);
         _verify_works::<P256KeyPair>(ALICE_KID_P256, ALICE_PKEY_P256, ALICE_MSG_P256);
         _verify_works::<K256KeyPair>(ALICE_KID_K256, ALICE_PKEY_K256, ALICE_MSG_K256);
+        _verify_works::<P384KeyPair>(ALICE_KID_P384, ALICE_PKEY_P384, ALICE_MSG_P384);
+        _verify_works::<P521KeyPair>(ALICE_KID_P521, ALICE_PKEY_P521, ALICE_MSG_P521);
 
-        fn _verify_works<K: FromJwk + KeySigVerify>(kid: &str, key: &str, msg: &str) {
+        fn _verify_works<K: FromJwk + KeySigVerify + 'static>(kid: &str, key: &str, msg: &str) {
             let res = _verify::<K>(kid, key, msg);
             let res = res.expect("res is err");
             assert!(res);
@@ -111,7 +130,7 @@ mod tests {
             ALICE_MSG_ED25519_P256_K256,
         );
 
-        fn _verify_works_multiple_signatures<K: FromJwk + KeySigVerify>(
+        fn _verify_works_multiple_signatures<K: FromJwk + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             msg: &str,
@@ -134,7 +153,11 @@ mod tests {
 
         _verify_works_different_key::<K256KeyPair>(ALICE_KID_K256, ALICE_PKEY_K256, BOB_MSG_K256);
 
-        fn _verify_works_different_key<K: FromJwk + KeySigVerify>(kid: &str, key: &str, msg: &str) {
+        fn _verify_works_different_key<K: FromJwk + KeySigVerify + 'static>(
+            kid: &str,
+            key: &str,
+            msg: &str,
+        ) {
             let res = _verify::<K>(kid, key, msg);
             let res = res.expect("res is err");
             assert!(!res);
@@ -161,7 +184,7 @@ mod tests {
             ALICE_MSG_K256_CHANGED_PAYLOAD,
         );
 
-        fn _verify_works_changed_payload<K: FromJwk + KeySigVerify>(
+        fn _verify_works_changed_payload<K: FromJwk + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             msg: &str,
@@ -202,7 +225,19 @@ mod tests {
 
         _verify_works_different_curve::<K256KeyPair>(ALICE_KID_P256, ALICE_PKEY_K256, BOB_MSG_P256);
 
-        fn _verify_works_different_curve<K: FromJwk + KeySigVerify>(
+        _verify_works_different_curve::<P256KeyPair>(
+            ALICE_KID_P521,
+            ALICE_PKEY_P256,
+            ALICE_MSG_P521,
+        );
+
+        _verify_works_different_curve::<P384KeyPair>(
+            ALICE_KID_P521,
+            ALICE_PKEY_P384,
+            ALICE_MSG_P521,
+        );
+
+        fn _verify_works_different_curve<K: FromJwk + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             msg: &str,
@@ -234,7 +269,11 @@ mod tests {
             ALICE_MSG_K256,
         );
 
-        fn _verify_works_kid_not_found<K: FromJwk + KeySigVerify>(kid: &str, key: &str, msg: &str) {
+        fn _verify_works_kid_not_found<K: FromJwk + KeySigVerify + 'static>(
+            kid: &str,
+            key: &str,
+            msg: &str,
+        ) {
             let res = _verify::<K>(kid, key, msg);
 
             let err = res.expect_err("res is ok");
@@ -263,7 +302,7 @@ mod tests {
             ALICE_MSG_K256_UNDECODABLE_SIG,
         );
 
-        fn _verify_works_undecodable_sig<K: FromJwk + KeySigVerify>(
+        fn _verify_works_undecodable_sig<K: FromJwk + KeySigVerify + 'static>(
             kid: &str,
             key: &str,
             msg: &str,
@@ -335,7 +374,7 @@ mod tests {
         );
     }
 
-    fn _verify<Key: FromJwk + KeySigVerify>(
+    fn _verify<Key: FromJwk + KeySigVerify + 'static>(
         kid: &str,
         key: &str,
         msg: &str,
@@ -347,7 +386,10 @@ mod tests {
         msg.verify((kid, &key))
     }
 
-    fn _verify_compact<Key: FromJwk + KeySigVerify>(key: &str, msg: &str) -> Result<bool, Error> {
+    fn _verify_compact<Key: FromJwk + KeySigVerify + 'static>(
+        key: &str,
+        msg: &str,
+    ) -> Result<bool, Error> {
         let key = Key::from_jwk(key).expect("unable from_jwk.");
 
         let msg = jws::parse_compact(msg).expect("unable parse.");
@@ -677,4 +719,56 @@ mod tests {
         "x":"VDXDwuGKVq91zxU6q7__jLDUq8_C5cuxECgd-1feFTE"
     }
     "#;
+
+    const ALICE_KID_P384: &str = "did:example:alice#key-4";
+
+    const ALICE_PKEY_P384: &str = r#"
+    {
+        "kty":"EC",
+        "crv":"P-384",
+        "x":"s8d7ooKzV4WBQC6musgaKwb-XnPpMfLfvqJ1LzaL_cR4o5W2BWKIIBdXo4ZXmAIp",
+        "y":"MpfI9PpFGg-oXWU0bk-970gaitT1cfQmGOi3NV0NeLW75Dvg9o8JpwdQdTWtLDot"
+    }
+    "#;
+
+    const ALICE_KID_P521: &str = "did:example:alice#key-5";
+
+    const ALICE_PKEY_P521: &str = r#"
+    {
+        "kty":"EC",
+        "crv":"P-521",
+        "x":"AWzEo8MAtw4QQhzuly2GogJvjuHhimGPlcDpFpFaCKyV86cN3-l49bre7cT3EOf2P5KO1U-8qd-fFPS76c-lGqqf",
+        "y":"AVzdIw--MrY7QzHBdpH4uslPHMimaRn5IovvnqqrmHv33_TYpXI-uvZYrHlECoDs1IG9fcVZvs_f5BvQj7m8XgKF"
+    }
+    "#;
+
+    const ALICE_MSG_P384: &str = r#"
+    {
+        "payload":"eyJpZCI6IjEyMzQ1Njc4OTAiLCJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLXBsYWluK2pzb24iLCJ0eXBlIjoiaHR0cDovL2V4YW1wbGUuY29tL3Byb3RvY29scy9sZXRzX2RvX2x1bmNoLzEuMC9wcm9wb3NhbCIsImZyb20iOiJkaWQ6ZXhhbXBsZTphbGljZSIsInRvIjpbImRpZDpleGFtcGxlOmJvYiJdLCJjcmVhdGVkX3RpbWUiOjE1MTYyNjkwMjIsImV4cGlyZXNfdGltZSI6MTUxNjM4NTkzMSwiYm9keSI6eyJtZXNzYWdlc3BlY2lmaWNhdHRyaWJ1dGUiOiJhbmQgaXRzIHZhbHVlIn19",
+        "signatures":[
+            {
+                "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLXNpZ25lZCtqc29uIiwiYWxnIjoiRVMzODQifQ",
+                "signature":"EOy2Qm94vuDE61LUwuePGaLcox_PCPWDo6j3oJ2hZ5JSqkRQjLDKJno9ep0LlG7ibN0fSjJwaJvLOBfjCjCwFqWPmqW59vuXOauqCmmBynWtMs_tXp82iOfuj--eeFu4",
+                "header":{
+                    "kid":"did:example:alice#key-4"
+                }
+            }
+        ]
+    }
+    "#;
+
+    const ALICE_MSG_P521: &str = r#"
+    {
+        "payload":"eyJpZCI6IjEyMzQ1Njc4OTAiLCJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLXBsYWluK2pzb24iLCJ0eXBlIjoiaHR0cDovL2V4YW1wbGUuY29tL3Byb3RvY29scy9sZXRzX2RvX2x1bmNoLzEuMC9wcm9wb3NhbCIsImZyb20iOiJkaWQ6ZXhhbXBsZTphbGljZSIsInRvIjpbImRpZDpleGFtcGxlOmJvYiJdLCJjcmVhdGVkX3RpbWUiOjE1MTYyNjkwMjIsImV4cGlyZXNfdGltZSI6MTUxNjM4NTkzMSwiYm9keSI6eyJtZXNzYWdlc3BlY2lmaWNhdHRyaWJ1dGUiOiJhbmQgaXRzIHZhbHVlIn19",
+        "signatures":[
+            {
+                "protected":"eyJ0eXAiOiJhcHBsaWNhdGlvbi9kaWRjb21tLXNpZ25lZCtqc29uIiwiYWxnIjoiRVM1MTIifQ",
+                "signature":"ACfgYsWut-nLlshfldICIMHDvF9LGzgpPehx2e0a_z3G2Eb7jXpRg0UVoUAybBzixr8SbktFkQFCiHLCLrz48NcaAR-HBgSyf8jdFedg_93BAwp6Hg4ZsJ9cX1cYFY9m57mK89ZwRTXFghXPGJDkj8iAWOLwkpuH_phheydAljQKnUVN",
+                "header":{
+                    "kid":"did:example:alice#key-5"
+                }
+            }
+        ]
+    }
+    "#;
 }
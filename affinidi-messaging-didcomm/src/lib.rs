@@ -23,6 +23,9 @@ pub mod envelope;
 pub mod error;
 pub mod protocols;
 
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 pub use message::{
     Attachment, AttachmentBuilder, AttachmentData, Base64AttachmentData, FromPrior,
     JsonAttachmentData, LinksAttachmentData, Message, MessageBuilder, MessagingServiceMetadata,
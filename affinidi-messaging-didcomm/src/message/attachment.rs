@@ -1,3 +1,14 @@
+use crate::{
+    document::{DIDCommVerificationMethodExt, did_or_url},
+    error::{ErrorKind, Result, ResultContext, ResultExt, err_msg},
+    jws,
+    utils::{crypto::AsKnownKeyPair, p521::P521KeyPair},
+};
+use affinidi_did_resolver_cache_sdk::DIDCacheClient;
+use askar_crypto::alg::{
+    ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair,
+};
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -154,6 +165,191 @@ impl AttachmentBuilder {
     }
 }
 
+impl Attachment {
+    /// Returns the embedded content of the attachment
+    /// - Base64 data is decoded (base64url, padding is optional)
+    /// - JSON data is serialized to a string
+    ///
+    /// # Errors
+    /// - `Malformed` Base64 data can't be decoded
+    /// - `Unsupported` Links attachments have no embedded content
+    pub fn content(&self) -> Result<Vec<u8>> {
+        match &self.data {
+            AttachmentData::Base64 { value } => BASE64_URL_SAFE_NO_PAD
+                .decode(value.base64.trim_end_matches('='))
+                .kind(ErrorKind::Malformed, "Attachment base64 data is invalid"),
+            AttachmentData::Json { value } => serde_json::to_vec(&value.json)
+                .kind(ErrorKind::Malformed, "Attachment JSON data is invalid"),
+            AttachmentData::Links { .. } => Err(err_msg(
+                ErrorKind::Unsupported,
+                "Links attachments have no embedded content",
+            )),
+        }
+    }
+
+    /// Verifies the JWS of an attachment against the DID Document of the signer.
+    /// The JWS may be detached (empty payload) or carry a payload that matches the attachment content.
+    ///
+    /// # Parameters
+    /// - `did_resolver` instance of `DIDCacheClient` to resolve the signer DID.
+    ///
+    /// # Returns
+    /// The key ID of the signer, or None if the attachment is not signed
+    ///
+    /// # Errors
+    /// - `Malformed` JWS is malformed, the signature is wrong or doesn't cover the attachment content.
+    /// - `DIDNotResolved` Signer DID not found.
+    /// - `DIDUrlNotFound` Signer authentication verification method is not found.
+    /// - `Unsupported` Used crypto or attachment type is unsupported.
+    pub async fn verify_jws(&self, did_resolver: &DIDCacheClient) -> Result<Option<String>> {
+        let compact = match &self.data {
+            AttachmentData::Base64 { value } => &value.jws,
+            AttachmentData::Json { value } => &value.jws,
+            AttachmentData::Links { value } => {
+                if value.jws.is_some() {
+                    return Err(err_msg(
+                        ErrorKind::Unsupported,
+                        "Signed links attachments are not supported",
+                    ));
+                }
+                &None
+            }
+        };
+        let Some(compact) = compact else {
+            return Ok(None);
+        };
+
+        let mut parsed = jws::parse_compact(compact)?;
+
+        // A detached JWS is verified against the attachment content, otherwise the signed
+        // payload must match what is attached
+        let content = self.content()?;
+        if parsed.payload.is_empty() {
+            parsed.payload = BASE64_URL_SAFE_NO_PAD.encode(&content);
+        } else {
+            let payload = BASE64_URL_SAFE_NO_PAD.decode(&parsed.payload).kind(
+                ErrorKind::Malformed,
+                "Attachment JWS payload is invalid base64",
+            )?;
+            let matches = match &self.data {
+                AttachmentData::Json { value } => serde_json::from_slice::<Value>(&payload)
+                    .map(|json| json == value.json)
+                    .unwrap_or(false),
+                _ => payload == content,
+            };
+            if !matches {
+                Err(err_msg(
+                    ErrorKind::Malformed,
+                    "Attachment JWS payload doesn't match the attachment content",
+                ))?
+            }
+        }
+
+        let kid = parsed.parsed_header.kid.clone();
+        let (did, did_url) = did_or_url(&kid);
+
+        if did_url.is_none() {
+            Err(err_msg(
+                ErrorKind::Malformed,
+                "Attachment JWS kid is not a DID URL",
+            ))?
+        }
+
+        let did_doc = match did_resolver.resolve(did).await {
+            Ok(response) => response.doc,
+            Err(err) => {
+                return Err(err_msg(
+                    ErrorKind::DIDNotResolved,
+                    format!(
+                        "Attachment signer DID ({}) couldn't be resolved. Reason: {}",
+                        did, err
+                    ),
+                ));
+            }
+        };
+
+        if !did_doc
+            .verification_relationships
+            .authentication
+            .iter()
+            .any(|a| a.id().resolve(did_doc.id.as_did()).as_str() == kid)
+        {
+            Err(err_msg(
+                ErrorKind::DIDUrlNotFound,
+                "Attachment signer kid is not found in DIDDoc",
+            ))?
+        }
+
+        let key = did_doc
+            .verification_method
+            .iter()
+            .find(|&vm| vm.id == kid.as_str())
+            .ok_or_else(|| {
+                err_msg(
+                    ErrorKind::DIDUrlNotFound,
+                    "Attachment signer verification method not found in DIDDoc",
+                )
+            })?;
+
+        let jwk = key
+            .get_jwk()
+            .ok_or_else(|| err_msg(ErrorKind::Unsupported, "Couldn't convert key to jwk"))?;
+
+        let valid = match parsed.parsed_header.alg {
+            jws::Algorithm::EdDSA => {
+                let key = key
+                    .as_ed25519(&jwk)
+                    .context("Unable to instantiate attachment signer key")?;
+                parsed
+                    .verify::<Ed25519KeyPair>(&key)
+                    .context("Unable to verify attachment signature")?
+            }
+            jws::Algorithm::Es256 => {
+                let key = key
+                    .as_p256(&jwk)
+                    .context("Unable to instantiate attachment signer key")?;
+                parsed
+                    .verify::<P256KeyPair>(&key)
+                    .context("Unable to verify attachment signature")?
+            }
+            jws::Algorithm::Es256K => {
+                let key = key
+                    .as_k256(&jwk)
+                    .context("Unable to instantiate attachment signer key")?;
+                parsed
+                    .verify::<K256KeyPair>(&key)
+                    .context("Unable to verify attachment signature")?
+            }
+            jws::Algorithm::Es384 => {
+                let key = key
+                    .as_p384(&jwk)
+                    .context("Unable to instantiate attachment signer key")?;
+                parsed
+                    .verify::<P384KeyPair>(&key)
+                    .context("Unable to verify attachment signature")?
+            }
+            jws::Algorithm::Es512 => {
+                let key = key
+                    .as_p521(&jwk)
+                    .context("Unable to instantiate attachment signer key")?;
+                parsed
+                    .verify::<P521KeyPair>(&key)
+                    .context("Unable to verify attachment signature")?
+            }
+            jws::Algorithm::Other(_) => Err(err_msg(
+                ErrorKind::Unsupported,
+                "Unsupported signature algorithm",
+            ))?,
+        };
+
+        if !valid {
+            Err(err_msg(ErrorKind::Malformed, "Wrong attachment signature"))?
+        }
+
+        Ok(Some(kid))
+    }
+}
+
 // Attention: we are using untagged enum serialization variant.
 // Serde will try to match the data against each variant in order and the
 // first one that deserializes successfully is the one returned.
@@ -212,10 +408,85 @@ pub struct LinksAttachmentData {
 
 #[cfg(test)]
 mod tests {
+    use affinidi_did_resolver_cache_sdk::config::DIDCacheConfigBuilder;
+    use affinidi_secrets_resolver::secrets::SecretMaterial;
+    use askar_crypto::jwk::FromJwk;
     use core::panic;
     use serde_json::json;
 
     use super::*;
+    use crate::test_vectors::CHARLIE_SECRET_AUTH_KEY_ED25519;
+
+    fn charlie_sign(payload: &[u8]) -> String {
+        let SecretMaterial::JWK { private_key_jwk } =
+            &CHARLIE_SECRET_AUTH_KEY_ED25519.secret_material
+        else {
+            panic!("Charlie's auth key isn't a JWK");
+        };
+        let key = Ed25519KeyPair::from_jwk(&private_key_jwk.to_string()).expect("Unable from_jwk");
+
+        jws::sign_compact(
+            payload,
+            (&CHARLIE_SECRET_AUTH_KEY_ED25519.id, &key),
+            "JWT",
+            jws::Algorithm::EdDSA,
+        )
+        .expect("Unable sign_compact")
+    }
+
+    #[tokio::test]
+    async fn attachment_verify_jws_works() {
+        let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+            .await
+            .unwrap();
+
+        // Attached payload
+        let attachment = Attachment::base64(BASE64_URL_SAFE_NO_PAD.encode("example"))
+            .jws(charlie_sign(b"example"))
+            .finalize();
+        let kid = attachment
+            .verify_jws(&did_resolver)
+            .await
+            .expect("res is err");
+        assert_eq!(kid, Some(CHARLIE_SECRET_AUTH_KEY_ED25519.id.clone()));
+
+        // Detached payload
+        let compact = charlie_sign(br#"{"example":true}"#);
+        let (header, rest) = compact.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let attachment = Attachment::json(json!({"example": true}))
+            .jws([header, "", signature].join("."))
+            .finalize();
+        let kid = attachment
+            .verify_jws(&did_resolver)
+            .await
+            .expect("res is err");
+        assert_eq!(kid, Some(CHARLIE_SECRET_AUTH_KEY_ED25519.id.clone()));
+
+        // Unsigned
+        let attachment = Attachment::json(json!("example")).finalize();
+        assert_eq!(attachment.verify_jws(&did_resolver).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn attachment_verify_jws_works_tampered() {
+        let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+            .await
+            .unwrap();
+
+        let compact = charlie_sign(b"example");
+        let (header, rest) = compact.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let attachment = Attachment::base64(BASE64_URL_SAFE_NO_PAD.encode("tampered"))
+            .jws([header, "", signature].join("."))
+            .finalize();
+
+        let err = attachment
+            .verify_jws(&did_resolver)
+            .await
+            .expect_err("res is ok");
+        assert_eq!(err.kind(), ErrorKind::Malformed);
+    }
 
     #[test]
     fn attachment_base64_works() {
@@ -135,6 +135,18 @@ impl FromPrior {
                 JWT_TYP,
                 Algorithm::Es256K,
             ),
+            KnownKeyPair::P384(ref key) => jws::sign_compact(
+                from_prior_str.as_bytes(),
+                (&kid, key),
+                JWT_TYP,
+                Algorithm::Es384,
+            ),
+            KnownKeyPair::P521(ref key) => jws::sign_compact(
+                from_prior_str.as_bytes(),
+                (&kid, key),
+                JWT_TYP,
+                Algorithm::Es512,
+            ),
             _ => Err(err_msg(ErrorKind::Unsupported, "Unsupported signature alg"))?,
         }
         .context("Unable to produce signature")?;
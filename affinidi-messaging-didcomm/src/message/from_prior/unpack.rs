@@ -3,10 +3,12 @@ use crate::{
     document::{DIDCommVerificationMethodExt, did_or_url},
     error::{ErrorKind, Result, ResultContext, ResultExt, err_msg},
     jws,
-    utils::crypto::AsKnownKeyPair,
+    utils::{crypto::AsKnownKeyPair, p521::P521KeyPair},
 };
 use affinidi_did_resolver_cache_sdk::DIDCacheClient;
-use askar_crypto::alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair};
+use askar_crypto::alg::{
+    ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair,
+};
 use base64::prelude::*;
 
 impl FromPrior {
@@ -123,6 +125,24 @@ impl FromPrior {
                     .verify::<K256KeyPair>(&key)
                     .context("Unable to verify from_prior signature")?
             }
+            jws::Algorithm::Es384 => {
+                let key = key
+                    .as_p384(&jwk)
+                    .context("Unable to instantiate from_prior issuer key")?;
+
+                parsed
+                    .verify::<P384KeyPair>(&key)
+                    .context("Unable to verify from_prior signature")?
+            }
+            jws::Algorithm::Es512 => {
+                let key = key
+                    .as_p521(&jwk)
+                    .context("Unable to instantiate from_prior issuer key")?;
+
+                parsed
+                    .verify::<P521KeyPair>(&key)
+                    .context("Unable to verify from_prior signature")?
+            }
             jws::Algorithm::Other(_) => Err(err_msg(
                 ErrorKind::Unsupported,
                 "Unsupported signature algorithm",
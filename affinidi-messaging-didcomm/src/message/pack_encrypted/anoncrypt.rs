@@ -5,6 +5,7 @@ use askar_crypto::{
         chacha20::{Chacha20Key, XC20P},
         k256::K256KeyPair,
         p256::P256KeyPair,
+        p384::P384KeyPair,
         x25519::X25519KeyPair,
     },
     kdf::ecdh_es::EcdhEs,
@@ -15,7 +16,10 @@ use crate::{
     document::{DIDCommVerificationMethodExt, did_or_url},
     error::{ErrorKind, Result, ResultContext, err_msg},
     jwe,
-    utils::crypto::{AsKnownKeyPair, KnownKeyAlg},
+    utils::{
+        crypto::{AsKnownKeyPair, KnownKeyAlg},
+        p521::P521KeyPair,
+    },
 };
 
 pub(crate) async fn anoncrypt(
@@ -298,6 +302,130 @@ pub(crate) async fn anoncrypt(
                 .context("Unable produce anoncrypt envelope")?,
             }
         }
+        KnownKeyAlg::P384 => {
+            let _to_keys = to_keys
+                .iter()
+                .map(|vm| {
+                    if let Some(jwk) = vm.get_jwk() {
+                        vm.as_p384(&jwk).map(|k| (&vm.id, k))
+                    } else {
+                        Err(err_msg(
+                            ErrorKind::NoCompatibleCrypto,
+                            "Couldn't create JWK for p384",
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let to_keys: Vec<_> = _to_keys
+                .iter()
+                .map(|(id, key)| (id.as_str(), key))
+                .collect();
+
+            match enc_alg_anon {
+                AnonCryptAlg::A256cbcHs512EcdhEsA256kw => jwe::encrypt::<
+                    AesKey<A256CbcHs512>,
+                    EcdhEs<'_, P384KeyPair>,
+                    P384KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::A256cbcHs512,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+                AnonCryptAlg::Xc20pEcdhEsA256kw => jwe::encrypt::<
+                    Chacha20Key<XC20P>,
+                    EcdhEs<'_, P384KeyPair>,
+                    P384KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::Xc20P,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+                AnonCryptAlg::A256gcmEcdhEsA256kw => jwe::encrypt::<
+                    AesKey<A256Gcm>,
+                    EcdhEs<'_, P384KeyPair>,
+                    P384KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::A256Gcm,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+            }
+        }
+        KnownKeyAlg::P521 => {
+            let _to_keys = to_keys
+                .iter()
+                .map(|vm| {
+                    if let Some(jwk) = vm.get_jwk() {
+                        vm.as_p521(&jwk).map(|k| (&vm.id, k))
+                    } else {
+                        Err(err_msg(
+                            ErrorKind::NoCompatibleCrypto,
+                            "Couldn't create JWK for p521",
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let to_keys: Vec<_> = _to_keys
+                .iter()
+                .map(|(id, key)| (id.as_str(), key))
+                .collect();
+
+            match enc_alg_anon {
+                AnonCryptAlg::A256cbcHs512EcdhEsA256kw => jwe::encrypt::<
+                    AesKey<A256CbcHs512>,
+                    EcdhEs<'_, P521KeyPair>,
+                    P521KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::A256cbcHs512,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+                AnonCryptAlg::Xc20pEcdhEsA256kw => jwe::encrypt::<
+                    Chacha20Key<XC20P>,
+                    EcdhEs<'_, P521KeyPair>,
+                    P521KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::Xc20P,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+                AnonCryptAlg::A256gcmEcdhEsA256kw => jwe::encrypt::<
+                    AesKey<A256Gcm>,
+                    EcdhEs<'_, P521KeyPair>,
+                    P521KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::EcdhEsA256kw,
+                    jwe::EncAlgorithm::A256Gcm,
+                    None,
+                    &to_keys,
+                )
+                .context("Unable produce anoncrypt envelope")?,
+            }
+        }
         _ => Err(err_msg(
             ErrorKind::InvalidState,
             "Unsupported recipient key agreement alg",
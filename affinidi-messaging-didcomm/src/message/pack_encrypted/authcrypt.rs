@@ -6,6 +6,7 @@ use askar_crypto::{
         chacha20::{Chacha20Key, XC20P},
         k256::K256KeyPair,
         p256::P256KeyPair,
+        p384::P384KeyPair,
         x25519::X25519KeyPair,
     },
     kdf::{ecdh_1pu::Ecdh1PU, ecdh_es::EcdhEs},
@@ -16,7 +17,10 @@ use crate::{
     document::{DIDCommVerificationMethodExt, did_or_url},
     error::{ErrorKind, Result, ResultContext, err_msg},
     jwe,
-    utils::crypto::{AsKnownKeyPair, AsKnownKeyPairSecret, KnownKeyAlg},
+    utils::{
+        crypto::{AsKnownKeyPair, AsKnownKeyPairSecret, KnownKeyAlg},
+        p521::P521KeyPair,
+    },
 };
 
 #[allow(clippy::too_many_arguments)]
@@ -459,6 +463,170 @@ where
                 msg
             }
         }
+        KnownKeyAlg::P384 => {
+            let _to_keys = to_keys
+                .iter()
+                .map(|vm| {
+                    if let Some(jwk) = vm.get_jwk() {
+                        vm.as_p384(&jwk).map(|k| (&vm.id, k))
+                    } else {
+                        Err(err_msg(
+                            ErrorKind::NoCompatibleCrypto,
+                            "Couldn't create JWK for p_384",
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let to_keys: Vec<_> = _to_keys
+                .iter()
+                .map(|(id, key)| (id.as_str(), key))
+                .collect();
+
+            let msg = match enc_alg_auth {
+                AuthCryptAlg::A256cbcHs512Ecdh1puA256kw => jwe::encrypt::<
+                    AesKey<A256CbcHs512>,
+                    Ecdh1PU<'_, P384KeyPair>,
+                    P384KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::Ecdh1puA256kw,
+                    jwe::EncAlgorithm::A256cbcHs512,
+                    Some((&from_key.id, &from_priv_key.as_p384()?)),
+                    &to_keys,
+                )
+                .context("Unable produce authcrypt envelope")?,
+            };
+
+            if protect_sender {
+                match enc_alg_anon {
+                    AnonCryptAlg::A256cbcHs512EcdhEsA256kw => jwe::encrypt::<
+                        AesKey<A256CbcHs512>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::A256cbcHs512,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                    AnonCryptAlg::Xc20pEcdhEsA256kw => jwe::encrypt::<
+                        Chacha20Key<XC20P>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::Xc20P,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                    AnonCryptAlg::A256gcmEcdhEsA256kw => jwe::encrypt::<
+                        AesKey<A256Gcm>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::A256Gcm,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                }
+            } else {
+                msg
+            }
+        }
+        KnownKeyAlg::P521 => {
+            let _to_keys = to_keys
+                .iter()
+                .map(|vm| {
+                    if let Some(jwk) = vm.get_jwk() {
+                        vm.as_p521(&jwk).map(|k| (&vm.id, k))
+                    } else {
+                        Err(err_msg(
+                            ErrorKind::NoCompatibleCrypto,
+                            "Couldn't create JWK for p_521",
+                        ))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let to_keys: Vec<_> = _to_keys
+                .iter()
+                .map(|(id, key)| (id.as_str(), key))
+                .collect();
+
+            let msg = match enc_alg_auth {
+                AuthCryptAlg::A256cbcHs512Ecdh1puA256kw => jwe::encrypt::<
+                    AesKey<A256CbcHs512>,
+                    Ecdh1PU<'_, P521KeyPair>,
+                    P521KeyPair,
+                    AesKey<A256Kw>,
+                >(
+                    msg,
+                    jwe::Algorithm::Ecdh1puA256kw,
+                    jwe::EncAlgorithm::A256cbcHs512,
+                    Some((&from_key.id, &from_priv_key.as_p521()?)),
+                    &to_keys,
+                )
+                .context("Unable produce authcrypt envelope")?,
+            };
+
+            if protect_sender {
+                match enc_alg_anon {
+                    AnonCryptAlg::A256cbcHs512EcdhEsA256kw => jwe::encrypt::<
+                        AesKey<A256CbcHs512>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::A256cbcHs512,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                    AnonCryptAlg::Xc20pEcdhEsA256kw => jwe::encrypt::<
+                        Chacha20Key<XC20P>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::Xc20P,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                    AnonCryptAlg::A256gcmEcdhEsA256kw => jwe::encrypt::<
+                        AesKey<A256Gcm>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(
+                        msg.as_bytes(),
+                        jwe::Algorithm::EcdhEsA256kw,
+                        jwe::EncAlgorithm::A256Gcm,
+                        None,
+                        &to_keys,
+                    )
+                    .context("Unable produce authcrypt envelope")?,
+                }
+            } else {
+                msg
+            }
+        }
         _ => Err(err_msg(
             ErrorKind::Unsupported,
             "Unsupported recipient key agreement method",
@@ -231,7 +231,8 @@ pub struct PackEncryptedOptions {
     /// If not present first service will be used.
     pub messaging_service: Option<String>,
 
-    /// Algorithm used for authenticated encryption
+    /// Algorithm used for authenticated encryption.
+    /// See [AuthCryptAlg] for why only A256CBC-HS512 is available.
     #[serde(default)]
     pub enc_alg_auth: AuthCryptAlg,
 
@@ -109,6 +109,12 @@ impl Message {
             KnownKeyPair::K256(ref key) => {
                 jws::sign(payload.as_bytes(), (&key_id, key), Algorithm::Es256K)
             }
+            KnownKeyPair::P384(ref key) => {
+                jws::sign(payload.as_bytes(), (&key_id, key), Algorithm::Es384)
+            }
+            KnownKeyPair::P521(ref key) => {
+                jws::sign(payload.as_bytes(), (&key_id, key), Algorithm::Es512)
+            }
             _ => Err(err_msg(ErrorKind::Unsupported, "Unsupported signature alg"))?,
         }?;
 
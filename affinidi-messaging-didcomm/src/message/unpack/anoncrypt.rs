@@ -5,6 +5,7 @@ use askar_crypto::{
         chacha20::{Chacha20Key, XC20P},
         k256::K256KeyPair,
         p256::P256KeyPair,
+        p384::P384KeyPair,
         x25519::X25519KeyPair,
     },
     kdf::ecdh_es::EcdhEs,
@@ -18,7 +19,10 @@ use crate::{
     envelope::{Envelope, MetaEnvelope, ParsedEnvelope},
     error::{ErrorKind, Result, ResultExt, err_msg},
     jwe,
-    utils::crypto::{AsKnownKeyPairSecret, KnownKeyPair},
+    utils::{
+        crypto::{AsKnownKeyPairSecret, KnownKeyPair},
+        p521::P521KeyPair,
+    },
 };
 
 pub(crate) async fn _try_unpack_anoncrypt<T>(
@@ -171,6 +175,66 @@ where
                         AesKey<A256Kw>,
                     >(None, (&to_kid, to_key))?
             }
+            (KnownKeyPair::P384(ref to_key), jwe::EncAlgorithm::A256cbcHs512) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::A256cbcHs512EcdhEsA256kw);
+
+                jwe.decrypt::<
+                        AesKey<A256CbcHs512>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
+            (KnownKeyPair::P384(ref to_key), jwe::EncAlgorithm::Xc20P) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::Xc20pEcdhEsA256kw);
+
+                jwe.decrypt::<
+                        Chacha20Key<XC20P>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
+            (KnownKeyPair::P384(ref to_key), jwe::EncAlgorithm::A256Gcm) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::A256gcmEcdhEsA256kw);
+
+                jwe.decrypt::<
+                        AesKey<A256Gcm>,
+                        EcdhEs<'_, P384KeyPair>,
+                        P384KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
+            (KnownKeyPair::P521(ref to_key), jwe::EncAlgorithm::A256cbcHs512) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::A256cbcHs512EcdhEsA256kw);
+
+                jwe.decrypt::<
+                        AesKey<A256CbcHs512>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
+            (KnownKeyPair::P521(ref to_key), jwe::EncAlgorithm::Xc20P) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::Xc20pEcdhEsA256kw);
+
+                jwe.decrypt::<
+                        Chacha20Key<XC20P>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
+            (KnownKeyPair::P521(ref to_key), jwe::EncAlgorithm::A256Gcm) => {
+                envelope.metadata.enc_alg_anon = Some(AnonCryptAlg::A256gcmEcdhEsA256kw);
+
+                jwe.decrypt::<
+                        AesKey<A256Gcm>,
+                        EcdhEs<'_, P521KeyPair>,
+                        P521KeyPair,
+                        AesKey<A256Kw>,
+                    >(None, (&to_kid, to_key))?
+            }
             _ => Err(err_msg(
                 ErrorKind::Unsupported,
                 "Unsupported recipient key agreement method",
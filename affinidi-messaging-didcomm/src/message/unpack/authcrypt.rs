@@ -5,6 +5,7 @@ use askar_crypto::{
         aes::{A256CbcHs512, A256Kw, AesKey},
         k256::K256KeyPair,
         p256::P256KeyPair,
+        p384::P384KeyPair,
         x25519::X25519KeyPair,
     },
     kdf::ecdh_1pu::Ecdh1PU,
@@ -18,7 +19,10 @@ use crate::{
     algorithms::AuthCryptAlg,
     error::{ErrorKind, Result, ResultExt, err_msg},
     jwe,
-    utils::crypto::{AsKnownKeyPairSecret, KnownKeyPair},
+    utils::{
+        crypto::{AsKnownKeyPairSecret, KnownKeyPair},
+        p521::P521KeyPair,
+    },
 };
 
 pub(crate) async fn _try_unpack_authcrypt<T>(
@@ -144,6 +148,34 @@ where
                     AesKey<A256Kw>,
                 >(Some((envelope.from_kid.as_ref().unwrap(), from_key)), (to_kid, to_key))?
             }
+            (
+                KnownKeyPair::P384(from_key),
+                KnownKeyPair::P384(to_key),
+                jwe::EncAlgorithm::A256cbcHs512,
+            ) => {
+                envelope.metadata.enc_alg_auth = Some(AuthCryptAlg::A256cbcHs512Ecdh1puA256kw);
+
+                jwe.decrypt::<
+                    AesKey<A256CbcHs512>,
+                    Ecdh1PU<'_, P384KeyPair>,
+                    P384KeyPair,
+                    AesKey<A256Kw>,
+                >(Some((envelope.from_kid.as_ref().unwrap(), from_key)), (to_kid, to_key))?
+            }
+            (
+                KnownKeyPair::P521(from_key),
+                KnownKeyPair::P521(to_key),
+                jwe::EncAlgorithm::A256cbcHs512,
+            ) => {
+                envelope.metadata.enc_alg_auth = Some(AuthCryptAlg::A256cbcHs512Ecdh1puA256kw);
+
+                jwe.decrypt::<
+                    AesKey<A256CbcHs512>,
+                    Ecdh1PU<'_, P521KeyPair>,
+                    P521KeyPair,
+                    AesKey<A256Kw>,
+                >(Some((envelope.from_kid.as_ref().unwrap(), from_key)), (to_kid, to_key))?
+            }
             (KnownKeyPair::X25519(_), KnownKeyPair::K256(_), _) => Err(err_msg(
                 ErrorKind::Malformed,
                 "Incompatible sender and recipient key agreement curves",
@@ -307,6 +307,220 @@ where
     Ok(!secrets_ids.await.is_empty())
 }
 
+#[cfg(test)]
+mod tests {
+    use affinidi_did_resolver_cache_sdk::{DIDCacheClient, config::DIDCacheConfigBuilder};
+    use affinidi_secrets_resolver::SimpleSecretsResolver;
+    use serde_json::json;
+
+    use crate::{
+        Message, PackEncryptedOptions, UnpackOptions,
+        algorithms::{AnonCryptAlg, AuthCryptAlg, SignAlg},
+        test_vectors::{
+            ALICE_DID_DOC, ALICE_SECRET_AUTH_KEY_P384, ALICE_SECRET_AUTH_KEY_P521,
+            ALICE_SECRET_KEY_AGREEMENT_KEY_P384, ALICE_SECRET_KEY_AGREEMENT_KEY_P521,
+            ALICE_SECRETS, BOB_DID_DOC, BOB_SECRET_KEY_AGREEMENT_KEY_P384_1,
+            BOB_SECRET_KEY_AGREEMENT_KEY_P521_1, BOB_SECRETS,
+        },
+    };
+
+    const ALICE: &str = "did:example:alice";
+    const BOB: &str = "did:example:bob";
+
+    async fn _did_resolver() -> DIDCacheClient {
+        let mut did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+            .await
+            .unwrap();
+        did_resolver.add_example_did(ALICE_DID_DOC).unwrap();
+        did_resolver.add_example_did(BOB_DID_DOC).unwrap();
+        did_resolver
+    }
+
+    fn _message() -> Message {
+        Message::build(
+            "1234567890".into(),
+            "http://example.com/protocols/lets_do_lunch/1.0/proposal".into(),
+            json!({"messagespecificattribute": "and its value"}),
+        )
+        .from(ALICE.into())
+        .to(BOB.into())
+        .finalize()
+    }
+
+    #[tokio::test]
+    async fn unpack_works_signed_p384_p521() {
+        _unpack_works_signed(&ALICE_SECRET_AUTH_KEY_P384.id, SignAlg::ES384).await;
+        _unpack_works_signed(&ALICE_SECRET_AUTH_KEY_P521.id, SignAlg::ES512).await;
+
+        async fn _unpack_works_signed(sign_by: &str, sign_alg: SignAlg) {
+            let did_resolver = _did_resolver().await;
+            let alice_secrets = SimpleSecretsResolver::new(&ALICE_SECRETS.clone()).await;
+            let bob_secrets = SimpleSecretsResolver::new(&BOB_SECRETS.clone()).await;
+
+            let (packed, metadata) = _message()
+                .pack_signed(sign_by, &did_resolver, &alice_secrets)
+                .await
+                .expect("Unable pack_signed");
+            assert_eq!(metadata.sign_by_kid, sign_by);
+
+            let (msg, metadata) = Message::unpack_string(
+                &packed,
+                &did_resolver,
+                &bob_secrets,
+                &UnpackOptions::default(),
+            )
+            .await
+            .expect("Unable unpack");
+
+            assert_eq!(msg, _message());
+            assert!(metadata.authenticated);
+            assert!(metadata.non_repudiation);
+            assert!(!metadata.encrypted);
+            assert_eq!(metadata.sign_from, Some(sign_by.into()));
+            assert_eq!(metadata.sign_alg, Some(sign_alg));
+        }
+    }
+
+    #[tokio::test]
+    async fn unpack_works_anoncrypt_p384_p521() {
+        for to in [
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P384_1.id,
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P521_1.id,
+        ] {
+            for enc_alg in [
+                AnonCryptAlg::A256cbcHs512EcdhEsA256kw,
+                AnonCryptAlg::Xc20pEcdhEsA256kw,
+                AnonCryptAlg::A256gcmEcdhEsA256kw,
+            ] {
+                _unpack_works_anoncrypt(to, enc_alg).await;
+            }
+        }
+
+        async fn _unpack_works_anoncrypt(to: &str, enc_alg: AnonCryptAlg) {
+            let did_resolver = _did_resolver().await;
+            let alice_secrets = SimpleSecretsResolver::new(&ALICE_SECRETS.clone()).await;
+            let bob_secrets = SimpleSecretsResolver::new(&BOB_SECRETS.clone()).await;
+
+            let (packed, metadata) = _message()
+                .pack_encrypted(
+                    to,
+                    None,
+                    None,
+                    &did_resolver,
+                    &alice_secrets,
+                    &PackEncryptedOptions {
+                        forward: false,
+                        enc_alg_anon: enc_alg.clone(),
+                        ..PackEncryptedOptions::default()
+                    },
+                )
+                .await
+                .expect("Unable pack_encrypted");
+            assert_eq!(metadata.to_kids, vec![to.to_string()]);
+
+            let (msg, metadata) = Message::unpack_string(
+                &packed,
+                &did_resolver,
+                &bob_secrets,
+                &UnpackOptions::default(),
+            )
+            .await
+            .expect("Unable unpack");
+
+            assert_eq!(msg, _message());
+            assert!(metadata.encrypted);
+            assert!(metadata.anonymous_sender);
+            assert!(!metadata.authenticated);
+            assert_eq!(metadata.enc_alg_anon, Some(enc_alg));
+            assert_eq!(metadata.encrypted_to_kids, vec![to.to_string()]);
+        }
+    }
+
+    #[tokio::test]
+    async fn unpack_works_authcrypt_p384_p521() {
+        _unpack_works_authcrypt(
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P384_1.id,
+            &ALICE_SECRET_KEY_AGREEMENT_KEY_P384.id,
+            None,
+        )
+        .await;
+
+        _unpack_works_authcrypt(
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P521_1.id,
+            &ALICE_SECRET_KEY_AGREEMENT_KEY_P521.id,
+            None,
+        )
+        .await;
+
+        _unpack_works_authcrypt(
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P384_1.id,
+            &ALICE_SECRET_KEY_AGREEMENT_KEY_P384.id,
+            Some((&ALICE_SECRET_AUTH_KEY_P384.id, SignAlg::ES384)),
+        )
+        .await;
+
+        _unpack_works_authcrypt(
+            &BOB_SECRET_KEY_AGREEMENT_KEY_P521_1.id,
+            &ALICE_SECRET_KEY_AGREEMENT_KEY_P521.id,
+            Some((&ALICE_SECRET_AUTH_KEY_P521.id, SignAlg::ES512)),
+        )
+        .await;
+
+        async fn _unpack_works_authcrypt(
+            to: &str,
+            from_kid: &str,
+            sign_by: Option<(&str, SignAlg)>,
+        ) {
+            let did_resolver = _did_resolver().await;
+            let alice_secrets = SimpleSecretsResolver::new(&ALICE_SECRETS.clone()).await;
+            let bob_secrets = SimpleSecretsResolver::new(&BOB_SECRETS.clone()).await;
+
+            let (packed, metadata) = _message()
+                .pack_encrypted(
+                    to,
+                    Some(ALICE),
+                    sign_by.as_ref().map(|(kid, _)| *kid),
+                    &did_resolver,
+                    &alice_secrets,
+                    &PackEncryptedOptions {
+                        forward: false,
+                        ..PackEncryptedOptions::default()
+                    },
+                )
+                .await
+                .expect("Unable pack_encrypted");
+            assert_eq!(metadata.from_kid, Some(from_kid.into()));
+            assert_eq!(metadata.to_kids, vec![to.to_string()]);
+
+            let (msg, metadata) = Message::unpack_string(
+                &packed,
+                &did_resolver,
+                &bob_secrets,
+                &UnpackOptions::default(),
+            )
+            .await
+            .expect("Unable unpack");
+
+            assert_eq!(msg, _message());
+            assert!(metadata.encrypted);
+            assert!(metadata.authenticated);
+            assert!(!metadata.anonymous_sender);
+            assert_eq!(
+                metadata.enc_alg_auth,
+                Some(AuthCryptAlg::A256cbcHs512Ecdh1puA256kw)
+            );
+            assert_eq!(metadata.encrypted_from_kid, Some(from_kid.into()));
+            assert_eq!(metadata.encrypted_to_kids, vec![to.to_string()]);
+            assert_eq!(metadata.non_repudiation, sign_by.is_some());
+            assert_eq!(
+                metadata.sign_from,
+                sign_by.as_ref().map(|(kid, _)| kid.to_string())
+            );
+            assert_eq!(metadata.sign_alg, sign_by.map(|(_, alg)| alg));
+        }
+    }
+}
+
 /*
 #[cfg(test)]
 mod test {
@@ -1,6 +1,8 @@
 use affinidi_did_resolver_cache_sdk::DIDCacheClient;
 use affinidi_did_resolver_cache_sdk::document::DocumentExt;
-use askar_crypto::alg::{ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair};
+use askar_crypto::alg::{
+    ed25519::Ed25519KeyPair, k256::K256KeyPair, p256::P256KeyPair, p384::P384KeyPair,
+};
 use tracing::debug;
 
 use crate::document::{DIDCommVerificationMethodExt, did_or_url};
@@ -10,7 +12,7 @@ use crate::{
     algorithms::SignAlg,
     error::{ErrorKind, Result, ResultContext, ResultExt, err_msg},
     jws,
-    utils::crypto::AsKnownKeyPair,
+    utils::{crypto::AsKnownKeyPair, p521::P521KeyPair},
 };
 use base64::prelude::*;
 use std::str::FromStr;
@@ -133,6 +135,28 @@ pub(crate) async fn _try_unpack_sign(
                 .verify::<K256KeyPair>((signer_kid, &signer_key))
                 .context("Unable verify sign envelope")?
         }
+        jws::Algorithm::Es384 => {
+            envelope.metadata.sign_alg = Some(SignAlg::ES384);
+
+            let signer_key = signer_key
+                .as_p384(&signer_key_jwk)
+                .context("Unable instantiate signer key")?;
+
+            parsed_jws
+                .verify::<P384KeyPair>((signer_kid, &signer_key))
+                .context("Unable verify sign envelope")?
+        }
+        jws::Algorithm::Es512 => {
+            envelope.metadata.sign_alg = Some(SignAlg::ES512);
+
+            let signer_key = signer_key
+                .as_p521(&signer_key_jwk)
+                .context("Unable instantiate signer key")?;
+
+            parsed_jws
+                .verify::<P521KeyPair>((signer_kid, &signer_key))
+                .context("Unable verify sign envelope")?
+        }
         jws::Algorithm::Other(_) => Err(err_msg(
             ErrorKind::Unsupported,
             "Unsupported signature algorithm",
@@ -174,7 +174,14 @@ fn find_did_comm_service(
     }
 }
 
-async fn resolve_did_comm_services_chain(
+/// Resolves the chain of DIDCommMessaging services required to reach `to`.
+/// If the service endpoint of `to` is itself a DID (i.e. a mediator), that DID's service
+/// is resolved and placed at the front of the chain.
+///
+/// Returns
+/// - Empty Vec if `to` has no DIDCommMessaging service
+/// - Vec of (service_id, service), the first element is the endpoint to deliver to
+pub async fn resolve_did_comm_services_chain(
     to: &str,
     service_id: Option<&str>,
     resolver: &DIDCacheClient,
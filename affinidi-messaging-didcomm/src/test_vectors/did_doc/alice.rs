@@ -0,0 +1,122 @@
+/// DID Document for `did:example:alice`, matching the keys in [`crate::test_vectors::ALICE_SECRETS`]
+pub const ALICE_DID_DOC: &str = r#"
+    {
+        "@context": [
+            "https://www.w3.org/ns/did/v1",
+            "https://w3id.org/security/suites/jws-2020/v1"
+        ],
+        "id": "did:example:alice",
+        "verificationMethod": [
+            {
+                "id": "did:example:alice#key-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "Ed25519",
+                    "x": "G-boxFB6vOZBu-wXkm-9Lh79I8nf9Z50cILaOgKKGww"
+                }
+            },
+            {
+                "id": "did:example:alice#key-2",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-256",
+                    "x": "2syLh57B-dGpa0F8p1JrO6JU7UUSF6j7qL-vfk1eOoY",
+                    "y": "BgsGtI7UPsObMRjdElxLOrgAO9JggNMjOcfzEPox18w"
+                }
+            },
+            {
+                "id": "did:example:alice#key-3",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "secp256k1",
+                    "x": "aToW5EaTq5mlAf8C5ECYDSkqsJycrW-e1SQ6_GJcAOk",
+                    "y": "JAGX94caA21WKreXwYUaOCYTBMrqaX4KWIlsQZTHWCk"
+                }
+            },
+            {
+                "id": "did:example:alice#key-4",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-384",
+                    "x": "s8d7ooKzV4WBQC6musgaKwb-XnPpMfLfvqJ1LzaL_cR4o5W2BWKIIBdXo4ZXmAIp",
+                    "y": "MpfI9PpFGg-oXWU0bk-970gaitT1cfQmGOi3NV0NeLW75Dvg9o8JpwdQdTWtLDot"
+                }
+            },
+            {
+                "id": "did:example:alice#key-5",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-521",
+                    "x": "AWzEo8MAtw4QQhzuly2GogJvjuHhimGPlcDpFpFaCKyV86cN3-l49bre7cT3EOf2P5KO1U-8qd-fFPS76c-lGqqf",
+                    "y": "AVzdIw--MrY7QzHBdpH4uslPHMimaRn5IovvnqqrmHv33_TYpXI-uvZYrHlECoDs1IG9fcVZvs_f5BvQj7m8XgKF"
+                }
+            },
+            {
+                "id": "did:example:alice#key-x25519-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "OKP",
+                    "crv": "X25519",
+                    "x": "avH0O2Y4tqLAq8y9zpianr8ajii5m4F_mICrzNlatXs"
+                }
+            },
+            {
+                "id": "did:example:alice#key-p256-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-256",
+                    "x": "L0crjMN1g0Ih4sYAJ_nGoHUck2cloltUpUVQDhF2nHE",
+                    "y": "SxYgE7CmEJYi7IDhgK5jI4ZiajO8jPRZDldVhqFpYoo"
+                }
+            },
+            {
+                "id": "did:example:alice#key-p384-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-384",
+                    "x": "S6cF3kOgdRAMj_loXOYaA5IW4liuDHPUX_N7uGq70OfDvBZhUsdiHXHFYB6eU_cA",
+                    "y": "E2zwXQ3lqJDuHuEInBTq4Ewr-fQCgBsYBG5guZR07S7dRVfyJ-eV8uTN6wrKJVyX"
+                }
+            },
+            {
+                "id": "did:example:alice#key-p521-1",
+                "type": "JsonWebKey2020",
+                "controller": "did:example:alice",
+                "publicKeyJwk": {
+                    "kty": "EC",
+                    "crv": "P-521",
+                    "x": "AHBEVPRhAv-WHDEvxVM9S0px9WxxwHL641Pemgk9sDdxvli9VpKCBdra5gg_4kupBDhz__AlaBgKOC_15J2Byptz",
+                    "y": "AciGcHJCD_yMikQvlmqpkBbVqqbg93mMVcgvXBYAQPP-u9AF7adybwZrNfHWCKAQwGF9ugd0Zhg7mLMEszIONFRk"
+                }
+            }
+        ],
+        "authentication": [
+            "did:example:alice#key-1",
+            "did:example:alice#key-2",
+            "did:example:alice#key-3",
+            "did:example:alice#key-4",
+            "did:example:alice#key-5"
+        ],
+        "keyAgreement": [
+            "did:example:alice#key-x25519-1",
+            "did:example:alice#key-p256-1",
+            "did:example:alice#key-p384-1",
+            "did:example:alice#key-p521-1"
+        ]
+    }
+"#;
@@ -0,0 +1,6 @@
+mod alice;
+mod bob;
+
+pub use alice::*;
+
+pub use bob::*;
@@ -1,4 +1,5 @@
 mod common;
+mod did_doc;
 mod encrypted;
 mod from_prior;
 mod from_prior_jwt;
@@ -9,6 +10,8 @@ mod signed;
 
 pub use common::*;
 
+pub use did_doc::*;
+
 pub use from_prior::*;
 
 pub use from_prior_jwt::*;
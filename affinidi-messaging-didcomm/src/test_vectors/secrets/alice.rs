@@ -41,6 +41,32 @@ lazy_static! {
             })
         },
     };
+    pub static ref ALICE_SECRET_AUTH_KEY_P384: Secret = Secret {
+        id: "did:example:alice#key-4".into(),
+        type_: SecretType::JsonWebKey2020,
+        secret_material: SecretMaterial::JWK {
+            private_key_jwk: json!({
+                "kty": "EC",
+                "d": "2eNsbSAPV2NWsapVbAsw8Bypt-ZnVmPtBVyjmcqa1mlgYyr6MsGo0MYzQ_d-9htB",
+                "crv": "P-384",
+                "x": "s8d7ooKzV4WBQC6musgaKwb-XnPpMfLfvqJ1LzaL_cR4o5W2BWKIIBdXo4ZXmAIp",
+                "y": "MpfI9PpFGg-oXWU0bk-970gaitT1cfQmGOi3NV0NeLW75Dvg9o8JpwdQdTWtLDot",
+            })
+        },
+    };
+    pub static ref ALICE_SECRET_AUTH_KEY_P521: Secret = Secret {
+        id: "did:example:alice#key-5".into(),
+        type_: SecretType::JsonWebKey2020,
+        secret_material: SecretMaterial::JWK {
+            private_key_jwk: json!({
+                "kty": "EC",
+                "d": "AbmUHWz52fa3PHkz6rl1JbpNSI-i73oEGTfuxiK2B4IwGXhiTbYNdGDrvH3e7HRmjNOQnNaVIfmB8mgcE6p1aZBD",
+                "crv": "P-521",
+                "x": "AWzEo8MAtw4QQhzuly2GogJvjuHhimGPlcDpFpFaCKyV86cN3-l49bre7cT3EOf2P5KO1U-8qd-fFPS76c-lGqqf",
+                "y": "AVzdIw--MrY7QzHBdpH4uslPHMimaRn5IovvnqqrmHv33_TYpXI-uvZYrHlECoDs1IG9fcVZvs_f5BvQj7m8XgKF",
+            })
+        },
+    };
     pub static ref ALICE_SECRET_KEY_AGREEMENT_KEY_X25519: Secret = Secret {
         id: "did:example:alice#key-x25519-1".into(),
         type_: SecretType::JsonWebKey2020,
@@ -66,6 +92,19 @@ lazy_static! {
             })
         },
     };
+    pub static ref ALICE_SECRET_KEY_AGREEMENT_KEY_P384: Secret = Secret {
+        id: "did:example:alice#key-p384-1".into(),
+        type_: SecretType::JsonWebKey2020,
+        secret_material: SecretMaterial::JWK {
+            private_key_jwk: json!({
+                "kty": "EC",
+                "d": "9upIPvgM9BjDOLtiTNs_sWjx6sSMiBVPwFoobWax_J9Al0pnXrKwZLB458g4BPKu",
+                "crv": "P-384",
+                "x": "S6cF3kOgdRAMj_loXOYaA5IW4liuDHPUX_N7uGq70OfDvBZhUsdiHXHFYB6eU_cA",
+                "y": "E2zwXQ3lqJDuHuEInBTq4Ewr-fQCgBsYBG5guZR07S7dRVfyJ-eV8uTN6wrKJVyX",
+            })
+        },
+    };
     pub static ref ALICE_SECRET_KEY_AGREEMENT_KEY_P521: Secret = Secret {
         id: "did:example:alice#key-p521-1".into(),
         type_: SecretType::JsonWebKey2020,
@@ -83,8 +122,11 @@ lazy_static! {
         ALICE_SECRET_AUTH_KEY_ED25519.clone(),
         ALICE_SECRET_AUTH_KEY_P256.clone(),
         ALICE_SECRET_AUTH_KEY_SECP256K1.clone(),
+        ALICE_SECRET_AUTH_KEY_P384.clone(),
+        ALICE_SECRET_AUTH_KEY_P521.clone(),
         ALICE_SECRET_KEY_AGREEMENT_KEY_X25519.clone(),
         ALICE_SECRET_KEY_AGREEMENT_KEY_P256.clone(),
+        ALICE_SECRET_KEY_AGREEMENT_KEY_P384.clone(),
         ALICE_SECRET_KEY_AGREEMENT_KEY_P521.clone(),
     ];
 }
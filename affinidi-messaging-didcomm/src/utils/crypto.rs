@@ -4,6 +4,7 @@ use askar_crypto::{
         ed25519::Ed25519KeyPair,
         k256::K256KeyPair,
         p256::P256KeyPair,
+        p384::P384KeyPair,
         x25519::X25519KeyPair,
     },
     buffer::SecretBytes,
@@ -13,7 +14,10 @@ use askar_crypto::{
 };
 use ssi::JWK;
 
-use crate::error::{Error, ErrorKind, Result, err_msg};
+use crate::{
+    error::{Error, ErrorKind, Result, err_msg},
+    utils::p521::P521KeyPair,
+};
 
 /// Note this trait is compatible with KW algorithms only
 pub(crate) trait KeyWrap: KeyAeadInPlace {
@@ -146,6 +150,8 @@ pub(crate) enum KnownKeyAlg {
     Ed25519,
     X25519,
     P256,
+    P384,
+    P521,
     K256,
     Unsupported,
 }
@@ -155,6 +161,8 @@ pub enum KnownKeyPair {
     Ed25519(Ed25519KeyPair),
     X25519(X25519KeyPair),
     P256(P256KeyPair),
+    P384(P384KeyPair),
+    P521(P521KeyPair),
     K256(K256KeyPair),
 }
 
@@ -195,6 +203,28 @@ pub trait AsKnownKeyPair {
         }
     }
 
+    fn as_p384(&self, jwk: &JWK) -> Result<P384KeyPair> {
+        if self.key_alg(jwk) != KnownKeyAlg::P384 {
+            Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
+        }
+
+        match self.as_key_pair(jwk)? {
+            KnownKeyPair::P384(k) => Ok(k),
+            _ => Err(err_msg(ErrorKind::InvalidState, "Unexpected key pair type"))?,
+        }
+    }
+
+    fn as_p521(&self, jwk: &JWK) -> Result<P521KeyPair> {
+        if self.key_alg(jwk) != KnownKeyAlg::P521 {
+            Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
+        }
+
+        match self.as_key_pair(jwk)? {
+            KnownKeyPair::P521(k) => Ok(k),
+            _ => Err(err_msg(ErrorKind::InvalidState, "Unexpected key pair type"))?,
+        }
+    }
+
     fn as_k256(&self, jwk: &JWK) -> Result<K256KeyPair> {
         if self.key_alg(jwk) != KnownKeyAlg::K256 {
             Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
@@ -245,6 +275,28 @@ pub trait AsKnownKeyPairSecret {
         }
     }
 
+    fn as_p384(&self) -> Result<P384KeyPair> {
+        if self.key_alg() != KnownKeyAlg::P384 {
+            Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
+        }
+
+        match self.as_key_pair()? {
+            KnownKeyPair::P384(k) => Ok(k),
+            _ => Err(err_msg(ErrorKind::InvalidState, "Unexpected key pair type"))?,
+        }
+    }
+
+    fn as_p521(&self) -> Result<P521KeyPair> {
+        if self.key_alg() != KnownKeyAlg::P521 {
+            Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
+        }
+
+        match self.as_key_pair()? {
+            KnownKeyPair::P521(k) => Ok(k),
+            _ => Err(err_msg(ErrorKind::InvalidState, "Unexpected key pair type"))?,
+        }
+    }
+
     fn as_k256(&self) -> Result<K256KeyPair> {
         if self.key_alg() != KnownKeyAlg::K256 {
             Err(err_msg(ErrorKind::InvalidState, "Unexpected key alg"))?
@@ -1,2 +1,3 @@
 pub(crate) mod crypto;
+pub(crate) mod p521;
 pub(crate) mod serde;
@@ -0,0 +1,231 @@
+//! P-521 key pair for ECDH-ES/ECDH-1PU key agreement and ES512 signatures
+//!
+//! askar-crypto has no P-521 support, so this implements the askar key traits
+//! on top of the RustCrypto `p521` crate. This allows P-521 keys to be used
+//! with the same generic JWE and JWS functions as the askar key types.
+
+use askar_crypto::{
+    Error as AskarError, ErrorKind as AskarErrorKind,
+    buffer::WriteBuffer,
+    jwk::{FromJwk, JwkEncoder, JwkParts, ToJwk},
+    kdf::KeyExchange,
+    random::KeyMaterial,
+    repr::KeyGen,
+    sign::{KeySigVerify, KeySign, SignatureType},
+};
+use p521::{
+    EncodedPoint, FieldBytes, PublicKey, SecretKey,
+    ecdh::diffie_hellman,
+    ecdsa::{
+        Signature, SigningKey, VerifyingKey,
+        signature::{Signer, Verifier},
+    },
+    elliptic_curve::sec1::{Coordinates, ToEncodedPoint},
+};
+use std::fmt;
+
+/// JWK curve name for P-521 keys
+pub(crate) const JWK_CURVE: &str = "P-521";
+
+/// JWK key type for P-521 keys
+pub(crate) const JWK_KEY_TYPE: &str = "EC";
+
+/// Length of a P-521 field element (and so each JWK coordinate) in bytes
+const FIELD_LENGTH: usize = 66;
+
+/// Length of an ES512 signature (r || s) in bytes
+const SIGNATURE_LENGTH: usize = FIELD_LENGTH * 2;
+
+/// A P-521 public key with an optional secret key
+#[derive(Clone)]
+pub struct P521KeyPair {
+    secret: Option<SecretKey>,
+    public: PublicKey,
+}
+
+impl P521KeyPair {
+    fn from_secret_key(secret: SecretKey) -> Self {
+        P521KeyPair {
+            public: secret.public_key(),
+            secret: Some(secret),
+        }
+    }
+}
+
+impl fmt::Debug for P521KeyPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("P521KeyPair")
+            .field("secret", &self.secret.as_ref().map(|_| "<secret>"))
+            .field("public", &self.public)
+            .finish()
+    }
+}
+
+impl KeyGen for P521KeyPair {
+    fn generate(mut rng: impl KeyMaterial) -> Result<Self, AskarError> {
+        let mut buf = FieldBytes::default();
+        loop {
+            rng.read_okm(&mut buf);
+            // P-521 scalars are 521 bits, only the lowest bit of the first byte can be set
+            buf[0] &= 0x01;
+            if let Ok(secret) = SecretKey::from_bytes(&buf) {
+                return Ok(Self::from_secret_key(secret));
+            }
+        }
+    }
+}
+
+impl KeyExchange for P521KeyPair {
+    fn write_key_exchange(
+        &self,
+        other: &Self,
+        out: &mut dyn WriteBuffer,
+    ) -> Result<(), AskarError> {
+        let Some(secret) = &self.secret else {
+            return Err(AskarError::from(AskarErrorKind::MissingSecretKey));
+        };
+
+        let shared = diffie_hellman(secret.to_nonzero_scalar(), other.public.as_affine());
+        out.buffer_write(shared.raw_secret_bytes().as_ref())
+    }
+}
+
+impl KeySign for P521KeyPair {
+    fn write_signature(
+        &self,
+        message: &[u8],
+        sig_type: Option<SignatureType>,
+        out: &mut dyn WriteBuffer,
+    ) -> Result<(), AskarError> {
+        // askar has no ES512 signature type, so only the default is accepted
+        if sig_type.is_some() {
+            return Err(AskarError::from_msg(
+                AskarErrorKind::Unsupported,
+                "Unsupported signature type",
+            ));
+        }
+
+        let Some(secret) = &self.secret else {
+            return Err(AskarError::from(AskarErrorKind::MissingSecretKey));
+        };
+
+        let signing_key = SigningKey::from_bytes(&secret.to_bytes())
+            .map_err(|_| AskarError::from_msg(AskarErrorKind::InvalidKeyData, "Invalid key"))?;
+        let signature: Signature = signing_key.sign(message);
+        out.buffer_write(signature.to_bytes().as_ref())
+    }
+}
+
+impl KeySigVerify for P521KeyPair {
+    fn verify_signature(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        sig_type: Option<SignatureType>,
+    ) -> Result<bool, AskarError> {
+        if sig_type.is_some() {
+            return Err(AskarError::from_msg(
+                AskarErrorKind::Unsupported,
+                "Unsupported signature type",
+            ));
+        }
+
+        if signature.len() != SIGNATURE_LENGTH {
+            return Ok(false);
+        }
+
+        let Ok(signature) = Signature::from_slice(signature) else {
+            return Ok(false);
+        };
+
+        let verifying_key = VerifyingKey::from_sec1_bytes(&self.public.to_sec1_bytes())
+            .map_err(|_| AskarError::from_msg(AskarErrorKind::InvalidKeyData, "Invalid key"))?;
+        Ok(verifying_key.verify(message, &signature).is_ok())
+    }
+}
+
+impl ToJwk for P521KeyPair {
+    fn encode_jwk(&self, enc: &mut dyn JwkEncoder) -> Result<(), AskarError> {
+        let encoded = self.public.to_encoded_point(false);
+        let (x, y) = match encoded.coordinates() {
+            Coordinates::Uncompressed { x, y } => (x, y),
+            _ => {
+                return Err(AskarError::from_msg(
+                    AskarErrorKind::Unsupported,
+                    "Cannot convert point to JWK",
+                ));
+            }
+        };
+
+        enc.add_str("crv", JWK_CURVE)?;
+        enc.add_str("kty", JWK_KEY_TYPE)?;
+        enc.add_as_base64("x", &x[..])?;
+        enc.add_as_base64("y", &y[..])?;
+        if enc.is_secret() {
+            if let Some(secret) = &self.secret {
+                enc.add_as_base64("d", &secret.to_bytes()[..])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromJwk for P521KeyPair {
+    fn from_jwk_parts(jwk: JwkParts<'_>) -> Result<Self, AskarError> {
+        if jwk.kty != JWK_KEY_TYPE {
+            return Err(AskarError::from_msg(
+                AskarErrorKind::InvalidKeyData,
+                "Unsupported key type",
+            ));
+        }
+        if jwk.crv != JWK_CURVE {
+            return Err(AskarError::from_msg(
+                AskarErrorKind::InvalidKeyData,
+                "Unsupported key algorithm",
+            ));
+        }
+
+        let mut x = FieldBytes::default();
+        let mut y = FieldBytes::default();
+        if jwk.x.decode_base64(&mut x)? != FIELD_LENGTH
+            || jwk.y.decode_base64(&mut y)? != FIELD_LENGTH
+        {
+            return Err(AskarError::from_msg(
+                AskarErrorKind::InvalidKeyData,
+                "Invalid coordinate length",
+            ));
+        }
+
+        let public = PublicKey::from_sec1_bytes(
+            EncodedPoint::from_affine_coordinates(&x, &y, false).as_bytes(),
+        )
+        .map_err(|_| AskarError::from_msg(AskarErrorKind::InvalidKeyData, "Invalid public key"))?;
+
+        if jwk.d.is_some() {
+            let mut d = FieldBytes::default();
+            if jwk.d.decode_base64(&mut d)? != FIELD_LENGTH {
+                return Err(AskarError::from_msg(
+                    AskarErrorKind::InvalidKeyData,
+                    "Invalid secret key length",
+                ));
+            }
+
+            let secret = SecretKey::from_bytes(&d).map_err(|_| {
+                AskarError::from_msg(AskarErrorKind::InvalidKeyData, "Invalid secret key")
+            })?;
+            if secret.public_key() != public {
+                return Err(AskarError::from_msg(
+                    AskarErrorKind::InvalidKeyData,
+                    "Public key mismatch",
+                ));
+            }
+
+            Ok(Self::from_secret_key(secret))
+        } else {
+            Ok(P521KeyPair {
+                secret: None,
+                public,
+            })
+        }
+    }
+}
@@ -0,0 +1,260 @@
+/*!
+ * WebAssembly (`wasm-bindgen`) bindings for packing and unpacking DIDComm messages
+ *
+ * Enabled with the `wasm` feature. Messages, options and unpack results are passed as plain
+ * JavaScript objects. All pack and unpack methods return a `Promise`.
+ *
+ * DIDs are resolved locally (did:key, did:peer etc.) by the DID cache client.
+ */
+
+use crate::{Message, PackEncryptedOptions, UnpackMetadata, UnpackOptions, error::Error};
+use affinidi_did_resolver_cache_sdk::{DIDCacheClient, config::DIDCacheConfigBuilder};
+use affinidi_secrets_resolver::{SimpleSecretsResolver, secrets::Secret};
+use js_sys::Promise;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+struct DIDCommInner {
+    did_resolver: DIDCacheClient,
+    secrets_resolver: SimpleSecretsResolver,
+}
+
+/// Options for `packEncrypted()`, all fields are optional
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptOptions {
+    #[serde(default)]
+    protect_sender: bool,
+    #[serde(default = "crate::utils::serde::_true")]
+    forward: bool,
+    #[serde(default)]
+    messaging_service: Option<String>,
+}
+
+impl From<EncryptOptions> for PackEncryptedOptions {
+    fn from(options: EncryptOptions) -> Self {
+        PackEncryptedOptions {
+            protect_sender: options.protect_sender,
+            forward: options.forward,
+            messaging_service: options.messaging_service,
+            ..PackEncryptedOptions::default()
+        }
+    }
+}
+
+/// Result of `unpack()`
+#[derive(Serialize)]
+struct Unpacked {
+    message: Message,
+    metadata: UnpackMetadata,
+}
+
+#[wasm_bindgen]
+pub struct DIDComm {
+    inner: Rc<DIDCommInner>,
+}
+
+impl DIDComm {
+    async fn new(secrets: &[Secret]) -> Result<DIDComm, JsValue> {
+        let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+            .await
+            .map_err(|err| JsError::new(&format!("Couldn't create DID resolver: {}", err)))?;
+
+        Ok(DIDComm {
+            inner: Rc::new(DIDCommInner {
+                did_resolver,
+                secrets_resolver: SimpleSecretsResolver::new(secrets).await,
+            }),
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl DIDComm {
+    /// Creates a DIDComm instance
+    /// - `secrets` - Array of secrets (JWK private keys) used to pack and unpack messages
+    ///
+    /// Returns a `Promise<DIDComm>`
+    pub fn create(secrets: JsValue) -> Promise {
+        future_to_promise(async move {
+            let secrets: Vec<Secret> = if secrets.is_undefined() || secrets.is_null() {
+                Vec::new()
+            } else {
+                from_js(secrets)?
+            };
+
+            Ok(DIDComm::new(&secrets).await?.into())
+        })
+    }
+
+    /// Packs a plaintext message, returns a `Promise<string>`
+    #[wasm_bindgen(js_name = packPlaintext)]
+    pub fn pack_plaintext(&self, message: JsValue) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            let message: Message = from_js(message)?;
+            let packed = message
+                .pack_plaintext(&inner.did_resolver)
+                .await
+                .map_err(to_js)?;
+            Ok(packed.into())
+        })
+    }
+
+    /// Packs a signed message, returns a `Promise<string>`
+    /// - `sign_by` - DID or key ID to sign with
+    #[wasm_bindgen(js_name = packSigned)]
+    pub fn pack_signed(&self, message: JsValue, sign_by: String) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            let message: Message = from_js(message)?;
+            let (packed, _) = message
+                .pack_signed(&sign_by, &inner.did_resolver, &inner.secrets_resolver)
+                .await
+                .map_err(to_js)?;
+            Ok(packed.into())
+        })
+    }
+
+    /// Packs an encrypted message, returns a `Promise<string>`
+    /// - `to` - Recipient DID or key ID
+    /// - `from` - Sender DID or key ID, anonymous encryption if undefined
+    /// - `sign_by` - DID or key ID to sign with, not signed if undefined
+    /// - `options` - `{ protectSender, forward, messagingService }`
+    #[wasm_bindgen(js_name = packEncrypted)]
+    pub fn pack_encrypted(
+        &self,
+        message: JsValue,
+        to: String,
+        from: Option<String>,
+        sign_by: Option<String>,
+        options: JsValue,
+    ) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            let message: Message = from_js(message)?;
+            let options: PackEncryptedOptions = if options.is_undefined() || options.is_null() {
+                PackEncryptedOptions::default()
+            } else {
+                from_js::<EncryptOptions>(options)?.into()
+            };
+
+            let (packed, _) = message
+                .pack_encrypted(
+                    &to,
+                    from.as_deref(),
+                    sign_by.as_deref(),
+                    &inner.did_resolver,
+                    &inner.secrets_resolver,
+                    &options,
+                )
+                .await
+                .map_err(to_js)?;
+            Ok(packed.into())
+        })
+    }
+
+    /// Unpacks a plaintext, signed or encrypted message
+    /// Returns a `Promise<{ message, metadata }>`
+    pub fn unpack(&self, message: String) -> Promise {
+        let inner = self.inner.clone();
+        future_to_promise(async move {
+            let (message, metadata) = Message::unpack_string(
+                &message,
+                &inner.did_resolver,
+                &inner.secrets_resolver,
+                &UnpackOptions::default(),
+            )
+            .await
+            .map_err(to_js)?;
+
+            serde_wasm_bindgen::to_value(&Unpacked { message, metadata })
+                .map_err(|err| JsError::new(&err.to_string()).into())
+        })
+    }
+}
+
+fn from_js<T>(value: JsValue) -> Result<T, JsValue>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    serde_wasm_bindgen::from_value(value)
+        .map_err(|err| JsError::new(&format!("Invalid argument: {}", err)).into())
+}
+
+/// Converts a DIDComm error to a JS `Error`, the error kind is available as `error.name`
+fn to_js(err: Error) -> JsValue {
+    let error = js_sys::Error::new(&err.to_string());
+    error.set_name(&format!("{:?}", err.kind()));
+    error.into()
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wasm_bindgen_futures::JsFuture;
+    use wasm_bindgen_test::*;
+
+    const DID: &str = "did:key:zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM";
+    const KID: &str = "did:key:zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM#zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM";
+
+    async fn didcomm() -> DIDComm {
+        let secrets: Vec<Secret> = serde_json::from_value(json!([{
+            "id": KID,
+            "type": "JsonWebKey2020",
+            "privateKeyJwk": {
+                "kty": "EC",
+                "crv": "P-256",
+                "x": "kgY1Us-Q4wnZ0eyhfZnA7rJe_hiOhhph5GIazpcpFvQ",
+                "y": "gEdgGqyfsxLyssrK6_nr6DK7xGmrjMm3MhGRLFlFmo0",
+                "d": "GclycCHT4Ze1b-mLa99yHmfBCXtqz63Fnx-x8ngJ-fY"
+            }
+        }]))
+        .unwrap();
+
+        DIDComm::new(&secrets).await.unwrap()
+    }
+
+    fn message() -> JsValue {
+        serde_wasm_bindgen::to_value(
+            &Message::build("1".into(), "test".into(), json!({"hello": "world"})).finalize(),
+        )
+        .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_pack_encrypted_round_trip() {
+        let didcomm = didcomm().await;
+
+        let packed = JsFuture::from(didcomm.pack_encrypted(
+            message(),
+            DID.into(),
+            None,
+            None,
+            serde_wasm_bindgen::to_value(&json!({"forward": false})).unwrap(),
+        ))
+        .await
+        .unwrap();
+
+        let unpacked = JsFuture::from(didcomm.unpack(packed.as_string().unwrap()))
+            .await
+            .unwrap();
+        let unpacked: serde_json::Value = serde_wasm_bindgen::from_value(unpacked).unwrap();
+        assert_eq!(unpacked["metadata"]["encrypted"], json!(true));
+        assert_eq!(unpacked["message"]["body"], json!({"hello": "world"}));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_unpack_malformed() {
+        let didcomm = didcomm().await;
+
+        let err = JsFuture::from(didcomm.unpack("not a message".into()))
+            .await
+            .unwrap_err();
+        let err: js_sys::Error = err.into();
+        assert_eq!(err.name(), "Malformed");
+    }
+}
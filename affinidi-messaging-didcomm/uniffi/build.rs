@@ -1,3 +0,0 @@
-fn main() {
-    uniffi_build::generate_scaffolding("./src/didcomm.udl").unwrap();
-}
@@ -0,0 +1,214 @@
+/*!
+ * Thin wrapper around the Affinidi Messaging SDK
+ *
+ * Covers the common client flow: add secrets and profiles, send messages via the mediator,
+ * fetch messages from the inbox and read live streamed messages over the websocket.
+ */
+
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_sdk::{
+    ATM,
+    config::ATMConfig,
+    messages::{DeleteMessageRequest, FetchDeletePolicy, fetch::FetchOptions},
+    profiles::ATMProfile,
+    protocols::message_pickup::MessagePickup,
+};
+use affinidi_secrets_resolver::{SecretsResolver, secrets::Secret};
+use affinidi_tdk_common::TDKSharedState;
+use std::{sync::Arc, time::Duration};
+
+use crate::{didcomm::Unpacked, errors::FfiError};
+
+/// A mediator profile (DID + mediator) managed by [ATMClient]
+#[derive(uniffi::Object)]
+pub struct Profile {
+    inner: Arc<ATMProfile>,
+}
+
+#[uniffi::export]
+impl Profile {
+    pub fn did(&self) -> String {
+        self.inner.inner.did.clone()
+    }
+
+    pub fn alias(&self) -> String {
+        self.inner.inner.alias.clone()
+    }
+
+    /// DID of the mediator this profile uses, if any
+    pub fn mediator_did(&self) -> Option<String> {
+        self.inner
+            .dids()
+            .ok()
+            .map(|(_, mediator)| mediator.to_string())
+    }
+}
+
+/// A message received from the mediator
+#[derive(uniffi::Record)]
+pub struct ReceivedMessage {
+    /// Mediator message ID, used to delete the message
+    pub msg_id: String,
+    pub unpacked: Unpacked,
+}
+
+#[derive(uniffi::Object)]
+pub struct ATMClient {
+    atm: ATM,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl ATMClient {
+    /// Creates an ATM client
+    /// - `ssl_certificates` - Paths to additional CA certificates (e.g. for a local mediator)
+    #[uniffi::constructor]
+    pub async fn new(ssl_certificates: Vec<String>) -> Result<Arc<Self>, FfiError> {
+        let mut ssl_certificates = ssl_certificates;
+        let config = ATMConfig::builder()
+            .with_ssl_certificates(&mut ssl_certificates)
+            .build()?;
+        let tdk = TDKSharedState::default().await;
+
+        Ok(Arc::new(ATMClient {
+            atm: ATM::new(config, tdk).await?,
+        }))
+    }
+
+    /// Adds a secret (private key) for a profile DID
+    /// - `kid` - DID URL of the key
+    /// - `jwk` - Private key as a JWK JSON string
+    pub async fn add_secret(&self, kid: String, jwk: String) -> Result<(), FfiError> {
+        let jwk: serde_json::Value = serde_json::from_str(&jwk)?;
+        self.atm
+            .get_tdk()
+            .secrets_resolver
+            .insert(Secret::from_str(&kid, &jwk))
+            .await;
+        Ok(())
+    }
+
+    /// Adds a profile, the secrets for `did` must already have been added
+    /// - `mediator_did` - Mediator to use, defaults to the mediator in the DID Document
+    /// - `live_stream` - Open a websocket to the mediator for live delivery
+    pub async fn profile_add(
+        &self,
+        alias: Option<String>,
+        did: String,
+        mediator_did: Option<String>,
+        live_stream: bool,
+    ) -> Result<Arc<Profile>, FfiError> {
+        let profile = ATMProfile::new(&self.atm, alias, did, mediator_did).await?;
+        let inner = self.atm.profile_add(&profile, live_stream).await?;
+
+        Ok(Arc::new(Profile { inner }))
+    }
+
+    /// Removes a profile, returns false if the profile didn't exist
+    pub async fn profile_remove(&self, did: String) -> Result<bool, FfiError> {
+        Ok(self.atm.profile_remove(&did).await?)
+    }
+
+    /// Encrypts (authcrypt, signed) a message from the profile to `to_did` and sends it via the
+    /// profile's mediator. Returns the DIDComm message ID
+    /// - `message` - Plaintext DIDComm message as JSON
+    pub async fn send_message(
+        &self,
+        profile: Arc<Profile>,
+        message: String,
+        to_did: String,
+    ) -> Result<String, FfiError> {
+        let message: Message = serde_json::from_str(&message)?;
+        let (profile_did, mediator_did) = profile.inner.dids()?;
+
+        let (packed, _) = self
+            .atm
+            .pack_encrypted(&message, &to_did, Some(profile_did), Some(profile_did))
+            .await?;
+
+        self.atm
+            .forward_and_send_message(
+                &profile.inner,
+                &packed,
+                Some(&message.id),
+                mediator_did,
+                &to_did,
+                None,
+                None,
+                false,
+            )
+            .await?;
+
+        Ok(message.id)
+    }
+
+    /// Fetches up to `limit` messages from the profile inbox and unpacks them
+    /// - `delete` - Delete the messages from the mediator once fetched
+    pub async fn fetch_messages(
+        &self,
+        profile: Arc<Profile>,
+        limit: u32,
+        delete: bool,
+    ) -> Result<Vec<ReceivedMessage>, FfiError> {
+        let options = FetchOptions {
+            limit: limit as usize,
+            delete_policy: if delete {
+                FetchDeletePolicy::Optimistic
+            } else {
+                FetchDeletePolicy::DoNotDelete
+            },
+            ..Default::default()
+        };
+        let response = self.atm.fetch_messages(&profile.inner, &options).await?;
+
+        let mut messages = Vec::new();
+        for element in response.success {
+            let Some(msg) = element.msg else {
+                continue;
+            };
+            let (message, metadata) = self.atm.unpack(&msg).await?;
+            messages.push(ReceivedMessage {
+                msg_id: element.msg_id,
+                unpacked: Unpacked::new(&message, &metadata)?,
+            });
+        }
+
+        Ok(messages)
+    }
+
+    /// Returns the next live streamed message for any profile with live streaming enabled
+    /// - `wait_ms` - How long to wait for a message, waits forever if None
+    ///
+    /// Returns None if no message arrived in time
+    pub async fn live_stream_next(
+        &self,
+        wait_ms: Option<u64>,
+    ) -> Result<Option<Unpacked>, FfiError> {
+        let next = MessagePickup::default()
+            .live_stream_next(&self.atm, wait_ms.map(Duration::from_millis))
+            .await?;
+
+        match next {
+            Some((message, metadata)) => Ok(Some(Unpacked::new(&message, &metadata)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Deletes messages from the mediator, returns the IDs that were deleted
+    pub async fn delete_messages(
+        &self,
+        profile: Arc<Profile>,
+        message_ids: Vec<String>,
+    ) -> Result<Vec<String>, FfiError> {
+        let response = self
+            .atm
+            .delete_messages_direct(&profile.inner, &DeleteMessageRequest { message_ids })
+            .await?;
+
+        Ok(response.success)
+    }
+
+    /// Closes websockets and stops the SDK background tasks
+    pub async fn shutdown(&self) {
+        self.atm.graceful_shutdown().await;
+    }
+}
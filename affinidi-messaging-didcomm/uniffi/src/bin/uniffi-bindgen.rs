@@ -0,0 +1,6 @@
+//! Generates the Kotlin and Swift bindings from the compiled library
+//! `cargo run --bin uniffi-bindgen generate --library <path to lib> --language kotlin --out-dir <dir>`
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -1,6 +0,0 @@
-pub mod resolvers;
-
-pub(crate) mod did_resolver;
-pub(crate) mod did_resolver_adapter;
-
-pub use did_resolver::{DIDResolver, OnDIDResolverResult};
@@ -0,0 +1,216 @@
+/*!
+ * DIDComm packing and unpacking
+ *
+ * DIDs are resolved locally by a `DIDCacheClient` (did:key, did:peer etc.), secrets are held in
+ * memory by a `ThreadedSecretsResolver` and need to be added with [DIDComm::add_secret].
+ */
+
+use affinidi_did_resolver_cache_sdk::{DIDCacheClient, config::DIDCacheConfigBuilder};
+use affinidi_messaging_didcomm::{Message, PackEncryptedOptions, UnpackMetadata, UnpackOptions};
+use affinidi_secrets_resolver::{SecretsResolver, ThreadedSecretsResolver, secrets::Secret};
+use std::sync::Arc;
+
+use crate::errors::FfiError;
+
+/// Options for [DIDComm::pack_encrypted]
+#[derive(uniffi::Record)]
+pub struct EncryptOptions {
+    /// Hide the sender from mediators (authcrypt is wrapped in anoncrypt)
+    #[uniffi(default = false)]
+    pub protect_sender: bool,
+    /// Wrap the message in forward messages for any mediators of the recipient
+    #[uniffi(default = true)]
+    pub forward: bool,
+    /// DID URL of the recipient messaging service to use, defaults to the first service
+    #[uniffi(default = None)]
+    pub messaging_service: Option<String>,
+}
+
+impl From<EncryptOptions> for PackEncryptedOptions {
+    fn from(options: EncryptOptions) -> Self {
+        PackEncryptedOptions {
+            protect_sender: options.protect_sender,
+            forward: options.forward,
+            messaging_service: options.messaging_service,
+            ..PackEncryptedOptions::default()
+        }
+    }
+}
+
+/// An unpacked DIDComm message
+#[derive(uniffi::Record)]
+pub struct Unpacked {
+    /// Plaintext DIDComm message as JSON
+    pub message: String,
+    pub encrypted: bool,
+    pub authenticated: bool,
+    pub non_repudiation: bool,
+    pub anonymous_sender: bool,
+    /// SHA256 hash of the packed message, used by the mediator as the message ID
+    pub sha256_hash: String,
+    pub encrypted_from_kid: Option<String>,
+    pub encrypted_to_kids: Vec<String>,
+    pub sign_from: Option<String>,
+}
+
+impl Unpacked {
+    pub(crate) fn new(message: &Message, metadata: &UnpackMetadata) -> Result<Self, FfiError> {
+        Ok(Unpacked {
+            message: serde_json::to_string(message)?,
+            encrypted: metadata.encrypted,
+            authenticated: metadata.authenticated,
+            non_repudiation: metadata.non_repudiation,
+            anonymous_sender: metadata.anonymous_sender,
+            sha256_hash: metadata.sha256_hash.clone(),
+            encrypted_from_kid: metadata.encrypted_from_kid.clone(),
+            encrypted_to_kids: metadata.encrypted_to_kids.clone(),
+            sign_from: metadata.sign_from.clone(),
+        })
+    }
+}
+
+#[derive(uniffi::Object)]
+pub struct DIDComm {
+    did_resolver: DIDCacheClient,
+    secrets_resolver: ThreadedSecretsResolver,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl DIDComm {
+    /// Creates a DIDComm instance with a local DID resolver and an empty secrets resolver
+    #[uniffi::constructor]
+    pub async fn new() -> Result<Arc<Self>, FfiError> {
+        let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+            .await
+            .map_err(|err| FfiError::DIDComm(format!("Couldn't create DID resolver: {}", err)))?;
+        let (secrets_resolver, _) = ThreadedSecretsResolver::new(None).await;
+
+        Ok(Arc::new(DIDComm {
+            did_resolver,
+            secrets_resolver,
+        }))
+    }
+
+    /// Adds a secret (private key) used to pack and unpack messages
+    /// - `kid` - DID URL of the key (e.g. `did:key:z6LS...#z6LS...`)
+    /// - `jwk` - Private key as a JWK JSON string
+    pub async fn add_secret(&self, kid: String, jwk: String) -> Result<(), FfiError> {
+        let jwk: serde_json::Value = serde_json::from_str(&jwk)?;
+        self.secrets_resolver
+            .insert(Secret::from_str(&kid, &jwk))
+            .await;
+        Ok(())
+    }
+
+    /// Packs a plaintext message
+    /// - `message` - Plaintext DIDComm message as JSON
+    pub async fn pack_plaintext(&self, message: String) -> Result<String, FfiError> {
+        let message: Message = serde_json::from_str(&message)?;
+        Ok(message.pack_plaintext(&self.did_resolver).await?)
+    }
+
+    /// Packs a signed message
+    /// - `sign_by` - DID or key ID to sign with
+    pub async fn pack_signed(&self, message: String, sign_by: String) -> Result<String, FfiError> {
+        let message: Message = serde_json::from_str(&message)?;
+        let (packed, _) = message
+            .pack_signed(&sign_by, &self.did_resolver, &self.secrets_resolver)
+            .await?;
+        Ok(packed)
+    }
+
+    /// Packs an encrypted message
+    /// - `to` - Recipient DID or key ID
+    /// - `from` - Sender DID or key ID, anonymous encryption if None
+    /// - `sign_by` - DID or key ID to sign with, not signed if None
+    pub async fn pack_encrypted(
+        &self,
+        message: String,
+        to: String,
+        from: Option<String>,
+        sign_by: Option<String>,
+        options: EncryptOptions,
+    ) -> Result<String, FfiError> {
+        let message: Message = serde_json::from_str(&message)?;
+        let (packed, _) = message
+            .pack_encrypted(
+                &to,
+                from.as_deref(),
+                sign_by.as_deref(),
+                &self.did_resolver,
+                &self.secrets_resolver,
+                &options.into(),
+            )
+            .await?;
+        Ok(packed)
+    }
+
+    /// Unpacks a plaintext, signed or encrypted message
+    pub async fn unpack(&self, message: String) -> Result<Unpacked, FfiError> {
+        let (message, metadata) = Message::unpack_string(
+            &message,
+            &self.did_resolver,
+            &self.secrets_resolver,
+            &UnpackOptions::default(),
+        )
+        .await?;
+
+        Unpacked::new(&message, &metadata)
+    }
+
+    /// Returns the key IDs from `kids` that have a secret in the secrets resolver
+    pub async fn find_secrets(&self, kids: Vec<String>) -> Vec<String> {
+        self.secrets_resolver.find_secrets(&kids).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DID: &str = "did:key:zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM";
+    const JWK: &str = r#"{"kty":"EC","crv":"P-256","x":"kgY1Us-Q4wnZ0eyhfZnA7rJe_hiOhhph5GIazpcpFvQ","y":"gEdgGqyfsxLyssrK6_nr6DK7xGmrjMm3MhGRLFlFmo0","d":"GclycCHT4Ze1b-mLa99yHmfBCXtqz63Fnx-x8ngJ-fY"}"#;
+
+    #[tokio::test]
+    async fn test_pack_encrypted_round_trip() {
+        let didcomm = DIDComm::new().await.unwrap();
+        didcomm
+            .add_secret(
+                [DID, "#zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM"].concat(),
+                JWK.into(),
+            )
+            .await
+            .unwrap();
+
+        let message = r#"{"id":"1","typ":"application/didcomm-plain+json","type":"test","body":{"hello":"world"}}"#;
+        let packed = didcomm
+            .pack_encrypted(
+                message.into(),
+                DID.into(),
+                None,
+                None,
+                EncryptOptions {
+                    protect_sender: false,
+                    forward: false,
+                    messaging_service: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let unpacked = didcomm.unpack(packed).await.unwrap();
+        assert!(unpacked.encrypted);
+        assert!(!unpacked.authenticated);
+        let message: Message = serde_json::from_str(&unpacked.message).unwrap();
+        assert_eq!(message.body, serde_json::json!({"hello": "world"}));
+    }
+
+    #[tokio::test]
+    async fn test_unpack_malformed() {
+        let didcomm = DIDComm::new().await.unwrap();
+        assert!(matches!(
+            didcomm.unpack("not a message".into()).await,
+            Err(FfiError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use affinidi_messaging_didcomm::error::{Error as DIDCommError, ErrorKind};
+use affinidi_messaging_sdk::errors::ATMError;
+
+/// Errors returned to the foreign language bindings
+/// DIDComm errors keep their kind, so callers can tell a missing DID or secret from a bad message
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum FfiError {
+    #[error("DID not resolved: {0}")]
+    DIDNotResolved(String),
+    #[error("DID URL not found: {0}")]
+    DIDUrlNotFound(String),
+    #[error("Secret not found: {0}")]
+    SecretNotFound(String),
+    #[error("Malformed message: {0}")]
+    Malformed(String),
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+    #[error("Illegal argument: {0}")]
+    IllegalArgument(String),
+    #[error("DIDComm error: {0}")]
+    DIDComm(String),
+    #[error("ACL Denied: {0}")]
+    ACLDenied(String),
+    #[error("Authentication error: {0}")]
+    Authentication(String),
+    #[error("Problem Report: code({0}) comment({1})")]
+    ProblemReport(String, String),
+    #[error("ATM error: {0}")]
+    ATM(String),
+}
+
+impl From<DIDCommError> for FfiError {
+    fn from(err: DIDCommError) -> Self {
+        let msg = err.to_string();
+        match err.kind() {
+            ErrorKind::DIDNotResolved => FfiError::DIDNotResolved(msg),
+            ErrorKind::DIDUrlNotFound => FfiError::DIDUrlNotFound(msg),
+            ErrorKind::SecretNotFound => FfiError::SecretNotFound(msg),
+            ErrorKind::Malformed => FfiError::Malformed(msg),
+            ErrorKind::Unsupported | ErrorKind::NoCompatibleCrypto => FfiError::Unsupported(msg),
+            ErrorKind::IllegalArgument => FfiError::IllegalArgument(msg),
+            _ => FfiError::DIDComm(msg),
+        }
+    }
+}
+
+impl From<ATMError> for FfiError {
+    fn from(err: ATMError) -> Self {
+        match err {
+            ATMError::ACLDenied(msg) => FfiError::ACLDenied(msg),
+            ATMError::AuthenticationError(msg) => FfiError::Authentication(msg),
+            ATMError::ProblemReport(code, comment, _) => FfiError::ProblemReport(code, comment),
+            err => FfiError::ATM(err.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for FfiError {
+    fn from(err: serde_json::Error) -> Self {
+        FfiError::Malformed(format!("Invalid JSON. Reason: {}", err))
+    }
+}
@@ -1,25 +1,20 @@
-mod common;
-mod did;
-mod didcomm;
-mod secrets;
+/*!
+ * UniFFI bindings for Affinidi DIDComm and the Affinidi Messaging SDK
+ *
+ * Exposes a thin, async interface that is generated into Kotlin and Swift:
+ * - [DIDComm] packs and unpacks DIDComm messages using a local DID resolver and secrets resolver
+ * - [ATMClient] manages profiles and sends, fetches and live streams messages via a mediator
+ *
+ * DIDComm messages cross the FFI boundary as JSON strings, so the foreign code doesn't need to
+ * track changes to the `Message` struct.
+ */
 
-pub use common::ErrorCode;
-pub use common::JsonValue;
-pub use did::resolvers::*;
-pub use did::*;
-pub use didcomm::*;
-pub use didcomm_core::algorithms::*;
-pub use didcomm_core::did::{
-    DIDCommMessagingService, DIDDoc, Service, ServiceKind, VerificationMaterial,
-    VerificationMethod, VerificationMethodType,
-};
-pub use didcomm_core::error::*;
-pub use didcomm_core::secrets::{Secret, SecretMaterial, SecretType};
-pub use didcomm_core::*;
-pub use secrets::resolvers::*;
-pub use secrets::*;
+mod atm;
+mod didcomm;
+mod errors;
 
-#[cfg(test)]
-mod test_helper;
+pub use atm::{ATMClient, Profile, ReceivedMessage};
+pub use didcomm::{DIDComm, EncryptOptions, Unpacked};
+pub use errors::FfiError;
 
-uniffi::include_scaffolding!("didcomm");
+uniffi::setup_scaffolding!();
@@ -1,6 +0,0 @@
-pub mod resolvers;
-
-pub(crate) mod secrets_resolver;
-pub(crate) mod secrets_resolver_adapter;
-
-pub use secrets_resolver::{OnFindSecretsResult, OnGetSecretResult, SecretsResolver};
@@ -1,3 +0,0 @@
-mod example;
-
-pub use example::ExampleSecretsResolver;
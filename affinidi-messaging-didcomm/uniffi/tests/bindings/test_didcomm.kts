@@ -0,0 +1,34 @@
+import uniffi.affinidi_messaging_uniffi.*
+import kotlinx.coroutines.runBlocking
+
+val did = "did:key:zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM"
+val kid = did + "#zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM"
+val jwk = """{"kty":"EC","crv":"P-256","x":"kgY1Us-Q4wnZ0eyhfZnA7rJe_hiOhhph5GIazpcpFvQ","y":"gEdgGqyfsxLyssrK6_nr6DK7xGmrjMm3MhGRLFlFmo0","d":"GclycCHT4Ze1b-mLa99yHmfBCXtqz63Fnx-x8ngJ-fY"}"""
+val message = """{"id":"1","typ":"application/didcomm-plain+json","type":"test","body":{"hello":"world"}}"""
+
+runBlocking {
+    val didcomm = DidComm()
+
+    // Plaintext round trip
+    val plaintext = didcomm.packPlaintext(message)
+    val unpackedPlaintext = didcomm.unpack(plaintext)
+    assert(!unpackedPlaintext.encrypted)
+    assert(unpackedPlaintext.message.contains("\"hello\":\"world\""))
+
+    // Malformed messages are reported as such
+    try {
+        didcomm.unpack("not a message")
+        throw RuntimeException("Should have thrown")
+    } catch (e: FfiException.Malformed) {
+        // Expected
+    }
+
+    // Anoncrypt round trip
+    didcomm.addSecret(kid, jwk)
+    assert(didcomm.findSecrets(listOf(kid)) == listOf(kid))
+    val packed = didcomm.packEncrypted(message, did, null, null, EncryptOptions(forward = false))
+    val unpacked = didcomm.unpack(packed)
+    assert(unpacked.encrypted)
+    assert(!unpacked.authenticated)
+    assert(unpacked.encryptedToKids == listOf(kid))
+}
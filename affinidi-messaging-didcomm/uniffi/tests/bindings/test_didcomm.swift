@@ -0,0 +1,40 @@
+import affinidi_messaging_uniffi
+import Foundation
+
+let did = "did:key:zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM"
+let kid = did + "#zDnaesVJRyFDUduPVYVe5ptaXNsngJnhgYy4rw6Vv2cbr98KM"
+let jwk = #"{"kty":"EC","crv":"P-256","x":"kgY1Us-Q4wnZ0eyhfZnA7rJe_hiOhhph5GIazpcpFvQ","y":"gEdgGqyfsxLyssrK6_nr6DK7xGmrjMm3MhGRLFlFmo0","d":"GclycCHT4Ze1b-mLa99yHmfBCXtqz63Fnx-x8ngJ-fY"}"#
+let message = #"{"id":"1","typ":"application/didcomm-plain+json","type":"test","body":{"hello":"world"}}"#
+
+let semaphore = DispatchSemaphore(value: 0)
+Task {
+    let didcomm = try! await DidComm()
+
+    // Plaintext round trip
+    let plaintext = try! await didcomm.packPlaintext(message: message)
+    let unpackedPlaintext = try! await didcomm.unpack(message: plaintext)
+    assert(!unpackedPlaintext.encrypted)
+    assert(unpackedPlaintext.message.contains(#""hello":"world""#))
+
+    // Malformed messages are reported as such
+    do {
+        _ = try await didcomm.unpack(message: "not a message")
+        fatalError("Should have thrown")
+    } catch FfiError.Malformed {
+        // Expected
+    }
+
+    // Anoncrypt round trip
+    try! await didcomm.addSecret(kid: kid, jwk: jwk)
+    let found = await didcomm.findSecrets(kids: [kid])
+    assert(found == [kid])
+    let packed = try! await didcomm.packEncrypted(
+        message: message, to: did, from: nil, signBy: nil, options: EncryptOptions(forward: false))
+    let unpacked = try! await didcomm.unpack(message: packed)
+    assert(unpacked.encrypted)
+    assert(!unpacked.authenticated)
+    assert(unpacked.encryptedToKids == [kid])
+
+    semaphore.signal()
+}
+semaphore.wait()
@@ -3,7 +3,10 @@ use affinidi_messaging_sdk::{
     ATM,
     config::ATMConfig,
     errors::ATMError,
-    messages::{DeleteMessageRequest, FetchDeletePolicy, Folder, fetch::FetchOptions},
+    messages::{
+        DeleteMessageRequest, FetchDeletePolicy, Folder, fetch::FetchOptions,
+        list::ListMessagesOptions,
+    },
     profiles::ATMProfile,
     protocols::Protocols,
 };
@@ -103,6 +106,7 @@ async fn main() -> Result<(), ATMError> {
                 limit: 100,
                 delete_policy: FetchDeletePolicy::Optimistic,
                 start_id: None,
+                recipient_did: None,
             },
         )
         .await?;
@@ -120,6 +124,7 @@ async fn main() -> Result<(), ATMError> {
                 limit: 100,
                 delete_policy: FetchDeletePolicy::Optimistic,
                 start_id: None,
+                recipient_did: None,
             },
         )
         .await?;
@@ -186,7 +191,9 @@ async fn main() -> Result<(), ATMError> {
     let new_msg_id = response.success.first().unwrap().msg_id.clone();
 
     // See if Bob has a message waiting
-    let response = atm.list_messages(&bob, Folder::Outbox).await?;
+    let response = atm
+        .list_messages(&bob, Folder::Outbox, &ListMessagesOptions::default())
+        .await?;
 
     println!(
         "Bob sent message msg_id({})",
@@ -229,8 +229,18 @@ pub(crate) async fn manage_account_menu(
                     .account_remove(atm, profile, Some(account.did_hash.clone()))
                     .await
                 {
-                    Ok(_) => {
+                    Ok(removed) => {
                         println!("{}", style("Account deleted successfully").green());
+                        println!(
+                            "  {} inbox messages, {} outbox messages, {} forward tasks, {} scheduled deliveries, {} OOB invites, {} sessions, {} streaming sessions removed",
+                            removed.inbox_messages,
+                            removed.outbox_messages,
+                            removed.forward_tasks,
+                            removed.scheduled_deliveries,
+                            removed.oob_invites,
+                            removed.sessions,
+                            removed.streaming_sessions
+                        );
                         return Ok(());
                     }
                     Err(err) => println!(
@@ -2,7 +2,10 @@
 use affinidi_messaging_sdk::{
     ATM,
     profiles::ATMProfile,
-    protocols::{Protocols, mediator::accounts::AccountType},
+    protocols::{
+        Protocols,
+        mediator::{accounts::AccountType, administration::AuditLogFilter},
+    },
 };
 use console::style;
 use dialoguer::{Confirm, Input, MultiSelect, Select, theme::ColorfulTheme};
@@ -23,6 +26,7 @@ pub(crate) async fn administration_accounts_menu(
         "List Administration DIDs",
         "Add Administration rights to a DID (will create if it doesn't exist)",
         "Strip Administration rights from a DID (DID will be set back to Standard)",
+        "View Audit Log",
         "Back",
     ];
 
@@ -40,7 +44,8 @@ pub(crate) async fn administration_accounts_menu(
             }
             1 => add_admin(atm, profile, protocols, theme).await,
             2 => strip_admins(atm, profile, protocols, shared_config, theme).await,
-            3 => {
+            3 => audit_log(atm, profile, protocols, theme).await,
+            4 => {
                 break;
             }
             _ => {
@@ -234,3 +239,78 @@ pub(crate) async fn strip_admins(
         }
     }
 }
+
+/// Pages through the audit log of administrative changes, newest first
+pub(crate) async fn audit_log(
+    atm: &ATM,
+    profile: &Arc<ATMProfile>,
+    protocols: &Protocols,
+    theme: &ColorfulTheme,
+) {
+    let target: String = Input::with_theme(theme)
+        .with_prompt("Only show changes to DID (SHA256 Hashed DID, blank for all)?")
+        .allow_empty(true)
+        .interact_text()
+        .unwrap();
+    let filter = AuditLogFilter {
+        target: if target.is_empty() {
+            None
+        } else {
+            Some(target)
+        },
+        ..Default::default()
+    };
+
+    let mut cursor = None;
+    loop {
+        let page = match protocols
+            .mediator
+            .audit_log(atm, profile, cursor, Some(20), Some(filter.clone()))
+            .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                println!("{}", style(format!("Error: {}", e)).red());
+                return;
+            }
+        };
+
+        if page.entries.is_empty() {
+            println!("{}", style("No audit log entries found").blue());
+        }
+        for entry in &page.entries {
+            println!(
+                "  {} {} {} {}",
+                style(entry.timestamp).blue(),
+                style(&entry.actor).yellow(),
+                style(entry.action).color256(208),
+                style(&entry.target).yellow(),
+            );
+            if entry.acls_before != entry.acls_after {
+                println!(
+                    "    {}",
+                    style(format!(
+                        "ACLs: {} -> {}",
+                        entry.acls_before.as_deref().unwrap_or("-"),
+                        entry.acls_after.as_deref().unwrap_or("-")
+                    ))
+                    .dim()
+                );
+            }
+            if let Some(detail) = &entry.detail {
+                println!("    {}", style(detail).dim());
+            }
+        }
+
+        cursor = page.cursor;
+        if cursor.is_none()
+            || !Confirm::with_theme(theme)
+                .with_prompt("Show older entries?")
+                .default(true)
+                .interact()
+                .unwrap()
+        {
+            break;
+        }
+    }
+}
@@ -1,22 +1,33 @@
+#[cfg(feature = "redis")]
 use crate::errors::MediatorError;
+#[cfg(feature = "redis")]
 use config::DatabaseConfig;
+#[cfg(feature = "redis")]
 use deadpool_redis::Connection;
+#[cfg(feature = "redis")]
 use redis::aio::PubSub;
+#[cfg(feature = "redis")]
 use semver::{Version, VersionReq};
+#[cfg(feature = "redis")]
 use std::{thread::sleep, time::Duration};
+#[cfg(feature = "redis")]
 use tracing::{Level, error, event, info};
 
 pub mod config;
+#[cfg(feature = "redis")]
 pub mod delete;
 
+#[cfg(feature = "redis")]
 #[derive(Clone)]
 pub struct DatabaseHandler {
     pub pool: deadpool_redis::Pool,
     redis_url: String,
 }
 
+#[cfg(feature = "redis")]
 const REDIS_VERSION_REQ: &str = ">=7.1, <8.0";
 
+#[cfg(feature = "redis")]
 impl DatabaseHandler {
     pub async fn new(config: &DatabaseConfig) -> Result<Self, MediatorError> {
         let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
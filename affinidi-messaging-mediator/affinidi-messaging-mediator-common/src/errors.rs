@@ -1,7 +1,10 @@
-use affinidi_messaging_sdk::messages::GenericDataStruct;
+use affinidi_messaging_sdk::messages::{
+    GenericDataStruct,
+    problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
+};
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use rand::{Rng, distr::Alphanumeric};
@@ -48,6 +51,8 @@ pub enum MediatorError {
     RequestDataError(SessId, String),
     #[error("Service Limit exceeded: {1}")]
     ServiceLimitError(SessId, String),
+    #[error("Rate limit exceeded: {1}, retry after {2} seconds")]
+    RateLimited(SessId, String, u64),
     #[error("Unauthorized: {1}")]
     Unauthorized(SessId, String),
     #[error("DID Error: did({1}) Error: {2}")]
@@ -90,8 +95,53 @@ impl From<ProcessorError> for MediatorError {
     }
 }
 
+impl MediatorError {
+    /// Converts the error into a DIDComm Problem Report for the sender of the message
+    /// Used when the error can't be returned as a HTTP response (e.g. over a WebSocket)
+    /// Codes are `e.m.<descriptor>`, where the descriptor matches the error variant
+    pub fn to_problem_report(&self) -> ProblemReport {
+        let descriptor = match self {
+            MediatorError::ErrorHandlingError(..) => "error-handling",
+            MediatorError::InternalError(..) => "internal",
+            MediatorError::ParseError(..) => "parse",
+            MediatorError::PermissionError(..) => "permission",
+            MediatorError::RequestDataError(..) => "request-data",
+            MediatorError::ServiceLimitError(..) => "service-limit",
+            MediatorError::RateLimited(..) => "rate-limited",
+            MediatorError::Unauthorized(..) => "unauthorized",
+            MediatorError::DIDError(..) => "did",
+            MediatorError::ConfigError(..) => "config",
+            MediatorError::DatabaseError(..) => "database",
+            MediatorError::MessageUnpackError(..) => "unpack",
+            MediatorError::MessageExpired(..) => "expired",
+            MediatorError::MessagePackError(..) => "pack",
+            MediatorError::NotImplemented(..) => "not-implemented",
+            MediatorError::SessionError(..) => "session",
+            MediatorError::AnonymousMessageError(..) => "anonymous",
+            MediatorError::ForwardMessageError(..) => "forward",
+            MediatorError::AuthenticationError(..) => "authentication",
+            MediatorError::ACLDenied(..) => "acl-denied",
+            MediatorError::ProcessorError(..) => "processor",
+        };
+
+        ProblemReport::new(
+            ProblemReportSorter::Error,
+            ProblemReportScope::Message,
+            descriptor.to_string(),
+            "{1}".to_string(),
+            vec![self.to_string()],
+            None,
+        )
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        // Rate limited clients are told when they can try again
+        let retry_after = match &self.0 {
+            MediatorError::RateLimited(_, _, retry_after) => Some(*retry_after),
+            _ => None,
+        };
         let response = match self.0 {
             MediatorError::ErrorHandlingError(session_id, msg) => {
                 let response = ErrorResponse {
@@ -159,6 +209,17 @@ impl IntoResponse for AppError {
                 event!(Level::WARN, "{}", response.to_string());
                 response
             }
+            MediatorError::RateLimited(session_id, msg, retry_after) => {
+                let response = ErrorResponse {
+                    httpCode: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+                    sessionId: session_id.to_string(),
+                    errorCode: 21,
+                    errorCodeStr: "TooManyRequests: RateLimited".to_string(),
+                    message: format!("{}, retry after {} seconds", msg, retry_after),
+                };
+                event!(Level::WARN, "{}", response.to_string());
+                response
+            }
             MediatorError::Unauthorized(session_id, msg) => {
                 let response = ErrorResponse {
                     httpCode: StatusCode::UNAUTHORIZED.as_u16(),
@@ -314,11 +375,16 @@ impl IntoResponse for AppError {
                 response
             }
         };
-        (
-            StatusCode::from_u16(response.httpCode).ok().unwrap(),
-            Json(response),
-        )
-            .into_response()
+        let status = StatusCode::from_u16(response.httpCode).ok().unwrap();
+        match retry_after {
+            Some(retry_after) => (
+                status,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                Json(response),
+            )
+                .into_response(),
+            None => (status, Json(response)).into_response(),
+        }
     }
 }
 
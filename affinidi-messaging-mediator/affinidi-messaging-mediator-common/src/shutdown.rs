@@ -0,0 +1,64 @@
+/*!
+ * Coordinated shutdown of the mediator and its processors
+ *
+ * The [ShutdownTrigger] is held by whatever handles the termination signal, each long running
+ * task holds a [Shutdown] and stops taking on new work once shutdown has been triggered.
+ */
+
+use tokio::sync::watch;
+
+/// Creates a linked trigger and shutdown listener
+pub fn channel() -> (ShutdownTrigger, Shutdown) {
+    let (tx, rx) = watch::channel(false);
+    (ShutdownTrigger(tx), Shutdown(rx))
+}
+
+/// Triggers the shutdown of all linked [Shutdown] listeners
+#[derive(Clone, Debug)]
+pub struct ShutdownTrigger(watch::Sender<bool>);
+
+impl ShutdownTrigger {
+    pub fn trigger(&self) {
+        self.0.send_replace(true);
+    }
+}
+
+/// Listens for shutdown, clone it for each task that needs to stop cleanly
+#[derive(Clone, Debug)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    /// Returns true if shutdown has been triggered
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until shutdown is triggered (or the trigger has been dropped)
+    pub async fn wait(&mut self) {
+        let _ = self.0.wait_for(|triggered| *triggered).await;
+    }
+}
+
+/// Waits for the process to receive SIGTERM or SIGINT (Ctrl-C)
+pub async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,29 @@
+// *****************************************************************
+// If running the processor separate, then we need some additional
+// configuration to run the processor
+// *****************************************************************
+
+use affinidi_messaging_mediator_common::database::config::DatabaseConfig;
+use affinidi_messaging_mediator_processors::forwarding::config::ForwardingConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Config {
+    pub database: DatabaseConfig,
+    pub mediator: MediatorConfig,
+    pub processors: ProcessorConfig,
+}
+
+/// Identity of the mediator this processor is working on behalf of
+/// Required to send problem reports back to the sender
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MediatorConfig {
+    pub mediator_did: String,
+    /// Path to a JSON file containing the mediator secrets
+    pub mediator_secrets_file: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcessorConfig {
+    pub forwarding: ForwardingConfig,
+}
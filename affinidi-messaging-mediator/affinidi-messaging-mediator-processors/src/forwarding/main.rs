@@ -0,0 +1,116 @@
+use affinidi_did_resolver_cache_sdk::{DIDCacheClient, config::DIDCacheConfigBuilder};
+use affinidi_messaging_mediator_common::{
+    database::DatabaseHandler, errors::ProcessorError, shutdown,
+};
+use affinidi_messaging_mediator_processors::forwarding::processor::ForwardingProcessor;
+use affinidi_secrets_resolver::{SecretsResolver, ThreadedSecretsResolver, secrets::Secret};
+use clap::Parser;
+use config::Config;
+use std::sync::Arc;
+use tokio::join;
+use tracing::{error, info};
+use tracing_subscriber::filter;
+
+mod config;
+
+/// Affinidi Messaging Processors
+/// Handles the forwarding of messages to remote mediators/agents
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "conf/forwarding.toml")]
+    config_file: String,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ProcessorError> {
+    let args = Args::parse();
+
+    // construct a subscriber that prints formatted traces to stdout
+    let subscriber = tracing_subscriber::fmt()
+        // Use a more compact, abbreviated log format
+        .with_env_filter(filter::EnvFilter::from_default_env())
+        .finish();
+    // use that subscriber to process traces emitted after this point
+    tracing::subscriber::set_global_default(subscriber).expect("Logging failed, exiting...");
+
+    let config = _read_config(&args.config_file)?;
+    info!("Configuration loaded successfully");
+
+    // Setting up the database durability and handling
+    info!("Connecting to database...");
+    let database = match DatabaseHandler::new(&config.database).await {
+        Ok(db) => db,
+        Err(err) => {
+            error!("Error opening database: {}", err);
+            error!("Exiting...");
+            return Err(ProcessorError::ForwardingError(format!(
+                "Error opening database. Reason: {}",
+                err
+            )));
+        }
+    };
+
+    let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+        .await
+        .map_err(|err| {
+            ProcessorError::ForwardingError(format!(
+                "Couldn't create DID resolver. Reason: {}",
+                err
+            ))
+        })?;
+
+    let secrets = _read_secrets(&config.mediator.mediator_secrets_file)?;
+    let (mediator_secrets, _) = ThreadedSecretsResolver::new(None).await;
+    mediator_secrets.insert_vec(&secrets).await;
+
+    let processor = ForwardingProcessor::new(
+        config.processors.forwarding,
+        database,
+        did_resolver,
+        &config.mediator.mediator_did,
+        Arc::new(mediator_secrets),
+    )?;
+
+    // Stop cleanly on SIGTERM/SIGINT
+    let (trigger, shutdown) = shutdown::channel();
+    let handle = {
+        tokio::spawn(async move {
+            processor
+                .start(shutdown)
+                .await
+                .expect("Error starting forwarding processor");
+        })
+    };
+
+    shutdown::signal().await;
+    info!("Shutdown signal received, finishing in-flight work...");
+    trigger.trigger();
+
+    let _ = join!(handle);
+
+    Ok(())
+}
+
+// Reads configuration file contents and converts it to a Config struct
+fn _read_config(file: &str) -> Result<Config, ProcessorError> {
+    let config = std::fs::read_to_string(file).expect("Couldn't read config file");
+    let config: Config = toml::from_str(&config).expect("Couldn't parse config file");
+    Ok(config)
+}
+
+// Reads the mediator secrets from a JSON file
+fn _read_secrets(file: &str) -> Result<Vec<Secret>, ProcessorError> {
+    let secrets = std::fs::read_to_string(file).map_err(|err| {
+        ProcessorError::ForwardingError(format!(
+            "Couldn't read secrets file ({}). Reason: {}",
+            file, err
+        ))
+    })?;
+    serde_json::from_str(&secrets).map_err(|err| {
+        ProcessorError::ForwardingError(format!(
+            "Couldn't parse secrets file ({}). Reason: {}",
+            file, err
+        ))
+    })
+}
@@ -0,0 +1,116 @@
+use affinidi_messaging_mediator_common::errors::ProcessorError;
+use ahash::AHashSet as HashSet;
+use serde::{Deserialize, Serialize};
+
+/// ForwardingConfig Struct contains configuration specific to DIDComm Routing/Forwarding
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForwardingConfig {
+    pub enabled: bool,
+    #[serde(default = "_default_future_time_limit")]
+    pub future_time_limit: u64,
+    #[serde(default = "_default_true")]
+    pub external_forwarding: bool,
+    #[serde(default = "_default_true")]
+    pub report_errors: bool,
+    /// DIDs and service URIs that must never be forwarded to (loopback protection)
+    #[serde(default)]
+    pub blocked_forwarding: HashSet<String>,
+    /// Maximum number of delivery attempts to a remote endpoint before giving up
+    #[serde(default = "_default_retry_limit")]
+    pub retry_limit: u32,
+    /// Initial backoff in milliseconds between delivery attempts, doubles on each retry
+    #[serde(default = "_default_retry_backoff")]
+    pub retry_backoff: u64,
+    /// Timeout in seconds for each HTTP(S) delivery attempt
+    #[serde(default = "_default_http_timeout")]
+    pub http_timeout: u64,
+    /// Maximum number of forward tasks to process concurrently
+    #[serde(default = "_default_batch_size")]
+    pub batch_size: usize,
+    /// Client certificate (PEM) presented to remote mediators that require mTLS
+    #[serde(default)]
+    pub tls_client_certificate_file: Option<String>,
+    /// Private key (PEM) for the client certificate
+    #[serde(default)]
+    pub tls_client_key_file: Option<String>,
+}
+
+impl Default for ForwardingConfig {
+    fn default() -> Self {
+        ForwardingConfig {
+            enabled: true,
+            future_time_limit: 86400,
+            external_forwarding: true,
+            report_errors: true,
+            blocked_forwarding: HashSet::new(),
+            retry_limit: 5,
+            retry_backoff: 1000,
+            http_timeout: 10,
+            batch_size: 10,
+            tls_client_certificate_file: None,
+            tls_client_key_file: None,
+        }
+    }
+}
+
+fn _default_true() -> bool {
+    true
+}
+
+fn _default_future_time_limit() -> u64 {
+    86400
+}
+
+fn _default_retry_limit() -> u32 {
+    5
+}
+
+fn _default_retry_backoff() -> u64 {
+    1000
+}
+
+fn _default_http_timeout() -> u64 {
+    10
+}
+
+fn _default_batch_size() -> usize {
+    10
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForwardingConfigRaw {
+    pub enabled: String,
+    pub future_time_limit: String,
+    pub external_forwarding: String,
+    pub report_errors: String,
+    pub blocked_forwarding_dids: String,
+    pub retry_limit: Option<String>,
+    pub retry_backoff: Option<String>,
+    pub http_timeout: Option<String>,
+    pub batch_size: Option<String>,
+    pub tls_client_certificate_file: Option<String>,
+    pub tls_client_key_file: Option<String>,
+}
+
+impl std::convert::TryFrom<ForwardingConfigRaw> for ForwardingConfig {
+    type Error = ProcessorError;
+
+    fn try_from(raw: ForwardingConfigRaw) -> Result<Self, Self::Error> {
+        Ok(ForwardingConfig {
+            enabled: raw.enabled.parse().unwrap_or(true),
+            future_time_limit: raw.future_time_limit.parse().unwrap_or(86400),
+            external_forwarding: raw.external_forwarding.parse().unwrap_or(true),
+            report_errors: raw.report_errors.parse().unwrap_or(true),
+            blocked_forwarding: HashSet::new(),
+            retry_limit: raw.retry_limit.and_then(|v| v.parse().ok()).unwrap_or(5),
+            retry_backoff: raw
+                .retry_backoff
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+            http_timeout: raw.http_timeout.and_then(|v| v.parse().ok()).unwrap_or(10),
+            batch_size: raw.batch_size.and_then(|v| v.parse().ok()).unwrap_or(10),
+            tls_client_certificate_file: raw.tls_client_certificate_file.filter(|v| !v.is_empty()),
+            tls_client_key_file: raw.tls_client_key_file.filter(|v| !v.is_empty()),
+        })
+    }
+}
@@ -0,0 +1,185 @@
+/*!
+ * Database operations against the `FORWARD_TASKS` stream
+ *
+ * Forward tasks are consumed using the Redis consumer group `FORWARD_PROCESSORS`, this allows
+ * multiple forwarding processors (local or standalone) to share the load.
+ *
+ * A task is only acknowledged and removed from the stream once it has been delivered, or has
+ * permanently failed. Tasks left pending by a processor that has died are re-claimed after
+ * `CLAIM_IDLE_MILLIS`.
+ */
+
+use crate::forwarding::{processor::ForwardingProcessor, task::ForwardTask};
+use affinidi_messaging_mediator_common::errors::ProcessorError;
+use redis::streams::{StreamAutoClaimReply, StreamId, StreamReadReply};
+use sha256::digest;
+use tracing::{debug, warn};
+
+const STREAM: &str = "FORWARD_TASKS";
+const GROUP: &str = "FORWARD_PROCESSORS";
+
+/// How long a task can be pending (in milliseconds) before another processor may claim it
+const CLAIM_IDLE_MILLIS: u64 = 600_000;
+
+impl ForwardingProcessor {
+    /// Creates the consumer group if it doesn't already exist
+    pub(crate) async fn ensure_consumer_group(&self) -> Result<(), ProcessorError> {
+        let mut conn = self.database.get_async_connection().await?;
+
+        let result: Result<(), redis::RedisError> = deadpool_redis::redis::cmd("XGROUP")
+            .arg("CREATE")
+            .arg(STREAM)
+            .arg(GROUP)
+            .arg("0")
+            .arg("MKSTREAM")
+            .exec_async(&mut conn)
+            .await;
+
+        match result {
+            Ok(_) => {
+                debug!("Created consumer group {} on {}", GROUP, STREAM);
+                Ok(())
+            }
+            Err(err) if err.code() == Some("BUSYGROUP") => Ok(()),
+            Err(err) => Err(ProcessorError::ForwardingError(format!(
+                "XGROUP CREATE {} {} failed. Reason: {}",
+                STREAM, GROUP, err
+            ))),
+        }
+    }
+
+    /// Reads new forward tasks from the stream, blocking for up to `block_millis`
+    pub(crate) async fn read_tasks(
+        &self,
+        block_millis: u64,
+    ) -> Result<Vec<ForwardTask>, ProcessorError> {
+        let mut conn = self.database.get_async_connection().await?;
+
+        let reply: Option<StreamReadReply> = deadpool_redis::redis::cmd("XREADGROUP")
+            .arg("GROUP")
+            .arg(GROUP)
+            .arg(&self.consumer_name)
+            .arg("COUNT")
+            .arg(self.config.batch_size)
+            .arg("BLOCK")
+            .arg(block_millis)
+            .arg("STREAMS")
+            .arg(STREAM)
+            .arg(">")
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                ProcessorError::ForwardingError(format!(
+                    "XREADGROUP {} failed. Reason: {}",
+                    STREAM, err
+                ))
+            })?;
+
+        let Some(reply) = reply else {
+            return Ok(Vec::new());
+        };
+
+        self.parse_entries(reply.keys.iter().flat_map(|k| k.ids.iter()))
+            .await
+    }
+
+    /// Claims tasks that have been pending with another consumer for too long
+    pub(crate) async fn claim_stale_tasks(&self) -> Result<Vec<ForwardTask>, ProcessorError> {
+        let mut conn = self.database.get_async_connection().await?;
+
+        let reply: StreamAutoClaimReply = deadpool_redis::redis::cmd("XAUTOCLAIM")
+            .arg(STREAM)
+            .arg(GROUP)
+            .arg(&self.consumer_name)
+            .arg(CLAIM_IDLE_MILLIS)
+            .arg("0-0")
+            .arg("COUNT")
+            .arg(self.config.batch_size)
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                ProcessorError::ForwardingError(format!(
+                    "XAUTOCLAIM {} failed. Reason: {}",
+                    STREAM, err
+                ))
+            })?;
+
+        self.parse_entries(reply.claimed.iter()).await
+    }
+
+    /// Acknowledges and removes a task from the stream
+    pub(crate) async fn complete_task(&self, stream_id: &str) -> Result<(), ProcessorError> {
+        let mut conn = self.database.get_async_connection().await?;
+
+        deadpool_redis::redis::pipe()
+            .atomic()
+            .cmd("XACK")
+            .arg(STREAM)
+            .arg(GROUP)
+            .arg(stream_id)
+            .cmd("XDEL")
+            .arg(STREAM)
+            .arg(stream_id)
+            .exec_async(&mut conn)
+            .await
+            .map_err(|err| {
+                ProcessorError::ForwardingError(format!(
+                    "Couldn't remove forward task ({}). Reason: {}",
+                    stream_id, err
+                ))
+            })
+    }
+
+    /// Stores a message generated by the forwarding processor (i.e. a problem report)
+    /// into the inbox of `to_did`
+    pub(crate) async fn store_message(
+        &self,
+        message: &str,
+        to_did: &str,
+        expires_at: u64,
+    ) -> Result<String, ProcessorError> {
+        let mut conn = self.database.get_async_connection().await?;
+
+        let message_hash = digest(message.as_bytes());
+        deadpool_redis::redis::cmd("FCALL")
+            .arg("store_message")
+            .arg(1)
+            .arg(&message_hash)
+            .arg(message)
+            .arg(expires_at)
+            .arg(message.len())
+            .arg(digest(to_did))
+            .arg(digest(self.mediator_did.as_str()))
+            // No queue quotas for messages generated by the mediator
+            .arg(-1)
+            .arg(-1)
+            .exec_async(&mut conn)
+            .await
+            .map_err(|err| {
+                ProcessorError::ForwardingError(format!(
+                    "Couldn't store message for ({}). Reason: {}",
+                    to_did, err
+                ))
+            })?;
+
+        Ok(message_hash)
+    }
+
+    /// Converts stream entries into tasks, invalid entries are removed from the stream
+    async fn parse_entries<'a>(
+        &self,
+        entries: impl Iterator<Item = &'a StreamId>,
+    ) -> Result<Vec<ForwardTask>, ProcessorError> {
+        let mut tasks = Vec::new();
+        for entry in entries {
+            match ForwardTask::try_from(entry) {
+                Ok(task) => tasks.push(task),
+                Err(err) => {
+                    warn!("Removing invalid forward task: {}", err);
+                    self.complete_task(&entry.id).await?;
+                }
+            }
+        }
+        Ok(tasks)
+    }
+}
@@ -0,0 +1,6 @@
+pub mod config;
+#[cfg(feature = "redis")]
+mod database;
+#[cfg(feature = "redis")]
+pub mod processor;
+pub mod task;
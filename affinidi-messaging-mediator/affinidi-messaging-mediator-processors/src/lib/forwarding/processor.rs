@@ -0,0 +1,637 @@
+/*!
+ * Main task that runs in a loop delivering forwarded messages to remote mediators/agents
+ *
+ * Messages are read from the `FORWARD_TASKS` stream, the DIDCommMessaging service of the
+ * `next` DID is resolved and the message is POSTed to the service endpoint.
+ * Failed deliveries are retried with exponential backoff, and if they ultimately fail a
+ * problem report is sent back to the sender (when `report_errors` is enabled).
+ */
+
+use super::{config::ForwardingConfig, task::ForwardTask};
+use affinidi_did_resolver_cache_sdk::DIDCacheClient;
+use affinidi_messaging_didcomm::{
+    Message, PackEncryptedOptions,
+    algorithms::AnonCryptAlg,
+    protocols::routing::{resolve_did_comm_services_chain, wrap_in_forward},
+};
+use affinidi_messaging_mediator_common::{
+    database::DatabaseHandler, errors::ProcessorError, shutdown::Shutdown,
+};
+use affinidi_messaging_sdk::messages::problem_report::{
+    ProblemReport, ProblemReportScope, ProblemReportSorter,
+};
+use affinidi_secrets_resolver::ThreadedSecretsResolver;
+use futures_util::future::join_all;
+use rustls::ClientConfig;
+use rustls_platform_verifier::{BuilderVerifierExt, ConfigVerifierExt};
+use serde_json::json;
+use std::{
+    fs::File,
+    io::BufReader,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Maximum number of keys per recipient when wrapping a message for routing keys
+const TO_KIDS_LIMIT: usize = 100;
+
+/// Expiry in seconds for problem reports where the original message has already expired
+const PROBLEM_REPORT_EXPIRY: u64 = 86_400;
+
+/// Result of a failed delivery attempt
+enum DeliveryError {
+    /// Worth trying again later (network errors, 5xx, 429)
+    Transient(String),
+    /// Retrying will not help (bad endpoint, 4xx, blocked destination)
+    Permanent(String),
+}
+
+/// ForwardingProcessor delivers messages from the `FORWARD_TASKS` stream to remote endpoints
+pub struct ForwardingProcessor {
+    /// Configuration for the ForwardingProcessor
+    pub(crate) config: ForwardingConfig,
+    /// Database handler for the Mediator
+    pub(crate) database: DatabaseHandler,
+    did_resolver: DIDCacheClient,
+    /// DID of the mediator, used as the sender of problem reports
+    pub(crate) mediator_did: String,
+    mediator_secrets: Arc<ThreadedSecretsResolver>,
+    http_client: reqwest::Client,
+    /// Unique name of this processor within the `FORWARD_PROCESSORS` consumer group
+    pub(crate) consumer_name: String,
+}
+
+/// TLS configuration for delivering to remote endpoints
+/// Presents a client certificate if one is configured (mTLS between mediators)
+fn _tls_config(config: &ForwardingConfig) -> Result<ClientConfig, ProcessorError> {
+    let (Some(cert_file), Some(key_file)) = (
+        &config.tls_client_certificate_file,
+        &config.tls_client_key_file,
+    ) else {
+        return Ok(ClientConfig::with_platform_verifier());
+    };
+
+    let _open = |file: &str| {
+        File::open(file).map(BufReader::new).map_err(|err| {
+            ProcessorError::ForwardingError(format!(
+                "Couldn't open TLS client file ({}). Reason: {}",
+                file, err
+            ))
+        })
+    };
+
+    let certs = rustls_pemfile::certs(&mut _open(cert_file)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| {
+            ProcessorError::ForwardingError(format!(
+                "Couldn't load TLS client certificate ({}). Reason: {}",
+                cert_file, err
+            ))
+        })?;
+    let key = rustls_pemfile::private_key(&mut _open(key_file)?)
+        .ok()
+        .flatten()
+        .ok_or_else(|| {
+            ProcessorError::ForwardingError(format!("Couldn't load TLS client key ({})", key_file))
+        })?;
+
+    ClientConfig::builder()
+        .with_platform_verifier()
+        .with_client_auth_cert(certs, key)
+        .map_err(|err| {
+            ProcessorError::ForwardingError(format!(
+                "Invalid TLS client certificate/key. Reason: {}",
+                err
+            ))
+        })
+}
+
+impl ForwardingProcessor {
+    pub fn new(
+        config: ForwardingConfig,
+        database: DatabaseHandler,
+        did_resolver: DIDCacheClient,
+        mediator_did: &str,
+        mediator_secrets: Arc<ThreadedSecretsResolver>,
+    ) -> Result<Self, ProcessorError> {
+        let http_client = reqwest::ClientBuilder::new()
+            .use_rustls_tls()
+            .use_preconfigured_tls(_tls_config(&config)?)
+            .timeout(Duration::from_secs(config.http_timeout))
+            .user_agent(format!(
+                "Affinidi Messaging Mediator {}",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .map_err(|err| {
+                ProcessorError::ForwardingError(format!(
+                    "Couldn't create HTTP client. Reason: {}",
+                    err
+                ))
+            })?;
+
+        Ok(ForwardingProcessor {
+            config,
+            database,
+            did_resolver,
+            mediator_did: mediator_did.to_string(),
+            mediator_secrets,
+            http_client,
+            consumer_name: Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Delivers forward tasks until shutdown is triggered
+    /// Tasks already read from the stream are delivered before returning, anything left pending
+    /// is claimed by another processor
+    pub async fn start(&self, shutdown: Shutdown) -> Result<(), ProcessorError> {
+        info!(
+            "Forwarding processor started. consumer({})",
+            self.consumer_name
+        );
+
+        self.ensure_consumer_group().await?;
+
+        while !shutdown.is_triggered() {
+            // Pick up any tasks abandoned by a processor that has gone away
+            match self.claim_stale_tasks().await {
+                Ok(tasks) if !tasks.is_empty() => {
+                    info!("Claimed {} stale forward tasks", tasks.len());
+                    self.process_tasks(&tasks).await;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    warn!("Error claiming stale forward tasks: {}", err);
+                }
+            }
+
+            let tasks = match self.read_tasks(1000).await {
+                Ok(tasks) => tasks,
+                Err(err) => {
+                    warn!("Error reading forward tasks: {}", err);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            if !tasks.is_empty() {
+                debug!("Processing {} forward tasks", tasks.len());
+                self.process_tasks(&tasks).await;
+            }
+        }
+
+        info!(
+            "Forwarding processor stopped. consumer({})",
+            self.consumer_name
+        );
+        Ok(())
+    }
+
+    async fn process_tasks(&self, tasks: &[ForwardTask]) {
+        join_all(tasks.iter().map(|task| self.process_task(task))).await;
+    }
+
+    /// Delivers a single task, reports any failure and then removes it from the stream
+    async fn process_task(&self, task: &ForwardTask) {
+        let now = _now();
+
+        if task.expires_at != 0 && task.expires_at <= now {
+            warn!(
+                "Forward task ({}) to ({}) expired before it could be delivered",
+                task.stream_id, task.to_did
+            );
+            self.report_problem(task, "message expired before it could be delivered")
+                .await;
+        } else if let Err(reason) = self.deliver(task).await {
+            warn!(
+                "Forward task ({}) to ({}) failed: {}",
+                task.stream_id, task.to_did, reason
+            );
+            self.report_problem(task, &reason).await;
+        } else {
+            info!(
+                "Forward task ({}) delivered to ({})",
+                task.stream_id, task.to_did
+            );
+        }
+
+        if let Err(err) = self.complete_task(&task.stream_id).await {
+            warn!("{}", err);
+        }
+    }
+
+    /// Delivers a task to the next hop, retrying transient errors with exponential backoff
+    async fn deliver(&self, task: &ForwardTask) -> Result<(), String> {
+        let (endpoint, message) = match self.prepare(task).await {
+            Ok(prepared) => prepared,
+            Err(DeliveryError::Permanent(reason) | DeliveryError::Transient(reason)) => {
+                return Err(reason);
+            }
+        };
+
+        _post_with_retry(
+            &self.http_client,
+            &self.config,
+            &task.stream_id,
+            &endpoint,
+            &message,
+        )
+        .await
+    }
+
+    /// Resolves the endpoint for the next hop and wraps the message for any routing keys
+    /// Returns (endpoint URI, message to send)
+    async fn prepare(&self, task: &ForwardTask) -> Result<(String, String), DeliveryError> {
+        // Strip any key ID from the DID URL
+        let to_did = task.to_did.split('#').next().unwrap_or(&task.to_did);
+        let services = resolve_did_comm_services_chain(to_did, None, &self.did_resolver)
+            .await
+            .map_err(|err| {
+                DeliveryError::Permanent(format!(
+                    "Couldn't resolve DIDCommMessaging service for ({}). Reason: {}",
+                    task.to_did, err
+                ))
+            })?;
+
+        let Some((_, first)) = services.first() else {
+            return Err(DeliveryError::Permanent(format!(
+                "DID ({}) has no DIDCommMessaging service",
+                task.to_did
+            )));
+        };
+        let endpoint = first.uri.clone();
+
+        if !(endpoint.starts_with("https://") || endpoint.starts_with("http://")) {
+            return Err(DeliveryError::Permanent(format!(
+                "Service endpoint ({}) is not a HTTP(S) endpoint",
+                endpoint
+            )));
+        }
+
+        if self.config.blocked_forwarding.contains(&endpoint) {
+            return Err(DeliveryError::Permanent(format!(
+                "Service endpoint ({}) is blocked from forwarding",
+                endpoint
+            )));
+        }
+
+        // Intermediate mediators and the routing keys of the final service each get a
+        // forward wrapper
+        let mut routing_keys: Vec<String> = services[1..]
+            .iter()
+            .map(|(_, service)| service.uri.clone())
+            .collect();
+        if let Some((_, last)) = services.last() {
+            routing_keys.extend(last.routing_keys.iter().cloned());
+        }
+
+        let message = if routing_keys.is_empty() {
+            task.message.clone()
+        } else {
+            wrap_in_forward(
+                &task.message,
+                None,
+                &task.to_did,
+                &routing_keys,
+                &AnonCryptAlg::default(),
+                &self.did_resolver,
+                TO_KIDS_LIMIT,
+            )
+            .await
+            .map_err(|err| {
+                DeliveryError::Permanent(format!(
+                    "Couldn't wrap message for routing keys. Reason: {}",
+                    err
+                ))
+            })?
+        };
+
+        Ok((endpoint, message))
+    }
+
+    /// Sends a problem report back to the sender of a forwarded message
+    /// Errors here are logged and otherwise ignored
+    async fn report_problem(&self, task: &ForwardTask, reason: &str) {
+        let now = _now();
+        let Some((from_did, pr_msg)) =
+            _problem_report(&self.config, &self.mediator_did, task, reason, now)
+        else {
+            return;
+        };
+
+        let packed = match pr_msg
+            .pack_encrypted(
+                &from_did,
+                Some(&self.mediator_did),
+                Some(&self.mediator_did),
+                &self.did_resolver,
+                &*self.mediator_secrets,
+                &PackEncryptedOptions {
+                    forward: false,
+                    to_kids_limit: TO_KIDS_LIMIT,
+                    ..PackEncryptedOptions::default()
+                },
+            )
+            .await
+        {
+            Ok((packed, _)) => packed,
+            Err(err) => {
+                warn!(
+                    "Couldn't pack problem report for ({}). Reason: {}",
+                    from_did, err
+                );
+                return;
+            }
+        };
+
+        let expires_at = if task.expires_at > now {
+            task.expires_at
+        } else {
+            now + PROBLEM_REPORT_EXPIRY
+        };
+
+        match self.store_message(&packed, &from_did, expires_at).await {
+            Ok(msg_id) => debug!("Problem report ({}) stored for ({})", msg_id, from_did),
+            Err(err) => warn!("{}", err),
+        }
+    }
+}
+
+/// POSTs a packed DIDComm message to a remote endpoint
+async fn _post(
+    client: &reqwest::Client,
+    endpoint: &str,
+    message: &str,
+) -> Result<(), DeliveryError> {
+    let response = client
+        .post(endpoint)
+        .header("Content-Type", "application/didcomm-encrypted+json")
+        .body(message.to_string())
+        .send()
+        .await
+        .map_err(|err| {
+            DeliveryError::Transient(format!("HTTP POST to ({}) failed: {}", endpoint, err))
+        })?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status.is_server_error() || status.as_u16() == 429 {
+        Err(DeliveryError::Transient(format!(
+            "({}) responded with status ({})",
+            endpoint, status
+        )))
+    } else {
+        Err(DeliveryError::Permanent(format!(
+            "({}) responded with status ({})",
+            endpoint, status
+        )))
+    }
+}
+
+/// POSTs to the endpoint, retrying transient errors with exponential backoff
+/// (`retry_backoff` doubling each attempt, up to `retry_limit` attempts)
+async fn _post_with_retry(
+    client: &reqwest::Client,
+    config: &ForwardingConfig,
+    stream_id: &str,
+    endpoint: &str,
+    message: &str,
+) -> Result<(), String> {
+    let mut backoff = config.retry_backoff;
+    let mut attempt = 1;
+    loop {
+        match _post(client, endpoint, message).await {
+            Ok(_) => return Ok(()),
+            Err(DeliveryError::Permanent(reason)) => return Err(reason),
+            Err(DeliveryError::Transient(reason)) => {
+                if attempt >= config.retry_limit {
+                    return Err(format!("gave up after {} attempts: {}", attempt, reason));
+                }
+                debug!(
+                    "Forward task ({}) attempt {} failed ({}), retrying in {}ms",
+                    stream_id, attempt, reason, backoff
+                );
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Builds the (unpacked) problem report for a failed task
+/// Returns the DID to send it to and the message, or None if no report should be sent
+fn _problem_report(
+    config: &ForwardingConfig,
+    mediator_did: &str,
+    task: &ForwardTask,
+    reason: &str,
+    now: u64,
+) -> Option<(String, Message)> {
+    if !config.report_errors {
+        return None;
+    }
+
+    let Some(from_did) = &task.from_did else {
+        debug!(
+            "Forward task ({}) has no sender, can't send a problem report",
+            task.stream_id
+        );
+        return None;
+    };
+
+    let problem_report = ProblemReport::new(
+        ProblemReportSorter::Error,
+        ProblemReportScope::Message,
+        "xfer.forward".to_string(),
+        "Couldn't forward message to ({1}). Reason: {2}".to_string(),
+        vec![task.to_did.clone(), reason.to_string()],
+        None,
+    );
+
+    let mut pr_msg = Message::build(
+        Uuid::new_v4().to_string(),
+        "https://didcomm.org/report-problem/2.0/problem-report".to_string(),
+        json!(problem_report),
+    )
+    .from(mediator_did.to_string())
+    .to(from_did.clone())
+    .created_time(now);
+    if let Some(msg_id) = &task.msg_id {
+        pr_msg = pr_msg.pthid(msg_id.clone());
+    }
+
+    Some((from_did.clone(), pr_msg.finalize()))
+}
+
+fn _now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    /// Local HTTP server standing in for a remote mediator
+    /// Answers each request with the next status in `statuses` (the last one repeats) and
+    /// returns the URL of the server and the (content-type, body) of the requests it receives
+    async fn stand_in(statuses: Vec<u16>) -> (String, mpsc::UnboundedReceiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/didcomm", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut request = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut stream = BufReader::new(stream);
+                let mut content_type = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if stream.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        match name.trim().to_lowercase().as_str() {
+                            "content-type" => content_type = value.trim().to_string(),
+                            "content-length" => length = value.trim().parse().unwrap_or(0),
+                            _ => {}
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                let _ = stream.read_exact(&mut body).await;
+
+                let status = statuses[request.min(statuses.len() - 1)];
+                request += 1;
+                let _ = stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                            status
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+                let _ = tx.send((content_type, String::from_utf8_lossy(&body).to_string()));
+            }
+        });
+
+        (url, rx)
+    }
+
+    fn _config() -> ForwardingConfig {
+        ForwardingConfig {
+            retry_limit: 3,
+            retry_backoff: 1,
+            report_errors: true,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn post_delivers_message() {
+        let (url, mut requests) = stand_in(vec![202]).await;
+
+        _post_with_retry(&reqwest::Client::new(), &_config(), "1-0", &url, "{}")
+            .await
+            .unwrap();
+
+        let (content_type, body) = requests.recv().await.unwrap();
+        assert_eq!(content_type, "application/didcomm-encrypted+json");
+        assert_eq!(body, "{}");
+    }
+
+    #[tokio::test]
+    async fn post_retries_transient_errors() {
+        let (url, mut requests) = stand_in(vec![503, 429, 200]).await;
+
+        _post_with_retry(&reqwest::Client::new(), &_config(), "1-0", &url, "{}")
+            .await
+            .unwrap();
+
+        for _ in 0..3 {
+            requests.recv().await.unwrap();
+        }
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn post_gives_up_after_retry_limit() {
+        let (url, mut requests) = stand_in(vec![500]).await;
+
+        let err = _post_with_retry(&reqwest::Client::new(), &_config(), "1-0", &url, "{}")
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("gave up after 3 attempts"));
+
+        for _ in 0..3 {
+            requests.recv().await.unwrap();
+        }
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn post_does_not_retry_permanent_errors() {
+        let (url, mut requests) = stand_in(vec![400, 200]).await;
+
+        assert!(
+            _post_with_retry(&reqwest::Client::new(), &_config(), "1-0", &url, "{}")
+                .await
+                .is_err()
+        );
+
+        requests.recv().await.unwrap();
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn problem_report_sent_to_sender() {
+        let task = ForwardTask::new(
+            "{}",
+            "did:example:bob",
+            Some("did:example:alice"),
+            Some("msg-1"),
+            42,
+        );
+
+        let (to, message) =
+            _problem_report(&_config(), "did:example:mediator", &task, "unreachable", 1).unwrap();
+        assert_eq!(to, "did:example:alice");
+        assert_eq!(message.to, Some(vec!["did:example:alice".to_string()]));
+        assert_eq!(message.from.as_deref(), Some("did:example:mediator"));
+        assert_eq!(message.pthid.as_deref(), Some("msg-1"));
+
+        let report: ProblemReport = serde_json::from_value(message.body).unwrap();
+        assert_eq!(report.code, "e.m.xfer.forward");
+        assert_eq!(
+            report.args,
+            vec!["did:example:bob".to_string(), "unreachable".to_string()]
+        );
+    }
+
+    #[test]
+    fn problem_report_not_sent() {
+        let task = ForwardTask::new("{}", "did:example:bob", Some("did:example:alice"), None, 42);
+        let config = ForwardingConfig {
+            report_errors: false,
+            .._config()
+        };
+        assert!(_problem_report(&config, "did:example:mediator", &task, "reason", 1).is_none());
+
+        // No sender to report to
+        let task = ForwardTask::new("{}", "did:example:bob", None, None, 42);
+        assert!(_problem_report(&_config(), "did:example:mediator", &task, "reason", 1).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+/*!
+ * A forward task is a DIDComm message that needs to be delivered to a remote endpoint
+ *
+ * Forward tasks are stored in the Redis Stream `FORWARD_TASKS`, each entry contains the fields:
+ * - `message`: The packed DIDComm message to deliver (already encrypted for the next hop)
+ * - `to_did`: The DID of the next hop (the `next` field of the routing message)
+ * - `from_did`: (optional) The DID that handed the message to the mediator, used for problem reports
+ * - `msg_id`: (optional) The ID of the forward message that created this task
+ * - `expires_at`: Epoch time in seconds after which the message should no longer be delivered
+ */
+
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_common::errors::ProcessorError;
+#[cfg(feature = "redis")]
+use redis::streams::StreamId;
+use sha256::digest;
+
+/// A single forwarding task read from the `FORWARD_TASKS` stream
+#[derive(Clone, Debug)]
+pub struct ForwardTask {
+    /// Redis stream ID of this task (empty until it has been added to the stream)
+    pub stream_id: String,
+    pub message: String,
+    pub to_did: String,
+    pub from_did: Option<String>,
+    pub msg_id: Option<String>,
+    pub expires_at: u64,
+}
+
+impl ForwardTask {
+    pub fn new(
+        message: &str,
+        to_did: &str,
+        from_did: Option<&str>,
+        msg_id: Option<&str>,
+        expires_at: u64,
+    ) -> Self {
+        ForwardTask {
+            stream_id: String::new(),
+            message: message.to_string(),
+            to_did: to_did.to_string(),
+            from_did: from_did.map(|s| s.to_string()),
+            msg_id: msg_id.map(|s| s.to_string()),
+            expires_at,
+        }
+    }
+
+    /// Is `did_hash` the next hop or the sender of this task?
+    pub fn involves(&self, did_hash: &str) -> bool {
+        digest(&self.to_did) == did_hash
+            || self
+                .from_did
+                .as_ref()
+                .is_some_and(|from_did| digest(from_did) == did_hash)
+    }
+
+    /// Converts the task into field/value pairs ready for XADD
+    pub fn to_stream_fields(&self) -> Vec<(&str, String)> {
+        let mut fields = vec![
+            ("message", self.message.clone()),
+            ("to_did", self.to_did.clone()),
+            ("expires_at", self.expires_at.to_string()),
+        ];
+        if let Some(from_did) = &self.from_did {
+            fields.push(("from_did", from_did.clone()));
+        }
+        if let Some(msg_id) = &self.msg_id {
+            fields.push(("msg_id", msg_id.clone()));
+        }
+        fields
+    }
+}
+
+#[cfg(feature = "redis")]
+impl TryFrom<&StreamId> for ForwardTask {
+    type Error = ProcessorError;
+
+    fn try_from(entry: &StreamId) -> Result<Self, Self::Error> {
+        let message: String = entry.get("message").ok_or_else(|| {
+            ProcessorError::ForwardingError(format!(
+                "Forward task ({}) is missing the message field",
+                entry.id
+            ))
+        })?;
+        let to_did: String = entry.get("to_did").ok_or_else(|| {
+            ProcessorError::ForwardingError(format!(
+                "Forward task ({}) is missing the to_did field",
+                entry.id
+            ))
+        })?;
+        let expires_at: u64 = entry.get("expires_at").unwrap_or(0);
+
+        Ok(ForwardTask {
+            stream_id: entry.id.clone(),
+            message,
+            to_did,
+            from_did: entry.get("from_did"),
+            msg_id: entry.get("msg_id"),
+            expires_at,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "redis"))]
+mod tests {
+    use super::*;
+    use redis::Value;
+
+    fn stream_entry(fields: &[(&str, &str)]) -> StreamId {
+        StreamId {
+            id: "1-0".into(),
+            map: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::BulkString(v.as_bytes().to_vec())))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn round_trip_stream_fields() {
+        let task = ForwardTask::new("{}", "did:example:bob", Some("did:example:alice"), None, 42);
+        let fields: Vec<(&str, String)> = task.to_stream_fields();
+        let pairs: Vec<(&str, &str)> = fields.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+        let parsed = ForwardTask::try_from(&stream_entry(&pairs)).unwrap();
+        assert_eq!(parsed.stream_id, "1-0");
+        assert_eq!(parsed.message, "{}");
+        assert_eq!(parsed.to_did, "did:example:bob");
+        assert_eq!(parsed.from_did.as_deref(), Some("did:example:alice"));
+        assert_eq!(parsed.msg_id, None);
+        assert_eq!(parsed.expires_at, 42);
+    }
+
+    #[test]
+    fn involves_sender_and_next_hop() {
+        let task = ForwardTask::new("{}", "did:example:bob", Some("did:example:alice"), None, 42);
+        assert!(task.involves(&digest("did:example:bob")));
+        assert!(task.involves(&digest("did:example:alice")));
+        assert!(!task.involves(&digest("did:example:carol")));
+    }
+
+    #[test]
+    fn missing_message_is_an_error() {
+        assert!(ForwardTask::try_from(&stream_entry(&[("to_did", "did:example:bob")])).is_err());
+    }
+}
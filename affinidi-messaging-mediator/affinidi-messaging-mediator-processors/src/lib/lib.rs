@@ -1 +1,2 @@
+pub mod forwarding;
 pub mod message_expiry_cleanup;
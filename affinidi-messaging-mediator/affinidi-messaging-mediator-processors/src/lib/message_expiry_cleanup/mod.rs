@@ -1,3 +1,5 @@
 pub mod config;
+#[cfg(feature = "redis")]
 mod database;
+#[cfg(feature = "redis")]
 pub mod processor;
@@ -2,7 +2,9 @@
  * Main task that runs in a loop checking for expired messages and removing them
  */
 
-use affinidi_messaging_mediator_common::{database::DatabaseHandler, errors::ProcessorError};
+use affinidi_messaging_mediator_common::{
+    database::DatabaseHandler, errors::ProcessorError, shutdown::Shutdown,
+};
 use std::time::Duration;
 use tracing::{debug, info, warn};
 
@@ -24,12 +26,16 @@ impl MessageExpiryCleanupProcessor {
         }
     }
 
-    pub async fn start(&self) -> Result<(), ProcessorError> {
+    /// Removes expired messages every second until shutdown is triggered
+    pub async fn start(&self, mut shutdown: Shutdown) -> Result<(), ProcessorError> {
         info!("Expired message cleanup processor started");
 
         loop {
             let sleep: tokio::time::Sleep = tokio::time::sleep(Duration::from_secs(1));
-            sleep.await;
+            tokio::select! {
+                _ = sleep => {}
+                _ = shutdown.wait() => break,
+            }
 
             let timeslots = match self.timeslot_scan().await {
                 Ok(timeslots) => timeslots,
@@ -62,6 +68,7 @@ impl MessageExpiryCleanupProcessor {
             }
         }
 
-        // Ok(())
+        info!("Expired message cleanup processor stopped");
+        Ok(())
     }
 }
@@ -1,4 +1,6 @@
-use affinidi_messaging_mediator_common::{database::DatabaseHandler, errors::ProcessorError};
+use affinidi_messaging_mediator_common::{
+    database::DatabaseHandler, errors::ProcessorError, shutdown,
+};
 use affinidi_messaging_mediator_processors::message_expiry_cleanup::processor::MessageExpiryCleanupProcessor;
 use clap::Parser;
 use config::Config;
@@ -49,15 +51,21 @@ async fn main() -> Result<(), ProcessorError> {
     let processor =
         MessageExpiryCleanupProcessor::new(config.processors.message_expiry_cleanup, database);
 
+    // Stop cleanly on SIGTERM/SIGINT
+    let (trigger, shutdown) = shutdown::channel();
     let handle = {
         tokio::spawn(async move {
             processor
-                .start()
+                .start(shutdown)
                 .await
                 .expect("Error starting message_expiry_cleanup processor");
         })
     };
 
+    shutdown::signal().await;
+    info!("Shutdown signal received, finishing in-flight work...");
+    trigger.trigger();
+
     let _ = join!(handle);
 
     Ok(())
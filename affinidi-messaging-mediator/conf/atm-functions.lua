@@ -1,17 +1,51 @@
 #!lua name=atm
 
+-- Would adding bytes to the queue of a DID exceed its quota?
+-- queue = SEND or RECEIVE
+-- The account's own quota (<queue>_QUEUE_BYTES_LIMIT) replaces default_quota, -1 means no limit
+local function queue_quota_exceeded(did_hash, queue, default_quota, bytes)
+    local r = redis.call('HMGET', 'DID:' .. did_hash, queue .. '_QUEUE_BYTES', queue .. '_QUEUE_BYTES_LIMIT')
+    local quota = tonumber(r[2]) or default_quota
+    if quota < 0 then
+        return false
+    end
+    return (tonumber(r[1]) or 0) + bytes > quota
+end
+
 -- store_message
 -- keys = message_hash
 -- args = [1] message
 --        [2] expiry epoch at in seconds resolution
 --        [3] message length in bytes
 --        [4] to_did_hash
---        [5] from_did_hash <optional>
+--        [5] from_did_hash (ANONYMOUS if the message is anonymous)
+--        [6] default send queue quota in bytes (-1: no limit)
+--        [7] default receive queue quota in bytes (-1: no limit)
+--        [8] signed_by <optional> key ID that signed the message (verified attachment JWS)
+-- Quotas are checked before anything is written, the message is rejected with a QUOTA_EXCEEDED error
 local function store_message(keys, args)
     -- Do we have the correct number of arguments?
-    -- from_did_hash can be optional!!! Means an anonymous message
-    if #args < 4 and #args > 5 then
-        return redis.error_reply('store_message: expected 4 or 5 arguments')
+    if #args < 7 or #args > 8 then
+        return redis.error_reply('store_message: expected 7 or 8 arguments')
+    end
+
+    local bytes = tonumber(args[3])
+    if bytes == nil then
+        return redis.error_reply('store_message: invalid bytes')
+    end
+    local send_quota = tonumber(args[6])
+    local receive_quota = tonumber(args[7])
+    if send_quota == nil or receive_quota == nil then
+        return redis.error_reply('store_message: invalid quota')
+    end
+
+    -- Check the queue quotas (RESP2, missing fields are false)
+    -- Anonymous senders share a single record, so only the receiver quota applies to them
+    if queue_quota_exceeded(args[4], 'RECEIVE', receive_quota, bytes) then
+        return redis.error_reply('QUOTA_EXCEEDED receive queue of (' .. args[4] .. ') is full')
+    end
+    if args[5] ~= 'ANONYMOUS' and queue_quota_exceeded(args[5], 'SEND', send_quota, bytes) then
+        return redis.error_reply('QUOTA_EXCEEDED send queue of (' .. args[5] .. ') is full')
     end
 
     -- set response type to Version 3
@@ -20,10 +54,6 @@ local function store_message(keys, args)
     -- Get current time on server
     local time = redis.call('TIME')
     local time = string.format("%d%03d", time[1], time[2] / 1000)
-    local bytes = tonumber(args[3])
-    if bytes == nil then
-        return redis.error_reply('store_message: invalid bytes')
-    end
 
     -- Store message
     redis.call('SET', 'MSG:' .. keys[1], args[1])
@@ -44,18 +74,15 @@ local function store_message(keys, args)
         args[5])
 
     -- Update the sender records
-    local SQ = nil
-    if table.getn(args) == 5 then
-        -- Update the sender records
-        redis.call('HINCRBY', 'DID:' .. args[5], 'SEND_QUEUE_BYTES', bytes)
-        redis.call('HINCRBY', 'DID:' .. args[5], 'SEND_QUEUE_COUNT', 1)
-        SQ = redis.call('XADD', 'SEND_Q:' .. args[5], time .. '-*', 'MSG_ID', keys[1], 'BYTES', bytes, 'TO', args[4])
-    end
+    redis.call('HINCRBY', 'DID:' .. args[5], 'SEND_QUEUE_BYTES', bytes)
+    redis.call('HINCRBY', 'DID:' .. args[5], 'SEND_QUEUE_COUNT', 1)
+    local SQ = redis.call('XADD', 'SEND_Q:' .. args[5], time .. '-*', 'MSG_ID', keys[1], 'BYTES', bytes, 'TO', args[4])
 
     -- Update message MetaData
     redis.call('HMSET', 'MSG:META:' .. keys[1], 'BYTES', bytes, 'TO', args[4], 'TIMESTAMP', time, 'RECEIVE_ID', RQ)
-    if SQ ~= nil then
-        redis.call('HMSET', 'MSG:META:' .. keys[1], 'FROM', args[5], 'SEND_ID', SQ)
+    redis.call('HMSET', 'MSG:META:' .. keys[1], 'FROM', args[5], 'SEND_ID', SQ)
+    if #args == 8 then
+        redis.call('HSET', 'MSG:META:' .. keys[1], 'SIGNED_BY', args[8])
     end
 
     return redis.status_reply('OK')
@@ -221,8 +248,8 @@ local function clean_start_streaming(keys, args)
         end
 
 
-        -- remove from global session list
-        redis.call('HDEL', 'GLOBAL_STREAMING', session)
+        -- remove this streaming service from the DID's streaming list
+        redis.call('HDEL', 'GLOBAL_STREAMING:' .. session, keys[1])
     end
 
     return counter
@@ -263,7 +290,7 @@ local function get_status_reply(keys, args)
     end
 
     -- Get live streaming status
-    local r = redis.call("HEXISTS", "GLOBAL_STREAMING", keys[1])
+    local r = redis.call("EXISTS", "GLOBAL_STREAMING:" .. keys[1])
     if r == 0 then
         response.map.live_delivery = false
     else
@@ -273,8 +300,75 @@ local function get_status_reply(keys, args)
     return response
 end
 
+-- rate_limit
+-- Token bucket rate limiter, shared by every mediator instance using this database
+-- keys = bucket identifier (e.g. inbound:<did_hash> or authenticate:<ip>)
+-- args = [1] limit in requests per minute
+--        [2] did_hash <optional> account whose RATE_LIMIT overrides the limit
+-- returns 0 if the request is allowed, otherwise milliseconds until a token is available
+local function rate_limit(keys, args)
+    -- Correct number of keys?
+    if #keys ~= 1 then
+        return redis.error_reply('rate_limit: only accepts one key (bucket)')
+    end
+
+    -- Correct number of args?
+    if #args < 1 or #args > 2 then
+        return redis.error_reply('rate_limit: expected 1 or 2 arguments')
+    end
+
+    local limit = tonumber(args[1])
+    if limit == nil then
+        return redis.error_reply('rate_limit: invalid limit')
+    end
+
+    -- Per account override, -1 means unlimited
+    if #args == 2 then
+        local override = tonumber(redis.call('HGET', 'DID:' .. args[2], 'RATE_LIMIT'))
+        if override == -1 then
+            return 0
+        elseif override ~= nil and override > 0 then
+            limit = override
+        end
+    end
+
+    if limit <= 0 then
+        return 0
+    end
+
+    -- Get current time on server in milliseconds
+    local time = redis.call('TIME')
+    local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+    -- Refill the bucket for the time elapsed since the last request
+    local bucket = 'RATE_LIMIT:' .. keys[1]
+    local r = redis.call('HMGET', bucket, 'TOKENS', 'UPDATED')
+    local tokens = tonumber(r[1])
+    local updated = tonumber(r[2])
+    if tokens == nil or updated == nil then
+        tokens = limit
+    else
+        tokens = math.min(limit, tokens + (math.max(0, now - updated) * limit / 60000))
+    end
+
+    local retry_after = 0
+    if tokens >= 1 then
+        tokens = tokens - 1
+    else
+        retry_after = math.ceil((1 - tokens) * 60000 / limit)
+        redis.call('HINCRBY', 'GLOBAL', 'RATE_LIMITED', 1)
+    end
+
+    -- A bucket that isn't touched for a minute is full again, so it can be dropped
+    redis.call('HSET', bucket, 'TOKENS', tostring(tokens), 'UPDATED', now)
+    redis.call('PEXPIRE', bucket, 60000)
+
+    return retry_after
+end
+
 redis.register_function('store_message', store_message)
 redis.register_function('delete_message', delete_message)
 redis.register_function('fetch_messages', fetch_messages)
 redis.register_function('clean_start_streaming', clean_start_streaming)
 redis.register_function('get_status_reply', get_status_reply)
+redis.register_function('rate_limit', rate_limit)
@@ -1,3 +1,4 @@
+use super::tls::{TlsConfig, parse_min_version};
 use affinidi_did_resolver_cache_sdk::{
     DIDCacheClient,
     config::{DIDCacheConfig, DIDCacheConfigBuilder},
@@ -6,12 +7,12 @@ use affinidi_messaging_mediator_common::{
     database::config::{DatabaseConfig, DatabaseConfigRaw},
     errors::MediatorError,
 };
-use affinidi_messaging_mediator_processors::message_expiry_cleanup::config::{
-    MessageExpiryCleanupConfig, MessageExpiryCleanupConfigRaw,
+use affinidi_messaging_mediator_processors::{
+    forwarding::config::{ForwardingConfig, ForwardingConfigRaw},
+    message_expiry_cleanup::config::{MessageExpiryCleanupConfig, MessageExpiryCleanupConfigRaw},
 };
 use affinidi_messaging_sdk::protocols::mediator::acls::{AccessListModeType, MediatorACLSet};
 use affinidi_secrets_resolver::{SecretsResolver, ThreadedSecretsResolver, secrets::Secret};
-use ahash::AHashSet as HashSet;
 use async_convert::{TryFrom, async_trait};
 use aws_config::{self, BehaviorVersion, Region, SdkConfig};
 use aws_sdk_secretsmanager;
@@ -44,6 +45,8 @@ pub struct ServerConfig {
     pub api_prefix: String,
     pub admin_did: String,
     pub did_web_self_hosted: Option<String>,
+    #[serde(default)]
+    pub shutdown_timeout: Option<String>,
 }
 
 /// SecurityConfig Struct contains security related configuration details
@@ -60,6 +63,16 @@ pub struct SecurityConfigRaw {
     pub jwt_access_expiry: String,
     pub jwt_refresh_expiry: String,
     pub cors_allow_origin: Option<String>,
+    #[serde(default)]
+    pub ssl_min_version: Option<String>,
+    #[serde(default)]
+    pub ssl_cipher_suites: Option<String>,
+    #[serde(default)]
+    pub ssl_client_auth: Option<String>,
+    #[serde(default)]
+    pub ssl_client_ca_file: Option<String>,
+    #[serde(default)]
+    pub ssl_reload_interval: Option<String>,
 }
 
 #[derive(Clone, Serialize)]
@@ -73,6 +86,7 @@ pub struct SecurityConfig {
     pub ssl_certificate_file: String,
     #[serde(skip_serializing)]
     pub ssl_key_file: String,
+    pub tls: TlsConfig,
     #[serde(skip_serializing)]
     pub jwt_encoding_key: EncodingKey,
     #[serde(skip_serializing)]
@@ -95,6 +109,7 @@ impl Debug for SecurityConfig {
             .field("use_ssl", &self.use_ssl)
             .field("ssl_certificate_file", &self.ssl_certificate_file)
             .field("ssl_key_file", &self.ssl_key_file)
+            .field("tls", &self.tls)
             .field("jwt_encoding_key?", &"<hidden>".to_string())
             .field("jwt_decoding_key?", &"<hidden>".to_string())
             .field("jwt_access_expiry", &self.jwt_access_expiry)
@@ -114,6 +129,7 @@ impl SecurityConfig {
             use_ssl: true,
             ssl_certificate_file: "".into(),
             ssl_key_file: "".into(),
+            tls: TlsConfig::default(),
             jwt_encoding_key: EncodingKey::from_ed_der(&[0; 32]),
             jwt_decoding_key: DecodingKey::from_ed_der(&[0; 32]),
             jwt_access_expiry: 900,
@@ -147,6 +163,33 @@ impl SecurityConfigRaw {
         Ok(origins)
     }
 
+    fn parse_tls(&self) -> Result<TlsConfig, MediatorError> {
+        let mut tls = TlsConfig::default();
+
+        if let Some(min_version) = &self.ssl_min_version {
+            tls.tls13_only = parse_min_version(min_version)?;
+        }
+        if let Some(cipher_suites) = &self.ssl_cipher_suites {
+            tls.cipher_suites = cipher_suites
+                .split(',')
+                .map(|suite| suite.trim().to_string())
+                .filter(|suite| !suite.is_empty())
+                .collect();
+        }
+        if let Some(client_auth) = &self.ssl_client_auth {
+            tls.client_auth = client_auth.parse()?;
+        }
+        tls.client_ca_file = self
+            .ssl_client_ca_file
+            .clone()
+            .filter(|file| !file.is_empty());
+        if let Some(reload_interval) = &self.ssl_reload_interval {
+            tls.reload_interval = reload_interval.parse().unwrap_or(30);
+        }
+
+        Ok(tls)
+    }
+
     async fn convert(&self, aws_config: &SdkConfig) -> Result<SecurityConfig, MediatorError> {
         let mut config = SecurityConfig {
             mediator_acl_mode: match self.mediator_acl_mode.as_str() {
@@ -161,6 +204,7 @@ impl SecurityConfigRaw {
             use_ssl: self.use_ssl.parse().unwrap_or(true),
             ssl_certificate_file: self.ssl_certificate_file.clone(),
             ssl_key_file: self.ssl_key_file.clone(),
+            tls: self.parse_tls()?,
             jwt_access_expiry: self.jwt_access_expiry.parse().unwrap_or(900),
             jwt_refresh_expiry: self.jwt_refresh_expiry.parse().unwrap_or(86_400),
             ..SecurityConfig::default().await
@@ -215,6 +259,121 @@ pub struct StreamingConfig {
     pub uuid: String,
 }
 
+/// MetricsConfig Struct contains the Prometheus metrics endpoint configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub enabled: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            enabled: "false".into(),
+        }
+    }
+}
+
+/// PushNotificationsConfig Struct contains the offline push notification configuration
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PushNotificationsConfig {
+    pub enabled: bool,
+    /// Milliseconds to collect messages for a DID before sending a single notification
+    pub batch_window: u64,
+    /// Timeout in seconds for each notification request
+    pub http_timeout: u64,
+    /// Allow webhooks on plain HTTP endpoints (testing only)
+    pub webhook_allow_http: bool,
+    /// Hosts (and their subdomains) webhooks may be registered on, any public host if empty
+    /// Listed hosts may resolve to private addresses, others must resolve to public addresses only
+    pub webhook_allowed_hosts: Vec<String>,
+    /// Firebase project that FCM notifications are sent through (requires the `push-fcm` feature)
+    pub fcm_project_id: Option<String>,
+    /// OAuth2 access token for the FCM HTTP v1 API
+    #[serde(skip_serializing)]
+    pub fcm_access_token: Option<String>,
+    /// Apple developer team ID (requires the `push-apns` feature)
+    pub apns_team_id: Option<String>,
+    /// ID of the APNs signing key
+    pub apns_key_id: Option<String>,
+    /// APNs signing key (PEM, .p8)
+    #[serde(skip_serializing)]
+    pub apns_key: Option<String>,
+    /// Bundle ID of the app receiving notifications
+    pub apns_topic: Option<String>,
+    /// Use the APNs sandbox (development) environment
+    pub apns_sandbox: bool,
+}
+
+impl Default for PushNotificationsConfig {
+    fn default() -> Self {
+        PushNotificationsConfig {
+            enabled: false,
+            batch_window: 5_000,
+            http_timeout: 10,
+            webhook_allow_http: false,
+            webhook_allowed_hosts: Vec::new(),
+            fcm_project_id: None,
+            fcm_access_token: None,
+            apns_team_id: None,
+            apns_key_id: None,
+            apns_key: None,
+            apns_topic: None,
+            apns_sandbox: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PushNotificationsConfigRaw {
+    #[serde(default)]
+    pub enabled: String,
+    #[serde(default)]
+    pub batch_window: String,
+    #[serde(default)]
+    pub http_timeout: String,
+    #[serde(default)]
+    pub webhook_allow_http: String,
+    #[serde(default)]
+    pub webhook_allowed_hosts: String,
+    pub fcm_project_id: Option<String>,
+    pub fcm_access_token: Option<String>,
+    pub apns_team_id: Option<String>,
+    pub apns_key_id: Option<String>,
+    pub apns_key_file: Option<String>,
+    pub apns_topic: Option<String>,
+    #[serde(default)]
+    pub apns_sandbox: String,
+}
+
+impl PushNotificationsConfigRaw {
+    fn convert(self) -> Result<PushNotificationsConfig, MediatorError> {
+        let apns_key = match self.apns_key_file.filter(|v| !v.is_empty()) {
+            Some(path) => Some(read_file_lines(&path)?.join("\n")),
+            None => None,
+        };
+
+        Ok(PushNotificationsConfig {
+            enabled: self.enabled.parse().unwrap_or(false),
+            batch_window: self.batch_window.parse().unwrap_or(5_000),
+            http_timeout: self.http_timeout.parse().unwrap_or(10),
+            webhook_allow_http: self.webhook_allow_http.parse().unwrap_or(false),
+            webhook_allowed_hosts: self
+                .webhook_allowed_hosts
+                .split(',')
+                .map(|host| host.trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+            fcm_project_id: self.fcm_project_id.filter(|v| !v.is_empty()),
+            fcm_access_token: self.fcm_access_token.filter(|v| !v.is_empty()),
+            apns_team_id: self.apns_team_id.filter(|v| !v.is_empty()),
+            apns_key_id: self.apns_key_id.filter(|v| !v.is_empty()),
+            apns_key,
+            apns_topic: self.apns_topic.filter(|v| !v.is_empty()),
+            apns_sandbox: self.apns_sandbox.parse().unwrap_or(false),
+        })
+    }
+}
+
 /// DIDResolverConfig Struct contains live streaming related configuration details
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DIDResolverConfig {
@@ -241,11 +400,20 @@ pub struct LimitsConfig {
     pub queued_send_messages_hard: i32,
     pub queued_receive_messages_soft: i32,
     pub queued_receive_messages_hard: i32,
+    pub queued_send_bytes_soft: i64,
+    pub queued_send_bytes_hard: i64,
+    pub queued_receive_bytes_soft: i64,
+    pub queued_receive_bytes_hard: i64,
     pub to_keys_per_recipient: usize,
     pub to_recipients: usize,
     pub ws_size: usize,
+    pub ws_connections_per_did: usize,
     pub access_list_limit: usize,
     pub oob_invite_ttl: usize,
+    pub rate_limit_inbound: u32,
+    pub rate_limit_authenticate: u32,
+    pub rate_limit_oob: u32,
+    pub audit_log_entries: usize,
 }
 
 impl Default for LimitsConfig {
@@ -264,11 +432,20 @@ impl Default for LimitsConfig {
             queued_send_messages_hard: 1_000,
             queued_receive_messages_soft: 200,
             queued_receive_messages_hard: 1_000,
+            queued_send_bytes_soft: 52_428_800,
+            queued_send_bytes_hard: 262_144_000,
+            queued_receive_bytes_soft: 52_428_800,
+            queued_receive_bytes_hard: 262_144_000,
             to_keys_per_recipient: 100,
             to_recipients: 100,
             ws_size: 10_485_760,
+            ws_connections_per_did: 10,
             access_list_limit: 1_000,
             oob_invite_ttl: 86_400,
+            rate_limit_inbound: 600,
+            rate_limit_authenticate: 30,
+            rate_limit_oob: 60,
+            audit_log_entries: 100_000,
         }
     }
 }
@@ -288,11 +465,29 @@ struct LimitsConfigRaw {
     pub queued_send_messages_hard: String,
     pub queued_receive_messages_soft: String,
     pub queued_receive_messages_hard: String,
+    #[serde(default)]
+    pub queued_send_bytes_soft: String,
+    #[serde(default)]
+    pub queued_send_bytes_hard: String,
+    #[serde(default)]
+    pub queued_receive_bytes_soft: String,
+    #[serde(default)]
+    pub queued_receive_bytes_hard: String,
     pub to_keys_per_recipient: String,
     pub to_recipients: String,
     pub ws_size: String,
+    #[serde(default)]
+    pub ws_connections_per_did: String,
     pub access_list_limit: String,
     pub oob_invite_ttl: String,
+    #[serde(default)]
+    pub rate_limit_inbound: String,
+    #[serde(default)]
+    pub rate_limit_authenticate: String,
+    #[serde(default)]
+    pub rate_limit_oob: String,
+    #[serde(default)]
+    pub audit_log_entries: String,
 }
 
 impl std::convert::TryFrom<LimitsConfigRaw> for LimitsConfig {
@@ -316,11 +511,20 @@ impl std::convert::TryFrom<LimitsConfigRaw> for LimitsConfig {
             queued_send_messages_hard: raw.queued_send_messages_hard.parse().unwrap_or(1_000),
             queued_receive_messages_soft: raw.queued_receive_messages_soft.parse().unwrap_or(100),
             queued_receive_messages_hard: raw.queued_receive_messages_hard.parse().unwrap_or(1_000),
+            queued_send_bytes_soft: raw.queued_send_bytes_soft.parse().unwrap_or(52_428_800),
+            queued_send_bytes_hard: raw.queued_send_bytes_hard.parse().unwrap_or(262_144_000),
+            queued_receive_bytes_soft: raw.queued_receive_bytes_soft.parse().unwrap_or(52_428_800),
+            queued_receive_bytes_hard: raw.queued_receive_bytes_hard.parse().unwrap_or(262_144_000),
             to_keys_per_recipient: raw.to_keys_per_recipient.parse().unwrap_or(100),
             to_recipients: raw.to_recipients.parse().unwrap_or(100),
             ws_size: raw.ws_size.parse().unwrap_or(10_485_760),
+            ws_connections_per_did: raw.ws_connections_per_did.parse().unwrap_or(10),
             access_list_limit: raw.access_list_limit.parse().unwrap_or(1_000),
             oob_invite_ttl: raw.oob_invite_ttl.parse().unwrap_or(86_400),
+            rate_limit_inbound: raw.rate_limit_inbound.parse().unwrap_or(600),
+            rate_limit_authenticate: raw.rate_limit_authenticate.parse().unwrap_or(30),
+            rate_limit_oob: raw.rate_limit_oob.parse().unwrap_or(60),
+            audit_log_entries: raw.audit_log_entries.parse().unwrap_or(100_000),
         })
     }
 }
@@ -338,51 +542,6 @@ struct ProcessorsConfigRaw {
     pub message_expiry_cleanup: MessageExpiryCleanupConfigRaw,
 }
 
-/// ForwardingConfig Struct contains configuration specific to DIDComm Routing/Forwarding
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ForwardingConfig {
-    pub enabled: bool,
-    pub future_time_limit: u64,
-    pub external_forwarding: bool,
-    pub report_errors: bool,
-    pub blocked_forwarding: HashSet<String>,
-}
-
-impl Default for ForwardingConfig {
-    fn default() -> Self {
-        ForwardingConfig {
-            enabled: true,
-            future_time_limit: 86400,
-            external_forwarding: true,
-            report_errors: true,
-            blocked_forwarding: HashSet::new(),
-        }
-    }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-struct ForwardingConfigRaw {
-    pub enabled: String,
-    pub future_time_limit: String,
-    pub external_forwarding: String,
-    pub report_errors: String,
-    pub blocked_forwarding_dids: String,
-}
-
-impl std::convert::TryFrom<ForwardingConfigRaw> for ForwardingConfig {
-    type Error = MediatorError;
-
-    fn try_from(raw: ForwardingConfigRaw) -> Result<Self, Self::Error> {
-        Ok(ForwardingConfig {
-            enabled: raw.enabled.parse().unwrap_or(true),
-            future_time_limit: raw.future_time_limit.parse().unwrap_or(86400),
-            external_forwarding: raw.external_forwarding.parse().unwrap_or(true),
-            report_errors: raw.report_errors.parse().unwrap_or(true),
-            blocked_forwarding: HashSet::new(),
-        })
-    }
-}
-
 impl DIDResolverConfig {
     pub fn convert(&self) -> DIDCacheConfig {
         let mut config = DIDCacheConfigBuilder::default()
@@ -410,6 +569,10 @@ struct ConfigRaw {
     pub database: DatabaseConfigRaw,
     pub security: SecurityConfigRaw,
     pub streaming: StreamingConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub push_notifications: PushNotificationsConfigRaw,
     pub did_resolver: DIDResolverConfig,
     pub limits: LimitsConfigRaw,
     pub processors: ProcessorsConfigRaw,
@@ -427,8 +590,12 @@ pub struct Config {
     pub mediator_did_doc: Option<Document>,
     pub admin_did: String,
     pub api_prefix: String,
+    /// Seconds to wait for connections and background tasks to finish on shutdown
+    pub shutdown_timeout: u64,
     pub streaming_enabled: bool,
     pub streaming_uuid: String,
+    pub metrics_enabled: bool,
+    pub push_notifications: PushNotificationsConfig,
     pub database: DatabaseConfig,
     pub security: SecurityConfig,
     #[serde(skip_serializing)]
@@ -450,8 +617,14 @@ impl fmt::Debug for Config {
             .field("database", &self.database)
             .field("streaming_enabled?", &self.streaming_enabled)
             .field("streaming_uuid", &self.streaming_uuid)
+            .field("metrics_enabled?", &self.metrics_enabled)
+            .field(
+                "push_notifications_enabled?",
+                &self.push_notifications.enabled,
+            )
             .field("DID Resolver config", &self.did_resolver_config)
             .field("api_prefix", &self.api_prefix)
+            .field("shutdown_timeout", &self.shutdown_timeout)
             .field("security", &self.security)
             .field("processors", &self.processors)
             .field("Limits", &self.limits)
@@ -460,7 +633,7 @@ impl fmt::Debug for Config {
 }
 
 impl Config {
-    async fn default() -> Self {
+    pub(crate) async fn default() -> Self {
         let did_resolver_config = DIDCacheConfigBuilder::default()
             .with_cache_capacity(1000)
             .with_cache_ttl(300)
@@ -479,8 +652,11 @@ impl Config {
             database: DatabaseConfig::default(),
             streaming_enabled: true,
             streaming_uuid: "".into(),
+            metrics_enabled: false,
+            push_notifications: PushNotificationsConfig::default(),
             did_resolver_config,
             api_prefix: "/mediator/v1/".into(),
+            shutdown_timeout: 30,
             security: SecurityConfig::default().await,
             processors: ProcessorsConfig {
                 forwarding: ForwardingConfig::default(),
@@ -521,8 +697,15 @@ impl TryFrom<ConfigRaw> for Config {
             admin_did: read_did_config(&raw.server.admin_did, &aws_config, "admin_did").await?,
             database: raw.database.try_into()?,
             streaming_enabled: raw.streaming.enabled.parse().unwrap_or(true),
+            metrics_enabled: raw.metrics.enabled.parse().unwrap_or(false),
+            push_notifications: raw.push_notifications.convert()?,
             did_resolver_config: raw.did_resolver.convert(),
             api_prefix: raw.server.api_prefix,
+            shutdown_timeout: raw
+                .server
+                .shutdown_timeout
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
             security: raw.security.convert(&aws_config).await?,
             processors: ProcessorsConfig {
                 forwarding: raw.processors.forwarding.clone().try_into()?,
@@ -991,6 +1174,14 @@ async fn load_forwarding_protection_blocks(
     Ok(())
 }
 
+/// Reads and processes the configuration file without setting up logging
+/// Useful when running several mediators in the same process (e.g. tests), [init] can only be called once
+pub async fn load(config_file: &str) -> Result<Config, MediatorError> {
+    let config = read_config_file(config_file)?;
+
+    <Config as async_convert::TryFrom<ConfigRaw>>::try_from(config).await
+}
+
 pub async fn init(config_file: &str, with_ansi: bool) -> Result<Config, MediatorError> {
     // Read configuration file parameters
     let config = read_config_file(config_file)?;
@@ -8,11 +8,8 @@ use axum::{
     extract::{FromRef, FromRequestParts},
     response::{IntoResponse, Response},
 };
-use axum_extra::{
-    TypedHeader,
-    headers::{Authorization, authorization::Bearer},
-};
-use http::{StatusCode, request::Parts};
+use axum_extra::headers::{Authorization, HeaderMapExt, authorization::Bearer};
+use http::{HeaderMap, StatusCode, header::SEC_WEBSOCKET_PROTOCOL, request::Parts};
 use jsonwebtoken::{TokenData, Validation};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -23,6 +20,13 @@ use std::{
 };
 use tracing::{Level, error, event, info, warn};
 
+/// WebSocket sub-protocol selected by the mediator when a client offers it
+pub(crate) const WEBSOCKET_PROTOCOL: &str = "atm";
+
+/// Browsers can't set an Authorization header on a WebSocket request, instead the access token is
+/// offered as a sub-protocol: `new WebSocket(url, ["atm", "atm.bearer.<access_token>"])`
+const WEBSOCKET_BEARER_PREFIX: &str = "atm.bearer.";
+
 // Payload contents of the JWT
 // All times are in seconds since UNIX EPOCH
 #[derive(Debug, Serialize, Deserialize)]
@@ -114,23 +118,15 @@ where
             }
         };
 
-        let mut validation = Validation::new(jsonwebtoken::Algorithm::EdDSA);
-        validation.set_audience(&["ATM"]);
-        validation.set_required_spec_claims(&["exp", "sub", "aud", "session_id"]);
-
-        let TypedHeader(Authorization(bearer)) = parts
-            .extract::<TypedHeader<Authorization<Bearer>>>()
-            .await
-            .map_err(|_| {
+        let token = match request_token(&parts.headers) {
+            Some(token) => token,
+            None => {
                 warn!("No Authorization Bearer header in request!");
-                AuthError::MissingCredentials
-            })?;
+                return Err(AuthError::MissingCredentials);
+            }
+        };
 
-        let token_data: TokenData<SessionClaims> = match jsonwebtoken::decode::<SessionClaims>(
-            bearer.token(),
-            &state.config.security.jwt_decoding_key,
-            &validation,
-        ) {
+        let token_data: TokenData<SessionClaims> = match decode_access_token(&state, &token) {
             Ok(token_data) => token_data,
             Err(err) => {
                 event!(Level::WARN, "Decoding JWT failed {:?}", err);
@@ -175,3 +171,40 @@ where
         Ok(saved_session)
     }
 }
+
+/// Returns the access token from the Authorization Bearer header, or the `Sec-WebSocket-Protocol`
+/// header for browser clients
+pub(crate) fn request_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .typed_get::<Authorization<Bearer>>()
+        .map(|bearer| bearer.token().to_string())
+        .or_else(|| websocket_protocol_token(headers))
+}
+
+/// Validates an access token issued by this mediator
+/// NOTE: This only checks the token itself, the session is not looked up in the database
+pub(crate) fn decode_access_token(
+    state: &SharedData,
+    token: &str,
+) -> jsonwebtoken::errors::Result<TokenData<SessionClaims>> {
+    let mut validation = Validation::new(jsonwebtoken::Algorithm::EdDSA);
+    validation.set_audience(&["ATM"]);
+    validation.set_required_spec_claims(&["exp", "sub", "aud", "session_id"]);
+
+    jsonwebtoken::decode::<SessionClaims>(
+        token,
+        &state.config.security.jwt_decoding_key,
+        &validation,
+    )
+}
+
+/// Returns the access token offered in the `Sec-WebSocket-Protocol` header (browser clients)
+fn websocket_protocol_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|protocol| protocol.trim().strip_prefix(WEBSOCKET_BEARER_PREFIX))
+        .map(|token| token.to_string())
+}
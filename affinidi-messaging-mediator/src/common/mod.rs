@@ -1,3 +1,5 @@
 pub mod acl_checks;
 pub mod config;
 pub mod jwt_auth;
+pub mod rate_limit;
+pub mod tls;
@@ -0,0 +1,156 @@
+/*!
+ * Rate limiting of the mediator API
+ *
+ * Requests are grouped into endpoint classes, each with its own limit in `[limits]`.
+ * Authenticated requests are limited per DID (and an account can have its own limit set by an
+ * admin), all other requests are limited per client IP address.
+ *
+ * The buckets are held in the database so the limits apply across all mediator instances.
+ */
+
+use super::{config::LimitsConfig, jwt_auth};
+use crate::SharedData;
+use affinidi_messaging_mediator_common::errors::{AppError, MediatorError};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sha256::digest;
+use std::net::SocketAddr;
+use tracing::{debug, warn};
+
+/// Groups of endpoints that share a rate limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RateLimitClass {
+    /// Messages sent to the mediator (`/inbound` and websocket messages)
+    Inbound,
+    /// Authentication requests (`/authenticate/*`)
+    Authenticate,
+    /// Out-of-band invitation requests (`/oob`)
+    Oob,
+}
+
+impl RateLimitClass {
+    /// Classifies a request path (relative to the api_prefix), None if the path isn't rate limited
+    fn from_path(path: &str) -> Option<Self> {
+        match path.trim_start_matches('/').split('/').next() {
+            Some("inbound") => Some(RateLimitClass::Inbound),
+            Some("authenticate") => Some(RateLimitClass::Authenticate),
+            Some("oob") => Some(RateLimitClass::Oob),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            RateLimitClass::Inbound => "inbound",
+            RateLimitClass::Authenticate => "authenticate",
+            RateLimitClass::Oob => "oob",
+        }
+    }
+
+    /// Configured requests per minute (0: unlimited)
+    fn per_minute(&self, limits: &LimitsConfig) -> u32 {
+        match self {
+            RateLimitClass::Inbound => limits.rate_limit_inbound,
+            RateLimitClass::Authenticate => limits.rate_limit_authenticate,
+            RateLimitClass::Oob => limits.rate_limit_oob,
+        }
+    }
+}
+
+/// Takes a token from the bucket for `class` and `client` (a DID hash or an IP address)
+/// - `did_hash` - Set when the client is authenticated, so the account's own limit applies
+///
+/// Returns a RateLimited error if the request must be rejected.
+/// If the database can't be reached the request is allowed, rate limiting must not take the mediator down.
+pub(crate) async fn check_rate_limit(
+    state: &SharedData,
+    session_id: &str,
+    class: RateLimitClass,
+    client: &str,
+    did_hash: Option<&str>,
+) -> Result<(), MediatorError> {
+    let per_minute = class.per_minute(&state.config.limits);
+    if per_minute == 0 && did_hash.is_none() {
+        return Ok(());
+    }
+
+    let key = [class.name(), ":", client].concat();
+    match state
+        .database
+        .rate_limit_take(&key, did_hash, per_minute)
+        .await
+    {
+        Ok(None) => Ok(()),
+        Ok(Some(retry_after)) => {
+            debug!("Rate limit ({}) exceeded, retry in {}ms", key, retry_after);
+            Err(MediatorError::RateLimited(
+                session_id.into(),
+                format!("Too many {} requests", class.name()),
+                retry_after.div_ceil(1000),
+            ))
+        }
+        Err(err) => {
+            warn!(
+                "Rate limit ({}) check failed, allowing request. Reason: {}",
+                key, err
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Middleware that rate limits requests by endpoint class
+pub async fn rate_limit(
+    State(state): State<SharedData>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request
+        .uri()
+        .path()
+        .strip_prefix(state.config.api_prefix.trim_end_matches('/'))
+        .unwrap_or_default();
+    let Some(class) = RateLimitClass::from_path(path) else {
+        return next.run(request).await;
+    };
+
+    // Authenticated clients are limited by DID, the session itself is checked by the handler
+    let did_hash = jwt_auth::request_token(request.headers())
+        .and_then(|token| jwt_auth::decode_access_token(&state, &token).ok())
+        .map(|token_data| digest(&token_data.claims.sub));
+
+    let result = match &did_hash {
+        Some(did_hash) => check_rate_limit(&state, "NA", class, did_hash, Some(did_hash)).await,
+        None => check_rate_limit(&state, "NA", class, &address.ip().to_string(), None).await,
+    };
+
+    match result {
+        Ok(_) => next.run(request).await,
+        Err(err) => AppError::from(err).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitClass;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(
+            RateLimitClass::from_path("/inbound"),
+            Some(RateLimitClass::Inbound)
+        );
+        assert_eq!(
+            RateLimitClass::from_path("/authenticate/challenge"),
+            Some(RateLimitClass::Authenticate)
+        );
+        assert_eq!(RateLimitClass::from_path("oob"), Some(RateLimitClass::Oob));
+        assert_eq!(RateLimitClass::from_path("/outbound"), None);
+        assert_eq!(RateLimitClass::from_path("/inbound_extra"), None);
+        assert_eq!(RateLimitClass::from_path(""), None);
+    }
+}
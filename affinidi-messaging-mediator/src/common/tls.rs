@@ -0,0 +1,292 @@
+/*!
+ * TLS configuration for the mediator
+ *
+ * Builds the rustls server configuration from the `[security]` settings:
+ * - Minimum TLS protocol version and the allowed cipher suites
+ * - Optional client certificate verification (mTLS), used by other mediators when forwarding
+ * - Certificate reload when the certificate, key or client CA files change on disk
+ */
+
+use super::config::SecurityConfig;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    RootCertStore, ServerConfig, SupportedProtocolVersion,
+    crypto::{CryptoProvider, aws_lc_rs},
+    pki_types::{CertificateDer, PrivateKeyDer},
+    server::WebPkiClientVerifier,
+    version::{TLS12, TLS13},
+};
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tracing::{debug, info, warn};
+
+/// Client certificate (mTLS) verification mode
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub enum ClientAuth {
+    /// Client certificates are not requested
+    #[default]
+    None,
+    /// Client certificates are verified if presented, clients without a certificate can connect
+    Optional,
+    /// All clients must present a certificate signed by the client CA
+    Required,
+}
+
+impl FromStr for ClientAuth {
+    type Err = MediatorError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "" | "none" => Ok(ClientAuth::None),
+            "optional" => Ok(ClientAuth::Optional),
+            "required" => Ok(ClientAuth::Required),
+            _ => Err(MediatorError::ConfigError(
+                "NA".into(),
+                format!(
+                    "Invalid ssl_client_auth ({}). Must be one of none, optional or required",
+                    value
+                ),
+            )),
+        }
+    }
+}
+
+/// TLS settings in addition to the certificate and key files
+#[derive(Clone, Debug, Serialize)]
+pub struct TlsConfig {
+    /// Only allow TLS 1.3 (otherwise TLS 1.2 and 1.3 are allowed)
+    pub tls13_only: bool,
+    /// Allowed cipher suites (rustls names), empty allows the provider defaults
+    pub cipher_suites: Vec<String>,
+    /// CA certificates used to verify client certificates
+    pub client_ca_file: Option<String>,
+    pub client_auth: ClientAuth,
+    /// How often (seconds) to check the certificate files for changes, 0 disables reloading
+    pub reload_interval: u64,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig {
+            tls13_only: false,
+            cipher_suites: Vec::new(),
+            client_ca_file: None,
+            client_auth: ClientAuth::None,
+            reload_interval: 30,
+        }
+    }
+}
+
+fn _tls_error(msg: String) -> MediatorError {
+    MediatorError::ConfigError("NA".into(), msg)
+}
+
+/// Parses the minimum TLS version (`1.2` or `1.3`), returns true if only TLS 1.3 is allowed
+pub(crate) fn parse_min_version(version: &str) -> Result<bool, MediatorError> {
+    match version {
+        "" | "1.2" => Ok(false),
+        "1.3" => Ok(true),
+        _ => Err(_tls_error(format!(
+            "Invalid ssl_min_version ({}). Must be 1.2 or 1.3",
+            version
+        ))),
+    }
+}
+
+/// Crypto provider restricted to the configured cipher suites
+fn _provider(cipher_suites: &[String]) -> Result<CryptoProvider, MediatorError> {
+    let mut provider = aws_lc_rs::default_provider();
+    if cipher_suites.is_empty() {
+        return Ok(provider);
+    }
+
+    for name in cipher_suites {
+        if !provider
+            .cipher_suites
+            .iter()
+            .any(|suite| format!("{:?}", suite.suite()) == *name)
+        {
+            return Err(_tls_error(format!("Unknown TLS cipher suite ({})", name)));
+        }
+    }
+    provider
+        .cipher_suites
+        .retain(|suite| cipher_suites.contains(&format!("{:?}", suite.suite())));
+
+    Ok(provider)
+}
+
+fn _open(file: &str) -> Result<BufReader<File>, MediatorError> {
+    Ok(BufReader::new(File::open(file).map_err(|err| {
+        _tls_error(format!("Couldn't open ({}). Reason: {}", file, err))
+    })?))
+}
+
+fn _load_certs(file: &str) -> Result<Vec<CertificateDer<'static>>, MediatorError> {
+    let certs = rustls_pemfile::certs(&mut _open(file)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| {
+            _tls_error(format!(
+                "Couldn't load certificates from ({}). Reason: {}",
+                file, err
+            ))
+        })?;
+
+    if certs.is_empty() {
+        return Err(_tls_error(format!("No certificates found in ({})", file)));
+    }
+    Ok(certs)
+}
+
+fn _load_key(file: &str) -> Result<PrivateKeyDer<'static>, MediatorError> {
+    rustls_pemfile::private_key(&mut _open(file)?)
+        .map_err(|err| {
+            _tls_error(format!(
+                "Couldn't load private key from ({}). Reason: {}",
+                file, err
+            ))
+        })?
+        .ok_or_else(|| _tls_error(format!("No private key found in ({})", file)))
+}
+
+/// Builds the rustls server configuration from the security settings
+pub fn server_config(security: &SecurityConfig) -> Result<ServerConfig, MediatorError> {
+    let tls = &security.tls;
+    let provider = Arc::new(_provider(&tls.cipher_suites)?);
+
+    let versions: &[&SupportedProtocolVersion] = if tls.tls13_only {
+        &[&TLS13]
+    } else {
+        &[&TLS12, &TLS13]
+    };
+
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(versions)
+        .map_err(|err| _tls_error(format!("Invalid TLS configuration. Reason: {}", err)))?;
+
+    let builder = match (&tls.client_auth, &tls.client_ca_file) {
+        (ClientAuth::None, _) => builder.with_no_client_auth(),
+        (_, None) => {
+            return Err(_tls_error(
+                "ssl_client_ca_file is required when ssl_client_auth is enabled".into(),
+            ));
+        }
+        (client_auth, Some(ca_file)) => {
+            let mut roots = RootCertStore::empty();
+            for cert in _load_certs(ca_file)? {
+                roots.add(cert).map_err(|err| {
+                    _tls_error(format!(
+                        "Invalid client CA certificate in ({}). Reason: {}",
+                        ca_file, err
+                    ))
+                })?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if *client_auth == ClientAuth::Optional {
+                verifier.allow_unauthenticated()
+            } else {
+                verifier
+            };
+            let verifier = verifier.build().map_err(|err| {
+                _tls_error(format!(
+                    "Couldn't create client certificate verifier. Reason: {}",
+                    err
+                ))
+            })?;
+            builder.with_client_cert_verifier(verifier)
+        }
+    };
+
+    let mut config = builder
+        .with_single_cert(
+            _load_certs(&security.ssl_certificate_file)?,
+            _load_key(&security.ssl_key_file)?,
+        )
+        .map_err(|err| _tls_error(format!("Invalid certificate/key. Reason: {}", err)))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+/// Modification times of the files used by the TLS configuration
+fn _modified(security: &SecurityConfig) -> Vec<Option<SystemTime>> {
+    [
+        Some(&security.ssl_certificate_file),
+        Some(&security.ssl_key_file),
+        security.tls.client_ca_file.as_ref(),
+    ]
+    .iter()
+    .flatten()
+    .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+    .collect()
+}
+
+/// Reloads the TLS configuration when the certificate, key or client CA files change
+/// Runs until the mediator exits, an invalid configuration is logged and the previous one is kept
+pub async fn reload_on_change(rustls_config: RustlsConfig, security: SecurityConfig) {
+    if security.tls.reload_interval == 0 {
+        return;
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(security.tls.reload_interval));
+    let mut last_modified = _modified(&security);
+
+    loop {
+        interval.tick().await;
+
+        let modified = _modified(&security);
+        if modified == last_modified {
+            continue;
+        }
+        debug!("TLS certificate files changed, reloading");
+
+        match server_config(&security) {
+            Ok(config) => {
+                rustls_config.reload_from_config(Arc::new(config));
+                last_modified = modified;
+                info!("TLS certificates reloaded");
+            }
+            // Files may be part way through being replaced, try again on the next tick
+            Err(err) => warn!("Couldn't reload TLS certificates. Reason: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_cipher_suites() {
+        let provider = _provider(&["TLS13_AES_256_GCM_SHA384".to_string()]).unwrap();
+        assert_eq!(provider.cipher_suites.len(), 1);
+
+        assert!(_provider(&["TLS_NOT_A_SUITE".to_string()]).is_err());
+        assert_eq!(
+            _provider(&[]).unwrap().cipher_suites.len(),
+            aws_lc_rs::default_provider().cipher_suites.len()
+        );
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        assert!(!parse_min_version("1.2").unwrap());
+        assert!(parse_min_version("1.3").unwrap());
+        assert!(parse_min_version("1.1").is_err());
+
+        assert_eq!("".parse::<ClientAuth>().unwrap(), ClientAuth::None);
+        assert_eq!(
+            "required".parse::<ClientAuth>().unwrap(),
+            ClientAuth::Required
+        );
+        assert!("sometimes".parse::<ClientAuth>().is_err());
+    }
+}
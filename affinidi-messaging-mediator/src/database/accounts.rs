@@ -4,14 +4,14 @@ use affinidi_messaging_mediator_common::errors::MediatorError;
 use affinidi_messaging_sdk::{
     messages::Folder,
     protocols::mediator::{
-        accounts::{Account, AccountType, MediatorAccountList},
+        accounts::{Account, AccountRemoveResponse, AccountType, MediatorAccountList},
         acls::MediatorACLSet,
     },
 };
 use ahash::AHashMap as HashMap;
 use redis::Pipeline;
 use tokio::join;
-use tracing::{Instrument, Level, debug, span};
+use tracing::{Instrument, Level, debug, info, span};
 
 // Private helper function to translate HashMap into an Account
 fn _to_account(map: HashMap<String, String>, access_list_count: u32) -> Account {
@@ -26,6 +26,9 @@ fn _to_account(map: HashMap<String, String>, access_list_count: u32) -> Account
             "ACLS" => account.acls = u64::from_str_radix(value, 16).unwrap_or(0_u64),
             "SEND_QUEUE_LIMIT" => account.queue_send_limit = value.parse().ok(),
             "RECEIVE_QUEUE_LIMIT" => account.queue_receive_limit = value.parse().ok(),
+            "SEND_QUEUE_BYTES_LIMIT" => account.queue_send_bytes_limit = value.parse().ok(),
+            "RECEIVE_QUEUE_BYTES_LIMIT" => account.queue_receive_bytes_limit = value.parse().ok(),
+            "RATE_LIMIT" => account.rate_limit = value.parse().ok(),
             "SEND_QUEUE_BYTES" => account.send_queue_bytes = value.parse().unwrap_or(0),
             "SEND_QUEUE_COUNT" => account.send_queue_count = value.parse().unwrap_or(0),
             "RECEIVE_QUEUE_BYTES" => account.receive_queue_bytes = value.parse().unwrap_or(0),
@@ -149,14 +152,16 @@ impl Database {
     /// - `did_hash` - SHA256 Hash of DID to remove
     /// - `remove_outbox` - This will remove messages that have not been delivered from this DID to others
     ///   NOTE: This should only be used as last resort. It is better to let the messages be delivered
-    /// - `remove_forwards` - This will remove messages that are queued to be delivered from this DID via forwarding
+    ///
+    /// Also removes pending forward tasks and scheduled deliveries to or from this DID, OOB invites
+    /// it created, its sessions and its streaming registration.
+    /// Returns a summary of what was removed
     pub(crate) async fn account_remove(
         &self,
         session: &Session,
         did_hash: &str,
         remove_outbox: bool,
-        remove_forwards: bool,
-    ) -> Result<bool, MediatorError> {
+    ) -> Result<AccountRemoveResponse, MediatorError> {
         let _span = span!(Level::DEBUG, "account_remove", "did_hash" = did_hash,);
 
         async move {
@@ -179,20 +184,33 @@ impl Database {
                 }
             }
 
+            let mut removed = AccountRemoveResponse {
+                did_hash: did_hash.to_string(),
+                ..Default::default()
+            };
+
             // Step 1 - block access to this account
             let mut blocked_acl = MediatorACLSet::from_u64(0);
             blocked_acl.set_blocked(true);
             self.set_did_acl(did_hash, &blocked_acl).await?;
 
-            // Step 2 - Remove forwarded messages as required
-            if remove_forwards {
-                // TODO: Implement a way to clear future forward tasks as needed
-            }
+            // Step 2 - Remove sessions and streaming so the DID can't pick up anything else
+            removed.sessions = self.sessions_remove_did(did_hash).await?;
+            removed.streaming_sessions = self.streaming_remove_did(did_hash).await?;
+
+            // Step 3 - Remove forward tasks and scheduled deliveries to or from this DID
+            removed.forward_tasks = self
+                .forward_queue_remove_did(&session.session_id, did_hash)
+                .await?;
+            removed.scheduled_deliveries = self
+                .scheduled_delivery_remove_did(&session.session_id, did_hash)
+                .await?;
 
-            // Step 3 - Remove messages from the outbox
+            // Step 4 - Remove messages from the outbox
             // This will remove any messages that are queued and still to be delivered to other DIDs
             if remove_outbox {
-                self.purge_messages(session, did_hash, Folder::Outbox)
+                (removed.outbox_messages, _) = self
+                    .purge_messages(session, did_hash, Folder::Outbox)
                     .await?;
             } else {
                 // Just remove the stream key, not the messages in other accounts
@@ -200,11 +218,15 @@ impl Database {
                     .await?;
             }
 
-            // Step 4 - Remove messages from the inbox
+            // Step 5 - Remove messages from the inbox
             // This will remove any messages that are queued and still to be delivered to this DID
-            self.purge_messages(session, did_hash, Folder::Inbox)
+            (removed.inbox_messages, _) = self
+                .purge_messages(session, did_hash, Folder::Inbox)
                 .await?;
 
+            // Step 6 - Remove OOB invites created by this DID
+            removed.oob_invites = self.oob_discovery_remove_did(did_hash).await?;
+
             // If DID is an admin account then remove from the admin list
             if let Some(current) = current {
                 if current._type.is_admin() {
@@ -216,6 +238,7 @@ impl Database {
             // Remove from Known DIDs
             // Remove DID Record
             // Remove ACCESS_LIST Set
+            // Remove MEDIATION_KEYLIST
             let mut con = self.0.get_async_connection().await?;
             deadpool_redis::redis::pipe()
                 .atomic()
@@ -226,6 +249,8 @@ impl Database {
                 .arg(["DID:", did_hash].concat())
                 .cmd("DEL")
                 .arg(["ACCESS_LIST:", did_hash].concat())
+                .cmd("DEL")
+                .arg(["MEDIATION_KEYLIST:", did_hash].concat())
                 .exec_async(&mut con)
                 .await
                 .map_err(|err| {
@@ -235,7 +260,8 @@ impl Database {
                     )
                 })?;
 
-            Ok(true)
+            info!("Account removed: {:?}", removed);
+            Ok(removed)
         }
         .instrument(_span)
         .await
@@ -389,8 +415,62 @@ impl Database {
         async move {
             debug!("Changing account queue_limits");
             let (send, receive) = join!(
-                self._change_queue_limit(did_hash, send_queue_limit, "SEND_QUEUE_LIMIT"),
-                self._change_queue_limit(did_hash, receive_queue_limit, "RECEIVE_QUEUE_LIMIT")
+                self._change_queue_limit(
+                    did_hash,
+                    send_queue_limit.map(i64::from),
+                    "SEND_QUEUE_LIMIT"
+                ),
+                self._change_queue_limit(
+                    did_hash,
+                    receive_queue_limit.map(i64::from),
+                    "RECEIVE_QUEUE_LIMIT"
+                )
+            );
+
+            send?;
+            receive?;
+
+            Ok(())
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Changes the queue quotas in bytes of an account
+    /// Assumes that all checks have been done prior to this call
+    /// - `did_hash` - SHA256 hash of the DID
+    /// - `send_queue_bytes_limit` - New send queue quota in bytes
+    /// - `receive_queue_bytes_limit` - New receive queue quota in bytes
+    ///
+    /// NOTE: Same values as the queue limits (None, -1, -2 or n)
+    ///       The quotas are enforced by the store_message function
+    pub(crate) async fn account_change_queue_bytes_limits(
+        &self,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<(), MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "account_change_queue_bytes_limits",
+            "did_hash" = did_hash,
+            "send_queue_bytes_limit" = send_queue_bytes_limit,
+            "receive_queue_bytes_limit" = receive_queue_bytes_limit
+        );
+
+        async move {
+            debug!("Changing account queue_bytes_limits");
+            let (send, receive) = join!(
+                self._change_queue_limit(
+                    did_hash,
+                    send_queue_bytes_limit,
+                    "SEND_QUEUE_BYTES_LIMIT"
+                ),
+                self._change_queue_limit(
+                    did_hash,
+                    receive_queue_bytes_limit,
+                    "RECEIVE_QUEUE_BYTES_LIMIT"
+                )
             );
 
             send?;
@@ -402,10 +482,40 @@ impl Database {
         .await
     }
 
+    /// Changes the rate limit of an account
+    /// Assumes that all checks have been done prior to this call
+    /// - `did_hash` - SHA256 hash of the DID
+    /// - `rate_limit` - New rate limit in requests per minute
+    ///
+    /// NOTE: rate_limit values:
+    ///       - -1: Unlimited
+    ///       - -2: Reset to the configured limits
+    ///       - n: set to n
+    pub(crate) async fn account_change_rate_limit(
+        &self,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<(), MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "account_change_rate_limit",
+            "did_hash" = did_hash,
+            "rate_limit" = rate_limit
+        );
+
+        async move {
+            debug!("Changing account rate_limit");
+            self._change_queue_limit(did_hash, Some(rate_limit.into()), "RATE_LIMIT")
+                .await
+        }
+        .instrument(_span)
+        .await
+    }
+
     async fn _change_queue_limit(
         &self,
         did_hash: &str,
-        queue_limit: Option<i32>,
+        queue_limit: Option<i64>,
         queue_name: &str,
     ) -> Result<(), MediatorError> {
         let mut con = self.0.get_async_connection().await?;
@@ -277,7 +277,7 @@ impl Database {
         &self,
         access_list_limit: usize,
         did_hash: &str,
-        hashes: &Vec<String>,
+        hashes: &[String],
     ) -> Result<MediatorAccessListAddResponse, MediatorError> {
         let _span = span!(
             Level::DEBUG,
@@ -331,7 +331,7 @@ impl Database {
     pub(crate) async fn access_list_remove(
         &self,
         did_hash: &str,
-        hashes: &Vec<String>,
+        hashes: &[String],
     ) -> Result<usize, MediatorError> {
         let _span = span!(
             Level::DEBUG,
@@ -396,7 +396,7 @@ impl Database {
     pub(crate) async fn access_list_get(
         &self,
         did_hash: &str,
-        hashes: &Vec<String>,
+        hashes: &[String],
     ) -> Result<MediatorAccessListGetResponse, MediatorError> {
         let _span = span!(
             Level::DEBUG,
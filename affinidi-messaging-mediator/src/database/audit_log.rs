@@ -0,0 +1,250 @@
+//! Append-only audit log of administrative changes
+//!
+//! Database structure (Redis):
+//! - `AUDIT_LOG`: Stream capped to `limits.audit_log_entries`, each entry holds the fields
+//!   ACTOR, ACTION, TARGET, TIMESTAMP and optionally ACLS_BEFORE, ACLS_AFTER and DETAIL
+//!
+//! The stream ID of an entry is its position in the log and is used as the cursor.
+
+use affinidi_messaging_sdk::protocols::mediator::administration::{
+    AuditAction, AuditLogEntry, AuditLogFilter, MediatorAuditLog,
+};
+use serde_json::Value;
+
+#[cfg(feature = "redis")]
+use super::Database;
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_common::errors::MediatorError;
+#[cfg(feature = "redis")]
+use itertools::Itertools;
+#[cfg(feature = "redis")]
+use tracing::{Level, event};
+
+/// Number of entries read from the stream at a time while filtering
+#[cfg(feature = "redis")]
+const AUDIT_LOG_BATCH: usize = 100;
+
+/// Name of the action as stored in the database (same as the protocol name)
+pub(crate) fn action_name(action: AuditAction) -> String {
+    match serde_json::to_value(action) {
+        Ok(Value::String(name)) => name,
+        _ => format!("{:?}", action),
+    }
+}
+
+/// Parses an action stored in the database
+pub(crate) fn parse_action(name: &str) -> Option<AuditAction> {
+    serde_json::from_value(Value::String(name.to_string())).ok()
+}
+
+/// Collects up to `limit` entries matching `filter` from `entries` (newest first, starting after
+/// the cursor). The cursor of the page is the ID of the last entry returned if the limit was reached
+pub(crate) fn audit_log_page(
+    entries: impl Iterator<Item = AuditLogEntry>,
+    limit: u32,
+    filter: &AuditLogFilter,
+) -> MediatorAuditLog {
+    let mut page = MediatorAuditLog {
+        entries: Vec::new(),
+        cursor: None,
+    };
+
+    for entry in entries.filter(|entry| filter.matches(entry)) {
+        page.entries.push(entry);
+        if page.entries.len() >= limit as usize {
+            page.cursor = page.entries.last().map(|entry| entry.id.clone());
+            break;
+        }
+    }
+
+    page
+}
+
+#[cfg(feature = "redis")]
+impl Database {
+    /// Appends an entry to the audit log
+    /// - `entry` - The entry to add, the ID is assigned by the database
+    /// - `max_entries` - Older entries are trimmed once the log exceeds this (approximate)
+    pub(crate) async fn audit_log_add(
+        &self,
+        entry: &AuditLogEntry,
+        max_entries: usize,
+    ) -> Result<(), MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        let mut cmd = deadpool_redis::redis::cmd("XADD");
+        cmd.arg("AUDIT_LOG")
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(max_entries)
+            .arg("*")
+            .arg("ACTOR")
+            .arg(&entry.actor)
+            .arg("ACTION")
+            .arg(action_name(entry.action))
+            .arg("TARGET")
+            .arg(&entry.target)
+            .arg("TIMESTAMP")
+            .arg(entry.timestamp);
+        if let Some(acls) = &entry.acls_before {
+            cmd.arg("ACLS_BEFORE").arg(acls);
+        }
+        if let Some(acls) = &entry.acls_after {
+            cmd.arg("ACLS_AFTER").arg(acls);
+        }
+        if let Some(detail) = &entry.detail {
+            cmd.arg("DETAIL").arg(detail);
+        }
+
+        cmd.exec_async(&mut conn).await.map_err(|err| {
+            event!(Level::ERROR, "Couldn't add to AUDIT_LOG. Reason: {}", err);
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!("Couldn't add to AUDIT_LOG. Reason: {}", err),
+            )
+        })
+    }
+
+    /// Reads the audit log, newest entries first
+    /// - `cursor` - Only entries older than this entry ID are returned
+    /// - `limit` - Maximum number of entries to return
+    /// - `filter` - Only entries matching the filter are returned
+    pub(crate) async fn audit_log_list(
+        &self,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &AuditLogFilter,
+    ) -> Result<MediatorAuditLog, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        fn _error<T: std::fmt::Display>(e: T) -> MediatorError {
+            event!(Level::ERROR, "Couldn't read AUDIT_LOG. Reason: {}", e);
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!("Couldn't read AUDIT_LOG. Reason: {}", e),
+            )
+        }
+
+        let mut end = match cursor {
+            Some(cursor) => ["(", cursor].concat(),
+            None => "+".to_string(),
+        };
+        let mut page = MediatorAuditLog {
+            entries: Vec::new(),
+            cursor: None,
+        };
+
+        loop {
+            let items: Vec<(String, Vec<String>)> = deadpool_redis::redis::cmd("XREVRANGE")
+                .arg("AUDIT_LOG")
+                .arg(&end)
+                .arg("-")
+                .arg("COUNT")
+                .arg(AUDIT_LOG_BATCH)
+                .query_async(&mut conn)
+                .await
+                .map_err(_error)?;
+            let batch_len = items.len();
+
+            let entries = items.into_iter().map(|(id, fields)| {
+                let mut entry = AuditLogEntry {
+                    id,
+                    timestamp: 0,
+                    actor: String::new(),
+                    action: AuditAction::AclSet,
+                    target: String::new(),
+                    acls_before: None,
+                    acls_after: None,
+                    detail: None,
+                };
+                for (k, v) in fields.into_iter().tuples() {
+                    match k.as_str() {
+                        "ACTOR" => entry.actor = v,
+                        "ACTION" => {
+                            if let Some(action) = parse_action(&v) {
+                                entry.action = action;
+                            }
+                        }
+                        "TARGET" => entry.target = v,
+                        "TIMESTAMP" => entry.timestamp = v.parse().unwrap_or(0),
+                        "ACLS_BEFORE" => entry.acls_before = Some(v),
+                        "ACLS_AFTER" => entry.acls_after = Some(v),
+                        "DETAIL" => entry.detail = Some(v),
+                        _ => {}
+                    }
+                }
+                entry
+            });
+
+            let mut last_id = None;
+            let batch = audit_log_page(
+                entries.inspect(|entry| last_id = Some(entry.id.clone())),
+                limit - page.entries.len() as u32,
+                filter,
+            );
+            page.entries.extend(batch.entries);
+            if batch.cursor.is_some() {
+                page.cursor = batch.cursor;
+                return Ok(page);
+            }
+
+            match last_id {
+                Some(last_id) if batch_len >= AUDIT_LOG_BATCH => {
+                    end = ["(", &last_id].concat();
+                }
+                _ => return Ok(page),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _entry(id: &str, action: AuditAction) -> AuditLogEntry {
+        AuditLogEntry {
+            id: id.into(),
+            timestamp: 0,
+            actor: "admin".into(),
+            action,
+            target: "target".into(),
+            acls_before: None,
+            acls_after: None,
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_action_name() {
+        assert_eq!(action_name(AuditAction::AclSet), "acl_set");
+        assert_eq!(
+            parse_action(&action_name(AuditAction::AccountChangeQueueLimits)),
+            Some(AuditAction::AccountChangeQueueLimits)
+        );
+        assert_eq!(parse_action("unknown"), None);
+    }
+
+    #[test]
+    fn test_audit_log_page() {
+        let entries = vec![
+            _entry("4", AuditAction::AclSet),
+            _entry("3", AuditAction::AccountAdd),
+            _entry("2", AuditAction::AclSet),
+            _entry("1", AuditAction::AclSet),
+        ];
+        let filter = AuditLogFilter {
+            action: Some(AuditAction::AclSet),
+            ..Default::default()
+        };
+
+        let page = audit_log_page(entries.clone().into_iter(), 2, &filter);
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[1].id, "2");
+        assert_eq!(page.cursor.as_deref(), Some("2"));
+
+        let page = audit_log_page(entries.into_iter().skip(3), 2, &filter);
+        assert_eq!(page.entries.len(), 1);
+        assert!(page.cursor.is_none());
+    }
+}
@@ -63,6 +63,7 @@ impl Database {
                         "META_BYTES" => message.size = v.parse().unwrap_or(0),
                         "META_TIMESTAMP" => message.timestamp = v.parse().unwrap_or(0),
                         "META_TO" => message.to_address = Some(v.clone()),
+                        "META_SIGNED_BY" => message.signed_by = Some(v.clone()),
                         "FROM_DID" => message.from_address = Some(v.clone()),
                         "MSG" => message.msg = Some(v.clone()),
                         _ => {}
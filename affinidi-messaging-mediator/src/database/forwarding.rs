@@ -0,0 +1,129 @@
+//! Handles queuing of messages that need to be forwarded to remote endpoints
+//! The `FORWARD_TASKS` stream is consumed by the forwarding processor
+
+use super::Database;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use redis::streams::StreamRangeReply;
+use tracing::{Instrument, Level, debug, span};
+
+/// Number of forward tasks read at a time when scanning the stream
+const SCAN_BATCH: usize = 100;
+
+/// Consumer group the forwarding processors read the `FORWARD_TASKS` stream with
+const PROCESSOR_GROUP: &str = "FORWARD_PROCESSORS";
+
+impl Database {
+    /// Adds a message to the FORWARD_TASKS stream for remote delivery
+    /// Returns the stream ID of the task
+    pub(crate) async fn forward_queue_enqueue(
+        &self,
+        session_id: &str,
+        task: &ForwardTask,
+    ) -> Result<String, MediatorError> {
+        let _span = span!(Level::DEBUG, "forward_queue_enqueue", to_did = task.to_did);
+        async move {
+            let mut conn = self.0.get_async_connection().await?;
+
+            let stream_id: String = deadpool_redis::redis::cmd("XADD")
+                .arg("FORWARD_TASKS")
+                .arg("*")
+                .arg(task.to_stream_fields())
+                .query_async(&mut conn)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        session_id.into(),
+                        format!("Couldn't add forward task. Reason: {}", err),
+                    )
+                })?;
+
+            debug!("Forward task ({}) queued", stream_id);
+            Ok(stream_id)
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Removes all pending forward tasks to or from a DID
+    /// Returns the number of tasks removed
+    pub(crate) async fn forward_queue_remove_did(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<usize, MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "forward_queue_remove_did",
+            did_hash = did_hash
+        );
+        async move {
+            let mut conn = self.0.get_async_connection().await?;
+            let mut start = "-".to_string();
+            let mut removed = 0;
+
+            loop {
+                let reply: StreamRangeReply = deadpool_redis::redis::cmd("XRANGE")
+                    .arg("FORWARD_TASKS")
+                    .arg(&start)
+                    .arg("+")
+                    .arg("COUNT")
+                    .arg(SCAN_BATCH)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|err| {
+                        MediatorError::DatabaseError(
+                            session_id.into(),
+                            format!("Couldn't read forward tasks. Reason: {}", err),
+                        )
+                    })?;
+
+                let matched: Vec<&str> = reply
+                    .ids
+                    .iter()
+                    .filter(|entry| {
+                        ForwardTask::try_from(*entry).is_ok_and(|task| task.involves(did_hash))
+                    })
+                    .map(|entry| entry.id.as_str())
+                    .collect();
+
+                if !matched.is_empty() {
+                    // Acknowledged and deleted the same as a completed task, so nothing is left
+                    // pending for the processors (XACK is a no-op if no processor has read the stream)
+                    let (deleted,): (usize,) = deadpool_redis::redis::pipe()
+                        .atomic()
+                        .cmd("XACK")
+                        .arg("FORWARD_TASKS")
+                        .arg(PROCESSOR_GROUP)
+                        .arg(&matched)
+                        .ignore()
+                        .cmd("XDEL")
+                        .arg("FORWARD_TASKS")
+                        .arg(&matched)
+                        .query_async(&mut conn)
+                        .await
+                        .map_err(|err| {
+                            MediatorError::DatabaseError(
+                                session_id.into(),
+                                format!("Couldn't remove forward tasks. Reason: {}", err),
+                            )
+                        })?;
+                    removed += deleted;
+                }
+
+                match reply.ids.last() {
+                    Some(last) if reply.ids.len() == SCAN_BATCH => {
+                        // Exclusive range, continue after the last entry read
+                        start = ["(", &last.id].concat();
+                    }
+                    _ => break,
+                }
+            }
+
+            debug!("Removed ({}) forward tasks", removed);
+            Ok(removed)
+        }
+        .instrument(_span)
+        .await
+    }
+}
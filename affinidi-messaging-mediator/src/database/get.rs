@@ -71,6 +71,7 @@ impl Database {
                     "TIMESTAMP" => message.timestamp = v.parse().unwrap_or(0),
                     "SEND_ID" => message.send_id = Some(v.clone()),
                     "RECEIVE_ID" => message.receive_id = Some(v.clone()),
+                    "SIGNED_BY" => message.signed_by = Some(v.clone()),
                     _ => {}
                 }
             }
@@ -0,0 +1,127 @@
+/*!
+ Database operations for Coordinate Mediation 2.0 keylists
+
+ SORTED SET KEY : MEDIATION_KEYLIST:<did_hash>
+   Recipient DIDs registered by a DID, all with score 0 so they are ordered lexically for paging
+*/
+
+use super::Database;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use tracing::{Instrument, Level, debug, span};
+
+impl Database {
+    /// Adds a recipient DID to the keylist of `did_hash`
+    /// Returns false if the recipient DID was already in the keylist
+    pub(crate) async fn keylist_add(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        let _span = span!(Level::DEBUG, "keylist_add", did_hash = did_hash);
+
+        async move {
+            debug!("Adding recipient DID ({}) to keylist", recipient_did);
+
+            let mut con = self.0.get_async_connection().await?;
+            let added: u32 = deadpool_redis::redis::cmd("ZADD")
+                .arg(["MEDIATION_KEYLIST:", did_hash].concat())
+                .arg("NX")
+                .arg(0)
+                .arg(recipient_did)
+                .query_async(&mut con)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        "NA".to_string(),
+                        format!("keylist_add failed. Reason: {}", err),
+                    )
+                })?;
+
+            Ok(added > 0)
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Removes a recipient DID from the keylist of `did_hash`
+    /// Returns false if the recipient DID wasn't in the keylist
+    pub(crate) async fn keylist_remove(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        let _span = span!(Level::DEBUG, "keylist_remove", did_hash = did_hash);
+
+        async move {
+            debug!("Removing recipient DID ({}) from keylist", recipient_did);
+
+            let mut con = self.0.get_async_connection().await?;
+            let removed: u32 = deadpool_redis::redis::cmd("ZREM")
+                .arg(["MEDIATION_KEYLIST:", did_hash].concat())
+                .arg(recipient_did)
+                .query_async(&mut con)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        "NA".to_string(),
+                        format!("keylist_remove failed. Reason: {}", err),
+                    )
+                })?;
+
+            Ok(removed > 0)
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Retrieves up to `limit` recipient DIDs from the keylist of `did_hash` starting at `offset`
+    /// Returns the recipient DIDs and the total number of DIDs in the keylist
+    pub(crate) async fn keylist_list(
+        &self,
+        did_hash: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), MediatorError> {
+        let _span = span!(Level::DEBUG, "keylist_list", did_hash = did_hash);
+
+        async move {
+            let key = ["MEDIATION_KEYLIST:", did_hash].concat();
+            let mut con = self.0.get_async_connection().await?;
+
+            let (dids, total): (Vec<String>, usize) = if limit == 0 {
+                let total: usize = deadpool_redis::redis::cmd("ZCARD")
+                    .arg(&key)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|err| {
+                        MediatorError::DatabaseError(
+                            "NA".to_string(),
+                            format!("keylist_list failed. Reason: {}", err),
+                        )
+                    })?;
+                (Vec::new(), total)
+            } else {
+                deadpool_redis::redis::pipe()
+                    .cmd("ZRANGE")
+                    .arg(&key)
+                    .arg(offset)
+                    .arg(offset + limit - 1)
+                    .cmd("ZCARD")
+                    .arg(&key)
+                    .query_async(&mut con)
+                    .await
+                    .map_err(|err| {
+                        MediatorError::DatabaseError(
+                            "NA".to_string(),
+                            format!("keylist_list failed. Reason: {}", err),
+                        )
+                    })?
+            };
+
+            debug!("Retrieved {} of {} keylist DIDs", dids.len(), total);
+            Ok((dids, total))
+        }
+        .instrument(_span)
+        .await
+    }
+}
@@ -0,0 +1,696 @@
+/*!
+ * Storage backend abstraction for the mediator
+ *
+ * All persistent state of the mediator (messages, accounts, ACLs, access lists, sessions,
+ * OOB invitations, streaming state and statistics) is accessed through [MediatorStore].
+ *
+ * Backends are enabled with cargo features and selected by the `database_url` scheme:
+ * - `redis://` or `rediss://` : Redis backend (feature `redis`, enabled by default)
+ * - `sqlite://<path>` : Embedded single-node SQLite backend (feature `sqlite`)
+ * - `memory://` : In-memory backend for tests, always available
+ */
+
+use super::{
+    scheduled_delivery::ScheduledDelivery, session::Session, stats::MetadataStats,
+    store::QueueByteQuota,
+};
+use crate::{common::config::Config, tasks::websocket_streaming::PubSubRecord};
+use affinidi_messaging_didcomm::Message;
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_common::database::DatabaseHandler;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use affinidi_messaging_sdk::{
+    messages::{
+        Folder, GetMessagesResponse, MessageList, MessageListElement, fetch::FetchOptions,
+        list::ListMessagesOptions,
+    },
+    protocols::{
+        mediator::{
+            accounts::{Account, AccountRemoveResponse, AccountType, MediatorAccountList},
+            acls::{AccessListModeType, MediatorACLSet},
+            acls_handler::{
+                MediatorACLGetResponse, MediatorAccessListAddResponse,
+                MediatorAccessListGetResponse, MediatorAccessListListResponse,
+            },
+            administration::{AuditLogEntry, AuditLogFilter, MediatorAdminList, MediatorAuditLog},
+            push_notifications::PushTarget,
+        },
+        message_pickup::MessagePickupStatusReply,
+    },
+};
+use async_trait::async_trait;
+use std::pin::Pin;
+use tokio_stream::Stream;
+
+/// How many stream entries [MediatorStore::list_messages_filtered] scans per request, as a
+/// multiple of the list limit
+const LIST_SCAN_FACTOR: usize = 4;
+
+/// Stream of live messages published to a streaming service (see [MediatorStore::streaming_subscribe])
+pub type PubSubStream = Pin<Box<dyn Stream<Item = PubSubRecord> + Send>>;
+
+/// How the database schema upgrade chain is run (see [MediatorStore::upgrade_schema])
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum SchemaUpgradeMode {
+    /// Upgrade the schema to the mediator version
+    #[default]
+    Apply,
+    /// Report the migrations that would run and the keys they would touch, without changes
+    DryRun,
+    /// Roll the schema back to the given version using the reverse migration steps
+    RollbackTo(String),
+}
+
+#[async_trait]
+pub trait MediatorStore: Send + Sync {
+    // ************************************************************************
+    // Setup and housekeeping
+
+    /// Initializes the backend and ensures the minimal configuration (mediator and admin accounts)
+    /// is in place. Called once when the mediator starts.
+    async fn initialize(&self, config: &Config) -> Result<(), MediatorError>;
+
+    /// Redis connection handler used by the standalone processors (message expiry cleanup and
+    /// forwarding). Backends that don't run on Redis return None.
+    #[cfg(feature = "redis")]
+    fn redis_handler(&self) -> Option<DatabaseHandler> {
+        None
+    }
+
+    /// Runs the versioned schema migrations in the given mode.
+    /// `initialize` already upgrades the schema, this is used for dry-runs and rollbacks.
+    ///
+    /// Backends without versioned migrations keep the default implementation.
+    async fn upgrade_schema(
+        &self,
+        _config: &Config,
+        mode: &SchemaUpgradeMode,
+    ) -> Result<(), MediatorError> {
+        match mode {
+            SchemaUpgradeMode::RollbackTo(version) => Err(MediatorError::DatabaseError(
+                "NA".into(),
+                format!(
+                    "Rolling back the schema to ({}) isn't supported by this storage backend",
+                    version
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flushes any buffered writes to durable storage. Called once when the mediator shuts down,
+    /// after all handlers and background tasks have stopped.
+    async fn flush(&self) -> Result<(), MediatorError> {
+        Ok(())
+    }
+
+    /// Removes all messages that expired at or before `now` (epoch seconds)
+    /// Returns the number of messages removed
+    ///
+    /// Backends that provide a [MediatorStore::redis_handler] leave this to the
+    /// MessageExpiryCleanupProcessor and keep the default implementation.
+    async fn expire_messages(&self, _now: u64) -> Result<usize, MediatorError> {
+        Ok(0)
+    }
+
+    // ************************************************************************
+    // Messages
+
+    /// Stores a message in the database
+    /// Returns the message_id (hash of the message)
+    /// - expires_at: The timestamp at which the message expires (since epoch in seconds)
+    /// - signed_by: Key ID of a verified signature over the message (stored in the metadata)
+    /// - quota: Default queue quotas in bytes, an account's own quota replaces the default
+    ///
+    /// The quotas of the receiver (and the sender, unless anonymous) are checked atomically with
+    /// storing the message. Returns a ServiceLimitError if the message would exceed either of them
+    #[allow(clippy::too_many_arguments)]
+    async fn store_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        to_did: &str,
+        from_did: Option<&str>,
+        expires_at: u64,
+        signed_by: Option<&str>,
+        quota: &QueueByteQuota,
+    ) -> Result<String, MediatorError>;
+
+    /// Get a message from the database, `did_hash` must be either the sender or the recipient
+    async fn get_message(
+        &self,
+        did_hash: &str,
+        msg_id: &str,
+    ) -> Result<MessageListElement, MediatorError>;
+
+    /// Fetch as many messages as possible (up to `options.limit`) from the inbox of `did_hash`
+    async fn fetch_messages(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        options: &FetchOptions,
+    ) -> Result<GetMessagesResponse, MediatorError>;
+
+    /// Retrieves list of messages for the specified DID and folder
+    /// - range: stream ID range to retrieve (defaults to '-' and '+' which gets all messages)
+    async fn list_messages(
+        &self,
+        did_hash: &str,
+        folder: Folder,
+        range: Option<(&str, &str)>,
+        limit: u32,
+    ) -> Result<MessageList, MediatorError>;
+
+    /// Lists messages for the specified DID and folder that match `options`
+    /// - limit: maximum number of messages to return, `options.limit` can only lower it
+    ///
+    /// The stream is read in batches of `limit` until the list is full, so filters don't shorten
+    /// a page. If more messages may match, `next_cursor` is set on the last element.
+    ///
+    /// Once something matched, at most `limit * LIST_SCAN_FACTOR` entries are scanned, a sparse
+    /// filter can return a short page that still has `next_cursor` set.
+    /// Backends only need to implement [MediatorStore::list_messages].
+    async fn list_messages_filtered(
+        &self,
+        did_hash: &str,
+        folder: Folder,
+        options: &ListMessagesOptions,
+        limit: u32,
+    ) -> Result<MessageList, MediatorError> {
+        let limit = options.limit.unwrap_or(limit).clamp(1, limit.max(1));
+        // The cursor always points past `since`, it came from a previous page
+        let mut start = match (&options.cursor, &options.since) {
+            (Some(cursor), _) => format!("({}", cursor),
+            (None, Some(since)) => since.clone(),
+            (None, None) => "-".into(),
+        };
+        let end = options.until.as_deref().unwrap_or("+");
+
+        let max_scanned = (limit as usize).saturating_mul(LIST_SCAN_FACTOR);
+        let mut scanned = 0;
+        let mut list = MessageList::new();
+        loop {
+            let batch = self
+                .list_messages(did_hash, folder.clone(), Some((&start, end)), limit)
+                .await?;
+            let exhausted = batch.len() < limit as usize;
+            let batch_len = batch.len();
+
+            for (index, mut element) in batch.into_iter().enumerate() {
+                let stream_id = match folder {
+                    Folder::Inbox => element.receive_id.clone(),
+                    Folder::Outbox => element.send_id.clone(),
+                };
+                let Some(stream_id) = stream_id else {
+                    return Err(MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!(
+                            "Message ({}) in the {} of DID ({}) has no stream ID",
+                            element.msg_id, folder, did_hash
+                        ),
+                    ));
+                };
+                start = format!("({}", stream_id);
+                scanned += 1;
+                let last = exhausted && index + 1 == batch_len;
+
+                if _list_filter_matches(&element, did_hash, options) {
+                    if list.len() + 1 == limit as usize {
+                        if !last {
+                            element.next_cursor = Some(stream_id);
+                        }
+                        list.push(element);
+                        return Ok(list);
+                    }
+                    list.push(element);
+                }
+
+                // Scanned enough for one request, the client continues from here
+                // Keeps scanning until something matched, the cursor needs an element to go on
+                if scanned >= max_scanned && !last {
+                    if let Some(element) = list.last_mut() {
+                        element.next_cursor = Some(stream_id);
+                        return Ok(list);
+                    }
+                }
+            }
+
+            if exhausted {
+                return Ok(list);
+            }
+        }
+    }
+
+    /// Deletes a message in the database
+    /// - did_hash: DID of the delete requestor (can be `ADMIN` if the mediator is deleting the message)
+    async fn delete_message(
+        &self,
+        session_id: Option<&str>,
+        did_hash: &str,
+        message_hash: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Will purge/delete all messages from the database for the given DID and folder
+    /// Returns the number of messages purged and the total bytes purged
+    async fn purge_messages(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: Folder,
+    ) -> Result<(usize, usize), MediatorError>;
+
+    /// Removes the folder (queue) for a DID without touching the messages themselves
+    async fn delete_folder_stream(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: &Folder,
+    ) -> Result<(), MediatorError>;
+
+    /// Message Pickup 3.0 status of the inbox for `did_hash`
+    /// `recipient_did` and `longest_waited_seconds` are left for the caller to fill in
+    async fn get_status_reply(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<MessagePickupStatusReply, MediatorError>;
+
+    // ************************************************************************
+    // Forwarding and scheduled deliveries
+
+    /// Queues a message for remote delivery, returns the ID of the task
+    async fn forward_queue_enqueue(
+        &self,
+        session_id: &str,
+        task: &ForwardTask,
+    ) -> Result<String, MediatorError>;
+
+    /// Number of forward tasks waiting for remote delivery
+    async fn get_forward_tasks_len(&self) -> Result<usize, MediatorError>;
+
+    /// Schedules a message for delivery at `deliver_at` (epoch milliseconds)
+    /// Returns the scheduled delivery ID
+    async fn scheduled_delivery_add(
+        &self,
+        session_id: &str,
+        deliver_at: u128,
+        delivery: &ScheduledDelivery,
+    ) -> Result<String, MediatorError>;
+
+    /// Returns up to `limit` scheduled delivery IDs that are due at `now` (epoch milliseconds)
+    async fn scheduled_delivery_due(
+        &self,
+        now: u128,
+        limit: usize,
+    ) -> Result<Vec<String>, MediatorError>;
+
+    /// Claims a scheduled delivery, removing it from the schedule
+    /// Returns None if it has already been claimed
+    async fn scheduled_delivery_claim(
+        &self,
+        id: &str,
+    ) -> Result<Option<ScheduledDelivery>, MediatorError>;
+
+    // ************************************************************************
+    // Accounts
+
+    /// Quick and efficient check if an account exists locally in the mediator
+    async fn account_exists(&self, did_hash: &str) -> Result<bool, MediatorError>;
+
+    /// Grab Account information, Ok(None) if the account does not exist
+    async fn account_get(&self, did_hash: &str) -> Result<Option<Account>, MediatorError>;
+
+    /// Add a DID account to the mediator
+    async fn account_add(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+        queue_limit: Option<u32>,
+    ) -> Result<Account, MediatorError>;
+
+    /// Removes an account from the mediator, along with its inbox, pending forward tasks and
+    /// scheduled deliveries (to or from the DID), the OOB invites it created, its sessions and its
+    /// streaming registration
+    /// - `remove_outbox` - Also remove messages from this DID that have not been delivered
+    ///
+    /// Returns a summary of what was removed
+    async fn account_remove(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        remove_outbox: bool,
+    ) -> Result<AccountRemoveResponse, MediatorError>;
+
+    /// Retrieves up to `limit` (max 100) accounts starting at `cursor` (0 is the start)
+    /// The returned cursor is 0 when there are no more accounts
+    async fn account_list(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAccountList, MediatorError>;
+
+    /// Changes the type of an account
+    async fn account_change_type(
+        &self,
+        did_hash: &str,
+        _type: &AccountType,
+    ) -> Result<(), MediatorError>;
+
+    /// Changes the queue limits of an account
+    /// None: no change, Some(-1): unlimited, Some(-2): reset to soft limit, Some(n): set to n
+    async fn account_change_queue_limits(
+        &self,
+        did_hash: &str,
+        send_queue_limit: Option<i32>,
+        receive_queue_limit: Option<i32>,
+    ) -> Result<(), MediatorError>;
+
+    /// Changes the queue quotas in bytes of an account
+    /// None: no change, Some(-1): unlimited, Some(-2): reset to soft limit, Some(n): set to n
+    async fn account_change_queue_bytes_limits(
+        &self,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<(), MediatorError>;
+
+    /// Changes the rate limit (requests per minute) of an account
+    /// -1: unlimited, -2: reset to the configured limits, n: set to n
+    async fn account_change_rate_limit(
+        &self,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<(), MediatorError>;
+
+    // ************************************************************************
+    // Admin accounts
+
+    /// Ensures that an admin account exists with the given type
+    async fn setup_admin_account(
+        &self,
+        admin_did_hash: &str,
+        admin_type: AccountType,
+        acls: &MediatorACLSet,
+    ) -> Result<(), MediatorError>;
+
+    /// Checks if the provided DID is an admin level account
+    async fn check_admin_account(&self, did_hash: &str) -> Result<bool, MediatorError>;
+
+    /// Adds up to 100 admin accounts to the mediator
+    async fn add_admin_accounts(
+        &self,
+        accounts: Vec<String>,
+        acls: &MediatorACLSet,
+    ) -> Result<usize, MediatorError>;
+
+    /// Strips admin rights from up to 100 accounts
+    async fn strip_admin_accounts(&self, accounts: Vec<String>) -> Result<i32, MediatorError>;
+
+    /// Retrieves up to 100 admin accounts from the mediator
+    async fn list_admin_accounts(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAdminList, MediatorError>;
+
+    // ************************************************************************
+    // Audit log
+
+    /// Appends an entry to the audit log, the ID is assigned by the store
+    /// Older entries are trimmed once the log holds more than `max_entries`
+    async fn audit_log_add(
+        &self,
+        entry: &AuditLogEntry,
+        max_entries: usize,
+    ) -> Result<(), MediatorError>;
+
+    /// Retrieves up to `limit` audit log entries matching `filter`, newest first
+    /// `cursor` is the cursor returned by the previous page (None starts from the newest entry)
+    async fn audit_log_list(
+        &self,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &AuditLogFilter,
+    ) -> Result<MediatorAuditLog, MediatorError>;
+
+    // ************************************************************************
+    // Push notification targets
+
+    /// Registers the push target for a DID, replacing any existing target
+    async fn push_target_set(
+        &self,
+        did_hash: &str,
+        target: &PushTarget,
+    ) -> Result<(), MediatorError>;
+
+    /// Gets the push target registered for a DID, or None if it has no target
+    async fn push_target_get(&self, did_hash: &str) -> Result<Option<PushTarget>, MediatorError>;
+
+    /// Removes the push target registered for a DID, returns true if a target was removed
+    async fn push_target_remove(&self, did_hash: &str) -> Result<bool, MediatorError>;
+
+    // ************************************************************************
+    // ACLs
+
+    /// Replace the ACL for a given DID
+    async fn set_did_acl(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+    ) -> Result<MediatorACLSet, MediatorError>;
+
+    /// Get ACL for a given DID, or None if DID isn't found
+    async fn get_did_acl(&self, did_hash: &str) -> Result<Option<MediatorACLSet>, MediatorError>;
+
+    /// Retrieves ACLs for up to 100 DID hashes
+    async fn get_did_acls(
+        &self,
+        dids: &[String],
+        mediator_acl_mode: AccessListModeType,
+    ) -> Result<MediatorACLGetResponse, MediatorError>;
+
+    // ************************************************************************
+    // Access lists
+
+    /// Checks if `from_hash` is allowed to send to `to_hash` based on its access list
+    async fn access_list_allowed(
+        &self,
+        to_hash: &str,
+        from_hash: Option<String>,
+    ) -> Result<bool, MediatorError>;
+
+    /// Retrieves DID hashes from the Access List, starting at `cursor` (0 is the start)
+    async fn access_list_list(
+        &self,
+        did_hash: &str,
+        cursor: u64,
+    ) -> Result<MediatorAccessListListResponse, MediatorError>;
+
+    /// Number of members in the Access List
+    async fn access_list_count(&self, did_hash: &str) -> Result<usize, MediatorError>;
+
+    /// Adds DID hashes to the Access List, truncated to `access_list_limit` members
+    async fn access_list_add(
+        &self,
+        access_list_limit: usize,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListAddResponse, MediatorError>;
+
+    /// Removes DID hashes from the Access List, returns the number removed
+    async fn access_list_remove(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<usize, MediatorError>;
+
+    /// Clears the Access List for a given DID
+    async fn access_list_clear(&self, did_hash: &str) -> Result<(), MediatorError>;
+
+    /// Returns the hashes that exist in the Access List
+    async fn access_list_get(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListGetResponse, MediatorError>;
+
+    // ************************************************************************
+    // Coordinate Mediation keylists
+
+    /// Adds a recipient DID to the mediation keylist of `did_hash`
+    /// Returns false if the recipient DID was already in the keylist
+    async fn keylist_add(&self, did_hash: &str, recipient_did: &str)
+    -> Result<bool, MediatorError>;
+
+    /// Removes a recipient DID from the mediation keylist of `did_hash`
+    /// Returns false if the recipient DID wasn't in the keylist
+    async fn keylist_remove(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError>;
+
+    /// Retrieves up to `limit` recipient DIDs (ordered by DID) starting at `offset`
+    /// Returns the recipient DIDs and the total number of DIDs in the keylist
+    async fn keylist_list(
+        &self,
+        did_hash: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), MediatorError>;
+
+    // ************************************************************************
+    // Sessions
+
+    /// Creates a new session, typically when sending the initial challenge to the client
+    async fn create_session(&self, session: &Session) -> Result<(), MediatorError>;
+
+    /// Retrieves a session along with the role and ACLs of the DID
+    async fn get_session(&self, session_id: &str, did: &str) -> Result<Session, MediatorError>;
+
+    /// Marks a session as authenticated under its new session ID
+    /// Also ensures that the DID is recorded as a known DID
+    async fn update_session_authenticated(
+        &self,
+        old_session_id: &str,
+        new_session_id: &str,
+        did_hash: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Stores a challenge used to prove control of `did_hash` within an authenticated session
+    /// The challenge expires after 15 minutes, the same as a new session
+    async fn session_link_challenge(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        challenge: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Links `did` to an authenticated session if `challenge` matches the stored link challenge
+    /// A link challenge can only be used once
+    /// Returns false if the challenge didn't match or has expired
+    async fn session_link_did(
+        &self,
+        session_id: &str,
+        did: &str,
+        challenge: &str,
+    ) -> Result<bool, MediatorError>;
+
+    // ************************************************************************
+    // OOB Discovery
+
+    /// Stores an OOB Discovery Invitation, returns the OOB ID
+    async fn oob_discovery_store(
+        &self,
+        did_hash: &str,
+        invite: &Message,
+        oob_invite_ttl: u64,
+    ) -> Result<String, MediatorError>;
+
+    /// Retrieve an OOB Discovery Invitation (base64 encoded) if it exists
+    async fn oob_discovery_get(&self, oob_id: &str) -> Result<Option<String>, MediatorError>;
+
+    /// Deletes an OOB Discovery Invitation
+    async fn oob_discovery_delete(&self, oob_id: &str) -> Result<bool, MediatorError>;
+
+    // ************************************************************************
+    // Rate limiting
+
+    /// Takes a token from the rate limit bucket `key` which refills at `per_minute` tokens per minute
+    /// If `did_hash` is set, the account's own rate limit replaces `per_minute`
+    /// Returns None if the request is allowed, otherwise the milliseconds until a token is available
+    async fn rate_limit_take(
+        &self,
+        key: &str,
+        did_hash: Option<&str>,
+        per_minute: u32,
+    ) -> Result<Option<u64>, MediatorError>;
+
+    // ************************************************************************
+    // Live streaming
+
+    /// Cleans up streaming sessions left over from a previous run of the streaming service `uuid`
+    async fn streaming_clean_start(&self, uuid: &str) -> Result<(), MediatorError>;
+
+    /// Returns the streaming service IDs the client is connected to where the DID hash is live
+    /// streaming (or connected at all when `force_delivery` is true)
+    /// A DID can be connected to several streaming services when it has multiple devices
+    async fn streaming_is_client_live(&self, did_hash: &str, force_delivery: bool) -> Vec<String>;
+
+    /// Publishes a live message to the streaming service `stream_uuid`
+    async fn streaming_publish_message(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+        message: &str,
+        force_delivery: bool,
+    ) -> Result<(), MediatorError>;
+
+    /// Subscribes to the messages published to the streaming service `stream_uuid`
+    /// The stream ends if the subscription is lost, callers are expected to subscribe again
+    async fn streaming_subscribe(&self, stream_uuid: &str) -> Result<PubSubStream, MediatorError>;
+
+    /// Registers a client to the live streaming service
+    async fn streaming_register_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Enables live streaming for the client
+    async fn streaming_start_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Disables live streaming for a client
+    async fn streaming_stop_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError>;
+
+    /// Removes client from live streaming service
+    async fn streaming_deregister_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError>;
+
+    // ************************************************************************
+    // Statistics
+
+    /// Retrieves metadata statistics that are global to the mediator database
+    async fn get_db_metadata(&self) -> Result<MetadataStats, MediatorError>;
+
+    /// Number of expiry timeslots (seconds with expiring messages) that are due at `now`
+    /// (epoch seconds) but have not been cleaned up yet
+    async fn get_expiry_backlog(&self, now: u64) -> Result<usize, MediatorError>;
+
+    /// Updates global send metrics
+    async fn update_send_stats(&self, sent_bytes: i64) -> Result<(), MediatorError>;
+
+    /// Increment WebSocket open count
+    async fn global_stats_increment_websocket_open(&self) -> Result<(), MediatorError>;
+
+    /// Increment WebSocket close count
+    async fn global_stats_increment_websocket_close(&self) -> Result<(), MediatorError>;
+}
+
+/// Does a listed message match the `options` filters
+/// Inbox elements only carry the sender and Outbox elements only the recipient, the other side
+/// is always `did_hash`
+fn _list_filter_matches(
+    element: &MessageListElement,
+    did_hash: &str,
+    options: &ListMessagesOptions,
+) -> bool {
+    let from = element.from_address.as_deref().unwrap_or(did_hash);
+    let to = element.to_address.as_deref().unwrap_or(did_hash);
+
+    options.from_did_hash.as_deref().is_none_or(|f| f == from)
+        && options.to_did_hash.as_deref().is_none_or(|t| t == to)
+        && options.max_size.is_none_or(|max| element.size <= max)
+}
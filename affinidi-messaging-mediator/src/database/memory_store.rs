@@ -0,0 +1,2664 @@
+/*!
+ * In-memory storage backend, intended for tests and throwaway mediators
+ *
+ * Mirrors the Redis data model and the semantics of the functions in `conf/atm-functions.lua`:
+ * - `GLOBAL`: Global counters (RECEIVED_BYTES, SENT_COUNT, etc)
+ * - `DID:<did_hash>`: DID records (ROLE_TYPE, ACLS, queue counters and limits)
+ * - `KNOWN_DIDS`, `ADMINS`, `ACCESS_LIST:<did_hash>`: Sets of DID hashes
+ * - `MEDIATION_KEYLIST:<did_hash>`: Recipient DIDs registered through Coordinate Mediation
+ * - `MSG:<msg_id>` and `MSG:META:<msg_id>`: Message and its metadata
+ * - `MSG_EXPIRY`: Sorted set of expiry times, each pointing to the set of messages expiring then
+ * - `RECEIVE_Q:<did_hash>` and `SEND_Q:<did_hash>`: Streams ordered by stream ID (`ms-seq`)
+ * - `SESSION:<session_id>`, `SESSION_LINK:<session_id>:<did_hash>`, `OOB_INVITES:<oob_id>`: Records that expire
+ * - `GLOBAL_STREAMING:<did_hash>` and `STREAMING_SESSIONS:<uuid>`: Live streaming state
+ * - `CHANNEL:<uuid>`: pub/sub channels, delivered through in-process channels
+ * - `RATE_LIMIT:<bucket>`: Token buckets of the rate limiter
+ * - `AUDIT_LOG`: Capped log of administrative changes
+ *
+ * Nothing is persisted, every [MemoryStore] starts empty. Select it with `database_url = "memory://"`
+ * or hand it directly to [crate::server::start_with].
+ */
+
+use super::{
+    MediatorStore, PubSubStream,
+    audit_log::audit_log_page,
+    rate_limit::{TokenBucket, effective_limit},
+    scheduled_delivery::ScheduledDelivery,
+    session::{SESSION_EXPIRY, Session, SessionState},
+    stats::MetadataStats,
+    store::{QueueByteQuota, quota_error, quota_exceeded},
+    stream_id::{parse_stream_id, range_bound},
+};
+use crate::{common::config::Config, tasks::websocket_streaming::PubSubRecord};
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use affinidi_messaging_sdk::{
+    messages::{
+        FetchDeletePolicy, Folder, GetMessagesResponse, MessageList, MessageListElement,
+        fetch::FetchOptions,
+    },
+    protocols::{
+        mediator::{
+            accounts::{Account, AccountRemoveResponse, AccountType, MediatorAccountList},
+            acls::{AccessListModeType, MediatorACLSet},
+            acls_handler::{
+                MediatorACLExpanded, MediatorACLGetResponse, MediatorAccessListAddResponse,
+                MediatorAccessListGetResponse, MediatorAccessListListResponse,
+            },
+            administration::{
+                AdminAccount, AuditLogEntry, AuditLogFilter, MediatorAdminList, MediatorAuditLog,
+            },
+            push_notifications::PushTarget,
+        },
+        message_pickup::MessagePickupStatusReply,
+    },
+};
+use async_trait::async_trait;
+use base64::prelude::*;
+use sha256::digest;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    sync::{Mutex, MutexGuard},
+    time::SystemTime,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+/// In-memory backend (`memory://`)
+#[derive(Default)]
+pub struct MemoryStore {
+    data: Mutex<Data>,
+    /// pub/sub subscribers, keyed by streaming service UUID
+    channels: Mutex<HashMap<String, Vec<mpsc::UnboundedSender<PubSubRecord>>>>,
+}
+
+/// All keys held by the store
+#[derive(Default)]
+struct Data {
+    schema_version: Option<String>,
+    global: HashMap<String, i64>,
+    dids: HashMap<String, DidRecord>,
+    known_dids: BTreeSet<String>,
+    admins: BTreeSet<String>,
+    access_lists: HashMap<String, BTreeSet<String>>,
+    mediation_keylists: HashMap<String, BTreeSet<String>>,
+    messages: HashMap<String, StoredMessage>,
+    /// MSG_EXPIRY: expires_at (epoch seconds) -> message IDs
+    msg_expiry: BTreeMap<u64, BTreeSet<String>>,
+    receive_q: HashMap<String, MessageStream>,
+    send_q: HashMap<String, MessageStream>,
+    sessions: HashMap<String, SessionRecord>,
+    /// SESSION_LINK: (session_id, did_hash) -> (challenge, expires_at)
+    link_challenges: HashMap<(String, String), (String, i64)>,
+    /// OOB_INVITES: oob_id -> (base64 invite, expires_at)
+    oob_invites: HashMap<String, (String, u64)>,
+    /// DID_OOB_INVITES: did_hash -> oob_ids created by the DID
+    oob_invite_creators: HashMap<String, BTreeSet<String>>,
+    /// GLOBAL_STREAMING: did_hash -> stream_uuid -> live
+    /// A DID can be connected to several streaming services at once (multiple devices)
+    global_streaming: HashMap<String, HashMap<String, bool>>,
+    /// STREAMING_SESSIONS: stream_uuid -> did_hashes
+    streaming_sessions: HashMap<String, BTreeSet<String>>,
+    forward_tasks: VecDeque<ForwardTask>,
+    forward_last_id: (i64, i64),
+    /// SCHEDULED_DELIVERIES: (deliver_at, id), records are held in `scheduled_records`
+    scheduled: BTreeSet<(u128, String)>,
+    scheduled_records: HashMap<String, (u128, ScheduledDelivery)>,
+    /// RATE_LIMIT:<bucket>
+    rate_limits: HashMap<String, TokenBucket>,
+    /// AUDIT_LOG: oldest entry first, IDs are assigned from `audit_log_last_id`
+    audit_log: VecDeque<AuditLogEntry>,
+    audit_log_last_id: u64,
+}
+
+/// DID:<did_hash>
+#[derive(Default)]
+struct DidRecord {
+    role_type: Option<String>,
+    acls: Option<String>,
+    send_queue_bytes: i64,
+    send_queue_count: i64,
+    receive_queue_bytes: i64,
+    receive_queue_count: i64,
+    send_queue_limit: Option<i32>,
+    receive_queue_limit: Option<i32>,
+    send_queue_bytes_limit: Option<i64>,
+    receive_queue_bytes_limit: Option<i64>,
+    rate_limit: Option<i32>,
+    push_target: Option<PushTarget>,
+}
+
+/// MSG:<msg_id> and MSG:META:<msg_id>
+struct StoredMessage {
+    message: String,
+    bytes: i64,
+    to_did_hash: String,
+    from_did_hash: String,
+    timestamp: i64,
+    receive_id: String,
+    send_id: String,
+    signed_by: Option<String>,
+    expires_at: u64,
+}
+
+/// Stream entry fields (MSG_ID, BYTES and FROM/TO)
+struct StreamEntry {
+    msg_id: String,
+    bytes: i64,
+    peer: String,
+}
+
+/// RECEIVE_Q or SEND_Q stream for a single DID
+/// Like Redis, the last ID is kept when entries are deleted so IDs are never reused
+#[derive(Default)]
+struct MessageStream {
+    last_id: (i64, i64),
+    entries: BTreeMap<(i64, i64), StreamEntry>,
+}
+
+impl MessageStream {
+    /// XADD with an auto generated sequence for `now_ms`, returns the stream ID
+    fn add(&mut self, now_ms: i64, entry: StreamEntry) -> String {
+        self.last_id = _next_id(self.last_id, now_ms);
+        self.entries.insert(self.last_id, entry);
+        format!("{}-{}", self.last_id.0, self.last_id.1)
+    }
+}
+
+/// SESSION:<session_id>
+struct SessionRecord {
+    challenge: String,
+    state: String,
+    did: String,
+    expires_at: i64,
+    /// SESSION_LINKED_DIDS:<session_id>
+    linked_dids: Vec<String>,
+}
+
+impl Data {
+    /// Removes the sessions, streaming registration, forward tasks, scheduled deliveries and OOB
+    /// invites of a DID that is being removed. Message counts are left for the caller to fill in
+    fn remove_did_records(&mut self, did_hash: &str) -> AccountRemoveResponse {
+        let sessions = self.sessions.len();
+        self.sessions.retain(|_, session| {
+            digest(&session.did) != did_hash
+                && !session
+                    .linked_dids
+                    .iter()
+                    .any(|did| digest(did) == did_hash)
+        });
+
+        let streaming = self.global_streaming.remove(did_hash).unwrap_or_default();
+        for stream_uuid in streaming.keys() {
+            if let Some(sessions) = self.streaming_sessions.get_mut(stream_uuid) {
+                sessions.remove(did_hash);
+            }
+        }
+        let streaming_sessions = streaming.len();
+
+        let forward_tasks = self.forward_tasks.len();
+        self.forward_tasks.retain(|task| !task.involves(did_hash));
+
+        let scheduled: Vec<String> = self
+            .scheduled_records
+            .iter()
+            .filter(|(_, (_, delivery))| delivery.involves(did_hash))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &scheduled {
+            if let Some((deliver_at, _)) = self.scheduled_records.remove(id) {
+                self.scheduled.remove(&(deliver_at, id.clone()));
+            }
+        }
+
+        let oob_invites = self
+            .oob_invite_creators
+            .remove(did_hash)
+            .unwrap_or_default()
+            .iter()
+            .filter(|oob_id| self.oob_invites.remove(*oob_id).is_some())
+            .count();
+
+        AccountRemoveResponse {
+            did_hash: did_hash.to_string(),
+            sessions: sessions - self.sessions.len(),
+            streaming_sessions,
+            forward_tasks: forward_tasks - self.forward_tasks.len(),
+            scheduled_deliveries: scheduled.len(),
+            oob_invites,
+            ..Default::default()
+        }
+    }
+
+    fn incr_global(&mut self, key: &str, by: i64) {
+        *self.global.entry(key.to_string()).or_default() += by;
+    }
+
+    /// Same as `delete_message` in atm-functions.lua
+    fn delete_message(&mut self, did_hash: &str, message_hash: &str) -> Result<(), String> {
+        let Some(message) = self.messages.get(message_hash) else {
+            return Err(format!("Message ({}) not found", message_hash));
+        };
+
+        // Check that the requesting DID has some form of ownership of this message
+        if message.to_did_hash != did_hash
+            && message.from_did_hash != did_hash
+            && did_hash != "ADMIN"
+        {
+            return Err("Requesting DID does not have ownership of this message".into());
+        }
+
+        let message = self.messages.remove(message_hash).unwrap();
+
+        self.incr_global("DELETED_BYTES", message.bytes);
+        self.incr_global("DELETED_COUNT", 1);
+
+        if let Some(expiring) = self.msg_expiry.get_mut(&message.expires_at) {
+            expiring.remove(message_hash);
+            if expiring.is_empty() {
+                self.msg_expiry.remove(&message.expires_at);
+            }
+        }
+
+        // Remove the receiver records
+        let receiver = self.dids.entry(message.to_did_hash.clone()).or_default();
+        receiver.receive_queue_bytes -= message.bytes;
+        receiver.receive_queue_count -= 1;
+        if let (Some(stream), Some(id)) = (
+            self.receive_q.get_mut(&message.to_did_hash),
+            parse_stream_id(&message.receive_id, 0),
+        ) {
+            stream.entries.remove(&id);
+        }
+
+        // Remove the sender records
+        let sender = self.dids.entry(message.from_did_hash.clone()).or_default();
+        sender.send_queue_bytes -= message.bytes;
+        sender.send_queue_count -= 1;
+        if let (Some(stream), Some(id)) = (
+            self.send_q.get_mut(&message.from_did_hash),
+            parse_stream_id(&message.send_id, 0),
+        ) {
+            stream.entries.remove(&id);
+        }
+
+        Ok(())
+    }
+
+    fn queue(&self, folder: &Folder) -> &HashMap<String, MessageStream> {
+        match folder {
+            Folder::Inbox => &self.receive_q,
+            Folder::Outbox => &self.send_q,
+        }
+    }
+
+    fn queue_mut(&mut self, folder: &Folder) -> &mut HashMap<String, MessageStream> {
+        match folder {
+            Folder::Inbox => &mut self.receive_q,
+            Folder::Outbox => &mut self.send_q,
+        }
+    }
+
+    /// Which queue ("receive" or "send") would exceed its quota if `bytes` were stored
+    /// Same as the quota check in the `store_message` function
+    fn quota_exceeded(
+        &self,
+        to_hash: &str,
+        from_hash: &str,
+        bytes: i64,
+        quota: &QueueByteQuota,
+    ) -> Option<&'static str> {
+        let receiver = self.dids.get(to_hash);
+        if quota_exceeded(
+            quota.receive,
+            receiver.and_then(|record| record.receive_queue_bytes_limit),
+            receiver
+                .map(|record| record.receive_queue_bytes)
+                .unwrap_or(0),
+            bytes,
+        ) {
+            return Some("receive");
+        }
+
+        // Anonymous senders share a single record, so only the receiver quota applies to them
+        let sender = self.dids.get(from_hash);
+        if from_hash != "ANONYMOUS"
+            && quota_exceeded(
+                quota.send,
+                sender.and_then(|record| record.send_queue_bytes_limit),
+                sender.map(|record| record.send_queue_bytes).unwrap_or(0),
+                bytes,
+            )
+        {
+            return Some("send");
+        }
+
+        None
+    }
+
+    fn account(&self, did_hash: &str) -> Option<Account> {
+        let record = self.dids.get(did_hash)?;
+        Some(Account {
+            did_hash: did_hash.to_string(),
+            _type: record
+                .role_type
+                .as_deref()
+                .map(AccountType::from)
+                .unwrap_or(AccountType::Standard),
+            acls: record
+                .acls
+                .as_deref()
+                .and_then(|acls| u64::from_str_radix(acls, 16).ok())
+                .unwrap_or(0),
+            send_queue_bytes: record.send_queue_bytes as u64,
+            send_queue_count: record.send_queue_count as u32,
+            receive_queue_bytes: record.receive_queue_bytes as u64,
+            receive_queue_count: record.receive_queue_count as u32,
+            queue_send_limit: record.send_queue_limit,
+            queue_receive_limit: record.receive_queue_limit,
+            queue_send_bytes_limit: record.send_queue_bytes_limit,
+            queue_receive_bytes_limit: record.receive_queue_bytes_limit,
+            rate_limit: record.rate_limit,
+            access_list_count: self
+                .access_lists
+                .get(did_hash)
+                .map(|list| list.len())
+                .unwrap_or(0) as u32,
+        })
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn data(&self) -> MutexGuard<'_, Data> {
+        self.data
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn channels(
+        &self,
+    ) -> MutexGuard<'_, HashMap<String, Vec<mpsc::UnboundedSender<PubSubRecord>>>> {
+        self.channels
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Registers the DID against a streaming service, `live` enables live delivery
+    fn _streaming_set(&self, did_hash: &str, stream_uuid: &str, live: bool) {
+        self.data()
+            .global_streaming
+            .entry(did_hash.to_string())
+            .or_default()
+            .insert(stream_uuid.to_string(), live);
+    }
+}
+
+// ****************************************************************************
+// Private helpers
+
+fn _now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn _now_secs() -> i64 {
+    _now_ms() / 1000
+}
+
+/// Next stream ID after `last` for an entry added at `now_ms`
+fn _next_id(last: (i64, i64), now_ms: i64) -> (i64, i64) {
+    if last.0 >= now_ms {
+        (last.0, last.1 + 1)
+    } else {
+        (now_ms, 0)
+    }
+}
+
+/// Next cursor for offset based pagination, 0 when there are no more results
+fn _next_cursor(cursor: u64, limit: u64, returned: usize) -> u64 {
+    if limit > 0 && returned as u64 >= limit {
+        cursor + limit
+    } else {
+        0
+    }
+}
+
+fn _delete_error(did_hash: &str, message_hash: &str, reason: &str) -> MediatorError {
+    MediatorError::DatabaseError(
+        did_hash.into(),
+        format!(
+            "Couldn't delete message_id({}) from database for DID {}: {}",
+            message_hash, did_hash, reason
+        ),
+    )
+}
+
+#[async_trait]
+impl MediatorStore for MemoryStore {
+    // ************************************************************************
+    // Setup and housekeeping
+
+    async fn initialize(&self, config: &Config) -> Result<(), MediatorError> {
+        {
+            let mut data = self.data();
+            match &data.schema_version {
+                Some(schema_version) if schema_version == env!("CARGO_PKG_VERSION") => {
+                    info!("Database schema version ({}) is good", schema_version);
+                }
+                schema_version => {
+                    warn!(
+                        "Database schema version ({:?}). Setting to ({})",
+                        schema_version,
+                        env!("CARGO_PKG_VERSION")
+                    );
+                    data.schema_version = Some(env!("CARGO_PKG_VERSION").to_string());
+                }
+            }
+        }
+
+        // Setup the mediator account if it doesn't exist
+        // Set the ACL for the mediator account to deny_all by default
+        self.setup_admin_account(
+            &config.mediator_did_hash,
+            AccountType::Mediator,
+            &MediatorACLSet::from_string_ruleset("DENY_ALL,LOCAL,BLOCKED").unwrap(),
+        )
+        .await?;
+
+        // Set up the administration account if it doesn't exist
+        self.setup_admin_account(
+            &digest(&config.admin_did),
+            AccountType::RootAdmin,
+            &config.security.global_acl_default,
+        )
+        .await
+    }
+
+    /// Walks the MSG_EXPIRY sorted set, also removes expired sessions and OOB invitations
+    async fn expire_messages(&self, now: u64) -> Result<usize, MediatorError> {
+        let mut data = self.data();
+
+        data.sessions
+            .retain(|_, session| session.expires_at > now as i64);
+        data.link_challenges
+            .retain(|_, (_, expires_at)| *expires_at > now as i64);
+        data.oob_invites
+            .retain(|_, (_, expires_at)| *expires_at > now);
+
+        let expired: Vec<String> = data
+            .msg_expiry
+            .range(..=now)
+            .flat_map(|(_, msg_ids)| msg_ids.iter().cloned())
+            .collect();
+
+        let mut count = 0;
+        for msg_id in expired {
+            match data.delete_message("ADMIN", &msg_id) {
+                Ok(_) => count += 1,
+                Err(err) => warn!("Couldn't expire message ({}). Reason: {}", msg_id, err),
+            }
+        }
+
+        if count > 0 {
+            debug!("Expired {} messages", count);
+        }
+        Ok(count)
+    }
+
+    // ************************************************************************
+    // Messages
+
+    async fn store_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        to_did: &str,
+        from_did: Option<&str>,
+        expires_at: u64,
+        signed_by: Option<&str>,
+        quota: &QueueByteQuota,
+    ) -> Result<String, MediatorError> {
+        let message_hash = digest(message.as_bytes());
+        let to_hash = digest(to_did.as_bytes());
+        let from_hash = if let Some(from_did) = from_did {
+            digest(from_did)
+        } else {
+            "ANONYMOUS".to_string()
+        };
+        let bytes = message.len() as i64;
+        let now = _now_ms();
+
+        let mut data = self.data();
+
+        // Check the queue quotas before anything is written
+        if let Some(queue) = data.quota_exceeded(&to_hash, &from_hash, bytes, quota) {
+            return Err(quota_error(session_id, queue));
+        }
+
+        data.incr_global("RECEIVED_BYTES", bytes);
+        data.incr_global("RECEIVED_COUNT", 1);
+
+        // Create Message Expiry Record
+        data.msg_expiry
+            .entry(expires_at)
+            .or_default()
+            .insert(message_hash.clone());
+
+        // Update the receiver records
+        let receiver = data.dids.entry(to_hash.clone()).or_default();
+        receiver.receive_queue_bytes += bytes;
+        receiver.receive_queue_count += 1;
+        let receive_id = data.receive_q.entry(to_hash.clone()).or_default().add(
+            now,
+            StreamEntry {
+                msg_id: message_hash.clone(),
+                bytes,
+                peer: from_hash.clone(),
+            },
+        );
+
+        // Update the sender records
+        let sender = data.dids.entry(from_hash.clone()).or_default();
+        sender.send_queue_bytes += bytes;
+        sender.send_queue_count += 1;
+        let send_id = data.send_q.entry(from_hash.clone()).or_default().add(
+            now,
+            StreamEntry {
+                msg_id: message_hash.clone(),
+                bytes,
+                peer: to_hash.clone(),
+            },
+        );
+
+        data.messages.insert(
+            message_hash.clone(),
+            StoredMessage {
+                message: message.to_string(),
+                bytes,
+                to_did_hash: to_hash.clone(),
+                from_did_hash: from_hash.clone(),
+                timestamp: now,
+                receive_id,
+                send_id,
+                signed_by: signed_by.map(|s| s.to_string()),
+                expires_at,
+            },
+        );
+
+        info!(
+            "Message hash({}) from({}) to({}) stored in database",
+            message_hash, from_hash, to_hash
+        );
+
+        Ok(message_hash)
+    }
+
+    async fn get_message(
+        &self,
+        did_hash: &str,
+        msg_id: &str,
+    ) -> Result<MessageListElement, MediatorError> {
+        let message = {
+            let data = self.data();
+            let Some(message) = data.messages.get(msg_id) else {
+                return Err(MediatorError::DatabaseError(
+                    did_hash.into(),
+                    format!("Message not found for ID: {}", msg_id),
+                ));
+            };
+
+            if message.from_did_hash != did_hash && message.to_did_hash != did_hash {
+                return Err(MediatorError::DatabaseError(
+                    did_hash.into(),
+                    format!("Message not found for DID: {}", did_hash),
+                ));
+            }
+
+            MessageListElement {
+                msg_id: msg_id.to_string(),
+                msg: Some(message.message.clone()),
+                size: message.bytes as u64,
+                from_address: Some(message.from_did_hash.clone()),
+                to_address: Some(message.to_did_hash.clone()),
+                timestamp: message.timestamp as u64,
+                send_id: Some(message.send_id.clone()),
+                receive_id: Some(message.receive_id.clone()),
+                signed_by: message.signed_by.clone(),
+                next_cursor: None,
+            }
+        };
+
+        let _ = self.update_send_stats(message.size as i64).await;
+        Ok(message)
+    }
+
+    async fn fetch_messages(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        options: &FetchOptions,
+    ) -> Result<GetMessagesResponse, MediatorError> {
+        // start_id is exclusive
+        let start = match options.start_id.as_deref() {
+            None | Some("-") => Some((0, 0)),
+            Some(start_id) => range_bound(&["(", start_id].concat(), true),
+        };
+        let Some(start) = start else {
+            return Err(MediatorError::DatabaseError(
+                session_id.into(),
+                format!("Invalid start_id ({:?})", options.start_id),
+            ));
+        };
+
+        let mut messages = GetMessagesResponse::default();
+        let mut data = self.data();
+
+        let fetched: Vec<MessageListElement> = match data.receive_q.get(did_hash) {
+            Some(stream) => stream
+                .entries
+                .range(start..)
+                .take(options.limit)
+                .map(|(_, entry)| {
+                    let message = data.messages.get(&entry.msg_id);
+                    MessageListElement {
+                        msg_id: entry.msg_id.clone(),
+                        from_address: Some(entry.peer.clone()),
+                        msg: message.map(|m| m.message.clone()),
+                        size: message.map(|m| m.bytes as u64).unwrap_or(0),
+                        to_address: message.map(|m| m.to_did_hash.clone()),
+                        timestamp: message.map(|m| m.timestamp as u64).unwrap_or(0),
+                        receive_id: message.map(|m| m.receive_id.clone()),
+                        send_id: message.map(|m| m.send_id.clone()),
+                        signed_by: message.and_then(|m| m.signed_by.clone()),
+                        next_cursor: None,
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        for message in fetched {
+            debug!("Message id({}) fetched", &message.msg_id);
+
+            if let FetchDeletePolicy::Optimistic = options.delete_policy {
+                match data.delete_message(did_hash, &message.msg_id) {
+                    Ok(_) => {
+                        debug!("Message deleted: ({})", message.msg_id);
+                    }
+                    Err(reason) => {
+                        let e = _delete_error(did_hash, &message.msg_id, &reason);
+                        warn!("Error deleting message: ({})", e);
+                        messages
+                            .delete_errors
+                            .push((message.msg_id.clone(), e.to_string()));
+                    }
+                }
+            }
+            messages.success.push(message);
+        }
+
+        Ok(messages)
+    }
+
+    async fn list_messages(
+        &self,
+        did_hash: &str,
+        folder: Folder,
+        range: Option<(&str, &str)>,
+        limit: u32,
+    ) -> Result<MessageList, MediatorError> {
+        let (start, end) = range.unwrap_or(("-", "+"));
+        let (Some(start), Some(end)) = (range_bound(start, true), range_bound(end, false)) else {
+            return Err(MediatorError::DatabaseError(
+                did_hash.into(),
+                format!("Invalid message_list range ({}, {})", start, end),
+            ));
+        };
+        if start > end {
+            return Ok(Vec::new());
+        }
+
+        let data = self.data();
+        let Some(stream) = data.queue(&folder).get(did_hash) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(stream
+            .entries
+            .range(start..=end)
+            .take(limit as usize)
+            .map(|((ms, seq), entry)| {
+                let stream_id = format!("{}-{}", ms, seq);
+                let mut msg_element = MessageListElement {
+                    msg_id: entry.msg_id.clone(),
+                    size: entry.bytes as u64,
+                    timestamp: *ms as u64,
+                    ..Default::default()
+                };
+                match folder {
+                    Folder::Inbox => {
+                        msg_element.receive_id = Some(stream_id);
+                        msg_element.from_address = Some(entry.peer.clone());
+                    }
+                    Folder::Outbox => {
+                        msg_element.send_id = Some(stream_id);
+                        msg_element.to_address = Some(entry.peer.clone());
+                    }
+                }
+                msg_element
+            })
+            .collect())
+    }
+
+    async fn delete_message(
+        &self,
+        session_id: Option<&str>,
+        did_hash: &str,
+        message_hash: &str,
+    ) -> Result<(), MediatorError> {
+        self.data()
+            .delete_message(did_hash, message_hash)
+            .map_err(|reason| _delete_error(did_hash, message_hash, &reason))?;
+
+        debug!(
+            "{}did_hash({}) message_id({}) deleted",
+            session_id.map(|s| format!("{}: ", s)).unwrap_or_default(),
+            did_hash,
+            message_hash
+        );
+        Ok(())
+    }
+
+    async fn purge_messages(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: Folder,
+    ) -> Result<(usize, usize), MediatorError> {
+        let entries: Vec<(String, i64)> = self
+            .data()
+            .queue(&folder)
+            .get(did_hash)
+            .map(|stream| {
+                stream
+                    .entries
+                    .values()
+                    .map(|entry| (entry.msg_id.clone(), entry.bytes))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut purge_count: usize = 0;
+        let mut purge_bytes: usize = 0;
+        for (msg_id, bytes) in entries {
+            self.delete_message(Some(&session.session_id), did_hash, &msg_id)
+                .await?;
+            purge_count += 1;
+            purge_bytes += bytes as usize;
+        }
+
+        self.delete_folder_stream(session, did_hash, &folder)
+            .await?;
+
+        Ok((purge_count, purge_bytes))
+    }
+
+    async fn delete_folder_stream(
+        &self,
+        _session: &Session,
+        did_hash: &str,
+        folder: &Folder,
+    ) -> Result<(), MediatorError> {
+        self.data().queue_mut(folder).remove(did_hash);
+        Ok(())
+    }
+
+    async fn get_status_reply(
+        &self,
+        _session_id: &str,
+        did_hash: &str,
+    ) -> Result<MessagePickupStatusReply, MediatorError> {
+        let data = self.data();
+        let mut status = MessagePickupStatusReply::default();
+
+        if let Some(record) = data.dids.get(did_hash) {
+            status.message_count = record.receive_queue_count as u32;
+            status.total_bytes = record.receive_queue_bytes as u64;
+        }
+
+        if let Some(stream) = data.receive_q.get(did_hash) {
+            status.oldest_received_time = stream
+                .entries
+                .first_key_value()
+                .map(|((ms, _), _)| *ms as u64 / 1000);
+            status.newest_received_time = stream
+                .entries
+                .last_key_value()
+                .map(|((ms, _), _)| *ms as u64 / 1000);
+        }
+
+        status.live_delivery = data.global_streaming.contains_key(did_hash);
+
+        Ok(status)
+    }
+
+    // ************************************************************************
+    // Forwarding and scheduled deliveries
+
+    async fn forward_queue_enqueue(
+        &self,
+        _session_id: &str,
+        task: &ForwardTask,
+    ) -> Result<String, MediatorError> {
+        let mut data = self.data();
+        data.forward_last_id = _next_id(data.forward_last_id, _now_ms());
+
+        let mut task = task.clone();
+        task.stream_id = format!("{}-{}", data.forward_last_id.0, data.forward_last_id.1);
+        let stream_id = task.stream_id.clone();
+        data.forward_tasks.push_back(task);
+
+        debug!("Forward task ({}) queued", stream_id);
+        Ok(stream_id)
+    }
+
+    async fn get_forward_tasks_len(&self) -> Result<usize, MediatorError> {
+        Ok(self.data().forward_tasks.len())
+    }
+
+    async fn scheduled_delivery_add(
+        &self,
+        _session_id: &str,
+        deliver_at: u128,
+        delivery: &ScheduledDelivery,
+    ) -> Result<String, MediatorError> {
+        let id = Uuid::new_v4().to_string();
+
+        let mut data = self.data();
+        data.scheduled.insert((deliver_at, id.clone()));
+        data.scheduled_records
+            .insert(id.clone(), (deliver_at, delivery.clone()));
+
+        debug!("Scheduled delivery ({}) at ({})", id, deliver_at);
+        Ok(id)
+    }
+
+    async fn scheduled_delivery_due(
+        &self,
+        now: u128,
+        limit: usize,
+    ) -> Result<Vec<String>, MediatorError> {
+        Ok(self
+            .data()
+            .scheduled
+            .iter()
+            .take_while(|(deliver_at, _)| *deliver_at <= now)
+            .take(limit)
+            .map(|(_, id)| id.clone())
+            .collect())
+    }
+
+    async fn scheduled_delivery_claim(
+        &self,
+        id: &str,
+    ) -> Result<Option<ScheduledDelivery>, MediatorError> {
+        let mut data = self.data();
+        let Some((deliver_at, delivery)) = data.scheduled_records.remove(id) else {
+            return Ok(None);
+        };
+        data.scheduled.remove(&(deliver_at, id.to_string()));
+
+        Ok(Some(delivery))
+    }
+
+    // ************************************************************************
+    // Accounts
+
+    async fn account_exists(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        Ok(self.data().dids.contains_key(did_hash))
+    }
+
+    async fn account_get(&self, did_hash: &str) -> Result<Option<Account>, MediatorError> {
+        Ok(self.data().account(did_hash))
+    }
+
+    async fn account_add(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+        _queue_limit: Option<u32>,
+    ) -> Result<Account, MediatorError> {
+        debug!("Adding account ({}) to the mediator", did_hash);
+
+        let mut data = self.data();
+        data.known_dids.insert(did_hash.to_string());
+
+        let record = data.dids.entry(did_hash.to_string()).or_default();
+        record.send_queue_bytes = 0;
+        record.send_queue_count = 0;
+        record.receive_queue_bytes = 0;
+        record.receive_queue_count = 0;
+        record.role_type = Some(AccountType::Standard.into());
+        record.acls = Some(acls.to_hex_string());
+
+        Ok(Account {
+            did_hash: did_hash.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn account_remove(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        remove_outbox: bool,
+    ) -> Result<AccountRemoveResponse, MediatorError> {
+        let current = self.account_get(did_hash).await?;
+
+        if let Some(current) = &current {
+            if current._type == AccountType::Mediator {
+                return Err(MediatorError::InternalError(
+                    "NA".to_string(),
+                    "Cannot remove the mediator account".to_string(),
+                ));
+            } else if current._type == AccountType::RootAdmin {
+                return Err(MediatorError::InternalError(
+                    "NA".to_string(),
+                    "Cannot remove the root admin account".to_string(),
+                ));
+            }
+        }
+
+        // Block access to this account
+        let mut blocked_acl = MediatorACLSet::from_u64(0);
+        blocked_acl.set_blocked(true);
+        self.set_did_acl(did_hash, &blocked_acl).await?;
+
+        let mut removed = self.data().remove_did_records(did_hash);
+
+        if remove_outbox {
+            (removed.outbox_messages, _) = self
+                .purge_messages(session, did_hash, Folder::Outbox)
+                .await?;
+        } else {
+            self.delete_folder_stream(session, did_hash, &Folder::Outbox)
+                .await?;
+        }
+
+        (removed.inbox_messages, _) = self
+            .purge_messages(session, did_hash, Folder::Inbox)
+            .await?;
+
+        if let Some(current) = current {
+            if current._type.is_admin() {
+                self.strip_admin_accounts(vec![did_hash.to_string()])
+                    .await?;
+            }
+        }
+
+        let mut data = self.data();
+        data.known_dids.remove(did_hash);
+        data.dids.remove(did_hash);
+        data.access_lists.remove(did_hash);
+        data.mediation_keylists.remove(did_hash);
+
+        info!("Account removed: {:?}", removed);
+        Ok(removed)
+    }
+
+    async fn account_list(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAccountList, MediatorError> {
+        if limit > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "limit cannot exceed 100".to_string(),
+            ));
+        }
+
+        let data = self.data();
+        let accounts: Vec<Account> = data
+            .known_dids
+            .iter()
+            .skip(cursor as usize)
+            .take(limit as usize)
+            .map(|did_hash| {
+                data.account(did_hash).unwrap_or_else(|| Account {
+                    did_hash: did_hash.clone(),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        Ok(MediatorAccountList {
+            cursor: _next_cursor(cursor as u64, limit as u64, accounts.len()) as u32,
+            accounts,
+        })
+    }
+
+    async fn account_change_type(
+        &self,
+        did_hash: &str,
+        _type: &AccountType,
+    ) -> Result<(), MediatorError> {
+        self.data()
+            .dids
+            .entry(did_hash.to_string())
+            .or_default()
+            .role_type = Some(_type.to_owned().into());
+        Ok(())
+    }
+
+    async fn account_change_queue_limits(
+        &self,
+        did_hash: &str,
+        send_queue_limit: Option<i32>,
+        receive_queue_limit: Option<i32>,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the soft limit (no value stored)
+        let _value = |limit: i32| (limit != -2).then_some(limit);
+
+        let mut data = self.data();
+        let record = data.dids.entry(did_hash.to_string()).or_default();
+        if let Some(limit) = send_queue_limit {
+            record.send_queue_limit = _value(limit);
+        }
+        if let Some(limit) = receive_queue_limit {
+            record.receive_queue_limit = _value(limit);
+        }
+        Ok(())
+    }
+
+    async fn account_change_queue_bytes_limits(
+        &self,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the soft limit (no value stored)
+        let _value = |limit: i64| (limit != -2).then_some(limit);
+
+        let mut data = self.data();
+        let record = data.dids.entry(did_hash.to_string()).or_default();
+        if let Some(limit) = send_queue_bytes_limit {
+            record.send_queue_bytes_limit = _value(limit);
+        }
+        if let Some(limit) = receive_queue_bytes_limit {
+            record.receive_queue_bytes_limit = _value(limit);
+        }
+        Ok(())
+    }
+
+    async fn account_change_rate_limit(
+        &self,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the configured limits (no value stored)
+        self.data()
+            .dids
+            .entry(did_hash.to_string())
+            .or_default()
+            .rate_limit = (rate_limit != -2).then_some(rate_limit);
+        Ok(())
+    }
+
+    // ************************************************************************
+    // Admin accounts
+
+    async fn setup_admin_account(
+        &self,
+        admin_did_hash: &str,
+        admin_type: AccountType,
+        acls: &MediatorACLSet,
+    ) -> Result<(), MediatorError> {
+        if !self.account_exists(admin_did_hash).await? {
+            debug!("Admin account doesn't exist, creating: {}", admin_did_hash);
+            self.account_add(admin_did_hash, acls, None).await?;
+        }
+
+        {
+            let mut data = self.data();
+            data.admins.insert(admin_did_hash.to_string());
+            data.dids
+                .entry(admin_did_hash.to_string())
+                .or_default()
+                .role_type = Some(admin_type.into());
+        }
+
+        info!("Admin account successfully setup: {}", admin_did_hash);
+        Ok(())
+    }
+
+    async fn check_admin_account(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        let data = self.data();
+        let role_type = data
+            .dids
+            .get(did_hash)
+            .and_then(|record| record.role_type.clone())
+            .unwrap_or_default();
+
+        Ok(data.admins.contains(did_hash)
+            && matches!(
+                AccountType::from(role_type.as_str()),
+                AccountType::RootAdmin | AccountType::Admin
+            ))
+    }
+
+    async fn add_admin_accounts(
+        &self,
+        accounts: Vec<String>,
+        acls: &MediatorACLSet,
+    ) -> Result<usize, MediatorError> {
+        if accounts.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "Number of admin accounts being added exceeds 100".to_string(),
+            ));
+        }
+
+        for account in &accounts {
+            debug!("Adding Admin account: {}", account);
+            self.setup_admin_account(account, AccountType::Admin, acls)
+                .await?;
+        }
+
+        Ok(accounts.len())
+    }
+
+    async fn strip_admin_accounts(&self, accounts: Vec<String>) -> Result<i32, MediatorError> {
+        if accounts.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "Number of admin accounts being removed exceeds 100".to_string(),
+            ));
+        }
+
+        let mut data = self.data();
+        let mut removed = 0;
+        for account in &accounts {
+            debug!("Removing Admin account: {}", account);
+            if data.admins.remove(account) {
+                removed += 1;
+            }
+            data.dids.entry(account.clone()).or_default().role_type =
+                Some(AccountType::Standard.into());
+        }
+
+        Ok(removed)
+    }
+
+    async fn list_admin_accounts(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAdminList, MediatorError> {
+        if limit > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "limit cannot exceed 100".to_string(),
+            ));
+        }
+
+        let data = self.data();
+        let accounts: Vec<AdminAccount> = data
+            .admins
+            .iter()
+            .skip(cursor as usize)
+            .take(limit as usize)
+            .map(|did_hash| AdminAccount {
+                did_hash: did_hash.clone(),
+                _type: AccountType::from(
+                    data.dids
+                        .get(did_hash)
+                        .and_then(|record| record.role_type.as_deref())
+                        .unwrap_or_default(),
+                ),
+            })
+            .collect();
+
+        Ok(MediatorAdminList {
+            cursor: _next_cursor(cursor as u64, limit as u64, accounts.len()) as u32,
+            accounts,
+        })
+    }
+
+    // ************************************************************************
+    // Audit log
+
+    async fn audit_log_add(
+        &self,
+        entry: &AuditLogEntry,
+        max_entries: usize,
+    ) -> Result<(), MediatorError> {
+        let mut data = self.data();
+        data.audit_log_last_id += 1;
+        let id = data.audit_log_last_id.to_string();
+        data.audit_log.push_back(AuditLogEntry {
+            id,
+            ..entry.clone()
+        });
+        while data.audit_log.len() > max_entries {
+            data.audit_log.pop_front();
+        }
+        Ok(())
+    }
+
+    async fn audit_log_list(
+        &self,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &AuditLogFilter,
+    ) -> Result<MediatorAuditLog, MediatorError> {
+        let cursor = match cursor {
+            Some(cursor) => cursor.parse::<u64>().map_err(|_| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!("Invalid audit log cursor ({})", cursor),
+                )
+            })?,
+            None => u64::MAX,
+        };
+
+        let data = self.data();
+        let entries = data
+            .audit_log
+            .iter()
+            .rev()
+            .filter(|entry| entry.id.parse::<u64>().unwrap_or(0) < cursor)
+            .cloned();
+        Ok(audit_log_page(entries, limit, filter))
+    }
+
+    // ************************************************************************
+    // Push notification targets
+
+    async fn push_target_set(
+        &self,
+        did_hash: &str,
+        target: &PushTarget,
+    ) -> Result<(), MediatorError> {
+        self.data()
+            .dids
+            .entry(did_hash.to_string())
+            .or_default()
+            .push_target = Some(target.clone());
+
+        Ok(())
+    }
+
+    async fn push_target_get(&self, did_hash: &str) -> Result<Option<PushTarget>, MediatorError> {
+        Ok(self
+            .data()
+            .dids
+            .get(did_hash)
+            .and_then(|record| record.push_target.clone()))
+    }
+
+    async fn push_target_remove(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        Ok(self
+            .data()
+            .dids
+            .get_mut(did_hash)
+            .and_then(|record| record.push_target.take())
+            .is_some())
+    }
+
+    // ************************************************************************
+    // ACLs
+
+    async fn set_did_acl(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+    ) -> Result<MediatorACLSet, MediatorError> {
+        self.data()
+            .dids
+            .entry(did_hash.to_string())
+            .or_default()
+            .acls = Some(acls.to_hex_string());
+
+        Ok(acls.to_owned())
+    }
+
+    async fn get_did_acl(&self, did_hash: &str) -> Result<Option<MediatorACLSet>, MediatorError> {
+        let acl = self
+            .data()
+            .dids
+            .get(did_hash)
+            .and_then(|record| record.acls.clone());
+
+        acl.map(|acl| {
+            MediatorACLSet::from_hex_string(&acl)
+                .map_err(|e| MediatorError::InternalError(did_hash.into(), e.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn get_did_acls(
+        &self,
+        dids: &[String],
+        mediator_acl_mode: AccessListModeType,
+    ) -> Result<MediatorACLGetResponse, MediatorError> {
+        if dids.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "# of DIDs cannot exceed 100".to_string(),
+            ));
+        }
+
+        let mut acl_response = MediatorACLGetResponse {
+            acl_response: vec![],
+            mediator_acl_mode,
+        };
+        for did_hash in dids {
+            if let Some(acls) = self.get_did_acl(did_hash).await? {
+                acl_response.acl_response.push(MediatorACLExpanded {
+                    did_hash: did_hash.clone(),
+                    acl_value: acls.to_hex_string(),
+                    acls,
+                });
+            }
+        }
+
+        Ok(acl_response)
+    }
+
+    // ************************************************************************
+    // Access lists
+
+    async fn access_list_allowed(
+        &self,
+        to_hash: &str,
+        from_hash: Option<String>,
+    ) -> Result<bool, MediatorError> {
+        let Some(acl) = self.get_did_acl(to_hash).await? else {
+            debug!("ACL not found for DID: {}", to_hash);
+            return Ok(false);
+        };
+
+        let Some(from_hash) = from_hash else {
+            // Anonymous Message
+            return Ok(acl.get_anon_receive().0);
+        };
+
+        let exists = self
+            .data()
+            .access_lists
+            .get(to_hash)
+            .is_some_and(|list| list.contains(&from_hash));
+
+        if acl.get_access_list_mode().0 == AccessListModeType::ExplicitAllow {
+            Ok(exists)
+        } else {
+            Ok(!exists)
+        }
+    }
+
+    async fn access_list_list(
+        &self,
+        did_hash: &str,
+        cursor: u64,
+    ) -> Result<MediatorAccessListListResponse, MediatorError> {
+        let hashes: Vec<String> = self
+            .data()
+            .access_lists
+            .get(did_hash)
+            .map(|list| {
+                list.iter()
+                    .skip(cursor as usize)
+                    .take(100)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(MediatorAccessListListResponse {
+            cursor: Some(_next_cursor(cursor, 100, hashes.len())),
+            did_hashes: hashes,
+        })
+    }
+
+    async fn access_list_count(&self, did_hash: &str) -> Result<usize, MediatorError> {
+        Ok(self
+            .data()
+            .access_lists
+            .get(did_hash)
+            .map(|list| list.len())
+            .unwrap_or(0))
+    }
+
+    async fn access_list_add(
+        &self,
+        access_list_limit: usize,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListAddResponse, MediatorError> {
+        let mut data = self.data();
+        let list = data.access_lists.entry(did_hash.to_string()).or_default();
+
+        let space = access_list_limit.saturating_sub(list.len());
+        let truncated = hashes.len() > space;
+        let hashes = &hashes[..hashes.len().min(space)];
+        list.extend(hashes.iter().cloned());
+
+        Ok(MediatorAccessListAddResponse {
+            did_hashes: hashes.to_vec(),
+            truncated,
+        })
+    }
+
+    async fn access_list_remove(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<usize, MediatorError> {
+        let mut data = self.data();
+        let Some(list) = data.access_lists.get_mut(did_hash) else {
+            return Ok(0);
+        };
+
+        Ok(hashes.iter().filter(|hash| list.remove(*hash)).count())
+    }
+
+    async fn access_list_clear(&self, did_hash: &str) -> Result<(), MediatorError> {
+        self.data().access_lists.remove(did_hash);
+        Ok(())
+    }
+
+    async fn access_list_get(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListGetResponse, MediatorError> {
+        let data = self.data();
+        let did_hashes = match data.access_lists.get(did_hash) {
+            Some(list) => hashes
+                .iter()
+                .filter(|hash| list.contains(*hash))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        };
+
+        Ok(MediatorAccessListGetResponse { did_hashes })
+    }
+
+    // ************************************************************************
+    // Coordinate Mediation keylists
+
+    async fn keylist_add(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        Ok(self
+            .data()
+            .mediation_keylists
+            .entry(did_hash.to_string())
+            .or_default()
+            .insert(recipient_did.to_string()))
+    }
+
+    async fn keylist_remove(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        Ok(self
+            .data()
+            .mediation_keylists
+            .get_mut(did_hash)
+            .is_some_and(|keylist| keylist.remove(recipient_did)))
+    }
+
+    async fn keylist_list(
+        &self,
+        did_hash: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), MediatorError> {
+        let data = self.data();
+        let Some(keylist) = data.mediation_keylists.get(did_hash) else {
+            return Ok((Vec::new(), 0));
+        };
+
+        Ok((
+            keylist.iter().skip(offset).take(limit).cloned().collect(),
+            keylist.len(),
+        ))
+    }
+
+    // ************************************************************************
+    // Sessions
+
+    async fn create_session(&self, session: &Session) -> Result<(), MediatorError> {
+        let mut data = self.data();
+        data.sessions.insert(
+            session.session_id.clone(),
+            SessionRecord {
+                challenge: session.challenge.clone(),
+                state: session.state.to_string(),
+                did: session.did.clone(),
+                expires_at: _now_secs() + 900,
+                linked_dids: Vec::new(),
+            },
+        );
+        data.incr_global("SESSIONS_CREATED", 1);
+
+        debug!("Session created: {:?}", session);
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str, did: &str) -> Result<Session, MediatorError> {
+        let data = self.data();
+
+        let Some(session) = data
+            .sessions
+            .get(session_id)
+            .filter(|session| session.expires_at > _now_secs())
+        else {
+            warn!(
+                "{}: No challenge found when retrieving session({})!",
+                session_id, session_id
+            );
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No challenge found when retrieving session!".into(),
+            ));
+        };
+
+        let record = data.dids.get(&digest(did));
+        let Some(role_type) = record.and_then(|record| record.role_type.as_deref()) else {
+            warn!("{}: Error parsing role_type!", session_id);
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No role_type found when retrieving session!".into(),
+            ));
+        };
+        let Some(acls) = record
+            .and_then(|record| record.acls.as_deref())
+            .and_then(|acls| u64::from_str_radix(acls, 16).ok())
+        else {
+            warn!("{}: Error parsing acls!", session_id);
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No ACL found when retrieving session!".into(),
+            ));
+        };
+
+        Ok(Session {
+            session_id: session_id.into(),
+            challenge: session.challenge.clone(),
+            state: SessionState::try_from(&session.state)?,
+            did_hash: digest(&session.did),
+            did: session.did.clone(),
+            account_type: AccountType::from(role_type),
+            acls: MediatorACLSet::from_u64(acls),
+            linked_dids: session.linked_dids.clone(),
+            ..Default::default()
+        })
+    }
+
+    async fn update_session_authenticated(
+        &self,
+        old_session_id: &str,
+        new_session_id: &str,
+        did_hash: &str,
+    ) -> Result<(), MediatorError> {
+        let mut data = self.data();
+
+        let Some(mut session) = data
+            .sessions
+            .remove(old_session_id)
+            .filter(|session| session.expires_at > _now_secs())
+        else {
+            error!("{}: update_session_authenticated failed", old_session_id);
+            return Err(MediatorError::DatabaseError(
+                old_session_id.into(),
+                "update_session_authenticated failed. Reason: session not found".into(),
+            ));
+        };
+
+        session.state = SessionState::Authenticated.to_string();
+        session.expires_at = _now_secs() + SESSION_EXPIRY;
+        data.sessions.insert(new_session_id.to_string(), session);
+        data.incr_global("SESSIONS_SUCCESS", 1);
+        data.known_dids.insert(did_hash.to_string());
+
+        Ok(())
+    }
+
+    async fn session_link_challenge(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        challenge: &str,
+    ) -> Result<(), MediatorError> {
+        self.data().link_challenges.insert(
+            (session_id.to_string(), did_hash.to_string()),
+            (challenge.to_string(), _now_secs() + 900),
+        );
+        Ok(())
+    }
+
+    async fn session_link_did(
+        &self,
+        session_id: &str,
+        did: &str,
+        challenge: &str,
+    ) -> Result<bool, MediatorError> {
+        let mut data = self.data();
+
+        let matched = data
+            .link_challenges
+            .remove(&(session_id.to_string(), digest(did)))
+            .is_some_and(|(stored, expires_at)| stored == challenge && expires_at > _now_secs());
+        if !matched {
+            return Ok(false);
+        }
+
+        let Some(session) = data.sessions.get_mut(session_id) else {
+            return Ok(false);
+        };
+        if !session.linked_dids.iter().any(|linked| linked == did) {
+            session.linked_dids.push(did.to_string());
+        }
+
+        Ok(true)
+    }
+
+    // ************************************************************************
+    // OOB Discovery
+
+    async fn oob_discovery_store(
+        &self,
+        did_hash: &str,
+        invite: &Message,
+        oob_invite_ttl: u64,
+    ) -> Result<String, MediatorError> {
+        let now = _now_secs() as u64;
+        let expire_at = match invite.expires_time {
+            Some(expiry) if expiry <= now + oob_invite_ttl => expiry,
+            _ => now + oob_invite_ttl,
+        };
+
+        let base64_invite = match serde_json::to_string(invite) {
+            Ok(msg) => BASE64_URL_SAFE_NO_PAD.encode(msg),
+            Err(err) => {
+                error!("serializing error on Message. {}", err);
+                return Err(MediatorError::InternalError(
+                    "NA".into(),
+                    format!("serializing error on Message. {}", err),
+                ));
+            }
+        };
+        let invite_hash = digest(&base64_invite);
+
+        let mut data = self.data();
+        data.oob_invites
+            .insert(invite_hash.clone(), (base64_invite, expire_at));
+        data.oob_invite_creators
+            .entry(did_hash.to_string())
+            .or_default()
+            .insert(invite_hash.clone());
+        data.incr_global("OOB_INVITES_CREATED", 1);
+
+        info!("OOB Invitation ID({}) created", invite_hash);
+        Ok(invite_hash)
+    }
+
+    async fn oob_discovery_get(&self, oob_id: &str) -> Result<Option<String>, MediatorError> {
+        let mut data = self.data();
+        let now = _now_secs() as u64;
+        let invitation = data
+            .oob_invites
+            .get(oob_id)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .map(|(invite, _)| invite.clone());
+        data.incr_global("OOB_INVITES_CLAIMED", 1);
+
+        Ok(invitation)
+    }
+
+    async fn oob_discovery_delete(&self, oob_id: &str) -> Result<bool, MediatorError> {
+        Ok(self.data().oob_invites.remove(oob_id).is_some())
+    }
+
+    // ************************************************************************
+    // Rate limiting
+
+    async fn rate_limit_take(
+        &self,
+        key: &str,
+        did_hash: Option<&str>,
+        per_minute: u32,
+    ) -> Result<Option<u64>, MediatorError> {
+        let mut data = self.data();
+        let account_limit = did_hash
+            .and_then(|did_hash| data.dids.get(did_hash))
+            .and_then(|record| record.rate_limit);
+        let Some(limit) = effective_limit(per_minute, account_limit) else {
+            return Ok(None);
+        };
+
+        let now = _now_ms() as u64;
+        // Drop buckets that are full again, as Redis does with the bucket expiry
+        data.rate_limits.retain(|_, bucket| !bucket.is_idle(now));
+
+        let retry_after = data
+            .rate_limits
+            .entry(key.to_string())
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .take(limit, now);
+        if retry_after.is_some() {
+            data.incr_global("RATE_LIMITED", 1);
+        }
+        Ok(retry_after)
+    }
+
+    // ************************************************************************
+    // Live streaming
+
+    async fn streaming_clean_start(&self, uuid: &str) -> Result<(), MediatorError> {
+        let mut data = self.data();
+        let sessions = data.streaming_sessions.remove(uuid).unwrap_or_default();
+        for did_hash in &sessions {
+            if let Some(streams) = data.global_streaming.get_mut(did_hash) {
+                streams.remove(uuid);
+                if streams.is_empty() {
+                    data.global_streaming.remove(did_hash);
+                }
+            }
+        }
+
+        info!(
+            "clean_start_streaming() cleaned {} sessions",
+            sessions.len()
+        );
+        Ok(())
+    }
+
+    async fn streaming_is_client_live(&self, did_hash: &str, force_delivery: bool) -> Vec<String> {
+        match self.data().global_streaming.get(did_hash) {
+            Some(streams) => streams
+                .iter()
+                .filter(|(_, live)| **live || force_delivery)
+                .map(|(uuid, _)| uuid.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    async fn streaming_publish_message(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+        message: &str,
+        force_delivery: bool,
+    ) -> Result<(), MediatorError> {
+        let mut channels = self.channels();
+
+        if let Some(subscribers) = channels.get_mut(stream_uuid) {
+            let record = PubSubRecord {
+                did_hash: did_hash.to_string(),
+                message: message.to_string(),
+                force_delivery,
+            };
+            // Drop subscribers that have gone away, same as a PUBLISH with no subscribers
+            subscribers.retain(|subscriber| subscriber.send(record.clone()).is_ok());
+            debug!(
+                "published message to channel(CHANNEL:{}) for did_hash({})",
+                stream_uuid, did_hash
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn streaming_subscribe(&self, stream_uuid: &str) -> Result<PubSubStream, MediatorError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.channels()
+            .entry(stream_uuid.to_string())
+            .or_default()
+            .push(tx);
+
+        info!("Subscribed to channel: CHANNEL:{}", stream_uuid);
+        Ok(Box::pin(UnboundedReceiverStream::new(rx)))
+    }
+
+    async fn streaming_register_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self.data()
+            .streaming_sessions
+            .entry(stream_uuid.to_string())
+            .or_default()
+            .insert(did_hash.to_string());
+        self._streaming_set(did_hash, stream_uuid, false);
+
+        debug!("did_hash({}) registered to ({})", did_hash, stream_uuid);
+        Ok(())
+    }
+
+    async fn streaming_start_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self._streaming_set(did_hash, stream_uuid, true);
+        Ok(())
+    }
+
+    async fn streaming_stop_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self._streaming_set(did_hash, stream_uuid, false);
+        Ok(())
+    }
+
+    async fn streaming_deregister_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        let mut data = self.data();
+        if let Some(sessions) = data.streaming_sessions.get_mut(stream_uuid) {
+            sessions.remove(did_hash);
+        }
+        if let Some(streams) = data.global_streaming.get_mut(did_hash) {
+            streams.remove(stream_uuid);
+            if streams.is_empty() {
+                data.global_streaming.remove(did_hash);
+            }
+        }
+
+        debug!("did_hash({}) deregistered from ({})", did_hash, stream_uuid);
+        Ok(())
+    }
+
+    // ************************************************************************
+    // Statistics
+
+    async fn get_db_metadata(&self) -> Result<MetadataStats, MediatorError> {
+        let data = self.data();
+        let get = |key: &str| data.global.get(key).copied().unwrap_or(0);
+
+        Ok(MetadataStats {
+            received_bytes: get("RECEIVED_BYTES"),
+            sent_bytes: get("SENT_BYTES"),
+            deleted_bytes: get("DELETED_BYTES"),
+            received_count: get("RECEIVED_COUNT"),
+            sent_count: get("SENT_COUNT"),
+            deleted_count: get("DELETED_COUNT"),
+            websocket_open: get("WEBSOCKET_OPEN"),
+            websocket_close: get("WEBSOCKET_CLOSE"),
+            sessions_created: get("SESSIONS_CREATED"),
+            sessions_success: get("SESSIONS_SUCCESS"),
+            oob_invites_created: get("OOB_INVITES_CREATED"),
+            oob_invites_claimed: get("OOB_INVITES_CLAIMED"),
+            rate_limited: get("RATE_LIMITED"),
+        })
+    }
+
+    async fn get_expiry_backlog(&self, now: u64) -> Result<usize, MediatorError> {
+        Ok(self.data().msg_expiry.range(..=now).count())
+    }
+
+    async fn update_send_stats(&self, sent_bytes: i64) -> Result<(), MediatorError> {
+        let mut data = self.data();
+        data.incr_global("SENT_BYTES", sent_bytes);
+        data.incr_global("SENT_COUNT", 1);
+        Ok(())
+    }
+
+    async fn global_stats_increment_websocket_open(&self) -> Result<(), MediatorError> {
+        self.data().incr_global("WEBSOCKET_OPEN", 1);
+        Ok(())
+    }
+
+    async fn global_stats_increment_websocket_close(&self) -> Result<(), MediatorError> {
+        self.data().incr_global("WEBSOCKET_CLOSE", 1);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use affinidi_messaging_sdk::{
+        messages::list::ListMessagesOptions, protocols::mediator::administration::AuditAction,
+    };
+    use serde_json::json;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_queue_counts_and_expiry() {
+        let store = MemoryStore::new();
+        let to_hash = digest("did:example:bob");
+        let from_hash = digest("did:example:alice");
+
+        let msg_id = store
+            .store_message(
+                "test",
+                "message one",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message two",
+                "did:example:bob",
+                Some("did:example:alice"),
+                1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.receive_queue_count, 2);
+        assert_eq!(bob.receive_queue_bytes, 22);
+        let alice = store.account_get(&from_hash).await.unwrap().unwrap();
+        assert_eq!(alice.send_queue_count, 2);
+
+        // Stream IDs are unique and ordered
+        let inbox = store
+            .list_messages(&to_hash, Folder::Inbox, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(inbox.len(), 2);
+        assert_ne!(inbox[0].receive_id, inbox[1].receive_id);
+
+        // Only the sender or recipient can delete
+        assert!(
+            store
+                .delete_message(None, "someone_else", &msg_id)
+                .await
+                .is_err()
+        );
+
+        // Second message has expired
+        assert_eq!(store.expire_messages(1).await.unwrap(), 1);
+        let status = store.get_status_reply("test", &to_hash).await.unwrap();
+        assert_eq!(status.message_count, 1);
+        assert_eq!(status.total_bytes, 11);
+
+        let fetched = store
+            .fetch_messages(
+                "test",
+                &to_hash,
+                &FetchOptions {
+                    delete_policy: FetchDeletePolicy::Optimistic,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(fetched.success.len(), 1);
+        assert_eq!(fetched.success[0].msg.as_deref(), Some("message one"));
+
+        let alice = store.account_get(&from_hash).await.unwrap().unwrap();
+        assert_eq!(alice.send_queue_count, 0);
+        assert_eq!(alice.send_queue_bytes, 0);
+
+        let stats = store.get_db_metadata().await.unwrap();
+        assert_eq!(stats.received_count, 2);
+        assert_eq!(stats.deleted_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_queue_byte_quota() {
+        let store = MemoryStore::new();
+        let to_hash = digest("did:example:bob");
+        let from_hash = digest("did:example:alice");
+        let quota = QueueByteQuota {
+            send: -1,
+            receive: 20,
+        };
+
+        // 11 bytes each, the second message would exceed the receive quota
+        store
+            .store_message(
+                "test",
+                "message one",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .store_message(
+                    "test",
+                    "message two",
+                    "did:example:bob",
+                    Some("did:example:alice"),
+                    u64::MAX >> 1,
+                    None,
+                    &quota,
+                )
+                .await,
+            Err(MediatorError::ServiceLimitError(..))
+        ));
+
+        // Nothing is written for a rejected message
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.receive_queue_count, 1);
+        assert_eq!(bob.receive_queue_bytes, 11);
+
+        // The account's own quota replaces the default
+        store
+            .account_change_queue_bytes_limits(&to_hash, None, Some(-1))
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message two",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.queue_receive_bytes_limit, Some(-1));
+
+        // Send quota
+        store
+            .account_change_queue_bytes_limits(&from_hash, Some(22), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .store_message(
+                    "test",
+                    "message three",
+                    "did:example:bob",
+                    Some("did:example:alice"),
+                    u64::MAX >> 1,
+                    None,
+                    &QueueByteQuota::UNLIMITED,
+                )
+                .await,
+            Err(MediatorError::ServiceLimitError(..))
+        ));
+
+        // Anonymous messages only count against the receiver
+        store
+            .store_message(
+                "test",
+                "message three",
+                "did:example:bob",
+                None,
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+
+        // -2 resets to the default quota
+        store
+            .account_change_queue_bytes_limits(&to_hash, None, Some(-2))
+            .await
+            .unwrap();
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.queue_receive_bytes_limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_list_messages_filtered() {
+        let store = MemoryStore::new();
+        let to_hash = digest("did:example:bob");
+        let alice_hash = digest("did:example:alice");
+        let carol_hash = digest("did:example:carol");
+
+        for (from, message) in [
+            ("did:example:alice", "one"),
+            ("did:example:carol", "two"),
+            ("did:example:alice", "three"),
+            ("did:example:carol", "four"),
+            ("did:example:alice", "five, the longest"),
+        ] {
+            store
+                .store_message(
+                    "test",
+                    message,
+                    "did:example:bob",
+                    Some(from),
+                    u64::MAX >> 1,
+                    None,
+                    &QueueByteQuota::UNLIMITED,
+                )
+                .await
+                .unwrap();
+        }
+
+        // Paging through a single sender
+        let mut options = ListMessagesOptions {
+            from_did_hash: Some(alice_hash.clone()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = store
+            .list_messages_filtered(&to_hash, Folder::Inbox, &options, 100)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert!(
+            page.iter()
+                .all(|m| m.from_address == Some(alice_hash.clone()))
+        );
+        assert!(page[0].next_cursor.is_none());
+        assert_eq!(page[1].next_cursor, page[1].receive_id);
+
+        options.cursor = page[1].next_cursor.clone();
+        let page = store
+            .list_messages_filtered(&to_hash, Folder::Inbox, &options, 100)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].size, 17);
+        assert!(page[0].next_cursor.is_none());
+
+        // The mediator limit caps the requested limit
+        let page = store
+            .list_messages_filtered(
+                &to_hash,
+                Folder::Inbox,
+                &ListMessagesOptions {
+                    limit: Some(10),
+                    ..Default::default()
+                },
+                3,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 3);
+        assert!(page[2].next_cursor.is_some());
+
+        // A filter that matches nothing scans to the end
+        let all = store
+            .list_messages(&to_hash, Folder::Inbox, None, 100)
+            .await
+            .unwrap();
+        let mut options = ListMessagesOptions {
+            from_did_hash: Some(digest("did:example:dave")),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = store
+            .list_messages_filtered(&to_hash, Folder::Inbox, &options, 100)
+            .await
+            .unwrap();
+        assert!(page.is_empty());
+
+        // Time range and size
+        let page = store
+            .list_messages_filtered(
+                &to_hash,
+                Folder::Inbox,
+                &ListMessagesOptions {
+                    since: all[1].receive_id.clone(),
+                    until: all[3].receive_id.clone(),
+                    max_size: Some(4),
+                    ..Default::default()
+                },
+                100,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.iter().map(|m| m.size).collect::<Vec<_>>(), vec![3, 4]);
+
+        // Outbox elements only carry the recipient, the sender is the owner
+        let page = store
+            .list_messages_filtered(
+                &carol_hash,
+                Folder::Outbox,
+                &ListMessagesOptions {
+                    from_did_hash: Some(carol_hash.clone()),
+                    to_did_hash: Some(to_hash.clone()),
+                    ..Default::default()
+                },
+                100,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        let page = store
+            .list_messages_filtered(
+                &carol_hash,
+                Folder::Outbox,
+                &ListMessagesOptions {
+                    from_did_hash: Some(alice_hash),
+                    ..Default::default()
+                },
+                100,
+            )
+            .await
+            .unwrap();
+        assert!(page.is_empty());
+
+        // Scanning stops after a few pages worth once something matched, the cursor is on the match
+        for from in std::iter::once("did:example:dave")
+            .chain(std::iter::repeat_n("did:example:carol", 8))
+            .chain(std::iter::once("did:example:dave"))
+        {
+            store
+                .store_message(
+                    "test",
+                    "filler",
+                    "did:example:bob",
+                    Some(from),
+                    u64::MAX >> 1,
+                    None,
+                    &QueueByteQuota::UNLIMITED,
+                )
+                .await
+                .unwrap();
+        }
+        let all = store
+            .list_messages(&to_hash, Folder::Inbox, None, 100)
+            .await
+            .unwrap();
+        options.limit = Some(2);
+        let page = store
+            .list_messages_filtered(&to_hash, Folder::Inbox, &options, 100)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].receive_id, all[5].receive_id);
+        assert_eq!(page[0].next_cursor, all[7].receive_id);
+
+        options.cursor = page[0].next_cursor.clone();
+        let page = store
+            .list_messages_filtered(&to_hash, Folder::Inbox, &options, 100)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].receive_id, all[14].receive_id);
+        assert!(page[0].next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_account_remove() {
+        let store = MemoryStore::new();
+        let to_hash = digest("did:example:bob");
+        let session = Session {
+            session_id: "test".into(),
+            did: "did:example:bob".into(),
+            did_hash: to_hash.clone(),
+            ..Default::default()
+        };
+
+        store.create_session(&session).await.unwrap();
+        store
+            .streaming_register_client(&to_hash, "uuid")
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message",
+                "did:example:bob",
+                None,
+                u64::MAX >> 1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+        store
+            .forward_queue_enqueue(
+                "test",
+                &ForwardTask::new("{}", "did:example:remote", Some("did:example:bob"), None, 0),
+            )
+            .await
+            .unwrap();
+        store
+            .scheduled_delivery_add(
+                "test",
+                u128::MAX,
+                &ScheduledDelivery {
+                    message: "{}".into(),
+                    to_did: "did:example:bob".into(),
+                    to_did_hash: to_hash.clone(),
+                    from_did: None,
+                    msg_id: None,
+                    expires_at: u64::MAX >> 1,
+                    ephemeral: false,
+                    remote: false,
+                    signed_by: None,
+                },
+            )
+            .await
+            .unwrap();
+        let oob_id = store
+            .oob_discovery_store(
+                &to_hash,
+                &Message::build("id".into(), "type".into(), json!({})).finalize(),
+                60,
+            )
+            .await
+            .unwrap();
+
+        let removed = store
+            .account_remove(&session, &to_hash, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            removed,
+            AccountRemoveResponse {
+                did_hash: to_hash.clone(),
+                inbox_messages: 1,
+                outbox_messages: 0,
+                forward_tasks: 1,
+                scheduled_deliveries: 1,
+                oob_invites: 1,
+                sessions: 1,
+                streaming_sessions: 1,
+            }
+        );
+        assert!(store.account_get(&to_hash).await.unwrap().is_none());
+        assert!(store.get_session("test", "did:example:bob").await.is_err());
+        assert_eq!(store.oob_discovery_get(&oob_id).await.unwrap(), None);
+        assert_eq!(store.get_forward_tasks_len().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_session_link_did() {
+        let store = MemoryStore::new();
+        let session = Session {
+            session_id: "test".into(),
+            did: "did:example:alice".into(),
+            state: SessionState::ChallengeSent,
+            ..Default::default()
+        };
+        let linked_hash = digest("did:example:bob");
+        store
+            .account_add(
+                &digest("did:example:alice"),
+                &MediatorACLSet::default(),
+                None,
+            )
+            .await
+            .unwrap();
+        store.create_session(&session).await.unwrap();
+
+        // Challenges can only be used once
+        store
+            .session_link_challenge("test", &linked_hash, "challenge")
+            .await
+            .unwrap();
+        assert!(
+            !store
+                .session_link_did("test", "did:example:bob", "wrong")
+                .await
+                .unwrap()
+        );
+        assert!(
+            !store
+                .session_link_did("test", "did:example:bob", "challenge")
+                .await
+                .unwrap()
+        );
+
+        store
+            .session_link_challenge("test", &linked_hash, "challenge")
+            .await
+            .unwrap();
+        assert!(
+            store
+                .session_link_did("test", "did:example:bob", "challenge")
+                .await
+                .unwrap()
+        );
+        let session = store
+            .get_session("test", "did:example:alice")
+            .await
+            .unwrap();
+        assert_eq!(session.linked_dids, vec!["did:example:bob".to_string()]);
+        assert!(session.owns_did_hash(&linked_hash));
+        assert_eq!(session.did_hashes().len(), 2);
+
+        // Removing a linked DID removes the sessions it is linked to
+        store
+            .account_remove(&session, &linked_hash, false)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .get_session("test", "did:example:alice")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keylist() {
+        let store = MemoryStore::new();
+
+        assert!(store.keylist_add("hash", "did:example:b").await.unwrap());
+        assert!(store.keylist_add("hash", "did:example:a").await.unwrap());
+        assert!(!store.keylist_add("hash", "did:example:a").await.unwrap());
+        assert_eq!(
+            store.keylist_list("hash", 1, 10).await.unwrap(),
+            (vec!["did:example:b".to_string()], 2)
+        );
+
+        assert!(store.keylist_remove("hash", "did:example:a").await.unwrap());
+        assert!(!store.keylist_remove("hash", "did:example:a").await.unwrap());
+        assert_eq!(store.keylist_list("other", 0, 10).await.unwrap().1, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let store = MemoryStore::new();
+
+        assert!(
+            store
+                .rate_limit_take("ip", None, 2)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .rate_limit_take("ip", None, 2)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let retry_after = store.rate_limit_take("ip", None, 2).await.unwrap();
+        assert!(retry_after.is_some_and(|ms| ms > 0 && ms <= 30_000));
+        // 0 disables the limit
+        assert!(
+            store
+                .rate_limit_take("ip", None, 0)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Account overrides
+        store
+            .account_add("hash", &MediatorACLSet::default(), None)
+            .await
+            .unwrap();
+        store.account_change_rate_limit("hash", 1).await.unwrap();
+        assert_eq!(
+            store.account_get("hash").await.unwrap().unwrap().rate_limit,
+            Some(1)
+        );
+        assert!(
+            store
+                .rate_limit_take("did", Some("hash"), 100)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .rate_limit_take("did", Some("hash"), 100)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        store.account_change_rate_limit("hash", -1).await.unwrap();
+        assert!(
+            store
+                .rate_limit_take("did", Some("hash"), 100)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        store.account_change_rate_limit("hash", -2).await.unwrap();
+        assert!(
+            store
+                .account_get("hash")
+                .await
+                .unwrap()
+                .unwrap()
+                .rate_limit
+                .is_none()
+        );
+        assert_eq!(store.get_db_metadata().await.unwrap().rate_limited, 2);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let store = MemoryStore::new();
+
+        for action in [
+            AuditAction::AccountAdd,
+            AuditAction::AclSet,
+            AuditAction::AccountAdd,
+            AuditAction::AclSet,
+        ] {
+            store
+                .audit_log_add(
+                    &AuditLogEntry {
+                        id: String::new(),
+                        timestamp: 0,
+                        actor: "admin".into(),
+                        action,
+                        target: "target".into(),
+                        acls_before: None,
+                        acls_after: Some("00".into()),
+                        detail: None,
+                    },
+                    3,
+                )
+                .await
+                .unwrap();
+        }
+
+        // Capped to the newest 3 entries
+        let page = store
+            .audit_log_list(None, 2, &AuditLogFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].action, AuditAction::AclSet);
+        assert_eq!(page.entries[0].acls_after.as_deref(), Some("00"));
+        let page = store
+            .audit_log_list(page.cursor.as_deref(), 2, &AuditLogFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert!(page.cursor.is_none());
+
+        let filter = AuditLogFilter {
+            action: Some(AuditAction::AccountAdd),
+            ..Default::default()
+        };
+        let page = store.audit_log_list(None, 10, &filter).await.unwrap();
+        assert_eq!(page.entries.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pubsub() {
+        let store = MemoryStore::new();
+        let mut stream = store.streaming_subscribe("uuid").await.unwrap();
+
+        store
+            .streaming_register_client("did_hash", "uuid")
+            .await
+            .unwrap();
+        assert!(
+            store
+                .streaming_is_client_live("did_hash", false)
+                .await
+                .is_empty()
+        );
+        store
+            .streaming_start_live("did_hash", "uuid")
+            .await
+            .unwrap();
+        assert_eq!(
+            store.streaming_is_client_live("did_hash", false).await,
+            vec!["uuid".to_string()]
+        );
+
+        // Same DID connected to a second streaming service
+        store
+            .streaming_register_client("did_hash", "uuid2")
+            .await
+            .unwrap();
+        assert_eq!(
+            store.streaming_is_client_live("did_hash", true).await.len(),
+            2
+        );
+        store
+            .streaming_deregister_client("did_hash", "uuid2")
+            .await
+            .unwrap();
+
+        store
+            .streaming_publish_message("did_hash", "uuid", "hello", false)
+            .await
+            .unwrap();
+        let record = stream.next().await.unwrap();
+        assert_eq!(record.did_hash, "did_hash");
+        assert_eq!(record.message, "hello");
+
+        store.streaming_clean_start("uuid").await.unwrap();
+        assert!(
+            store
+                .streaming_is_client_live("did_hash", true)
+                .await
+                .is_empty()
+        );
+    }
+}
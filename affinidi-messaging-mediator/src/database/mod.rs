@@ -1,22 +1,102 @@
-//! All Redis related database methods are handled by `DatabaseHandler` module
+//! Storage for the mediator
+//!
+//! All database access goes through the [MediatorStore] trait. The Redis backend is handled by
+//! the [Database] methods spread across this module, the embedded SQLite backend lives in
+//! `sqlite_store` and the in-memory backend (for tests) lives in `memory_store`.
 
+use affinidi_messaging_mediator_common::{database::config::DatabaseConfig, errors::MediatorError};
+pub use mediator_store::{MediatorStore, PubSubStream};
+use std::sync::Arc;
+
+#[cfg(feature = "redis")]
 use affinidi_messaging_mediator_common::database::DatabaseHandler;
 
+#[cfg(feature = "redis")]
 pub mod accounts;
+#[cfg(feature = "redis")]
 pub(crate) mod acls;
+#[cfg(feature = "redis")]
 pub mod admin_accounts;
+pub(crate) mod audit_log;
+#[cfg(feature = "redis")]
 pub mod fetch;
+#[cfg(feature = "redis")]
+pub(crate) mod forwarding;
+#[cfg(feature = "redis")]
 pub mod get;
+#[cfg(feature = "redis")]
 pub mod handlers;
+#[cfg(feature = "redis")]
 pub(crate) mod initialization;
+#[cfg(feature = "redis")]
 pub mod list;
+#[cfg(feature = "redis")]
+pub(crate) mod mediation;
+pub mod mediator_store;
+pub mod memory_store;
+#[cfg(feature = "redis")]
 pub(crate) mod messages;
+#[cfg(feature = "redis")]
 pub(crate) mod oob_discovery;
+#[cfg(feature = "redis")]
+pub(crate) mod push_notifications;
+pub(crate) mod rate_limit;
+#[cfg(feature = "redis")]
+pub(crate) mod redis_store;
+pub mod scheduled_delivery;
 pub mod session;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_store;
 pub mod stats;
+#[cfg(feature = "redis")]
+pub(crate) mod status_reply;
 pub mod store;
+pub(crate) mod stream_id;
+#[cfg(feature = "redis")]
 pub mod streaming;
+#[cfg(feature = "redis")]
 pub(crate) mod upgrades;
 
+/// Redis backend
+#[cfg(feature = "redis")]
 #[derive(Clone)]
 pub struct Database(pub DatabaseHandler);
+
+/// Opens the storage backend selected by the `database_url` scheme
+/// - `redis://` or `rediss://` : Redis backend (feature `redis`)
+/// - `sqlite://<path>` : Embedded SQLite backend (feature `sqlite`)
+/// - `memory://` : In-memory backend, nothing is persisted
+pub async fn open(config: &DatabaseConfig) -> Result<Arc<dyn MediatorStore>, MediatorError> {
+    let url = config.database_url.as_str();
+
+    if url.starts_with("redis://") || url.starts_with("rediss://") {
+        #[cfg(feature = "redis")]
+        return Ok(Arc::new(Database(DatabaseHandler::new(config).await?)));
+    } else if let Some(_path) = url.strip_prefix("sqlite://") {
+        #[cfg(feature = "sqlite")]
+        return Ok(Arc::new(sqlite_store::SqliteStore::open(_path)?));
+    } else if url.starts_with("memory://") {
+        return Ok(Arc::new(memory_store::MemoryStore::new()));
+    }
+
+    Err(MediatorError::ConfigError(
+        "NA".into(),
+        format!(
+            "database_url ({}) isn't supported by this build. Supported schemes: {}",
+            url,
+            _supported_schemes().join(", ")
+        ),
+    ))
+}
+
+fn _supported_schemes() -> Vec<&'static str> {
+    let mut schemes = Vec::new();
+    if cfg!(feature = "redis") {
+        schemes.extend(["redis://", "rediss://"]);
+    }
+    if cfg!(feature = "sqlite") {
+        schemes.push("sqlite://");
+    }
+    schemes.push("memory://");
+    schemes
+}
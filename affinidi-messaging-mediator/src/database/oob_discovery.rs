@@ -7,6 +7,9 @@
 
  OOB_INVITES Field Naming = OOB_ID
    OOB_ID = SHA256 Hash of the Invite Message
+
+ SET KEY : DID_OOB_INVITES:<did_hash>
+   OOB_IDs created by a DID, used to remove the invites when the account is removed
 */
 
 use super::Database;
@@ -80,6 +83,7 @@ impl Database {
 
             let invite_hash = digest(&base64_invite);
             let key = Database::to_cache_key(invite_hash.to_owned());
+            let did_invites_key = ["DID_OOB_INVITES:", did_hash].concat();
 
             match deadpool_redis::redis::pipe()
                 .atomic()
@@ -89,6 +93,18 @@ impl Database {
                 .cmd("EXPIREAT")
                 .arg(key)
                 .arg(expire_at)
+                .cmd("SADD")
+                .arg(&did_invites_key)
+                .arg(&invite_hash)
+                // The index lives as long as the longest lived invite
+                .cmd("EXPIREAT")
+                .arg(&did_invites_key)
+                .arg(expire_at)
+                .arg("NX")
+                .cmd("EXPIREAT")
+                .arg(&did_invites_key)
+                .arg(expire_at)
+                .arg("GT")
                 .cmd("HINCRBY")
                 .arg("GLOBAL")
                 .arg("OOB_INVITES_CREATED")
@@ -187,6 +203,52 @@ impl Database {
         .await
     }
 
+    /// Removes all OOB Discovery Invitations created by a DID
+    /// Returns the number of invitations removed
+    pub(crate) async fn oob_discovery_remove_did(
+        &self,
+        did_hash: &str,
+    ) -> Result<usize, MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "oob_discovery_remove_did",
+            did_hash = did_hash
+        );
+
+        async move {
+            let mut conn = self.0.get_async_connection().await?;
+            let did_invites_key = ["DID_OOB_INVITES:", did_hash].concat();
+
+            let oob_ids: Vec<String> = deadpool_redis::redis::cmd("SMEMBERS")
+                .arg(&did_invites_key)
+                .query_async(&mut conn)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!("database fetch error: {}", err),
+                    )
+                })?;
+
+            let mut query = deadpool_redis::redis::pipe();
+            let query = query.atomic();
+            for oob_id in oob_ids {
+                query.cmd("DEL").arg(Database::to_cache_key(oob_id));
+            }
+            query.cmd("DEL").arg(&did_invites_key).ignore();
+
+            let removed: Vec<usize> = query.query_async(&mut conn).await.map_err(|err| {
+                MediatorError::DatabaseError("NA".into(), format!("database delete error: {}", err))
+            })?;
+
+            let removed = removed.iter().sum();
+            debug!("Removed ({}) OOB invites", removed);
+            Ok(removed)
+        }
+        .instrument(_span)
+        .await
+    }
+
     fn to_cache_key(id: String) -> String {
         format!("{HASH_KEY_PREFIX}{id}")
     }
@@ -0,0 +1,92 @@
+//! Push notification targets
+//!
+//! Database structure:
+//! - `DID:<did_hash>` field `PUSH_TARGET`: JSON encoded [PushTarget] registered by the DID
+//!
+//! The target is removed along with the rest of the DID record when the account is removed.
+
+use super::Database;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::protocols::mediator::push_notifications::PushTarget;
+use tracing::{Instrument, Level, debug, span};
+
+impl Database {
+    /// Registers the push target for a DID, replacing any existing target
+    pub(crate) async fn push_target_set(
+        &self,
+        did_hash: &str,
+        target: &PushTarget,
+    ) -> Result<(), MediatorError> {
+        let _span = span!(Level::DEBUG, "push_target_set", "did_hash" = did_hash);
+
+        async move {
+            let target = serde_json::to_string(target).map_err(|err| {
+                MediatorError::InternalError(
+                    "NA".into(),
+                    format!("Couldn't serialize push target. Reason: {}", err),
+                )
+            })?;
+
+            let mut con = self.0.get_async_connection().await?;
+            deadpool_redis::redis::cmd("HSET")
+                .arg(["DID:", did_hash].concat())
+                .arg("PUSH_TARGET")
+                .arg(target)
+                .exec_async(&mut con)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!("Couldn't set push target. Reason: {}", err),
+                    )
+                })?;
+
+            debug!("Push target set");
+            Ok(())
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Gets the push target registered for a DID
+    pub(crate) async fn push_target_get(
+        &self,
+        did_hash: &str,
+    ) -> Result<Option<PushTarget>, MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+
+        let target: Option<String> = deadpool_redis::redis::cmd("HGET")
+            .arg(["DID:", did_hash].concat())
+            .arg("PUSH_TARGET")
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!("Couldn't get push target. Reason: {}", err),
+                )
+            })?;
+
+        Ok(target.and_then(|target| serde_json::from_str(&target).ok()))
+    }
+
+    /// Removes the push target registered for a DID
+    /// Returns true if a target was removed
+    pub(crate) async fn push_target_remove(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+
+        let removed: u32 = deadpool_redis::redis::cmd("HDEL")
+            .arg(["DID:", did_hash].concat())
+            .arg("PUSH_TARGET")
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!("Couldn't remove push target. Reason: {}", err),
+                )
+            })?;
+
+        Ok(removed > 0)
+    }
+}
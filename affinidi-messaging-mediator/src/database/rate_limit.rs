@@ -0,0 +1,140 @@
+//! Token bucket rate limiting
+//!
+//! Each bucket holds up to `limit` tokens and refills at `limit` tokens per minute, every request
+//! takes a token. Buckets that haven't been used for a minute are full again and can be dropped.
+//!
+//! Database structure (Redis, see the `rate_limit` function):
+//! - `RATE_LIMIT:<bucket>`: Hash, fields `TOKENS` and `UPDATED` (epoch milliseconds)
+//! - `DID:<did_hash>` field `RATE_LIMIT`: per account override of the configured limit
+//!
+//! The embedded backends keep their buckets in process using [TokenBucket].
+
+#[cfg(feature = "redis")]
+use super::Database;
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_common::errors::MediatorError;
+#[cfg(feature = "redis")]
+use tracing::{Level, event};
+
+/// Milliseconds it takes for an empty bucket to refill
+const REFILL_MILLIS: u64 = 60_000;
+
+/// Resolves the limit that applies to a request
+/// - `per_minute` - configured limit for the endpoint class (0: unlimited)
+/// - `account_limit` - account override (-1: unlimited, n > 0: replaces the configured limit)
+///
+/// Returns None if the request isn't limited
+pub(crate) fn effective_limit(per_minute: u32, account_limit: Option<i32>) -> Option<u32> {
+    let limit = match account_limit {
+        Some(-1) => return None,
+        Some(n) if n > 0 => n as u32,
+        _ => per_minute,
+    };
+
+    if limit == 0 { None } else { Some(limit) }
+}
+
+/// In process token bucket, mirrors the `rate_limit` Redis function
+#[derive(Clone, Debug)]
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    /// Epoch milliseconds of the last request
+    updated: u64,
+}
+
+impl TokenBucket {
+    /// A full bucket
+    pub(crate) fn new(limit: u32, now: u64) -> Self {
+        TokenBucket {
+            tokens: limit as f64,
+            updated: now,
+        }
+    }
+
+    /// Takes a token from the bucket
+    /// Returns None if the request is allowed, otherwise the milliseconds until a token is available
+    pub(crate) fn take(&mut self, limit: u32, now: u64) -> Option<u64> {
+        let limit = limit as f64;
+        let elapsed = now.saturating_sub(self.updated) as f64;
+        self.tokens = limit.min(self.tokens + elapsed * limit / REFILL_MILLIS as f64);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(((1.0 - self.tokens) * REFILL_MILLIS as f64 / limit).ceil() as u64)
+        }
+    }
+
+    /// Has the bucket been unused long enough that it is full again?
+    pub(crate) fn is_idle(&self, now: u64) -> bool {
+        now.saturating_sub(self.updated) >= REFILL_MILLIS
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Database {
+    /// Takes a token from the rate limit bucket (uses the `rate_limit` function)
+    /// - `key` - bucket identifier
+    /// - `did_hash` - account whose rate limit overrides `per_minute`
+    /// - `per_minute` - configured limit (0: unlimited)
+    ///
+    /// Returns None if the request is allowed, otherwise the milliseconds until a token is available
+    pub(crate) async fn rate_limit_take(
+        &self,
+        key: &str,
+        did_hash: Option<&str>,
+        per_minute: u32,
+    ) -> Result<Option<u64>, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        let mut cmd = deadpool_redis::redis::cmd("FCALL");
+        cmd.arg("rate_limit").arg(1).arg(key).arg(per_minute);
+        if let Some(did_hash) = did_hash {
+            cmd.arg(did_hash);
+        }
+
+        let retry_after: u64 = cmd.query_async(&mut conn).await.map_err(|err| {
+            event!(Level::ERROR, "rate_limit({}) failed. Reason: {}", key, err);
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!("rate_limit({}) failed. Reason: {}", key, err),
+            )
+        })?;
+
+        if retry_after == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(retry_after))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TokenBucket, effective_limit};
+
+    #[test]
+    fn test_effective_limit() {
+        assert_eq!(effective_limit(60, None), Some(60));
+        assert_eq!(effective_limit(0, None), None);
+        assert_eq!(effective_limit(60, Some(-1)), None);
+        assert_eq!(effective_limit(60, Some(10)), Some(10));
+        assert_eq!(effective_limit(0, Some(10)), Some(10));
+        assert_eq!(effective_limit(60, Some(0)), Some(60));
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut bucket = TokenBucket::new(2, 0);
+        assert_eq!(bucket.take(2, 0), None);
+        assert_eq!(bucket.take(2, 0), None);
+        // Empty, one token every 30 seconds
+        assert_eq!(bucket.take(2, 0), Some(30_000));
+        assert_eq!(bucket.take(2, 15_000), Some(15_000));
+        assert_eq!(bucket.take(2, 30_000), None);
+        assert!(!bucket.is_idle(60_000));
+        assert!(bucket.is_idle(90_000));
+    }
+}
@@ -0,0 +1,582 @@
+//! [MediatorStore] implementation for the Redis backend
+//!
+//! The Redis specific logic lives in the other modules of `database` as methods on [Database],
+//! this implementation simply delegates to them.
+
+use super::{
+    Database,
+    mediator_store::{MediatorStore, PubSubStream, SchemaUpgradeMode},
+    scheduled_delivery::ScheduledDelivery,
+    session::Session,
+    stats::MetadataStats,
+    store::QueueByteQuota,
+};
+use crate::{common::config::Config, tasks::websocket_streaming::PubSubRecord};
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::{database::DatabaseHandler, errors::MediatorError};
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use affinidi_messaging_sdk::{
+    messages::{Folder, GetMessagesResponse, MessageList, MessageListElement, fetch::FetchOptions},
+    protocols::{
+        mediator::{
+            accounts::{Account, AccountRemoveResponse, AccountType, MediatorAccountList},
+            acls::{AccessListModeType, MediatorACLSet},
+            acls_handler::{
+                MediatorACLGetResponse, MediatorAccessListAddResponse,
+                MediatorAccessListGetResponse, MediatorAccessListListResponse,
+            },
+            administration::{AuditLogEntry, AuditLogFilter, MediatorAdminList, MediatorAuditLog},
+            push_notifications::PushTarget,
+        },
+        message_pickup::MessagePickupStatusReply,
+    },
+};
+use async_trait::async_trait;
+use tokio_stream::StreamExt;
+use tracing::{Level, error, event, info};
+
+#[async_trait]
+impl MediatorStore for Database {
+    async fn initialize(&self, config: &Config) -> Result<(), MediatorError> {
+        let Some(functions_file) = &config.database.functions_file else {
+            return Err(MediatorError::ConfigError(
+                "Initialization".into(),
+                "The Redis backend requires database.functions_file to be set".into(),
+            ));
+        };
+        event!(
+            Level::INFO,
+            "Loading LUA scripts into the database from file: {}",
+            functions_file
+        );
+        self.load_scripts(functions_file).await?;
+
+        Database::initialize(self, config).await
+    }
+
+    fn redis_handler(&self) -> Option<DatabaseHandler> {
+        Some(self.0.clone())
+    }
+
+    async fn upgrade_schema(
+        &self,
+        config: &Config,
+        mode: &SchemaUpgradeMode,
+    ) -> Result<(), MediatorError> {
+        Database::upgrade_schema(self, config, mode).await
+    }
+
+    async fn store_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        to_did: &str,
+        from_did: Option<&str>,
+        expires_at: u64,
+        signed_by: Option<&str>,
+        quota: &QueueByteQuota,
+    ) -> Result<String, MediatorError> {
+        Database::store_message(
+            self, session_id, message, to_did, from_did, expires_at, signed_by, quota,
+        )
+        .await
+    }
+
+    async fn get_message(
+        &self,
+        did_hash: &str,
+        msg_id: &str,
+    ) -> Result<MessageListElement, MediatorError> {
+        Database::get_message(self, did_hash, msg_id).await
+    }
+
+    async fn fetch_messages(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        options: &FetchOptions,
+    ) -> Result<GetMessagesResponse, MediatorError> {
+        Database::fetch_messages(self, session_id, did_hash, options).await
+    }
+
+    async fn list_messages(
+        &self,
+        did_hash: &str,
+        folder: Folder,
+        range: Option<(&str, &str)>,
+        limit: u32,
+    ) -> Result<MessageList, MediatorError> {
+        Database::list_messages(self, did_hash, folder, range, limit).await
+    }
+
+    async fn delete_message(
+        &self,
+        session_id: Option<&str>,
+        did_hash: &str,
+        message_hash: &str,
+    ) -> Result<(), MediatorError> {
+        self.0
+            .delete_message(session_id, did_hash, message_hash)
+            .await
+    }
+
+    async fn purge_messages(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: Folder,
+    ) -> Result<(usize, usize), MediatorError> {
+        Database::purge_messages(self, session, did_hash, folder).await
+    }
+
+    async fn delete_folder_stream(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: &Folder,
+    ) -> Result<(), MediatorError> {
+        Database::delete_folder_stream(self, session, did_hash, folder).await
+    }
+
+    async fn get_status_reply(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<MessagePickupStatusReply, MediatorError> {
+        Database::get_status_reply(self, session_id, did_hash).await
+    }
+
+    async fn forward_queue_enqueue(
+        &self,
+        session_id: &str,
+        task: &ForwardTask,
+    ) -> Result<String, MediatorError> {
+        Database::forward_queue_enqueue(self, session_id, task).await
+    }
+
+    async fn get_forward_tasks_len(&self) -> Result<usize, MediatorError> {
+        Database::get_forward_tasks_len(self).await
+    }
+
+    async fn scheduled_delivery_add(
+        &self,
+        session_id: &str,
+        deliver_at: u128,
+        delivery: &ScheduledDelivery,
+    ) -> Result<String, MediatorError> {
+        Database::scheduled_delivery_add(self, session_id, deliver_at, delivery).await
+    }
+
+    async fn scheduled_delivery_due(
+        &self,
+        now: u128,
+        limit: usize,
+    ) -> Result<Vec<String>, MediatorError> {
+        Database::scheduled_delivery_due(self, now, limit).await
+    }
+
+    async fn scheduled_delivery_claim(
+        &self,
+        id: &str,
+    ) -> Result<Option<ScheduledDelivery>, MediatorError> {
+        Database::scheduled_delivery_claim(self, id).await
+    }
+
+    async fn account_exists(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        Database::account_exists(self, did_hash).await
+    }
+
+    async fn account_get(&self, did_hash: &str) -> Result<Option<Account>, MediatorError> {
+        Database::account_get(self, did_hash).await
+    }
+
+    async fn account_add(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+        queue_limit: Option<u32>,
+    ) -> Result<Account, MediatorError> {
+        Database::account_add(self, did_hash, acls, queue_limit).await
+    }
+
+    async fn account_remove(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        remove_outbox: bool,
+    ) -> Result<AccountRemoveResponse, MediatorError> {
+        Database::account_remove(self, session, did_hash, remove_outbox).await
+    }
+
+    async fn account_list(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAccountList, MediatorError> {
+        Database::account_list(self, cursor, limit).await
+    }
+
+    async fn account_change_type(
+        &self,
+        did_hash: &str,
+        _type: &AccountType,
+    ) -> Result<(), MediatorError> {
+        Database::account_change_type(self, did_hash, _type).await
+    }
+
+    async fn account_change_queue_limits(
+        &self,
+        did_hash: &str,
+        send_queue_limit: Option<i32>,
+        receive_queue_limit: Option<i32>,
+    ) -> Result<(), MediatorError> {
+        Database::account_change_queue_limits(self, did_hash, send_queue_limit, receive_queue_limit)
+            .await
+    }
+
+    async fn account_change_queue_bytes_limits(
+        &self,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<(), MediatorError> {
+        Database::account_change_queue_bytes_limits(
+            self,
+            did_hash,
+            send_queue_bytes_limit,
+            receive_queue_bytes_limit,
+        )
+        .await
+    }
+
+    async fn account_change_rate_limit(
+        &self,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<(), MediatorError> {
+        Database::account_change_rate_limit(self, did_hash, rate_limit).await
+    }
+
+    async fn setup_admin_account(
+        &self,
+        admin_did_hash: &str,
+        admin_type: AccountType,
+        acls: &MediatorACLSet,
+    ) -> Result<(), MediatorError> {
+        Database::setup_admin_account(self, admin_did_hash, admin_type, acls).await
+    }
+
+    async fn check_admin_account(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        Database::check_admin_account(self, did_hash).await
+    }
+
+    async fn add_admin_accounts(
+        &self,
+        accounts: Vec<String>,
+        acls: &MediatorACLSet,
+    ) -> Result<usize, MediatorError> {
+        Database::add_admin_accounts(self, accounts, acls).await
+    }
+
+    async fn strip_admin_accounts(&self, accounts: Vec<String>) -> Result<i32, MediatorError> {
+        Database::strip_admin_accounts(self, accounts).await
+    }
+
+    async fn list_admin_accounts(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAdminList, MediatorError> {
+        Database::list_admin_accounts(self, cursor, limit).await
+    }
+
+    async fn audit_log_add(
+        &self,
+        entry: &AuditLogEntry,
+        max_entries: usize,
+    ) -> Result<(), MediatorError> {
+        Database::audit_log_add(self, entry, max_entries).await
+    }
+
+    async fn audit_log_list(
+        &self,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &AuditLogFilter,
+    ) -> Result<MediatorAuditLog, MediatorError> {
+        Database::audit_log_list(self, cursor, limit, filter).await
+    }
+
+    async fn push_target_set(
+        &self,
+        did_hash: &str,
+        target: &PushTarget,
+    ) -> Result<(), MediatorError> {
+        Database::push_target_set(self, did_hash, target).await
+    }
+
+    async fn push_target_get(&self, did_hash: &str) -> Result<Option<PushTarget>, MediatorError> {
+        Database::push_target_get(self, did_hash).await
+    }
+
+    async fn push_target_remove(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        Database::push_target_remove(self, did_hash).await
+    }
+
+    async fn set_did_acl(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+    ) -> Result<MediatorACLSet, MediatorError> {
+        Database::set_did_acl(self, did_hash, acls).await
+    }
+
+    async fn get_did_acl(&self, did_hash: &str) -> Result<Option<MediatorACLSet>, MediatorError> {
+        Database::get_did_acl(self, did_hash).await
+    }
+
+    async fn get_did_acls(
+        &self,
+        dids: &[String],
+        mediator_acl_mode: AccessListModeType,
+    ) -> Result<MediatorACLGetResponse, MediatorError> {
+        Database::get_did_acls(self, dids, mediator_acl_mode).await
+    }
+
+    async fn access_list_allowed(
+        &self,
+        to_hash: &str,
+        from_hash: Option<String>,
+    ) -> Result<bool, MediatorError> {
+        Database::access_list_allowed(self, to_hash, from_hash).await
+    }
+
+    async fn access_list_list(
+        &self,
+        did_hash: &str,
+        cursor: u64,
+    ) -> Result<MediatorAccessListListResponse, MediatorError> {
+        Database::access_list_list(self, did_hash, cursor).await
+    }
+
+    async fn access_list_count(&self, did_hash: &str) -> Result<usize, MediatorError> {
+        Database::access_list_count(self, did_hash).await
+    }
+
+    async fn access_list_add(
+        &self,
+        access_list_limit: usize,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListAddResponse, MediatorError> {
+        Database::access_list_add(self, access_list_limit, did_hash, hashes).await
+    }
+
+    async fn access_list_remove(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<usize, MediatorError> {
+        Database::access_list_remove(self, did_hash, hashes).await
+    }
+
+    async fn access_list_clear(&self, did_hash: &str) -> Result<(), MediatorError> {
+        Database::access_list_clear(self, did_hash).await
+    }
+
+    async fn access_list_get(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListGetResponse, MediatorError> {
+        Database::access_list_get(self, did_hash, hashes).await
+    }
+
+    async fn keylist_add(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        Database::keylist_add(self, did_hash, recipient_did).await
+    }
+
+    async fn keylist_remove(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        Database::keylist_remove(self, did_hash, recipient_did).await
+    }
+
+    async fn keylist_list(
+        &self,
+        did_hash: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), MediatorError> {
+        Database::keylist_list(self, did_hash, offset, limit).await
+    }
+
+    async fn create_session(&self, session: &Session) -> Result<(), MediatorError> {
+        Database::create_session(self, session).await
+    }
+
+    async fn get_session(&self, session_id: &str, did: &str) -> Result<Session, MediatorError> {
+        Database::get_session(self, session_id, did).await
+    }
+
+    async fn update_session_authenticated(
+        &self,
+        old_session_id: &str,
+        new_session_id: &str,
+        did_hash: &str,
+    ) -> Result<(), MediatorError> {
+        Database::update_session_authenticated(self, old_session_id, new_session_id, did_hash).await
+    }
+
+    async fn session_link_challenge(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        challenge: &str,
+    ) -> Result<(), MediatorError> {
+        Database::session_link_challenge(self, session_id, did_hash, challenge).await
+    }
+
+    async fn session_link_did(
+        &self,
+        session_id: &str,
+        did: &str,
+        challenge: &str,
+    ) -> Result<bool, MediatorError> {
+        Database::session_link_did(self, session_id, did, challenge).await
+    }
+
+    async fn oob_discovery_store(
+        &self,
+        did_hash: &str,
+        invite: &Message,
+        oob_invite_ttl: u64,
+    ) -> Result<String, MediatorError> {
+        Database::oob_discovery_store(self, did_hash, invite, oob_invite_ttl).await
+    }
+
+    async fn oob_discovery_get(&self, oob_id: &str) -> Result<Option<String>, MediatorError> {
+        Database::oob_discovery_get(self, oob_id).await
+    }
+
+    async fn oob_discovery_delete(&self, oob_id: &str) -> Result<bool, MediatorError> {
+        Database::oob_discovery_delete(self, oob_id).await
+    }
+
+    async fn rate_limit_take(
+        &self,
+        key: &str,
+        did_hash: Option<&str>,
+        per_minute: u32,
+    ) -> Result<Option<u64>, MediatorError> {
+        Database::rate_limit_take(self, key, did_hash, per_minute).await
+    }
+
+    async fn streaming_clean_start(&self, uuid: &str) -> Result<(), MediatorError> {
+        Database::streaming_clean_start(self, uuid).await
+    }
+
+    async fn streaming_is_client_live(&self, did_hash: &str, force_delivery: bool) -> Vec<String> {
+        Database::streaming_is_client_live(self, did_hash, force_delivery).await
+    }
+
+    async fn streaming_publish_message(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+        message: &str,
+        force_delivery: bool,
+    ) -> Result<(), MediatorError> {
+        Database::streaming_publish_message(self, did_hash, stream_uuid, message, force_delivery)
+            .await
+    }
+
+    async fn streaming_subscribe(&self, stream_uuid: &str) -> Result<PubSubStream, MediatorError> {
+        let mut pubsub = self.0.get_pubsub_connection().await?;
+
+        let channel = format!("CHANNEL:{}", stream_uuid);
+        pubsub.subscribe(channel.clone()).await.map_err(|err| {
+            error!("Error subscribing to channel: {}", err);
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!("Error subscribing to channel: {}", err),
+            )
+        })?;
+        info!("Subscribed to channel: {}", channel);
+
+        Ok(Box::pin(pubsub.into_on_message().filter_map(|msg| {
+            let payload = match msg.get_payload::<String>() {
+                Ok(payload) => payload,
+                Err(err) => {
+                    error!("Error getting payload from message: {}", err);
+                    return None;
+                }
+            };
+            match serde_json::from_str::<PubSubRecord>(&payload) {
+                Ok(record) => Some(record),
+                Err(err) => {
+                    error!("Error parsing pub/sub record: {}", err);
+                    None
+                }
+            }
+        })))
+    }
+
+    async fn streaming_register_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        Database::streaming_register_client(self, did_hash, stream_uuid).await
+    }
+
+    async fn streaming_start_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        Database::streaming_start_live(self, did_hash, stream_uuid).await
+    }
+
+    async fn streaming_stop_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        Database::streaming_stop_live(self, did_hash, stream_uuid).await
+    }
+
+    async fn streaming_deregister_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        Database::streaming_deregister_client(self, did_hash, stream_uuid).await
+    }
+
+    async fn get_db_metadata(&self) -> Result<MetadataStats, MediatorError> {
+        Database::get_db_metadata(self).await
+    }
+
+    async fn get_expiry_backlog(&self, now: u64) -> Result<usize, MediatorError> {
+        Database::get_expiry_backlog(self, now).await
+    }
+
+    async fn update_send_stats(&self, sent_bytes: i64) -> Result<(), MediatorError> {
+        Database::update_send_stats(self, sent_bytes).await
+    }
+
+    async fn global_stats_increment_websocket_open(&self) -> Result<(), MediatorError> {
+        Database::global_stats_increment_websocket_open(self).await
+    }
+
+    async fn global_stats_increment_websocket_close(&self) -> Result<(), MediatorError> {
+        Database::global_stats_increment_websocket_close(self).await
+    }
+}
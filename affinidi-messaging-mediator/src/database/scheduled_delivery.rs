@@ -0,0 +1,263 @@
+//! Scheduled (delayed) delivery of forwarded messages
+//!
+//! A forward message can request a delivery delay using the `delay_milli` header.
+//! Delayed messages are held outside of the recipient's RECEIVE_Q until they are due.
+//!
+//! Database structure:
+//! - `SCHEDULED_DELIVERIES`: Sorted Set, score is the epoch time (milliseconds) the message is due
+//!   and the member is the scheduled delivery ID
+//! - `SCHEDULED_DELIVERY:<ID>`: String, JSON serialized [ScheduledDelivery]
+
+use serde::{Deserialize, Serialize};
+use sha256::digest;
+
+#[cfg(feature = "redis")]
+use super::Database;
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_common::errors::MediatorError;
+#[cfg(feature = "redis")]
+use tracing::{Instrument, Level, debug, span};
+#[cfg(feature = "redis")]
+use uuid::Uuid;
+
+/// A forwarded message waiting for its delivery time
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScheduledDelivery {
+    /// Packed message to deliver
+    pub message: String,
+    /// DID of the recipient (next hop)
+    pub to_did: String,
+    /// SHA256 hash of the recipient DID
+    pub to_did_hash: String,
+    /// DID of the sender if known
+    pub from_did: Option<String>,
+    /// ID of the forward message that requested the delay
+    pub msg_id: Option<String>,
+    /// Epoch time in seconds when the message expires
+    pub expires_at: u64,
+    /// Ephemeral messages are only live streamed and never stored
+    pub ephemeral: bool,
+    /// Remote messages are handed to the forwarding processor when due
+    pub remote: bool,
+    /// Key ID that signed the forwarded attachment (if verified)
+    #[serde(default)]
+    pub signed_by: Option<String>,
+}
+
+impl ScheduledDelivery {
+    /// Is `did_hash` the recipient or the sender of this delivery?
+    pub fn involves(&self, did_hash: &str) -> bool {
+        self.to_did_hash == did_hash
+            || self
+                .from_did
+                .as_ref()
+                .is_some_and(|from_did| digest(from_did) == did_hash)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Database {
+    /// Schedules a message for delivery at `deliver_at` (epoch milliseconds)
+    /// Returns the scheduled delivery ID
+    pub(crate) async fn scheduled_delivery_add(
+        &self,
+        session_id: &str,
+        deliver_at: u128,
+        delivery: &ScheduledDelivery,
+    ) -> Result<String, MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "scheduled_delivery_add",
+            to_did_hash = delivery.to_did_hash
+        );
+        async move {
+            let id = Uuid::new_v4().to_string();
+            let record = serde_json::to_string(delivery).map_err(|err| {
+                MediatorError::InternalError(
+                    session_id.into(),
+                    format!("Couldn't serialize scheduled delivery. Reason: {}", err),
+                )
+            })?;
+
+            let mut conn = self.0.get_async_connection().await?;
+            deadpool_redis::redis::pipe()
+                .atomic()
+                .cmd("SET")
+                .arg(["SCHEDULED_DELIVERY:", &id].concat())
+                .arg(record)
+                .cmd("ZADD")
+                .arg("SCHEDULED_DELIVERIES")
+                .arg(deliver_at as u64)
+                .arg(&id)
+                .exec_async(&mut conn)
+                .await
+                .map_err(|err| {
+                    MediatorError::DatabaseError(
+                        session_id.into(),
+                        format!("Couldn't schedule delivery. Reason: {}", err),
+                    )
+                })?;
+
+            debug!("Scheduled delivery ({}) at ({})", id, deliver_at);
+            Ok(id)
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Returns up to `limit` scheduled delivery IDs that are due at `now` (epoch milliseconds)
+    pub(crate) async fn scheduled_delivery_due(
+        &self,
+        now: u128,
+        limit: usize,
+    ) -> Result<Vec<String>, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        deadpool_redis::redis::cmd("ZRANGE")
+            .arg("SCHEDULED_DELIVERIES")
+            .arg("-inf")
+            .arg(now as u64)
+            .arg("BYSCORE")
+            .arg("LIMIT")
+            .arg(0)
+            .arg(limit)
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "INTERNAL".into(),
+                    format!("Couldn't get due scheduled deliveries. Reason: {}", err),
+                )
+            })
+    }
+
+    /// Claims a scheduled delivery, removing it from the schedule
+    /// Returns None if another mediator has already claimed it
+    pub(crate) async fn scheduled_delivery_claim(
+        &self,
+        id: &str,
+    ) -> Result<Option<ScheduledDelivery>, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+        let key = ["SCHEDULED_DELIVERY:", id].concat();
+
+        let (removed, record): (u32, Option<String>) = deadpool_redis::redis::pipe()
+            .atomic()
+            .cmd("ZREM")
+            .arg("SCHEDULED_DELIVERIES")
+            .arg(id)
+            .cmd("GET")
+            .arg(&key)
+            .cmd("DEL")
+            .arg(&key)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "INTERNAL".into(),
+                    format!(
+                        "Couldn't claim scheduled delivery ({}). Reason: {}",
+                        id, err
+                    ),
+                )
+            })?;
+
+        if removed == 0 {
+            return Ok(None);
+        }
+
+        match record {
+            Some(record) => serde_json::from_str(&record).map(Some).map_err(|err| {
+                MediatorError::InternalError(
+                    "INTERNAL".into(),
+                    format!(
+                        "Couldn't parse scheduled delivery ({}). Reason: {}",
+                        id, err
+                    ),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes all scheduled deliveries to or from a DID
+    /// Returns the number of deliveries removed
+    pub(crate) async fn scheduled_delivery_remove_did(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<usize, MediatorError> {
+        let _span = span!(
+            Level::DEBUG,
+            "scheduled_delivery_remove_did",
+            did_hash = did_hash
+        );
+        async move {
+            let mut conn = self.0.get_async_connection().await?;
+            let mut cursor: u64 = 0;
+            let mut matched: Vec<String> = Vec::new();
+
+            loop {
+                // ZSCAN returns member and score pairs
+                let (next, members): (u64, Vec<String>) = deadpool_redis::redis::cmd("ZSCAN")
+                    .arg("SCHEDULED_DELIVERIES")
+                    .arg(cursor)
+                    .arg("COUNT")
+                    .arg(100)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|err| {
+                        MediatorError::DatabaseError(
+                            session_id.into(),
+                            format!("Couldn't scan scheduled deliveries. Reason: {}", err),
+                        )
+                    })?;
+
+                let ids: Vec<&String> = members.iter().step_by(2).collect();
+                if !ids.is_empty() {
+                    let keys: Vec<String> = ids
+                        .iter()
+                        .map(|id| ["SCHEDULED_DELIVERY:", id].concat())
+                        .collect();
+                    let records: Vec<Option<String>> = deadpool_redis::redis::cmd("MGET")
+                        .arg(&keys)
+                        .query_async(&mut conn)
+                        .await
+                        .map_err(|err| {
+                            MediatorError::DatabaseError(
+                                session_id.into(),
+                                format!("Couldn't get scheduled deliveries. Reason: {}", err),
+                            )
+                        })?;
+
+                    for (id, record) in ids.into_iter().zip(records) {
+                        let involved = record
+                            .and_then(|r| serde_json::from_str::<ScheduledDelivery>(&r).ok())
+                            .is_some_and(|delivery| delivery.involves(did_hash));
+                        if involved {
+                            matched.push(id.clone());
+                        }
+                    }
+                }
+
+                if next == 0 {
+                    break;
+                }
+                cursor = next;
+            }
+
+            let mut removed = 0;
+            for id in matched {
+                // Claiming removes the delivery, unless it has just been delivered
+                if self.scheduled_delivery_claim(&id).await?.is_some() {
+                    removed += 1;
+                }
+            }
+
+            debug!("Removed ({}) scheduled deliveries", removed);
+            Ok(removed)
+        }
+        .instrument(_span)
+        .await
+    }
+}
@@ -3,10 +3,19 @@ use affinidi_messaging_sdk::protocols::mediator::{accounts::AccountType, acls::M
 use ahash::AHashMap as HashMap;
 use serde::{Deserialize, Serialize};
 use sha256::digest;
-use std::fmt::{self, Display, Formatter};
-use tracing::{debug, warn};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::{Arc, atomic::AtomicU64},
+};
+use tracing::warn;
 
+#[cfg(feature = "redis")]
 use super::Database;
+#[cfg(feature = "redis")]
+use tracing::debug;
+
+/// Lifetime of an authenticated session (seconds), refresh tokens never outlive it
+pub const SESSION_EXPIRY: i64 = 86400;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SessionClaims {
@@ -61,6 +70,40 @@ pub struct Session {
     pub acls: MediatorACLSet,
     pub account_type: AccountType,
     pub expires_at: u64,
+    /// Additional DIDs the session has proven control of (see `/authenticate/link`)
+    #[serde(default)]
+    pub linked_dids: Vec<String>,
+    /// New access token expiry when the access token is refreshed in-band (over a websocket)
+    /// Allows the websocket to stay connected past `expires_at`, 0 if not refreshed
+    #[serde(skip)]
+    pub refreshed_expires_at: Arc<AtomicU64>,
+    /// Websocket connection the request arrived on, None for HTTP requests
+    /// Live delivery is enabled/disabled per websocket connection
+    #[serde(skip)]
+    pub connection_id: Option<String>,
+}
+
+impl Session {
+    /// Returns true if `did` is the session DID or one of its linked DIDs
+    pub fn owns_did(&self, did: &str) -> bool {
+        self.did == did || self.linked_dids.iter().any(|linked| linked == did)
+    }
+
+    /// Returns true if `did_hash` is the hash of the session DID or one of its linked DIDs
+    pub fn owns_did_hash(&self, did_hash: &str) -> bool {
+        self.did_hash == did_hash
+            || self
+                .linked_dids
+                .iter()
+                .any(|linked| digest(linked) == did_hash)
+    }
+
+    /// Hashes of all DIDs in the session, the session DID is always first
+    pub fn did_hashes(&self) -> Vec<String> {
+        std::iter::once(self.did_hash.clone())
+            .chain(self.linked_dids.iter().map(digest))
+            .collect()
+    }
 }
 
 impl TryFrom<(&str, HashMap<String, String>)> for Session {
@@ -128,6 +171,7 @@ impl TryFrom<(&str, HashMap<String, String>)> for Session {
     }
 }
 
+#[cfg(feature = "redis")]
 impl Database {
     /// Creates a new session in the database
     /// Typically called when sending the initial challenge to the client
@@ -172,26 +216,32 @@ impl Database {
     pub async fn get_session(&self, session_id: &str, did: &str) -> Result<Session, MediatorError> {
         let mut con = self.0.get_async_connection().await?;
 
-        let (session_db, did_db): (HashMap<String, String>, Vec<Option<String>>) =
-            deadpool_redis::redis::pipe()
-                .atomic()
-                .cmd("HGETALL")
-                .arg(format!("SESSION:{}", session_id))
-                .cmd("HMGET")
-                .arg(["DID:", &digest(did)].concat())
-                .arg("ROLE_TYPE")
-                .arg("ACLS")
-                .query_async(&mut con)
-                .await
-                .map_err(|err| {
-                    MediatorError::SessionError(
-                        session_id.into(),
-                        format!("tried to retrieve session({}). Error: {}", session_id, err),
-                    )
-                })?;
+        let (session_db, did_db, linked_dids): (
+            HashMap<String, String>,
+            Vec<Option<String>>,
+            Vec<String>,
+        ) = deadpool_redis::redis::pipe()
+            .atomic()
+            .cmd("HGETALL")
+            .arg(format!("SESSION:{}", session_id))
+            .cmd("HMGET")
+            .arg(["DID:", &digest(did)].concat())
+            .arg("ROLE_TYPE")
+            .arg("ACLS")
+            .cmd("SMEMBERS")
+            .arg(["SESSION_LINKED_DIDS:", session_id].concat())
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::SessionError(
+                    session_id.into(),
+                    format!("tried to retrieve session({}). Error: {}", session_id, err),
+                )
+            })?;
 
         let mut session: Session = Session {
             session_id: session_id.into(),
+            linked_dids,
             ..Default::default()
         };
 
@@ -309,7 +359,11 @@ impl Database {
             .cmd("SADD")
             .arg("KNOWN_DIDS")
             .arg(did_hash)
-            .expire(&new_sid, 86400)
+            .cmd("SADD")
+            .arg(["DID_SESSIONS:", did_hash].concat())
+            .arg(new_session_id)
+            .expire(&new_sid, SESSION_EXPIRY)
+            .expire(["DID_SESSIONS:", did_hash].concat(), SESSION_EXPIRY)
             .exec_async(&mut con)
             .await
             .map_err(|err| {
@@ -324,4 +378,126 @@ impl Database {
 
         Ok(())
     }
+
+    /// Removes all authenticated sessions of a DID
+    /// Returns the number of sessions removed
+    pub(crate) async fn sessions_remove_did(&self, did_hash: &str) -> Result<usize, MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+        let key = ["DID_SESSIONS:", did_hash].concat();
+
+        let session_ids: Vec<String> = deadpool_redis::redis::cmd("SMEMBERS")
+            .arg(&key)
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!(
+                        "Couldn't get sessions for DID ({}). Error: {}",
+                        did_hash, err
+                    ),
+                )
+            })?;
+
+        let mut query = deadpool_redis::redis::pipe();
+        let query = query.atomic();
+        for session_id in &session_ids {
+            query.cmd("DEL").arg(format!("SESSION:{}", session_id));
+            query
+                .cmd("DEL")
+                .arg(["SESSION_LINKED_DIDS:", session_id].concat())
+                .ignore();
+        }
+        query.cmd("DEL").arg(&key).ignore();
+
+        let removed: Vec<usize> = query.query_async(&mut con).await.map_err(|err| {
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!(
+                    "Couldn't remove sessions for DID ({}). Error: {}",
+                    did_hash, err
+                ),
+            )
+        })?;
+
+        Ok(removed.iter().sum())
+    }
+
+    /// Stores a challenge used to prove control of `did_hash` within an authenticated session
+    /// The challenge expires after 15 minutes, the same as a new session
+    pub(crate) async fn session_link_challenge(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        challenge: &str,
+    ) -> Result<(), MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+
+        deadpool_redis::redis::cmd("SET")
+            .arg(["SESSION_LINK:", session_id, ":", did_hash].concat())
+            .arg(challenge)
+            .arg("EX")
+            .arg(900)
+            .exec_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::SessionError(
+                    session_id.into(),
+                    format!("tried to store link challenge. Error: {}", err),
+                )
+            })?;
+
+        Ok(())
+    }
+
+    /// Links `did` to an authenticated session if `challenge` matches the stored link challenge
+    /// A link challenge can only be used once
+    /// Returns false if the challenge didn't match or has expired
+    pub(crate) async fn session_link_did(
+        &self,
+        session_id: &str,
+        did: &str,
+        challenge: &str,
+    ) -> Result<bool, MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+        let did_hash = digest(did);
+
+        let stored: Option<String> = deadpool_redis::redis::cmd("GETDEL")
+            .arg(["SESSION_LINK:", session_id, ":", &did_hash].concat())
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::SessionError(
+                    session_id.into(),
+                    format!("tried to retrieve link challenge. Error: {}", err),
+                )
+            })?;
+
+        if stored.as_deref() != Some(challenge) {
+            debug!("Link challenge for DID ({}) didn't match", did_hash);
+            return Ok(false);
+        }
+
+        let linked_key = ["SESSION_LINKED_DIDS:", session_id].concat();
+        deadpool_redis::redis::pipe()
+            .atomic()
+            .cmd("SADD")
+            .arg(&linked_key)
+            .arg(did)
+            .cmd("SADD")
+            .arg(["DID_SESSIONS:", &did_hash].concat())
+            .arg(session_id)
+            .expire(&linked_key, SESSION_EXPIRY)
+            .expire(["DID_SESSIONS:", &did_hash].concat(), SESSION_EXPIRY)
+            .exec_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::SessionError(
+                    session_id.into(),
+                    format!("tried to link DID to session. Error: {}", err),
+                )
+            })?;
+
+        Ok(true)
+    }
 }
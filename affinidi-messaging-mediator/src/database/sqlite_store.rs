@@ -0,0 +1,3100 @@
+/*!
+ * Embedded single-node storage backend using SQLite
+ *
+ * Mirrors the Redis data model (and the semantics of the functions in `conf/atm-functions.lua`)
+ * using tables. Live streaming is handled by in-process channels, so this backend is only suitable
+ * for a single mediator node.
+ *
+ * Tables:
+ * - `metadata`: SCHEMA_VERSION
+ * - `global`: Global counters (RECEIVED_BYTES, SENT_COUNT, etc)
+ * - `accounts`: DID records (ROLE_TYPE, ACLS, queue counters and limits)
+ * - `known_dids`, `admins`: Sets of DID hashes
+ * - `access_lists`: Access List members for each DID
+ * - `mediation_keylists`: Recipient DIDs registered by each DID through Coordinate Mediation
+ * - `messages`: Message and its metadata, including when it expires
+ * - `queues`: RECEIVE_Q (Inbox) and SEND_Q (Outbox) entries, ordered by stream ID (`ms-seq`)
+ * - `queue_last_ids`: Last stream ID issued per queue, so IDs are never reused
+ * - `sessions`, `session_link_challenges`, `oob_invites`: Records that expire (checked on read,
+ *   removed by [MediatorStore::expire_messages])
+ * - `session_linked_dids`: Additional DIDs each session has proven control of
+ * - `oob_invite_creators`: OOB invites created by each DID, removed with the account
+ * - `streaming`: Live streaming state for each DID
+ * - `forward_tasks`, `scheduled_deliveries`
+ * - `audit_log`: Capped log of administrative changes
+ *
+ * Rate limit buckets are held in process, as only a single node uses the database.
+ */
+
+use super::{
+    MediatorStore, PubSubStream,
+    audit_log::{action_name, audit_log_page, parse_action},
+    rate_limit::{TokenBucket, effective_limit},
+    scheduled_delivery::ScheduledDelivery,
+    session::{SESSION_EXPIRY, Session, SessionState},
+    stats::MetadataStats,
+    store::{QueueByteQuota, quota_error, quota_exceeded},
+    stream_id::{parse_stream_id, range_bound},
+};
+use crate::{common::config::Config, tasks::websocket_streaming::PubSubRecord};
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use affinidi_messaging_sdk::{
+    messages::{
+        FetchDeletePolicy, Folder, GetMessagesResponse, MessageList, MessageListElement,
+        fetch::FetchOptions,
+    },
+    protocols::{
+        mediator::{
+            accounts::{Account, AccountRemoveResponse, AccountType, MediatorAccountList},
+            acls::{AccessListModeType, MediatorACLSet},
+            acls_handler::{
+                MediatorACLExpanded, MediatorACLGetResponse, MediatorAccessListAddResponse,
+                MediatorAccessListGetResponse, MediatorAccessListListResponse,
+            },
+            administration::{
+                AdminAccount, AuditAction, AuditLogEntry, AuditLogFilter, MediatorAdminList,
+                MediatorAuditLog,
+            },
+            push_notifications::PushTarget,
+        },
+        message_pickup::MessagePickupStatusReply,
+    },
+};
+use async_trait::async_trait;
+use base64::prelude::*;
+use rusqlite::{Connection, OptionalExtension, Row, Transaction, params};
+use sha256::digest;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+const RECEIVE_Q: &str = "RECEIVE_Q";
+const SEND_Q: &str = "SEND_Q";
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS global (
+    key TEXT PRIMARY KEY,
+    value INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    did_hash TEXT PRIMARY KEY,
+    role_type TEXT,
+    acls TEXT,
+    send_queue_bytes INTEGER NOT NULL DEFAULT 0,
+    send_queue_count INTEGER NOT NULL DEFAULT 0,
+    receive_queue_bytes INTEGER NOT NULL DEFAULT 0,
+    receive_queue_count INTEGER NOT NULL DEFAULT 0,
+    send_queue_limit INTEGER,
+    receive_queue_limit INTEGER,
+    rate_limit INTEGER,
+    send_queue_bytes_limit INTEGER,
+    receive_queue_bytes_limit INTEGER
+);
+CREATE TABLE IF NOT EXISTS known_dids (
+    did_hash TEXT PRIMARY KEY
+);
+CREATE TABLE IF NOT EXISTS admins (
+    did_hash TEXT PRIMARY KEY
+);
+CREATE TABLE IF NOT EXISTS access_lists (
+    did_hash TEXT NOT NULL,
+    member TEXT NOT NULL,
+    PRIMARY KEY (did_hash, member)
+);
+CREATE TABLE IF NOT EXISTS mediation_keylists (
+    did_hash TEXT NOT NULL,
+    recipient_did TEXT NOT NULL,
+    PRIMARY KEY (did_hash, recipient_did)
+);
+CREATE TABLE IF NOT EXISTS messages (
+    msg_id TEXT PRIMARY KEY,
+    message TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    to_did_hash TEXT NOT NULL,
+    from_did_hash TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    receive_id TEXT NOT NULL,
+    send_id TEXT NOT NULL,
+    signed_by TEXT,
+    expires_at INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS messages_expires_at ON messages (expires_at);
+CREATE TABLE IF NOT EXISTS queues (
+    folder TEXT NOT NULL,
+    did_hash TEXT NOT NULL,
+    ms INTEGER NOT NULL,
+    seq INTEGER NOT NULL,
+    msg_id TEXT NOT NULL,
+    bytes INTEGER NOT NULL,
+    peer TEXT NOT NULL,
+    PRIMARY KEY (folder, did_hash, ms, seq)
+);
+CREATE TABLE IF NOT EXISTS queue_last_ids (
+    folder TEXT NOT NULL,
+    did_hash TEXT NOT NULL,
+    ms INTEGER NOT NULL,
+    seq INTEGER NOT NULL,
+    PRIMARY KEY (folder, did_hash)
+);
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id TEXT PRIMARY KEY,
+    challenge TEXT NOT NULL,
+    state TEXT NOT NULL,
+    did TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS session_link_challenges (
+    session_id TEXT NOT NULL,
+    did_hash TEXT NOT NULL,
+    challenge TEXT NOT NULL,
+    expires_at INTEGER NOT NULL,
+    PRIMARY KEY (session_id, did_hash)
+);
+CREATE TABLE IF NOT EXISTS session_linked_dids (
+    session_id TEXT NOT NULL,
+    did TEXT NOT NULL,
+    did_hash TEXT NOT NULL,
+    PRIMARY KEY (session_id, did)
+);
+CREATE TABLE IF NOT EXISTS oob_invites (
+    oob_id TEXT PRIMARY KEY,
+    invite TEXT NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS oob_invite_creators (
+    did_hash TEXT NOT NULL,
+    oob_id TEXT NOT NULL,
+    PRIMARY KEY (did_hash, oob_id)
+);
+CREATE TABLE IF NOT EXISTS streaming (
+    did_hash TEXT PRIMARY KEY,
+    uuid TEXT NOT NULL,
+    live INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE IF NOT EXISTS forward_tasks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    message TEXT NOT NULL,
+    to_did TEXT NOT NULL,
+    from_did TEXT,
+    msg_id TEXT,
+    expires_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS scheduled_deliveries (
+    id TEXT PRIMARY KEY,
+    deliver_at INTEGER NOT NULL,
+    record TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS scheduled_deliveries_deliver_at ON scheduled_deliveries (deliver_at);
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    target TEXT NOT NULL,
+    acls_before TEXT,
+    acls_after TEXT,
+    detail TEXT
+);
+CREATE TABLE IF NOT EXISTS push_targets (
+    did_hash TEXT PRIMARY KEY,
+    target TEXT NOT NULL
+);
+"#;
+
+/// Embedded SQLite backend (`sqlite://<path>`, use `sqlite://:memory:` for a transient database)
+pub struct SqliteStore {
+    /// Shared with the blocking threads that run the queries (see [SqliteStore::with_conn])
+    conn: Arc<Mutex<Connection>>,
+    /// Live streaming channels, keyed by streaming service UUID
+    channels: Mutex<HashMap<String, mpsc::UnboundedSender<PubSubRecord>>>,
+    /// Rate limit buckets, keyed by bucket identifier
+    rate_limits: Mutex<HashMap<String, TokenBucket>>,
+}
+
+/// Outcome of a delete_message transaction
+enum DeleteOutcome {
+    Deleted,
+    NotFound,
+    NotOwner,
+}
+
+impl SqliteStore {
+    /// Opens (creating if needed) the SQLite database at `path`
+    pub fn open(path: &str) -> Result<Self, MediatorError> {
+        let _error = |err: rusqlite::Error| {
+            error!("Couldn't open SQLite database ({}). Reason: {}", path, err);
+            MediatorError::DatabaseError(
+                "NA".into(),
+                format!("Couldn't open SQLite database ({}). Reason: {}", path, err),
+            )
+        };
+
+        let conn = Connection::open(path).map_err(_error)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .map_err(_error)?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))
+            .map_err(_error)?;
+        conn.execute_batch(SCHEMA).map_err(_error)?;
+
+        // Databases created by earlier versions are missing the newer account columns
+        for column in [
+            "rate_limit",
+            "send_queue_bytes_limit",
+            "receive_queue_bytes_limit",
+        ] {
+            let exists: bool = conn
+                .query_row(
+                    "SELECT EXISTS (SELECT 1 FROM pragma_table_info('accounts') WHERE name = ?1)",
+                    params![column],
+                    |row| row.get(0),
+                )
+                .map_err(_error)?;
+            if !exists {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE accounts ADD COLUMN {column} INTEGER;"
+                ))
+                .map_err(_error)?;
+            }
+        }
+
+        info!("SQLite database ({}) opened", path);
+
+        Ok(SqliteStore {
+            conn: Arc::new(Mutex::new(conn)),
+            channels: Mutex::new(HashMap::new()),
+            rate_limits: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Runs `f` against the connection on the blocking thread pool, mapping SQLite errors to a
+    /// DatabaseError
+    /// NOTE: The connection is locked for the duration of `f`, never call back into the store from `f`
+    async fn with_conn<T: Send + 'static>(
+        &self,
+        session_id: &str,
+        context: &str,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    ) -> Result<T, MediatorError> {
+        let _error = |err: &dyn std::fmt::Display| {
+            error!("{} failed. Reason: {}", context, err);
+            MediatorError::DatabaseError(
+                session_id.into(),
+                format!("{} failed. Reason: {}", context, err),
+            )
+        };
+
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(|err| _error(&err))?
+        .map_err(|err| _error(&err))
+    }
+}
+
+// ****************************************************************************
+// Private helpers
+
+/// Removes the sessions, streaming registration, forward tasks, scheduled deliveries and OOB
+/// invites of a DID that is being removed. Message counts are left for the caller to fill in
+fn _remove_did_records(
+    tx: &Transaction,
+    did_hash: &str,
+) -> rusqlite::Result<AccountRemoveResponse> {
+    let mut removed = AccountRemoveResponse {
+        did_hash: did_hash.to_string(),
+        ..Default::default()
+    };
+
+    // Sessions only hold the DID, so match on its hash
+    let mut session_ids: Vec<String> = tx
+        .prepare("SELECT session_id, did FROM sessions")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, did)| digest(did) == did_hash)
+        .map(|(session_id, _)| session_id)
+        .collect();
+    // Sessions that the DID is linked to are removed as well
+    session_ids.extend(
+        tx.prepare("SELECT session_id FROM session_linked_dids WHERE did_hash = ?1")?
+            .query_map(params![did_hash], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?,
+    );
+    session_ids.sort();
+    session_ids.dedup();
+    for session_id in &session_ids {
+        removed.sessions += tx.execute(
+            "DELETE FROM sessions WHERE session_id = ?1",
+            params![session_id],
+        )?;
+        tx.execute(
+            "DELETE FROM session_linked_dids WHERE session_id = ?1",
+            params![session_id],
+        )?;
+    }
+
+    removed.streaming_sessions = tx.execute(
+        "DELETE FROM streaming WHERE did_hash = ?1",
+        params![did_hash],
+    )?;
+
+    let task_ids: Vec<i64> = tx
+        .prepare("SELECT id, message, to_did, from_did, msg_id, expires_at FROM forward_tasks")?
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                ForwardTask::new(
+                    &row.get::<_, String>(1)?,
+                    &row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?.as_deref(),
+                    row.get::<_, Option<String>>(4)?.as_deref(),
+                    row.get::<_, i64>(5)? as u64,
+                ),
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, task)| task.involves(did_hash))
+        .map(|(id, _)| id)
+        .collect();
+    for id in &task_ids {
+        removed.forward_tasks +=
+            tx.execute("DELETE FROM forward_tasks WHERE id = ?1", params![id])?;
+    }
+
+    let scheduled_ids: Vec<String> = tx
+        .prepare("SELECT id, record FROM scheduled_deliveries")?
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|(_, record)| {
+            serde_json::from_str::<ScheduledDelivery>(record)
+                .is_ok_and(|delivery| delivery.involves(did_hash))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in &scheduled_ids {
+        removed.scheduled_deliveries += tx.execute(
+            "DELETE FROM scheduled_deliveries WHERE id = ?1",
+            params![id],
+        )?;
+    }
+
+    removed.oob_invites = tx.execute(
+        "DELETE FROM oob_invites WHERE oob_id IN
+         (SELECT oob_id FROM oob_invite_creators WHERE did_hash = ?1)",
+        params![did_hash],
+    )?;
+    tx.execute(
+        "DELETE FROM oob_invite_creators WHERE did_hash = ?1",
+        params![did_hash],
+    )?;
+
+    Ok(removed)
+}
+
+fn _now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+fn _now_secs() -> i64 {
+    _now_ms() / 1000
+}
+
+fn _folder_key(folder: &Folder) -> &'static str {
+    match folder {
+        Folder::Inbox => RECEIVE_Q,
+        Folder::Outbox => SEND_Q,
+    }
+}
+
+fn _incr_global(conn: &Connection, key: &str, by: i64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO global (key, value) VALUES (?1, ?2)
+         ON CONFLICT (key) DO UPDATE SET value = value + excluded.value",
+        params![key, by],
+    )?;
+    Ok(())
+}
+
+/// Adjusts the queue counters of a DID, creating the DID record if needed (same as HINCRBY)
+fn _adjust_queue(
+    conn: &Connection,
+    folder: &str,
+    did_hash: &str,
+    bytes: i64,
+    count: i64,
+) -> rusqlite::Result<()> {
+    let sql = if folder == RECEIVE_Q {
+        "INSERT INTO accounts (did_hash, receive_queue_bytes, receive_queue_count) VALUES (?1, ?2, ?3)
+         ON CONFLICT (did_hash) DO UPDATE SET
+            receive_queue_bytes = receive_queue_bytes + excluded.receive_queue_bytes,
+            receive_queue_count = receive_queue_count + excluded.receive_queue_count"
+    } else {
+        "INSERT INTO accounts (did_hash, send_queue_bytes, send_queue_count) VALUES (?1, ?2, ?3)
+         ON CONFLICT (did_hash) DO UPDATE SET
+            send_queue_bytes = send_queue_bytes + excluded.send_queue_bytes,
+            send_queue_count = send_queue_count + excluded.send_queue_count"
+    };
+    conn.execute(sql, params![did_hash, bytes, count])?;
+    Ok(())
+}
+
+/// Would adding `bytes` to the queue of a DID exceed its quota?
+/// The account's own quota replaces `default_quota` (same as the store_message function)
+fn _queue_quota_exceeded(
+    conn: &Connection,
+    folder: &str,
+    did_hash: &str,
+    default_quota: i64,
+    bytes: i64,
+) -> rusqlite::Result<bool> {
+    let sql = if folder == RECEIVE_Q {
+        "SELECT receive_queue_bytes, receive_queue_bytes_limit FROM accounts WHERE did_hash = ?1"
+    } else {
+        "SELECT send_queue_bytes, send_queue_bytes_limit FROM accounts WHERE did_hash = ?1"
+    };
+    let (queued, account_quota) = conn
+        .query_row(sql, params![did_hash], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?))
+        })
+        .optional()?
+        .unwrap_or((0, None));
+
+    Ok(quota_exceeded(default_quota, account_quota, queued, bytes))
+}
+
+/// Adds an entry to a queue, returns the stream ID of the entry
+fn _queue_add(
+    conn: &Connection,
+    folder: &str,
+    did_hash: &str,
+    now_ms: i64,
+    msg_id: &str,
+    bytes: i64,
+    peer: &str,
+) -> rusqlite::Result<String> {
+    let last: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT ms, seq FROM queue_last_ids WHERE folder = ?1 AND did_hash = ?2",
+            params![folder, did_hash],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let (ms, seq) = match last {
+        Some((ms, seq)) if ms >= now_ms => (ms, seq + 1),
+        _ => (now_ms, 0),
+    };
+
+    conn.execute(
+        "INSERT INTO queue_last_ids (folder, did_hash, ms, seq) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (folder, did_hash) DO UPDATE SET ms = excluded.ms, seq = excluded.seq",
+        params![folder, did_hash, ms, seq],
+    )?;
+    conn.execute(
+        "INSERT INTO queues (folder, did_hash, ms, seq, msg_id, bytes, peer)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![folder, did_hash, ms, seq, msg_id, bytes, peer],
+    )?;
+
+    Ok(format!("{}-{}", ms, seq))
+}
+
+fn _queue_delete(
+    conn: &Connection,
+    folder: &str,
+    did_hash: &str,
+    stream_id: &str,
+) -> rusqlite::Result<()> {
+    if let Some((ms, seq)) = parse_stream_id(stream_id, 0) {
+        conn.execute(
+            "DELETE FROM queues WHERE folder = ?1 AND did_hash = ?2 AND ms = ?3 AND seq = ?4",
+            params![folder, did_hash, ms, seq],
+        )?;
+    }
+    Ok(())
+}
+
+/// Sets a single column of a DID record, creating the DID record if needed (same as HSET)
+fn _set_account_field(
+    conn: &Connection,
+    did_hash: &str,
+    field: &str,
+    value: Option<String>,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO accounts (did_hash, {field}) VALUES (?1, ?2)
+             ON CONFLICT (did_hash) DO UPDATE SET {field} = excluded.{field}"
+        ),
+        params![did_hash, value],
+    )?;
+    Ok(())
+}
+
+/// Translates a row (did_hash, role_type, acls, send bytes/count, receive bytes/count,
+/// send/receive limits, rate limit, send/receive byte limits, access list count) into an Account
+fn _to_account(row: &Row) -> rusqlite::Result<Account> {
+    Ok(Account {
+        did_hash: row.get(0)?,
+        _type: row
+            .get::<_, Option<String>>(1)?
+            .map(|role_type| AccountType::from(role_type.as_str()))
+            .unwrap_or(AccountType::Standard),
+        acls: row
+            .get::<_, Option<String>>(2)?
+            .and_then(|acls| u64::from_str_radix(&acls, 16).ok())
+            .unwrap_or(0),
+        send_queue_bytes: row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+        send_queue_count: row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u32,
+        receive_queue_bytes: row.get::<_, Option<i64>>(5)?.unwrap_or(0) as u64,
+        receive_queue_count: row.get::<_, Option<i64>>(6)?.unwrap_or(0) as u32,
+        queue_send_limit: row.get::<_, Option<i64>>(7)?.map(|limit| limit as i32),
+        queue_receive_limit: row.get::<_, Option<i64>>(8)?.map(|limit| limit as i32),
+        rate_limit: row.get::<_, Option<i64>>(9)?.map(|limit| limit as i32),
+        queue_send_bytes_limit: row.get(10)?,
+        queue_receive_bytes_limit: row.get(11)?,
+        access_list_count: row.get::<_, i64>(12)? as u32,
+    })
+}
+
+/// Next cursor for offset based pagination, 0 when there are no more results
+fn _next_cursor(cursor: u64, limit: u64, returned: usize) -> u64 {
+    if limit > 0 && returned as u64 >= limit {
+        cursor + limit
+    } else {
+        0
+    }
+}
+
+#[async_trait]
+impl MediatorStore for SqliteStore {
+    // ************************************************************************
+    // Setup and housekeeping
+
+    async fn initialize(&self, config: &Config) -> Result<(), MediatorError> {
+        let schema_version: Option<String> = self
+            .with_conn("NA", "get SCHEMA_VERSION", move |conn| {
+                conn.query_row(
+                    "SELECT value FROM metadata WHERE key = 'SCHEMA_VERSION'",
+                    [],
+                    |row| row.get(0),
+                )
+                .optional()
+            })
+            .await?;
+
+        match schema_version {
+            Some(schema_version) if schema_version == env!("CARGO_PKG_VERSION") => {
+                info!("Database schema version ({}) is good", schema_version);
+            }
+            schema_version => {
+                // Tables are created on open, so there is nothing to upgrade yet
+                warn!(
+                    "Database schema version ({:?}). Setting to ({})",
+                    schema_version,
+                    env!("CARGO_PKG_VERSION")
+                );
+                self.with_conn("NA", "set SCHEMA_VERSION", move |conn| {
+                    conn.execute(
+                        "INSERT INTO metadata (key, value) VALUES ('SCHEMA_VERSION', ?1)
+                         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                        params![env!("CARGO_PKG_VERSION")],
+                    )
+                })
+                .await?;
+            }
+        }
+
+        // Setup the mediator account if it doesn't exist
+        // Set the ACL for the mediator account to deny_all by default
+        self.setup_admin_account(
+            &config.mediator_did_hash,
+            AccountType::Mediator,
+            &MediatorACLSet::from_string_ruleset("DENY_ALL,LOCAL,BLOCKED").unwrap(),
+        )
+        .await?;
+
+        // Set up the administration account if it doesn't exist
+        self.setup_admin_account(
+            &digest(&config.admin_did),
+            AccountType::RootAdmin,
+            &config.security.global_acl_default,
+        )
+        .await
+    }
+
+    /// Checkpoints the write-ahead log into the main database file
+    async fn flush(&self) -> Result<(), MediatorError> {
+        self.with_conn("NA", "wal_checkpoint", move |conn| {
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        })
+        .await
+    }
+
+    /// Also removes expired sessions and OOB invitations
+    async fn expire_messages(&self, now: u64) -> Result<usize, MediatorError> {
+        let expired: Vec<String> = self.with_conn("NA", "expire_messages", move |conn| {
+            conn.execute(
+                "DELETE FROM sessions WHERE expires_at <= ?1",
+                params![now as i64],
+            )?;
+            conn.execute(
+                "DELETE FROM session_linked_dids WHERE session_id NOT IN (SELECT session_id FROM sessions)",
+                [],
+            )?;
+            conn.execute(
+                "DELETE FROM session_link_challenges WHERE expires_at <= ?1",
+                params![now as i64],
+            )?;
+            conn.execute(
+                "DELETE FROM oob_invites WHERE expires_at <= ?1",
+                params![now as i64],
+            )?;
+            conn.execute(
+                "DELETE FROM oob_invite_creators WHERE oob_id NOT IN (SELECT oob_id FROM oob_invites)",
+                [],
+            )?;
+
+            let mut stmt = conn.prepare("SELECT msg_id FROM messages WHERE expires_at <= ?1")?;
+            stmt.query_map(params![now as i64], |row| row.get(0))?
+                .collect()
+        })
+.await?;
+
+        let mut count = 0;
+        for msg_id in expired {
+            match self.delete_message(None, "ADMIN", &msg_id).await {
+                Ok(_) => count += 1,
+                Err(err) => warn!("Couldn't expire message ({}). Reason: {}", msg_id, err),
+            }
+        }
+
+        if count > 0 {
+            debug!("Expired {} messages", count);
+        }
+        Ok(count)
+    }
+
+    // ************************************************************************
+    // Messages
+
+    async fn store_message(
+        &self,
+        session_id: &str,
+        message: &str,
+        to_did: &str,
+        from_did: Option<&str>,
+        expires_at: u64,
+        signed_by: Option<&str>,
+        quota: &QueueByteQuota,
+    ) -> Result<String, MediatorError> {
+        let message_hash = digest(message.as_bytes());
+        let to_hash = digest(to_did.as_bytes());
+        let from_hash = if let Some(from_did) = from_did {
+            digest(from_did)
+        } else {
+            "ANONYMOUS".to_string()
+        };
+        let bytes = message.len() as i64;
+
+        let rejected = self.with_conn(session_id, "store_message", {
+                let message_hash = message_hash.clone();
+                let from_hash = from_hash.clone();
+                let to_hash = to_hash.clone();
+                let message = message.to_string();
+                let signed_by = signed_by.map(str::to_string);
+                let quota = *quota;
+                move |conn| {
+            let now = _now_ms();
+            let tx = conn.transaction()?;
+
+            // Check the queue quotas before anything is written
+            // Anonymous senders share a single record, so only the receiver quota applies to them
+            if _queue_quota_exceeded(&tx, RECEIVE_Q, &to_hash, quota.receive, bytes)? {
+                return Ok(Some("receive"));
+            }
+            if from_hash != "ANONYMOUS"
+                && _queue_quota_exceeded(&tx, SEND_Q, &from_hash, quota.send, bytes)?
+            {
+                return Ok(Some("send"));
+            }
+
+            _incr_global(&tx, "RECEIVED_BYTES", bytes)?;
+            _incr_global(&tx, "RECEIVED_COUNT", 1)?;
+
+            // Update the receiver records
+            _adjust_queue(&tx, RECEIVE_Q, &to_hash, bytes, 1)?;
+            let receive_id = _queue_add(
+                &tx,
+                RECEIVE_Q,
+                &to_hash,
+                now,
+                &message_hash,
+                bytes,
+                &from_hash,
+            )?;
+
+            // Update the sender records
+            _adjust_queue(&tx, SEND_Q, &from_hash, bytes, 1)?;
+            let send_id = _queue_add(&tx, SEND_Q, &from_hash, now, &message_hash, bytes, &to_hash)?;
+
+            tx.execute(
+                "INSERT OR REPLACE INTO messages
+                 (msg_id, message, bytes, to_did_hash, from_did_hash, timestamp, receive_id, send_id, signed_by, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    message_hash,
+                    message,
+                    bytes,
+                    to_hash,
+                    from_hash,
+                    now,
+                    receive_id,
+                    send_id,
+                    signed_by,
+                    expires_at as i64
+                ],
+            )?;
+
+            tx.commit()?;
+            Ok(None)
+        }
+            })
+.await?;
+
+        if let Some(queue) = rejected {
+            info!(
+                "Message hash({}) rejected, {} queue quota exceeded",
+                message_hash, queue
+            );
+            return Err(quota_error(session_id, queue));
+        }
+
+        info!(
+            "Message hash({}) from({}) to({}) stored in database",
+            message_hash, from_hash, to_hash
+        );
+
+        Ok(message_hash)
+    }
+
+    async fn get_message(
+        &self,
+        did_hash: &str,
+        msg_id: &str,
+    ) -> Result<MessageListElement, MediatorError> {
+        let message = self.with_conn("NA", "get_message", {
+                let msg_id = msg_id.to_string();
+                move |conn| {
+            conn.query_row(
+                "SELECT message, bytes, from_did_hash, to_did_hash, timestamp, send_id, receive_id, signed_by
+                 FROM messages WHERE msg_id = ?1",
+                params![msg_id],
+                |row| {
+                    Ok(MessageListElement {
+                        msg_id: msg_id.to_string(),
+                        msg: Some(row.get(0)?),
+                        size: row.get::<_, i64>(1)? as u64,
+                        from_address: Some(row.get(2)?),
+                        to_address: Some(row.get(3)?),
+                        timestamp: row.get::<_, i64>(4)? as u64,
+                        send_id: Some(row.get(5)?),
+                        receive_id: Some(row.get(6)?),
+                        signed_by: row.get(7)?,
+                        next_cursor: None,
+                    })
+                },
+            )
+            .optional()
+        }
+            })
+.await?;
+
+        let Some(message) = message else {
+            return Err(MediatorError::DatabaseError(
+                did_hash.into(),
+                format!("Message not found for ID: {}", msg_id),
+            ));
+        };
+
+        if message.from_address.as_deref() == Some(did_hash)
+            || message.to_address.as_deref() == Some(did_hash)
+        {
+            let _ = self.update_send_stats(message.size as i64).await;
+            Ok(message)
+        } else {
+            Err(MediatorError::DatabaseError(
+                did_hash.into(),
+                format!("Message not found for DID: {}", did_hash),
+            ))
+        }
+    }
+
+    async fn fetch_messages(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        options: &FetchOptions,
+    ) -> Result<GetMessagesResponse, MediatorError> {
+        // start_id is exclusive
+        let start = match options.start_id.as_deref() {
+            None | Some("-") => Some((0, 0)),
+            Some(start_id) => range_bound(&["(", start_id].concat(), true),
+        };
+        let Some((start_ms, start_seq)) = start else {
+            return Err(MediatorError::DatabaseError(
+                session_id.into(),
+                format!("Invalid start_id ({:?})", options.start_id),
+            ));
+        };
+
+        let fetched: Vec<MessageListElement> = self
+            .with_conn(session_id, "fetch_messages", {
+                let did_hash = did_hash.to_string();
+                let limit = options.limit as i64;
+                move |conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT q.msg_id, q.peer, m.message, m.bytes, m.to_did_hash, m.timestamp,
+                            m.receive_id, m.send_id, m.signed_by
+                     FROM queues q LEFT JOIN messages m ON m.msg_id = q.msg_id
+                     WHERE q.folder = ?1 AND q.did_hash = ?2 AND (q.ms, q.seq) >= (?3, ?4)
+                     ORDER BY q.ms, q.seq LIMIT ?5",
+                    )?;
+                    stmt.query_map(
+                        params![RECEIVE_Q, did_hash, start_ms, start_seq, limit],
+                        |row| {
+                            Ok(MessageListElement {
+                                msg_id: row.get(0)?,
+                                from_address: Some(row.get(1)?),
+                                msg: row.get(2)?,
+                                size: row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+                                to_address: row.get(4)?,
+                                timestamp: row.get::<_, Option<i64>>(5)?.unwrap_or(0) as u64,
+                                receive_id: row.get(6)?,
+                                send_id: row.get(7)?,
+                                signed_by: row.get(8)?,
+                                next_cursor: None,
+                            })
+                        },
+                    )?
+                    .collect()
+                }
+            })
+            .await?;
+
+        let mut messages = GetMessagesResponse::default();
+        for message in fetched {
+            debug!("Message id({}) fetched", &message.msg_id);
+
+            if let FetchDeletePolicy::Optimistic = options.delete_policy {
+                match self
+                    .delete_message(Some(session_id), did_hash, &message.msg_id)
+                    .await
+                {
+                    Ok(_) => {
+                        debug!("Message deleted: ({})", message.msg_id);
+                    }
+                    Err(e) => {
+                        warn!("Error deleting message: ({})", e);
+                        messages
+                            .delete_errors
+                            .push((message.msg_id.clone(), e.to_string()));
+                    }
+                }
+            }
+            messages.success.push(message);
+        }
+
+        Ok(messages)
+    }
+
+    async fn list_messages(
+        &self,
+        did_hash: &str,
+        folder: Folder,
+        range: Option<(&str, &str)>,
+        limit: u32,
+    ) -> Result<MessageList, MediatorError> {
+        let (start, end) = range.unwrap_or(("-", "+"));
+        let (Some((start_ms, start_seq)), Some((end_ms, end_seq))) =
+            (range_bound(start, true), range_bound(end, false))
+        else {
+            return Err(MediatorError::DatabaseError(
+                did_hash.into(),
+                format!("Invalid message_list range ({}, {})", start, end),
+            ));
+        };
+
+        self.with_conn(did_hash, "list_messages", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT ms, seq, msg_id, bytes, peer FROM queues
+                 WHERE folder = ?1 AND did_hash = ?2 AND (ms, seq) >= (?3, ?4) AND (ms, seq) <= (?5, ?6)
+                 ORDER BY ms, seq LIMIT ?7",
+            )?;
+            stmt
+                .query_map(
+                    params![
+                        _folder_key(&folder),
+                        did_hash,
+                        start_ms,
+                        start_seq,
+                        end_ms,
+                        end_seq,
+                        limit
+                    ],
+                    |row| {
+                        let ms: i64 = row.get(0)?;
+                        let stream_id = format!("{}-{}", ms, row.get::<_, i64>(1)?);
+                        let peer: String = row.get(4)?;
+                        let mut msg_element = MessageListElement {
+                            msg_id: row.get(2)?,
+                            size: row.get::<_, i64>(3)? as u64,
+                            timestamp: ms as u64,
+                            ..Default::default()
+                        };
+                        match folder {
+                            Folder::Inbox => {
+                                msg_element.receive_id = Some(stream_id);
+                                msg_element.from_address = Some(peer);
+                            }
+                            Folder::Outbox => {
+                                msg_element.send_id = Some(stream_id);
+                                msg_element.to_address = Some(peer);
+                            }
+                        }
+                        Ok(msg_element)
+                    },
+                )?
+                .collect()
+        }
+            })
+.await
+    }
+
+    async fn delete_message(
+        &self,
+        session_id: Option<&str>,
+        did_hash: &str,
+        message_hash: &str,
+    ) -> Result<(), MediatorError> {
+        let outcome = self
+            .with_conn(did_hash, &format!("delete_message({})", message_hash), {
+                let did_hash = did_hash.to_string();
+                let message_hash = message_hash.to_string();
+                move |conn| {
+                    let tx = conn.transaction()?;
+
+                    let meta: Option<(i64, String, String, String, String)> = tx
+                        .query_row(
+                            "SELECT bytes, to_did_hash, from_did_hash, receive_id, send_id
+                         FROM messages WHERE msg_id = ?1",
+                            params![message_hash],
+                            |row| {
+                                Ok((
+                                    row.get(0)?,
+                                    row.get(1)?,
+                                    row.get(2)?,
+                                    row.get(3)?,
+                                    row.get(4)?,
+                                ))
+                            },
+                        )
+                        .optional()?;
+
+                    let Some((bytes, to_hash, from_hash, receive_id, send_id)) = meta else {
+                        return Ok(DeleteOutcome::NotFound);
+                    };
+
+                    // Check that the requesting DID has some form of ownership of this message
+                    if to_hash != did_hash && from_hash != did_hash && did_hash != "ADMIN" {
+                        return Ok(DeleteOutcome::NotOwner);
+                    }
+
+                    tx.execute(
+                        "DELETE FROM messages WHERE msg_id = ?1",
+                        params![message_hash],
+                    )?;
+
+                    _incr_global(&tx, "DELETED_BYTES", bytes)?;
+                    _incr_global(&tx, "DELETED_COUNT", 1)?;
+
+                    // Remove the receiver records
+                    _adjust_queue(&tx, RECEIVE_Q, &to_hash, -bytes, -1)?;
+                    _queue_delete(&tx, RECEIVE_Q, &to_hash, &receive_id)?;
+
+                    // Remove the sender records
+                    _adjust_queue(&tx, SEND_Q, &from_hash, -bytes, -1)?;
+                    _queue_delete(&tx, SEND_Q, &from_hash, &send_id)?;
+
+                    tx.commit()?;
+                    Ok(DeleteOutcome::Deleted)
+                }
+            })
+            .await?;
+
+        let reason = match outcome {
+            DeleteOutcome::Deleted => {
+                debug!(
+                    "{}did_hash({}) message_id({}) deleted",
+                    session_id.map(|s| format!("{}: ", s)).unwrap_or_default(),
+                    did_hash,
+                    message_hash
+                );
+                return Ok(());
+            }
+            DeleteOutcome::NotFound => format!("Message ({}) not found", message_hash),
+            DeleteOutcome::NotOwner => {
+                "Requesting DID does not have ownership of this message".to_string()
+            }
+        };
+
+        Err(MediatorError::DatabaseError(
+            did_hash.into(),
+            format!(
+                "Couldn't delete message_id({}) from database for DID {}: {}",
+                message_hash, did_hash, reason
+            ),
+        ))
+    }
+
+    async fn purge_messages(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: Folder,
+    ) -> Result<(usize, usize), MediatorError> {
+        let entries: Vec<(String, i64)> = self
+            .with_conn(&session.session_id, "purge_messages", {
+                let folder = folder.clone();
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT msg_id, bytes FROM queues WHERE folder = ?1 AND did_hash = ?2
+                     ORDER BY ms, seq",
+                    )?;
+                    stmt.query_map(params![_folder_key(&folder), did_hash], |row| {
+                        Ok((row.get(0)?, row.get(1)?))
+                    })?
+                    .collect()
+                }
+            })
+            .await?;
+
+        let mut purge_count: usize = 0;
+        let mut purge_bytes: usize = 0;
+        for (msg_id, bytes) in entries {
+            self.delete_message(Some(&session.session_id), did_hash, &msg_id)
+                .await?;
+            purge_count += 1;
+            purge_bytes += bytes as usize;
+        }
+
+        self.delete_folder_stream(session, did_hash, &folder)
+            .await?;
+
+        Ok((purge_count, purge_bytes))
+    }
+
+    async fn delete_folder_stream(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        folder: &Folder,
+    ) -> Result<(), MediatorError> {
+        self.with_conn(&session.session_id, "delete_folder_stream", {
+            let did_hash = did_hash.to_string();
+            let folder = folder.clone();
+            move |conn| {
+                conn.execute(
+                    "DELETE FROM queues WHERE folder = ?1 AND did_hash = ?2",
+                    params![_folder_key(&folder), did_hash],
+                )?;
+                conn.execute(
+                    "DELETE FROM queue_last_ids WHERE folder = ?1 AND did_hash = ?2",
+                    params![_folder_key(&folder), did_hash],
+                )?;
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn get_status_reply(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<MessagePickupStatusReply, MediatorError> {
+        self.with_conn(session_id, "get_status_reply", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+            let mut status = MessagePickupStatusReply::default();
+
+            if let Some((count, bytes)) = conn
+                .query_row(
+                    "SELECT receive_queue_count, receive_queue_bytes FROM accounts WHERE did_hash = ?1",
+                    params![did_hash],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+                )
+                .optional()?
+            {
+                status.message_count = count as u32;
+                status.total_bytes = bytes as u64;
+            }
+
+            let (oldest, newest): (Option<i64>, Option<i64>) = conn.query_row(
+                "SELECT MIN(ms), MAX(ms) FROM queues WHERE folder = ?1 AND did_hash = ?2",
+                params![RECEIVE_Q, did_hash],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            status.oldest_received_time = oldest.map(|ms| ms as u64 / 1000);
+            status.newest_received_time = newest.map(|ms| ms as u64 / 1000);
+
+            status.live_delivery = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM streaming WHERE did_hash = ?1)",
+                params![did_hash],
+                |row| row.get(0),
+            )?;
+
+            Ok(status)
+        }
+            })
+.await
+    }
+
+    // ************************************************************************
+    // Forwarding and scheduled deliveries
+
+    async fn forward_queue_enqueue(
+        &self,
+        session_id: &str,
+        task: &ForwardTask,
+    ) -> Result<String, MediatorError> {
+        let id = self
+            .with_conn(session_id, "forward_queue_enqueue", {
+                let task = task.clone();
+                move |conn| {
+                    conn.execute(
+                        "INSERT INTO forward_tasks (message, to_did, from_did, msg_id, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            task.message,
+                            task.to_did,
+                            task.from_did,
+                            task.msg_id,
+                            task.expires_at as i64
+                        ],
+                    )?;
+                    Ok(conn.last_insert_rowid())
+                }
+            })
+            .await?;
+
+        Ok(id.to_string())
+    }
+
+    async fn get_forward_tasks_len(&self) -> Result<usize, MediatorError> {
+        self.with_conn("INTERNAL", "get_forward_tasks_len", move |conn| {
+            conn.query_row("SELECT COUNT(*) FROM forward_tasks", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        })
+        .await
+        .map(|count| count as usize)
+    }
+
+    async fn scheduled_delivery_add(
+        &self,
+        session_id: &str,
+        deliver_at: u128,
+        delivery: &ScheduledDelivery,
+    ) -> Result<String, MediatorError> {
+        let id = Uuid::new_v4().to_string();
+        let record = serde_json::to_string(delivery).map_err(|err| {
+            MediatorError::InternalError(
+                session_id.into(),
+                format!("Couldn't serialize scheduled delivery. Reason: {}", err),
+            )
+        })?;
+
+        self.with_conn(session_id, "scheduled_delivery_add", {
+            let id = id.clone();
+            move |conn| {
+                conn.execute(
+                    "INSERT INTO scheduled_deliveries (id, deliver_at, record) VALUES (?1, ?2, ?3)",
+                    params![id, deliver_at as i64, record],
+                )
+            }
+        })
+        .await?;
+
+        debug!("Scheduled delivery ({}) at ({})", id, deliver_at);
+        Ok(id)
+    }
+
+    async fn scheduled_delivery_due(
+        &self,
+        now: u128,
+        limit: usize,
+    ) -> Result<Vec<String>, MediatorError> {
+        self.with_conn("INTERNAL", "scheduled_delivery_due", move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id FROM scheduled_deliveries WHERE deliver_at <= ?1
+                 ORDER BY deliver_at LIMIT ?2",
+            )?;
+            stmt.query_map(params![now as i64, limit as i64], |row| row.get(0))?
+                .collect()
+        })
+        .await
+    }
+
+    async fn scheduled_delivery_claim(
+        &self,
+        id: &str,
+    ) -> Result<Option<ScheduledDelivery>, MediatorError> {
+        let record: Option<String> = self
+            .with_conn("INTERNAL", &format!("scheduled_delivery_claim({})", id), {
+                let id = id.to_string();
+                move |conn| {
+                    conn.query_row(
+                        "DELETE FROM scheduled_deliveries WHERE id = ?1 RETURNING record",
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                }
+            })
+            .await?;
+
+        match record {
+            Some(record) => serde_json::from_str(&record).map(Some).map_err(|err| {
+                MediatorError::InternalError(
+                    "INTERNAL".into(),
+                    format!(
+                        "Couldn't parse scheduled delivery ({}). Reason: {}",
+                        id, err
+                    ),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    // ************************************************************************
+    // Accounts
+
+    async fn account_exists(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        self.with_conn("NA", "account_exists", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM accounts WHERE did_hash = ?1)",
+                    params![did_hash],
+                    |row| row.get(0),
+                )
+            }
+        })
+        .await
+    }
+
+    async fn account_get(&self, did_hash: &str) -> Result<Option<Account>, MediatorError> {
+        self.with_conn("NA", "account_get", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.query_row(
+                "SELECT a.did_hash, a.role_type, a.acls, a.send_queue_bytes, a.send_queue_count,
+                        a.receive_queue_bytes, a.receive_queue_count, a.send_queue_limit,
+                        a.receive_queue_limit, a.rate_limit, a.send_queue_bytes_limit,
+                        a.receive_queue_bytes_limit,
+                        (SELECT COUNT(*) FROM access_lists l WHERE l.did_hash = a.did_hash)
+                 FROM accounts a WHERE a.did_hash = ?1",
+                params![did_hash],
+                _to_account,
+            )
+            .optional()
+            }
+        })
+        .await
+    }
+
+    async fn account_add(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+        _queue_limit: Option<u32>,
+    ) -> Result<Account, MediatorError> {
+        debug!("Adding account ({}) to the mediator", did_hash);
+
+        self.with_conn("NA", "account_add", {
+            let did_hash = did_hash.to_string();
+            let acls = acls.clone();
+            move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO known_dids (did_hash) VALUES (?1)",
+                    params![did_hash],
+                )?;
+                tx.execute(
+                    "INSERT INTO accounts (did_hash, role_type, acls) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (did_hash) DO UPDATE SET
+                    role_type = excluded.role_type, acls = excluded.acls,
+                    send_queue_bytes = 0, send_queue_count = 0,
+                    receive_queue_bytes = 0, receive_queue_count = 0",
+                    params![
+                        did_hash,
+                        String::from(AccountType::Standard),
+                        acls.to_hex_string()
+                    ],
+                )?;
+                tx.commit()
+            }
+        })
+        .await?;
+
+        Ok(Account {
+            did_hash: did_hash.to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn account_remove(
+        &self,
+        session: &Session,
+        did_hash: &str,
+        remove_outbox: bool,
+    ) -> Result<AccountRemoveResponse, MediatorError> {
+        let current = self.account_get(did_hash).await?;
+
+        if let Some(current) = &current {
+            if current._type == AccountType::Mediator {
+                return Err(MediatorError::InternalError(
+                    "NA".to_string(),
+                    "Cannot remove the mediator account".to_string(),
+                ));
+            } else if current._type == AccountType::RootAdmin {
+                return Err(MediatorError::InternalError(
+                    "NA".to_string(),
+                    "Cannot remove the root admin account".to_string(),
+                ));
+            }
+        }
+
+        // Block access to this account
+        let mut blocked_acl = MediatorACLSet::from_u64(0);
+        blocked_acl.set_blocked(true);
+        self.set_did_acl(did_hash, &blocked_acl).await?;
+
+        let mut removed = self
+            .with_conn(
+                &session.session_id,
+                &format!("account_remove({}) records", did_hash),
+                {
+                    let did_hash = did_hash.to_string();
+                    move |conn| {
+                        let tx = conn.transaction()?;
+                        let removed = _remove_did_records(&tx, &did_hash)?;
+                        tx.commit()?;
+                        Ok(removed)
+                    }
+                },
+            )
+            .await?;
+
+        if remove_outbox {
+            (removed.outbox_messages, _) = self
+                .purge_messages(session, did_hash, Folder::Outbox)
+                .await?;
+        } else {
+            self.delete_folder_stream(session, did_hash, &Folder::Outbox)
+                .await?;
+        }
+
+        (removed.inbox_messages, _) = self
+            .purge_messages(session, did_hash, Folder::Inbox)
+            .await?;
+
+        if let Some(current) = current {
+            if current._type.is_admin() {
+                self.strip_admin_accounts(vec![did_hash.to_string()])
+                    .await?;
+            }
+        }
+
+        self.with_conn("NA", &format!("account_remove({})", did_hash), {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "DELETE FROM known_dids WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                tx.execute(
+                    "DELETE FROM accounts WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                tx.execute(
+                    "DELETE FROM access_lists WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                tx.execute(
+                    "DELETE FROM mediation_keylists WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                tx.execute(
+                    "DELETE FROM push_targets WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                tx.commit()
+            }
+        })
+        .await?;
+
+        info!("Account removed: {:?}", removed);
+        Ok(removed)
+    }
+
+    async fn account_list(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAccountList, MediatorError> {
+        if limit > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "limit cannot exceed 100".to_string(),
+            ));
+        }
+
+        let accounts: Vec<Account> = self
+            .with_conn("NA", "account_list", move |conn| {
+                let mut stmt = conn.prepare(
+                "SELECT k.did_hash, a.role_type, a.acls, a.send_queue_bytes, a.send_queue_count,
+                        a.receive_queue_bytes, a.receive_queue_count, a.send_queue_limit,
+                        a.receive_queue_limit, a.rate_limit, a.send_queue_bytes_limit,
+                        a.receive_queue_bytes_limit,
+                        (SELECT COUNT(*) FROM access_lists l WHERE l.did_hash = k.did_hash)
+                 FROM known_dids k LEFT JOIN accounts a ON a.did_hash = k.did_hash
+                 ORDER BY k.did_hash LIMIT ?1 OFFSET ?2",
+            )?;
+                stmt.query_map(params![limit, cursor], _to_account)?
+                    .collect()
+            })
+            .await?;
+
+        Ok(MediatorAccountList {
+            cursor: _next_cursor(cursor as u64, limit as u64, accounts.len()) as u32,
+            accounts,
+        })
+    }
+
+    async fn account_change_type(
+        &self,
+        did_hash: &str,
+        _type: &AccountType,
+    ) -> Result<(), MediatorError> {
+        self.with_conn("NA", "account_change_type", {
+            let did_hash = did_hash.to_string();
+            let _type = *_type;
+            move |conn| {
+                _set_account_field(conn, &did_hash, "role_type", Some(_type.to_owned().into()))
+            }
+        })
+        .await
+    }
+
+    async fn account_change_queue_limits(
+        &self,
+        did_hash: &str,
+        send_queue_limit: Option<i32>,
+        receive_queue_limit: Option<i32>,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the soft limit (no value stored)
+        let _value = |limit: i32| (limit != -2).then(|| limit.to_string());
+
+        self.with_conn("NA", "account_change_queue_limits", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                if let Some(limit) = send_queue_limit {
+                    _set_account_field(conn, &did_hash, "send_queue_limit", _value(limit))?;
+                }
+                if let Some(limit) = receive_queue_limit {
+                    _set_account_field(conn, &did_hash, "receive_queue_limit", _value(limit))?;
+                }
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn account_change_queue_bytes_limits(
+        &self,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the soft limit (no value stored)
+        let _value = |limit: i64| (limit != -2).then(|| limit.to_string());
+
+        self.with_conn("NA", "account_change_queue_bytes_limits", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                if let Some(limit) = send_queue_bytes_limit {
+                    _set_account_field(conn, &did_hash, "send_queue_bytes_limit", _value(limit))?;
+                }
+                if let Some(limit) = receive_queue_bytes_limit {
+                    _set_account_field(
+                        conn,
+                        &did_hash,
+                        "receive_queue_bytes_limit",
+                        _value(limit),
+                    )?;
+                }
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn account_change_rate_limit(
+        &self,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<(), MediatorError> {
+        // -2 resets to the configured limits (no value stored)
+        let value = (rate_limit != -2).then(|| rate_limit.to_string());
+
+        self.with_conn("NA", "account_change_rate_limit", {
+            let did_hash = did_hash.to_string();
+            move |conn| _set_account_field(conn, &did_hash, "rate_limit", value)
+        })
+        .await
+    }
+
+    // ************************************************************************
+    // Admin accounts
+
+    async fn setup_admin_account(
+        &self,
+        admin_did_hash: &str,
+        admin_type: AccountType,
+        acls: &MediatorACLSet,
+    ) -> Result<(), MediatorError> {
+        if !self.account_exists(admin_did_hash).await? {
+            debug!("Admin account doesn't exist, creating: {}", admin_did_hash);
+            self.account_add(admin_did_hash, acls, None).await?;
+        }
+
+        self.with_conn(
+            "NA",
+            &format!("setup of admin account for ({})", admin_did_hash),
+            {
+                let admin_did_hash = admin_did_hash.to_string();
+                move |conn| {
+                    conn.execute(
+                        "INSERT OR IGNORE INTO admins (did_hash) VALUES (?1)",
+                        params![admin_did_hash],
+                    )?;
+                    _set_account_field(conn, &admin_did_hash, "role_type", Some(admin_type.into()))
+                }
+            },
+        )
+        .await?;
+
+        info!("Admin account successfully setup: {}", admin_did_hash);
+        Ok(())
+    }
+
+    async fn check_admin_account(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        let (exists, role_type): (bool, Option<String>) = self
+            .with_conn("NA", "check_admin_account", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    conn.query_row(
+                        "SELECT EXISTS (SELECT 1 FROM admins WHERE did_hash = ?1),
+                            (SELECT role_type FROM accounts WHERE did_hash = ?1)",
+                        params![did_hash],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                }
+            })
+            .await?;
+
+        Ok(exists
+            && matches!(
+                AccountType::from(role_type.unwrap_or_default().as_str()),
+                AccountType::RootAdmin | AccountType::Admin
+            ))
+    }
+
+    async fn add_admin_accounts(
+        &self,
+        accounts: Vec<String>,
+        acls: &MediatorACLSet,
+    ) -> Result<usize, MediatorError> {
+        if accounts.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "Number of admin accounts being added exceeds 100".to_string(),
+            ));
+        }
+
+        for account in &accounts {
+            debug!("Adding Admin account: {}", account);
+            self.setup_admin_account(account, AccountType::Admin, acls)
+                .await?;
+        }
+
+        Ok(accounts.len())
+    }
+
+    async fn strip_admin_accounts(&self, accounts: Vec<String>) -> Result<i32, MediatorError> {
+        if accounts.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "Number of admin accounts being removed exceeds 100".to_string(),
+            ));
+        }
+
+        self.with_conn("NA", "strip_admin_accounts", move |conn| {
+            let tx = conn.transaction()?;
+            let mut removed = 0;
+            for account in &accounts {
+                debug!("Removing Admin account: {}", account);
+                removed +=
+                    tx.execute("DELETE FROM admins WHERE did_hash = ?1", params![account])?;
+                _set_account_field(
+                    &tx,
+                    account,
+                    "role_type",
+                    Some(AccountType::Standard.into()),
+                )?;
+            }
+            tx.commit()?;
+            Ok(removed as i32)
+        })
+        .await
+    }
+
+    async fn list_admin_accounts(
+        &self,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<MediatorAdminList, MediatorError> {
+        if limit > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "limit cannot exceed 100".to_string(),
+            ));
+        }
+
+        let accounts: Vec<AdminAccount> = self
+            .with_conn("NA", "list_admin_accounts", move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT ad.did_hash, a.role_type
+                 FROM admins ad LEFT JOIN accounts a ON a.did_hash = ad.did_hash
+                 ORDER BY ad.did_hash LIMIT ?1 OFFSET ?2",
+                )?;
+                stmt.query_map(params![limit, cursor], |row| {
+                    Ok(AdminAccount {
+                        did_hash: row.get(0)?,
+                        _type: AccountType::from(
+                            row.get::<_, Option<String>>(1)?
+                                .unwrap_or_default()
+                                .as_str(),
+                        ),
+                    })
+                })?
+                .collect()
+            })
+            .await?;
+
+        Ok(MediatorAdminList {
+            cursor: _next_cursor(cursor as u64, limit as u64, accounts.len()) as u32,
+            accounts,
+        })
+    }
+
+    // ************************************************************************
+    // Audit log
+
+    async fn audit_log_add(
+        &self,
+        entry: &AuditLogEntry,
+        max_entries: usize,
+    ) -> Result<(), MediatorError> {
+        self.with_conn("NA", "audit_log_add", {
+                let entry = entry.clone();
+                move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO audit_log (timestamp, actor, action, target, acls_before, acls_after, detail)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    entry.timestamp as i64,
+                    entry.actor,
+                    action_name(entry.action),
+                    entry.target,
+                    entry.acls_before,
+                    entry.acls_after,
+                    entry.detail
+                ],
+            )?;
+            let id = tx.last_insert_rowid();
+            tx.execute(
+                "DELETE FROM audit_log WHERE id <= ?1",
+                params![id - max_entries as i64],
+            )?;
+            tx.commit()
+        }
+            })
+.await
+    }
+
+    async fn audit_log_list(
+        &self,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &AuditLogFilter,
+    ) -> Result<MediatorAuditLog, MediatorError> {
+        let cursor = match cursor {
+            Some(cursor) => cursor.parse::<i64>().map_err(|_| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!("Invalid audit log cursor ({})", cursor),
+                )
+            })?,
+            None => i64::MAX,
+        };
+
+        self.with_conn("NA", "audit_log_list", {
+            let filter = filter.clone();
+            move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT id, timestamp, actor, action, target, acls_before, acls_after, detail
+                 FROM audit_log WHERE id < ?1 ORDER BY id DESC",
+                )?;
+                let entries = stmt
+                    .query_map(params![cursor], |row| {
+                        Ok(AuditLogEntry {
+                            id: row.get::<_, i64>(0)?.to_string(),
+                            timestamp: row.get::<_, i64>(1)? as u64,
+                            actor: row.get(2)?,
+                            action: parse_action(&row.get::<_, String>(3)?)
+                                .unwrap_or(AuditAction::AclSet),
+                            target: row.get(4)?,
+                            acls_before: row.get(5)?,
+                            acls_after: row.get(6)?,
+                            detail: row.get(7)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok(audit_log_page(entries.into_iter(), limit, &filter))
+            }
+        })
+        .await
+    }
+
+    // ************************************************************************
+    // Push notification targets
+
+    async fn push_target_set(
+        &self,
+        did_hash: &str,
+        target: &PushTarget,
+    ) -> Result<(), MediatorError> {
+        let target = serde_json::to_string(target).map_err(|err| {
+            MediatorError::InternalError(
+                "NA".into(),
+                format!("Couldn't serialize push target. Reason: {}", err),
+            )
+        })?;
+
+        self.with_conn("NA", "push_target_set", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.execute(
+                    "INSERT INTO push_targets (did_hash, target) VALUES (?1, ?2)
+                 ON CONFLICT (did_hash) DO UPDATE SET target = excluded.target",
+                    params![did_hash, target],
+                )
+                .map(|_| ())
+            }
+        })
+        .await
+    }
+
+    async fn push_target_get(&self, did_hash: &str) -> Result<Option<PushTarget>, MediatorError> {
+        let target: Option<String> = self
+            .with_conn("NA", "push_target_get", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    conn.query_row(
+                        "SELECT target FROM push_targets WHERE did_hash = ?1",
+                        params![did_hash],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                }
+            })
+            .await?;
+
+        Ok(target.and_then(|target| serde_json::from_str(&target).ok()))
+    }
+
+    async fn push_target_remove(&self, did_hash: &str) -> Result<bool, MediatorError> {
+        self.with_conn("NA", "push_target_remove", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.execute(
+                    "DELETE FROM push_targets WHERE did_hash = ?1",
+                    params![did_hash],
+                )
+                .map(|removed| removed > 0)
+            }
+        })
+        .await
+    }
+
+    // ************************************************************************
+    // ACLs
+
+    async fn set_did_acl(
+        &self,
+        did_hash: &str,
+        acls: &MediatorACLSet,
+    ) -> Result<MediatorACLSet, MediatorError> {
+        self.with_conn("NA", "set_acl", {
+            let did_hash = did_hash.to_string();
+            let acls = acls.clone();
+            move |conn| _set_account_field(conn, &did_hash, "acls", Some(acls.to_hex_string()))
+        })
+        .await?;
+
+        Ok(acls.to_owned())
+    }
+
+    async fn get_did_acl(&self, did_hash: &str) -> Result<Option<MediatorACLSet>, MediatorError> {
+        let acl: Option<String> = self
+            .with_conn("NA", "get_did_acl", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    conn.query_row(
+                        "SELECT acls FROM accounts WHERE did_hash = ?1",
+                        params![did_hash],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map(Option::flatten)
+                }
+            })
+            .await?;
+
+        acl.map(|acl| {
+            MediatorACLSet::from_hex_string(&acl)
+                .map_err(|e| MediatorError::InternalError(did_hash.into(), e.to_string()))
+        })
+        .transpose()
+    }
+
+    async fn get_did_acls(
+        &self,
+        dids: &[String],
+        mediator_acl_mode: AccessListModeType,
+    ) -> Result<MediatorACLGetResponse, MediatorError> {
+        if dids.len() > 100 {
+            return Err(MediatorError::DatabaseError(
+                "NA".to_string(),
+                "# of DIDs cannot exceed 100".to_string(),
+            ));
+        }
+
+        let mut acl_response = MediatorACLGetResponse {
+            acl_response: vec![],
+            mediator_acl_mode,
+        };
+        for did_hash in dids {
+            if let Some(acls) = self.get_did_acl(did_hash).await? {
+                acl_response.acl_response.push(MediatorACLExpanded {
+                    did_hash: did_hash.clone(),
+                    acl_value: acls.to_hex_string(),
+                    acls,
+                });
+            }
+        }
+
+        Ok(acl_response)
+    }
+
+    // ************************************************************************
+    // Access lists
+
+    async fn access_list_allowed(
+        &self,
+        to_hash: &str,
+        from_hash: Option<String>,
+    ) -> Result<bool, MediatorError> {
+        let Some(acl) = self.get_did_acl(to_hash).await? else {
+            debug!("ACL not found for DID: {}", to_hash);
+            return Ok(false);
+        };
+
+        let Some(from_hash) = from_hash else {
+            // Anonymous Message
+            return Ok(acl.get_anon_receive().0);
+        };
+
+        let exists: bool = self
+            .with_conn("NA", "access_list_allowed", {
+                let to_hash = to_hash.to_string();
+                move |conn| {
+                    conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM access_lists WHERE did_hash = ?1 AND member = ?2)",
+                params![to_hash, from_hash],
+                |row| row.get(0),
+            )
+                }
+            })
+            .await?;
+
+        if acl.get_access_list_mode().0 == AccessListModeType::ExplicitAllow {
+            Ok(exists)
+        } else {
+            Ok(!exists)
+        }
+    }
+
+    async fn access_list_list(
+        &self,
+        did_hash: &str,
+        cursor: u64,
+    ) -> Result<MediatorAccessListListResponse, MediatorError> {
+        let hashes: Vec<String> = self
+            .with_conn("NA", "access_list_list", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT member FROM access_lists WHERE did_hash = ?1
+                 ORDER BY member LIMIT 100 OFFSET ?2",
+                    )?;
+                    stmt.query_map(params![did_hash, cursor as i64], |row| row.get(0))?
+                        .collect()
+                }
+            })
+            .await?;
+
+        Ok(MediatorAccessListListResponse {
+            cursor: Some(_next_cursor(cursor, 100, hashes.len())),
+            did_hashes: hashes,
+        })
+    }
+
+    async fn access_list_count(&self, did_hash: &str) -> Result<usize, MediatorError> {
+        self.with_conn("NA", "access_list_count", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.query_row(
+                    "SELECT COUNT(*) FROM access_lists WHERE did_hash = ?1",
+                    params![did_hash],
+                    |row| row.get::<_, i64>(0),
+                )
+            }
+        })
+        .await
+        .map(|count| count as usize)
+    }
+
+    async fn access_list_add(
+        &self,
+        access_list_limit: usize,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListAddResponse, MediatorError> {
+        let count = self.access_list_count(did_hash).await?;
+        let space = access_list_limit.saturating_sub(count);
+        let truncated = hashes.len() > space;
+        let hashes = &hashes[..hashes.len().min(space)];
+
+        self.with_conn("NA", "access_list_add", {
+            let did_hash = did_hash.to_string();
+            let hashes = hashes.to_vec();
+            move |conn| {
+                let tx = conn.transaction()?;
+                for hash in hashes {
+                    tx.execute(
+                        "INSERT OR IGNORE INTO access_lists (did_hash, member) VALUES (?1, ?2)",
+                        params![did_hash, hash],
+                    )?;
+                }
+                tx.commit()
+            }
+        })
+        .await?;
+
+        Ok(MediatorAccessListAddResponse {
+            did_hashes: hashes.to_vec(),
+            truncated,
+        })
+    }
+
+    async fn access_list_remove(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<usize, MediatorError> {
+        self.with_conn("NA", "access_list_remove", {
+            let did_hash = did_hash.to_string();
+            let hashes = hashes.to_vec();
+            move |conn| {
+                let tx = conn.transaction()?;
+                let mut removed = 0;
+                for hash in hashes {
+                    removed += tx.execute(
+                        "DELETE FROM access_lists WHERE did_hash = ?1 AND member = ?2",
+                        params![did_hash, hash],
+                    )?;
+                }
+                tx.commit()?;
+                Ok(removed)
+            }
+        })
+        .await
+    }
+
+    async fn access_list_clear(&self, did_hash: &str) -> Result<(), MediatorError> {
+        self.with_conn("NA", "access_list_clear", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.execute(
+                    "DELETE FROM access_lists WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn access_list_get(
+        &self,
+        did_hash: &str,
+        hashes: &[String],
+    ) -> Result<MediatorAccessListGetResponse, MediatorError> {
+        let did_hashes = self
+            .with_conn("NA", "access_list_get", {
+                let did_hash = did_hash.to_string();
+                let hashes = hashes.to_vec();
+                move |conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT 1 FROM access_lists WHERE did_hash = ?1 AND member = ?2",
+                    )?;
+                    let mut found = Vec::new();
+                    for hash in hashes {
+                        if stmt.exists(params![did_hash, hash])? {
+                            found.push(hash.clone());
+                        }
+                    }
+                    Ok(found)
+                }
+            })
+            .await?;
+
+        Ok(MediatorAccessListGetResponse { did_hashes })
+    }
+
+    // ************************************************************************
+    // Coordinate Mediation keylists
+
+    async fn keylist_add(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        self.with_conn("NA", "keylist_add", {
+                let did_hash = did_hash.to_string();
+                let recipient_did = recipient_did.to_string();
+                move |conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO mediation_keylists (did_hash, recipient_did) VALUES (?1, ?2)",
+                params![did_hash, recipient_did],
+            )
+        }
+            })
+.await
+        .map(|added| added > 0)
+    }
+
+    async fn keylist_remove(
+        &self,
+        did_hash: &str,
+        recipient_did: &str,
+    ) -> Result<bool, MediatorError> {
+        self.with_conn("NA", "keylist_remove", {
+            let did_hash = did_hash.to_string();
+            let recipient_did = recipient_did.to_string();
+            move |conn| {
+                conn.execute(
+                    "DELETE FROM mediation_keylists WHERE did_hash = ?1 AND recipient_did = ?2",
+                    params![did_hash, recipient_did],
+                )
+            }
+        })
+        .await
+        .map(|removed| removed > 0)
+    }
+
+    async fn keylist_list(
+        &self,
+        did_hash: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<String>, usize), MediatorError> {
+        self.with_conn("NA", "keylist_list", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                let total: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM mediation_keylists WHERE did_hash = ?1",
+                    params![did_hash],
+                    |row| row.get(0),
+                )?;
+
+                let mut stmt = conn.prepare(
+                    "SELECT recipient_did FROM mediation_keylists WHERE did_hash = ?1
+                 ORDER BY recipient_did LIMIT ?2 OFFSET ?3",
+                )?;
+                let dids = stmt
+                    .query_map(params![did_hash, limit as i64, offset as i64], |row| {
+                        row.get(0)
+                    })?
+                    .collect::<Result<Vec<String>, _>>()?;
+
+                Ok((dids, total as usize))
+            }
+        })
+        .await
+    }
+
+    // ************************************************************************
+    // Sessions
+
+    async fn create_session(&self, session: &Session) -> Result<(), MediatorError> {
+        self.with_conn(&session.session_id, "create_session", {
+            let session_id = session.session_id.clone();
+            let challenge = session.challenge.clone();
+            let state = session.state.to_string();
+            let did = session.did.clone();
+            move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                "INSERT OR REPLACE INTO sessions (session_id, challenge, state, did, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    session_id,
+                    challenge,
+                    state,
+                    did,
+                    _now_secs() + 900
+                ],
+            )?;
+                _incr_global(&tx, "SESSIONS_CREATED", 1)?;
+                tx.commit()
+            }
+        })
+        .await?;
+
+        debug!("Session created: {:?}", session);
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str, did: &str) -> Result<Session, MediatorError> {
+        let (session_db, did_db, linked_dids) = self
+            .with_conn(session_id, "get_session", {
+                let session_id = session_id.to_string();
+                let did = did.to_string();
+                move |conn| {
+                    let session_db: Option<(String, String, String)> = conn
+                        .query_row(
+                            "SELECT challenge, state, did FROM sessions
+                     WHERE session_id = ?1 AND expires_at > ?2",
+                            params![session_id, _now_secs()],
+                            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                        )
+                        .optional()?;
+                    let did_db: Option<(Option<String>, Option<String>)> = conn
+                        .query_row(
+                            "SELECT role_type, acls FROM accounts WHERE did_hash = ?1",
+                            params![digest(did)],
+                            |row| Ok((row.get(0)?, row.get(1)?)),
+                        )
+                        .optional()?;
+                    let linked_dids: Vec<String> = conn
+                        .prepare("SELECT did FROM session_linked_dids WHERE session_id = ?1")?
+                        .query_map(params![session_id], |row| row.get(0))?
+                        .collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok((session_db, did_db, linked_dids))
+                }
+            })
+            .await?;
+
+        let Some((challenge, state, did)) = session_db else {
+            warn!(
+                "{}: No challenge found when retrieving session({})!",
+                session_id, session_id
+            );
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No challenge found when retrieving session!".into(),
+            ));
+        };
+
+        let (role_type, acls) = did_db.unwrap_or_default();
+        let Some(role_type) = role_type else {
+            warn!("{}: Error parsing role_type!", session_id);
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No role_type found when retrieving session!".into(),
+            ));
+        };
+        let Some(acls) = acls.and_then(|acls| u64::from_str_radix(&acls, 16).ok()) else {
+            warn!("{}: Error parsing acls!", session_id);
+            return Err(MediatorError::SessionError(
+                session_id.into(),
+                "No ACL found when retrieving session!".into(),
+            ));
+        };
+
+        Ok(Session {
+            session_id: session_id.into(),
+            challenge,
+            state: SessionState::try_from(&state)?,
+            did_hash: digest(&did),
+            did,
+            account_type: AccountType::from(role_type.as_str()),
+            acls: MediatorACLSet::from_u64(acls),
+            linked_dids,
+            ..Default::default()
+        })
+    }
+
+    async fn update_session_authenticated(
+        &self,
+        old_session_id: &str,
+        new_session_id: &str,
+        did_hash: &str,
+    ) -> Result<(), MediatorError> {
+        self.with_conn(old_session_id, "update_session_authenticated", {
+            let old_session_id = old_session_id.to_string();
+            let new_session_id = new_session_id.to_string();
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                let tx = conn.transaction()?;
+                tx.execute(
+                    "DELETE FROM sessions WHERE session_id = ?1",
+                    params![new_session_id],
+                )?;
+                let updated = tx.execute(
+                    "UPDATE sessions SET session_id = ?1, state = ?2, expires_at = ?3
+                 WHERE session_id = ?4 AND expires_at > ?5",
+                    params![
+                        new_session_id,
+                        SessionState::Authenticated.to_string(),
+                        _now_secs() + SESSION_EXPIRY,
+                        old_session_id,
+                        _now_secs()
+                    ],
+                )?;
+                if updated == 0 {
+                    return Err(rusqlite::Error::QueryReturnedNoRows);
+                }
+                _incr_global(&tx, "SESSIONS_SUCCESS", 1)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO known_dids (did_hash) VALUES (?1)",
+                    params![did_hash],
+                )?;
+                tx.commit()
+            }
+        })
+        .await
+    }
+
+    async fn session_link_challenge(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+        challenge: &str,
+    ) -> Result<(), MediatorError> {
+        self.with_conn(session_id, "session_link_challenge", {
+                let session_id = session_id.to_string();
+                let did_hash = did_hash.to_string();
+                let challenge = challenge.to_string();
+                move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO session_link_challenges (session_id, did_hash, challenge, expires_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![session_id, did_hash, challenge, _now_secs() + 900],
+            )?;
+            Ok(())
+        }
+            })
+.await
+    }
+
+    async fn session_link_did(
+        &self,
+        session_id: &str,
+        did: &str,
+        challenge: &str,
+    ) -> Result<bool, MediatorError> {
+        let did_hash = digest(did);
+
+        self.with_conn(session_id, "session_link_did", {
+                let session_id = session_id.to_string();
+                let did = did.to_string();
+                let challenge = challenge.to_string();
+                move |conn| {
+            let tx = conn.transaction()?;
+            let stored: Option<String> = tx
+                .query_row(
+                    "DELETE FROM session_link_challenges
+                     WHERE session_id = ?1 AND did_hash = ?2 AND expires_at > ?3
+                     RETURNING challenge",
+                    params![session_id, did_hash, _now_secs()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if stored.as_deref() != Some(challenge.as_str()) {
+                tx.commit()?;
+                return Ok(false);
+            }
+
+            tx.execute(
+                "INSERT OR IGNORE INTO session_linked_dids (session_id, did, did_hash) VALUES (?1, ?2, ?3)",
+                params![session_id, did, did_hash],
+            )?;
+            tx.commit()?;
+            Ok(true)
+        }
+            })
+.await
+    }
+
+    // ************************************************************************
+    // OOB Discovery
+
+    async fn oob_discovery_store(
+        &self,
+        did_hash: &str,
+        invite: &Message,
+        oob_invite_ttl: u64,
+    ) -> Result<String, MediatorError> {
+        let now = _now_secs() as u64;
+        let expire_at = match invite.expires_time {
+            Some(expiry) if expiry <= now + oob_invite_ttl => expiry,
+            _ => now + oob_invite_ttl,
+        };
+
+        let base64_invite = match serde_json::to_string(invite) {
+            Ok(msg) => BASE64_URL_SAFE_NO_PAD.encode(msg),
+            Err(err) => {
+                error!("serializing error on Message. {}", err);
+                return Err(MediatorError::InternalError(
+                    "NA".into(),
+                    format!("serializing error on Message. {}", err),
+                ));
+            }
+        };
+        let invite_hash = digest(&base64_invite);
+
+        self.with_conn("NA", "oob_discovery_store", {
+                let invite_hash = invite_hash.clone();
+                let did_hash = did_hash.to_string();
+                move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT OR REPLACE INTO oob_invites (oob_id, invite, expires_at) VALUES (?1, ?2, ?3)",
+                params![invite_hash, base64_invite, expire_at as i64],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO oob_invite_creators (did_hash, oob_id) VALUES (?1, ?2)",
+                params![did_hash, invite_hash],
+            )?;
+            _incr_global(&tx, "OOB_INVITES_CREATED", 1)?;
+            tx.commit()
+        }
+            })
+.await?;
+
+        info!("OOB Invitation ID({}) created", invite_hash);
+        Ok(invite_hash)
+    }
+
+    async fn oob_discovery_get(&self, oob_id: &str) -> Result<Option<String>, MediatorError> {
+        self.with_conn("NA", "oob_discovery_get", {
+            let oob_id = oob_id.to_string();
+            move |conn| {
+                let invitation = conn
+                    .query_row(
+                        "SELECT invite FROM oob_invites WHERE oob_id = ?1 AND expires_at > ?2",
+                        params![oob_id, _now_secs()],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                _incr_global(conn, "OOB_INVITES_CLAIMED", 1)?;
+                Ok(invitation)
+            }
+        })
+        .await
+    }
+
+    async fn oob_discovery_delete(&self, oob_id: &str) -> Result<bool, MediatorError> {
+        self.with_conn("NA", "oob_discovery_delete", {
+            let oob_id = oob_id.to_string();
+            move |conn| conn.execute("DELETE FROM oob_invites WHERE oob_id = ?1", params![oob_id])
+        })
+        .await
+        .map(|deleted| deleted > 0)
+    }
+
+    // ************************************************************************
+    // Rate limiting
+
+    async fn rate_limit_take(
+        &self,
+        key: &str,
+        did_hash: Option<&str>,
+        per_minute: u32,
+    ) -> Result<Option<u64>, MediatorError> {
+        let account_limit = match did_hash {
+            Some(did_hash) => {
+                self.with_conn("NA", "rate_limit_take", {
+                    let did_hash = did_hash.to_string();
+                    move |conn| {
+                        conn.query_row(
+                            "SELECT rate_limit FROM accounts WHERE did_hash = ?1",
+                            params![did_hash],
+                            |row| row.get::<_, Option<i64>>(0),
+                        )
+                        .optional()
+                        .map(|limit| limit.flatten().map(|limit| limit as i32))
+                    }
+                })
+                .await?
+            }
+            None => None,
+        };
+        let Some(limit) = effective_limit(per_minute, account_limit) else {
+            return Ok(None);
+        };
+
+        let now = _now_ms() as u64;
+        let retry_after = {
+            let mut buckets = self
+                .rate_limits
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            // Drop buckets that are full again, as Redis does with the bucket expiry
+            buckets.retain(|_, bucket| !bucket.is_idle(now));
+            buckets
+                .entry(key.to_string())
+                .or_insert_with(|| TokenBucket::new(limit, now))
+                .take(limit, now)
+        };
+
+        if retry_after.is_some() {
+            self.with_conn("NA", "rate_limit_take", move |conn| {
+                _incr_global(conn, "RATE_LIMITED", 1)
+            })
+            .await?;
+        }
+        Ok(retry_after)
+    }
+
+    // ************************************************************************
+    // Live streaming
+
+    async fn streaming_clean_start(&self, uuid: &str) -> Result<(), MediatorError> {
+        let count = self
+            .with_conn("NA", "clean_start_streaming", {
+                let uuid = uuid.to_string();
+                move |conn| conn.execute("DELETE FROM streaming WHERE uuid = ?1", params![uuid])
+            })
+            .await?;
+
+        info!("clean_start_streaming() cleaned {} sessions", count);
+        Ok(())
+    }
+
+    async fn streaming_is_client_live(&self, did_hash: &str, force_delivery: bool) -> Vec<String> {
+        // Only a single streaming service shares this database, multiple websockets for a DID
+        // are handled by the streaming task
+        let row: Option<(String, bool)> = self
+            .with_conn("NA", "streaming_is_client_live", {
+                let did_hash = did_hash.to_string();
+                move |conn| {
+                    conn.query_row(
+                        "SELECT uuid, live FROM streaming WHERE did_hash = ?1",
+                        params![did_hash],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()
+                }
+            })
+            .await
+            .ok()
+            .flatten();
+
+        match row {
+            Some((uuid, live)) if live || force_delivery => vec![uuid],
+            _ => Vec::new(),
+        }
+    }
+
+    async fn streaming_publish_message(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+        message: &str,
+        force_delivery: bool,
+    ) -> Result<(), MediatorError> {
+        let mut channels = self
+            .channels
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(channel) = channels.get(stream_uuid) {
+            let record = PubSubRecord {
+                did_hash: did_hash.to_string(),
+                message: message.to_string(),
+                force_delivery,
+            };
+            if channel.send(record).is_err() {
+                // Subscriber has gone away, same as a PUBLISH with no subscribers
+                channels.remove(stream_uuid);
+            } else {
+                debug!(
+                    "published message to channel({}) for did_hash({})",
+                    stream_uuid, did_hash
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn streaming_subscribe(&self, stream_uuid: &str) -> Result<PubSubStream, MediatorError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.channels
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(stream_uuid.to_string(), tx);
+
+        Ok(Box::pin(UnboundedReceiverStream::new(rx)))
+    }
+
+    async fn streaming_register_client(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self._streaming_set(did_hash, stream_uuid, false).await
+    }
+
+    async fn streaming_start_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self._streaming_set(did_hash, stream_uuid, true).await
+    }
+
+    async fn streaming_stop_live(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self._streaming_set(did_hash, stream_uuid, false).await
+    }
+
+    async fn streaming_deregister_client(
+        &self,
+        did_hash: &str,
+        _stream_uuid: &str,
+    ) -> Result<(), MediatorError> {
+        self.with_conn("NA", "streaming_deregister_client", {
+            let did_hash = did_hash.to_string();
+            move |conn| {
+                conn.execute(
+                    "DELETE FROM streaming WHERE did_hash = ?1",
+                    params![did_hash],
+                )?;
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    // ************************************************************************
+    // Statistics
+
+    async fn get_db_metadata(&self) -> Result<MetadataStats, MediatorError> {
+        let counters: Vec<(String, i64)> = self
+            .with_conn("NA", "get_db_metadata", move |conn| {
+                let mut stmt = conn.prepare("SELECT key, value FROM global")?;
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect()
+            })
+            .await?;
+
+        let mut stats = MetadataStats::default();
+        for (k, v) in counters {
+            match k.as_str() {
+                "RECEIVED_BYTES" => stats.received_bytes = v,
+                "SENT_BYTES" => stats.sent_bytes = v,
+                "DELETED_BYTES" => stats.deleted_bytes = v,
+                "RECEIVED_COUNT" => stats.received_count = v,
+                "SENT_COUNT" => stats.sent_count = v,
+                "DELETED_COUNT" => stats.deleted_count = v,
+                "WEBSOCKET_OPEN" => stats.websocket_open = v,
+                "WEBSOCKET_CLOSE" => stats.websocket_close = v,
+                "SESSIONS_CREATED" => stats.sessions_created = v,
+                "SESSIONS_SUCCESS" => stats.sessions_success = v,
+                "OOB_INVITES_CREATED" => stats.oob_invites_created = v,
+                "OOB_INVITES_CLAIMED" => stats.oob_invites_claimed = v,
+                "RATE_LIMITED" => stats.rate_limited = v,
+                _ => {}
+            }
+        }
+
+        Ok(stats)
+    }
+
+    async fn get_expiry_backlog(&self, now: u64) -> Result<usize, MediatorError> {
+        self.with_conn("INTERNAL", "get_expiry_backlog", move |conn| {
+            conn.query_row(
+                "SELECT COUNT(DISTINCT expires_at) FROM messages WHERE expires_at <= ?1",
+                params![now as i64],
+                |row| row.get::<_, i64>(0),
+            )
+        })
+        .await
+        .map(|count| count as usize)
+    }
+
+    async fn update_send_stats(&self, sent_bytes: i64) -> Result<(), MediatorError> {
+        self.with_conn("INTERNAL", "update_send_stats", move |conn| {
+            _incr_global(conn, "SENT_BYTES", sent_bytes)?;
+            _incr_global(conn, "SENT_COUNT", 1)
+        })
+        .await
+    }
+
+    async fn global_stats_increment_websocket_open(&self) -> Result<(), MediatorError> {
+        self.with_conn("INTERNAL", "update WEBSOCKET_OPEN", move |conn| {
+            _incr_global(conn, "WEBSOCKET_OPEN", 1)
+        })
+        .await
+    }
+
+    async fn global_stats_increment_websocket_close(&self) -> Result<(), MediatorError> {
+        self.with_conn("INTERNAL", "update WEBSOCKET_CLOSE", move |conn| {
+            _incr_global(conn, "WEBSOCKET_CLOSE", 1)
+        })
+        .await
+    }
+}
+
+impl SqliteStore {
+    /// Registers the DID against a streaming service, `live` enables live delivery
+    async fn _streaming_set(
+        &self,
+        did_hash: &str,
+        stream_uuid: &str,
+        live: bool,
+    ) -> Result<(), MediatorError> {
+        self.with_conn("NA", "streaming_set", {
+            let did_hash = did_hash.to_string();
+            let stream_uuid = stream_uuid.to_string();
+            move |conn| {
+                conn.execute(
+                    "INSERT INTO streaming (did_hash, uuid, live) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (did_hash) DO UPDATE SET uuid = excluded.uuid, live = excluded.live",
+                    params![did_hash, stream_uuid, live],
+                )?;
+                Ok(())
+            }
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _session() -> Session {
+        Session {
+            session_id: "test".into(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_store_fetch_delete() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let to_hash = digest("did:example:bob");
+        let from_hash = digest("did:example:alice");
+
+        let msg_id = store
+            .store_message(
+                "test",
+                "message one",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message two",
+                "did:example:bob",
+                None,
+                0,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+
+        let account = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(account.receive_queue_count, 2);
+        assert_eq!(account.receive_queue_bytes, 22);
+
+        let fetched = store
+            .fetch_messages("test", &to_hash, &FetchOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(fetched.success.len(), 2);
+        assert_eq!(fetched.success[0].msg_id, msg_id);
+        assert_eq!(fetched.success[0].from_address, Some(from_hash.clone()));
+
+        // Fetching after the first message only returns the second
+        let fetched = store
+            .fetch_messages(
+                "test",
+                &to_hash,
+                &FetchOptions {
+                    start_id: fetched.success[0].receive_id.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(fetched.success.len(), 1);
+        assert_eq!(fetched.success[0].from_address, Some("ANONYMOUS".into()));
+
+        // Only the sender or recipient can delete a message
+        assert!(store.delete_message(None, "other", &msg_id).await.is_err());
+        store
+            .delete_message(None, &from_hash, &msg_id)
+            .await
+            .unwrap();
+        assert!(store.delete_message(None, &to_hash, &msg_id).await.is_err());
+
+        let outbox = store
+            .list_messages(&from_hash, Folder::Outbox, None, 100)
+            .await
+            .unwrap();
+        assert!(outbox.is_empty());
+
+        // Second message has expired
+        assert_eq!(store.expire_messages(1).await.unwrap(), 1);
+        let status = store.get_status_reply("test", &to_hash).await.unwrap();
+        assert_eq!(status.message_count, 0);
+        assert_eq!(status.total_bytes, 0);
+        assert_eq!(status.newest_received_time, None);
+
+        let stats = store.get_db_metadata().await.unwrap();
+        assert_eq!(stats.received_count, 2);
+        assert_eq!(stats.deleted_count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_queue_byte_quota() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let to_hash = digest("did:example:bob");
+        let from_hash = digest("did:example:alice");
+        let quota = QueueByteQuota {
+            send: -1,
+            receive: 20,
+        };
+
+        // 11 bytes each, the second message would exceed the receive quota
+        store
+            .store_message(
+                "test",
+                "message one",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .store_message(
+                    "test",
+                    "message two",
+                    "did:example:bob",
+                    Some("did:example:alice"),
+                    u64::MAX >> 1,
+                    None,
+                    &quota,
+                )
+                .await,
+            Err(MediatorError::ServiceLimitError(..))
+        ));
+
+        // Nothing is written for a rejected message
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.receive_queue_count, 1);
+        assert_eq!(bob.receive_queue_bytes, 11);
+
+        // The account's own quota replaces the default
+        store
+            .account_change_queue_bytes_limits(&to_hash, None, Some(-1))
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message two",
+                "did:example:bob",
+                Some("did:example:alice"),
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.queue_receive_bytes_limit, Some(-1));
+
+        // Send quota
+        store
+            .account_change_queue_bytes_limits(&from_hash, Some(22), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            store
+                .store_message(
+                    "test",
+                    "message three",
+                    "did:example:bob",
+                    Some("did:example:alice"),
+                    u64::MAX >> 1,
+                    None,
+                    &QueueByteQuota::UNLIMITED,
+                )
+                .await,
+            Err(MediatorError::ServiceLimitError(..))
+        ));
+
+        // Anonymous messages only count against the receiver
+        store
+            .store_message(
+                "test",
+                "message three",
+                "did:example:bob",
+                None,
+                u64::MAX >> 1,
+                None,
+                &quota,
+            )
+            .await
+            .unwrap();
+
+        // -2 resets to the default quota
+        store
+            .account_change_queue_bytes_limits(&to_hash, None, Some(-2))
+            .await
+            .unwrap();
+        let bob = store.account_get(&to_hash).await.unwrap().unwrap();
+        assert_eq!(bob.queue_receive_bytes_limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_account_remove() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let to_hash = digest("did:example:bob");
+
+        store
+            .account_add(&to_hash, &MediatorACLSet::default(), None)
+            .await
+            .unwrap();
+        store
+            .store_message(
+                "test",
+                "message",
+                "did:example:bob",
+                None,
+                u64::MAX >> 1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+
+        store
+            .forward_queue_enqueue(
+                "test",
+                &ForwardTask::new("{}", "did:example:remote", Some("did:example:bob"), None, 0),
+            )
+            .await
+            .unwrap();
+        store
+            .forward_queue_enqueue(
+                "test",
+                &ForwardTask::new("{}", "did:example:remote", None, None, 0),
+            )
+            .await
+            .unwrap();
+
+        let removed = store
+            .account_remove(&_session(), &to_hash, false)
+            .await
+            .unwrap();
+        assert_eq!(removed.inbox_messages, 1);
+        assert_eq!(removed.forward_tasks, 1);
+        assert_eq!(store.get_forward_tasks_len().await.unwrap(), 1);
+        assert!(store.account_get(&to_hash).await.unwrap().is_none());
+        assert_eq!(store.account_list(0, 100).await.unwrap().accounts.len(), 0);
+        assert!(
+            store
+                .list_messages(&to_hash, Folder::Inbox, None, 100)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let store = SqliteStore::open(":memory:").unwrap();
+
+        assert!(
+            store
+                .rate_limit_take("ip", None, 1)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .rate_limit_take("ip", None, 1)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        store
+            .account_add("hash", &MediatorACLSet::default(), None)
+            .await
+            .unwrap();
+        store.account_change_rate_limit("hash", -1).await.unwrap();
+        assert_eq!(
+            store.account_get("hash").await.unwrap().unwrap().rate_limit,
+            Some(-1)
+        );
+        for _ in 0..3 {
+            assert!(
+                store
+                    .rate_limit_take("did", Some("hash"), 1)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+        assert_eq!(store.get_db_metadata().await.unwrap().rate_limited, 1);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let store = SqliteStore::open(":memory:").unwrap();
+
+        for action in [
+            AuditAction::AccountAdd,
+            AuditAction::AclSet,
+            AuditAction::AccountAdd,
+            AuditAction::AclSet,
+        ] {
+            store
+                .audit_log_add(
+                    &AuditLogEntry {
+                        id: String::new(),
+                        timestamp: 0,
+                        actor: "admin".into(),
+                        action,
+                        target: "target".into(),
+                        acls_before: None,
+                        acls_after: Some("00".into()),
+                        detail: None,
+                    },
+                    3,
+                )
+                .await
+                .unwrap();
+        }
+
+        // Capped to the newest 3 entries
+        let page = store
+            .audit_log_list(None, 2, &AuditLogFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].action, AuditAction::AclSet);
+        assert_eq!(page.entries[0].acls_after.as_deref(), Some("00"));
+        let page = store
+            .audit_log_list(page.cursor.as_deref(), 2, &AuditLogFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert!(page.cursor.is_none());
+
+        let filter = AuditLogFilter {
+            action: Some(AuditAction::AccountAdd),
+            ..Default::default()
+        };
+        let page = store.audit_log_list(None, 10, &filter).await.unwrap();
+        assert_eq!(page.entries.len(), 1);
+    }
+}
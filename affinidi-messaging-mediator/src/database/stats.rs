@@ -1,9 +1,15 @@
+use num_format::{Locale, ToFormattedString};
+use std::fmt::{self, Display, Formatter};
+
+#[cfg(feature = "redis")]
 use super::Database;
+#[cfg(feature = "redis")]
 use affinidi_messaging_mediator_common::errors::MediatorError;
+#[cfg(feature = "redis")]
 use itertools::Itertools;
-use num_format::{Locale, ToFormattedString};
+#[cfg(feature = "redis")]
 use redis::{Value, from_redis_value};
-use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "redis")]
 use tracing::{Level, debug, event};
 
 /// Statistics for the mediator
@@ -21,6 +27,7 @@ pub struct MetadataStats {
     pub sessions_success: i64,    // Total number of sessions successfully authenticated
     pub oob_invites_created: i64, // Total number of out-of-band invites created
     pub oob_invites_claimed: i64, // Total number of out-of-band invites claimed
+    pub rate_limited: i64,        // Total number of requests rejected by the rate limiter
 }
 
 impl Display for MetadataStats {
@@ -32,6 +39,7 @@ impl Display for MetadataStats {
     Storage: received({}), sent({}), deleted({}), current_queued({})
     Connections: ws_open({}) ws_close({}) ws_current({}) :: sessions_created({}), sessions_authenticated({})
     OOB Invites: created({}) claimed({})
+    Rate limited: requests({})
             "#,
             self.received_count.to_formatted_string(&Locale::en),
             self.sent_count.to_formatted_string(&Locale::en),
@@ -47,7 +55,8 @@ impl Display for MetadataStats {
             self.sessions_created.to_formatted_string(&Locale::en),
             self.sessions_success.to_formatted_string(&Locale::en),
             self.oob_invites_created.to_formatted_string(&Locale::en),
-            self.oob_invites_claimed.to_formatted_string(&Locale::en)
+            self.oob_invites_claimed.to_formatted_string(&Locale::en),
+            self.rate_limited.to_formatted_string(&Locale::en)
         )
     }
 }
@@ -68,10 +77,12 @@ impl MetadataStats {
             sessions_success: self.sessions_success - previous.sessions_success,
             oob_invites_created: self.oob_invites_created - previous.oob_invites_created,
             oob_invites_claimed: self.oob_invites_claimed - previous.oob_invites_claimed,
+            rate_limited: self.rate_limited - previous.rate_limited,
         }
     }
 }
 
+#[cfg(feature = "redis")]
 impl Database {
     /// Retrieves metadata statistics that are global to the mediator database
     /// This means it may include more than this mediator's messages
@@ -118,6 +129,7 @@ impl Database {
                 "SESSIONS_SUCCESS" => stats.sessions_success = v.parse().unwrap_or(0),
                 "OOB_INVITES_CREATED" => stats.oob_invites_created = v.parse().unwrap_or(0),
                 "OOB_INVITES_CLAIMED" => stats.oob_invites_claimed = v.parse().unwrap_or(0),
+                "RATE_LIMITED" => stats.rate_limited = v.parse().unwrap_or(0),
                 _ => {}
             }
         }
@@ -213,4 +225,23 @@ impl Database {
 
         Ok(result)
     }
+
+    /// Number of expiry timeslots in `MSG_EXPIRY` that are due at `now` (epoch seconds) but have
+    /// not been cleaned up yet by the MessageExpiryCleanupProcessor
+    pub async fn get_expiry_backlog(&self, now: u64) -> Result<usize, MediatorError> {
+        let mut con = self.0.get_async_connection().await?;
+
+        deadpool_redis::redis::cmd("ZCOUNT")
+            .arg("MSG_EXPIRY")
+            .arg("-inf")
+            .arg(now)
+            .query_async(&mut con)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "INTERNAL".into(),
+                    format!("Couldn't retrieve expiry backlog. Reason: {}", err),
+                )
+            })
+    }
 }
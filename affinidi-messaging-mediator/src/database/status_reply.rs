@@ -0,0 +1,84 @@
+use super::Database;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::protocols::message_pickup::MessagePickupStatusReply;
+use itertools::Itertools;
+use redis::{Value, from_redis_value};
+use tracing::{Level, event, warn};
+
+impl Database {
+    /// Message Pickup 3.0 status for a DID inbox (uses the `get_status_reply` function)
+    pub async fn get_status_reply(
+        &self,
+        session_id: &str,
+        did_hash: &str,
+    ) -> Result<MessagePickupStatusReply, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        let response: Vec<Value> = deadpool_redis::redis::cmd("FCALL")
+            .arg("get_status_reply")
+            .arg(1)
+            .arg(did_hash)
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                event!(
+                    Level::ERROR,
+                    "get_status_reply({}) failed. Reason: {}",
+                    did_hash,
+                    err
+                );
+                MediatorError::DatabaseError(
+                    session_id.into(),
+                    format!("get_status_reply({}) failed. Reason: {}", did_hash, err),
+                )
+            })?;
+
+        let mut status = MessagePickupStatusReply::default();
+
+        for (k, v) in response.into_iter().tuples() {
+            match from_redis_value::<String>(&k).unwrap_or("".into()).as_str() {
+                "newest_received" => {
+                    if let Ok(v) = from_redis_value::<String>(&v) {
+                        status.newest_received_time = _stream_id_to_secs(&v);
+                    }
+                }
+                "oldest_received" => {
+                    if let Ok(v) = from_redis_value::<String>(&v) {
+                        status.oldest_received_time = _stream_id_to_secs(&v);
+                    }
+                }
+                "message_count" => {
+                    if let Ok(v) = from_redis_value::<u32>(&v) {
+                        status.message_count = v;
+                    }
+                }
+                "queue_count" => continue,
+                "live_delivery" => {
+                    if let Ok(v) = from_redis_value::<bool>(&v) {
+                        status.live_delivery = v;
+                    }
+                }
+                "total_bytes" => {
+                    if let Ok(v) = from_redis_value::<u64>(&v) {
+                        status.total_bytes = v;
+                    }
+                }
+                "recipient_did" => continue,
+                _ => {
+                    warn!("Unknown key: ({:?}) with value: ({:?})", k, v);
+                }
+            }
+        }
+
+        Ok(status)
+    }
+}
+
+/// Converts a stream ID (`<milliseconds>-<sequence>`) to epoch seconds
+fn _stream_id_to_secs(stream_id: &str) -> Option<u64> {
+    let a: Vec<&str> = stream_id.split('-').collect();
+    if a.len() != 2 {
+        return None;
+    }
+    a[0].parse::<u64>().ok().map(|t| t / 1000)
+}
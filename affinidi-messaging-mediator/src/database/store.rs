@@ -1,7 +1,12 @@
+use crate::common::config::LimitsConfig;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "redis")]
 use super::Database;
 use affinidi_messaging_mediator_common::errors::MediatorError;
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "redis")]
 use sha256::digest;
+#[cfg(feature = "redis")]
 use tracing::{Instrument, Level, debug, event, info, span};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -12,10 +17,63 @@ pub struct MessageMetaData {
     pub timestamp: u128,
 }
 
+/// Default queue quotas in bytes, enforced by `store_message` for accounts that have no quota of their own
+/// NOTE: -1 means no limit
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QueueByteQuota {
+    pub send: i64,
+    pub receive: i64,
+}
+
+impl QueueByteQuota {
+    /// No quota is enforced, used for messages that were already accepted (or generated by the mediator)
+    pub const UNLIMITED: QueueByteQuota = QueueByteQuota {
+        send: -1,
+        receive: -1,
+    };
+}
+
+impl From<&LimitsConfig> for QueueByteQuota {
+    fn from(limits: &LimitsConfig) -> Self {
+        QueueByteQuota {
+            send: limits.queued_send_bytes_soft,
+            receive: limits.queued_receive_bytes_soft,
+        }
+    }
+}
+
+/// Would adding `bytes` to a queue currently holding `queued` bytes exceed the quota?
+/// - `account_quota` - The account's own quota, replaces `default_quota` when set
+pub(crate) fn quota_exceeded(
+    default_quota: i64,
+    account_quota: Option<i64>,
+    queued: i64,
+    bytes: i64,
+) -> bool {
+    let quota = account_quota.unwrap_or(default_quota);
+    quota >= 0 && queued + bytes > quota
+}
+
+/// Error returned when a message is rejected because a queue is over its quota
+/// - `queue` - "send" or "receive"
+pub(crate) fn quota_error(session_id: &str, queue: &str) -> MediatorError {
+    MediatorError::ServiceLimitError(
+        session_id.into(),
+        format!(
+            "The {} queue is full (byte quota exceeded). Try again later",
+            queue
+        ),
+    )
+}
+
+#[cfg(feature = "redis")]
 impl Database {
     /// Stores a message in the database
     /// Returns the message_id (hash of the message)
     /// - expires_at: The timestamp at which the message expires (since epoch in seconds)
+    /// - signed_by: Key ID of a verified signature over the message (stored in the metadata)
+    /// - quota: Default queue quotas in bytes, checked atomically with storing the message
+    #[allow(clippy::too_many_arguments)]
     pub async fn store_message(
         &self,
         session_id: &str,
@@ -23,6 +81,8 @@ impl Database {
         to_did: &str,
         from_did: Option<&str>,
         expires_at: u64,
+        signed_by: Option<&str>,
+        quota: &QueueByteQuota,
     ) -> Result<String, MediatorError> {
         let _span = span!(Level::DEBUG, "store_message", session_id = session_id);
         async move {
@@ -46,15 +106,27 @@ impl Database {
             );
 
             let mut conn = self.0.get_async_connection().await?;
-            deadpool_redis::redis::cmd("FCALL")
-            .arg("store_message")
+            let mut cmd = deadpool_redis::redis::cmd("FCALL");
+            cmd.arg("store_message")
                 .arg(1)
                 .arg(&message_hash)
                 .arg(message)
                 .arg(expires_at)
                 .arg(message.len())
                 .arg(&to_hash)
-                .arg(&from_hash).exec_async(&mut conn).await.map_err(|err| {
+                .arg(&from_hash)
+                .arg(quota.send)
+                .arg(quota.receive);
+            if let Some(signed_by) = signed_by {
+                cmd.arg(signed_by);
+            }
+            cmd.exec_async(&mut conn).await.map_err(|err| {
+                    let reason = err.to_string();
+                    if reason.contains("QUOTA_EXCEEDED") {
+                        info!("Message hash({}) rejected: {}", message_hash, reason);
+                        let queue = if reason.contains("send queue") { "send" } else { "receive" };
+                        return quota_error(session_id, queue);
+                    }
                     event!(Level::ERROR, "Couldn't store message in database: {}", err);
                     MediatorError::DatabaseError(
                         session_id.into(),
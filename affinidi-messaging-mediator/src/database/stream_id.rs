@@ -0,0 +1,51 @@
+//! Stream IDs (`<milliseconds>-<sequence>`) for the embedded backends
+//!
+//! The embedded backends order their queues the same way Redis orders the RECEIVE_Q and SEND_Q
+//! streams, so clients can keep using stream IDs as cursors regardless of the backend.
+
+/// Parses a stream ID (`<milliseconds>-<sequence>`), a bare `<milliseconds>` uses `default_seq`
+pub(crate) fn parse_stream_id(id: &str, default_seq: i64) -> Option<(i64, i64)> {
+    match id.split_once('-') {
+        Some((ms, seq)) => Some((ms.parse().ok()?, seq.parse().ok()?)),
+        None => Some((id.parse().ok()?, default_seq)),
+    }
+}
+
+/// Converts a Redis XRANGE style bound (`-`, `+`, `<id>` or exclusive `(<id>`) to an inclusive (ms, seq)
+pub(crate) fn range_bound(bound: &str, start: bool) -> Option<(i64, i64)> {
+    match bound {
+        "-" => return Some((0, 0)),
+        "+" => return Some((i64::MAX, i64::MAX)),
+        _ => {}
+    }
+
+    let (id, exclusive) = match bound.strip_prefix('(') {
+        Some(id) => (id, true),
+        None => (bound, false),
+    };
+    let (ms, seq) = parse_stream_id(id, if start { 0 } else { i64::MAX })?;
+
+    match (exclusive, start) {
+        (false, _) => Some((ms, seq)),
+        (true, true) if seq == i64::MAX => Some((ms + 1, 0)),
+        (true, true) => Some((ms, seq + 1)),
+        (true, false) if seq == 0 => Some((ms - 1, i64::MAX)),
+        (true, false) => Some((ms, seq - 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_bound() {
+        assert_eq!(range_bound("-", true), Some((0, 0)));
+        assert_eq!(range_bound("+", false), Some((i64::MAX, i64::MAX)));
+        assert_eq!(range_bound("100-2", true), Some((100, 2)));
+        assert_eq!(range_bound("(100-2", true), Some((100, 3)));
+        assert_eq!(range_bound("(100-0", false), Some((99, i64::MAX)));
+        assert_eq!(range_bound("100", false), Some((100, i64::MAX)));
+        assert_eq!(range_bound("abc", true), None);
+    }
+}
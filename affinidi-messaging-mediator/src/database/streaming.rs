@@ -1,3 +1,13 @@
+/*!
+ Database operations for live streaming
+
+ HASH KEY : GLOBAL_STREAMING:<did_hash>
+   field: streaming service UUID, value: TRUE|FALSE (live delivery enabled)
+   A DID is registered against every streaming service it has a websocket connected to
+
+ SET KEY : STREAMING_SESSIONS:<uuid>
+   DID hashes registered against the streaming service
+*/
 use crate::tasks::websocket_streaming::PubSubRecord;
 use affinidi_messaging_mediator_common::errors::MediatorError;
 use redis::{Value, from_redis_value};
@@ -58,50 +68,31 @@ impl Database {
     /// Checks if the given DID hash is live streaming
     /// did_hash: The DID hash to check
     /// force_delivery: If true, the message will be delivered even if the client is not live streaming
-    /// Returns the streaming service ID that the client is connecting to if the DID hash is live streaming
+    /// Returns the streaming service IDs that the client is connected to where the DID hash is live streaming
     pub async fn streaming_is_client_live(
         &self,
         did_hash: &str,
         force_delivery: bool,
-    ) -> Option<String> {
+    ) -> Vec<String> {
         let mut conn = match self.0.get_async_connection().await {
             Ok(conn) => conn,
             _ => {
                 error!("is_live_streaming(): Failed to get connection to Redis");
-                return None;
+                return Vec::new();
             }
         };
 
-        match deadpool_redis::redis::cmd("HGET")
-            .arg("GLOBAL_STREAMING")
-            .arg(did_hash)
-            .query_async::<Option<String>>(&mut conn)
+        match deadpool_redis::redis::cmd("HGETALL")
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .query_async::<Vec<(String, String)>>(&mut conn)
             .await
         {
-            Ok(response) => {
-                if let Some(row) = response {
-                    let parts: Vec<&str> = row.split(':').collect();
-
-                    if parts.len() == 2 {
-                        if parts[1] == "TRUE" {
-                            // Client is live streaming
-                            Some(parts[0].to_string())
-                        } else if force_delivery {
-                            // Client is not live streaming, but we are forcing delivery
-                            Some(parts[0].to_string())
-                        } else {
-                            // Client is not live streaming
-                            None
-                        }
-                    } else {
-                        // Invalid Redis data
-                        None
-                    }
-                } else {
-                    // No Redis data found.
-                    None
-                }
-            }
+            Ok(response) => response
+                .into_iter()
+                // Client is live streaming, or not live streaming but we are forcing delivery
+                .filter(|(_, live)| live == "TRUE" || force_delivery)
+                .map(|(stream_uuid, _)| stream_uuid)
+                .collect(),
             Err(err) => {
                 event!(
                     Level::ERROR,
@@ -109,7 +100,7 @@ impl Database {
                     did_hash,
                     err
                 );
-                None
+                Vec::new()
             }
         }
     }
@@ -200,9 +191,9 @@ impl Database {
             .arg(["STREAMING_SESSIONS:", stream_uuid].concat())
             .arg(did_hash)
             .cmd("HSET")
-            .arg("GLOBAL_STREAMING")
-            .arg(did_hash)
-            .arg([stream_uuid, ":FALSE"].concat())
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .arg(stream_uuid)
+            .arg("FALSE")
             .exec_async(&mut conn)
             .await
         {
@@ -239,9 +230,9 @@ impl Database {
         let mut conn = self.0.get_async_connection().await?;
 
         match deadpool_redis::redis::cmd("HSET")
-            .arg("GLOBAL_STREAMING")
-            .arg(did_hash)
-            .arg([stream_uuid, ":", "TRUE"].concat())
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .arg(stream_uuid)
+            .arg("TRUE")
             .exec_async(&mut conn)
             .await
         {
@@ -281,9 +272,9 @@ impl Database {
         let mut conn = self.0.get_async_connection().await?;
 
         match deadpool_redis::redis::cmd("HSET")
-            .arg("GLOBAL_STREAMING")
-            .arg(did_hash)
-            .arg([stream_uuid, ":", "FALSE"].concat())
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .arg(stream_uuid)
+            .arg("FALSE")
             .exec_async(&mut conn)
             .await
         {
@@ -328,8 +319,8 @@ impl Database {
             .arg(["STREAMING_SESSIONS:", stream_uuid].concat())
             .arg(did_hash)
             .cmd("HDEL")
-            .arg("GLOBAL_STREAMING")
-            .arg(did_hash)
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .arg(stream_uuid)
             .exec_async(&mut conn)
             .await
         {
@@ -356,4 +347,33 @@ impl Database {
             }
         }
     }
+
+    /// Removes the live streaming registrations of a DID (if any)
+    /// Returns the number of registrations removed
+    pub(crate) async fn streaming_remove_did(
+        &self,
+        did_hash: &str,
+    ) -> Result<usize, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        let stream_uuids: Vec<String> = deadpool_redis::redis::cmd("HKEYS")
+            .arg(["GLOBAL_STREAMING:", did_hash].concat())
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!(
+                        "streaming_remove_did() for did_hash({}) failed. Reason: {}",
+                        did_hash, err
+                    ),
+                )
+            })?;
+
+        for stream_uuid in &stream_uuids {
+            self.streaming_deregister_client(did_hash, stream_uuid)
+                .await?;
+        }
+        Ok(stream_uuids.len())
+    }
 }
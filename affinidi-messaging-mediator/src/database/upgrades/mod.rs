@@ -1,11 +1,130 @@
 /*!
  * Handles upgrades from one version to another for the database schema
+ *
+ * Each [Migration] declares the schema version it upgrades from and to, a forward step and an
+ * optional reverse step. When the mediator starts, the migrations between the database
+ * SCHEMA_VERSION and the mediator version are run in order. Versions without a migration have no
+ * schema changes, the SCHEMA_VERSION is simply updated.
+ *
+ * Only one mediator replica can upgrade the database at a time, this is enforced by the
+ * `SCHEMA_UPGRADE_LOCK` key. Other replicas wait for the lock to be released.
+ * The lock is renewed while the migrations run, the upgrade is aborted if it can't be renewed.
  */
 
-use super::Database;
+use super::{Database, mediator_store::SchemaUpgradeMode};
+use crate::common::config::Config;
 use affinidi_messaging_mediator_common::errors::MediatorError;
+use async_trait::async_trait;
+use semver::Version;
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 pub(crate) mod v0_10_0;
+pub(crate) mod v0_10_1;
+
+/// Key used to stop multiple mediators from upgrading the database at the same time
+const UPGRADE_LOCK: &str = "SCHEMA_UPGRADE_LOCK";
+/// Lock expiry, in case a mediator dies while upgrading
+const UPGRADE_LOCK_TTL_MS: u64 = 300_000;
+/// How often the lock is renewed while upgrading, well within the lock expiry
+const UPGRADE_LOCK_RENEW_MS: u64 = UPGRADE_LOCK_TTL_MS / 3;
+
+/// A single step in the schema upgrade chain
+#[async_trait]
+pub(crate) trait Migration: Send + Sync {
+    /// Schema version this migration upgrades from
+    #[allow(clippy::wrong_self_convention)]
+    fn from_version(&self) -> Version;
+
+    /// Schema version this migration upgrades to
+    fn to_version(&self) -> Version;
+
+    /// Short description of the changes, used in logs and dry-run reports
+    fn description(&self) -> &'static str;
+
+    /// Keys that the forward (or reverse) step would touch, used in dry-run reports
+    async fn touched_keys(
+        &self,
+        database: &Database,
+        config: &Config,
+    ) -> Result<Vec<String>, MediatorError>;
+
+    /// Upgrades the schema from `from_version` to `to_version`
+    async fn forward(&self, database: &Database, config: &Config) -> Result<(), MediatorError>;
+
+    /// Does this migration have a reverse step?
+    fn reversible(&self) -> bool {
+        false
+    }
+
+    /// Rolls the schema back from `to_version` to `from_version`
+    async fn reverse(&self, _database: &Database, _config: &Config) -> Result<(), MediatorError> {
+        Err(MediatorError::DatabaseError(
+            "NA".into(),
+            format!(
+                "The upgrade from ({}) to ({}) can't be reversed",
+                self.from_version(),
+                self.to_version()
+            ),
+        ))
+    }
+}
+
+/// All known migrations, oldest first
+fn registry() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(v0_10_0::Upgrade0_10_0),
+        Box::new(v0_10_1::Upgrade0_10_1),
+    ]
+}
+
+/// Returns the migrations to run (in order) to move the schema from `current` to `target`
+/// Upgrades run forward steps oldest first, rollbacks run reverse steps newest first
+fn plan<'a>(
+    registry: &'a [Box<dyn Migration>],
+    current: &Version,
+    target: &Version,
+) -> Result<Vec<&'a dyn Migration>, MediatorError> {
+    if current <= target {
+        return Ok(registry
+            .iter()
+            .filter(|m| m.to_version() > *current && m.to_version() <= *target)
+            .map(|m| m.as_ref())
+            .collect());
+    }
+
+    let steps: Vec<&dyn Migration> = registry
+        .iter()
+        .rev()
+        .filter(|m| m.to_version() <= *current && m.to_version() > *target)
+        .map(|m| m.as_ref())
+        .collect();
+
+    if let Some(step) = steps.iter().find(|m| !m.reversible()) {
+        return Err(MediatorError::DatabaseError(
+            "NA".into(),
+            format!(
+                "Can't roll back the database schema to ({}): the upgrade from ({}) to ({}) can't be reversed",
+                target,
+                step.from_version(),
+                step.to_version()
+            ),
+        ));
+    }
+
+    Ok(steps)
+}
+
+fn _parse_version(version: &str, context: &str) -> Result<Version, MediatorError> {
+    Version::parse(version).map_err(|e| {
+        MediatorError::InternalError(
+            "NA".into(),
+            format!("Couldn't parse {} ({}). Reason: {}", context, version, e),
+        )
+    })
+}
 
 impl Database {
     pub(crate) async fn upgrade_change_schema_version(
@@ -23,4 +142,379 @@ impl Database {
                 )
             })
     }
+
+    /// Runs the schema upgrade chain
+    /// - `Apply`: Upgrades the schema to the mediator version
+    /// - `DryRun`: Reports the migrations and the keys they would touch, nothing is changed
+    /// - `RollbackTo`: Runs the reverse steps back to the given version
+    pub(crate) async fn upgrade_schema(
+        &self,
+        config: &Config,
+        mode: &SchemaUpgradeMode,
+    ) -> Result<(), MediatorError> {
+        let mediator_version = _parse_version(env!("CARGO_PKG_VERSION"), "mediator version")?;
+
+        match mode {
+            SchemaUpgradeMode::DryRun => {
+                return self._run_migrations(config, &mediator_version, true).await;
+            }
+            SchemaUpgradeMode::Apply => {
+                // Don't take the lock when there is nothing to do
+                if self._get_schema_version().await?.as_ref() == Some(&mediator_version) {
+                    info!("Database schema version ({}) is good", mediator_version);
+                    return Ok(());
+                }
+            }
+            SchemaUpgradeMode::RollbackTo(_) => {}
+        }
+
+        let target = match mode {
+            SchemaUpgradeMode::RollbackTo(version) => {
+                let target = _parse_version(version, "rollback version")?;
+                if target > mediator_version {
+                    return Err(MediatorError::ConfigError(
+                        "NA".into(),
+                        format!(
+                            "Can't roll back to ({}), it is newer than this mediator ({})",
+                            target, mediator_version
+                        ),
+                    ));
+                }
+                target
+            }
+            _ => mediator_version,
+        };
+
+        let lock = self._upgrade_lock().await?;
+        // Stops the migrations if the lock is lost, another mediator could take it over
+        let result = tokio::select! {
+            result = self._run_migrations(config, &target, false) => result,
+            err = self._upgrade_lock_keepalive(&lock) => {
+                error!("Schema upgrade aborted. Reason: {}", err);
+                Err(err)
+            }
+        };
+        self._upgrade_unlock(&lock).await;
+
+        result
+    }
+
+    /// Runs (or reports when `dry_run`) the migrations from the current schema version to `target`
+    /// Re-reads the schema version, another mediator may have upgraded it while waiting on the lock
+    async fn _run_migrations(
+        &self,
+        config: &Config,
+        target: &Version,
+        dry_run: bool,
+    ) -> Result<(), MediatorError> {
+        let prefix = if dry_run { "[dry-run] " } else { "" };
+
+        let Some(current) = self._get_schema_version().await? else {
+            warn!(
+                "{}Unknown database schema version. Setting to ({})",
+                prefix, target
+            );
+            if dry_run {
+                info!("{}Would touch key: GLOBAL (SCHEMA_VERSION)", prefix);
+                return Ok(());
+            }
+            return self
+                .upgrade_change_schema_version(&target.to_string())
+                .await;
+        };
+
+        if current == *target {
+            info!("{}Database schema version ({}) is good", prefix, current);
+            return Ok(());
+        }
+
+        let mediator_version = _parse_version(env!("CARGO_PKG_VERSION"), "mediator version")?;
+        if current > mediator_version {
+            // This mediator doesn't know the migrations of newer versions
+            warn!(
+                "{}Database schema version ({}) is newer than this mediator ({}). Run --rollback-to from the newer mediator to downgrade the schema",
+                prefix, current, mediator_version
+            );
+            return Ok(());
+        }
+
+        let registry = registry();
+        let steps = plan(&registry, &current, target)?;
+        let forward = current < *target;
+        warn!(
+            "{}Database schema version ({}) doesn't match target version ({}). {} migration(s) to run",
+            prefix,
+            current,
+            target,
+            steps.len()
+        );
+
+        for step in steps {
+            let (from, to) = if forward {
+                (step.from_version(), step.to_version())
+            } else {
+                (step.to_version(), step.from_version())
+            };
+            info!(
+                "{}Migration ({}) -> ({}): {}",
+                prefix,
+                from,
+                to,
+                step.description()
+            );
+
+            if dry_run {
+                for key in step.touched_keys(self, config).await? {
+                    info!("{}Would touch key: {}", prefix, key);
+                }
+                continue;
+            }
+
+            let result = if forward {
+                step.forward(self, config).await
+            } else {
+                step.reverse(self, config).await
+            };
+            if let Err(err) = result {
+                error!("Migration ({}) -> ({}) failed. Reason: {}", from, to, err);
+                return Err(err);
+            }
+            self.upgrade_change_schema_version(&to.to_string()).await?;
+        }
+
+        if dry_run {
+            info!(
+                "{}Would touch key: GLOBAL (SCHEMA_VERSION = {})",
+                prefix, target
+            );
+            return Ok(());
+        }
+
+        self.upgrade_change_schema_version(&target.to_string())
+            .await?;
+        info!("Database schema version updated to ({})", target);
+        Ok(())
+    }
+
+    async fn _get_schema_version(&self) -> Result<Option<Version>, MediatorError> {
+        let mut conn = self.0.get_async_connection().await?;
+
+        let schema_version: Option<String> =
+            deadpool_redis::redis::Cmd::hget("GLOBAL", "SCHEMA_VERSION")
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| {
+                    MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!("Couldn't get database SCHEMA_VERSION: {}", e),
+                    )
+                })?;
+
+        schema_version
+            .map(|version| _parse_version(&version, "database SCHEMA_VERSION"))
+            .transpose()
+    }
+
+    /// Waits until this mediator holds the upgrade lock, returns the lock token
+    async fn _upgrade_lock(&self) -> Result<String, MediatorError> {
+        let token = Uuid::new_v4().to_string();
+        let mut conn = self.0.get_async_connection().await?;
+
+        loop {
+            let acquired: Option<String> = deadpool_redis::redis::cmd("SET")
+                .arg(UPGRADE_LOCK)
+                .arg(&token)
+                .arg("NX")
+                .arg("PX")
+                .arg(UPGRADE_LOCK_TTL_MS)
+                .query_async(&mut conn)
+                .await
+                .map_err(|e| {
+                    MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!("Couldn't take the schema upgrade lock: {}", e),
+                    )
+                })?;
+
+            if acquired.is_some() {
+                return Ok(token);
+            }
+
+            info!("Another mediator is upgrading the database schema, waiting...");
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Renews the upgrade lock held by `token` until it can't be renewed
+    /// Only returns (with the reason) when the lock has been lost or renewing it failed
+    async fn _upgrade_lock_keepalive(&self, token: &str) -> MediatorError {
+        let script = deadpool_redis::redis::Script::new(
+            r#"if redis.call('GET', KEYS[1]) == ARGV[1] then
+                return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+            else
+                return 0
+            end"#,
+        );
+
+        loop {
+            sleep(Duration::from_millis(UPGRADE_LOCK_RENEW_MS)).await;
+
+            let mut conn = match self.0.get_async_connection().await {
+                Ok(conn) => conn,
+                Err(err) => return err,
+            };
+
+            match script
+                .key(UPGRADE_LOCK)
+                .arg(token)
+                .arg(UPGRADE_LOCK_TTL_MS)
+                .invoke_async::<i64>(&mut conn)
+                .await
+            {
+                Ok(1) => debug!("Schema upgrade lock renewed"),
+                Ok(_) => {
+                    return MediatorError::DatabaseError(
+                        "NA".into(),
+                        "The schema upgrade lock is no longer held by this mediator".into(),
+                    );
+                }
+                Err(err) => {
+                    return MediatorError::DatabaseError(
+                        "NA".into(),
+                        format!("Couldn't renew the schema upgrade lock: {}", err),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Releases the upgrade lock if it is still held by `token`
+    async fn _upgrade_unlock(&self, token: &str) {
+        let script = deadpool_redis::redis::Script::new(
+            r#"if redis.call('GET', KEYS[1]) == ARGV[1] then
+                return redis.call('DEL', KEYS[1])
+            else
+                return 0
+            end"#,
+        );
+
+        let mut conn = match self.0.get_async_connection().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                warn!("Couldn't release the schema upgrade lock: {}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = script
+            .key(UPGRADE_LOCK)
+            .arg(token)
+            .invoke_async::<i64>(&mut conn)
+            .await
+        {
+            warn!("Couldn't release the schema upgrade lock: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestMigration(Version, Version, bool);
+
+    #[async_trait]
+    impl Migration for TestMigration {
+        fn from_version(&self) -> Version {
+            self.0.clone()
+        }
+
+        fn to_version(&self) -> Version {
+            self.1.clone()
+        }
+
+        fn description(&self) -> &'static str {
+            "test"
+        }
+
+        async fn touched_keys(
+            &self,
+            _database: &Database,
+            _config: &Config,
+        ) -> Result<Vec<String>, MediatorError> {
+            Ok(vec![])
+        }
+
+        async fn forward(
+            &self,
+            _database: &Database,
+            _config: &Config,
+        ) -> Result<(), MediatorError> {
+            Ok(())
+        }
+
+        fn reversible(&self) -> bool {
+            self.2
+        }
+    }
+
+    fn _registry() -> Vec<Box<dyn Migration>> {
+        vec![
+            Box::new(TestMigration(
+                Version::new(0, 9, 7),
+                Version::new(0, 10, 0),
+                false,
+            )),
+            Box::new(TestMigration(
+                Version::new(0, 10, 0),
+                Version::new(0, 11, 0),
+                true,
+            )),
+            Box::new(TestMigration(
+                Version::new(0, 11, 0),
+                Version::new(0, 12, 0),
+                true,
+            )),
+        ]
+    }
+
+    fn _versions(steps: &[&dyn Migration]) -> Vec<String> {
+        steps.iter().map(|m| m.to_version().to_string()).collect()
+    }
+
+    #[test]
+    fn test_registry_is_ordered() {
+        let registry = registry();
+        for pair in registry.windows(2) {
+            assert!(pair[0].to_version() <= pair[1].from_version());
+        }
+        for m in &registry {
+            assert!(m.from_version() < m.to_version());
+        }
+    }
+
+    #[test]
+    fn test_plan_forward() {
+        let registry = _registry();
+
+        let steps = plan(&registry, &Version::new(0, 9, 0), &Version::new(0, 11, 2)).unwrap();
+        assert_eq!(_versions(&steps), vec!["0.10.0", "0.11.0"]);
+
+        let steps = plan(&registry, &Version::new(0, 10, 1), &Version::new(0, 12, 0)).unwrap();
+        assert_eq!(_versions(&steps), vec!["0.11.0", "0.12.0"]);
+
+        let steps = plan(&registry, &Version::new(0, 12, 0), &Version::new(0, 12, 0)).unwrap();
+        assert!(steps.is_empty());
+    }
+
+    #[test]
+    fn test_plan_rollback() {
+        let registry = _registry();
+
+        let steps = plan(&registry, &Version::new(0, 12, 0), &Version::new(0, 10, 0)).unwrap();
+        assert_eq!(_versions(&steps), vec!["0.12.0", "0.11.0"]);
+
+        // 0.9.7 -> 0.10.0 has no reverse step
+        assert!(plan(&registry, &Version::new(0, 12, 0), &Version::new(0, 9, 7)).is_err());
+    }
 }
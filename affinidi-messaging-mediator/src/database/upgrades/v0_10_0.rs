@@ -3,34 +3,99 @@
  *
  * Adds ACL Flag to set queue limits. Sets this flag based on the mediator configuration
  */
-use crate::database::Database;
+use super::Migration;
+use crate::{common::config::Config, database::Database};
 use affinidi_messaging_mediator_common::errors::MediatorError;
 use affinidi_messaging_sdk::protocols::mediator::acls::MediatorACLSet;
+use async_trait::async_trait;
+use semver::Version;
 use tracing::info;
 
-impl Database {
-    /// No schema changes, simply update version number
-    pub(crate) async fn upgrade_0_10_0(
+pub(crate) struct Upgrade0_10_0;
+
+#[async_trait]
+impl Migration for Upgrade0_10_0 {
+    fn from_version(&self) -> Version {
+        Version::new(0, 9, 7)
+    }
+
+    fn to_version(&self) -> Version {
+        Version::new(0, 10, 0)
+    }
+
+    fn description(&self) -> &'static str {
+        "Set the self manage queue limit ACL flags on all accounts"
+    }
+
+    async fn touched_keys(
         &self,
-        default_acl: &MediatorACLSet,
-    ) -> Result<(), MediatorError> {
-        let send_limit_flag = default_acl.get_self_manage_send_queue_limit();
-        let receive_limit_flag = default_acl.get_self_manage_receive_queue_limit();
+        database: &Database,
+        config: &Config,
+    ) -> Result<Vec<String>, MediatorError> {
+        let (send, receive) = _queue_limit_flags(&config.security.global_acl_default);
+        if !send && !receive {
+            return Ok(vec![]);
+        }
+
+        let mut keys = Vec::new();
+        let mut cursor: u32 = 0;
+        loop {
+            let dids = database.account_list(cursor, 100).await?;
+            keys.extend(
+                dids.accounts
+                    .iter()
+                    .map(|account| ["DID:", &account.did_hash].concat()),
+            );
+
+            if dids.cursor == 0 {
+                break;
+            } else {
+                cursor = dids.cursor;
+            }
+        }
+        Ok(keys)
+    }
 
-        if send_limit_flag || receive_limit_flag {
-            self.update_acl_flag_queue_limits(send_limit_flag, receive_limit_flag)
+    async fn forward(&self, database: &Database, config: &Config) -> Result<(), MediatorError> {
+        let (send, receive) = _queue_limit_flags(&config.security.global_acl_default);
+
+        if send || receive {
+            database
+                .update_acl_flag_queue_limits(send, receive, true)
                 .await?;
         }
+        Ok(())
+    }
+
+    fn reversible(&self) -> bool {
+        true
+    }
 
-        self.upgrade_change_schema_version("0.10.0").await
+    /// The flags didn't exist before 0.10.0, clear them on all accounts
+    async fn reverse(&self, database: &Database, _config: &Config) -> Result<(), MediatorError> {
+        database
+            .update_acl_flag_queue_limits(true, true, false)
+            .await
     }
+}
 
+/// Which queue limit flags are set in the default ACL (send, receive)
+fn _queue_limit_flags(default_acl: &MediatorACLSet) -> (bool, bool) {
+    (
+        default_acl.get_self_manage_send_queue_limit(),
+        default_acl.get_self_manage_receive_queue_limit(),
+    )
+}
+
+impl Database {
+    /// Sets the self_change_queue_limit flags to `value` on all accounts
+    /// - send/receive: which of the flags to change
     async fn update_acl_flag_queue_limits(
         &self,
         send: bool,
         receive: bool,
+        value: bool,
     ) -> Result<(), MediatorError> {
-        // Update all accounts to have the self_change_queue_limit flag set to true
         let mut cursor: u32 = 0;
         let mut counter = 0;
         loop {
@@ -41,10 +106,10 @@ impl Database {
 
                 let mut acls = MediatorACLSet::from_u64(account.acls);
                 if send {
-                    acls.set_self_manage_send_queue_limit(true);
+                    acls.set_self_manage_send_queue_limit(value);
                 }
                 if receive {
-                    acls.set_self_manage_receive_queue_limit(true);
+                    acls.set_self_manage_receive_queue_limit(value);
                 }
                 self.set_did_acl(&account.did_hash, &acls).await?;
             }
@@ -56,8 +121,8 @@ impl Database {
             }
         }
         info!(
-            "Updated {} accounts with self_change_queue_limit flag",
-            counter
+            "Updated {} accounts with self_change_queue_limit flag ({})",
+            counter, value
         );
         Ok(())
     }
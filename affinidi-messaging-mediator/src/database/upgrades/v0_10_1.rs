@@ -0,0 +1,69 @@
+/*!
+ * Upgrades to version 0.10.1
+ *
+ * Live streaming state moved from the single `GLOBAL_STREAMING` hash to a hash per DID
+ * (`GLOBAL_STREAMING:<did_hash>`). Removes the old hash, nothing reads it anymore.
+ */
+use super::Migration;
+use crate::{common::config::Config, database::Database};
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use async_trait::async_trait;
+use semver::Version;
+use tracing::info;
+
+/// Live streaming hash used before 0.10.1
+const LEGACY_STREAMING_KEY: &str = "GLOBAL_STREAMING";
+
+pub(crate) struct Upgrade0_10_1;
+
+#[async_trait]
+impl Migration for Upgrade0_10_1 {
+    fn from_version(&self) -> Version {
+        Version::new(0, 10, 0)
+    }
+
+    fn to_version(&self) -> Version {
+        Version::new(0, 10, 1)
+    }
+
+    fn description(&self) -> &'static str {
+        "Remove the legacy GLOBAL_STREAMING hash (replaced by GLOBAL_STREAMING:<did_hash>)"
+    }
+
+    async fn touched_keys(
+        &self,
+        _database: &Database,
+        _config: &Config,
+    ) -> Result<Vec<String>, MediatorError> {
+        Ok(vec![LEGACY_STREAMING_KEY.into()])
+    }
+
+    async fn forward(&self, database: &Database, _config: &Config) -> Result<(), MediatorError> {
+        let mut conn = database.0.get_async_connection().await?;
+
+        let removed: u32 = deadpool_redis::redis::cmd("DEL")
+            .arg(LEGACY_STREAMING_KEY)
+            .query_async(&mut conn)
+            .await
+            .map_err(|err| {
+                MediatorError::DatabaseError(
+                    "NA".into(),
+                    format!("Couldn't remove {}. Reason: {}", LEGACY_STREAMING_KEY, err),
+                )
+            })?;
+
+        if removed > 0 {
+            info!("Removed legacy {} hash", LEGACY_STREAMING_KEY);
+        }
+        Ok(())
+    }
+
+    fn reversible(&self) -> bool {
+        true
+    }
+
+    /// Nothing to restore, older mediators rebuild the hash as websockets (re)connect
+    async fn reverse(&self, _database: &Database, _config: &Config) -> Result<(), MediatorError> {
+        Ok(())
+    }
+}
@@ -5,17 +5,24 @@
 //! 4. If the challenge is correct, the server sends two JWT tokens to the client (access and refresh tokens)
 //! 5. Client uses the access token to access protected services
 //! 6. If the access token expires, the client uses the refresh token to get a new access token
+//!    (POST /authenticate/refresh, or in-band over an open websocket connection)
+//!
+//! Linking additional DIDs to an authenticated session
+//! 1. Client requests a challenge for the additional DID (POST /authenticate/link/challenge)
+//! 2. Client encrypts the challenge in a message from the additional DID (POST /authenticate/link)
+//! 3. Server verifies the challenge, the session can now fetch, list, delete, pickup and live
+//!    stream messages for the additional DID
 
 use super::message_inbound::InboundMessage;
 use crate::{
     SharedData,
     common::acl_checks::ACLCheck,
-    database::session::{Session, SessionClaims, SessionState},
+    database::session::{SESSION_EXPIRY, Session, SessionClaims, SessionState},
 };
 use affinidi_messaging_didcomm::{Message, UnpackOptions, envelope::MetaEnvelope};
 use affinidi_messaging_mediator_common::errors::{AppError, MediatorError, SuccessResponse};
 use affinidi_messaging_sdk::{
-    authentication::AuthRefreshResponse,
+    authentication::{AuthLinkResponse, AuthRefreshResponse},
     messages::{AuthorizationResponse, GenericDataStruct, known::MessageType},
     protocols::mediator::{
         accounts::AccountType,
@@ -62,6 +69,7 @@ pub async fn authentication_challenge(
         acls: MediatorACLSet::default(), // this will be updated later
         account_type: AccountType::Standard,
         expires_at: 0,
+        ..Default::default()
     };
     let _span = span!(
         Level::DEBUG,
@@ -284,35 +292,22 @@ pub async fn authentication_response(
             state.config.security.jwt_access_expiry,
             &state.config.security.jwt_encoding_key,
         )?;
-        let refresh_claims = SessionClaims {
-            aud: "ATM".to_string(),
-            sub: session.did.clone(),
-            session_id: session.session_id.clone(),
-            exp: (SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-                + (state.config.security.jwt_refresh_expiry
-                    - state.config.security.jwt_access_expiry)),
-        };
+        // The refresh token can't outlive the session it refreshes
+        let (refresh_token, refresh_expires_at) = _create_refresh_token(
+            &session.did,
+            &session.session_id,
+            (state.config.security.jwt_refresh_expiry - state.config.security.jwt_access_expiry)
+                .min(SESSION_EXPIRY as u64),
+            &state.config.security.jwt_encoding_key,
+        )?;
 
         session.expires_at = access_expires_at;
 
         let response = AuthorizationResponse {
             access_token,
             access_expires_at,
-            refresh_token: encode(
-                &Header::new(jsonwebtoken::Algorithm::EdDSA),
-                &refresh_claims,
-                &state.config.security.jwt_encoding_key,
-            )
-            .map_err(|err| {
-                MediatorError::InternalError(
-                    "UNKNOWN".into(),
-                    format!("Couldn't encode refresh token. Reason: {}", err),
-                )
-            })?,
-            refresh_expires_at: refresh_claims.exp,
+            refresh_token,
+            refresh_expires_at,
         };
 
         // Set the session state to Authorized
@@ -460,82 +455,322 @@ pub async fn authentication_refresh(
             .into());
         };
 
-        // Decode the refresh token
-        let mut validation = Validation::new(jsonwebtoken::Algorithm::EdDSA);
-        validation.set_audience(&["ATM"]);
-        validation.set_required_spec_claims(&["exp", "sub", "aud", "session_id"]);
-        let results = match jsonwebtoken::decode::<SessionClaims>(
-            refresh_token,
-            &state.config.security.jwt_decoding_key,
-            &validation,
-        ) {
-            Ok(token) => token,
-            Err(err) => {
-                return Err(MediatorError::AuthenticationError(format!(
-                    "Couldn't decode refresh token. Reason: {}",
-                    err
-                ))
-                .into());
-            }
+        let Some(from_did) = &envelope.from_did else {
+            return Err(MediatorError::AuthenticationError(
+                "Could not determine from_did".to_string(),
+            )
+            .into());
         };
 
-        // Refresh token is valid - check against database and ensure it still exists
-        let session_check = match &envelope.from_did {
-            Some(from_did) => {
-                state
-                    .database
-                    .get_session(&results.claims.session_id, from_did)
-                    .await?
+        let (session_check, refreshed) =
+            refresh_access_token(&state, refresh_token, from_did).await?;
+
+        Ok((
+            StatusCode::OK,
+            Json(SuccessResponse {
+                sessionId: session_check.session_id.clone(),
+                httpCode: StatusCode::OK.as_u16(),
+                errorCode: 0,
+                errorCodeStr: "NA".to_string(),
+                message: "Success".to_string(),
+                data: Some(refreshed),
+            }),
+        ))
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Validates a refresh token and creates a new access token for the session
+/// Used by POST /authenticate/refresh and in-band refreshes over a websocket
+/// - `from_did`: DID that sent the refresh request
+///
+/// Returns the refreshed session and the new access token
+/// The refresh token isn't renewed, it stays valid until it expires with the session
+pub(crate) async fn refresh_access_token(
+    state: &SharedData,
+    refresh_token: &str,
+    from_did: &str,
+) -> Result<(Session, AuthRefreshResponse), MediatorError> {
+    // Decode the refresh token
+    let mut validation = Validation::new(jsonwebtoken::Algorithm::EdDSA);
+    validation.set_audience(&["ATM"]);
+    validation.set_required_spec_claims(&["exp", "sub", "aud", "session_id"]);
+    let results = match jsonwebtoken::decode::<SessionClaims>(
+        refresh_token,
+        &state.config.security.jwt_decoding_key,
+        &validation,
+    ) {
+        Ok(token) => token,
+        Err(err) => {
+            return Err(MediatorError::AuthenticationError(format!(
+                "Couldn't decode refresh token. Reason: {}",
+                err
+            )));
+        }
+    };
+
+    // Refresh token is valid - check against database and ensure it still exists
+    let session_check = state
+        .database
+        .get_session(&results.claims.session_id, from_did)
+        .await?;
+
+    // Is the session in an authenticated state? If not, then we can't refresh
+    if session_check.state != SessionState::Authenticated {
+        return Err(MediatorError::SessionError(
+            results.claims.session_id.clone(),
+            "Session is not in an authenticated state".into(),
+        ));
+    }
+
+    // Does the Global ACL still allow them to connect?
+    if session_check.acls.get_blocked() {
+        info!("DID({}) is blocked from connecting", session_check.did);
+        return Err(MediatorError::ACLDenied("DID Blocked".to_string()));
+    }
+
+    // Generate a new access token
+    let (access_token, access_expires_at) = _create_access_token(
+        &session_check.did,
+        &session_check.session_id,
+        state.config.security.jwt_access_expiry,
+        &state.config.security.jwt_encoding_key,
+    )?;
+
+    info!(
+        "{}: Access JWT refreshed for DID({})",
+        session_check.session_id,
+        digest(&session_check.did)
+    );
+
+    Ok((
+        session_check,
+        AuthRefreshResponse {
+            access_token,
+            access_expires_at,
+        },
+    ))
+}
+
+/// POST /authenticate/link/challenge
+/// Request from an authenticated session to get a challenge for an additional DID
+/// The additional DID must pass the same ACL checks as a DID requesting a new session
+pub async fn authentication_link_challenge(
+    session: Session,
+    State(state): State<SharedData>,
+    Json(body): Json<ChallengeBody>,
+) -> Result<(StatusCode, Json<SuccessResponse<AuthenticationChallenge>>), AppError> {
+    let did_hash = digest(&body.did);
+    let _span = span!(
+        Level::DEBUG,
+        "authentication_link_challenge",
+        session_id = session.session_id,
+        did_hash = did_hash
+    );
+    async move {
+        if session.owns_did(&body.did) {
+            return Err(MediatorError::RequestDataError(
+                session.session_id.clone(),
+                format!("DID ({}) is already part of this session", body.did),
+            )
+            .into());
+        }
+
+        // Same checks as authentication_challenge(), linked DIDs must also have LOCAL access
+        match state.database.get_did_acl(&did_hash).await? {
+            Some(acls) => {
+                if acls.get_blocked() {
+                    info!("DID({}) is blocked from connecting", body.did);
+                    return Err(MediatorError::ACLDenied("DID Blocked".to_string()).into());
+                } else if !acls.get_local() {
+                    return Err(
+                        MediatorError::ACLDenied("DID does not have LOCAL access".into()).into(),
+                    );
+                }
             }
             _ => {
-                return Err(MediatorError::AuthenticationError(
-                    "Could not determine from_did".to_string(),
+                // Unknown DID
+                if state.config.security.mediator_acl_mode == AccessListModeType::ExplicitAllow
+                    || !state.config.security.global_acl_default.get_local()
+                {
+                    info!("Unknown DID({}) is blocked from connecting", body.did);
+                    return Err(MediatorError::ACLDenied("DID Blocked".to_string()).into());
+                } else {
+                    // Register the DID as a local DID
+                    state
+                        .database
+                        .account_add(&did_hash, &state.config.security.global_acl_default, None)
+                        .await?;
+                }
+            }
+        }
+
+        let challenge = create_random_string(32);
+        state
+            .database
+            .session_link_challenge(&session.session_id, &did_hash, &challenge)
+            .await?;
+
+        debug!(
+            "{}: Link challenge sent for DID({})",
+            session.session_id, body.did
+        );
+
+        Ok((
+            StatusCode::OK,
+            Json(SuccessResponse {
+                sessionId: session.session_id.clone(),
+                httpCode: StatusCode::OK.as_u16(),
+                errorCode: 0,
+                errorCodeStr: "NA".to_string(),
+                message: "Success".to_string(),
+                data: Some(AuthenticationChallenge {
+                    challenge,
+                    session_id: session.session_id.clone(),
+                }),
+            }),
+        ))
+    }
+    .instrument(_span)
+    .await
+}
+
+/// POST /authenticate/link
+/// Response from the additional DID to the link challenge
+/// The message must be an Affinidi Authenticate message from the additional DID (authcrypt)
+/// On success the DID is linked to the session until the session expires
+pub async fn authentication_link_response(
+    session: Session,
+    State(state): State<SharedData>,
+    Json(body): Json<InboundMessage>,
+) -> Result<(StatusCode, Json<SuccessResponse<AuthLinkResponse>>), AppError> {
+    let _span = span!(
+        Level::DEBUG,
+        "authentication_link_response",
+        session_id = session.session_id
+    );
+
+    async move {
+        let s = serde_json::to_string(&body).unwrap();
+
+        let mut envelope = match MetaEnvelope::new(&s, &state.did_resolver).await {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                return Err(MediatorError::ParseError(
+                    session.session_id.clone(),
+                    "Raw inbound DIDComm message".into(),
+                    e.to_string(),
                 )
                 .into());
             }
         };
 
-        // Is the session in an authenticated state? If not, then we can't refresh
-        if session_check.state != SessionState::Authenticated {
+        let Some(from_did) = envelope.from_did.clone() else {
+            return Err(MediatorError::AuthenticationError(
+                "Could not determine from_did".to_string(),
+            )
+            .into());
+        };
+
+        // Unpack the message
+        let (msg, _) = Message::unpack(
+            &mut envelope,
+            &state.did_resolver,
+            &*state.config.security.mediator_secrets,
+            &UnpackOptions::default(),
+        )
+        .await
+        .map_err(|e| {
+            MediatorError::MessageUnpackError(
+                session.session_id.clone(),
+                format!("Couldn't unpack incoming link message. Reason: {}", e),
+            )
+        })?;
+
+        // Only accepts AffinidiAuthenticate messages
+        if !matches!(
+            msg.type_.parse::<MessageType>(),
+            Ok(MessageType::AffinidiAuthenticate)
+        ) {
             return Err(MediatorError::SessionError(
-                results.claims.session_id.clone(),
-                "Session is not in an authenticated state".into(),
+                session.session_id.clone(),
+                "Only accepts Affinidi Authentication protocol messages".to_string(),
             )
             .into());
         }
 
-        // Does the Global ACL still allow them to connect?
-        if session_check.acls.get_blocked() {
-            info!("DID({}) is blocked from connecting", session_check.did);
-            return Err(MediatorError::ACLDenied("DID Blocked".to_string()).into());
+        // Ensure the message hasn't expired
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if let Some(expires) = msg.expires_time {
+            if expires <= now {
+                return Err(MediatorError::MessageExpired(
+                    session.session_id.clone(),
+                    expires.to_string(),
+                    now.to_string(),
+                )
+                .into());
+            }
         }
 
-        // Generate a new access token
-        let (access_token, access_expires_at) = _create_access_token(
-            &session_check.did,
-            &session_check.session_id,
-            state.config.security.jwt_access_expiry,
-            &state.config.security.jwt_encoding_key,
-        )?;
+        if msg.from.as_ref().is_some_and(|from| *from != from_did) {
+            return Err(MediatorError::SessionError(
+                session.session_id.clone(),
+                "from DID of the link message does not match the envelope".into(),
+            )
+            .into());
+        }
+
+        let challenge: AuthenticationChallenge =
+            serde_json::from_value(msg.body).map_err(|err| {
+                MediatorError::SessionError(
+                    session.session_id.clone(),
+                    format!(
+                        "Couldn't parse body into AuthenticationChallenge. Reason: {}",
+                        err
+                    ),
+                )
+            })?;
+
+        if challenge.session_id != session.session_id
+            || !state
+                .database
+                .session_link_did(&session.session_id, &from_did, &challenge.challenge)
+                .await?
+        {
+            warn!(
+                "{}: Invalid or expired link challenge for DID({})",
+                session.session_id, from_did
+            );
+            return Err(MediatorError::SessionError(
+                session.session_id.clone(),
+                "Invalid or expired link challenge".into(),
+            )
+            .into());
+        }
 
         info!(
-            "{}: Access JWT refreshed for DID({})",
-            session_check.session_id,
-            digest(session_check.did)
+            "{}: DID({}) linked to session",
+            session.session_id, from_did
         );
 
+        let mut dids = session.linked_dids.clone();
+        if !dids.contains(&from_did) {
+            dids.push(from_did);
+        }
+
         Ok((
             StatusCode::OK,
             Json(SuccessResponse {
-                sessionId: session_check.session_id.clone(),
+                sessionId: session.session_id.clone(),
                 httpCode: StatusCode::OK.as_u16(),
                 errorCode: 0,
                 errorCodeStr: "NA".to_string(),
                 message: "Success".to_string(),
-                data: Some(AuthRefreshResponse {
-                    access_token,
-                    access_expires_at,
-                }),
+                data: Some(AuthLinkResponse { linked_dids: dids }),
             }),
         ))
     }
@@ -584,3 +819,35 @@ fn _create_access_token(
 
     Ok((access_token, access_claims.exp))
 }
+
+fn _create_refresh_token(
+    did: &str,
+    session_id: &str,
+    expiry: u64,
+    encoding_key: &EncodingKey,
+) -> Result<(String, u64), MediatorError> {
+    let refresh_claims = SessionClaims {
+        aud: "ATM".to_string(),
+        sub: did.to_owned(),
+        session_id: session_id.to_owned(),
+        exp: (SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + expiry),
+    };
+
+    let refresh_token = encode(
+        &Header::new(jsonwebtoken::Algorithm::EdDSA),
+        &refresh_claims,
+        encoding_key,
+    )
+    .map_err(|err| {
+        MediatorError::InternalError(
+            "UNKNOWN".into(),
+            format!("Couldn't encode refresh token. Reason: {}", err),
+        )
+    })?;
+
+    Ok((refresh_token, refresh_claims.exp))
+}
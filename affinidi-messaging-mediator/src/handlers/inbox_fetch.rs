@@ -4,6 +4,7 @@ use affinidi_messaging_sdk::messages::{GetMessagesResponse, fetch::FetchOptions}
 use axum::{Json, extract::State};
 use http::StatusCode;
 use regex::Regex;
+use sha256::digest;
 use tracing::{Instrument, Level, span};
 
 /// Fetches available messages from the inbox
@@ -47,8 +48,23 @@ pub async fn inbox_fetch_handler(
             }
         }
 
+        // Fetch for a DID linked to the session if requested
+        let did_hash = match &body.recipient_did {
+            Some(recipient_did) => {
+                if !session.owns_did(recipient_did) {
+                    return Err(MediatorError::PermissionError(
+                        session.session_id,
+                        format!("recipient_did ({}) is not linked to this session", recipient_did),
+                    )
+                    .into());
+                }
+                digest(recipient_did)
+            }
+            None => session.did_hash.clone(),
+        };
+
         // Fetch messages if possible
-        let results = state.database.fetch_messages(&session.session_id, &session.did_hash, &body).await?;
+        let results = state.database.fetch_messages(&session.session_id, &did_hash, &body).await?;
 
         Ok((
             StatusCode::OK,
@@ -49,12 +49,28 @@ pub async fn message_delete_handler(
         }
         let mut deleted: DeleteMessageResponse = DeleteMessageResponse::default();
 
+        let did_hashes = session.did_hashes();
         for message in &body.message_ids {
             debug!("Deleting message: message_id({})", message);
-            let result = state
-                .database.0
-                .delete_message(Some(&session.session_id), &session.did_hash, message)
-                .await;
+            // The message may belong to any DID linked to the session, the session DID is tried first
+            let mut result = Ok(());
+            for (index, did_hash) in did_hashes.iter().enumerate() {
+                match state
+                    .database
+                    .delete_message(Some(&session.session_id), did_hash, message)
+                    .await
+                {
+                    Ok(_) => {
+                        result = Ok(());
+                        break;
+                    }
+                    Err(err) => {
+                        if index == 0 {
+                            result = Err(err);
+                        }
+                    }
+                }
+            }
 
             match result {
                 Ok(_) => deleted.success.push(message.into()),
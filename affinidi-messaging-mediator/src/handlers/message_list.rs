@@ -1,10 +1,15 @@
-use crate::{SharedData, database::session::Session};
+use crate::{
+    SharedData,
+    database::{session::Session, stream_id::parse_stream_id},
+};
 use affinidi_messaging_didcomm::UnpackMetadata;
 use affinidi_messaging_mediator_common::errors::{AppError, MediatorError, SuccessResponse};
-use affinidi_messaging_sdk::messages::{Folder, GenericDataStruct, MessageList};
+use affinidi_messaging_sdk::messages::{
+    Folder, GenericDataStruct, MessageList, list::ListMessagesOptions,
+};
 use axum::{
     Json,
-    extract::{Path, State},
+    extract::{Path, Query, State},
 };
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
@@ -23,9 +28,12 @@ impl GenericDataStruct for ResponseData {}
 /// - `session`: Session information
 /// - `folder`: Folder to retrieve messages from
 /// - `did_hash`: sha256 hash of the DID we are checking
+/// - `options`: Query parameters to filter and page the list (see [ListMessagesOptions])
+///   limit is capped at `limits.listed_messages`
 pub async fn message_list_handler(
     session: Session,
     Path((did_hash, folder)): Path<(String, Folder)>,
+    Query(options): Query<ListMessagesOptions>,
     State(state): State<SharedData>,
 ) -> Result<(StatusCode, Json<SuccessResponse<MessageList>>), AppError> {
     let _span = span!(
@@ -42,9 +50,8 @@ pub async fn message_list_handler(
             return Err(MediatorError::ACLDenied("DID does not have LOCAL access".into()).into());
         }
 
-        // Check that the DID hash matches the session DID
-        // TODO: In the future, add support for lists of DID's owned by the session owner
-        if session.did_hash != did_hash {
+        // Check that the DID hash matches the session DID or a DID linked to the session
+        if !session.owns_did_hash(&did_hash) {
             return Err(MediatorError::PermissionError(
                 session.session_id,
                 "You don't have permission to access this resource.".into(),
@@ -52,12 +59,29 @@ pub async fn message_list_handler(
             .into());
         }
 
+        // Stream IDs are passed through to the database, reject anything that isn't one
+        for (name, value) in [
+            ("since", &options.since),
+            ("until", &options.until),
+            ("cursor", &options.cursor),
+        ] {
+            if let Some(value) = value {
+                if parse_stream_id(value, 0).is_none() {
+                    return Err(MediatorError::RequestDataError(
+                        session.session_id,
+                        format!("{} ({}) is not a valid stream ID", name, value),
+                    )
+                    .into());
+                }
+            }
+        }
+
         let messages = state
             .database
-            .list_messages(
+            .list_messages_filtered(
                 &did_hash,
                 folder,
-                None,
+                &options,
                 state.config.limits.listed_messages as u32,
             )
             .await?;
@@ -44,7 +44,6 @@ pub async fn message_outbound_handler(
                         debug!("Deleting message: {}", msg_id);
                         match state
                             .database
-                            .0
                             .delete_message(Some(&session.session_id), &session.did_hash, msg_id)
                             .await
                         {
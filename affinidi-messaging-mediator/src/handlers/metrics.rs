@@ -0,0 +1,270 @@
+/*!
+ * Prometheus/OpenMetrics endpoint for the mediator
+ *
+ * Exports in the Prometheus text exposition format:
+ * - The `GLOBAL` counters from [MetadataStats] (shared by all mediators using the same database)
+ * - Forward task queue depth and message expiry backlog (timeslots due for cleanup)
+ * - Latency histograms for each API handler (local to this mediator)
+ *
+ * Enabled with `[metrics] enabled = true` in the mediator configuration.
+ */
+
+use crate::{SharedData, database::stats::MetadataStats};
+use affinidi_messaging_mediator_common::errors::AppError;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http::{StatusCode, header};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::Mutex,
+    time::{Instant, SystemTime},
+};
+
+/// Upper bounds (seconds) of the handler latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Latency histogram for a single handler
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    /// Count of observations per bucket (not cumulative)
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+/// Per handler latency histograms, keyed by (method, route)
+#[derive(Debug, Default)]
+pub struct HandlerMetrics {
+    histograms: Mutex<BTreeMap<(String, String), Histogram>>,
+}
+
+impl HandlerMetrics {
+    /// Records how long a handler took to respond
+    pub fn observe(&self, method: &str, route: &str, seconds: f64) {
+        self.histograms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry((method.to_string(), route.to_string()))
+            .or_default()
+            .observe(seconds);
+    }
+
+    fn snapshot(&self) -> BTreeMap<(String, String), Histogram> {
+        self.histograms
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+/// Middleware that records the latency of each matched route
+pub async fn track_latency(
+    State(state): State<SharedData>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    state
+        .metrics
+        .observe(&method, &route, start.elapsed().as_secs_f64());
+
+    response
+}
+
+/// Returns the mediator metrics in the Prometheus text exposition format
+pub async fn metrics_handler(State(state): State<SharedData>) -> Result<Response, AppError> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    let stats = state.database.get_db_metadata().await?;
+    let forward_tasks = state.database.get_forward_tasks_len().await?;
+    let expiry_backlog = state.database.get_expiry_backlog(now).await?;
+
+    Ok((
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        render(&stats, forward_tasks, expiry_backlog, &state.metrics),
+    )
+        .into_response())
+}
+
+fn _metric(out: &mut String, name: &str, _type: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, _type);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// Renders the metrics in the Prometheus text exposition format
+fn render(
+    stats: &MetadataStats,
+    forward_tasks: usize,
+    expiry_backlog: usize,
+    metrics: &HandlerMetrics,
+) -> String {
+    let mut out = String::new();
+
+    let counters = [
+        ("received_bytes", "Bytes received", stats.received_bytes),
+        ("sent_bytes", "Bytes sent", stats.sent_bytes),
+        ("deleted_bytes", "Bytes deleted", stats.deleted_bytes),
+        (
+            "received_messages",
+            "Messages received",
+            stats.received_count,
+        ),
+        ("sent_messages", "Messages sent", stats.sent_count),
+        ("deleted_messages", "Messages deleted", stats.deleted_count),
+        (
+            "websocket_open",
+            "WebSocket connections opened",
+            stats.websocket_open,
+        ),
+        (
+            "websocket_close",
+            "WebSocket connections closed",
+            stats.websocket_close,
+        ),
+        (
+            "sessions_created",
+            "Sessions created",
+            stats.sessions_created,
+        ),
+        (
+            "sessions_authenticated",
+            "Sessions successfully authenticated",
+            stats.sessions_success,
+        ),
+        (
+            "oob_invites_created",
+            "OOB invites created",
+            stats.oob_invites_created,
+        ),
+        (
+            "oob_invites_claimed",
+            "OOB invites claimed",
+            stats.oob_invites_claimed,
+        ),
+        (
+            "rate_limited_requests",
+            "Requests rejected by the rate limiter",
+            stats.rate_limited,
+        ),
+    ];
+    for (name, help, value) in counters {
+        _metric(
+            &mut out,
+            &["mediator_", name, "_total"].concat(),
+            "counter",
+            help,
+            value,
+        );
+    }
+
+    _metric(
+        &mut out,
+        "mediator_forward_tasks",
+        "gauge",
+        "Forward tasks waiting for remote delivery",
+        forward_tasks,
+    );
+    _metric(
+        &mut out,
+        "mediator_expiry_backlog_timeslots",
+        "gauge",
+        "Message expiry timeslots due for cleanup",
+        expiry_backlog,
+    );
+
+    let name = "mediator_handler_duration_seconds";
+    let _ = writeln!(out, "# HELP {} Handler response time in seconds", name);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    for ((method, route), histogram) in metrics.snapshot() {
+        let labels = format!("method=\"{}\",route=\"{}\"", method, route);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{},le=\"{}\"}} {}",
+                name, labels, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{},le=\"+Inf\"}} {}",
+            name, labels, histogram.count
+        );
+        let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+        let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = HandlerMetrics::default();
+        metrics.observe("POST", "/inbound", 0.02);
+        metrics.observe("POST", "/inbound", 0.2);
+        metrics.observe("POST", "/inbound", 60.0);
+
+        let stats = MetadataStats {
+            received_count: 3,
+            ..Default::default()
+        };
+        let out = render(&stats, 5, 2, &metrics);
+
+        assert!(out.contains("# TYPE mediator_received_messages_total counter\n"));
+        assert!(out.contains("mediator_received_messages_total 3\n"));
+        assert!(out.contains("mediator_forward_tasks 5\n"));
+        assert!(out.contains("mediator_expiry_backlog_timeslots 2\n"));
+        assert!(out.contains(
+            "mediator_handler_duration_seconds_bucket{method=\"POST\",route=\"/inbound\",le=\"0.01\"} 0\n"
+        ));
+        assert!(out.contains(
+            "mediator_handler_duration_seconds_bucket{method=\"POST\",route=\"/inbound\",le=\"0.025\"} 1\n"
+        ));
+        assert!(out.contains(
+            "mediator_handler_duration_seconds_bucket{method=\"POST\",route=\"/inbound\",le=\"10\"} 2\n"
+        ));
+        assert!(out.contains(
+            "mediator_handler_duration_seconds_bucket{method=\"POST\",route=\"/inbound\",le=\"+Inf\"} 3\n"
+        ));
+        assert!(out.contains(
+            "mediator_handler_duration_seconds_count{method=\"POST\",route=\"/inbound\"} 3\n"
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use affinidi_messaging_sdk::messages::SuccessResponse;
 use axum::{
     Json, Router,
     extract::State,
+    middleware,
     response::IntoResponse,
     routing::{delete, get, post},
 };
@@ -15,6 +16,7 @@ pub mod message_delete;
 pub mod message_inbound;
 pub mod message_list;
 pub mod message_outbound;
+pub mod metrics;
 pub(crate) mod oob_discovery;
 pub mod websocket;
 pub mod well_known_did_fetch;
@@ -47,6 +49,15 @@ pub fn application_routes(api_prefix: &str, shared_data: &SharedData) -> Router
             "/authenticate/refresh",
             post(authenticate::authentication_refresh),
         )
+        // Link additional DIDs to an authenticated session (challenge, then response)
+        .route(
+            "/authenticate/link/challenge",
+            post(authenticate::authentication_link_challenge),
+        )
+        .route(
+            "/authenticate/link",
+            post(authenticate::authentication_link_response),
+        )
         // Websocket endpoint for ATM clients
         .route("/ws", get(websocket::websocket_handler))
         // Out Of Band Discovery Routes
@@ -70,6 +81,16 @@ pub fn application_routes(api_prefix: &str, shared_data: &SharedData) -> Router
         );
     }
 
+    // Prometheus metrics, handler latency is recorded for all routes above
+    if shared_data.config.metrics_enabled {
+        app = app
+            .route_layer(middleware::from_fn_with_state(
+                shared_data.to_owned(),
+                metrics::track_latency,
+            ))
+            .route("/metrics", get(metrics::metrics_handler));
+    }
+
     let mut router = Router::new();
     router = if api_prefix.is_empty() || api_prefix == "/" {
         router.merge(app)
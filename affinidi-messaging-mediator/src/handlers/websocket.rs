@@ -1,13 +1,18 @@
 use crate::{
     SharedData,
+    common::{
+        jwt_auth::WEBSOCKET_PROTOCOL,
+        rate_limit::{RateLimitClass, check_rate_limit},
+    },
     database::session::Session,
     messages::inbound::handle_inbound,
     tasks::websocket_streaming::{StreamingUpdate, StreamingUpdateState, WebSocketCommands},
 };
 use affinidi_messaging_didcomm::{Message as DidcommMessage, PackEncryptedOptions};
 use affinidi_messaging_mediator_common::errors::{AppError, MediatorError};
-use affinidi_messaging_sdk::messages::problem_report::{
-    ProblemReport, ProblemReportScope, ProblemReportSorter,
+use affinidi_messaging_sdk::messages::{
+    problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
+    sending::InboundMessageResponse,
 };
 use axum::{
     extract::{
@@ -17,7 +22,10 @@ use axum::{
     response::IntoResponse,
 };
 use serde_json::json;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 use tokio::{
     select,
     sync::mpsc::{self, Receiver, Sender},
@@ -39,9 +47,17 @@ pub async fn websocket_handler(
     );
     // ACL Check (websockets only work on local DID's)
     if session.acls.get_local() {
-        async move { ws.on_upgrade(move |socket| handle_socket(socket, state, session)) }
-            .instrument(_span)
-            .await
+        async move {
+            // Browser clients offer the `atm` sub-protocol, it must be selected for the upgrade to succeed
+            // Tracked so that shutdown waits for the connection to close
+            let websockets = state.websockets.clone();
+            ws.protocols([WEBSOCKET_PROTOCOL])
+                .on_upgrade(move |socket| {
+                    websockets.track_future(handle_socket(socket, state, session))
+                })
+        }
+        .instrument(_span)
+        .await
     } else {
         let app_error: AppError =
             MediatorError::ACLDenied("DID does not have LOCAL access".into()).into();
@@ -50,7 +66,7 @@ pub async fn websocket_handler(
 }
 
 /// WebSocket state machine. This is spawned per connection.
-async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Session) {
+async fn handle_socket(mut socket: WebSocket, state: SharedData, mut session: Session) {
     let _span = span!(
         tracing::Level::INFO,
         "handle_socket",
@@ -58,22 +74,31 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
     );
     async move {
         // Register the transmission channel between websocket_streaming task and this websocket.
+        // The same channel is registered for every DID linked to the session.
+        // Other websockets for the same DID (e.g. other devices) stay connected, the connection ID
+        // identifies this websocket to the streaming task.
+        let connection_id = Uuid::new_v4().to_string();
+        // Live delivery requests on this websocket only apply to this connection
+        session.connection_id = Some(connection_id.clone());
         let (tx, mut rx): (Sender<WebSocketCommands>, Receiver<WebSocketCommands>) = mpsc::channel(5);
         if let Some(streaming) = &state.streaming_task {
-
-            let start = StreamingUpdate {
-                did_hash: session.did_hash.clone(),
-                state: StreamingUpdateState::Register(tx),
-            };
-            match streaming.channel.send(start).await {
-                Ok(_) => {
-                    debug!("Sent start message to streaming task");
-                }
-                Err(e) => {
-                    warn!("Error sending start message to streaming task: {:?}", e);
-                    return;
+            for did_hash in session.did_hashes() {
+                let start = StreamingUpdate {
+                    did_hash,
+                    state: StreamingUpdateState::Register(connection_id.clone(), tx.clone()),
+                };
+                match streaming.channel.send(start).await {
+                    Ok(_) => {
+                        debug!("Sent start message to streaming task");
+                    }
+                    Err(e) => {
+                        warn!("Error sending start message to streaming task: {:?}", e);
+                        return;
+                    }
                 }
             }
+            // Only the streaming task holds the channel
+            drop(tx);
         }
 
         let _ = state.database.global_stats_increment_websocket_open().await;
@@ -84,14 +109,26 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
         tokio::pin!(auth_timeout);
         debug!("WebSocket will timeout in {:?}", auth_timeout);
 
-        // Flag to prevent double deregistration
-        // This can occur because in some situations the streaming-task will send a close message
-        // due to duplicate channels. If we were to deregister on close in this scenario, we would
-        // also deregister the new channel that is still in use.
-        let mut already_deregistered_flag = false;
+        let mut shutdown = state.shutdown.clone();
         loop {
             select! {
+                _ = shutdown.wait() => {
+                    // Let the client know why it is being disconnected so it can reconnect elsewhere
+                    if let Ok(msg) = _generate_problem_report(&state, &session, "mediator-shutdown", "The mediator is shutting down, reconnect to continue receiving messages").await {
+                        let _ = socket.send(Message::Text(msg.into())).await;
+                    }
+                    debug!("Mediator shutting down, closing websocket connection");
+                    break;
+                }
                 _ = &mut auth_timeout => {
+                    // Has the session been refreshed in-band since the timeout was set?
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                    let refreshed_expires_at = session.refreshed_expires_at.load(Ordering::Relaxed);
+                    if refreshed_expires_at > now {
+                        debug!("Session was refreshed, extending timeout to ({})", refreshed_expires_at);
+                        auth_timeout.as_mut().reset(tokio::time::Instant::now() + Duration::from_secs(refreshed_expires_at - now));
+                        continue;
+                    }
                     debug!("Auth Timeout reached");
                     break;
                 }
@@ -104,6 +141,14 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
                                     debug!("ws: Received text message: {:?}", msg);
                                     if msg.len() > state.config.limits.ws_size {
                                         warn!("Error processing message, the size is too big. limit is {}, message size is {}", state.config.limits.ws_size, msg.len());
+                                        let error = MediatorError::ServiceLimitError(session.session_id.clone(), format!("Message size ({}) exceeds the websocket limit ({})", msg.len(), state.config.limits.ws_size));
+                                        _send_error(&mut socket, &state, &session, None, None, &error).await;
+                                        continue;
+                                    }
+
+                                    if let Err(error) = check_rate_limit(&state, &session.session_id, RateLimitClass::Inbound, &session.did_hash, Some(&session.did_hash)).await {
+                                        warn!("Websocket message rejected: {}", error);
+                                        _send_error(&mut socket, &state, &session, None, None, &error).await;
                                         continue;
                                     }
 
@@ -111,10 +156,14 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
                                     match handle_inbound(&state, &session, &msg).await {
                                         Ok(response) => {
                                             debug!("Successful handling of message - finished processing");
-                                            response
+                                            // Ephemeral responses only go back to this websocket
+                                            if let InboundMessageResponse::Ephemeral(response) = response {
+                                                let _ = socket.send(Message::Text(response.into())).await;
+                                            }
                                         }
                                         Err(e) => {
                                             warn!("Error processing message: {:?}", e);
+                                            _send_error(&mut socket, &state, &session, e.msg_id.as_deref(), e.thid.as_deref(), &e.error).await;
                                             continue;
                                         }
                                     };
@@ -129,6 +178,8 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
                                     debug!("ws: Received binary message: {:?}", msg);
                                     if msg.len() > state.config.limits.ws_size {
                                         warn!("Error processing message, the size is too big. limit is {}, message size is {}", state.config.limits.ws_size, msg.len());
+                                        let error = MediatorError::ServiceLimitError(session.session_id.clone(), format!("Message size ({}) exceeds the websocket limit ({})", msg.len(), state.config.limits.ws_size));
+                                        _send_error(&mut socket, &state, &session, None, None, &error).await;
                                         continue;
                                     }
 
@@ -136,18 +187,30 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
                                         Ok(msg) => msg,
                                         Err(e) => {
                                             warn!("Error processing binary message: {:?}", e);
+                                            let error = MediatorError::ParseError(session.session_id.clone(), "Binary websocket message".into(), e.to_string());
+                                            _send_error(&mut socket, &state, &session, None, None, &error).await;
                                             continue;
                                         }
                                     };
 
+                                    if let Err(error) = check_rate_limit(&state, &session.session_id, RateLimitClass::Inbound, &session.did_hash, Some(&session.did_hash)).await {
+                                        warn!("Websocket message rejected: {}", error);
+                                        _send_error(&mut socket, &state, &session, None, None, &error).await;
+                                        continue;
+                                    }
+
                                     // Process the message, which also takes care of any storing and live-streaming of the message
                                     match handle_inbound(&state, &session, &msg).await {
                                         Ok(response) => {
                                             debug!("Successful handling of message - finished processing");
-                                            response
+                                            // Ephemeral responses only go back to this websocket
+                                            if let InboundMessageResponse::Ephemeral(response) = response {
+                                                let _ = socket.send(Message::Text(response.into())).await;
+                                            }
                                         }
                                         Err(e) => {
                                             warn!("Error processing message: {:?}", e);
+                                            _send_error(&mut socket, &state, &session, e.msg_id.as_deref(), e.thid.as_deref(), &e.error).await;
                                             continue;
                                         }
                                     };
@@ -171,11 +234,10 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
                                 let _ = socket.send(Message::Text(msg.into())).await;
                             },
                             WebSocketCommands::Close => {
-                                if let Ok(msg) =  _generate_duplicate_connection_problem_report(&state, &session).await {
+                                if let Ok(msg) = _generate_problem_report(&state, &session, "too-many-connections", "Too many websocket connections for this DID, the oldest connection has been terminated").await {
                                    let _ = socket.send(Message::Text(msg.into())).await;
                             }
                                 debug!("Received close message from streaming task, closing websocket connection");
-                                already_deregistered_flag = true;
                                 break;
                             }
                         }
@@ -187,12 +249,12 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
             }
         }
 
-        // Remove this websocket and associated info from the streaming task
-        if !already_deregistered_flag { // Skip if close initiated by the streaming task
-            if let Some(streaming) = &state.streaming_task  {
+        // Remove this websocket from the streaming task, other websockets for the DID are unaffected
+        if let Some(streaming) = &state.streaming_task  {
+            for did_hash in session.did_hashes() {
                 let stop = StreamingUpdate {
-                    did_hash: session.did_hash.clone(),
-                    state: StreamingUpdateState::Deregister,
+                    did_hash,
+                    state: StreamingUpdateState::Deregister(connection_id.clone()),
                 };
                 let _ = streaming.channel.send(stop).await;
             }
@@ -211,34 +273,63 @@ async fn handle_socket(mut socket: WebSocket, state: SharedData, session: Sessio
     .await
 }
 
-async fn _generate_duplicate_connection_problem_report(
+/// Packs a websocket problem report for the client, sent before the mediator closes the websocket
+async fn _generate_problem_report(
     state: &SharedData,
     session: &Session,
+    code: &str,
+    comment: &str,
 ) -> Result<String, MediatorError> {
     let problem_report = ProblemReport::new(
         ProblemReportSorter::Warning,
         ProblemReportScope::Other("websocket".to_string()),
-        "duplicate-channel".to_string(),
-        "A new duplicate websocket connection for this DID has caused this websocket to terminate"
-            .to_string(),
+        code.to_string(),
+        comment.to_string(),
         vec![],
         None,
     );
 
-    let pr_msg = DidcommMessage::build(
-        Uuid::new_v4().to_string(),
-        "https://didcomm.org/report-problem/2.0/problem-report".to_string(),
-        json!(problem_report),
-    )
-    .from(state.config.mediator_did.clone())
-    .to(session.did.to_string())
-    .created_time(
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-    )
-    .finalize();
+    _pack_problem_report(state, session, problem_report, None, None).await
+}
+
+/// Sends a problem report for an inbound message that couldn't be processed
+/// - `msg_id` - ID of the message that caused the error, if it could be unpacked
+/// - `thid` - Thread ID of the message that caused the error
+async fn _send_error(
+    socket: &mut WebSocket,
+    state: &SharedData,
+    session: &Session,
+    msg_id: Option<&str>,
+    thid: Option<&str>,
+    error: &MediatorError,
+) {
+    match _pack_problem_report(state, session, error.to_problem_report(), msg_id, thid).await {
+        Ok(msg) => {
+            let _ = socket.send(Message::Text(msg.into())).await;
+        }
+        Err(err) => {
+            warn!("Couldn't create problem report. Reason: {}", err);
+        }
+    }
+}
+
+/// Packs a problem report for the session DID
+/// - `msg_id` - ID of the message the problem report relates to (acknowledged)
+/// - `thid` - Thread ID of the message the problem report relates to (used as the pthid)
+async fn _pack_problem_report(
+    state: &SharedData,
+    session: &Session,
+    problem_report: ProblemReport,
+    msg_id: Option<&str>,
+    thid: Option<&str>,
+) -> Result<String, MediatorError> {
+    let pr_msg = _problem_report_message(
+        &state.config.mediator_did,
+        &session.did,
+        problem_report,
+        msg_id,
+        thid,
+    );
 
     let (packed, _) = pr_msg
         .pack_encrypted(
@@ -262,3 +353,101 @@ async fn _generate_duplicate_connection_problem_report(
 
     Ok(packed)
 }
+
+/// Builds the (unpacked) problem report message from the mediator to `to_did`
+fn _problem_report_message(
+    mediator_did: &str,
+    to_did: &str,
+    problem_report: ProblemReport,
+    msg_id: Option<&str>,
+    thid: Option<&str>,
+) -> DidcommMessage {
+    let mut pr_msg = DidcommMessage::build(
+        Uuid::new_v4().to_string(),
+        "https://didcomm.org/report-problem/2.0/problem-report".to_string(),
+        json!(problem_report),
+    )
+    .from(mediator_did.to_string())
+    .to(to_did.to_string())
+    .created_time(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    );
+    if let Some(thid) = thid {
+        pr_msg = pr_msg.pthid(thid.to_string());
+    }
+    if let Some(msg_id) = msg_id {
+        pr_msg = pr_msg.header("ack".into(), json!([msg_id]));
+    }
+    pr_msg.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use affinidi_messaging_sdk::errors::ATMError;
+
+    /// What the SDK sees when the mediator rejects a websocket message with `error`
+    fn _sdk_error(error: MediatorError) -> (DidcommMessage, ATMError) {
+        let message = _problem_report_message(
+            "did:example:mediator",
+            "did:example:alice",
+            error.to_problem_report(),
+            Some("msg-2"),
+            Some("thread-1"),
+        );
+        let error = ATMError::from_problem_report(&message);
+        (message, error)
+    }
+
+    #[test]
+    fn test_problem_report_threading() {
+        let (message, _) = _sdk_error(MediatorError::ACLDenied("denied".into()));
+        assert_eq!(message.pthid.as_deref(), Some("thread-1"));
+        assert_eq!(message.extra_headers.get("ack"), Some(&json!(["msg-2"])));
+    }
+
+    #[test]
+    fn test_acl_denied_reaches_sdk() {
+        match _sdk_error(MediatorError::ACLDenied(
+            "Message blocked due to ACL".into(),
+        ))
+        .1
+        {
+            ATMError::ACLDenied(comment) => assert!(comment.contains("Message blocked due to ACL")),
+            err => panic!("Expected ACLDenied, got {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_limit_reaches_sdk() {
+        let error = MediatorError::ServiceLimitError(
+            "session".into(),
+            "Message size (20) exceeds the websocket limit (10)".into(),
+        );
+        match _sdk_error(error).1 {
+            ATMError::LimitError(comment) => {
+                assert!(comment.contains("exceeds the websocket limit"))
+            }
+            err => panic!("Expected LimitError, got {:?}", err),
+        }
+
+        let error = MediatorError::RateLimited("session".into(), "inbound".into(), 5);
+        assert!(matches!(_sdk_error(error).1, ATMError::LimitError(_)));
+    }
+
+    #[test]
+    fn test_parse_reaches_sdk() {
+        let error = MediatorError::ParseError(
+            "session".into(),
+            "Binary websocket message".into(),
+            "invalid utf-8".into(),
+        );
+        match _sdk_error(error).1 {
+            ATMError::ParseError(comment) => assert!(comment.contains("invalid utf-8")),
+            err => panic!("Expected ParseError, got {:?}", err),
+        }
+    }
+}
@@ -1,11 +1,14 @@
 use affinidi_did_resolver_cache_sdk::DIDCacheClient;
+use affinidi_messaging_mediator_common::shutdown::Shutdown;
 use axum::extract::{FromRef, FromRequestParts};
 use chrono::{DateTime, Utc};
 use common::{config::Config, jwt_auth::AuthError};
-use database::Database;
+use database::MediatorStore;
+use handlers::metrics::HandlerMetrics;
 use http::request::Parts;
-use std::fmt::Debug;
-use tasks::websocket_streaming::StreamingTask;
+use std::{fmt::Debug, sync::Arc};
+use tasks::{push_notifications::PushNotificationTask, websocket_streaming::StreamingTask};
+use tokio_util::task::TaskTracker;
 
 pub mod common;
 pub mod database;
@@ -19,8 +22,15 @@ pub struct SharedData {
     pub config: Config,
     pub service_start_timestamp: DateTime<Utc>,
     pub did_resolver: DIDCacheClient,
-    pub database: Database,
+    pub database: Arc<dyn MediatorStore>,
     pub streaming_task: Option<StreamingTask>,
+    /// Notifies DIDs without a live connection of queued messages (if enabled)
+    pub push_notifications: Option<PushNotificationTask>,
+    pub metrics: Arc<HandlerMetrics>,
+    /// Triggered when the mediator is shutting down
+    pub shutdown: Shutdown,
+    /// Open websocket connections, shutdown waits for these to close
+    pub websockets: TaskTracker,
 }
 
 impl Debug for SharedData {
@@ -43,3 +53,24 @@ where
         Ok(Self::from_ref(state)) // <---- added this line
     }
 }
+
+#[cfg(test)]
+impl SharedData {
+    /// Shared state for tests, the default configuration backed by `database`
+    pub(crate) async fn for_tests(database: Arc<dyn MediatorStore>) -> Self {
+        let config = Config::default().await;
+        SharedData {
+            did_resolver: DIDCacheClient::new(config.did_resolver_config.clone())
+                .await
+                .unwrap(),
+            config,
+            service_start_timestamp: Utc::now(),
+            database,
+            streaming_task: None,
+            push_notifications: None,
+            metrics: Arc::new(HandlerMetrics::default()),
+            shutdown: affinidi_messaging_mediator_common::shutdown::channel().1,
+            websockets: TaskTracker::new(),
+        }
+    }
+}
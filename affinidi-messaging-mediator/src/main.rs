@@ -1,6 +1,30 @@
-use affinidi_messaging_mediator::server::start;
+use affinidi_messaging_mediator::{database::mediator_store::SchemaUpgradeMode, server::start};
+use clap::Parser;
+
+/// Affinidi Secure Messaging Mediator
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Report the database schema migrations that would run and the keys they touch, then exit
+    #[arg(long, conflicts_with = "rollback_to")]
+    dry_run: bool,
+
+    /// Roll the database schema back to VERSION using the reverse migration steps, then exit
+    #[arg(long, value_name = "VERSION")]
+    rollback_to: Option<String>,
+}
 
 #[tokio::main]
 async fn main() {
-    return start().await;
+    let args = Args::parse();
+
+    let schema_upgrade = if args.dry_run {
+        SchemaUpgradeMode::DryRun
+    } else if let Some(version) = args.rollback_to {
+        SchemaUpgradeMode::RollbackTo(version)
+    } else {
+        SchemaUpgradeMode::Apply
+    };
+
+    return start(schema_upgrade).await;
 }
@@ -13,10 +13,45 @@ use tracing::{Instrument, debug, info, span};
 
 use super::{ProcessMessageResponse, WrapperType};
 
+/// Error from handling an inbound message
+/// `msg_id` and `thid` identify the message that caused the error, if it could be unpacked
+#[derive(Debug)]
+pub(crate) struct InboundError {
+    pub msg_id: Option<String>,
+    /// Thread of the message, its ID when it doesn't belong to a thread
+    pub thid: Option<String>,
+    pub error: MediatorError,
+}
+
+impl From<InboundError> for MediatorError {
+    fn from(error: InboundError) -> Self {
+        error.error
+    }
+}
+
 pub(crate) async fn handle_inbound(
     state: &SharedData,
     session: &Session,
     message: &str,
+) -> Result<InboundMessageResponse, InboundError> {
+    let mut unpacked = None;
+    _handle_inbound(state, session, message, &mut unpacked)
+        .await
+        .map_err(|error| {
+            let (msg_id, thid) = unpacked.unzip();
+            InboundError {
+                msg_id,
+                thid,
+                error,
+            }
+        })
+}
+
+async fn _handle_inbound(
+    state: &SharedData,
+    session: &Session,
+    message: &str,
+    unpacked: &mut Option<(String, String)>,
 ) -> Result<InboundMessageResponse, MediatorError> {
     let _span = span!(tracing::Level::DEBUG, "handle_inbound",);
 
@@ -60,6 +95,10 @@ pub(crate) async fn handle_inbound(
                     };
 
                     debug!("message unpacked:\n{:#?}", msg);
+                    *unpacked = Some((
+                        msg.id.clone(),
+                        msg.thid.clone().unwrap_or_else(|| msg.id.clone()),
+                    ));
 
                     // Process the message
                     let message_response = msg.process(state, session).await?;
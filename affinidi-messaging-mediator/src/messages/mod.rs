@@ -9,7 +9,8 @@ use affinidi_messaging_sdk::messages::known::MessageType as SDKMessageType;
 use affinidi_secrets_resolver::SecretsResolver;
 use ahash::AHashSet as HashSet;
 use protocols::{
-    mediator::{accounts, acls, administration},
+    authenticate, coordinate_mediation, discover_features,
+    mediator::{accounts, acls, administration, push_notifications},
     message_pickup, routing,
 };
 use ssi::dids::document::service::Endpoint;
@@ -40,6 +41,9 @@ impl MessageType {
                 accounts::process(message, state, session).await
             }
             SDKMessageType::MediatorACLManagement => acls::process(message, state, session).await,
+            SDKMessageType::MediatorPushNotifications => {
+                push_notifications::process(message, state, session).await
+            }
             SDKMessageType::TrustPing => ping::process(message, session),
             SDKMessageType::MessagePickupStatusRequest => {
                 message_pickup::status_request(message, state, session).await
@@ -61,11 +65,39 @@ impl MessageType {
                 session.session_id.clone(),
                 "Affinidi Authentication is only handled by the Authorization handler".into(),
             )),
-            SDKMessageType::AffinidiAuthenticateRefresh => Err(MediatorError::NotImplemented(
+            SDKMessageType::AffinidiAuthenticateRefresh => {
+                authenticate::refresh(message, state, session).await
+            }
+            SDKMessageType::AffinidiAuthenticateRefreshResponse => {
+                Err(MediatorError::NotImplemented(
+                    session.session_id.clone(),
+                    "Mediator does not handle Affinidi Authentication refresh responses".into(),
+                ))
+            }
+            SDKMessageType::DiscoverFeaturesQueries => {
+                discover_features::process(message, state, session).await
+            }
+            SDKMessageType::DiscoverFeaturesDisclose => Err(MediatorError::NotImplemented(
                 session.session_id.clone(),
-                "Affinidi Authentication is only handled by the Authorization handler".into(),
+                "Mediator does not handle Discover Features disclosures".into(),
             )),
             SDKMessageType::ForwardRequest => routing::process(message, state, session).await,
+            SDKMessageType::CoordinateMediationRequest => {
+                coordinate_mediation::mediate_request(message, state, session).await
+            }
+            SDKMessageType::CoordinateMediationKeylistUpdate => {
+                coordinate_mediation::keylist_update(message, state, session).await
+            }
+            SDKMessageType::CoordinateMediationKeylistQuery => {
+                coordinate_mediation::keylist_query(message, state, session).await
+            }
+            SDKMessageType::CoordinateMediationGrant
+            | SDKMessageType::CoordinateMediationDeny
+            | SDKMessageType::CoordinateMediationKeylistUpdateResponse
+            | SDKMessageType::CoordinateMediationKeylist => Err(MediatorError::NotImplemented(
+                session.session_id.clone(),
+                "Mediator does not handle Coordinate Mediation responses".into(),
+            )),
             SDKMessageType::ProblemReport => Err(MediatorError::NotImplemented(
                 session.session_id.clone(),
                 "Problem Report is only handled by the Error handler".into(),
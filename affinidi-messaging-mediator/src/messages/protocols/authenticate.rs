@@ -0,0 +1,98 @@
+/*!
+ * In-band authentication refresh
+ *
+ * Long lived websocket connections can refresh their access token by sending an
+ * `https://affinidi.com/atm/1.0/authenticate/refresh` message over the websocket.
+ * The response carries the new access token and a rotated refresh token, and the websocket stays
+ * open until the new access token expires instead of disconnecting at the original JWT expiry.
+ * The response is only returned on the websocket that asked for it, it is never stored or
+ * live streamed to other connections of the DID.
+ */
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use serde_json::json;
+use std::{sync::atomic::Ordering, time::SystemTime};
+use tracing::{Instrument, info, span};
+use uuid::Uuid;
+
+use crate::{
+    SharedData,
+    database::session::Session,
+    handlers::authenticate::refresh_access_token,
+    messages::{ProcessMessageResponse, WrapperType},
+};
+
+pub(crate) const REFRESH_RESPONSE: &str =
+    "https://affinidi.com/atm/1.0/authenticate/refresh/response";
+
+/// Process an authentication refresh message, responds with a new access token
+pub(crate) async fn refresh(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "authenticate_refresh");
+
+    async move {
+        // Only the session DID can refresh the session
+        if msg.from.as_deref() != Some(session.did.as_str()) {
+            return Err(MediatorError::Unauthorized(
+                session.session_id.clone(),
+                "Refresh must be sent from the session DID".into(),
+            ));
+        }
+
+        let Some(refresh_token) = msg.body.get("refresh_token").and_then(|t| t.as_str()) else {
+            return Err(MediatorError::RequestDataError(
+                session.session_id.clone(),
+                "Couldn't parse message body into refresh_token".into(),
+            ));
+        };
+
+        let (refreshed_session, refreshed) =
+            refresh_access_token(state, refresh_token, &session.did).await?;
+
+        // The refresh token must belong to this session
+        if refreshed_session.session_id != session.session_id {
+            return Err(MediatorError::SessionError(
+                session.session_id.clone(),
+                "Refresh token belongs to a different session".into(),
+            ));
+        }
+
+        // Extends any websocket connections using this session
+        session
+            .refreshed_expires_at
+            .store(refreshed.access_expires_at, Ordering::Relaxed);
+        info!(
+            "{}: Session refreshed in-band, expires at ({})",
+            session.session_id, refreshed.access_expires_at
+        );
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let response = Message::build(
+            Uuid::new_v4().into(),
+            REFRESH_RESPONSE.to_owned(),
+            json!(refreshed),
+        )
+        .thid(msg.id.clone())
+        .to(session.did.clone())
+        .from(state.config.mediator_did.clone())
+        .created_time(now)
+        .expires_time(now + 300)
+        .finalize();
+
+        Ok(ProcessMessageResponse {
+            store_message: false,
+            force_live_delivery: false,
+            data: WrapperType::Message(response),
+            forward_message: false,
+        })
+    }
+    .instrument(_span)
+    .await
+}
@@ -0,0 +1,478 @@
+/*!
+ * Coordinate Mediation 2.0 implementation
+ * <https://didcomm.org/coordinate-mediation/2.0/>
+ *
+ * Maps the protocol onto mediator accounts and ACLs:
+ * - mediate-request is granted when the requesting DID is a local account that isn't blocked
+ * - keylist-update adds recipient DIDs to the requestor's keylist and creates an account for each
+ *   new recipient DID (using the global default ACLs), so forwarded messages are accepted for it
+ * - keylist-update remove only removes the DID from the keylist, the account is left in place
+ * - keylist-query pages through the requestor's keylist
+ * - keylist-update and keylist-query are only available to DIDs that would be granted mediation
+ */
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::{
+    messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
+    protocols::{
+        coordinate_mediation::{
+            KEYLIST, KEYLIST_UPDATE_RESPONSE, Keylist, KeylistAction, KeylistKey,
+            KeylistPagination, KeylistQuery, KeylistUpdate, KeylistUpdateResponse,
+            KeylistUpdateResult, KeylistUpdated, MEDIATE_DENY, MEDIATE_GRANT, MediateGrant,
+        },
+        mediator::{accounts::AccountType, acls::AccessListModeType},
+    },
+};
+use serde_json::{Value, json};
+use sha256::digest;
+use std::time::SystemTime;
+use tracing::{Instrument, debug, info, span, warn};
+use uuid::Uuid;
+
+use crate::{
+    SharedData,
+    database::session::Session,
+    messages::{ProcessMessageResponse, error_response::generate_error_response},
+};
+
+/// Maximum number of recipient DIDs returned in a single keylist message, or changed by a single
+/// keylist-update message
+const MAX_KEYLIST_LIMIT: usize = 100;
+
+/// Is the session's DID granted mediation (a local account that isn't blocked)?
+fn _mediation_granted(session: &Session) -> bool {
+    session.acls.get_local() && !session.acls.get_blocked()
+}
+
+/// Process a mediate-request message, responds with a mediate-grant or mediate-deny
+pub(crate) async fn mediate_request(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "mediate_request");
+
+    async move {
+        if _mediation_granted(session) {
+            info!("Mediation granted to DID ({})", session.did_hash);
+            _generate_response_message(
+                msg,
+                state,
+                session,
+                MEDIATE_GRANT,
+                json!(MediateGrant {
+                    routing_did: vec![state.config.mediator_did.clone()],
+                }),
+            )
+        } else {
+            info!("Mediation denied to DID ({})", session.did_hash);
+            _generate_response_message(msg, state, session, MEDIATE_DENY, json!({}))
+        }
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Process a keylist-update message, responds with a keylist-update-response
+pub(crate) async fn keylist_update(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "keylist_update");
+
+    async move {
+        if !_mediation_granted(session) {
+            return _unauthorized(msg, state, session, "keylist-update");
+        }
+
+        let request: KeylistUpdate = match serde_json::from_value(msg.body.clone()) {
+            Ok(request) => request,
+            Err(err) => return _invalid_request(msg, state, session, "keylist-update", err),
+        };
+
+        if request.updates.len() > MAX_KEYLIST_LIMIT {
+            warn!(
+                "keylist-update from DID ({}) has too many updates ({})",
+                session.did_hash,
+                request.updates.len()
+            );
+            return generate_error_response(
+                state,
+                session,
+                &msg.id,
+                ProblemReport::new(
+                    ProblemReportSorter::Error,
+                    ProblemReportScope::Protocol,
+                    "invalid_request".into(),
+                    "keylist-update has {1} updates, at most {2} are allowed".into(),
+                    vec![
+                        request.updates.len().to_string(),
+                        MAX_KEYLIST_LIMIT.to_string(),
+                    ],
+                    None,
+                ),
+                false,
+            );
+        }
+
+        let mut updated = Vec::with_capacity(request.updates.len());
+        for update in request.updates {
+            let result = match update.action {
+                KeylistAction::Add => _keylist_add(state, session, &update.recipient_did).await,
+                KeylistAction::Remove => match state
+                    .database
+                    .keylist_remove(&session.did_hash, &update.recipient_did)
+                    .await
+                {
+                    Ok(true) => KeylistUpdateResult::Success,
+                    Ok(false) => KeylistUpdateResult::NoChange,
+                    Err(err) => {
+                        warn!("Error removing keylist DID. Reason: {}", err);
+                        KeylistUpdateResult::ServerError
+                    }
+                },
+            };
+            debug!(
+                "keylist {:?} ({}): {:?}",
+                update.action, update.recipient_did, result
+            );
+
+            updated.push(KeylistUpdated {
+                recipient_did: update.recipient_did,
+                action: update.action,
+                result,
+            });
+        }
+
+        _generate_response_message(
+            msg,
+            state,
+            session,
+            KEYLIST_UPDATE_RESPONSE,
+            json!(KeylistUpdateResponse { updated }),
+        )
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Adds a recipient DID to the keylist, creating an account for it if required
+async fn _keylist_add(
+    state: &SharedData,
+    session: &Session,
+    recipient_did: &str,
+) -> KeylistUpdateResult {
+    let recipient_hash = digest(recipient_did);
+
+    match state.database.account_exists(&recipient_hash).await {
+        Ok(true) => {}
+        Ok(false) => {
+            // Only admins can create new accounts when the mediator is in explicit_allow mode
+            if state.config.security.mediator_acl_mode == AccessListModeType::ExplicitAllow
+                && !(session.account_type == AccountType::Admin
+                    || session.account_type == AccountType::RootAdmin)
+            {
+                warn!(
+                    "DID ({}) is not an admin account, can't create account for ({})",
+                    session.did_hash, recipient_did
+                );
+                return KeylistUpdateResult::ClientError;
+            }
+
+            if let Err(err) = state
+                .database
+                .account_add(
+                    &recipient_hash,
+                    &state.config.security.global_acl_default,
+                    None,
+                )
+                .await
+            {
+                warn!("Error adding keylist account. Reason: {}", err);
+                return KeylistUpdateResult::ServerError;
+            }
+        }
+        Err(err) => {
+            warn!("Error checking keylist account. Reason: {}", err);
+            return KeylistUpdateResult::ServerError;
+        }
+    }
+
+    match state
+        .database
+        .keylist_add(&session.did_hash, recipient_did)
+        .await
+    {
+        Ok(true) => KeylistUpdateResult::Success,
+        Ok(false) => KeylistUpdateResult::NoChange,
+        Err(err) => {
+            warn!("Error adding keylist DID. Reason: {}", err);
+            KeylistUpdateResult::ServerError
+        }
+    }
+}
+
+/// Process a keylist-query message, responds with a keylist
+pub(crate) async fn keylist_query(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "keylist_query");
+
+    async move {
+        if !_mediation_granted(session) {
+            return _unauthorized(msg, state, session, "keylist-query");
+        }
+
+        let request: KeylistQuery = match serde_json::from_value(msg.body.clone()) {
+            Ok(request) => request,
+            Err(err) => return _invalid_request(msg, state, session, "keylist-query", err),
+        };
+
+        let (offset, limit) = match &request.paginate {
+            Some(paginate) => (paginate.offset, paginate.limit.min(MAX_KEYLIST_LIMIT)),
+            None => (0, MAX_KEYLIST_LIMIT),
+        };
+
+        let (dids, total) = match state
+            .database
+            .keylist_list(&session.did_hash, offset, limit)
+            .await
+        {
+            Ok(response) => response,
+            Err(err) => {
+                warn!("Error retrieving keylist. Reason: {}", err);
+                return generate_error_response(
+                    state,
+                    session,
+                    &msg.id,
+                    ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "database_error".into(),
+                        "Error retrieving keylist {1}".into(),
+                        vec![err.to_string()],
+                        None,
+                    ),
+                    false,
+                );
+            }
+        };
+
+        let pagination = KeylistPagination {
+            count: dids.len(),
+            offset,
+            remaining: total.saturating_sub(offset + dids.len()),
+        };
+
+        _generate_response_message(
+            msg,
+            state,
+            session,
+            KEYLIST,
+            json!(Keylist {
+                keys: dids
+                    .into_iter()
+                    .map(|recipient_did| KeylistKey { recipient_did })
+                    .collect(),
+                pagination: Some(pagination),
+            }),
+        )
+    }
+    .instrument(_span)
+    .await
+}
+
+fn _unauthorized(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+    name: &str,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    warn!(
+        "DID ({}) isn't granted mediation, {} denied",
+        session.did_hash, name
+    );
+    generate_error_response(
+        state,
+        session,
+        &msg.id,
+        ProblemReport::new(
+            ProblemReportSorter::Error,
+            ProblemReportScope::Protocol,
+            "unauthorized".into(),
+            "{1} is only available to DIDs that are granted mediation".into(),
+            vec![name.to_string()],
+            None,
+        ),
+        false,
+    )
+}
+
+fn _invalid_request(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+    name: &str,
+    err: serde_json::Error,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    warn!("Error parsing {} request. Reason: {}", name, err);
+    generate_error_response(
+        state,
+        session,
+        &msg.id,
+        ProblemReport::new(
+            ProblemReportSorter::Error,
+            ProblemReportScope::Protocol,
+            "invalid_request".into(),
+            "Error parsing {1} request. Reason: {2}".into(),
+            vec![name.to_string(), err.to_string()],
+            None,
+        ),
+        false,
+    )
+}
+
+fn _generate_response_message(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+    type_: &str,
+    body: Value,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // Build the message
+    let response = Message::build(Uuid::new_v4().into(), type_.to_owned(), body)
+        .thid(msg.id.clone())
+        .to(session.did.clone())
+        .from(state.config.mediator_did.clone())
+        .created_time(now)
+        .expires_time(now + 300)
+        .finalize();
+
+    Ok(ProcessMessageResponse {
+        store_message: true,
+        force_live_delivery: false,
+        data: crate::messages::WrapperType::Message(response),
+        forward_message: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::memory_store::MemoryStore, messages::WrapperType};
+    use affinidi_messaging_sdk::protocols::{
+        coordinate_mediation::{KEYLIST_QUERY, KEYLIST_UPDATE, KeylistUpdateItem},
+        mediator::acls::MediatorACLSet,
+    };
+    use std::sync::Arc;
+
+    fn _session(local: bool, blocked: bool) -> Session {
+        let mut acls = MediatorACLSet::from_u64(0);
+        acls.set_local(local);
+        acls.set_blocked(blocked);
+        Session {
+            session_id: "test".into(),
+            did: "did:example:alice".into(),
+            did_hash: digest("did:example:alice"),
+            acls,
+            ..Default::default()
+        }
+    }
+
+    fn _message(type_: &str, body: Value) -> Message {
+        Message::build(Uuid::new_v4().into(), type_.to_owned(), body).finalize()
+    }
+
+    fn _add(count: usize) -> Message {
+        _message(
+            KEYLIST_UPDATE,
+            json!(KeylistUpdate {
+                updates: (0..count)
+                    .map(|i| KeylistUpdateItem {
+                        recipient_did: format!("did:example:{}", i),
+                        action: KeylistAction::Add,
+                    })
+                    .collect(),
+            }),
+        )
+    }
+
+    /// Returns the response message, asserting its type
+    fn _response(response: ProcessMessageResponse, type_: &str) -> Message {
+        let WrapperType::Message(message) = response.data else {
+            panic!("Expected a response message");
+        };
+        assert_eq!(message.type_, type_);
+        message
+    }
+
+    async fn _keylist(state: &SharedData) -> Vec<String> {
+        state
+            .database
+            .keylist_list(&digest("did:example:alice"), 0, 100)
+            .await
+            .unwrap()
+            .0
+    }
+
+    #[tokio::test]
+    async fn test_keylist_requires_mediation() {
+        let state = SharedData::for_tests(Arc::new(MemoryStore::new())).await;
+        let problem_report = "https://didcomm.org/report-problem/2.0/problem-report";
+
+        for session in [_session(false, false), _session(true, true)] {
+            let response = keylist_update(&_add(1), &state, &session).await.unwrap();
+            _response(response, problem_report);
+
+            let query = _message(KEYLIST_QUERY, json!({}));
+            let response = keylist_query(&query, &state, &session).await.unwrap();
+            _response(response, problem_report);
+        }
+        assert!(_keylist(&state).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_keylist_update_and_query() {
+        let state = SharedData::for_tests(Arc::new(MemoryStore::new())).await;
+        let session = _session(true, false);
+
+        let response = keylist_update(&_add(2), &state, &session).await.unwrap();
+        let response: KeylistUpdateResponse =
+            serde_json::from_value(_response(response, KEYLIST_UPDATE_RESPONSE).body).unwrap();
+        assert_eq!(response.updated.len(), 2);
+        assert!(
+            response
+                .updated
+                .iter()
+                .all(|updated| updated.result == KeylistUpdateResult::Success)
+        );
+
+        let query = _message(KEYLIST_QUERY, json!({}));
+        let response = keylist_query(&query, &state, &session).await.unwrap();
+        let keylist: Keylist = serde_json::from_value(_response(response, KEYLIST).body).unwrap();
+        assert_eq!(keylist.keys.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_keylist_update_limit() {
+        let state = SharedData::for_tests(Arc::new(MemoryStore::new())).await;
+        let session = _session(true, false);
+
+        let response = keylist_update(&_add(MAX_KEYLIST_LIMIT + 1), &state, &session)
+            .await
+            .unwrap();
+        _response(
+            response,
+            "https://didcomm.org/report-problem/2.0/problem-report",
+        );
+        assert!(_keylist(&state).await.is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+/*!
+ * Discover Features 2.0 implementation
+ * <https://didcomm.org/discover-features/2.0/>
+ *
+ * Discloses the protocols wired up in `MessageType::process`, the goal codes
+ * (optional mediator capabilities) and the mediator ACL mode. Protocols and goal codes the
+ * requesting DID isn't allowed to use (based on its ACLs) are not disclosed.
+ */
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::{
+    messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
+    protocols::{
+        discover_features::{
+            DISCLOSE, Disclose, Disclosure, FEATURE_ACL_MODE, FEATURE_GOAL_CODE, FEATURE_PROTOCOL,
+            Queries,
+        },
+        mediator::{
+            accounts::AccountType,
+            acls::{AccessListModeType, MediatorACLSet},
+        },
+    },
+};
+use serde_json::json;
+use std::time::SystemTime;
+use tracing::{Instrument, debug, span, warn};
+use uuid::Uuid;
+
+use crate::{
+    SharedData,
+    database::session::Session,
+    messages::{ProcessMessageResponse, error_response::generate_error_response},
+};
+
+/// Who a feature is disclosed to
+enum Allowed {
+    All,
+    Admin,
+    /// Checked against the requesting DID's ACLs
+    Acl(fn(&MediatorACLSet) -> bool),
+    /// Mediator live streaming must be enabled and the DID allowed to receive messages
+    Streaming,
+    /// Push notifications must be enabled and the DID allowed to receive messages
+    PushNotifications,
+}
+
+/// Protocols handled by the mediator: (PIURI, roles, who it is disclosed to)
+const PROTOCOLS: &[(&str, &[&str], Allowed)] = &[
+    (
+        "https://affinidi.com/atm/1.0/authenticate",
+        &["responder"],
+        Allowed::All,
+    ),
+    (
+        "https://didcomm.org/coordinate-mediation/2.0",
+        &["mediator"],
+        Allowed::Acl(MediatorACLSet::get_local),
+    ),
+    (
+        "https://didcomm.org/discover-features/2.0",
+        &["responder"],
+        Allowed::All,
+    ),
+    (
+        "https://didcomm.org/mediator/1.0/account-management",
+        &["mediator"],
+        Allowed::All,
+    ),
+    (
+        "https://didcomm.org/mediator/1.0/acl-management",
+        &["mediator"],
+        Allowed::All,
+    ),
+    (
+        "https://didcomm.org/mediator/1.0/admin-management",
+        &["mediator"],
+        Allowed::Admin,
+    ),
+    (
+        "https://didcomm.org/mediator/1.0/push-notifications",
+        &["mediator"],
+        Allowed::PushNotifications,
+    ),
+    (
+        "https://didcomm.org/messagepickup/3.0",
+        &["mediator"],
+        Allowed::Acl(|acls| acls.get_receive_messages().0),
+    ),
+    (
+        "https://didcomm.org/report-problem/2.0",
+        &["notifier"],
+        Allowed::All,
+    ),
+    (
+        "https://didcomm.org/routing/2.0",
+        &["mediator"],
+        Allowed::Acl(|acls| acls.get_send_forwarded().0),
+    ),
+    (
+        "https://didcomm.org/trust-ping/2.0",
+        &["receiver"],
+        Allowed::All,
+    ),
+];
+
+/// Optional mediator capabilities: (goal code, who it is disclosed to)
+const GOAL_CODES: &[(&str, Allowed)] = &[
+    (
+        "affinidi.messaging.oob-discovery",
+        Allowed::Acl(|acls| acls.get_create_invites().0),
+    ),
+    ("affinidi.messaging.live-delivery", Allowed::Streaming),
+];
+
+/// Process a Discover Features queries message, responds with a disclose message
+pub(crate) async fn process(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "discover_features");
+
+    async move {
+        let queries: Queries = match serde_json::from_value(msg.body.clone()) {
+            Ok(queries) => queries,
+            Err(err) => {
+                warn!("Error parsing Discover Features queries. Reason: {}", err);
+                return generate_error_response(
+                    state,
+                    session,
+                    &msg.id,
+                    ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "invalid_request".into(),
+                        "Error parsing Discover Features queries. Reason: {1}".into(),
+                        vec![err.to_string()],
+                        None,
+                    ),
+                    false,
+                );
+            }
+        };
+
+        let disclosures: Vec<Disclosure> = _features(state, session)
+            .into_iter()
+            .filter(|feature| {
+                queries.queries.iter().any(|query| {
+                    query.feature_type == feature.feature_type
+                        && _matches(&query.match_, &feature.id)
+                })
+            })
+            .collect();
+        debug!("Disclosing {} features", disclosures.len());
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let response = Message::build(
+            Uuid::new_v4().into(),
+            DISCLOSE.to_owned(),
+            json!(Disclose { disclosures }),
+        )
+        .thid(msg.id.clone())
+        .to(session.did.clone())
+        .from(state.config.mediator_did.clone())
+        .created_time(now)
+        .expires_time(now + 300)
+        .finalize();
+
+        Ok(ProcessMessageResponse {
+            store_message: true,
+            force_live_delivery: false,
+            data: crate::messages::WrapperType::Message(response),
+            forward_message: false,
+        })
+    }
+    .instrument(_span)
+    .await
+}
+
+/// All features available to the session's DID
+fn _features(state: &SharedData, session: &Session) -> Vec<Disclosure> {
+    let allowed = |allowed: &Allowed| match allowed {
+        Allowed::All => true,
+        Allowed::Admin => {
+            session.account_type == AccountType::Admin
+                || session.account_type == AccountType::RootAdmin
+        }
+        Allowed::Acl(check) => check(&session.acls),
+        Allowed::Streaming => {
+            state.config.streaming_enabled && session.acls.get_receive_messages().0
+        }
+        Allowed::PushNotifications => {
+            state.config.push_notifications.enabled && session.acls.get_receive_messages().0
+        }
+    };
+
+    let protocols = PROTOCOLS
+        .iter()
+        .filter(|(_, _, who)| allowed(who))
+        .map(|(id, roles, _)| Disclosure {
+            feature_type: FEATURE_PROTOCOL.into(),
+            id: id.to_string(),
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        });
+
+    let goal_codes = GOAL_CODES
+        .iter()
+        .filter(|(_, who)| allowed(who))
+        .map(|(id, _)| Disclosure {
+            feature_type: FEATURE_GOAL_CODE.into(),
+            id: id.to_string(),
+            roles: Vec::new(),
+        });
+
+    let acl_mode = Disclosure {
+        feature_type: FEATURE_ACL_MODE.into(),
+        id: match state.config.security.mediator_acl_mode {
+            AccessListModeType::ExplicitAllow => "explicit_allow".into(),
+            AccessListModeType::ExplicitDeny => "explicit_deny".into(),
+        },
+        roles: Vec::new(),
+    };
+
+    protocols
+        .chain(goal_codes)
+        .chain(std::iter::once(acl_mode))
+        .collect()
+}
+
+/// Matches a feature id against a query, where `*` matches any sequence of characters
+fn _matches(pattern: &str, id: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always returns at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = id.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No wildcard, must be an exact match
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::_matches;
+
+    #[test]
+    fn test_matches() {
+        assert!(_matches("*", "https://didcomm.org/routing/2.0"));
+        assert!(_matches(
+            "https://didcomm.org/messagepickup/3.*",
+            "https://didcomm.org/messagepickup/3.0"
+        ));
+        assert!(_matches(
+            "https://didcomm.org/*/2.0",
+            "https://didcomm.org/trust-ping/2.0"
+        ));
+        assert!(_matches(
+            "https://didcomm.org/routing/2.0",
+            "https://didcomm.org/routing/2.0"
+        ));
+        assert!(!_matches(
+            "https://didcomm.org/routing/2.0",
+            "https://didcomm.org/routing/2.01"
+        ));
+        assert!(!_matches(
+            "https://didcomm.org/messagepickup/2.*",
+            "https://didcomm.org/messagepickup/3.0"
+        ));
+    }
+}
@@ -5,8 +5,12 @@ use affinidi_messaging_mediator_common::errors::MediatorError;
 use affinidi_messaging_sdk::{
     messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
     protocols::mediator::{
-        accounts::{AccountChangeQueueLimitsResponse, AccountType, MediatorAccountRequest},
+        accounts::{
+            AccountChangeQueueLimitsResponse, AccountChangeRateLimitResponse, AccountType,
+            MediatorAccountRequest,
+        },
         acls::{AccessListModeType, MediatorACLSet},
+        administration::AuditAction,
     },
 };
 use serde_json::{Value, json};
@@ -20,7 +24,7 @@ use crate::{
     messages::{ProcessMessageResponse, error_response::generate_error_response},
 };
 
-use super::acls::check_permissions;
+use super::{acls::check_permissions, audit};
 
 pub(crate) async fn process(
     msg: &Message,
@@ -180,17 +184,21 @@ pub(crate) async fn process(
                     state.config.security.global_acl_default.clone()
                 };
 
+                let acls_before = audit::current_acls(state, &did_hash).await;
                 match state
                     .database
                     .account_add(&did_hash, &acls, None)
                     .await
                 {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!(response),
-                    ),
+                    Ok(response) => {
+                        audit::record(state, session, AuditAction::AccountAdd, &did_hash, acls_before, None).await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!(response),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error adding account. Reason: {}", err);
                         generate_error_response(
@@ -268,13 +276,17 @@ pub(crate) async fn process(
                         false,
                     );
                 }
-                match state.database.account_remove(session,&did_hash, false, false).await {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!(response),
-                    ),
+                let acls_before = audit::current_acls(state, &did_hash).await;
+                match state.database.account_remove(session,&did_hash, false).await {
+                    Ok(response) => {
+                        audit::record(state, session, AuditAction::AccountRemove, &did_hash, acls_before, None).await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!(response),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error removing account. Reason: {}", err);
                         generate_error_response(
@@ -309,6 +321,8 @@ pub(crate) async fn process(
 
                 // Get current account type and handle any shift to/from admin
                 let current = state.database.account_get(&did_hash).await?;
+                let acls_before = current.as_ref().map(|current| MediatorACLSet::from_u64(current.acls));
+                let type_change = format!("{} -> {}", current.as_ref().map(|current| current._type).unwrap_or(AccountType::Unknown), _type);
                 if let Some(current) = &current {
                     if current._type == _type {
                         // Types are the same, no need to change.
@@ -328,6 +342,7 @@ pub(crate) async fn process(
                         // Need to add admin rights
                         state.database.setup_admin_account(&did_hash, _type, &MediatorACLSet::from_u64(current.acls)).await?;
                         info!("Added admin ({}) rights to DID: {}", _type, did_hash);
+                        audit::record(state, session, AuditAction::AccountChangeType, &did_hash, acls_before, Some(type_change)).await;
                         return _generate_response_message(
                             &msg.id,
                             &session.did,
@@ -347,6 +362,7 @@ pub(crate) async fn process(
                             "Unknown".to_string()
                         };
                         info!("Changed account type for DID: ({}) from ({}) to ({})", did_hash, current_type, _type);
+                        audit::record(state, session, AuditAction::AccountChangeType, &did_hash, acls_before, Some(type_change)).await;
                         _generate_response_message(
                         &msg.id,
                         &session.did,
@@ -372,7 +388,7 @@ pub(crate) async fn process(
                     }
                 }
             }
-            MediatorAccountRequest::AccountChangeQueueLimits {did_hash, send_queue_limit, receive_queue_limit } => {
+            MediatorAccountRequest::AccountChangeQueueLimits {did_hash, send_queue_limit, receive_queue_limit, send_queue_bytes_limit, receive_queue_bytes_limit } => {
                  // Check permissions and ACLs
                  if !check_permissions(session, &[did_hash.clone()]) {
                     warn!("ACL Request from DID ({}) failed. ", session.did_hash);
@@ -433,14 +449,40 @@ pub(crate) async fn process(
                     (send_queue_limit, receive_queue_limit)
                 };
 
-                match state.database.account_change_queue_limits(&did_hash, send_queue_limit, receive_queue_limit).await {
+                // Byte quotas follow the same ACLs, capped at the hard limits
+                let (send_queue_bytes_limit, receive_queue_bytes_limit) = if session.account_type == AccountType::Standard {
+                    let send_queue_bytes_limit = if session.acls.get_self_manage_send_queue_limit() {
+                        _cap_bytes_limit(send_queue_bytes_limit, state.config.limits.queued_send_bytes_hard)
+                    } else {
+                        None
+                    };
+
+                    let receive_queue_bytes_limit = if session.acls.get_self_manage_receive_queue_limit() {
+                        _cap_bytes_limit(receive_queue_bytes_limit, state.config.limits.queued_receive_bytes_hard)
+                    } else {
+                        None
+                    };
+
+                    (send_queue_bytes_limit, receive_queue_bytes_limit)
+                } else {
+                    // Admin account
+                    (send_queue_bytes_limit, receive_queue_bytes_limit)
+                };
+
+                let acls_before = audit::current_acls(state, &did_hash).await;
+                let result = match state.database.account_change_queue_limits(&did_hash, send_queue_limit, receive_queue_limit).await {
+                    Ok(_) => state.database.account_change_queue_bytes_limits(&did_hash, send_queue_bytes_limit, receive_queue_bytes_limit).await,
+                    Err(err) => Err(err),
+                };
+                match result {
                     Ok(_) => {
-                        info!("Changed account queue_limits for DID: ({}) to send({:?}) receive({:?})", did_hash, send_queue_limit, receive_queue_limit);
+                        info!("Changed account queue_limits for DID: ({}) to send({:?}) receive({:?}) send_bytes({:?}) receive_bytes({:?})", did_hash, send_queue_limit, receive_queue_limit, send_queue_bytes_limit, receive_queue_bytes_limit);
+                        audit::record(state, session, AuditAction::AccountChangeQueueLimits, &did_hash, acls_before, Some(format!("send({:?}) receive({:?}) send_bytes({:?}) receive_bytes({:?})", send_queue_limit, receive_queue_limit, send_queue_bytes_limit, receive_queue_bytes_limit))).await;
                         _generate_response_message(
                         &msg.id,
                         &session.did,
                         &state.config.mediator_did,
-                        &json!(AccountChangeQueueLimitsResponse { send_queue_limit, receive_queue_limit}),
+                        &json!(AccountChangeQueueLimitsResponse { send_queue_limit, receive_queue_limit, send_queue_bytes_limit, receive_queue_bytes_limit }),
                     )}
                     Err(err) => {
                         warn!("Error Changing account type. Reason: {}", err);
@@ -461,6 +503,59 @@ pub(crate) async fn process(
                     }
                 }
             }
+            MediatorAccountRequest::AccountChangeRateLimit { did_hash, rate_limit } => {
+                // Must be an admin level account to change this
+                if !state.database.check_admin_account(&session.did_hash).await? {
+                    warn!("DID ({}) is not an admin account", session.did_hash);
+                    return generate_error_response(state, session, &msg.id, ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "unauthorized".into(),
+                        "unauthorized to change account rate limit. Must be an administrator for this Mediator!".into(),
+                        vec![], None
+                    ), false);
+                }
+
+                if rate_limit < -2 {
+                    return generate_error_response(state, session, &msg.id, ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "invalid_request".into(),
+                        "rate_limit ({1}) is invalid. Use -1 (unlimited), -2 (reset) or the requests per minute".into(),
+                        vec![rate_limit.to_string()], None
+                    ), false);
+                }
+
+                let acls_before = audit::current_acls(state, &did_hash).await;
+                match state.database.account_change_rate_limit(&did_hash, rate_limit).await {
+                    Ok(_) => {
+                        info!("Changed account rate_limit for DID: ({}) to ({})", did_hash, rate_limit);
+                        audit::record(state, session, AuditAction::AccountChangeRateLimit, &did_hash, acls_before, Some(format!("rate_limit({})", rate_limit))).await;
+                        _generate_response_message(
+                        &msg.id,
+                        &session.did,
+                        &state.config.mediator_did,
+                        &json!(AccountChangeRateLimitResponse { rate_limit: (rate_limit != -2).then_some(rate_limit) }),
+                    )}
+                    Err(err) => {
+                        warn!("Error changing account rate_limit. Reason: {}", err);
+                        generate_error_response(
+                            state,
+                            session,
+                            &msg.id,
+                            ProblemReport::new(
+                                ProblemReportSorter::Error,
+                                ProblemReportScope::Protocol,
+                                "database_error".into(),
+                                "Error changing account rate limit. Reason: {1}".into(),
+                                vec![err.to_string()],
+                                None,
+                            ),
+                            false,
+                        )
+                    }
+                }
+            }
         }
     }
     .instrument(_span)
@@ -503,3 +598,14 @@ fn _generate_response_message(
         forward_message: false,
     })
 }
+
+/// Caps a queue byte quota requested by a standard account at the hard limit
+/// -2 (reset to the soft limit) is passed through, -1 (unlimited) is only allowed if the hard limit is unlimited
+fn _cap_bytes_limit(limit: Option<i64>, hard_limit: i64) -> Option<i64> {
+    match limit {
+        Some(-2) => limit,
+        Some(_) if hard_limit == -1 => limit,
+        Some(limit) if limit == -1 || limit > hard_limit => Some(hard_limit),
+        _ => limit,
+    }
+}
@@ -11,12 +11,15 @@ use affinidi_messaging_sdk::{
     messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
     protocols::mediator::{
         accounts::AccountType, acls::MediatorACLSet, acls_handler::MediatorACLRequest,
+        administration::AuditAction,
     },
 };
 use serde_json::{Value, json};
 use tracing::{Instrument, span, warn};
 use uuid::Uuid;
 
+use super::audit;
+
 pub(crate) async fn process(
     msg: &Message,
     state: &SharedData,
@@ -119,17 +122,29 @@ pub(crate) async fn process(
                     );
                 }
 
+                let acls_before = audit::current_acls(state, &did_hash).await;
                 match state
                     .database
                     .set_did_acl(&did_hash, &MediatorACLSet::from_u64(acls))
                     .await
                 {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!({"acls": response}),
-                    ),
+                    Ok(response) => {
+                        audit::record(
+                            state,
+                            session,
+                            AuditAction::AclSet,
+                            &did_hash,
+                            acls_before,
+                            None,
+                        )
+                        .await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!({"acls": response}),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error setting ACLs. Reason: {}", err);
                         generate_error_response(
@@ -236,17 +251,29 @@ pub(crate) async fn process(
                     );
                 }
 
+                let acls_before = audit::current_acls(state, &did_hash).await;
                 match state
                     .database
                     .access_list_add(state.config.limits.access_list_limit, &did_hash, &hashes)
                     .await
                 {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!(response),
-                    ),
+                    Ok(response) => {
+                        audit::record(
+                            state,
+                            session,
+                            AuditAction::AccessListAdd,
+                            &did_hash,
+                            acls_before,
+                            Some(format!("{} hashes", hashes.len())),
+                        )
+                        .await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!(response),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error Add to Access List. Reason: {}", err);
                         generate_error_response(
@@ -306,13 +333,25 @@ pub(crate) async fn process(
                     );
                 }
 
+                let acls_before = audit::current_acls(state, &did_hash).await;
                 match state.database.access_list_remove(&did_hash, &hashes).await {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!(response),
-                    ),
+                    Ok(response) => {
+                        audit::record(
+                            state,
+                            session,
+                            AuditAction::AccessListRemove,
+                            &did_hash,
+                            acls_before,
+                            Some(format!("{} hashes", hashes.len())),
+                        )
+                        .await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!(response),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error Remove from Access List. Reason: {}", err);
                         generate_error_response(
@@ -352,13 +391,25 @@ pub(crate) async fn process(
                     );
                 }
 
+                let acls_before = audit::current_acls(state, &did_hash).await;
                 match state.database.access_list_clear(&did_hash).await {
-                    Ok(response) => _generate_response_message(
-                        &msg.id,
-                        &session.did,
-                        &state.config.mediator_did,
-                        &json!(response),
-                    ),
+                    Ok(response) => {
+                        audit::record(
+                            state,
+                            session,
+                            AuditAction::AccessListClear,
+                            &did_hash,
+                            acls_before,
+                            None,
+                        )
+                        .await;
+                        _generate_response_message(
+                            &msg.id,
+                            &session.did,
+                            &state.config.mediator_did,
+                            &json!(response),
+                        )
+                    }
                     Err(err) => {
                         warn!("Error Clearing Access List. Reason: {}", err);
                         generate_error_response(
@@ -6,13 +6,14 @@ use affinidi_messaging_didcomm::Message;
 use affinidi_messaging_mediator_common::errors::MediatorError;
 use affinidi_messaging_sdk::{
     messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
-    protocols::mediator::administration::MediatorAdminRequest,
+    protocols::mediator::administration::{AuditAction, MediatorAdminRequest},
 };
 use serde_json::{Value, json};
 use sha256::digest;
 use tracing::{Instrument, span, warn};
 use uuid::Uuid;
 
+use super::audit;
 use crate::{
     SharedData,
     database::session::Session,
@@ -79,8 +80,15 @@ pub(crate) async fn process(
                 }
             }
             MediatorAdminRequest::AdminAdd(attr) => {
+                let mut acls_before = Vec::with_capacity(attr.len());
+                for did_hash in &attr {
+                    acls_before.push((did_hash.clone(), audit::current_acls(state, did_hash).await));
+                }
                 match  state.database.add_admin_accounts(attr, &state.config.security.global_acl_default).await {
                     Ok(response) => {
+                        for (did_hash, acls) in acls_before {
+                            audit::record(state, session, AuditAction::AdminAdd, &did_hash, acls, None).await;
+                        }
                         _generate_response_message(&msg.id, &session.did, &state.config.mediator_did, &json!(response))
                     }
                     Err(err) => {
@@ -109,8 +117,15 @@ pub(crate) async fn process(
                         vec![], None
                     ), false);
                 }
+                let mut acls_before = Vec::with_capacity(attr.len());
+                for did_hash in &attr {
+                    acls_before.push((did_hash.clone(), audit::current_acls(state, did_hash).await));
+                }
                 match  state.database.strip_admin_accounts(attr).await {
                     Ok(response) => {
+                        for (did_hash, acls) in acls_before {
+                            audit::record(state, session, AuditAction::AdminStrip, &did_hash, acls, None).await;
+                        }
                         _generate_response_message(&msg.id, &session.did, &state.config.mediator_did, &json!(response))
                     }
                     Err(err) => {
@@ -125,6 +140,24 @@ pub(crate) async fn process(
                     }
                 }
             }
+            MediatorAdminRequest::AuditLog{cursor, limit, filter} => {
+                let limit = limit.clamp(1, 100);
+                match state.database.audit_log_list(cursor.as_deref(), limit, &filter).await {
+                    Ok(response) => {
+                        _generate_response_message(&msg.id, &session.did, &state.config.mediator_did, &json!(response))
+                    }
+                    Err(err) => {
+                        warn!("Error reading the audit log. Reason: {}", err);
+                        generate_error_response(state, session, &msg.id, ProblemReport::new(
+                            ProblemReportSorter::Error,
+                            ProblemReportScope::Protocol,
+                            "database_error".into(),
+                            "Error reading the audit log {1}".into(),
+                            vec![err.to_string()], None
+                        ), false)
+                    }
+                }
+            }
             MediatorAdminRequest::Configuration(_) => {
                 // Return the current configuration
                 let config = json!({"version": env!("CARGO_PKG_VERSION"), "config": state.config});
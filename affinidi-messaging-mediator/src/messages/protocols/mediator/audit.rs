@@ -0,0 +1,59 @@
+//! Records administrative changes made through the mediator protocols in the audit log
+use std::time::SystemTime;
+
+use affinidi_messaging_sdk::protocols::mediator::{
+    acls::MediatorACLSet,
+    administration::{AuditAction, AuditLogEntry},
+};
+use tracing::{debug, warn};
+
+use crate::{SharedData, database::session::Session};
+
+/// Current ACLs of a DID, used to record the state before a change
+/// Returns None if the DID doesn't exist (or can't be read)
+pub(crate) async fn current_acls(state: &SharedData, did_hash: &str) -> Option<MediatorACLSet> {
+    state.database.get_did_acl(did_hash).await.ok().flatten()
+}
+
+/// Records a change that has been applied to `target`
+/// - `acls_before` - ACLs of the target before the change (see [current_acls])
+/// - `detail` - Additional information about the change
+///
+/// The ACLs after the change are read back from the database.
+/// NOTE: Failing to record the change is logged and doesn't fail the request, the change has already been applied
+pub(crate) async fn record(
+    state: &SharedData,
+    session: &Session,
+    action: AuditAction,
+    target: &str,
+    acls_before: Option<MediatorACLSet>,
+    detail: Option<String>,
+) {
+    let entry = AuditLogEntry {
+        id: String::new(),
+        timestamp: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        actor: session.did_hash.clone(),
+        action,
+        target: target.to_string(),
+        acls_before: acls_before.map(|acls| acls.to_hex_string()),
+        acls_after: current_acls(state, target)
+            .await
+            .map(|acls| acls.to_hex_string()),
+        detail,
+    };
+
+    match state
+        .database
+        .audit_log_add(&entry, state.config.limits.audit_log_entries)
+        .await
+    {
+        Ok(_) => debug!("Audit log: ({}) {} on ({})", entry.actor, action, target),
+        Err(err) => warn!(
+            "Couldn't record ({}) {} on ({}) in the audit log. Reason: {}",
+            entry.actor, action, target, err
+        ),
+    }
+}
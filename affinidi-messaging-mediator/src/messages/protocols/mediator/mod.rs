@@ -1,3 +1,5 @@
 pub(crate) mod accounts;
 pub(crate) mod acls;
 pub(crate) mod administration;
+pub(crate) mod audit;
+pub(crate) mod push_notifications;
@@ -0,0 +1,202 @@
+//! Registers where a DID is notified of queued messages while it has no live connection
+//! A DID can only manage its own push target
+use std::time::SystemTime;
+
+use crate::{
+    SharedData,
+    database::session::Session,
+    messages::{ProcessMessageResponse, error_response::generate_error_response},
+    tasks::push_notifications::validate_target,
+};
+use affinidi_messaging_didcomm::Message;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::{
+    messages::problem_report::{ProblemReport, ProblemReportScope, ProblemReportSorter},
+    protocols::mediator::push_notifications::MediatorPushRequest,
+};
+use serde_json::{Value, json};
+use tracing::{Instrument, info, span, warn};
+use uuid::Uuid;
+
+/// Responsible for processing a Mediator Push Notifications message
+pub(crate) async fn process(
+    msg: &Message,
+    state: &SharedData,
+    session: &Session,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let _span = span!(tracing::Level::DEBUG, "mediator_push_notifications");
+
+    async move {
+        if !state.config.push_notifications.enabled {
+            return generate_error_response(
+                state,
+                session,
+                &msg.id,
+                ProblemReport::new(
+                    ProblemReportSorter::Error,
+                    ProblemReportScope::Protocol,
+                    "push_notifications_disabled".into(),
+                    "Push notifications are not enabled on this mediator".into(),
+                    vec![],
+                    None,
+                ),
+                false,
+            );
+        }
+
+        // Only DIDs that can receive messages have anything to be notified about
+        if !session.acls.get_receive_messages().0 {
+            warn!(
+                "DID ({}) is not allowed to receive messages",
+                session.did_hash
+            );
+            return generate_error_response(
+                state,
+                session,
+                &msg.id,
+                ProblemReport::new(
+                    ProblemReportSorter::Error,
+                    ProblemReportScope::Protocol,
+                    "permission_error".into(),
+                    "DID isn't allowed to receive messages".into(),
+                    vec![],
+                    None,
+                ),
+                false,
+            );
+        }
+
+        // Parse the message body
+        let request: MediatorPushRequest = match serde_json::from_value(msg.body.clone()) {
+            Ok(request) => request,
+            Err(err) => {
+                warn!("Error parsing Mediator Push request. Reason: {}", err);
+                return generate_error_response(
+                    state,
+                    session,
+                    &msg.id,
+                    ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "invalid_request".into(),
+                        "Error parsing Mediator Push request. Reason: {1}".into(),
+                        vec![err.to_string()],
+                        None,
+                    ),
+                    false,
+                );
+            }
+        };
+
+        let result = match request {
+            MediatorPushRequest::PushRegister(target) => {
+                if let Err(reason) =
+                    validate_target(&state.config.push_notifications, &target).await
+                {
+                    warn!(
+                        "Invalid push target from DID ({}): {}",
+                        session.did_hash, reason
+                    );
+                    return generate_error_response(
+                        state,
+                        session,
+                        &msg.id,
+                        ProblemReport::new(
+                            ProblemReportSorter::Error,
+                            ProblemReportScope::Protocol,
+                            "invalid_request".into(),
+                            "Invalid push target. Reason: {1}".into(),
+                            vec![reason],
+                            None,
+                        ),
+                        false,
+                    );
+                }
+
+                state
+                    .database
+                    .push_target_set(&session.did_hash, &target)
+                    .await
+                    .map(|_| {
+                        info!("Push target registered for DID ({})", session.did_hash);
+                        json!(true)
+                    })
+            }
+            MediatorPushRequest::PushGet => state
+                .database
+                .push_target_get(&session.did_hash)
+                .await
+                .map(|target| json!(target.map(|target| target.redacted()))),
+            MediatorPushRequest::PushRemove => state
+                .database
+                .push_target_remove(&session.did_hash)
+                .await
+                .map(|removed| json!(removed)),
+        };
+
+        match result {
+            Ok(response) => _generate_response_message(
+                &msg.id,
+                &session.did,
+                &state.config.mediator_did,
+                &response,
+            ),
+            Err(err) => {
+                warn!("Error processing Mediator Push request. Reason: {}", err);
+                generate_error_response(
+                    state,
+                    session,
+                    &msg.id,
+                    ProblemReport::new(
+                        ProblemReportSorter::Error,
+                        ProblemReportScope::Protocol,
+                        "database_error".into(),
+                        "Error processing push notification request {1}".into(),
+                        vec![err.to_string()],
+                        None,
+                    ),
+                    false,
+                )
+            }
+        }
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Helper method that generates a response message
+/// - `thid` - The thread ID of the message
+/// - `to` - The recipient of the message
+/// - `from` - The sender of the message
+/// - `value` - The value to send in the message
+fn _generate_response_message(
+    thid: &str,
+    to: &str,
+    from: &str,
+    value: &Value,
+) -> Result<ProcessMessageResponse, MediatorError> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    // Build the message
+    let response = Message::build(
+        Uuid::new_v4().into(),
+        "https://didcomm.org/mediator/1.0/push-notifications".to_owned(),
+        value.to_owned(),
+    )
+    .thid(thid.to_owned())
+    .to(to.to_owned())
+    .from(from.to_owned())
+    .created_time(now)
+    .expires_time(now + 300)
+    .finalize();
+
+    Ok(ProcessMessageResponse {
+        store_message: true,
+        force_live_delivery: false,
+        data: crate::messages::WrapperType::Message(response),
+        forward_message: false,
+    })
+}
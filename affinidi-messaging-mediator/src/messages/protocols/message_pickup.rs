@@ -10,16 +10,14 @@ use affinidi_messaging_sdk::{
     messages::fetch::FetchOptions,
     protocols::message_pickup::{
         MessagePickupDeliveryRequest, MessagePickupLiveDelivery, MessagePickupMessagesReceived,
-        MessagePickupStatusReply, MessagePickupStatusRequest,
+        MessagePickupStatusRequest,
     },
 };
 use base64::prelude::*;
-use itertools::Itertools;
-use redis::{Value, from_redis_value};
 use serde_json::json;
 use sha256::digest;
 use std::time::SystemTime;
-use tracing::{Instrument, Level, debug, error, event, info, span, warn};
+use tracing::{Instrument, debug, error, info, span, warn};
 use uuid::Uuid;
 
 use crate::{
@@ -54,31 +52,31 @@ pub(crate) async fn status_request(
             serde_json::from_value::<MessagePickupStatusRequest>(msg.body.to_owned())
         {
             if let Some(recipient_did) = body.recipient_did {
-                if recipient_did != session.did {
+                if !session.owns_did(&recipient_did) {
                     debug!(
-                        "recipient_did: ({}) doesn't match session.did!",
+                        "recipient_did: ({}) isn't linked to the session!",
                         recipient_did
                     );
                     return Err(MediatorError::RequestDataError(
                         session.session_id.clone(),
                         format!(
-                            "recipient_did: ({}) doesn't match session.did!",
+                            "recipient_did: ({}) isn't linked to the session!",
                             recipient_did
                         ),
                     ));
                 } else {
-                    digest(recipient_did)
+                    recipient_did
                 }
             } else {
-                session.did_hash.clone()
+                session.did.clone()
             }
         } else {
-            session.did_hash.clone()
+            session.did.clone()
         };
         debug!("Body: recipient_did: {}", recipient_did);
 
         info!(
-            "MessagePickup Status-Request received from: ({}) recipient_did_hash({:?})",
+            "MessagePickup Status-Request received from: ({}) recipient_did({:?})",
             msg.from.clone().unwrap_or_else(|| "ANONYMOUS".to_string()),
             recipient_did
         );
@@ -90,6 +88,7 @@ pub(crate) async fn status_request(
 }
 
 /// Creates the reply to a valid StatusRequest message
+/// recipient_did: The session DID or a DID linked to the session
 /// force_live_delivery: If true, will force the message to be live streamed even if live streaming is disabled.
 ///           Required due to the protocol specification to send a status update on live_streaming changes
 /// override_live_delivery: If Some(bool), will override the live delivery status of the recipient
@@ -97,7 +96,7 @@ pub(crate) async fn status_request(
 async fn generate_status_reply(
     state: &SharedData,
     session: &Session,
-    did_hash: &str,
+    recipient_did: &str,
     thid: &str,
     force_live_delivery: bool,
     override_live_delivery: Option<bool>,
@@ -105,82 +104,11 @@ async fn generate_status_reply(
     let _span = span!(tracing::Level::DEBUG, "generate_status_reply",);
 
     async move {
-        let mut conn = state.database.0.get_async_connection().await?;
-
-        let response: Vec<Value> = deadpool_redis::redis::cmd("FCALL")
-            .arg("get_status_reply")
-            .arg(1)
-            .arg(did_hash)
-            .query_async(&mut conn)
-            .await
-            .map_err(|err| {
-                event!(
-                    Level::ERROR,
-                    "get_status_reply({}) failed. Reason: {}",
-                    did_hash,
-                    err
-                );
-                MediatorError::DatabaseError(
-                    session.session_id.clone(),
-                    format!("get_status_reply({}) failed. Reason: {}", did_hash, err),
-                )
-            })?;
-
-        let mut status = MessagePickupStatusReply {
-            recipient_did: session.did.clone(),
-            ..Default::default()
-        };
-
-        for (k, v) in response.into_iter().tuples() {
-            match from_redis_value::<String>(&k).unwrap_or("".into()).as_str() {
-                "newest_received" => {
-                    if let Ok(v) = from_redis_value::<String>(&v) {
-                        let a: Vec<&str> = v.split('-').collect();
-                        if a.len() != 2 {
-                            continue;
-                        }
-                        status.newest_received_time = if let Ok(t) = a[0].parse::<u64>() {
-                            Some(t / 1000)
-                        } else {
-                            None
-                        };
-                    }
-                }
-                "oldest_received" => {
-                    if let Ok(v) = from_redis_value::<String>(&v) {
-                        let a: Vec<&str> = v.split('-').collect();
-                        if a.len() != 2 {
-                            continue;
-                        }
-                        status.oldest_received_time = if let Ok(t) = a[0].parse::<u64>() {
-                            Some(t / 1000)
-                        } else {
-                            None
-                        };
-                    }
-                }
-                "message_count" => {
-                    if let Ok(v) = from_redis_value::<u32>(&v) {
-                        status.message_count = v;
-                    }
-                }
-                "queue_count" => continue,
-                "live_delivery" => {
-                    if let Ok(v) = from_redis_value::<bool>(&v) {
-                        status.live_delivery = v;
-                    }
-                }
-                "total_bytes" => {
-                    if let Ok(v) = from_redis_value::<u64>(&v) {
-                        status.total_bytes = v;
-                    }
-                }
-                "recipient_did" => continue,
-                _ => {
-                    warn!("Unknown key: ({:?}) with value: ({:?})", k, v);
-                }
-            }
-        }
+        let mut status = state
+            .database
+            .get_status_reply(&session.session_id, &digest(recipient_did))
+            .await?;
+        status.recipient_did = recipient_did.to_string();
 
         if let Some(live_delivery) = override_live_delivery {
             status.live_delivery = live_delivery;
@@ -261,45 +189,51 @@ pub(crate) async fn toggle_live_delivery(
         if live_delivery {
             // Enable Live delivery
             if let Some(stream_task) = &state.streaming_task {
-                stream_task
-                    .channel
-                    .send(StreamingUpdate {
-                        did_hash: session.did_hash.clone(),
-                        state: StreamingUpdateState::Start,
-                    })
-                    .await
-                    .map_err(|e| {
-                        error!("Error sending start message to streaming task: {:?}", e);
-                        MediatorError::InternalError(
-                            session.session_id.clone(),
-                            "Error sending start message to streaming task".into(),
-                        )
-                    })?;
+                // Applies to every DID linked to the session, on the websocket that asked for it
+                for did_hash in session.did_hashes() {
+                    stream_task
+                        .channel
+                        .send(StreamingUpdate {
+                            did_hash,
+                            state: StreamingUpdateState::Start(session.connection_id.clone()),
+                        })
+                        .await
+                        .map_err(|e| {
+                            error!("Error sending start message to streaming task: {:?}", e);
+                            MediatorError::InternalError(
+                                session.session_id.clone(),
+                                "Error sending start message to streaming task".into(),
+                            )
+                        })?;
+                }
             }
         } else {
             // Disable live delivery
             if let Some(stream_task) = &state.streaming_task {
-                stream_task
-                    .channel
-                    .send(StreamingUpdate {
-                        did_hash: session.did_hash.clone(),
-                        state: StreamingUpdateState::Stop,
-                    })
-                    .await
-                    .map_err(|e| {
-                        error!("Error sending stop message to streaming task: {:?}", e);
-                        MediatorError::InternalError(
-                            session.session_id.clone(),
-                            "Error sending stop message to streaming task".into(),
-                        )
-                    })?;
+                // Applies to every DID linked to the session, on the websocket that asked for it
+                for did_hash in session.did_hashes() {
+                    stream_task
+                        .channel
+                        .send(StreamingUpdate {
+                            did_hash,
+                            state: StreamingUpdateState::Stop(session.connection_id.clone()),
+                        })
+                        .await
+                        .map_err(|e| {
+                            error!("Error sending stop message to streaming task: {:?}", e);
+                            MediatorError::InternalError(
+                                session.session_id.clone(),
+                                "Error sending stop message to streaming task".into(),
+                            )
+                        })?;
+                }
             }
         }
 
         generate_status_reply(
             state,
             session,
-            &session.did_hash,
+            &session.did,
             &thid,
             true,
             Some(live_delivery),
@@ -396,7 +330,7 @@ pub(crate) async fn delivery_request(
                 forward_message: false,
             })
         } else {
-            generate_status_reply(state, session, &recipient_did_hash, &thid, false, None).await
+            generate_status_reply(state, session, &recipient_did, &thid, false, None).await
         }
     }
     .instrument(_span)
@@ -425,34 +359,39 @@ pub(crate) async fn messages_received(
 
         debug!("Messages Id list: {:?}", message_id_list);
 
+        let did_hashes = session.did_hashes();
         for msg_id in &message_id_list {
             debug!("getting message with id: {}", msg_id);
-            match state.database.get_message(&session.did_hash, msg_id).await {
-                Ok(msg) => {
-                    debug!("Got message: {:?}", msg);
-                    debug!("Deleting message: {}", msg_id);
-                    match state
-                        .database
-                        .0
-                        .delete_message(Some(&session.session_id), &session.did_hash, msg_id)
-                        .await
-                    {
-                        Ok(_) => {
-                            info!("Deleted message: {}", msg_id);
-                        }
-                        Err(err) => {
-                            info!("Error deleting message: {:?}", err);
-                        }
+            // The message may belong to any DID linked to the session
+            let mut found = false;
+            for did_hash in &did_hashes {
+                let Ok(msg) = state.database.get_message(did_hash, msg_id).await else {
+                    continue;
+                };
+                found = true;
+                debug!("Got message: {:?}", msg);
+                debug!("Deleting message: {}", msg_id);
+                match state
+                    .database
+                    .delete_message(Some(&session.session_id), did_hash, msg_id)
+                    .await
+                {
+                    Ok(_) => {
+                        info!("Deleted message: {}", msg_id);
+                    }
+                    Err(err) => {
+                        info!("Error deleting message: {:?}", err);
                     }
                 }
-                Err(err) => {
-                    warn!("Error getting message: {:?}", err);
-                }
+                break;
+            }
+            if !found {
+                warn!("Error getting message: {}", msg_id);
             }
         }
 
         Ok(
-            generate_status_reply(state, session, &session.did_hash, &thid, false, None)
+            generate_status_reply(state, session, &session.did, &thid, false, None)
                 .await
                 .unwrap(),
         )
@@ -494,12 +433,12 @@ fn _parse_and_validate_delivery_request_body(
             }
         };
 
-    if session.did != recipient_did {
+    if !session.owns_did(&recipient_did) {
         return Err(MediatorError::Unauthorized(
             session.session_id.clone(),
             format!(
-                "Session DID \"{}\" doesn't match recipient DID \"{}\"",
-                session.did, recipient_did
+                "Recipient DID \"{}\" isn't linked to session DID \"{}\"",
+                recipient_did, session.did
             ),
         ));
     }
@@ -1,6 +1,9 @@
 //! This module contains the protocol definitions for the mediator.
 //! Each protocol is defined in a separate sub-module.
 
+pub(crate) mod authenticate;
+pub(crate) mod coordinate_mediation;
+pub(crate) mod discover_features;
 pub(crate) mod mediator;
 pub mod message_pickup;
 pub mod ping;
@@ -2,16 +2,15 @@ use std::time::SystemTime;
 
 use crate::{
     SharedData,
-    database::session::Session,
+    database::{scheduled_delivery::ScheduledDelivery, session::Session},
     messages::{ProcessMessageResponse, WrapperType, store::store_forwarded_message},
 };
-use affinidi_messaging_didcomm::{AttachmentData, Message};
+use affinidi_messaging_didcomm::{Message, protocols::routing::resolve_did_comm_services_chain};
 use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
 use affinidi_messaging_sdk::protocols::mediator::{accounts::Account, acls::MediatorACLSet};
-use base64::prelude::*;
 use serde::Deserialize;
 use sha256::digest;
-use ssi::dids::{Document, document::service::Endpoint};
 use tracing::{Instrument, debug, span, warn};
 
 // Reads the body of an incoming forward message
@@ -20,7 +19,8 @@ struct ForwardRequest {
     next: Option<String>, // Defaults to true
 }
 
-/// Process a forward message, run checks and then if accepted place into FORWARD_TASKS stream
+/// Process a forward message, run checks and then if accepted either store locally or
+/// place into FORWARD_TASKS stream for remote delivery
 pub(crate) async fn process(
     msg: &Message,
     state: &SharedData,
@@ -53,13 +53,16 @@ pub(crate) async fn process(
 
         // ****************************************************
         // Get the next account if it exists
-        let next_account = match state.database.account_get(&next_did_hash).await {
-            Ok(Some(next_account)) => next_account,
-            Ok(None) => Account {
-                did_hash: next_did_hash.clone(),
-                acls: state.config.security.global_acl_default.to_u64(),
-                ..Default::default()
-            },
+        let (next_account, next_is_known) = match state.database.account_get(&next_did_hash).await {
+            Ok(Some(next_account)) => (next_account, true),
+            Ok(None) => (
+                Account {
+                    did_hash: next_did_hash.clone(),
+                    acls: state.config.security.global_acl_default.to_u64(),
+                    ..Default::default()
+                },
+                false,
+            ),
             Err(e) => {
                 return Err(MediatorError::DatabaseError(
                     session.session_id.clone(),
@@ -89,15 +92,7 @@ pub(crate) async fn process(
                 "Nothing to forward, attachments are not defined!".into(),
             ));
         };
-        let attachments_bytes = attachments
-            .iter()
-            .map(|a| a.byte_count.unwrap_or(0))
-            .sum::<u64>();
-        debug!(
-            "Attachments: count({}) bytes({})",
-            attachments.len(),
-            attachments_bytes
-        );
+        debug!("Attachments: count({})", attachments.len());
 
         // ****************************************************
         // Determine who the from did is
@@ -229,41 +224,76 @@ pub(crate) async fn process(
             ));
         }
 
-        // Forward is good, lets process the attachments and add to the queues
-        // First step is to determine if the next hop is local to the mediator or remote?
-        //if next_did_doc.service
+        // Forward is good, verify and decode every attachment before queuing any of them
+        let mut payloads: Vec<(String, Option<String>)> = Vec::with_capacity(attachments.len());
+        for attachment in &attachments {
+            let signed_by = attachment
+                .verify_jws(&state.did_resolver)
+                .await
+                .map_err(|err| {
+                    MediatorError::RequestDataError(
+                        session.session_id.clone(),
+                        format!("Attachment JWS couldn't be verified: {}", err),
+                    )
+                })?;
 
-        let attachment = attachments.first().unwrap();
-        let data = match attachment.data {
-            AttachmentData::Base64 { ref value } => {
-                String::from_utf8(BASE64_URL_SAFE_NO_PAD.decode(&value.base64).unwrap()).unwrap()
-            }
-            AttachmentData::Json { ref value } => {
-                if value.jws.is_some() {
-                    // TODO: Implement JWS verification
-                    return Err(MediatorError::NotImplemented(
+            let data = attachment
+                .content()
+                .map_err(|err| err.to_string())
+                .and_then(|data| String::from_utf8(data).map_err(|err| err.to_string()))
+                .map_err(|err| {
+                    MediatorError::RequestDataError(
                         session.session_id.clone(),
-                        "Attachment contains a JWS encrypted JSON payload.".into(),
-                    ));
-                } else {
-                    match serde_json::to_string(&value.json) {
-                        Ok(data) => data,
-                        Err(e) => {
-                            return Err(MediatorError::RequestDataError(
-                                session.session_id.clone(),
-                                format!("Attachment is not valid JSON: {}", e),
-                            ));
-                        }
-                    }
-                }
-            }
-            _ => {
-                return Err(MediatorError::RequestDataError(
-                    session.session_id.clone(),
-                    "Attachment is wrong format".into(),
-                ));
+                        format!("Attachment is wrong format: {}", err),
+                    )
+                })?;
+
+            if let Some(kid) = &signed_by {
+                debug!("Attachment JWS verified, signed by ({})", kid);
             }
-        };
+            payloads.push((data, signed_by));
+        }
+
+        // Byte quotas are enforced when each message is stored, checking the decoded size of all
+        // attachments up front means a forward is either accepted whole or not at all
+        let attachments_bytes = payloads
+            .iter()
+            .map(|(data, _)| data.len() as u64)
+            .sum::<u64>();
+        debug!("Attachments: bytes({})", attachments_bytes);
+
+        let send_bytes_limit = from_account
+            .queue_send_bytes_limit
+            .unwrap_or(state.config.limits.queued_send_bytes_soft);
+        if msg.from.is_some()
+            && send_bytes_limit != -1
+            && from_account.send_queue_bytes + attachments_bytes > send_bytes_limit as u64
+        {
+            warn!(
+                "Sender DID ({}) has too many bytes waiting to be delivered",
+                session.did_hash
+            );
+            return Err(MediatorError::ServiceLimitError(
+                session.session_id.clone(),
+                "Sender DID has too much data waiting to be delivered. Try again later".into(),
+            ));
+        }
+
+        let recv_bytes_limit = next_account
+            .queue_receive_bytes_limit
+            .unwrap_or(state.config.limits.queued_receive_bytes_soft);
+        if recv_bytes_limit != -1
+            && next_account.receive_queue_bytes + attachments_bytes > recv_bytes_limit as u64
+        {
+            warn!(
+                "Next DID ({}) has too many bytes waiting to be delivered",
+                next_did_hash
+            );
+            return Err(MediatorError::ServiceLimitError(
+                session.session_id.clone(),
+                "Next DID has too much data waiting to be delivered. Try again later".into(),
+            ));
+        }
 
         let expires_at = if let Some(expires_at) = msg.expires_time {
             let now = SystemTime::now()
@@ -287,7 +317,7 @@ pub(crate) async fn process(
         debug!(" *************************************** ");
         debug!(" TO: {}", next);
         debug!(" FROM: {:?}", msg.from);
-        debug!(" Forwarded message:\n{}", data);
+        debug!(" Attachments: {}", payloads.len());
         debug!(" Ephemeral: {:?}", msg.extra_headers.get("ephemeral"));
         debug!(" *************************************** ");
 
@@ -297,32 +327,102 @@ pub(crate) async fn process(
             false
         };
 
-        if ephemeral {
-            // Live stream the message?
-            if let Some(stream_uuid) = state
-                .database
-                .streaming_is_client_live(&next_did_hash, false)
-                .await
-            {
-                if state
+        // Determine if the next hop is local to the mediator or remote?
+        let remote = !_next_hop_is_local(session, state, &next, next_is_known).await?;
+
+        // Problem reports for remote delivery go back to whoever handed us the message
+        let from_did = if remote {
+            Some(msg.from.as_deref().unwrap_or(&session.did))
+        } else {
+            msg.from.as_deref()
+        };
+
+        let deliver_at = _deliver_at(
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+            delay_milli,
+        );
+        if let Some(deliver_at) = deliver_at {
+            if deliver_at / 1000 >= expires_at as u128 {
+                return Err(MediatorError::ServiceLimitError(
+                    session.session_id.clone(),
+                    "Forwarding delay is longer than the message expiry".into(),
+                ));
+            }
+        }
+
+        // Each attachment is delivered as its own message
+        let total = payloads.len();
+        for (delivered, (data, signed_by)) in payloads.into_iter().enumerate() {
+            debug!("Forwarded message:\n{}", data);
+
+            let result = if let Some(deliver_at) = deliver_at {
+                state
                     .database
-                    .streaming_publish_message(&next_did_hash, &stream_uuid, &data, false)
+                    .scheduled_delivery_add(
+                        &session.session_id,
+                        deliver_at,
+                        &ScheduledDelivery {
+                            message: data,
+                            to_did: next.clone(),
+                            to_did_hash: next_did_hash.clone(),
+                            from_did: from_did.map(|f| f.to_string()),
+                            msg_id: Some(msg.id.clone()),
+                            expires_at,
+                            ephemeral,
+                            remote,
+                            signed_by,
+                        },
+                    )
+                    .await
+                    .map(|_| ())
+            } else if remote {
+                let task = ForwardTask::new(&data, &next, from_did, Some(&msg.id), expires_at);
+                state
+                    .database
+                    .forward_queue_enqueue(&session.session_id, &task)
+                    .await
+                    .map(|_| ())
+            } else if ephemeral {
+                // Live stream the message?
+                for stream_uuid in state
+                    .database
+                    .streaming_is_client_live(&next_did_hash, false)
                     .await
-                    .is_ok()
                 {
-                    debug!("Live streaming message to UUID: {}", stream_uuid);
+                    if state
+                        .database
+                        .streaming_publish_message(&next_did_hash, &stream_uuid, &data, false)
+                        .await
+                        .is_ok()
+                    {
+                        debug!("Live streaming message to UUID: {}", stream_uuid);
+                    }
                 }
+                Ok(())
+            } else {
+                store_forwarded_message(
+                    state,
+                    session,
+                    &data,
+                    msg.from.as_deref(),
+                    &next,
+                    Some(expires_at),
+                    signed_by.as_deref(),
+                )
+                .await
+            };
+
+            if let Err(err) = result {
+                return Err(_partial_forward_error(
+                    &session.session_id,
+                    delivered,
+                    total,
+                    err,
+                ));
             }
-        } else {
-            store_forwarded_message(
-                state,
-                session,
-                &data,
-                msg.from.as_deref(),
-                &next,
-                Some(expires_at),
-            )
-            .await?;
         }
 
         Ok(ProcessMessageResponse {
@@ -336,34 +436,62 @@ pub(crate) async fn process(
     .await
 }
 
+/// When a delayed forward becomes visible to the next hop (epoch milliseconds)
+/// Returns None if there is no delay
+///
+/// Negative delays are "deliver no earlier than", positive delays are delivered when
+/// due. Either way the message is never made available before the delay has elapsed.
+fn _deliver_at(now_ms: u128, delay_milli: i64) -> Option<u128> {
+    if delay_milli == 0 {
+        None
+    } else {
+        Some(now_ms + delay_milli.unsigned_abs() as u128)
+    }
+}
+
+/// Error for an attachment that couldn't be delivered
+/// If earlier attachments of the same forward were already delivered they can't be taken back,
+/// so the sender is told how many went through rather than getting the bare error
+fn _partial_forward_error(
+    session_id: &str,
+    delivered: usize,
+    total: usize,
+    err: MediatorError,
+) -> MediatorError {
+    if delivered == 0 {
+        err
+    } else {
+        warn!(
+            "Forward partially delivered ({} of {} attachments): {}",
+            delivered, total, err
+        );
+        MediatorError::ForwardMessageError(
+            session_id.into(),
+            format!(
+                "Only {} of {} attachments were forwarded: {}",
+                delivered, total, err
+            ),
+        )
+    }
+}
+
 /// Determines if the next hop is local to the mediator or remote
 /// The next field of a routing message is a DID
 /// https://identity.foundation/didcomm-messaging/spec/#routing-protocol-20
 /// - next: DID (may include key ID) of the next hop
-/// - next_doc: Resolved DID Document of the next hop
+/// - next_is_known: true if the next DID has an account on this mediator
 ///
-/*
-{
-    "id": "did:example:123456789abcdefghi#didcomm-1",
-    "type": "DIDCommMessaging",
-    "serviceEndpoint": [{
-        "uri": "https://example.com/path",
-        "accept": [
-            "didcomm/v2",
-            "didcomm/aip2;env=rfc587"
-        ],
-        "routingKeys": ["did:example:somemediator#somekey"]
-    }]
-}
-*/
-fn _service_local(
+/// The next hop is local if it has an account on this mediator, has no DIDCommMessaging
+/// service, or any service in its chain points back to this mediator (blocked_forwarding)
+///
+/// When external forwarding is disabled, or the DID can't be resolved, the message is stored
+/// locally as it was before the forwarding processor existed
+async fn _next_hop_is_local(
     session: &Session,
     state: &SharedData,
     next: &str,
-    next_doc: &Document,
+    next_is_known: bool,
 ) -> Result<bool, MediatorError> {
-    let mut _error = None;
-
     // If the next hop is the mediator itself, then this is a recursive forward
     if next == state.config.mediator_did {
         warn!(
@@ -375,36 +503,77 @@ fn _service_local(
         ));
     }
 
-    let local = next_doc
-        .service
-        .iter()
-        .filter(|s| s.type_.contains(&"DIDCommMessaging".to_string()))
-        .any(|s| {
-            // Service Type is DIDCommMessaging
-            if let Some(service_endpoint) = &s.service_endpoint {
-                service_endpoint.into_iter().any(|endpoint| {
-                    match endpoint {
-                        Endpoint::Uri(uri) => {
-                            if uri.as_str().eq(&state.config.mediator_did) {
-                                warn!("next hop is the mediator itself, but this should have been unpacked. not accepting this message");
-                                _error = Some(MediatorError::ForwardMessageError(session.session_id.clone(), "next hop is the mediator, recursive forward found".into()));
-                                false
-                            } else {
-                                // Next hop is remote to the mediator
-                                false
-                            }
-                        }
-                        Endpoint::Map(_) => {true}
-                    }
-                })
-            } else {
-                false
-            }
-        });
+    if next_is_known || !state.config.processors.forwarding.external_forwarding {
+        return Ok(true);
+    }
 
-    if let Some(e) = _error {
-        Err(e)
-    } else {
-        Ok(local)
+    // Strip any key ID from the DID URL
+    let next_did = next.split('#').next().unwrap_or(next);
+    let services = match resolve_did_comm_services_chain(next_did, None, &state.did_resolver).await
+    {
+        Ok(services) => services,
+        Err(err) => {
+            warn!(
+                "Couldn't resolve DIDCommMessaging service for next hop ({}), storing locally. Reason: {}",
+                next, err
+            );
+            return Ok(true);
+        }
+    };
+
+    Ok(services.is_empty()
+        || services.iter().any(|(_, service)| {
+            state
+                .config
+                .processors
+                .forwarding
+                .blocked_forwarding
+                .contains(&service.uri)
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deliver_at() {
+        assert_eq!(_deliver_at(1_000, 0), None);
+        assert_eq!(_deliver_at(1_000, 500), Some(1_500));
+        // Negative delays are "no earlier than", never sooner or random
+        assert_eq!(_deliver_at(1_000, -500), Some(1_500));
+        assert_eq!(
+            _deliver_at(1_000, i64::MIN),
+            Some(1_000 + 9_223_372_036_854_775_808)
+        );
+    }
+
+    #[test]
+    fn test_partial_forward_error() {
+        // Nothing delivered yet, the original error is returned as is
+        assert!(matches!(
+            _partial_forward_error(
+                "test",
+                0,
+                2,
+                MediatorError::ServiceLimitError("test".into(), "full".into())
+            ),
+            MediatorError::ServiceLimitError(..)
+        ));
+
+        match _partial_forward_error(
+            "test",
+            1,
+            3,
+            MediatorError::DatabaseError("test".into(), "down".into()),
+        ) {
+            MediatorError::ForwardMessageError(_, msg) => {
+                assert_eq!(
+                    msg,
+                    "Only 1 of 3 attachments were forwarded: Database Error: down"
+                )
+            }
+            err => panic!("unexpected error: {}", err),
+        }
     }
 }
@@ -1,7 +1,8 @@
 use std::time::SystemTime;
 
-use crate::database::Database;
+use crate::database::MediatorStore;
 use crate::database::session::Session;
+use crate::database::store::QueueByteQuota;
 use crate::messages::MessageHandler;
 use crate::{SharedData, messages::PackOptions};
 use affinidi_messaging_didcomm::{PackEncryptedMetadata, UnpackMetadata};
@@ -20,23 +21,24 @@ async fn _store_message(
     to_did: &str,
     expiry: u64,
 ) -> Result<String, MediatorError> {
+    let to_hash = digest(to_did);
     // Live stream the message?
-    if let Some(stream_uuid) = state
+    let streams = state
         .database
-        .streaming_is_client_live(&session.did_hash, response.force_live_delivery)
-        .await
-    {
+        .streaming_is_client_live(&to_hash, response.force_live_delivery)
+        .await;
+    for stream_uuid in &streams {
         _live_stream(
-            &state.database,
-            &session.did_hash,
-            &stream_uuid,
+            state.database.as_ref(),
+            &to_hash,
+            stream_uuid,
             data,
             response.force_live_delivery,
         )
         .await;
     }
 
-    state
+    let result = state
         .database
         .store_message(
             &session.session_id,
@@ -44,8 +46,20 @@ async fn _store_message(
             to_did,
             Some(&state.config.mediator_did),
             expiry,
+            None,
+            // The mediator is the sender, only the recipient's quota applies
+            &QueueByteQuota {
+                send: -1,
+                ..QueueByteQuota::from(&state.config.limits)
+            },
         )
-        .await
+        .await;
+
+    if streams.is_empty() && result.is_ok() {
+        _push_notify(state, &to_hash);
+    }
+
+    result
 }
 
 /// Stores a message in the mediator's database
@@ -199,14 +213,18 @@ pub(crate) async fn store_message(
             }
             trace!("Ephemeral message packed (meta):\n{:#?}", meta);
             trace!("Ephemeral message (msg):\n{:#?}", packed);
+            // Websockets send the response straight back to the requesting connection
+            if session.connection_id.is_some() {
+                return Ok(InboundMessageResponse::Ephemeral(packed));
+            }
             // Live stream the message?
-            if let Some(stream_uuid) = state
+            for stream_uuid in state
                 .database
                 .streaming_is_client_live(&session.did_hash, response.force_live_delivery)
                 .await
             {
                 _live_stream(
-                    &state.database,
+                    state.database.as_ref(),
                     &session.did_hash,
                     &stream_uuid,
                     &packed,
@@ -227,10 +245,17 @@ pub(crate) async fn store_message(
     .await
 }
 
+/// Notifies the recipient of a queued message if it has no live stream (and push notifications are enabled)
+fn _push_notify(state: &SharedData, did_hash: &str) {
+    if let Some(push_notifications) = &state.push_notifications {
+        push_notifications.notify(did_hash);
+    }
+}
+
 /// If live streaming is enabled, this function will send the message to the live stream
 /// Ok to ignore errors here
 async fn _live_stream(
-    database: &Database,
+    database: &dyn MediatorStore,
     did_hash: &str,
     stream_uuid: &str,
     message: &str,
@@ -254,6 +279,7 @@ async fn _live_stream(
 /// - expires_at: Option<u64>
 ///   - None: use default expiry
 ///   - Some: use the provided expiry time in seconds
+/// - signed_by: Key ID that signed the forwarded attachment (if verified)
 pub(crate) async fn store_forwarded_message(
     state: &SharedData,
     session: &Session,
@@ -261,6 +287,7 @@ pub(crate) async fn store_forwarded_message(
     sender: Option<&str>,
     recipient: &str,
     expires_at: Option<u64>,
+    signed_by: Option<&str>,
 ) -> Result<(), MediatorError> {
     let _span = span!(
         tracing::Level::DEBUG,
@@ -271,12 +298,19 @@ pub(crate) async fn store_forwarded_message(
     async move {
         let did_hash = digest(recipient);
         // Live stream the message?
-        if let Some(stream_uuid) = state
+        let streams = state
             .database
             .streaming_is_client_live(&did_hash, false)
-            .await
-        {
-            _live_stream(&state.database, &did_hash, &stream_uuid, message, false).await;
+            .await;
+        for stream_uuid in &streams {
+            _live_stream(
+                state.database.as_ref(),
+                &did_hash,
+                stream_uuid,
+                message,
+                false,
+            )
+            .await;
             debug!("Live streaming message to did_hash: {}", did_hash);
         }
 
@@ -301,7 +335,15 @@ pub(crate) async fn store_forwarded_message(
 
         match state
             .database
-            .store_message(&session.session_id, message, recipient, sender, expires_at)
+            .store_message(
+                &session.session_id,
+                message,
+                recipient,
+                sender,
+                expires_at,
+                signed_by,
+                &QueueByteQuota::from(&state.config.limits),
+            )
             .await
         {
             Ok(msg_id) => {
@@ -309,6 +351,14 @@ pub(crate) async fn store_forwarded_message(
                     "message id({}) stored successfully recipient({})",
                     msg_id, recipient
                 );
+                if streams.is_empty() {
+                    _push_notify(state, &did_hash);
+                }
+            }
+            Err(e @ MediatorError::ServiceLimitError(..)) => {
+                // Queue quota exceeded, the sender needs to know
+                warn!("message rejected recipient({}): {}", recipient, e);
+                return Err(e);
             }
             Err(e) => {
                 warn!("error storing message recipient({}): {:?}", recipient, e);
@@ -320,3 +370,93 @@ pub(crate) async fn store_forwarded_message(
     .instrument(_span)
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::memory_store::MemoryStore, tasks::push_notifications::PushNotificationTask,
+    };
+    use std::sync::Arc;
+    use tokio_stream::StreamExt;
+
+    async fn _state() -> (SharedData, tokio::sync::mpsc::Receiver<String>) {
+        let mut state = SharedData::for_tests(Arc::new(MemoryStore::new())).await;
+        let (push_notifications, rx) = PushNotificationTask::channel();
+        state.push_notifications = Some(push_notifications);
+        (state, rx)
+    }
+
+    fn _session(did: &str) -> Session {
+        Session {
+            session_id: "test".into(),
+            did: did.into(),
+            did_hash: digest(did),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sender_live_recipient_offline() {
+        let (state, mut rx) = _state().await;
+        let alice_hash = digest("did:example:alice");
+        state
+            .database
+            .streaming_register_client(&alice_hash, "uuid")
+            .await
+            .unwrap();
+        state
+            .database
+            .streaming_start_live(&alice_hash, "uuid")
+            .await
+            .unwrap();
+
+        _store_message(
+            &state,
+            &_session("did:example:alice"),
+            &ProcessMessageResponse::default(),
+            "hello",
+            "did:example:bob",
+            u64::MAX >> 1,
+        )
+        .await
+        .unwrap();
+
+        // The offline recipient is notified, whoever sent it
+        assert_eq!(rx.try_recv().unwrap(), digest("did:example:bob"));
+    }
+
+    #[tokio::test]
+    async fn test_recipient_live_is_streamed() {
+        let (state, mut rx) = _state().await;
+        let bob_hash = digest("did:example:bob");
+        let mut stream = state.database.streaming_subscribe("uuid").await.unwrap();
+        state
+            .database
+            .streaming_register_client(&bob_hash, "uuid")
+            .await
+            .unwrap();
+        state
+            .database
+            .streaming_start_live(&bob_hash, "uuid")
+            .await
+            .unwrap();
+
+        _store_message(
+            &state,
+            &_session("did:example:alice"),
+            &ProcessMessageResponse::default(),
+            "hello",
+            "did:example:bob",
+            u64::MAX >> 1,
+        )
+        .await
+        .unwrap();
+
+        let record = stream.next().await.unwrap();
+        assert_eq!(record.did_hash, bob_hash);
+        assert_eq!(record.message, "hello");
+        // Live recipients don't need a push
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,21 +1,45 @@
 use crate::{
     SharedData,
-    common::config::init,
-    database::Database,
-    handlers::{application_routes, health_checker_handler},
-    tasks::{statistics::statistics, websocket_streaming::StreamingTask},
+    common::{
+        config::{Config, init},
+        rate_limit::rate_limit,
+        tls,
+    },
+    database::{self, MediatorStore, mediator_store::SchemaUpgradeMode, store::QueueByteQuota},
+    handlers::{application_routes, health_checker_handler, metrics::HandlerMetrics},
+    tasks::{
+        message_expiry_cleanup::message_expiry_cleanup, push_notifications::PushNotificationTask,
+        scheduled_delivery::scheduled_delivery, statistics::statistics,
+        websocket_streaming::StreamingTask,
+    },
 };
 use affinidi_did_resolver_cache_sdk::DIDCacheClient;
-use affinidi_messaging_mediator_common::database::DatabaseHandler;
-use affinidi_messaging_mediator_processors::message_expiry_cleanup::processor::MessageExpiryCleanupProcessor;
-use axum::{Router, routing::get};
-use axum_server::tls_rustls::RustlsConfig;
-use std::{env, net::SocketAddr};
+use affinidi_messaging_mediator_common::{
+    errors::MediatorError,
+    shutdown::{self, ShutdownTrigger},
+};
+#[cfg(feature = "redis")]
+use affinidi_messaging_mediator_processors::{
+    forwarding::processor::ForwardingProcessor,
+    message_expiry_cleanup::processor::MessageExpiryCleanupProcessor,
+};
+use axum::{Router, middleware, routing::get};
+use axum_server::{Handle, tls_rustls::RustlsConfig};
+use std::{env, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    select,
+    task::JoinHandle,
+    time::{Instant, timeout_at},
+};
+use tokio_util::task::TaskTracker;
 use tower_http::limit::RequestBodyLimitLayer;
 use tower_http::trace::{self, TraceLayer};
 use tracing::{Level, event};
 
-pub async fn start() {
+/// Loads the configuration, opens the database and starts the mediator
+/// - schema_upgrade: `Apply` starts the mediator, `DryRun` and `RollbackTo` only run the schema
+///   upgrade chain and exit
+pub async fn start(schema_upgrade: SchemaUpgradeMode) {
     let ansi = env::var("LOCAL").is_ok();
 
     if ansi {
@@ -54,7 +78,8 @@ pub async fn start() {
         .expect("Couldn't initialize mediator!");
 
     // Start setting up the database durability and handling
-    let database = match DatabaseHandler::new(&config.database).await {
+    // The storage backend is selected by the database_url scheme
+    let database = match database::open(&config.database).await {
         Ok(db) => db,
         Err(err) => {
             event!(Level::ERROR, "Error opening database: {}", err);
@@ -63,75 +88,251 @@ pub async fn start() {
         }
     };
 
-    // Convert from the common generic DatabaseHandler to the Mediator specific Database
-    let database = Database(database);
+    if schema_upgrade != SchemaUpgradeMode::Apply {
+        match database.upgrade_schema(&config, &schema_upgrade).await {
+            Ok(_) => {
+                event!(
+                    Level::INFO,
+                    "Schema upgrade ({:?}) complete",
+                    schema_upgrade
+                );
+                std::process::exit(0);
+            }
+            Err(err) => {
+                event!(
+                    Level::ERROR,
+                    "Schema upgrade ({:?}) failed: {}",
+                    schema_upgrade,
+                    err
+                );
+                std::process::exit(1);
+            }
+        }
+    }
 
-    database
-        .initialize(&config)
-        .await
-        .expect("Error initializing database");
+    let mut mediator = match start_with(config, database).await {
+        Ok(mediator) => mediator,
+        Err(err) => {
+            event!(Level::ERROR, "Error starting mediator: {}", err);
+            event!(Level::ERROR, "Exiting...");
+            std::process::exit(1);
+        }
+    };
 
-    if let Some(functions_file) = &config.database.functions_file {
-        event!(
-            Level::INFO,
-            "Loading LUA scripts into the database from file: {}",
-            functions_file
-        );
-        database.load_scripts(functions_file).await.unwrap();
-    } else {
-        event!(
-            Level::INFO,
-            "No LUA scripts file specified in the configuration. Skipping loading LUA scripts."
-        );
-        return;
+    // Stop accepting new connections and wind down on SIGTERM/SIGINT
+    let stopped = tokio::select! {
+        _ = shutdown::signal() => None,
+        result = mediator.stopped() => Some(result),
+    };
+    let result = match stopped {
+        Some(result) => result,
+        None => {
+            event!(
+                Level::INFO,
+                "Shutdown signal received, draining connections (timeout {}s)",
+                mediator.shutdown_timeout.as_secs()
+            );
+            mediator.shutdown();
+            mediator.stopped().await
+        }
+    };
+
+    if let Err(err) = result {
+        event!(Level::ERROR, "Mediator stopped with an error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// A running mediator, returned by [start_with]
+pub struct MediatorHandle {
+    /// Address the mediator is listening on
+    /// When `listen_address` uses port 0 this is the port assigned by the OS
+    pub address: SocketAddr,
+    shutdown_timeout: Duration,
+    shutdown_trigger: ShutdownTrigger,
+    server: JoinHandle<Result<(), MediatorError>>,
+}
+
+impl MediatorHandle {
+    /// Starts a graceful shutdown of the mediator, see [start_with]
+    pub fn shutdown(&self) {
+        self.shutdown_trigger.trigger();
+    }
+
+    /// Waits until the mediator has stopped, either after [MediatorHandle::shutdown] or because
+    /// the server failed. Returns once the store has been flushed.
+    /// NOTE: Only call this until it has returned once
+    pub async fn stopped(&mut self) -> Result<(), MediatorError> {
+        (&mut self.server).await.map_err(|err| {
+            MediatorError::InternalError(
+                "NA".into(),
+                format!("Mediator server task failed. Reason: {}", err),
+            )
+        })?
     }
+}
+
+/// Starts the mediator with an already opened storage backend
+/// Runs the full mediator (background tasks and API routes) in-process, so tests can start a
+/// throwaway mediator using a [crate::database::memory_store::MemoryStore].
+/// Use port 0 in `listen_address` to have the OS pick a free port, see [MediatorHandle::address]
+///
+/// No signal handlers are installed, call [MediatorHandle::shutdown] to stop the mediator.
+/// The mediator then stops accepting connections, closes websockets with a problem report,
+/// stops background tasks and processors and flushes the store, waiting up to
+/// `shutdown_timeout` seconds for in-flight work to finish
+pub async fn start_with(
+    mut config: Config,
+    database: Arc<dyn MediatorStore>,
+) -> Result<MediatorHandle, MediatorError> {
+    database.initialize(&config).await?;
+
+    let (shutdown_trigger, shutdown) = shutdown::channel();
+    let mut tasks: Vec<JoinHandle<()>> = Vec::new();
 
     // Start the statistics thread
     let _stats_database = database.clone(); // Clone the database handler for the statistics thread
-    tokio::spawn(async move {
-        statistics(_stats_database)
-            .await
-            .expect("Error starting statistics thread");
-    });
+    let _shutdown = shutdown.clone();
+    tasks.push(tokio::spawn(async move {
+        if let Err(err) = statistics(_stats_database, _shutdown).await {
+            event!(Level::ERROR, "Statistics thread failed: {}", err);
+        }
+    }));
+
+    // Start the push notification thread if enabled
+    let push_notifications = if config.push_notifications.enabled {
+        let (_task, _handle) = PushNotificationTask::new(
+            config.push_notifications.clone(),
+            database.clone(),
+            shutdown.clone(),
+        )?;
+        tasks.push(_handle);
+        Some(_task)
+    } else {
+        None
+    };
+
+    // Start the scheduled (delayed) delivery thread
+    let _scheduled_database = database.clone(); // Clone the database handler for the scheduled delivery thread
+    let _push_notifications = push_notifications.clone();
+    let _quota = QueueByteQuota::from(&config.limits);
+    let _shutdown = shutdown.clone();
+    tasks.push(tokio::spawn(async move {
+        if let Err(err) =
+            scheduled_delivery(_scheduled_database, _push_notifications, _quota, _shutdown).await
+        {
+            event!(Level::ERROR, "Scheduled delivery thread failed: {}", err);
+        }
+    }));
+
+    // Connection handler for the standalone processors, only the Redis backend provides one
+    #[cfg(feature = "redis")]
+    let redis_database = database.redis_handler();
+    #[cfg(not(feature = "redis"))]
+    let redis_database: Option<()> = None;
 
     // Start the message expiry cleanup thread if required
     if config.processors.message_expiry_cleanup.enabled {
-        let _database = database.0.clone(); // Clone the database handler for the message expiry cleanup thread
-        let _config = config.processors.message_expiry_cleanup.clone();
-        tokio::spawn(async move {
-            let _processor = MessageExpiryCleanupProcessor::new(_config, _database);
-            _processor
-                .start()
-                .await
-                .expect("Error starting message expiry cleanup processor");
-        });
+        let _shutdown = shutdown.clone();
+        if let Some(_database) = &redis_database {
+            #[cfg(feature = "redis")]
+            {
+                let _config = config.processors.message_expiry_cleanup.clone();
+                let _database = _database.clone();
+                tasks.push(tokio::spawn(async move {
+                    let _processor = MessageExpiryCleanupProcessor::new(_config, _database);
+                    if let Err(err) = _processor.start(_shutdown).await {
+                        event!(
+                            Level::ERROR,
+                            "Message expiry cleanup processor failed: {}",
+                            err
+                        );
+                    }
+                }));
+            }
+        } else {
+            let _database = database.clone(); // Clone the database handler for the message expiry cleanup thread
+            tasks.push(tokio::spawn(async move {
+                if let Err(err) = message_expiry_cleanup(_database, _shutdown).await {
+                    event!(
+                        Level::ERROR,
+                        "Message expiry cleanup thread failed: {}",
+                        err
+                    );
+                }
+            }));
+        }
     }
 
     // Start the streaming thread if enabled
-    let (streaming_task, _) = if config.streaming_enabled {
+    let streaming_task = if config.streaming_enabled {
         let _database = database.clone(); // Clone the database handler for the subscriber thread
         let uuid = config.streaming_uuid.clone();
-        let (_task, _handle) = StreamingTask::new(_database.clone(), &uuid)
-            .await
-            .expect("Error starting streaming task");
-        (Some(_task), Some(_handle))
+        let (_task, _handle) = StreamingTask::new(
+            _database.clone(),
+            &uuid,
+            config.limits.ws_connections_per_did,
+            shutdown.clone(),
+        )
+        .await?;
+        tasks.push(_handle);
+        Some(_task)
     } else {
-        (None, None)
+        None
     };
 
     // Create the DID Resolver
     let did_resolver = DIDCacheClient::new(config.did_resolver_config.clone())
         .await
-        .unwrap();
+        .map_err(|err| {
+            MediatorError::ConfigError(
+                "NA".into(),
+                format!("Couldn't create DID resolver. Reason: {}", err),
+            )
+        })?;
+
+    // Start the forwarding thread if required
+    if config.processors.forwarding.enabled {
+        if let Some(_database) = redis_database {
+            #[cfg(feature = "redis")]
+            {
+                let _config = config.processors.forwarding.clone();
+                let _processor = ForwardingProcessor::new(
+                    _config,
+                    _database,
+                    did_resolver.clone(),
+                    &config.mediator_did,
+                    config.security.mediator_secrets.clone(),
+                )?;
+                let _shutdown = shutdown.clone();
+                tasks.push(tokio::spawn(async move {
+                    if let Err(err) = _processor.start(_shutdown).await {
+                        event!(Level::ERROR, "Forwarding processor failed: {}", err);
+                    }
+                }));
+            }
+        } else {
+            event!(
+                Level::WARN,
+                "The forwarding processor requires the Redis backend. External forwarding is disabled"
+            );
+            config.processors.forwarding.external_forwarding = false;
+        }
+    }
 
     // Create the shared application State
     let shared_state = SharedData {
         config: config.clone(),
         service_start_timestamp: chrono::Utc::now(),
         did_resolver,
-        database,
+        database: database.clone(),
         streaming_task,
+        push_notifications,
+        metrics: Arc::new(HandlerMetrics::default()),
+        shutdown: shutdown.clone(),
+        websockets: TaskTracker::new(),
     };
+    let websockets = shared_state.websockets.clone();
 
     // build our application routes
     let app: Router = application_routes(&config.api_prefix, &shared_state);
@@ -139,7 +340,12 @@ pub async fn start() {
     // Add middleware to all routes
     let app = Router::new()
         .merge(app)
-        .layer(config.security.cors_allow_origin)
+        // Rate limits are per DID or client IP, see [limits] in the configuration
+        .layer(middleware::from_fn_with_state(
+            shared_state.clone(),
+            rate_limit,
+        ))
+        .layer(config.security.cors_allow_origin.clone())
         .layer(
             TraceLayer::new_for_http()
                 .make_span_with(trace::DefaultMakeSpan::new().level(Level::INFO))
@@ -152,30 +358,175 @@ pub async fn start() {
             get(health_checker_handler).with_state(shared_state),
         );
 
-    if config.security.use_ssl {
+    let listener = std::net::TcpListener::bind(&config.listen_address).map_err(|err| {
+        MediatorError::ConfigError(
+            "NA".into(),
+            format!(
+                "Couldn't listen on ({}). Reason: {}",
+                config.listen_address, err
+            ),
+        )
+    })?;
+    let address = listener.local_addr().map_err(|err| {
+        MediatorError::ConfigError(
+            "NA".into(),
+            format!("Couldn't get listen address. Reason: {}", err),
+        )
+    })?;
+
+    let ssl_config = if config.security.use_ssl {
         event!(
             Level::INFO,
             "This mediator is using SSL/TLS for secure communication."
         );
-        // configure certificate and private key used by https
-        // TODO: Build a proper TLS Config
+        // configure certificate, private key and TLS settings used by https
         let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
-        let ssl_config = RustlsConfig::from_pem_file(
-            config.security.ssl_certificate_file,
-            config.security.ssl_key_file,
-        )
-        .await
-        .expect("bad certificate/key");
+        let ssl_config = RustlsConfig::from_config(Arc::new(tls::server_config(&config.security)?));
 
-        axum_server::bind_rustls(config.listen_address.parse().unwrap(), ssl_config)
-            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
-            .await
-            .unwrap();
+        // Pick up rotated certificates without a restart
+        tokio::spawn(tls::reload_on_change(
+            ssl_config.clone(),
+            config.security.clone(),
+        ));
+        Some(ssl_config)
     } else {
         event!(Level::WARN, "**** WARNING: Running without SSL/TLS ****");
-        axum_server::bind(config.listen_address.parse().unwrap())
-            .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        None
+    };
+
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout);
+    let handle = Handle::new();
+
+    event!(Level::INFO, "Mediator listening on ({})", address);
+    let _shutdown_trigger = shutdown_trigger.clone();
+    let mut _shutdown = shutdown.clone();
+    let server = tokio::spawn(async move {
+        let app = app.into_make_service_with_connect_info::<SocketAddr>();
+        let serve = async {
+            match ssl_config {
+                Some(ssl_config) => {
+                    axum_server::from_tcp_rustls(listener, ssl_config)
+                        .handle(handle.clone())
+                        .serve(app)
+                        .await
+                }
+                None => {
+                    axum_server::from_tcp(listener)
+                        .handle(handle.clone())
+                        .serve(app)
+                        .await
+                }
+            }
+        };
+        tokio::pin!(serve);
+
+        // Serve until shutdown is triggered (or the server stops on its own)
+        let served = select! {
+            served = &mut serve => Some(served),
+            _ = _shutdown.wait() => None,
+        };
+
+        // Connections, websockets and background tasks all share the one deadline
+        let deadline = Instant::now() + shutdown_timeout;
+
+        // Stop the background tasks too if the server stopped on its own
+        _shutdown_trigger.trigger();
+
+        // Stop accepting new connections and let in-flight requests finish
+        let served = match served {
+            Some(served) => served,
+            None => {
+                handle.graceful_shutdown(Some(shutdown_timeout));
+                serve.await
+            }
+        };
+        if let Err(err) = &served {
+            event!(Level::ERROR, "Mediator server failed: {}", err);
+        }
+
+        if !drain(deadline, websockets, tasks).await {
+            event!(
+                Level::WARN,
+                "Websockets and background tasks didn't stop within {}s, exiting anyway",
+                shutdown_timeout.as_secs()
+            );
+        }
+
+        if let Err(err) = database.flush().await {
+            event!(Level::ERROR, "Error flushing database on shutdown: {}", err);
+        }
+
+        event!(Level::INFO, "Mediator shutdown complete");
+        served.map_err(|err| {
+            MediatorError::InternalError("NA".into(), format!("Mediator server failed: {}", err))
+        })
+    });
+
+    Ok(MediatorHandle {
+        address,
+        shutdown_timeout,
+        shutdown_trigger,
+        server,
+    })
+}
+
+/// Waits for the websocket connections and background tasks to finish, giving up at `deadline`
+/// - Upgraded websockets are no longer tracked by the server, so they are waited on here
+/// - Returns false if they didn't all finish in time
+async fn drain(deadline: Instant, websockets: TaskTracker, tasks: Vec<JoinHandle<()>>) -> bool {
+    websockets.close();
+    let drain = async {
+        websockets.wait().await;
+        for task in tasks {
+            let _ = task.await;
+        }
+    };
+    timeout_at(deadline, drain).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn test_drain_waits_for_websockets_and_tasks() {
+        let websockets = TaskTracker::new();
+        let closed = Arc::new(AtomicBool::new(false));
+        let _closed = closed.clone();
+        websockets.spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            _closed.store(true, Ordering::SeqCst);
+        });
+        let finished = Arc::new(AtomicBool::new(false));
+        let _finished = finished.clone();
+        let tasks = vec![tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            _finished.store(true, Ordering::SeqCst);
+        })];
+
+        assert!(
+            drain(
+                Instant::now() + Duration::from_secs(5),
+                websockets.clone(),
+                tasks
+            )
             .await
-            .unwrap();
+        );
+        assert!(closed.load(Ordering::SeqCst));
+        assert!(finished.load(Ordering::SeqCst));
+        assert!(websockets.is_closed());
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_at_deadline() {
+        let websockets = TaskTracker::new();
+        websockets.spawn(std::future::pending::<()>());
+        let tasks = vec![tokio::spawn(std::future::pending::<()>())];
+
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(100);
+        assert!(!drain(deadline, websockets, tasks).await);
+        assert!(started.elapsed() < Duration::from_secs(1));
     }
 }
@@ -0,0 +1,45 @@
+use crate::database::MediatorStore;
+use affinidi_messaging_mediator_common::{errors::MediatorError, shutdown::Shutdown};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tracing::{Instrument, Level, debug, info, span, warn};
+
+/// Removes expired messages for storage backends that don't run the standalone
+/// MessageExpiryCleanupProcessor (i.e. anything other than Redis).
+/// Is spawned as a task from main(), runs until shutdown is triggered.
+pub async fn message_expiry_cleanup(
+    database: Arc<dyn MediatorStore>,
+    mut shutdown: Shutdown,
+) -> Result<(), MediatorError> {
+    let _span = span!(Level::INFO, "message_expiry_cleanup");
+
+    async move {
+        debug!("Starting message expiry cleanup thread...");
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            match database.expire_messages(now).await {
+                Ok(0) => {}
+                Ok(expired) => info!("expired {} messages", expired),
+                Err(err) => warn!("Error expiring messages: {}", err),
+            }
+        }
+
+        debug!("Message expiry cleanup thread stopped");
+        Ok(())
+    }
+    .instrument(_span)
+    .await
+}
@@ -1,3 +1,6 @@
 /// Any parallel task (thread) that needs to be spawned should be defined here.
+pub mod message_expiry_cleanup;
+pub mod push_notifications;
+pub mod scheduled_delivery;
 pub mod statistics;
 pub mod websocket_streaming;
@@ -0,0 +1,157 @@
+//! Apple Push Notification service push target (feature `push-apns`)
+//!
+//! Notifications are sent as background pushes (`content-available`) so the app can fetch its
+//! messages, authenticated with a token signed by the APNs key (ES256). Tokens are reused for
+//! up to 50 minutes as APNs rejects tokens older than an hour.
+
+use crate::common::config::PushNotificationsConfig;
+use affinidi_messaging_mediator_common::errors::MediatorError;
+use affinidi_messaging_sdk::protocols::mediator::push_notifications::PushNotification;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::{sync::Mutex, time::SystemTime};
+
+const APNS_API: &str = "https://api.push.apple.com/3/device";
+const APNS_SANDBOX_API: &str = "https://api.sandbox.push.apple.com/3/device";
+
+/// Seconds a provider token is reused for
+const TOKEN_LIFETIME: u64 = 3_000;
+
+#[derive(Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    iat: u64,
+}
+
+pub(crate) struct ApnsClient {
+    api: &'static str,
+    team_id: String,
+    key_id: String,
+    key: EncodingKey,
+    topic: String,
+    /// Current provider token and when it was issued
+    token: Mutex<Option<(String, u64)>>,
+}
+
+impl ApnsClient {
+    /// Returns None if APNs isn't configured
+    pub(crate) fn new(config: &PushNotificationsConfig) -> Result<Option<Self>, MediatorError> {
+        let (Some(team_id), Some(key_id), Some(key), Some(topic)) = (
+            &config.apns_team_id,
+            &config.apns_key_id,
+            &config.apns_key,
+            &config.apns_topic,
+        ) else {
+            return Ok(None);
+        };
+
+        let key = EncodingKey::from_ec_pem(key.as_bytes()).map_err(|err| {
+            MediatorError::ConfigError(
+                "NA".into(),
+                format!("Couldn't load APNs key. Reason: {}", err),
+            )
+        })?;
+
+        Ok(Some(ApnsClient {
+            api: if config.apns_sandbox {
+                APNS_SANDBOX_API
+            } else {
+                APNS_API
+            },
+            team_id: team_id.clone(),
+            key_id: key_id.clone(),
+            key,
+            topic: topic.clone(),
+            token: Mutex::new(None),
+        }))
+    }
+
+    /// Provider token, a new one is signed when the current one is too old
+    fn _provider_token(&self) -> Result<String, String> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut token = self
+            .token
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((token, issued)) = &*token {
+            if now < issued + TOKEN_LIFETIME {
+                return Ok(token.clone());
+            }
+        }
+
+        let mut header = Header::new(Algorithm::ES256);
+        header.kid = Some(self.key_id.clone());
+        let signed = jsonwebtoken::encode(
+            &header,
+            &Claims {
+                iss: &self.team_id,
+                iat: now,
+            },
+            &self.key,
+        )
+        .map_err(|err| format!("Couldn't sign APNs token. Reason: {}", err))?;
+
+        *token = Some((signed.clone(), now));
+        Ok(signed)
+    }
+
+    /// Sends a notification to an APNs device token
+    pub(crate) async fn send(
+        &self,
+        client: &reqwest::Client,
+        device_token: &str,
+        notification: &PushNotification,
+    ) -> Result<(), String> {
+        let response = client
+            .post(format!("{}/{}", self.api, device_token))
+            .bearer_auth(self._provider_token()?)
+            .header("apns-topic", &self.topic)
+            .header("apns-push-type", "background")
+            .header("apns-priority", "5")
+            .json(&_payload(notification))
+            .send()
+            .await
+            .map_err(|err| format!("APNs request failed: {}", err))?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "APNs responded with status ({})",
+                response.status()
+            ))
+        }
+    }
+}
+
+/// APNs payload for a notification
+fn _payload(notification: &PushNotification) -> Value {
+    json!({
+        "aps": { "content-available": 1 },
+        "did_hash": notification.did_hash,
+        "count": notification.count,
+        "timestamp": notification.timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let payload = _payload(&PushNotification {
+            did_hash: "did_hash".into(),
+            count: 3,
+            timestamp: 1,
+        });
+        assert_eq!(payload["aps"]["content-available"], 1);
+        assert!(payload["aps"].get("alert").is_none());
+        assert_eq!(payload["count"], 3);
+    }
+}
@@ -0,0 +1,71 @@
+//! Firebase Cloud Messaging push target (feature `push-fcm`)
+//!
+//! Notifications are sent as FCM data messages through the HTTP v1 API, so the app decides how
+//! to present them. FCM data values must be strings.
+
+use crate::common::config::PushNotificationsConfig;
+use affinidi_messaging_sdk::protocols::mediator::push_notifications::PushNotification;
+use serde_json::{Value, json};
+
+const FCM_API: &str = "https://fcm.googleapis.com/v1/projects";
+
+/// FCM message for a notification
+fn _message(token: &str, notification: &PushNotification) -> Value {
+    json!({
+        "message": {
+            "token": token,
+            "data": {
+                "did_hash": notification.did_hash,
+                "count": notification.count.to_string(),
+                "timestamp": notification.timestamp.to_string(),
+            },
+        }
+    })
+}
+
+/// Sends a notification to an FCM registration token
+pub(crate) async fn send(
+    client: &reqwest::Client,
+    config: &PushNotificationsConfig,
+    token: &str,
+    notification: &PushNotification,
+) -> Result<(), String> {
+    let (Some(project_id), Some(access_token)) = (&config.fcm_project_id, &config.fcm_access_token)
+    else {
+        return Err("FCM is not configured".into());
+    };
+
+    let response = client
+        .post(format!("{}/{}/messages:send", FCM_API, project_id))
+        .bearer_auth(access_token)
+        .json(&_message(token, notification))
+        .send()
+        .await
+        .map_err(|err| format!("FCM request failed: {}", err))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("FCM responded with status ({})", response.status()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message() {
+        let message = _message(
+            "token",
+            &PushNotification {
+                did_hash: "did_hash".into(),
+                count: 3,
+                timestamp: 1,
+            },
+        );
+        assert_eq!(message["message"]["token"], "token");
+        assert_eq!(message["message"]["data"]["count"], "3");
+        assert!(message["message"].get("notification").is_none());
+    }
+}
@@ -0,0 +1,451 @@
+/*!
+ Push notifications for DIDs that have no live connection.
+
+ When a message is queued for a DID that isn't live streaming, [PushNotificationTask::notify] is
+ called. The task counts these per DID for `batch_window` milliseconds and then sends a single
+ notification per DID to the [PushTarget] it registered (see the `push-notifications` protocol).
+
+ Notifications only contain the DID hash and how many messages were queued, never message content.
+ Delivery is best effort, failures are logged and the messages remain queued for pickup.
+
+ Targets:
+ - Webhook: always available, see [webhook]
+ - FCM: requires the `push-fcm` feature
+ - APNs: requires the `push-apns` feature
+*/
+use crate::{common::config::PushNotificationsConfig, database::MediatorStore};
+use affinidi_messaging_mediator_common::{errors::MediatorError, shutdown::Shutdown};
+use affinidi_messaging_sdk::protocols::mediator::push_notifications::{
+    PushNotification, PushTarget,
+};
+use ahash::AHashMap as HashMap;
+use futures_util::future::join_all;
+use rustls::ClientConfig;
+use rustls_platform_verifier::ConfigVerifierExt;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    select,
+    sync::mpsc,
+    task::JoinHandle,
+    time::{Instant, interval_at},
+};
+use tracing::{Instrument, Level, debug, info, span, warn};
+
+#[cfg(feature = "push-apns")]
+pub(crate) mod apns;
+#[cfg(feature = "push-fcm")]
+pub(crate) mod fcm;
+pub(crate) mod webhook;
+
+/// Number of notification requests that can be queued before new ones are dropped
+const CHANNEL_SIZE: usize = 10_000;
+
+/// Handle to the push notification task, cheap to clone
+#[derive(Clone)]
+pub struct PushNotificationTask {
+    channel: mpsc::Sender<String>,
+}
+
+impl PushNotificationTask {
+    /// Starts the push notification task
+    pub fn new(
+        config: PushNotificationsConfig,
+        database: Arc<dyn MediatorStore>,
+        shutdown: Shutdown,
+    ) -> Result<(Self, JoinHandle<()>), MediatorError> {
+        let sender = Arc::new(PushSender::new(config)?);
+        let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+
+        let handle = tokio::spawn(async move {
+            _push_notification_task(sender, database, rx, shutdown).await;
+        });
+
+        Ok((PushNotificationTask { channel: tx }, handle))
+    }
+
+    /// Handle whose notifications are received on the returned channel instead of being sent
+    #[cfg(test)]
+    pub(crate) fn channel() -> (Self, mpsc::Receiver<String>) {
+        let (tx, rx) = mpsc::channel(CHANNEL_SIZE);
+        (PushNotificationTask { channel: tx }, rx)
+    }
+
+    /// Notifies a DID that a message has been queued for it
+    /// Never blocks, the notification is dropped if the task can't keep up
+    pub fn notify(&self, did_hash: &str) {
+        if let Err(err) = self.channel.try_send(did_hash.to_string()) {
+            debug!("Push notification for ({}) dropped: {}", did_hash, err);
+        }
+    }
+}
+
+/// Checks a target can be notified by this mediator before it is registered
+/// Returns the reason if it can't
+pub(crate) async fn validate_target(
+    config: &PushNotificationsConfig,
+    target: &PushTarget,
+) -> Result<(), String> {
+    match target {
+        PushTarget::Webhook { url, secret } => {
+            webhook::validate_url(config, url).await?;
+            if secret.is_empty() {
+                return Err("webhook secret must not be empty".into());
+            }
+            Ok(())
+        }
+        PushTarget::Fcm { token } => {
+            if !cfg!(feature = "push-fcm")
+                || config.fcm_project_id.is_none()
+                || config.fcm_access_token.is_none()
+            {
+                return Err("FCM notifications are not enabled on this mediator".into());
+            }
+            if token.is_empty() {
+                return Err("FCM token must not be empty".into());
+            }
+            Ok(())
+        }
+        PushTarget::Apns { token } => {
+            if !cfg!(feature = "push-apns")
+                || config.apns_team_id.is_none()
+                || config.apns_key_id.is_none()
+                || config.apns_key.is_none()
+                || config.apns_topic.is_none()
+            {
+                return Err("APNs notifications are not enabled on this mediator".into());
+            }
+            if token.is_empty() || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err("APNs token must be a hex encoded device token".into());
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Collects notifications per DID and sends them once per `batch_window`
+async fn _push_notification_task(
+    sender: Arc<PushSender>,
+    database: Arc<dyn MediatorStore>,
+    mut rx: mpsc::Receiver<String>,
+    mut shutdown: Shutdown,
+) {
+    let _span = span!(Level::INFO, "push_notifications");
+
+    async move {
+        info!(
+            "Push notification task started. batch_window({}ms)",
+            sender.config.batch_window
+        );
+
+        let mut pending: HashMap<String, u32> = HashMap::new();
+        let batch_window = Duration::from_millis(sender.config.batch_window.max(1));
+        let mut ticker = interval_at(Instant::now() + batch_window, batch_window);
+
+        loop {
+            select! {
+                did_hash = rx.recv() => {
+                    let Some(did_hash) = did_hash else {
+                        break;
+                    };
+                    *pending.entry(did_hash).or_default() += 1;
+                }
+                _ = ticker.tick() => {
+                    if !pending.is_empty() {
+                        let batch = std::mem::take(&mut pending);
+                        let sender = sender.clone();
+                        let database = database.clone();
+                        tokio::spawn(async move {
+                            sender.send_batch(database.as_ref(), batch).await;
+                        });
+                    }
+                }
+                _ = shutdown.wait() => break,
+            }
+        }
+
+        // Messages stay queued, they'll be picked up on the next connection
+        if !pending.is_empty() {
+            debug!(
+                "Push notification task stopping, {} DIDs not notified",
+                pending.len()
+            );
+        }
+        info!("Push notification task stopped");
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Sends notifications to the registered targets
+pub(crate) struct PushSender {
+    config: PushNotificationsConfig,
+    http_client: reqwest::Client,
+    #[cfg(feature = "push-apns")]
+    apns: Option<apns::ApnsClient>,
+}
+
+impl PushSender {
+    pub(crate) fn new(config: PushNotificationsConfig) -> Result<Self, MediatorError> {
+        let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+        // APNs only accepts HTTP/2
+        let mut tls = ClientConfig::with_platform_verifier();
+        tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        let http_client = reqwest::ClientBuilder::new()
+            .use_rustls_tls()
+            .use_preconfigured_tls(tls)
+            .timeout(Duration::from_secs(config.http_timeout))
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(webhook::PublicResolver::new(
+                &config.webhook_allowed_hosts,
+            )))
+            .user_agent(format!(
+                "Affinidi Messaging Mediator {}",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .map_err(|err| {
+                MediatorError::ConfigError(
+                    "NA".into(),
+                    format!(
+                        "Couldn't create push notification HTTP client. Reason: {}",
+                        err
+                    ),
+                )
+            })?;
+
+        Ok(PushSender {
+            #[cfg(feature = "push-apns")]
+            apns: apns::ApnsClient::new(&config)?,
+            config,
+            http_client,
+        })
+    }
+
+    /// Sends one notification per DID in the batch (did_hash -> number of messages queued)
+    async fn send_batch(&self, database: &dyn MediatorStore, batch: HashMap<String, u32>) {
+        join_all(
+            batch
+                .into_iter()
+                .map(|(did_hash, count)| self.notify(database, did_hash, count)),
+        )
+        .await;
+    }
+
+    async fn notify(&self, database: &dyn MediatorStore, did_hash: String, count: u32) {
+        let target = match database.push_target_get(&did_hash).await {
+            Ok(Some(target)) => target,
+            Ok(None) => return,
+            Err(err) => {
+                warn!("Couldn't get push target for ({}): {}", did_hash, err);
+                return;
+            }
+        };
+
+        let notification = PushNotification {
+            did_hash,
+            count,
+            timestamp: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+
+        match self.send(&target, &notification).await {
+            Ok(_) => debug!(
+                "Push notification sent to ({}) count({})",
+                notification.did_hash, count
+            ),
+            Err(err) => warn!(
+                "Push notification to ({}) failed: {}",
+                notification.did_hash, err
+            ),
+        }
+    }
+
+    /// Sends a notification to a target
+    pub(crate) async fn send(
+        &self,
+        target: &PushTarget,
+        notification: &PushNotification,
+    ) -> Result<(), String> {
+        match target {
+            PushTarget::Webhook { url, secret } => {
+                // The allow-list or DNS may have changed since the webhook was registered
+                webhook::validate_url(&self.config, url).await?;
+                webhook::send(&self.http_client, url, secret, notification).await
+            }
+            #[cfg(feature = "push-fcm")]
+            PushTarget::Fcm { token } => {
+                fcm::send(&self.http_client, &self.config, token, notification).await
+            }
+            #[cfg(feature = "push-apns")]
+            PushTarget::Apns { token } => match &self.apns {
+                Some(apns) => apns.send(&self.http_client, token, notification).await,
+                None => Err("APNs is not configured".into()),
+            },
+            #[allow(unreachable_patterns)]
+            _ => Err("push target type is not enabled on this mediator".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_store::MemoryStore;
+    use affinidi_messaging_mediator_common::shutdown;
+
+    /// The stand in webhook listens on 127.0.0.1, so it has to be allow-listed
+    fn _config() -> PushNotificationsConfig {
+        PushNotificationsConfig {
+            enabled: true,
+            batch_window: 50,
+            webhook_allow_http: true,
+            webhook_allowed_hosts: vec!["127.0.0.1".into()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_target() {
+        let config = PushNotificationsConfig {
+            webhook_allowed_hosts: vec![],
+            .._config()
+        };
+        let webhook = |url: &str, secret: &str| PushTarget::Webhook {
+            url: url.into(),
+            secret: secret.into(),
+        };
+
+        assert!(
+            validate_target(&config, &webhook("https://8.8.8.8/notify", "secret"))
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_target(&config, &webhook("https://8.8.8.8/notify", ""))
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_target(&config, &webhook("ftp://8.8.8.8", "secret"))
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_target(&config, &webhook("not a url", "secret"))
+                .await
+                .is_err()
+        );
+
+        // Private, loopback, link-local and metadata addresses
+        for url in [
+            "http://localhost:1234",
+            "http://127.0.0.1:1234",
+            "https://10.0.0.1",
+            "https://172.16.0.1",
+            "https://192.168.1.1",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.100.100.200",
+            "https://0.0.0.0",
+            "https://[::1]",
+            "https://[fe80::1]",
+            "https://[fd00:ec2::254]",
+            "https://[::ffff:127.0.0.1]",
+        ] {
+            assert!(
+                validate_target(&config, &webhook(url, "secret"))
+                    .await
+                    .is_err(),
+                "{}",
+                url
+            );
+        }
+
+        // Allow-listed hosts may be private, other hosts are rejected
+        let config = PushNotificationsConfig {
+            webhook_allowed_hosts: vec!["localhost".into(), "example.com".into()],
+            ..config
+        };
+        assert!(
+            validate_target(&config, &webhook("http://localhost:1234", "secret"))
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_target(&config, &webhook("https://hooks.example.com", "secret"))
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_target(&config, &webhook("https://badexample.com", "secret"))
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_target(&config, &webhook("https://8.8.8.8/notify", "secret"))
+                .await
+                .is_err()
+        );
+
+        let config = PushNotificationsConfig {
+            webhook_allow_http: false,
+            ..config
+        };
+        assert!(
+            validate_target(&config, &webhook("http://localhost:1234", "secret"))
+                .await
+                .is_err()
+        );
+
+        // Not configured
+        let target = PushTarget::Fcm {
+            token: "token".into(),
+        };
+        assert!(validate_target(&config, &target).await.is_err());
+        let target = PushTarget::Apns {
+            token: "abcdef".into(),
+        };
+        assert!(validate_target(&config, &target).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_batching() {
+        let (url, mut requests) = webhook::tests::stand_in().await;
+        let store = Arc::new(MemoryStore::new());
+        store
+            .push_target_set(
+                "did_hash",
+                &PushTarget::Webhook {
+                    url,
+                    secret: "secret".into(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let (trigger, shutdown) = shutdown::channel();
+        let (task, handle) = PushNotificationTask::new(_config(), store, shutdown).unwrap();
+        for _ in 0..3 {
+            task.notify("did_hash");
+        }
+        // No target registered, nothing is sent
+        task.notify("other_hash");
+
+        let request = tokio::time::timeout(Duration::from_secs(5), requests.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let notification: PushNotification = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(notification.did_hash, "did_hash");
+        assert_eq!(notification.count, 3);
+
+        trigger.trigger();
+        handle.await.unwrap();
+        assert!(requests.try_recv().is_err());
+    }
+}
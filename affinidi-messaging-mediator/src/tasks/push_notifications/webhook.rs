@@ -0,0 +1,365 @@
+//! Generic webhook push target
+//!
+//! The [PushNotification] is POSTed as JSON to the registered URL. The receiver verifies the
+//! notification came from the mediator by recomputing the signature with the webhook secret:
+//! - `X-ATM-Timestamp`: Epoch seconds when the notification was sent
+//! - `X-ATM-Signature`: `sha256=<hex>`, HMAC-SHA256 of `<timestamp>.<body>`
+//!
+//! Receivers should reject stale timestamps to prevent replays.
+//!
+//! Webhook URLs are supplied by DIDs, so the mediator only connects to public addresses unless
+//! the host is on `webhook_allowed_hosts`. This is checked when the webhook is registered, again
+//! before each notification, and by [PublicResolver] when connecting. Redirects aren't followed.
+
+use crate::common::config::PushNotificationsConfig;
+use affinidi_messaging_sdk::protocols::mediator::push_notifications::PushNotification;
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+};
+use ring::hmac;
+use std::net::{IpAddr, SocketAddr};
+use tokio::net::lookup_host;
+
+/// Signature header value for a notification body
+pub(crate) fn sign(secret: &str, timestamp: u64, body: &str) -> String {
+    [
+        "sha256=",
+        &_hmac_hex(secret, &format!("{}.{}", timestamp, body)),
+    ]
+    .concat()
+}
+
+fn _hmac_hex(secret: &str, data: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::sign(&key, data.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Checks a webhook URL can be notified by this mediator
+/// Returns the reason if it can't
+pub(crate) async fn validate_url(
+    config: &PushNotificationsConfig,
+    url: &str,
+) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|err| format!("webhook url is invalid: {}", err))?;
+    match url.scheme() {
+        "https" => {}
+        "http" if config.webhook_allow_http => {}
+        _ => return Err("webhook url must be a HTTPS URL".into()),
+    }
+
+    // IPv6 hosts are enclosed in brackets
+    let host = match url.host_str() {
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase(),
+        None => return Err("webhook url must have a host".into()),
+    };
+
+    if is_allowed_host(&config.webhook_allowed_hosts, &host) {
+        return Ok(());
+    } else if !config.webhook_allowed_hosts.is_empty() {
+        return Err(format!("webhook host ({}) is not allowed", host));
+    }
+
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = lookup_host((host.as_str(), port))
+        .await
+        .map_err(|err| format!("couldn't resolve webhook host ({}): {}", host, err))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("webhook host ({}) has no addresses", host));
+    }
+    if addrs.iter().any(|addr| !is_public(addr.ip())) {
+        return Err(format!(
+            "webhook host ({}) resolves to a private address",
+            host
+        ));
+    }
+
+    Ok(())
+}
+
+/// Is the host (or a domain it is a subdomain of) in the allow-list?
+fn is_allowed_host(allowed_hosts: &[String], host: &str) -> bool {
+    allowed_hosts.iter().any(|allowed| {
+        host == allowed
+            || host
+                .strip_suffix(allowed.as_str())
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+/// Rejects loopback, private, link-local (including cloud metadata) and other non-routable addresses
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // 0.0.0.0/8 "this network"
+                || a == 0
+                // 100.64.0.0/10 shared address space
+                || (a == 100 && b & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// DNS resolver for the push notification HTTP client, drops non-public addresses so a host
+/// can't be pointed at internal services after it has been validated
+pub(crate) struct PublicResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl PublicResolver {
+    pub(crate) fn new(allowed_hosts: &[String]) -> Self {
+        PublicResolver {
+            allowed_hosts: allowed_hosts.to_vec(),
+        }
+    }
+}
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_ascii_lowercase();
+        let trusted = is_allowed_host(&self.allowed_hosts, &host);
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| trusted || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("({}) has no public addresses", host).into());
+            }
+
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// POSTs a signed notification to a webhook
+pub(crate) async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    notification: &PushNotification,
+) -> Result<(), String> {
+    let body = serde_json::to_string(notification)
+        .map_err(|err| format!("Couldn't serialize notification. Reason: {}", err))?;
+
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("X-ATM-Timestamp", notification.timestamp.to_string())
+        .header(
+            "X-ATM-Signature",
+            sign(secret, notification.timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| format!("HTTP POST to ({}) failed: {}", url, err))?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "({}) responded with status ({})",
+            url,
+            response.status()
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tasks::push_notifications::PushSender;
+    use affinidi_messaging_sdk::protocols::mediator::push_notifications::PushTarget;
+    use ahash::AHashMap as HashMap;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        sync::mpsc,
+    };
+
+    /// A request received by the [stand_in] webhook
+    pub(crate) struct StandInRequest {
+        /// Header names are lowercase
+        pub headers: HashMap<String, String>,
+        pub body: String,
+    }
+
+    /// Local HTTP server standing in for a webhook, every request is answered with 200 OK
+    /// Returns the URL of the server and the requests it receives
+    pub(crate) async fn stand_in() -> (String, mpsc::UnboundedReceiver<StandInRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/notify", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mut stream = BufReader::new(stream);
+                let mut headers = HashMap::new();
+                loop {
+                    let mut line = String::new();
+                    if stream.read_line(&mut line).await.unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+                    }
+                }
+
+                let length = headers
+                    .get("content-length")
+                    .and_then(|length| length.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; length];
+                let _ = stream.read_exact(&mut body).await;
+
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                    .await;
+                let _ = tx.send(StandInRequest {
+                    headers,
+                    body: String::from_utf8_lossy(&body).to_string(),
+                });
+            }
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn test_hmac() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            _hmac_hex("Jefe", "what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert!(sign("secret", 1, "{}").starts_with("sha256="));
+        assert_ne!(sign("secret", 1, "{}"), sign("secret", 2, "{}"));
+    }
+
+    fn _config(allowed_hosts: &[&str]) -> PushNotificationsConfig {
+        PushNotificationsConfig {
+            webhook_allow_http: true,
+            webhook_allowed_hosts: allowed_hosts.iter().map(|host| host.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let (url, mut requests) = stand_in().await;
+        let sender = PushSender::new(_config(&["127.0.0.1"])).unwrap();
+
+        let notification = PushNotification {
+            did_hash: "did_hash".into(),
+            count: 2,
+            timestamp: 1_700_000_000,
+        };
+        sender
+            .send(
+                &PushTarget::Webhook {
+                    url,
+                    secret: "secret".into(),
+                },
+                &notification,
+            )
+            .await
+            .unwrap();
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(
+            request.headers.get("x-atm-timestamp").map(String::as_str),
+            Some("1700000000")
+        );
+        assert_eq!(
+            request.headers.get("x-atm-signature"),
+            Some(&sign("secret", 1_700_000_000, &request.body))
+        );
+        // Only the notification itself is sent, no message content
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&request.body).unwrap(),
+            serde_json::to_value(&notification).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_send_rejects_private_address() {
+        let (url, mut requests) = stand_in().await;
+        let sender = PushSender::new(_config(&[])).unwrap();
+
+        let notification = PushNotification {
+            did_hash: "did_hash".into(),
+            count: 1,
+            timestamp: 1_700_000_000,
+        };
+        let err = sender
+            .send(
+                &PushTarget::Webhook {
+                    url,
+                    secret: "secret".into(),
+                },
+                &notification,
+            )
+            .await
+            .unwrap_err();
+        assert!(err.contains("private address"), "{}", err);
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_public_resolver() {
+        let resolver = PublicResolver::new(&[]);
+        assert!(
+            resolver
+                .resolve("localhost".parse().unwrap())
+                .await
+                .is_err()
+        );
+
+        let resolver = PublicResolver::new(&["localhost".into()]);
+        let addrs: Vec<SocketAddr> = resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback()));
+        assert!(!addrs.is_empty());
+    }
+
+    #[test]
+    fn test_is_allowed_host() {
+        let allowed = vec!["example.com".to_string()];
+        assert!(is_allowed_host(&allowed, "example.com"));
+        assert!(is_allowed_host(&allowed, "hooks.example.com"));
+        assert!(!is_allowed_host(&allowed, "badexample.com"));
+        assert!(!is_allowed_host(&allowed, "example.com.evil.org"));
+        assert!(!is_allowed_host(&[], "example.com"));
+    }
+}
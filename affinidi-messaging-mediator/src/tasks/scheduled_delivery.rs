@@ -0,0 +1,358 @@
+use crate::{
+    database::{MediatorStore, scheduled_delivery::ScheduledDelivery, store::QueueByteQuota},
+    tasks::push_notifications::PushNotificationTask,
+};
+use affinidi_messaging_mediator_common::{errors::MediatorError, shutdown::Shutdown};
+use affinidi_messaging_mediator_processors::forwarding::task::ForwardTask;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tracing::{Instrument, Level, debug, info, span, warn};
+
+/// Maximum number of scheduled deliveries handled per tick
+const BATCH_LIMIT: usize = 100;
+
+/// How long to wait before retrying a delivery that couldn't be completed, i.e. the recipient
+/// queue is full or the database failed (milliseconds)
+const RETRY_DELAY: u128 = 60_000;
+
+/// Delivers delayed forward messages once their delivery time has been reached.
+/// Is spawned as a task from main().
+///
+/// Messages are never delivered before they are due (`delay_milli` is a "no earlier than" time),
+/// the check interval determines how late a message may be.
+/// On shutdown the current batch is finished, anything not yet due is left for the next start.
+///
+/// Queue quotas apply at delivery time, a delivery that would exceed them is retried later
+/// (until the message expires). The same applies when the message can't be stored or queued for
+/// forwarding.
+pub async fn scheduled_delivery(
+    database: Arc<dyn MediatorStore>,
+    push_notifications: Option<PushNotificationTask>,
+    quota: QueueByteQuota,
+    mut shutdown: Shutdown,
+) -> Result<(), MediatorError> {
+    let _span = span!(Level::INFO, "scheduled_delivery");
+
+    async move {
+        debug!("Starting scheduled delivery thread...");
+        let mut interval = tokio::time::interval(Duration::from_millis(250));
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+
+            let due = match database.scheduled_delivery_due(now, BATCH_LIMIT).await {
+                Ok(due) => due,
+                Err(err) => {
+                    warn!("Couldn't fetch due scheduled deliveries: {}", err);
+                    continue;
+                }
+            };
+
+            for id in due {
+                match database.scheduled_delivery_claim(&id).await {
+                    Ok(Some(delivery)) => {
+                        _deliver(
+                            database.as_ref(),
+                            push_notifications.as_ref(),
+                            &quota,
+                            &id,
+                            &delivery,
+                        )
+                        .await
+                    }
+                    Ok(None) => debug!("Scheduled delivery ({}) already claimed", id),
+                    Err(err) => warn!("{}", err),
+                }
+            }
+        }
+
+        debug!("Scheduled delivery thread stopped");
+        Ok(())
+    }
+    .instrument(_span)
+    .await
+}
+
+/// Makes a scheduled message visible to the recipient (or hands it to the forwarding processor)
+async fn _deliver(
+    database: &dyn MediatorStore,
+    push_notifications: Option<&PushNotificationTask>,
+    quota: &QueueByteQuota,
+    id: &str,
+    delivery: &ScheduledDelivery,
+) {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap();
+
+    if delivery.expires_at <= now.as_secs() {
+        info!(
+            "Scheduled delivery ({}) expired before it was due, dropping",
+            id
+        );
+        return;
+    }
+
+    if delivery.remote {
+        let task = ForwardTask::new(
+            &delivery.message,
+            &delivery.to_did,
+            delivery.from_did.as_deref(),
+            delivery.msg_id.as_deref(),
+            delivery.expires_at,
+        );
+        if let Err(err) = database.forward_queue_enqueue("SCHEDULED", &task).await {
+            warn!(
+                "Scheduled delivery ({}) couldn't be forwarded, retrying later: {}",
+                id, err
+            );
+            _reschedule(database, id, delivery, now.as_millis()).await;
+        }
+        return;
+    }
+
+    // Store first, so that a full queue holds back the live stream as well
+    let stored = if delivery.ephemeral {
+        false
+    } else {
+        match database
+            .store_message(
+                "SCHEDULED",
+                &delivery.message,
+                &delivery.to_did,
+                delivery.from_did.as_deref(),
+                delivery.expires_at,
+                delivery.signed_by.as_deref(),
+                quota,
+            )
+            .await
+        {
+            Ok(msg_id) => {
+                debug!("Scheduled delivery ({}) stored as ({})", id, msg_id);
+                true
+            }
+            Err(MediatorError::ServiceLimitError(_, err)) => {
+                warn!(
+                    "Scheduled delivery ({}) is over quota, retrying later: {}",
+                    id, err
+                );
+                _reschedule(database, id, delivery, now.as_millis()).await;
+                return;
+            }
+            Err(err) => {
+                warn!(
+                    "Scheduled delivery ({}) couldn't be stored, retrying later: {}",
+                    id, err
+                );
+                _reschedule(database, id, delivery, now.as_millis()).await;
+                return;
+            }
+        }
+    };
+
+    // Live stream the message?
+    let streams = database
+        .streaming_is_client_live(&delivery.to_did_hash, false)
+        .await;
+    for stream_uuid in &streams {
+        if database
+            .streaming_publish_message(&delivery.to_did_hash, stream_uuid, &delivery.message, false)
+            .await
+            .is_ok()
+        {
+            debug!("Live streaming message to UUID: {}", stream_uuid);
+        }
+    }
+
+    // Recipient isn't connected, let it know there is mail waiting
+    if stored && streams.is_empty() {
+        if let Some(push_notifications) = push_notifications {
+            push_notifications.notify(&delivery.to_did_hash);
+        }
+    }
+}
+
+/// Puts a delivery that couldn't be completed back on the schedule, to be retried after RETRY_DELAY
+async fn _reschedule(
+    database: &dyn MediatorStore,
+    id: &str,
+    delivery: &ScheduledDelivery,
+    now: u128,
+) {
+    if let Err(err) = database
+        .scheduled_delivery_add("SCHEDULED", now + RETRY_DELAY, delivery)
+        .await
+    {
+        warn!(
+            "Scheduled delivery ({}) couldn't be rescheduled: {}",
+            id, err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::memory_store::MemoryStore;
+    use affinidi_messaging_mediator_common::shutdown;
+    use affinidi_messaging_sdk::messages::Folder;
+    use sha256::digest;
+    use tokio_stream::StreamExt;
+
+    fn _now_ms() -> u128 {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    }
+
+    fn _delivery(message: &str) -> ScheduledDelivery {
+        ScheduledDelivery {
+            message: message.into(),
+            to_did: "did:example:bob".into(),
+            to_did_hash: digest("did:example:bob"),
+            from_did: Some("did:example:alice".into()),
+            msg_id: None,
+            expires_at: u64::MAX >> 1,
+            ephemeral: false,
+            remote: false,
+            signed_by: None,
+        }
+    }
+
+    async fn _inbox(store: &MemoryStore) -> Vec<u64> {
+        store
+            .list_messages(&digest("did:example:bob"), Folder::Inbox, None, 100)
+            .await
+            .unwrap()
+            .iter()
+            .map(|m| m.size)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_not_visible_before_delay() {
+        let store = Arc::new(MemoryStore::new());
+        store
+            .scheduled_delivery_add("test", _now_ms() + 60_000, &_delivery("later"))
+            .await
+            .unwrap();
+
+        let (trigger, shutdown) = shutdown::channel();
+        let handle = tokio::spawn(scheduled_delivery(
+            store.clone(),
+            None,
+            QueueByteQuota::UNLIMITED,
+            shutdown,
+        ));
+        // A few check intervals
+        tokio::time::sleep(Duration::from_millis(800)).await;
+
+        assert!(_inbox(&store).await.is_empty());
+        assert_eq!(
+            store
+                .scheduled_delivery_due(u128::MAX, BATCH_LIMIT)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        trigger.trigger();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delivered_and_streamed_when_due() {
+        let store = Arc::new(MemoryStore::new());
+        let to_hash = digest("did:example:bob");
+        let mut stream = store.streaming_subscribe("uuid").await.unwrap();
+        store
+            .streaming_register_client(&to_hash, "uuid")
+            .await
+            .unwrap();
+        store.streaming_start_live(&to_hash, "uuid").await.unwrap();
+
+        let deliver_at = _now_ms() + 500;
+        store
+            .scheduled_delivery_add("test", deliver_at, &_delivery("delayed"))
+            .await
+            .unwrap();
+
+        let (trigger, shutdown) = shutdown::channel();
+        let handle = tokio::spawn(scheduled_delivery(
+            store.clone(),
+            None,
+            QueueByteQuota::UNLIMITED,
+            shutdown,
+        ));
+        assert!(_inbox(&store).await.is_empty());
+
+        // Live streamed once the delay has elapsed, never before
+        let record = tokio::time::timeout(Duration::from_secs(5), stream.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(_now_ms() >= deliver_at);
+        assert_eq!(record.did_hash, to_hash);
+        assert_eq!(record.message, "delayed");
+
+        // And stored in the RECEIVE_Q
+        assert_eq!(_inbox(&store).await, vec!["delayed".len() as u64]);
+        assert!(
+            store
+                .scheduled_delivery_due(u128::MAX, BATCH_LIMIT)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        trigger.trigger();
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_over_quota_is_rescheduled() {
+        let store = MemoryStore::new();
+        let quota = QueueByteQuota {
+            send: -1,
+            receive: 10,
+        };
+
+        // Doesn't fit in the recipient's queue, held back and retried later
+        let before = _now_ms();
+        _deliver(&store, None, &quota, "test", &_delivery("too large to fit")).await;
+        assert!(_inbox(&store).await.is_empty());
+        assert!(
+            store
+                .scheduled_delivery_due(before + RETRY_DELAY - 1, BATCH_LIMIT)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let due = store
+            .scheduled_delivery_due(u128::MAX, BATCH_LIMIT)
+            .await
+            .unwrap();
+        assert_eq!(due.len(), 1);
+
+        // Delivered once the quota allows it
+        let delivery = store
+            .scheduled_delivery_claim(&due[0])
+            .await
+            .unwrap()
+            .unwrap();
+        _deliver(&store, None, &QueueByteQuota::UNLIMITED, &due[0], &delivery).await;
+        assert_eq!(_inbox(&store).await, vec!["too large to fit".len() as u64]);
+    }
+}
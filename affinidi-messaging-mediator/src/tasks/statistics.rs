@@ -1,12 +1,16 @@
-use crate::database::{Database, stats::MetadataStats};
-use affinidi_messaging_mediator_common::errors::MediatorError;
+use crate::database::{MediatorStore, stats::MetadataStats};
+use affinidi_messaging_mediator_common::{errors::MediatorError, shutdown::Shutdown};
 use std::env;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{Instrument, Level, debug, info, span};
 
 /// Periodically logs statistics about the database.
-/// Is spawned as a task from main().
-pub async fn statistics(database: Database) -> Result<(), MediatorError> {
+/// Is spawned as a task from main(), runs until shutdown is triggered.
+pub async fn statistics(
+    database: Arc<dyn MediatorStore>,
+    mut shutdown: Shutdown,
+) -> Result<(), MediatorError> {
     let _span = span!(Level::INFO, "statistics");
 
     async move {
@@ -21,7 +25,10 @@ pub async fn statistics(database: Database) -> Result<(), MediatorError> {
         };
 
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.wait() => break,
+            }
             let stats = database.get_db_metadata().await?;
             let delta = stats.delta(&previous_stats);
             info!(
@@ -60,6 +67,9 @@ pub async fn statistics(database: Database) -> Result<(), MediatorError> {
 
             previous_stats = stats;
         }
+
+        debug!("Statistics thread stopped");
+        Ok(())
     }
     .instrument(_span)
     .await
@@ -1,19 +1,24 @@
 /*!
- A task that listens for messages on a Redis pub/sub channel and sends them to clients over a websocket.
+ A task that listens for messages on the database pub/sub channel and sends them to clients over a websocket.
 
- It will maintain a HashSet of channels based on the DID hash. As a result, if duplicate websockets
- are created for the same DID, only the most recent one will receive messages as the older websocket
- channel will be forcibly closed.
+ It will maintain a HashMap of channels based on the DID hash. A DID can have multiple websockets
+ connected at the same time (e.g. phone and desktop), live messages are fanned out to every channel.
+ When more than `limits.ws_connections_per_did` websockets are connected for a DID, the oldest one
+ is forcibly closed.
 
- Any status on the existing websocket channel for a DID will need to be reset on the new channel.
+ Live delivery is enabled/disabled per websocket connection, live messages only go to the devices
+ that asked for them. The DID is live in the database while any of its websockets is live.
+ Messages are held in a single queue per DID, once a message is acknowledged (deleted) on one device
+ it will not be returned to the other devices.
+
+ On shutdown, any clients still registered are deregistered from the database before the task exits.
 
 */
-use crate::database::Database;
-use affinidi_messaging_mediator_common::errors::MediatorError;
+use crate::database::{MediatorStore, PubSubStream};
+use affinidi_messaging_mediator_common::{errors::MediatorError, shutdown::Shutdown};
 use ahash::AHashMap as HashMap;
-use redis::aio::PubSub;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use tokio::{select, sync::mpsc, task::JoinHandle, time::sleep};
 use tokio_stream::StreamExt;
 use tracing::{Instrument, Level, debug, error, info, span, warn};
@@ -22,15 +27,15 @@ use tracing::{Instrument, Level, debug, error, info, span, warn};
 // https://github.com/redis-rs/redis-rs/issues/509
 
 /// Used when updating the streaming state.
-/// Register: Creates the hash map entry for the DID hash and TX Channel
-/// Start: Start streaming messages to clients.
-/// Stop: Stop streaming messages to clients.
-/// Deregister: Remove the hash map entry for the DID hash.
+/// Register: Adds the websocket (connection ID and TX Channel) to the DID hash
+/// Start: Start streaming messages to the websocket (connection ID), None for every websocket of the DID.
+/// Stop: Stop streaming messages to the websocket (connection ID), None for every websocket of the DID.
+/// Deregister: Removes the websocket (connection ID) from the DID hash.
 pub enum StreamingUpdateState {
-    Register(mpsc::Sender<WebSocketCommands>),
-    Start,
-    Stop,
-    Deregister,
+    Register(String, mpsc::Sender<WebSocketCommands>),
+    Start(Option<String>),
+    Stop(Option<String>),
+    Deregister(String),
 }
 
 /// Used to send commands from the streaming task to the websocket handler
@@ -51,6 +56,30 @@ pub struct StreamingUpdate {
 pub struct StreamingTask {
     pub uuid: String,
     pub channel: mpsc::Sender<StreamingUpdate>,
+    /// Maximum number of websockets connected for a single DID
+    max_connections: usize,
+}
+
+/// A websocket connected for a DID
+struct StreamingConnection {
+    id: String,
+    channel: mpsc::Sender<WebSocketCommands>,
+    /// Live delivery is enabled for this websocket
+    live: bool,
+}
+
+/// Websockets connected for a DID
+#[derive(Default)]
+struct StreamingClient {
+    /// Oldest first
+    connections: Vec<StreamingConnection>,
+}
+
+impl StreamingClient {
+    /// Live delivery is enabled for at least one websocket
+    fn is_live(&self) -> bool {
+        self.connections.iter().any(|connection| connection.live)
+    }
 }
 
 /// This is the format of the JSON message that is sent to the pub/sub channel.
@@ -59,7 +88,7 @@ pub struct StreamingTask {
 /// force_delivery : If true, the message will be sent to the client even if they are not active.
 ///
 /// NOTE: The force_delivery is required as when changing live_delivery status, standard says to send a status message
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct PubSubRecord {
     pub did_hash: String,
     pub message: String,
@@ -69,8 +98,10 @@ pub struct PubSubRecord {
 impl StreamingTask {
     /// Creates the streaming task handler
     pub async fn new(
-        database: Database,
+        database: Arc<dyn MediatorStore>,
         mediator_uuid: &str,
+        max_connections: usize,
+        shutdown: Shutdown,
     ) -> Result<(Self, JoinHandle<()>), MediatorError> {
         let _span = span!(Level::INFO, "StreamingTask::new");
 
@@ -80,6 +111,7 @@ impl StreamingTask {
             let task = StreamingTask {
                 channel: tx.clone(),
                 uuid: mediator_uuid.to_string(),
+                max_connections: max_connections.max(1),
             };
 
             // Start the streaming task
@@ -88,7 +120,7 @@ impl StreamingTask {
                 let _task = task.clone();
                 tokio::spawn(async move {
                     _task
-                        .ws_streaming_task(database, &mut rx)
+                        .ws_streaming_task(database, &mut rx, shutdown)
                         .await
                         .expect("Error starting websocket_streaming thread");
                 })
@@ -100,36 +132,27 @@ impl StreamingTask {
         .await
     }
 
-    /// Starts a pubsub connection to Redis and subscribes to a channel.
-    /// Useful way to restart a terminated connection from within a loop.
-    async fn _start_pubsub(&self, database: Database, uuid: &str) -> Result<PubSub, MediatorError> {
+    /// Subscribes to the pub/sub channel of this streaming service.
+    /// Useful way to restart a terminated subscription from within a loop.
+    async fn _start_pubsub(
+        &self,
+        database: &dyn MediatorStore,
+        uuid: &str,
+    ) -> Result<PubSubStream, MediatorError> {
         let _span = span!(Level::INFO, "_start_pubsub");
 
-        async move {
-            let mut pubsub = database.0.get_pubsub_connection().await?;
-
-            let channel = format!("CHANNEL:{}", uuid);
-            pubsub.subscribe(channel.clone()).await.map_err(|err| {
-                error!("Error subscribing to channel: {}", err);
-                MediatorError::DatabaseError(
-                    "NA".into(),
-                    format!("Error subscribing to channel: {}", err),
-                )
-            })?;
-
-            info!("Subscribed to channel: {}", channel);
-            Ok(pubsub)
-        }
-        .instrument(_span)
-        .await
+        async move { database.streaming_subscribe(uuid).await }
+            .instrument(_span)
+            .await
     }
 
     /// Streams messages to subscribed clients over websocket.
     /// Is spawned as a task
     async fn ws_streaming_task(
         self,
-        database: Database,
+        database: Arc<dyn MediatorStore>,
         channel: &mut mpsc::Receiver<StreamingUpdate>,
+        mut shutdown: Shutdown,
     ) -> Result<(), MediatorError> {
         let _span = span!(Level::INFO, "ws_streaming_task", uuid = &self.uuid);
 
@@ -139,55 +162,57 @@ impl StreamingTask {
             // Clean up any existing sessions left over from previous runs
             database.streaming_clean_start(&self.uuid).await?;
 
-            // Create a hashmap to store the clients and if they are active (true = yes)
-            let mut clients: HashMap<String, (mpsc::Sender<WebSocketCommands>, bool)> = HashMap::new();
+            // Create a hashmap to store the clients channels and if they are active
+            let mut clients: HashMap<String, StreamingClient> = HashMap::new();
 
             // Start streaming messages to clients
-            let mut pubsub = self._start_pubsub(database.clone(), &self.uuid).await?;
+            let mut stream = self._start_pubsub(database.as_ref(), &self.uuid).await?;
             loop {
-                let mut stream = pubsub.on_message();
-
-                // Listen for an update on either the redis pubsub stream, or the command channel
-                // stream: redis pubsub of incoming messages destined for a client
+                // Listen for an update on either the pubsub stream, or the command channel
+                // stream: pubsub of incoming messages destined for a client
                 // channel: command channel to start/stop streaming for a client
+                // shutdown: the mediator is stopping
                 select! {
-                    value = stream.next() => { // redis pubsub
-                        if let Some(msg) = value {
-                            if let Ok(payload) = msg.get_payload::<String>() {
-                                let payload: PubSubRecord = serde_json::from_str(&payload).unwrap();
-
-                                // Find the MPSC transmit channel for the associated DID hash
-                                match clients.get(&payload.did_hash) { Some((tx, active)) => {
-                                    if payload.force_delivery ||  *active {
-                                        // Send the message to the client
-                                        match tx.send(WebSocketCommands::Message(payload.message.clone())).await { Err(err) => {
-                                            error!("Error sending message to client ({}): {}", payload.did_hash, err);
+                    _ = shutdown.wait() => {
+                        // Websockets close themselves on shutdown, make sure none are left registered
+                        info!("Shutting down, deregistering {} streaming clients", clients.len());
+                        for did_hash in clients.keys() {
+                            if let Err(err) = database.streaming_deregister_client(did_hash, &self.uuid).await {
+                                error!("Error deregistering streaming client ({}): {}", did_hash, err);
+                            }
+                        }
+                        break;
+                    }
+                    value = stream.next() => { // pubsub
+                        if let Some(payload) = value {
+                            // Find the MPSC transmit channels for the associated DID hash
+                            match clients.get(&payload.did_hash) { Some(client) => {
+                                if payload.force_delivery || client.is_live() {
+                                    // Send the message to every websocket for the client that is live
+                                    for connection in client.connections.iter().filter(|connection| payload.force_delivery || connection.live) {
+                                        match connection.channel.send(WebSocketCommands::Message(payload.message.clone())).await { Err(err) => {
+                                            error!("Error sending message to client ({}) connection ({}): {}", payload.did_hash, connection.id, err);
                                         } _ => {
-                                            info!("Sent message to client ({})", payload.did_hash);
+                                            info!("Sent message to client ({}) connection ({})", payload.did_hash, connection.id);
                                         }}
-                                    } else {
-                                        warn!("pub/sub msg received for did_hash({}) but it is not active", payload.did_hash);
-                                        if let Err(err) = database.streaming_stop_live(&payload.did_hash, &self.uuid).await {
-                                            error!("Error stopping streaming for client ({}): {}", payload.did_hash, err);
-                                        }
                                     }
-                                } _ => {
-                                    warn!("pub/sub msg received for did_hash({}) but it doesn't exist in clients HashMap", payload.did_hash);
-                                }}
-
-                            } else {
-                                error!("Error getting payload from message");
-                                continue;
-                            };
+                                } else {
+                                    warn!("pub/sub msg received for did_hash({}) but it is not active", payload.did_hash);
+                                    if let Err(err) = database.streaming_stop_live(&payload.did_hash, &self.uuid).await {
+                                        error!("Error stopping streaming for client ({}): {}", payload.did_hash, err);
+                                    }
+                                }
+                            } _ => {
+                                warn!("pub/sub msg received for did_hash({}) but it doesn't exist in clients HashMap", payload.did_hash);
+                            }}
                         } else {
-                            // Redis connection dropped, need to retry
-                            error!("Redis connection dropped, retrying...");
-                            drop(stream);
+                            // pubsub connection dropped, need to retry
+                            error!("pubsub connection dropped, retrying...");
 
-                            pubsub = loop {
+                            stream = loop {
                                 sleep(Duration::from_secs(1)).await;
-                                match self._start_pubsub(database.clone(), &self.uuid).await {
-                                    Ok(pubsub) => break pubsub,
+                                match self._start_pubsub(database.as_ref(), &self.uuid).await {
+                                    Ok(stream) => break stream,
                                     Err(err) => {
                                         error!("Error starting pubsub: {}", err);
                                         continue;
@@ -199,84 +224,372 @@ impl StreamingTask {
                     value = channel.recv() => { // mpsc command channel
                         if let Some(value) = &value {
                             match &value.state {
-                                StreamingUpdateState::Register(client_tx) => {
-                                    self._handle_registration(&database, &mut clients, value, client_tx).await;
+                                StreamingUpdateState::Register(connection_id, client_tx) => {
+                                    self._handle_registration(database.as_ref(), &mut clients, value, connection_id, client_tx).await;
                                 },
-                                StreamingUpdateState::Start => {
-                                    if let Some((_, active)) = clients.get_mut(&value.did_hash) {
-                                        info!("Starting streaming for DID: ({})", value.did_hash);
-                                        *active = true;
-                                    };
-
-                                    if let Err(err) = database.streaming_start_live(&value.did_hash, &self.uuid).await {
-                                        error!("Error starting streaming to client ({}) streaming: {}",value.did_hash, err);
-                                    }
+                                StreamingUpdateState::Start(connection_id) => {
+                                    self._handle_live_delivery(database.as_ref(), &mut clients, value, connection_id.as_deref(), true).await;
                                 },
-                                StreamingUpdateState::Stop => {
-                                    // Set active to false
-                                    if let Some((_, active)) = clients.get_mut(&value.did_hash) {
-                                        info!("Stopping streaming for DID: ({})", value.did_hash);
-                                        *active = false;
-                                    };
-
-                                    if let Err(err) = database.streaming_stop_live(&value.did_hash, &self.uuid).await {
-                                        error!("Error stopping streaming for client ({}): {}",value.did_hash, err);
-                                    }
+                                StreamingUpdateState::Stop(connection_id) => {
+                                    self._handle_live_delivery(database.as_ref(), &mut clients, value, connection_id.as_deref(), false).await;
                                 },
-                                StreamingUpdateState::Deregister => {
-                                    let count = if !clients.is_empty() {
-                                        clients.len() - 1
-                                    } else {
-                                        warn!("Duplicate websocket channels has us off by one");
-                                        0
-                                    };
-                                    info!("Deregistered streaming for DID: ({}) registered_clients({})", value.did_hash, count);
-                                    if let Err(err) = database.streaming_deregister_client(&value.did_hash, &self.uuid).await {
-                                        error!("Error stopping streaming for client ({}): {}",value.did_hash, err);
-                                    }
-                                    clients.remove(value.did_hash.as_str());
+                                StreamingUpdateState::Deregister(connection_id) => {
+                                    self._handle_deregistration(database.as_ref(), &mut clients, value, connection_id).await;
                                 }
                             }
                         }
                     }
                 }
             }
+
+            debug!("Stopped");
+            Ok(())
         }
         .instrument(_span)
         .await
     }
 
-    /// Helper function to handle the registration of a new client.
-    /// Handles if this is a duplicate channel for a DID
+    /// Helper function to handle the registration of a new client websocket.
+    /// Closes the oldest websocket if the DID has too many websockets connected
     async fn _handle_registration(
         &self,
-        database: &Database,
-        clients: &mut HashMap<String, (mpsc::Sender<WebSocketCommands>, bool)>,
+        database: &dyn MediatorStore,
+        clients: &mut HashMap<String, StreamingClient>,
         value: &StreamingUpdate,
+        connection_id: &str,
         client_tx: &mpsc::Sender<WebSocketCommands>,
     ) {
-        if let Some((channel, _)) = clients.get(&value.did_hash) {
-            debug!("Duplicate WebSocket channel detected");
-            // Channel already exists, close the old one
-            let _ = channel.send(WebSocketCommands::Close).await;
-            debug!("Sent close WebSocket command to old channel");
+        let new_client = !clients.contains_key(&value.did_hash);
+        let client = clients.entry(value.did_hash.clone()).or_default();
+
+        let mut evicted = false;
+        while client.connections.len() >= self.max_connections {
+            let old = client.connections.remove(0);
+            debug!(
+                "Too many WebSocket channels for DID: ({}), closing connection ({})",
+                value.did_hash, old.id
+            );
+            let _ = old.channel.send(WebSocketCommands::Close).await;
+            evicted = true;
         }
+        client.connections.push(StreamingConnection {
+            id: connection_id.to_string(),
+            channel: client_tx.clone(),
+            live: false,
+        });
+        let connections = client.connections.len();
+        let live = client.is_live();
 
         info!(
-            "Registered streaming for DID: ({}) registered_clients({})",
+            "Registered streaming for DID: ({}) connection({}) connections({}) registered_clients({})",
             value.did_hash,
-            clients.len() + 1
+            connection_id,
+            connections,
+            clients.len()
         );
-        clients.insert(value.did_hash.clone(), (client_tx.clone(), false));
 
+        // The closed websocket may have been the only live one
+        if evicted {
+            self._update_live(database, &value.did_hash, live).await;
+        }
+
+        // Only the first websocket registers the DID against this streaming service, so live
+        // delivery state isn't reset for the websockets already connected
+        if new_client {
+            if let Err(err) = database
+                .streaming_register_client(&value.did_hash, &self.uuid)
+                .await
+            {
+                error!(
+                    "Error starting streaming to client ({}) streaming: {}",
+                    value.did_hash, err
+                );
+            }
+        }
+    }
+
+    /// Helper function to handle a client websocket closing.
+    /// The DID is deregistered from this streaming service once its last websocket has closed
+    async fn _handle_deregistration(
+        &self,
+        database: &dyn MediatorStore,
+        clients: &mut HashMap<String, StreamingClient>,
+        value: &StreamingUpdate,
+        connection_id: &str,
+    ) {
+        let Some(client) = clients.get_mut(&value.did_hash) else {
+            // Already removed (e.g. closed as the oldest websocket)
+            return;
+        };
+        client
+            .connections
+            .retain(|connection| connection.id != connection_id);
+        if !client.connections.is_empty() {
+            info!(
+                "Deregistered connection ({}) for DID: ({}) connections({})",
+                connection_id,
+                value.did_hash,
+                client.connections.len()
+            );
+            // The closed websocket may have been the only live one
+            let live = client.is_live();
+            self._update_live(database, &value.did_hash, live).await;
+            return;
+        }
+
+        clients.remove(value.did_hash.as_str());
+        info!(
+            "Deregistered streaming for DID: ({}) registered_clients({})",
+            value.did_hash,
+            clients.len()
+        );
         if let Err(err) = database
-            .streaming_register_client(&value.did_hash, &self.uuid)
+            .streaming_deregister_client(&value.did_hash, &self.uuid)
             .await
         {
             error!(
-                "Error starting streaming to client ({}) streaming: {}",
+                "Error stopping streaming for client ({}): {}",
                 value.did_hash, err
             );
         }
     }
+
+    /// Helper function to enable/disable live delivery for a websocket, or for every websocket of
+    /// the DID when `connection_id` is None
+    async fn _handle_live_delivery(
+        &self,
+        database: &dyn MediatorStore,
+        clients: &mut HashMap<String, StreamingClient>,
+        value: &StreamingUpdate,
+        connection_id: Option<&str>,
+        live: bool,
+    ) {
+        let Some(client) = clients.get_mut(&value.did_hash) else {
+            warn!(
+                "Live delivery change for DID: ({}) without a websocket connected",
+                value.did_hash
+            );
+            return;
+        };
+        for connection in client
+            .connections
+            .iter_mut()
+            .filter(|connection| connection_id.is_none_or(|id| id == connection.id))
+        {
+            info!(
+                "{} streaming for DID: ({}) connection ({})",
+                if live { "Starting" } else { "Stopping" },
+                value.did_hash,
+                connection.id
+            );
+            connection.live = live;
+        }
+        let live = client.is_live();
+        self._update_live(database, &value.did_hash, live).await;
+    }
+
+    /// Helper function to set the live delivery state of the DID in the database
+    async fn _update_live(&self, database: &dyn MediatorStore, did_hash: &str, live: bool) {
+        let result = if live {
+            database.streaming_start_live(did_hash, &self.uuid).await
+        } else {
+            database.streaming_stop_live(did_hash, &self.uuid).await
+        };
+        if let Err(err) = result {
+            error!(
+                "Error updating live streaming ({}) for client ({}): {}",
+                live, did_hash, err
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{memory_store::MemoryStore, store::QueueByteQuota};
+    use affinidi_messaging_mediator_common::shutdown::{self, ShutdownTrigger};
+    use affinidi_messaging_sdk::messages::Folder;
+    use sha256::digest;
+    use tokio::time::timeout;
+
+    const UUID: &str = "streaming-test";
+
+    fn _did_hash() -> String {
+        digest("did:example:bob")
+    }
+
+    async fn _start(
+        max_connections: usize,
+    ) -> (
+        Arc<MemoryStore>,
+        StreamingTask,
+        ShutdownTrigger,
+        JoinHandle<()>,
+    ) {
+        let store = Arc::new(MemoryStore::new());
+        let (trigger, shutdown) = shutdown::channel();
+        let (task, handle) = StreamingTask::new(store.clone(), UUID, max_connections, shutdown)
+            .await
+            .unwrap();
+        (store, task, trigger, handle)
+    }
+
+    async fn _update(task: &StreamingTask, state: StreamingUpdateState) {
+        task.channel
+            .send(StreamingUpdate {
+                did_hash: _did_hash(),
+                state,
+            })
+            .await
+            .unwrap();
+        // Let the streaming task process the update
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    async fn _register(task: &StreamingTask, id: &str) -> mpsc::Receiver<WebSocketCommands> {
+        let (tx, rx) = mpsc::channel(5);
+        _update(task, StreamingUpdateState::Register(id.into(), tx)).await;
+        rx
+    }
+
+    /// Returns the next message sent to the websocket, None if nothing arrives
+    async fn _recv(rx: &mut mpsc::Receiver<WebSocketCommands>) -> Option<String> {
+        match timeout(Duration::from_millis(300), rx.recv()).await {
+            Ok(Some(WebSocketCommands::Message(msg))) => Some(msg),
+            _ => None,
+        }
+    }
+
+    async fn _publish(store: &MemoryStore, message: &str, force_delivery: bool) {
+        store
+            .streaming_publish_message(&_did_hash(), UUID, message, force_delivery)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_to_every_live_websocket() {
+        let (store, task, trigger, handle) = _start(5).await;
+        let mut phone = _register(&task, "phone").await;
+        let mut desktop = _register(&task, "desktop").await;
+        _update(&task, StreamingUpdateState::Start(None)).await;
+
+        _publish(&store, "hello", false).await;
+        assert_eq!(_recv(&mut phone).await.as_deref(), Some("hello"));
+        assert_eq!(_recv(&mut desktop).await.as_deref(), Some("hello"));
+
+        trigger.trigger();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oldest_websocket_evicted() {
+        let (store, task, trigger, handle) = _start(2).await;
+        let mut first = _register(&task, "first").await;
+        let mut second = _register(&task, "second").await;
+        let mut third = _register(&task, "third").await;
+
+        assert!(matches!(first.recv().await, Some(WebSocketCommands::Close)));
+
+        _publish(&store, "status", true).await;
+        assert_eq!(_recv(&mut second).await.as_deref(), Some("status"));
+        assert_eq!(_recv(&mut third).await.as_deref(), Some("status"));
+        assert!(_recv(&mut first).await.is_none());
+
+        trigger.trigger();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_delivery_per_websocket() {
+        let (store, task, trigger, handle) = _start(5).await;
+        let mut phone = _register(&task, "phone").await;
+        let mut desktop = _register(&task, "desktop").await;
+
+        // Only the phone asked for live delivery
+        _update(&task, StreamingUpdateState::Start(Some("phone".into()))).await;
+        assert_eq!(
+            store.streaming_is_client_live(&_did_hash(), false).await,
+            vec![UUID.to_string()]
+        );
+        _publish(&store, "one", false).await;
+        assert_eq!(_recv(&mut phone).await.as_deref(), Some("one"));
+        assert!(_recv(&mut desktop).await.is_none());
+
+        // Stopping the desktop doesn't affect the phone
+        _update(&task, StreamingUpdateState::Stop(Some("desktop".into()))).await;
+        _publish(&store, "two", false).await;
+        assert_eq!(_recv(&mut phone).await.as_deref(), Some("two"));
+        assert!(_recv(&mut desktop).await.is_none());
+
+        // The DID is no longer live once its last live websocket stops
+        _update(&task, StreamingUpdateState::Stop(Some("phone".into()))).await;
+        assert!(
+            store
+                .streaming_is_client_live(&_did_hash(), false)
+                .await
+                .is_empty()
+        );
+
+        // Closing the only live websocket also ends live delivery for the DID
+        _update(&task, StreamingUpdateState::Start(Some("desktop".into()))).await;
+        _update(&task, StreamingUpdateState::Deregister("desktop".into())).await;
+        assert!(
+            store
+                .streaming_is_client_live(&_did_hash(), false)
+                .await
+                .is_empty()
+        );
+
+        trigger.trigger();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_single_queue_per_did() {
+        let (store, task, trigger, handle) = _start(5).await;
+        let mut phone = _register(&task, "phone").await;
+        let mut desktop = _register(&task, "desktop").await;
+
+        let msg_id = store
+            .store_message(
+                "test",
+                "queued",
+                "did:example:bob",
+                None,
+                u64::MAX >> 1,
+                None,
+                &QueueByteQuota::UNLIMITED,
+            )
+            .await
+            .unwrap();
+
+        // Both devices see the one queued message
+        let inbox = store
+            .list_messages(&_did_hash(), Folder::Inbox, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].msg_id, msg_id);
+
+        // Once acknowledged on one device it is gone for the other
+        store
+            .delete_message(Some("test"), &_did_hash(), &msg_id)
+            .await
+            .unwrap();
+        assert!(
+            store
+                .list_messages(&_did_hash(), Folder::Inbox, None, 100)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        // Deleting isn't streamed to the devices
+        assert!(_recv(&mut phone).await.is_none());
+        assert!(_recv(&mut desktop).await.is_none());
+
+        trigger.trigger();
+        handle.await.unwrap();
+    }
 }
@@ -1,13 +1,18 @@
 use affinidi_did_resolver_cache_sdk::{DIDCacheClient, config::DIDCacheConfigBuilder};
-use affinidi_messaging_didcomm::{Message, PackEncryptedOptions};
-use affinidi_messaging_mediator::server::start;
+use affinidi_messaging_didcomm::{Message, PackEncryptedOptions, UnpackOptions};
+use affinidi_messaging_mediator::{
+    common::config::load,
+    database::{memory_store::MemoryStore, session::SESSION_EXPIRY},
+    server::{MediatorHandle, start_with},
+};
 use affinidi_messaging_sdk::{
+    authentication::AuthRefreshResponse,
     config::ATMConfig,
     errors::ATMError,
     messages::{
         AuthenticationChallenge, AuthorizationResponse, DeleteMessageRequest,
         DeleteMessageResponse, Folder, GetMessagesRequest, GetMessagesResponse, MessageList,
-        MessageListElement, SuccessResponse, fetch::FetchOptions,
+        MessageListElement, SuccessResponse, fetch::FetchOptions, sending::InboundMessageResponse,
     },
     transports::SendMessageResponse,
 };
@@ -19,6 +24,7 @@ use core::panic;
 use message_builders::{
     build_delivery_request_message, build_forward_request_message, build_message_received_message,
     build_ping_message, build_status_request_message, create_auth_challenge_response,
+    create_refresh_message,
 };
 use reqwest::{Certificate, Client, ClientBuilder};
 use response_validations::{
@@ -28,21 +34,23 @@ use response_validations::{
 use serde_json::json;
 use sha256::digest;
 use std::{
+    env,
     fs::{self, File},
     io::{self, BufRead, BufReader},
     path::Path,
     process::Command,
     str,
-    time::Duration,
+    sync::Arc,
+    time::{Duration, SystemTime},
 };
-use tokio::time::sleep;
+use tokio::time::timeout;
 
 mod common;
 mod message_builders;
 mod response_validations;
 
-//#[tokio::test]
-#[allow(dead_code)]
+#[tokio::test]
+#[ignore = "generates the mediator secrets and certificates in ./conf and listens on port 7037"]
 async fn test_mediator_server() {
     // Generate secrets and did for mediator if not existing
     if fs::metadata(SECRETS_PATH).is_err() {
@@ -54,10 +62,7 @@ async fn test_mediator_server() {
         println!("Secrets generated and did injected to mediator.toml");
     }
 
-    _start_mediator_server().await;
-
-    // Allow some time for the server to start
-    sleep(Duration::from_millis(2000)).await;
+    let mut mediator = _start_mediator_server().await;
 
     let config = ATMConfig::builder()
         .with_ssl_certificates(&mut vec![
@@ -82,11 +87,13 @@ async fn test_mediator_server() {
 
     let client = init_client(config.clone());
 
-    let mediator_did = _well_known(client.clone()).await;
+    let mediator_did = _well_known(client.clone(), MEDIATOR_API).await;
 
     // Start Authentication
-    let alice_authentication_challenge = _authenticate_challenge(client.clone(), ALICE_DID).await;
-    let bob_authentication_challenge = _authenticate_challenge(client.clone(), BOB_DID).await;
+    let alice_authentication_challenge =
+        _authenticate_challenge(client.clone(), MEDIATOR_API, ALICE_DID).await;
+    let bob_authentication_challenge =
+        _authenticate_challenge(client.clone(), MEDIATOR_API, BOB_DID).await;
 
     // /authenticate/challenge
     let alice_auth_response_msg =
@@ -98,6 +105,7 @@ async fn test_mediator_server() {
     // /authenticate
     let alice_authentication_response = _authenticate(
         client.clone(),
+        MEDIATOR_API,
         alice_auth_response_msg,
         ALICE_DID,
         &mediator_did,
@@ -107,6 +115,7 @@ async fn test_mediator_server() {
     .await;
     let bob_authentication_response = _authenticate(
         client.clone(),
+        MEDIATOR_API,
         bob_auth_response_msg,
         BOB_DID,
         &mediator_did,
@@ -357,6 +366,7 @@ async fn test_mediator_server() {
             limit: 10,
             start_id: None,
             delete_policy: affinidi_messaging_sdk::messages::FetchDeletePolicy::DoNotDelete,
+            recipient_did: None,
         },
     )
     .await;
@@ -378,11 +388,174 @@ async fn test_mediator_server() {
     )
     .await;
     assert_eq!(deleted_msgs.success.len(), 4);
+
+    mediator.shutdown();
+    mediator.stopped().await.unwrap();
 }
 
-async fn _start_mediator_server() {
-    tokio::spawn(async move { start().await });
-    println!("Server running");
+/// Runs a throwaway mediator against the in-memory backend on a port picked by the OS
+/// Needs no Redis, generated secrets or certificates
+#[tokio::test]
+async fn test_memory_mediator() {
+    // Alice stands in as the mediator, her did:peer DID resolves without network access
+    let secrets_path = env::temp_dir().join(format!(
+        "atm-memory-mediator-secrets-{}.json",
+        std::process::id()
+    ));
+    fs::write(
+        &secrets_path,
+        json!([
+            {"id": format!("{}#key-1", ALICE_DID), "type": "JsonWebKey2020", "privateKeyJwk": ALICE_V1.clone()},
+            {"id": format!("{}#key-2", ALICE_DID), "type": "JsonWebKey2020", "privateKeyJwk": ALICE_E1.clone()},
+        ])
+        .to_string(),
+    )
+    .unwrap();
+
+    // The default configuration with overrides for this test
+    // SAFETY: This is the only test in this binary that runs by default and reads the environment
+    unsafe {
+        env::set_var("MEDIATOR_DID", format!("did://{}", ALICE_DID));
+        env::set_var(
+            "MEDIATOR_SECRETS",
+            format!("file://{}", secrets_path.display()),
+        );
+        env::set_var("LISTEN_ADDRESS", "127.0.0.1:0");
+        env::set_var("USE_SSL", "false");
+        env::set_var("DATABASE_URL", "memory://");
+    }
+    let config = load(CONFIG_PATH)
+        .await
+        .expect("Couldn't load mediator configuration");
+
+    let mut mediator = start_with(config, Arc::new(MemoryStore::new()))
+        .await
+        .expect("Couldn't start mediator");
+    assert_ne!(mediator.address.port(), 0);
+    let api = format!("http://{}/mediator/v1", mediator.address);
+
+    let client = Client::new();
+    let mediator_did = _well_known(client.clone(), &api).await;
+    assert_eq!(mediator_did, ALICE_DID);
+
+    // Bob authenticates, his session and account live in the MemoryStore
+    let did_resolver = DIDCacheClient::new(DIDCacheConfigBuilder::default().build())
+        .await
+        .unwrap();
+    let bob_secrets_resolver = SimpleSecretsResolver::new(&[
+        Secret::from_str(&format!("{}#key-1", BOB_DID), &BOB_V1),
+        Secret::from_str(&format!("{}#key-2", BOB_DID), &BOB_E1),
+    ])
+    .await;
+    let challenge = _authenticate_challenge(client.clone(), &api, BOB_DID).await;
+    let tokens = _authenticate(
+        client.clone(),
+        &api,
+        create_auth_challenge_response(&challenge, BOB_DID, &mediator_did),
+        BOB_DID,
+        &mediator_did,
+        &did_resolver,
+        &bob_secrets_resolver,
+    )
+    .await;
+    assert!(!tokens.access_token.is_empty());
+
+    // The refresh token expires with the session
+    assert!(
+        tokens.refresh_expires_at
+            <= SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + SESSION_EXPIRY as u64
+    );
+
+    // Refreshing over HTTP only issues a new access token
+    let refreshed = _refresh(
+        client.clone(),
+        &api,
+        create_refresh_message(&tokens.refresh_token, BOB_DID, &mediator_did),
+        BOB_DID,
+        &mediator_did,
+        &did_resolver,
+        &bob_secrets_resolver,
+    )
+    .await;
+    assert!(!refreshed.access_token.is_empty());
+
+    // The same refresh token can be used to refresh in-band
+    let refresh_msg = create_refresh_message(&tokens.refresh_token, BOB_DID, &mediator_did);
+    let refresh_msg_id = refresh_msg.id.clone();
+    let (refresh_msg, _) = refresh_msg
+        .pack_encrypted(
+            &mediator_did,
+            Some(BOB_DID),
+            Some(BOB_DID),
+            &did_resolver,
+            &bob_secrets_resolver,
+            &PackEncryptedOptions::default(),
+        )
+        .await
+        .unwrap();
+    let res = client
+        .post(format!("{}/inbound", api))
+        .header("Content-Type", "application/json")
+        .header("Authorization", format!("Bearer {}", tokens.access_token))
+        .body(refresh_msg)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: SuccessResponse<InboundMessageResponse> = res.json().await.unwrap();
+    let Some(InboundMessageResponse::Ephemeral(response)) = body.data else {
+        panic!("Refresh response should be returned to the requestor");
+    };
+    let (response, _) = Message::unpack_string(
+        &response,
+        &did_resolver,
+        &bob_secrets_resolver,
+        &UnpackOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        response.type_,
+        "https://affinidi.com/atm/1.0/authenticate/refresh/response"
+    );
+    assert_eq!(response.thid, Some(refresh_msg_id));
+    let in_band: AuthRefreshResponse = serde_json::from_value(response.body).unwrap();
+    assert!(!in_band.access_token.is_empty());
+
+    // The refresh response isn't queued for Bob's other devices
+    let res = client
+        .get(format!("{}/list/{}/inbox", api, digest(BOB_DID)))
+        .header("Authorization", format!("Bearer {}", in_band.access_token))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), 200);
+    let body: SuccessResponse<MessageList> = res.json().await.unwrap();
+    assert!(body.data.unwrap().is_empty());
+
+    mediator.shutdown();
+    timeout(Duration::from_secs(30), mediator.stopped())
+        .await
+        .expect("Mediator didn't stop in time")
+        .expect("Mediator stopped with an error");
+
+    let _ = fs::remove_file(secrets_path);
+}
+
+async fn _start_mediator_server() -> MediatorHandle {
+    // Runs the mediator in-process against the in-memory backend, no Redis required
+    let config = load(CONFIG_PATH)
+        .await
+        .expect("Couldn't load mediator configuration");
+    let mediator = start_with(config, Arc::new(MemoryStore::new()))
+        .await
+        .expect("Couldn't start mediator");
+    println!("Server running on ({})", mediator.address);
+    mediator
 }
 
 #[allow(dead_code)]
@@ -406,8 +579,8 @@ fn init_client(config: ATMConfig) -> Client {
     }
 }
 
-async fn _well_known(client: Client) -> String {
-    let well_known_did_atm_api = format!("{}/.well-known/did", MEDIATOR_API);
+async fn _well_known(client: Client, api: &str) -> String {
+    let well_known_did_atm_api = format!("{}/.well-known/did", api);
 
     let res = client
         .get(well_known_did_atm_api)
@@ -434,9 +607,9 @@ async fn _well_known(client: Client) -> String {
     did
 }
 
-async fn _authenticate_challenge(client: Client, did: &str) -> AuthenticationChallenge {
+async fn _authenticate_challenge(client: Client, api: &str, did: &str) -> AuthenticationChallenge {
     let res = client
-        .post(format!("{}/authenticate/challenge", MEDIATOR_API))
+        .post(format!("{}/authenticate/challenge", api))
         .header("Content-Type", "application/json")
         .body(format!("{{\"did\": \"{}\"}}", did).to_string())
         .send()
@@ -468,6 +641,7 @@ async fn _authenticate_challenge(client: Client, did: &str) -> AuthenticationCha
 
 async fn _authenticate<S>(
     client: Client,
+    api: &str,
     auth_response: Message,
     actor_did: &str,
     atm_did: &str,
@@ -496,7 +670,7 @@ where
         .unwrap();
 
     let res = client
-        .post(format!("{}/authenticate", MEDIATOR_API))
+        .post(format!("{}/authenticate", api))
         .header("Content-Type", "application/json")
         .body(auth_msg)
         .send()
@@ -526,6 +700,50 @@ where
     }
 }
 
+async fn _refresh<S>(
+    client: Client,
+    api: &str,
+    refresh_request: Message,
+    actor_did: &str,
+    atm_did: &str,
+    did_resolver: &DIDCacheClient,
+    secrets_resolver: &S,
+) -> AuthRefreshResponse
+where
+    S: SecretsResolver,
+{
+    let (refresh_msg, _) = refresh_request
+        .pack_encrypted(
+            atm_did,
+            Some(actor_did),
+            Some(actor_did),
+            did_resolver,
+            secrets_resolver,
+            &PackEncryptedOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    let res = client
+        .post(format!("{}/authenticate/refresh", api))
+        .header("Content-Type", "application/json")
+        .body(refresh_msg)
+        .send()
+        .await
+        .unwrap();
+
+    let status = res.status();
+    let body = res.text().await.unwrap();
+    assert!(status.is_success(), "Received status code: {}", status);
+
+    let body = serde_json::from_str::<SuccessResponse<AuthRefreshResponse>>(&body).unwrap();
+    if let Some(tokens) = body.data {
+        tokens
+    } else {
+        panic!("No tokens received from ATM");
+    }
+}
+
 async fn _send_inbound_message(
     client: Client,
     tokens: AuthorizationResponse,
@@ -33,6 +33,22 @@ pub fn create_auth_challenge_response(
     .finalize()
 }
 
+#[allow(dead_code)]
+pub fn create_refresh_message(refresh_token: &str, actor_did: &str, atm_did: &str) -> Message {
+    let now = _get_time_now();
+
+    Message::build(
+        Uuid::new_v4().into(),
+        "https://affinidi.com/atm/1.0/authenticate/refresh".to_owned(),
+        json!({"refresh_token": refresh_token}),
+    )
+    .to(atm_did.to_owned())
+    .from(actor_did.to_owned())
+    .created_time(now)
+    .expires_time(now + 60)
+    .finalize()
+}
+
 #[allow(dead_code)]
 pub async fn build_ping_message<S>(
     to_did: &str,
@@ -10,7 +10,7 @@ use tracing::{Instrument, Level, debug, error, span};
 use uuid::Uuid;
 
 use crate::{
-    SharedState,
+    ATM, SharedState,
     errors::ATMError,
     messages::{
         AuthenticationChallenge, AuthorizationResponse, GenericDataStruct, SuccessResponse,
@@ -25,6 +25,14 @@ pub struct AuthRefreshResponse {
 }
 impl GenericDataStruct for AuthRefreshResponse {}
 
+/// Response from POST /authenticate/link
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct AuthLinkResponse {
+    /// All DIDs linked to the session (excluding the session DID)
+    pub linked_dids: Vec<String>,
+}
+impl GenericDataStruct for AuthLinkResponse {}
+
 impl ATMProfile {
     /// Authenticate the SDK against Affinidi Trusted Messaging
     ///
@@ -111,6 +119,7 @@ impl ATMProfile {
                 &shared_state.tdk_common.client,
                 &[&mediator_endpoint, "/authenticate/challenge"].concat(),
                 &format!("{{\"did\": \"{}\"}}", profile_did).to_string(),
+                None,
             )
             .await?;
 
@@ -154,6 +163,7 @@ impl ATMProfile {
                 &shared_state.tdk_common.client,
                 &[&mediator_endpoint, "/authenticate"].concat(),
                 &auth_msg,
+                None,
             )
             .await?;
 
@@ -187,7 +197,18 @@ impl ATMProfile {
         &self,
         body: &AuthenticationChallenge,
     ) -> Result<Message, ATMError> {
-        let (profile_did, mediator_did) = self.dids()?;
+        let (profile_did, _) = self.dids()?;
+        self._create_auth_challenge_response_from(profile_did, body)
+    }
+
+    /// Same as `_create_auth_challenge_response()` but sent from `from_did`
+    /// Used when linking additional DIDs to the session
+    fn _create_auth_challenge_response_from(
+        &self,
+        from_did: &str,
+        body: &AuthenticationChallenge,
+    ) -> Result<Message, ATMError> {
+        let (_, mediator_did) = self.dids()?;
         let now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap()
@@ -198,7 +219,7 @@ impl ATMProfile {
             json!(body),
         )
         .to(mediator_did.to_owned())
-        .from(profile_did.to_owned())
+        .from(from_did.to_owned())
         .created_time(now)
         .expires_time(now + 60)
         .finalize())
@@ -208,12 +229,12 @@ impl ATMProfile {
     /// # Arguments
     ///   * `refresh_token` - The refresh token to be used
     /// # Returns
-    /// A packed DIDComm message to be sent
-    async fn _create_refresh_request(
+    /// The message ID and the packed DIDComm message to be sent
+    pub(crate) async fn _create_refresh_request(
         &self,
         refresh_token: &str,
         shared_state: &Arc<SharedState>,
-    ) -> Result<String, ATMError> {
+    ) -> Result<(String, String), ATMError> {
         let (profile_did, mediator_did) = self.dids()?;
         let now = SystemTime::now()
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -230,6 +251,7 @@ impl ATMProfile {
         .created_time(now)
         .expires_time(now + 60)
         .finalize();
+        let msg_id = refresh_message.id.clone();
 
         match refresh_message
             .pack_encrypted(
@@ -242,7 +264,7 @@ impl ATMProfile {
             )
             .await
         {
-            Ok((refresh_msg, _)) => Ok(refresh_msg),
+            Ok((refresh_msg, _)) => Ok((msg_id, refresh_msg)),
             Err(err) => Err(ATMError::MsgSendError(format!(
                 "Couldn't pack authentication refresh message: {:?}",
                 err
@@ -284,13 +306,14 @@ impl ATMProfile {
                     ));
                 };
 
-                let refresh_msg = self
+                let (_, refresh_msg) = self
                     ._create_refresh_request(&tokens.refresh_token, shared_state)
                     .await?;
                 let new_tokens = _http_post::<AuthRefreshResponse>(
                     &shared_state.tdk_common.client,
                     &[&mediator_endpoint, "/authenticate/refresh"].concat(),
                     &refresh_msg,
+                    None,
                 )
                 .await?;
 
@@ -317,17 +340,90 @@ impl ATMProfile {
     }
 }
 
+impl ATM {
+    /// Links an additional DID to the profile's authenticated session
+    /// Once linked, the session can fetch, list, delete, pickup and live stream messages for `did`
+    /// - `did` - The DID to link, its secrets must be loaded in the secrets resolver
+    /// # Returns
+    /// All DIDs linked to the session (excluding the profile DID)
+    pub async fn link_did(
+        &self,
+        profile: &Arc<ATMProfile>,
+        did: &str,
+    ) -> Result<Vec<String>, ATMError> {
+        let _span = span!(Level::DEBUG, "link_did", did = did);
+        async move {
+            let (_, mediator_did) = profile.dids()?;
+            let Some(mediator_endpoint) = profile.get_mediator_rest_endpoint() else {
+                return Err(ATMError::AuthenticationError(
+                    "there is no mediation REST endpoint".to_string(),
+                ));
+            };
+
+            let tokens = profile.authenticate(&self.inner).await?;
+
+            // Step 1. Get the link challenge for the additional DID
+            let challenge = _http_post::<AuthenticationChallenge>(
+                &self.inner.tdk_common.client,
+                &[&mediator_endpoint, "/authenticate/link/challenge"].concat(),
+                &json!({"did": did}).to_string(),
+                Some(&tokens.access_token),
+            )
+            .await?
+            .data
+            .ok_or_else(|| {
+                ATMError::AuthenticationError("No link challenge received from ATM".to_owned())
+            })?;
+
+            // Step 2. Respond to the challenge from the additional DID
+            let (link_msg, _) = profile
+                ._create_auth_challenge_response_from(did, &challenge)?
+                .pack_encrypted(
+                    mediator_did,
+                    Some(did),
+                    Some(did),
+                    &self.inner.tdk_common.did_resolver,
+                    &self.inner.tdk_common.secrets_resolver,
+                    &PackEncryptedOptions::default(),
+                )
+                .await
+                .map_err(|e| {
+                    ATMError::MsgSendError(format!("Couldn't pack link response message: {:?}", e))
+                })?;
+
+            let response = _http_post::<AuthLinkResponse>(
+                &self.inner.tdk_common.client,
+                &[&mediator_endpoint, "/authenticate/link"].concat(),
+                &link_msg,
+                Some(&tokens.access_token),
+            )
+            .await?;
+
+            debug!("DID linked to session");
+            Ok(response.data.map(|r| r.linked_dids).unwrap_or_default())
+        }
+        .instrument(_span)
+        .await
+    }
+}
+
+/// POSTs to the mediator, `access_token` is sent as a Bearer token when provided
 async fn _http_post<T: GenericDataStruct>(
     client: &Client,
     url: &str,
     body: &str,
+    access_token: Option<&str>,
 ) -> Result<SuccessResponse<T>, ATMError> {
     debug!("POSTing to {}", url);
     debug!("Body: {}", body);
-    let response = client
+    let mut request = client
         .post(url)
         .header("Content-Type", "application/json")
-        .body(body.to_string())
+        .body(body.to_string());
+    if let Some(access_token) = access_token {
+        request = request.header("Authorization", format!("Bearer {}", access_token));
+    }
+    let response = request
         .send()
         .await
         .map_err(|e| ATMError::TransportError(format!("HTTP POST failed ({}): {:?}", url, e)))?;
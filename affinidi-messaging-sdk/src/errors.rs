@@ -1,4 +1,5 @@
 use affinidi_messaging_didcomm::Message;
+#[cfg(not(target_arch = "wasm32"))]
 use affinidi_tdk_common::errors::TDKError;
 use thiserror::Error;
 
@@ -37,10 +38,17 @@ pub enum ATMError {
     ProblemReport(String, String, String),
     #[error("DIDComm Mediator error: code({0}), message: ({1})")]
     MediatorError(String, String),
+    #[error("Mediator limit exceeded: {0}")]
+    LimitError(String),
+    #[error("Mediator couldn't parse the message: {0}")]
+    ParseError(String),
 }
 
 impl ATMError {
     /// Creates an ATM Error from a DIDComm Problem Report Error Message
+    /// Mediator ACL, authentication, limit and parse errors (`e.m.acl-denied`, `e.m.authentication`,
+    /// `e.m.unauthorized`, `e.m.service-limit`, `e.m.rate-limited`, `e.m.parse`) are returned as
+    /// their matching ATMError, everything else as a ProblemReport
     pub fn from_problem_report(message: &Message) -> Self {
         if let Ok(MessageType::ProblemReport) = message.type_.parse::<MessageType>() {
             let body: ProblemReport = match serde_json::from_value(message.body.clone()) {
@@ -55,11 +63,17 @@ impl ATMError {
 
             let comment = body.interpolation();
 
-            ATMError::ProblemReport(
-                body.code,
-                comment,
-                body.escalate_to.unwrap_or("NONE".into()),
-            )
+            match body.code.as_str() {
+                "e.m.acl-denied" => ATMError::ACLDenied(comment),
+                "e.m.authentication" | "e.m.unauthorized" => ATMError::AuthenticationError(comment),
+                "e.m.service-limit" | "e.m.rate-limited" => ATMError::LimitError(comment),
+                "e.m.parse" => ATMError::ParseError(comment),
+                _ => ATMError::ProblemReport(
+                    body.code,
+                    comment,
+                    body.escalate_to.unwrap_or("NONE".into()),
+                ),
+            }
         } else {
             // Handling for non-Problem Report messages
             ATMError::SDKError(format!(
@@ -70,12 +84,14 @@ impl ATMError {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<ATMError> for TDKError {
     fn from(err: ATMError) -> Self {
         TDKError::ATM(err.to_string())
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<TDKError> for ATMError {
     fn from(err: TDKError) -> Self {
         ATMError::TDKError(err.to_string())
@@ -112,6 +128,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_from_problem_report_mediator_error() {
+        let message = Message::build(
+            "example-1".into(),
+            "https://didcomm.org/report-problem/2.0/problem-report".into(),
+            serde_json::json!({
+                "code": "e.m.acl-denied",
+                "comment": "{1}",
+                "args": ["ACL Denied: Message blocked due to ACL"]
+            }),
+        )
+        .finalize();
+
+        match ATMError::from_problem_report(&message) {
+            ATMError::ACLDenied(comment) => {
+                assert_eq!(comment, "ACL Denied: Message blocked due to ACL");
+            }
+            _ => panic!("Expected ACLDenied error"),
+        }
+    }
+
     #[test]
     fn test_from_problem_report_wrong_type() {
         let message = Message::build(
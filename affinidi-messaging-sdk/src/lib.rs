@@ -1,34 +1,52 @@
+#[cfg(not(target_arch = "wasm32"))]
 use affinidi_messaging_didcomm::{Message, UnpackMetadata};
+#[cfg(not(target_arch = "wasm32"))]
 use affinidi_tdk_common::TDKSharedState;
+#[cfg(not(target_arch = "wasm32"))]
 use config::ATMConfig;
+#[cfg(not(target_arch = "wasm32"))]
 use delete_handler::DeletionHandlerCommands;
+#[cfg(not(target_arch = "wasm32"))]
 use errors::ATMError;
+#[cfg(not(target_arch = "wasm32"))]
 use profiles::Profiles;
+#[cfg(not(target_arch = "wasm32"))]
 use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::{
     Mutex, RwLock, broadcast,
     mpsc::{self, Receiver, Sender},
 };
 
+#[cfg(not(target_arch = "wasm32"))]
 use tracing::debug;
+#[cfg(not(target_arch = "wasm32"))]
 use transports::websockets::ws_handler::{WsHandlerCommands, WsHandlerMode};
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod authentication;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod delete_handler;
 pub mod errors;
 pub mod messages;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod profiles;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod protocols;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod public;
 pub mod transports;
 
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Clone)]
 pub struct ATM {
     pub(crate) inner: Arc<SharedState>,
 }
 
 /// Private SharedState struct for the ATM to be used across tasks
+#[cfg(not(target_arch = "wasm32"))]
 pub(crate) struct SharedState {
     pub(crate) config: ATMConfig,
     pub(crate) tdk_common: TDKSharedState,
@@ -58,6 +76,7 @@ pub(crate) struct SharedState {
 ///
 /// let response = atm.ping("did:example:123", true);
 /// ```
+#[cfg(not(target_arch = "wasm32"))]
 impl ATM {
     /// Creates a new instance of the SDK with a given configuration
     /// You need to add at least the DID Method for the SDK DID to work
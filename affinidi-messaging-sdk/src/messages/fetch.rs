@@ -21,6 +21,9 @@ pub struct FetchOptions {
     pub start_id: Option<String>,
     /// Delete policy for messages after fetching. Default: DoNotDelete
     pub delete_policy: FetchDeletePolicy,
+    /// Fetch messages for a DID linked to the session instead of the profile DID. Default: None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient_did: Option<String>,
 }
 
 impl Default for FetchOptions {
@@ -29,6 +32,7 @@ impl Default for FetchOptions {
             limit: 10,
             start_id: None,
             delete_policy: FetchDeletePolicy::DoNotDelete,
+            recipient_did: None,
         }
     }
 }
@@ -42,6 +46,7 @@ impl ATM {
     /// * `limit`         - The maximum number of messages to fetch (default: 10, minimum: 1, maximum: 100)
     /// * `start_id`      - The message_id to start fetching from (default: Starts with oldest message)
     /// * `delete_policy` - Delete policy for messages after fetching (default: DoNotDelete)
+    /// * `recipient_did` - Fetch for a DID linked to the session with `link_did()` (default: profile DID)
     ///
     /// Calling fetch with no start_id and default delete_policy will result in the same messages being retrieved again and again
     ///
@@ -5,20 +5,31 @@ use std::str::FromStr;
 use crate::errors::ATMError;
 
 pub enum MessageType {
-    AffinidiAuthenticate,            // Affinidi Messaging Authentication Response
-    AffinidiAuthenticateRefresh,     // Affinidi Messaging Authentication Refresh
-    ForwardRequest,                  // DidComm Routing 2.0 Forward Request
-    MediatorAdministration,          // Mediator Administration Protocol
-    MediatorAccountManagement,       // Mediator Account Management Protocol
-    MediatorACLManagement,           // Mediator Global ACL Management Protocol
-    MessagePickupStatusRequest,      // Message Pickup 3.0 Status Request
-    MessagePickupStatusResponse,     // Message Pickup 3.0 Status Request
-    MessagePickupDeliveryRequest,    // Message Pickup 3.0 Delivery Request
-    MessagePickupMessagesReceived,   // Message Pickup 3.0 Messages Received (ok to delete)
+    AffinidiAuthenticate,        // Affinidi Messaging Authentication Response
+    AffinidiAuthenticateRefresh, // Affinidi Messaging Authentication Refresh
+    AffinidiAuthenticateRefreshResponse, // Affinidi Messaging Authentication Refresh Response
+    CoordinateMediationRequest,  // Coordinate Mediation 2.0 Mediate Request
+    CoordinateMediationGrant,    // Coordinate Mediation 2.0 Mediate Grant
+    CoordinateMediationDeny,     // Coordinate Mediation 2.0 Mediate Deny
+    CoordinateMediationKeylistUpdate, // Coordinate Mediation 2.0 Keylist Update
+    CoordinateMediationKeylistUpdateResponse, // Coordinate Mediation 2.0 Keylist Update Response
+    CoordinateMediationKeylistQuery, // Coordinate Mediation 2.0 Keylist Query
+    CoordinateMediationKeylist,  // Coordinate Mediation 2.0 Keylist
+    DiscoverFeaturesQueries,     // Discover Features 2.0 Queries
+    DiscoverFeaturesDisclose,    // Discover Features 2.0 Disclose
+    ForwardRequest,              // DidComm Routing 2.0 Forward Request
+    MediatorAdministration,      // Mediator Administration Protocol
+    MediatorAccountManagement,   // Mediator Account Management Protocol
+    MediatorACLManagement,       // Mediator Global ACL Management Protocol
+    MediatorPushNotifications,   // Mediator Push Notifications Protocol
+    MessagePickupStatusRequest,  // Message Pickup 3.0 Status Request
+    MessagePickupStatusResponse, // Message Pickup 3.0 Status Request
+    MessagePickupDeliveryRequest, // Message Pickup 3.0 Delivery Request
+    MessagePickupMessagesReceived, // Message Pickup 3.0 Messages Received (ok to delete)
     MessagePickupLiveDeliveryChange, // Message Pickup 3.0 Live-delivery-change (Streaming enabled)
-    ProblemReport,                   // Problem Report Protocol
-    TrustPing,                       // Trust Ping Protocol
-    Other(String),                   // Other message type
+    ProblemReport,               // Problem Report Protocol
+    TrustPing,                   // Trust Ping Protocol
+    Other(String),               // Other message type
 }
 
 impl FromStr for MessageType {
@@ -31,11 +42,44 @@ impl FromStr for MessageType {
             "https://affinidi.com/atm/1.0/authenticate/refresh" => {
                 Ok(Self::AffinidiAuthenticateRefresh)
             }
+            "https://affinidi.com/atm/1.0/authenticate/refresh/response" => {
+                Ok(Self::AffinidiAuthenticateRefreshResponse)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/mediate-request" => {
+                Ok(Self::CoordinateMediationRequest)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/mediate-grant" => {
+                Ok(Self::CoordinateMediationGrant)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/mediate-deny" => {
+                Ok(Self::CoordinateMediationDeny)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/keylist-update" => {
+                Ok(Self::CoordinateMediationKeylistUpdate)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/keylist-update-response" => {
+                Ok(Self::CoordinateMediationKeylistUpdateResponse)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/keylist-query" => {
+                Ok(Self::CoordinateMediationKeylistQuery)
+            }
+            "https://didcomm.org/coordinate-mediation/2.0/keylist" => {
+                Ok(Self::CoordinateMediationKeylist)
+            }
+            "https://didcomm.org/discover-features/2.0/queries" => {
+                Ok(Self::DiscoverFeaturesQueries)
+            }
+            "https://didcomm.org/discover-features/2.0/disclose" => {
+                Ok(Self::DiscoverFeaturesDisclose)
+            }
             "https://didcomm.org/mediator/1.0/admin-management" => Ok(Self::MediatorAdministration),
             "https://didcomm.org/mediator/1.0/account-management" => {
                 Ok(Self::MediatorAccountManagement)
             }
             "https://didcomm.org/mediator/1.0/acl-management" => Ok(Self::MediatorACLManagement),
+            "https://didcomm.org/mediator/1.0/push-notifications" => {
+                Ok(Self::MediatorPushNotifications)
+            }
             "https://didcomm.org/messagepickup/3.0/status-request" => {
                 Ok(Self::MessagePickupStatusRequest)
             }
@@ -1,18 +1,67 @@
 use super::{Folder, MessageList};
 use crate::{ATM, errors::ATMError, messages::SuccessResponse, profiles::ATMProfile};
+use serde::{Deserialize, Serialize};
 use sha256::digest;
 use std::sync::Arc;
 use tracing::{Instrument, Level, debug, span};
 
+/// list_messages() options, all fields are optional and sent as query parameters
+///
+/// Stream IDs (`<milliseconds>-<sequence>`) are time based, a bare `<milliseconds>` can be used
+/// wherever a stream ID is expected.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct ListMessagesOptions {
+    /// Only messages sent from this DID hash. Default: None (all senders)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_did_hash: Option<String>,
+    /// Only messages sent to this DID hash. Default: None (all recipients)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_did_hash: Option<String>,
+    /// Only messages stored at or after this stream ID. Default: None (oldest message)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    /// Only messages stored at or before this stream ID. Default: None (newest message)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<String>,
+    /// Continue after this stream ID, use the `next_cursor` of the previous list. Default: None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Only messages of at most this many bytes. Default: None (any size)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+    /// The maximum number of messages to list, capped by the mediator. Default: None (mediator limit)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
 impl ATM {
     /// Returns a list of messages that are stored in the ATM
     /// # Parameters
     /// - `did`: The DID to list messages for
     /// - `folder`: The folder to list messages from
+    /// - `options`: Filters and pagination, see [ListMessagesOptions]
+    ///
+    /// If more messages match, the last element of the list has `next_cursor` set.
+    /// Pass it as `options.cursor` to list the next page.
+    ///
+    /// The mediator scans a bounded number of messages per request, so a page can be short and
+    /// still have `next_cursor` set. An empty list means nothing else matches.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Everything (up to the mediator limit)
+    /// let list = atm.list_messages(&profile, Folder::Inbox, &ListMessagesOptions::default()).await?;
+    ///
+    /// // Messages from a single sender, 20 at a time
+    /// let mut options = ListMessagesOptions {from_did_hash: Some(digest(sender_did)), limit: Some(20), ..Default::default()};
+    /// let list = atm.list_messages(&profile, Folder::Inbox, &options).await?;
+    /// options.cursor = list.last().and_then(|element| element.next_cursor.clone());
+    /// ```
     pub async fn list_messages(
         &self,
         profile: &Arc<ATMProfile>,
         folder: Folder,
+        options: &ListMessagesOptions,
     ) -> Result<MessageList, ATMError> {
         let _span = span!(Level::DEBUG, "list_messages", folder = folder.to_string());
         async move {
@@ -38,6 +87,7 @@ impl ATM {
                     digest(profile_did),
                     folder,
                 ))
+                .query(options)
                 .header("Content-Type", "application/json")
                 .header("Authorization", format!("Bearer {}", tokens.access_token))
                 .send()
@@ -2,14 +2,20 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
+#[cfg(not(target_arch = "wasm32"))]
 pub mod delete;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod fetch;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod get;
 pub mod known;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod list;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod pack;
 pub mod problem_report;
 pub mod sending;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod unpack;
 
 pub trait MessageDelete<T> {
@@ -69,6 +75,8 @@ impl GenericDataStruct for DeleteMessageRequest {}
 /// - to_address    : Address the message was sent to
 /// - from_address  : Address the message was sent from (if applicable)
 /// - msg           : The message itself
+/// - signed_by     : Key ID of the verified signer of a forwarded attachment (if applicable)
+/// - next_cursor   : Set on the last element of a list when more messages match (see [list::ListMessagesOptions])
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(default)]
 pub struct MessageListElement {
@@ -85,6 +93,10 @@ pub struct MessageListElement {
     pub from_address: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub msg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signed_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 impl GenericDataStruct for MessageListElement {}
 
@@ -0,0 +1,315 @@
+//! DIDComm Coordinate Mediation 2.0 protocol
+//! <https://didcomm.org/coordinate-mediation/2.0/>
+//!
+//! Lets standard DIDComm agents request mediation from a mediator and manage the recipient DIDs
+//! (keylist) that the mediator will accept forwarded messages for.
+
+use affinidi_messaging_didcomm::{Message, PackEncryptedOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{sync::Arc, time::SystemTime};
+use tracing::{Instrument, Level, debug, span};
+use uuid::Uuid;
+
+use crate::{
+    ATM, errors::ATMError, messages::known::MessageType, profiles::ATMProfile,
+    transports::SendMessageResponse,
+};
+
+pub const MEDIATE_REQUEST: &str = "https://didcomm.org/coordinate-mediation/2.0/mediate-request";
+pub const MEDIATE_GRANT: &str = "https://didcomm.org/coordinate-mediation/2.0/mediate-grant";
+pub const MEDIATE_DENY: &str = "https://didcomm.org/coordinate-mediation/2.0/mediate-deny";
+pub const KEYLIST_UPDATE: &str = "https://didcomm.org/coordinate-mediation/2.0/keylist-update";
+pub const KEYLIST_UPDATE_RESPONSE: &str =
+    "https://didcomm.org/coordinate-mediation/2.0/keylist-update-response";
+pub const KEYLIST_QUERY: &str = "https://didcomm.org/coordinate-mediation/2.0/keylist-query";
+pub const KEYLIST: &str = "https://didcomm.org/coordinate-mediation/2.0/keylist";
+
+/// Body of a mediate-grant message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MediateGrant {
+    /// DIDs of the mediator that senders should wrap forwarded messages for
+    pub routing_did: Vec<String>,
+}
+
+/// Whether a recipient DID is being added to or removed from the keylist
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeylistAction {
+    Add,
+    Remove,
+}
+
+/// A single change in a keylist-update message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeylistUpdateItem {
+    pub recipient_did: String,
+    pub action: KeylistAction,
+}
+
+/// Body of a keylist-update message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeylistUpdate {
+    pub updates: Vec<KeylistUpdateItem>,
+}
+
+/// Outcome of a single keylist change
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeylistUpdateResult {
+    ClientError,
+    ServerError,
+    NoChange,
+    Success,
+}
+
+/// A single result in a keylist-update-response message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeylistUpdated {
+    pub recipient_did: String,
+    pub action: KeylistAction,
+    pub result: KeylistUpdateResult,
+}
+
+/// Body of a keylist-update-response message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeylistUpdateResponse {
+    pub updated: Vec<KeylistUpdated>,
+}
+
+/// Pagination requested in a keylist-query message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeylistQueryPaginate {
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Body of a keylist-query message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeylistQuery {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paginate: Option<KeylistQueryPaginate>,
+}
+
+/// A recipient DID in a keylist message
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct KeylistKey {
+    pub recipient_did: String,
+}
+
+/// Pagination details of a keylist message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct KeylistPagination {
+    /// Number of keys in this message
+    pub count: usize,
+    pub offset: usize,
+    /// Number of keys after this page
+    pub remaining: usize,
+}
+
+/// Body of a keylist message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Keylist {
+    pub keys: Vec<KeylistKey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<KeylistPagination>,
+}
+
+#[derive(Default)]
+pub struct CoordinateMediation {}
+
+impl CoordinateMediation {
+    /// Requests mediation from the profile's mediator
+    /// # Returns
+    /// The mediate-grant (routing DIDs to use), or None if the mediator denied mediation
+    pub async fn mediate_request(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+    ) -> Result<Option<MediateGrant>, ATMError> {
+        let _span = span!(Level::DEBUG, "mediate_request");
+
+        async move {
+            debug!("Requesting mediation from mediator");
+
+            let message = self._send(atm, profile, MEDIATE_REQUEST, json!({})).await?;
+
+            match message.type_.as_str() {
+                MEDIATE_GRANT => Ok(Some(self._parse(&message, "mediate-grant")?)),
+                MEDIATE_DENY => Ok(None),
+                _ => Err(_unexpected(&message)),
+            }
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Adds and removes recipient DIDs from the keylist on the mediator
+    /// - `updates` - The recipient DIDs and whether to add or remove each of them
+    /// # Returns
+    /// The result of each update
+    pub async fn keylist_update(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        updates: Vec<KeylistUpdateItem>,
+    ) -> Result<KeylistUpdateResponse, ATMError> {
+        let _span = span!(Level::DEBUG, "keylist_update");
+
+        async move {
+            debug!("Sending {} keylist updates to mediator", updates.len());
+
+            let message = self
+                ._send(
+                    atm,
+                    profile,
+                    KEYLIST_UPDATE,
+                    json!(KeylistUpdate { updates }),
+                )
+                .await?;
+
+            if message.type_ == KEYLIST_UPDATE_RESPONSE {
+                self._parse(&message, "keylist-update-response")
+            } else {
+                Err(_unexpected(&message))
+            }
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Retrieves the recipient DIDs registered with the mediator
+    /// - `paginate` - Optional (limit, offset) of the page to return
+    pub async fn keylist_query(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        paginate: Option<(usize, usize)>,
+    ) -> Result<Keylist, ATMError> {
+        let _span = span!(Level::DEBUG, "keylist_query");
+
+        async move {
+            debug!("Querying keylist from mediator");
+
+            let query = KeylistQuery {
+                paginate: paginate.map(|(limit, offset)| KeylistQueryPaginate { limit, offset }),
+            };
+            let message = self
+                ._send(atm, profile, KEYLIST_QUERY, json!(query))
+                .await?;
+
+            if message.type_ == KEYLIST {
+                self._parse(&message, "keylist")
+            } else {
+                Err(_unexpected(&message))
+            }
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Sends a message of `type_` to the mediator and waits for the response
+    async fn _send(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        type_: &str,
+        body: Value,
+    ) -> Result<Message, ATMError> {
+        let (profile_did, mediator_did) = profile.dids()?;
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let msg = Message::build(Uuid::new_v4().into(), type_.to_owned(), body)
+            .to(mediator_did.into())
+            .from(profile_did.into())
+            .created_time(now)
+            .expires_time(now + 10)
+            .finalize();
+
+        let msg_id = msg.id.clone();
+
+        // Pack the message
+        let (msg, _) = msg
+            .pack_encrypted(
+                mediator_did,
+                Some(profile_did),
+                Some(profile_did),
+                &atm.inner.tdk_common.did_resolver,
+                &atm.inner.tdk_common.secrets_resolver,
+                &PackEncryptedOptions::default(),
+            )
+            .await
+            .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+        match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+            SendMessageResponse::Message(message) => {
+                if let Ok(MessageType::ProblemReport) = message.type_.parse::<MessageType>() {
+                    Err(ATMError::from_problem_report(&message))
+                } else {
+                    Ok(message)
+                }
+            }
+            _ => Err(ATMError::MsgReceiveError(
+                "No response from mediator".to_owned(),
+            )),
+        }
+    }
+
+    /// Parses the body of a response from the mediator
+    fn _parse<T: for<'de> Deserialize<'de>>(
+        &self,
+        message: &Message,
+        name: &str,
+    ) -> Result<T, ATMError> {
+        serde_json::from_value(message.body.clone()).map_err(|err| {
+            ATMError::MsgReceiveError(format!(
+                "Coordinate Mediation {} response could not be parsed. Reason: {}",
+                name, err
+            ))
+        })
+    }
+}
+
+fn _unexpected(message: &Message) -> ATMError {
+    ATMError::MsgReceiveError(format!(
+        "Unexpected response type ({}) from mediator",
+        message.type_
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keylist_wire_format() {
+        let update: KeylistUpdate = serde_json::from_value(json!({
+            "updates": [
+                {"recipient_did": "did:example:alice", "action": "add"},
+                {"recipient_did": "did:example:bob", "action": "remove"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(update.updates[0].action, KeylistAction::Add);
+        assert_eq!(update.updates[1].action, KeylistAction::Remove);
+
+        let response = KeylistUpdateResponse {
+            updated: vec![KeylistUpdated {
+                recipient_did: "did:example:alice".into(),
+                action: KeylistAction::Add,
+                result: KeylistUpdateResult::NoChange,
+            }],
+        };
+        assert_eq!(
+            json!(response),
+            json!({"updated": [{"recipient_did": "did:example:alice", "action": "add", "result": "no_change"}]})
+        );
+
+        let query: KeylistQuery = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(query.paginate, None);
+    }
+}
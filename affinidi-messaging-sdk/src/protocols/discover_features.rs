@@ -0,0 +1,225 @@
+//! DIDComm Discover Features 2.0 protocol
+//! <https://didcomm.org/discover-features/2.0/>
+//!
+//! Asks the mediator which protocols, goal codes and ACL modes it supports for this profile.
+//! Disclosures are cached per profile, as they only change when the mediator is reconfigured.
+
+use affinidi_messaging_didcomm::{Message, PackEncryptedOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+use tokio::sync::RwLock;
+use tracing::{Instrument, Level, debug, span};
+use uuid::Uuid;
+
+use crate::{
+    ATM, errors::ATMError, messages::known::MessageType, profiles::ATMProfile,
+    transports::SendMessageResponse,
+};
+
+pub const QUERIES: &str = "https://didcomm.org/discover-features/2.0/queries";
+pub const DISCLOSE: &str = "https://didcomm.org/discover-features/2.0/disclose";
+
+/// Feature types disclosed by the mediator
+pub const FEATURE_PROTOCOL: &str = "protocol";
+pub const FEATURE_GOAL_CODE: &str = "goal-code";
+/// Mediator ACL mode (`explicit_allow` or `explicit_deny`)
+pub const FEATURE_ACL_MODE: &str = "acl-mode";
+
+/// A single query in a queries message
+/// `match` may contain `*` wildcards (e.g. `https://didcomm.org/messagepickup/3.*`)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FeatureQuery {
+    #[serde(rename = "feature-type")]
+    pub feature_type: String,
+    #[serde(rename = "match")]
+    pub match_: String,
+}
+
+/// Body of a queries message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Queries {
+    pub queries: Vec<FeatureQuery>,
+}
+
+/// A single feature disclosed by the mediator
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct Disclosure {
+    #[serde(rename = "feature-type")]
+    pub feature_type: String,
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+/// Body of a disclose message
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Disclose {
+    pub disclosures: Vec<Disclosure>,
+}
+
+impl Disclose {
+    /// Returns true if a feature of `feature_type` was disclosed whose id starts with `id`
+    /// e.g. `supports(FEATURE_PROTOCOL, "https://didcomm.org/messagepickup/3.")`
+    pub fn supports(&self, feature_type: &str, id: &str) -> bool {
+        self.disclosures
+            .iter()
+            .any(|d| d.feature_type == feature_type && d.id.starts_with(id))
+    }
+}
+
+#[derive(Default)]
+pub struct DiscoverFeatures {
+    /// Disclosures from the mediator, keyed by profile DID
+    cache: RwLock<HashMap<String, Disclose>>,
+}
+
+impl DiscoverFeatures {
+    /// Discovers all features the profile's mediator supports for this profile
+    /// - `refresh` - Ignore any cached disclosures and query the mediator again
+    pub async fn discover(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        refresh: bool,
+    ) -> Result<Disclose, ATMError> {
+        let _span = span!(Level::DEBUG, "discover_features");
+
+        async move {
+            let (profile_did, _) = profile.dids()?;
+
+            if !refresh {
+                if let Some(disclose) = self.cache.read().await.get(profile_did) {
+                    debug!("Using cached feature disclosures");
+                    return Ok(disclose.clone());
+                }
+            }
+
+            let queries = [FEATURE_PROTOCOL, FEATURE_GOAL_CODE, FEATURE_ACL_MODE]
+                .iter()
+                .map(|feature_type| FeatureQuery {
+                    feature_type: feature_type.to_string(),
+                    match_: "*".into(),
+                })
+                .collect();
+
+            let disclose = self.query(atm, profile, queries).await?;
+            self.cache
+                .write()
+                .await
+                .insert(profile_did.to_string(), disclose.clone());
+
+            Ok(disclose)
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Sends a queries message to the mediator, the result is not cached
+    /// - `queries` - Features to query for
+    pub async fn query(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        queries: Vec<FeatureQuery>,
+    ) -> Result<Disclose, ATMError> {
+        let _span = span!(Level::DEBUG, "query");
+
+        async move {
+            let (profile_did, mediator_did) = profile.dids()?;
+            debug!("Sending {} feature queries to mediator", queries.len());
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let msg = Message::build(
+                Uuid::new_v4().into(),
+                QUERIES.to_owned(),
+                json!(Queries { queries }),
+            )
+            .to(mediator_did.into())
+            .from(profile_did.into())
+            .created_time(now)
+            .expires_time(now + 10)
+            .finalize();
+
+            let msg_id = msg.id.clone();
+
+            // Pack the message
+            let (msg, _) = msg
+                .pack_encrypted(
+                    mediator_did,
+                    Some(profile_did),
+                    Some(profile_did),
+                    &atm.inner.tdk_common.did_resolver,
+                    &atm.inner.tdk_common.secrets_resolver,
+                    &PackEncryptedOptions::default(),
+                )
+                .await
+                .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+            let message = match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+                SendMessageResponse::Message(message) => message,
+                _ => {
+                    return Err(ATMError::MsgReceiveError(
+                        "No response from mediator".to_owned(),
+                    ));
+                }
+            };
+
+            match message.type_.parse::<MessageType>() {
+                Ok(MessageType::DiscoverFeaturesDisclose) => serde_json::from_value(message.body)
+                    .map_err(|err| {
+                        ATMError::MsgReceiveError(format!(
+                            "Discover Features disclose response could not be parsed. Reason: {}",
+                            err
+                        ))
+                    }),
+                Ok(MessageType::ProblemReport) => Err(ATMError::from_problem_report(&message)),
+                _ => Err(ATMError::MsgReceiveError(format!(
+                    "Unexpected response type ({}) from mediator",
+                    message.type_
+                ))),
+            }
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Removes the cached disclosures for a profile
+    pub async fn clear_cache(&self, profile: &Arc<ATMProfile>) {
+        self.cache.write().await.remove(&profile.inner.did);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wire_format() {
+        let query = Queries {
+            queries: vec![FeatureQuery {
+                feature_type: FEATURE_PROTOCOL.into(),
+                match_: "https://didcomm.org/messagepickup/3.*".into(),
+            }],
+        };
+        assert_eq!(
+            json!(query),
+            json!({"queries": [{"feature-type": "protocol", "match": "https://didcomm.org/messagepickup/3.*"}]})
+        );
+
+        let disclose: Disclose = serde_json::from_value(json!({
+            "disclosures": [
+                {"feature-type": "protocol", "id": "https://didcomm.org/messagepickup/3.0", "roles": ["mediator"]},
+                {"feature-type": "acl-mode", "id": "explicit_deny"}
+            ]
+        }))
+        .unwrap();
+        assert!(disclose.supports(FEATURE_PROTOCOL, "https://didcomm.org/messagepickup/3."));
+        assert!(disclose.supports(FEATURE_ACL_MODE, "explicit_deny"));
+        assert!(!disclose.supports(FEATURE_PROTOCOL, "https://didcomm.org/routing/2.0"));
+    }
+}
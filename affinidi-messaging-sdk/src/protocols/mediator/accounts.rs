@@ -34,7 +34,13 @@ pub enum MediatorAccountRequest {
         did_hash: String,
         send_queue_limit: Option<i32>,
         receive_queue_limit: Option<i32>,
+        #[serde(default)]
+        send_queue_bytes_limit: Option<i64>,
+        #[serde(default)]
+        receive_queue_bytes_limit: Option<i64>,
     },
+    #[serde(rename = "account_change_rate_limit")]
+    AccountChangeRateLimit { did_hash: String, rate_limit: i32 },
 }
 
 /// Different levels of accounts in the mediator
@@ -138,6 +144,14 @@ pub struct Account {
     /// Number of messages that can be in the queue for this account
     pub queue_send_limit: Option<i32>,
     pub queue_receive_limit: Option<i32>,
+    /// Number of bytes that can be in the queue for this account
+    #[serde(default)]
+    pub queue_send_bytes_limit: Option<i64>,
+    #[serde(default)]
+    pub queue_receive_bytes_limit: Option<i64>,
+    /// Requests per minute allowed for this account, overrides the mediator limits
+    #[serde(default)]
+    pub rate_limit: Option<i32>,
     pub send_queue_count: u32,
     pub send_queue_bytes: u64,
     pub receive_queue_count: u32,
@@ -153,6 +167,9 @@ impl Default for Account {
             access_list_count: 0,
             queue_send_limit: None,
             queue_receive_limit: None,
+            queue_send_bytes_limit: None,
+            queue_receive_bytes_limit: None,
+            rate_limit: None,
             send_queue_count: 0,
             send_queue_bytes: 0,
             receive_queue_count: 0,
@@ -167,10 +184,39 @@ pub struct MediatorAccountList {
     pub cursor: u32,
 }
 
+/// Summary of what was deleted when an account was removed
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct AccountRemoveResponse {
+    pub did_hash: String,
+    /// Messages removed from the inbox
+    pub inbox_messages: usize,
+    /// Undelivered messages sent by this DID that were removed (0 unless the outbox was purged)
+    pub outbox_messages: usize,
+    /// Pending forward tasks to or from this DID
+    pub forward_tasks: usize,
+    /// Delayed deliveries to or from this DID
+    pub scheduled_deliveries: usize,
+    /// OOB invitations created by this DID
+    pub oob_invites: usize,
+    /// Active sessions of this DID
+    pub sessions: usize,
+    /// Live streaming registrations of this DID
+    pub streaming_sessions: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountChangeQueueLimitsResponse {
     pub send_queue_limit: Option<i32>,
     pub receive_queue_limit: Option<i32>,
+    #[serde(default)]
+    pub send_queue_bytes_limit: Option<i64>,
+    #[serde(default)]
+    pub receive_queue_bytes_limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountChangeRateLimitResponse {
+    pub rate_limit: Option<i32>,
 }
 
 impl Mediator {
@@ -325,12 +371,14 @@ impl Mediator {
     /// - `atm` - The ATM client to use
     /// - `profile` - The profile to use
     /// - `did_hash` - The DID hash to remove (Defaults to the profile DID hash if not provided)
+    /// # Returns
+    /// A summary of the records that were deleted
     pub async fn account_remove(
         &self,
         atm: &ATM,
         profile: &Arc<ATMProfile>,
         did_hash: Option<String>,
-    ) -> Result<bool, ATMError> {
+    ) -> Result<AccountRemoveResponse, ATMError> {
         let _span = span!(Level::DEBUG, "account_remove");
 
         async move {
@@ -384,7 +432,10 @@ impl Mediator {
     }
 
     /// Parses the response from the mediator for account_remove
-    fn _parse_account_remove_response(&self, message: &Message) -> Result<bool, ATMError> {
+    fn _parse_account_remove_response(
+        &self,
+        message: &Message,
+    ) -> Result<AccountRemoveResponse, ATMError> {
         serde_json::from_value(message.body.clone()).map_err(|err| {
             ATMError::MsgReceiveError(format!(
                 "Mediator Account Remove response could not be parsed. Reason: {}",
@@ -643,4 +694,162 @@ impl Mediator {
             ))
         })
     }
+
+    /// Change the queue quotas in bytes for a DID
+    /// - `atm` - The ATM client to use
+    /// - `profile` - The profile to use
+    /// - `did_hash` - The DID hash to change the quotas for
+    /// - `send_queue_bytes_limit`
+    /// - `receive_queue_bytes_limit`
+    ///
+    /// NOTE: queue_bytes_limit values
+    ///       - None: No change
+    ///       - Some(-1): Unlimited
+    ///       - Some(-2): Reset to soft_limit
+    ///       - Some(n): Set to n bytes
+    ///
+    /// Byte quotas apply alongside the message count limits, whichever is reached first
+    ///
+    /// # Returns
+    /// The limits now set on the account
+    pub async fn account_change_queue_bytes_limits(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        did_hash: &str,
+        send_queue_bytes_limit: Option<i64>,
+        receive_queue_bytes_limit: Option<i64>,
+    ) -> Result<AccountChangeQueueLimitsResponse, ATMError> {
+        let _span = span!(Level::DEBUG, "account_change_queue_bytes_limits");
+
+        async move {
+            debug!(
+                "Changing account ({}) queue_bytes_limits to send({:?}) receive({:?}).",
+                did_hash, send_queue_bytes_limit, receive_queue_bytes_limit
+            );
+
+            let (profile_did, mediator_did) = profile.dids()?;
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let msg = Message::build(
+                Uuid::new_v4().into(),
+                "https://didcomm.org/mediator/1.0/account-management".to_owned(),
+                json!({"account_change_queue_limits": {"did_hash": did_hash, "send_queue_limit": null, "receive_queue_limit": null, "send_queue_bytes_limit": send_queue_bytes_limit, "receive_queue_bytes_limit": receive_queue_bytes_limit}}),
+            )
+            .to(mediator_did.into())
+            .from(profile_did.into())
+            .created_time(now)
+            .expires_time(now + 10)
+            .finalize();
+
+            let msg_id = msg.id.clone();
+
+            // Pack the message
+            let (msg, _) = msg
+                .pack_encrypted(
+                    mediator_did,
+                    Some(profile_did),
+                    Some(profile_did),
+                    &atm.inner.tdk_common.did_resolver,
+                    &atm.inner.tdk_common.secrets_resolver,
+                    &PackEncryptedOptions::default(),
+                )
+                .await
+                .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+            match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+                SendMessageResponse::Message(message) => {
+                    self._parse_account_change_queue_limit_response(&message)
+                }
+                _ => Err(ATMError::MsgReceiveError(
+                    "No response from mediator".to_owned(),
+                )),
+            }
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Change the Rate Limit for a DID (admin only)
+    /// - `atm` - The ATM client to use
+    /// - `profile` - The profile to use
+    /// - `did_hash` - The DID hash to change the rate limit for
+    /// - `rate_limit` - Requests per minute
+    ///
+    /// NOTE: rate_limit values
+    ///       - -1: Unlimited
+    ///       - -2: Reset to the mediator limits
+    ///       - n: Set to n
+    ///
+    /// # Returns
+    /// The rate limit now set on the account (None if the mediator limits apply)
+    pub async fn account_change_rate_limit(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        did_hash: &str,
+        rate_limit: i32,
+    ) -> Result<AccountChangeRateLimitResponse, ATMError> {
+        let _span = span!(Level::DEBUG, "account_change_rate_limit");
+
+        async move {
+            debug!(
+                "Changing account ({}) rate_limit to ({}).",
+                did_hash, rate_limit
+            );
+
+            let (profile_did, mediator_did) = profile.dids()?;
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let msg = Message::build(
+                Uuid::new_v4().into(),
+                "https://didcomm.org/mediator/1.0/account-management".to_owned(),
+                json!({"account_change_rate_limit": {"did_hash": did_hash, "rate_limit": rate_limit}}),
+            )
+            .to(mediator_did.into())
+            .from(profile_did.into())
+            .created_time(now)
+            .expires_time(now + 10)
+            .finalize();
+
+            let msg_id = msg.id.clone();
+
+            // Pack the message
+            let (msg, _) = msg
+                .pack_encrypted(
+                    mediator_did,
+                    Some(profile_did),
+                    Some(profile_did),
+                    &atm.inner.tdk_common.did_resolver,
+                    &atm.inner.tdk_common.secrets_resolver,
+                    &PackEncryptedOptions::default(),
+                )
+                .await
+                .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+            match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+                SendMessageResponse::Message(message) => {
+                    serde_json::from_value(message.body.clone()).map_err(|err| {
+                        ATMError::MsgReceiveError(format!(
+                            "Mediator Account Change Rate Limit response could not be parsed. Reason: {}",
+                            err
+                        ))
+                    })
+                }
+                _ => Err(ATMError::MsgReceiveError(
+                    "No response from mediator".to_owned(),
+                )),
+            }
+        }
+        .instrument(_span)
+        .await
+    }
 }
@@ -1,6 +1,7 @@
 //! Handles mediator configuration and administration tasks
 //! Admin account management
 //! Global ACL management
+//! Audit log of administrative changes
 
 use crate::{ATM, errors::ATMError, profiles::ATMProfile, transports::SendMessageResponse};
 use affinidi_messaging_didcomm::{Message, PackEncryptedOptions};
@@ -8,7 +9,11 @@ use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use sha256::digest;
-use std::{sync::Arc, time::SystemTime};
+use std::{
+    fmt::{self, Display, Formatter},
+    sync::Arc,
+    time::SystemTime,
+};
 use tracing::{Instrument, Level, debug, span};
 use uuid::Uuid;
 
@@ -29,6 +34,13 @@ pub enum MediatorAdminRequest {
         limit: u32,
     },
     Configuration(Value),
+    #[serde(rename = "audit_log")]
+    AuditLog {
+        cursor: Option<String>,
+        limit: u32,
+        #[serde(default)]
+        filter: AuditLogFilter,
+    },
 }
 
 /// A list of admins in the mediator
@@ -47,6 +59,95 @@ pub struct AdminAccount {
     pub _type: AccountType,
 }
 
+/// Administrative changes that are recorded in the audit log
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    AccountAdd,
+    AccountRemove,
+    AccountChangeType,
+    AccountChangeQueueLimits,
+    AccountChangeRateLimit,
+    AclSet,
+    AccessListAdd,
+    AccessListRemove,
+    AccessListClear,
+    AdminAdd,
+    AdminStrip,
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditAction::AccountAdd => write!(f, "Account Add"),
+            AuditAction::AccountRemove => write!(f, "Account Remove"),
+            AuditAction::AccountChangeType => write!(f, "Account Change Type"),
+            AuditAction::AccountChangeQueueLimits => write!(f, "Account Change Queue Limits"),
+            AuditAction::AccountChangeRateLimit => write!(f, "Account Change Rate Limit"),
+            AuditAction::AclSet => write!(f, "ACL Set"),
+            AuditAction::AccessListAdd => write!(f, "Access List Add"),
+            AuditAction::AccessListRemove => write!(f, "Access List Remove"),
+            AuditAction::AccessListClear => write!(f, "Access List Clear"),
+            AuditAction::AdminAdd => write!(f, "Admin Add"),
+            AuditAction::AdminStrip => write!(f, "Admin Strip"),
+        }
+    }
+}
+
+/// A single entry in the audit log
+/// - `id` - Position of the entry in the audit log (used as the cursor)
+/// - `timestamp` - When the change was made (seconds since UNIX EPOCH)
+/// - `actor` - SHA256 Hashed DID that made the change
+/// - `target` - SHA256 Hashed DID that was changed
+/// - `acls_before` - ACLs (hex) of the target before the change, if the target existed
+/// - `acls_after` - ACLs (hex) of the target after the change, if the target still exists
+/// - `detail` - Additional information about the change (e.g. the new queue limits)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub timestamp: u64,
+    pub actor: String,
+    pub action: AuditAction,
+    pub target: String,
+    pub acls_before: Option<String>,
+    pub acls_after: Option<String>,
+    pub detail: Option<String>,
+}
+
+/// Restricts the audit log entries returned, all set fields must match
+/// - `actor` - SHA256 Hashed DID that made the change
+/// - `target` - SHA256 Hashed DID that was changed
+/// - `action` - Type of change
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub actor: Option<String>,
+    pub target: Option<String>,
+    pub action: Option<AuditAction>,
+}
+
+impl AuditLogFilter {
+    /// Does the entry match this filter?
+    pub fn matches(&self, entry: &AuditLogEntry) -> bool {
+        self.actor
+            .as_ref()
+            .is_none_or(|actor| actor == &entry.actor)
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|target| target == &entry.target)
+            && self.action.is_none_or(|action| action == entry.action)
+    }
+}
+
+/// A page of the audit log, newest entries first
+/// - `entries` - Audit log entries matching the filter
+/// - `cursor` - Cursor for the next page, None when there are no older entries
+#[derive(Serialize, Deserialize)]
+pub struct MediatorAuditLog {
+    pub entries: Vec<AuditLogEntry>,
+    pub cursor: Option<String>,
+}
+
 impl Mediator {
     pub async fn get_config(
         &self,
@@ -373,4 +474,82 @@ impl Mediator {
         .instrument(_span)
         .await
     }
+
+    /// Parses the response from the mediator for the audit log
+    fn _parse_audit_log_response(&self, message: &Message) -> Result<MediatorAuditLog, ATMError> {
+        serde_json::from_value(message.body.clone()).map_err(|err| {
+            ATMError::MsgReceiveError(format!(
+                "Mediator Audit Log response could not be parsed. Reason: {}",
+                err
+            ))
+        })
+    }
+
+    /// Reads the audit log of administrative changes, newest entries first
+    /// - `atm` - The ATM client to use
+    /// - `cursor` - Cursor returned by the previous page (None starts at the newest entry)
+    /// - `limit` - The maximum number of entries to return (Defaults to 100 if not provided)
+    /// - `filter` - Only return entries matching the filter
+    /// # Returns
+    /// A page of the audit log
+    pub async fn audit_log(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        cursor: Option<String>,
+        limit: Option<u32>,
+        filter: Option<AuditLogFilter>,
+    ) -> Result<MediatorAuditLog, ATMError> {
+        let _span = span!(Level::DEBUG, "audit_log");
+
+        async move {
+            debug!(
+                "Requesting audit log from mediator. Cursor: {:?} Limit: {}",
+                cursor,
+                limit.unwrap_or(100)
+            );
+
+            let (profile_did, mediator_did) = profile.dids()?;
+
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+
+            let msg = Message::build(
+                Uuid::new_v4().into(),
+                "https://didcomm.org/mediator/1.0/admin-management".to_owned(),
+                json!({"audit_log": {"cursor": cursor, "limit": limit.unwrap_or(100), "filter": filter.unwrap_or_default()}}),
+            )
+            .to(mediator_did.into())
+            .from(profile_did.into())
+            .created_time(now)
+            .expires_time(now + 10)
+            .finalize();
+
+            let msg_id = msg.id.clone();
+
+            // Pack the message
+            let (msg, _) = msg
+                .pack_encrypted(
+                    mediator_did,
+                    Some(profile_did),
+                    Some(profile_did),
+                    &atm.inner.tdk_common.did_resolver,
+                    &atm.inner.tdk_common.secrets_resolver,
+                    &PackEncryptedOptions::default(),
+                )
+                .await
+                .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+            match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+                SendMessageResponse::Message(message) => self._parse_audit_log_response(&message),
+                _ => Err(ATMError::MsgReceiveError(
+                    "No response from mediator".to_owned(),
+                )),
+            }
+        }
+        .instrument(_span)
+        .await
+    }
 }
@@ -3,3 +3,4 @@ pub mod acls;
 pub mod acls_handler;
 #[allow(clippy::module_inception)]
 pub mod administration;
+pub mod push_notifications;
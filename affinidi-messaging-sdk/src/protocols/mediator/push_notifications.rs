@@ -0,0 +1,230 @@
+//! Push notification targets
+//!
+//! A DID can register one push target with the mediator. When messages are queued for the DID
+//! while it has no live (WebSocket) connection, the mediator notifies the target instead.
+//! Notifications are batched per DID and never contain message content, only how many messages
+//! are waiting (see [PushNotification]).
+//!
+//! Webhook notifications are POSTed as JSON with the headers:
+//! - `X-ATM-Timestamp`: Epoch seconds when the notification was sent
+//! - `X-ATM-Signature`: `sha256=<hex>`, HMAC-SHA256 of `<timestamp>.<body>` keyed with the webhook secret
+use affinidi_messaging_didcomm::{Message, PackEncryptedOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{sync::Arc, time::SystemTime};
+use tracing::{Instrument, Level, debug, span};
+use uuid::Uuid;
+
+use super::administration::Mediator;
+use crate::{ATM, errors::ATMError, profiles::ATMProfile, transports::SendMessageResponse};
+
+#[derive(Serialize, Deserialize)]
+pub enum MediatorPushRequest {
+    #[serde(rename = "push_register")]
+    PushRegister(PushTarget),
+    #[serde(rename = "push_get")]
+    PushGet,
+    #[serde(rename = "push_remove")]
+    PushRemove,
+}
+
+/// Where the mediator sends push notifications for a DID
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PushTarget {
+    /// HTTPS endpoint, notifications are signed with `secret` (see the module documentation)
+    Webhook { url: String, secret: String },
+    /// Firebase Cloud Messaging registration token
+    Fcm { token: String },
+    /// Apple Push Notification service device token
+    Apns { token: String },
+}
+
+impl PushTarget {
+    /// Copy of the target that is safe to return to clients (webhook secret removed)
+    pub fn redacted(&self) -> Self {
+        match self {
+            PushTarget::Webhook { url, .. } => PushTarget::Webhook {
+                url: url.clone(),
+                secret: String::new(),
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+/// Body of a push notification
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PushNotification {
+    /// SHA256 hash of the DID that has messages waiting
+    pub did_hash: String,
+    /// Number of messages queued since the last notification
+    pub count: u32,
+    /// Epoch seconds when the notification was sent
+    pub timestamp: u64,
+}
+
+impl Mediator {
+    /// Registers a push target for the profile's DID, replacing any existing target
+    /// - `atm` - The ATM client to use
+    /// - `profile` - The profile (DID) to notify
+    /// - `target` - Where to send notifications
+    /// # Returns
+    /// true if the target was registered
+    pub async fn push_register(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+        target: &PushTarget,
+    ) -> Result<bool, ATMError> {
+        let _span = span!(Level::DEBUG, "push_register");
+
+        async move {
+            debug!("Registering push target");
+            let message =
+                _send_push_request(atm, profile, json!({"push_register": target})).await?;
+            _parse_push_response(message, "Register")
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Gets the push target registered for the profile's DID (webhook secret is not returned)
+    /// - `atm` - The ATM client to use
+    /// - `profile` - The profile (DID) to get the target for
+    pub async fn push_get(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+    ) -> Result<Option<PushTarget>, ATMError> {
+        let _span = span!(Level::DEBUG, "push_get");
+
+        async move {
+            debug!("Getting push target");
+            let message = _send_push_request(atm, profile, json!("push_get")).await?;
+            _parse_push_response(message, "Get")
+        }
+        .instrument(_span)
+        .await
+    }
+
+    /// Removes the push target registered for the profile's DID
+    /// - `atm` - The ATM client to use
+    /// - `profile` - The profile (DID) to remove the target for
+    /// # Returns
+    /// true if a target was removed
+    pub async fn push_remove(
+        &self,
+        atm: &ATM,
+        profile: &Arc<ATMProfile>,
+    ) -> Result<bool, ATMError> {
+        let _span = span!(Level::DEBUG, "push_remove");
+
+        async move {
+            debug!("Removing push target");
+            let message = _send_push_request(atm, profile, json!("push_remove")).await?;
+            _parse_push_response(message, "Remove")
+        }
+        .instrument(_span)
+        .await
+    }
+}
+
+/// Sends a push notification request to the mediator and waits for the response
+async fn _send_push_request(
+    atm: &ATM,
+    profile: &Arc<ATMProfile>,
+    body: Value,
+) -> Result<Message, ATMError> {
+    let (profile_did, mediator_did) = profile.dids()?;
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let msg = Message::build(
+        Uuid::new_v4().into(),
+        "https://didcomm.org/mediator/1.0/push-notifications".to_owned(),
+        body,
+    )
+    .to(mediator_did.into())
+    .from(profile_did.into())
+    .created_time(now)
+    .expires_time(now + 10)
+    .finalize();
+
+    let msg_id = msg.id.clone();
+
+    // Pack the message
+    let (msg, _) = msg
+        .pack_encrypted(
+            mediator_did,
+            Some(profile_did),
+            Some(profile_did),
+            &atm.inner.tdk_common.did_resolver,
+            &atm.inner.tdk_common.secrets_resolver,
+            &PackEncryptedOptions::default(),
+        )
+        .await
+        .map_err(|e| ATMError::MsgSendError(format!("Error packing message: {}", e)))?;
+
+    match atm.send_message(profile, &msg, &msg_id, true, true).await? {
+        SendMessageResponse::Message(message) => Ok(message),
+        _ => Err(ATMError::MsgReceiveError(
+            "No response from mediator".to_owned(),
+        )),
+    }
+}
+
+fn _parse_push_response<T>(message: Message, request: &str) -> Result<T, ATMError>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    serde_json::from_value(message.body).map_err(|err| {
+        ATMError::MsgReceiveError(format!(
+            "Mediator Push {} response could not be parsed. Reason: {}",
+            request, err
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_request_serialization() {
+        let request = MediatorPushRequest::PushRegister(PushTarget::Webhook {
+            url: "https://example.com/notify".into(),
+            secret: "secret".into(),
+        });
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({"push_register": {"type": "webhook", "url": "https://example.com/notify", "secret": "secret"}})
+        );
+
+        let request: MediatorPushRequest = serde_json::from_value(json!("push_get")).unwrap();
+        assert!(matches!(request, MediatorPushRequest::PushGet));
+    }
+
+    #[test]
+    fn test_redacted() {
+        let target = PushTarget::Webhook {
+            url: "https://example.com/notify".into(),
+            secret: "secret".into(),
+        };
+        assert_eq!(
+            target.redacted(),
+            PushTarget::Webhook {
+                url: "https://example.com/notify".into(),
+                secret: String::new(),
+            }
+        );
+
+        let target = PushTarget::Fcm {
+            token: "token".into(),
+        };
+        assert_eq!(target.redacted(), target);
+    }
+}
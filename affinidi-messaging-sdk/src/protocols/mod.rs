@@ -5,6 +5,8 @@ use mediator::administration::Mediator;
 
 #[derive(Default)]
 pub struct Protocols {
+    pub coordinate_mediation: coordinate_mediation::CoordinateMediation,
+    pub discover_features: discover_features::DiscoverFeatures,
     pub message_pickup: message_pickup::MessagePickup,
     pub trust_ping: trust_ping::TrustPing,
     pub routing: routing::Routing,
@@ -12,6 +14,8 @@ pub struct Protocols {
     pub oob_discovery: oob_discovery::OOBDiscovery,
 }
 
+pub mod coordinate_mediation;
+pub mod discover_features;
 pub mod mediator;
 pub mod message_pickup;
 pub mod oob_discovery;
@@ -24,6 +28,8 @@ impl GenericDataStruct for MessageString {}
 impl Protocols {
     pub fn new() -> Protocols {
         Protocols {
+            coordinate_mediation: coordinate_mediation::CoordinateMediation::default(),
+            discover_features: discover_features::DiscoverFeatures::default(),
             message_pickup: message_pickup::MessagePickup::default(),
             trust_ping: trust_ping::TrustPing::default(),
             routing: routing::Routing::default(),
@@ -26,7 +26,7 @@ impl Routing {
     /// - next_did: The DID of the next agent to forward the message to
     /// - expires_time: The time at which the message expires if not delivered
     /// - delay_milli: The time to wait before delivering the message
-    ///   NOTE: If negative, picks a random delay between 0 and the absolute value
+    ///   NOTE: If negative, the message is delivered no earlier than the absolute value
     ///
     /// Returns:
     ///     (message_id, message)
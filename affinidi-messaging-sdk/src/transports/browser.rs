@@ -0,0 +1,412 @@
+/*!
+ * Browser transport for `wasm32` builds (`browser` feature)
+ *
+ * Uses the global `fetch()` for the mediator REST API and the global `WebSocket` for live
+ * delivery, so it works in browsers, web workers and Node.js (v22+).
+ *
+ * Messages are sent and received packed, pack and unpack them with `affinidi-messaging-didcomm`
+ * (`wasm` feature). Authentication is done by the caller using [BrowserTransport::post] against
+ * `/authenticate/challenge` and `/authenticate`, the resulting access token is then set with
+ * [BrowserTransport::set_access_token].
+ *
+ * Browsers can't set an Authorization header on a WebSocket, the access token is offered to the
+ * mediator as a WebSocket sub-protocol instead.
+ */
+
+use crate::{
+    errors::ATMError,
+    messages::{GenericDataStruct, SuccessResponse, sending::InboundMessageResponse},
+};
+use futures_channel::mpsc::{UnboundedReceiver, unbounded};
+use futures_util::StreamExt;
+use js_sys::{Array, ArrayBuffer, Promise, Uint8Array};
+use tracing::{debug, warn};
+use wasm_bindgen::{JsCast, JsValue, closure::Closure, prelude::wasm_bindgen};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    BinaryType, CloseEvent, Event, MessageEvent, Request, RequestInit, Response, WebSocket,
+};
+
+/// WebSocket sub-protocol the mediator selects for browser clients
+const WEBSOCKET_PROTOCOL: &str = "atm";
+/// Prefix of the sub-protocol that carries the access token
+const WEBSOCKET_BEARER_PREFIX: &str = "atm.bearer.";
+
+#[wasm_bindgen]
+extern "C" {
+    /// Global `fetch()`, unlike `window.fetch()` this also exists in web workers and Node.js
+    #[wasm_bindgen(js_name = fetch)]
+    fn global_fetch(request: &Request) -> Promise;
+}
+
+/// Mediator transport using the browser `fetch()` and `WebSocket` APIs
+#[derive(Clone, Debug)]
+pub struct BrowserTransport {
+    rest_endpoint: String,
+    websocket_endpoint: Option<String>,
+    access_token: Option<String>,
+}
+
+impl BrowserTransport {
+    /// Creates a transport for a mediator
+    /// - rest_endpoint: Mediator REST API endpoint (e.g. `https://localhost:7037/mediator/v1`)
+    pub fn new(rest_endpoint: &str) -> Self {
+        BrowserTransport {
+            rest_endpoint: rest_endpoint.trim_end_matches('/').to_string(),
+            websocket_endpoint: None,
+            access_token: None,
+        }
+    }
+
+    /// Sets the WebSocket endpoint, defaults to `<rest_endpoint>/ws` using `ws(s)://`
+    pub fn with_websocket_endpoint(mut self, websocket_endpoint: &str) -> Self {
+        self.websocket_endpoint = Some(websocket_endpoint.to_string());
+        self
+    }
+
+    /// Sets the access token used for authenticated requests and the WebSocket
+    pub fn set_access_token(&mut self, access_token: &str) {
+        self.access_token = Some(access_token.to_string());
+    }
+
+    /// Returns the WebSocket endpoint of the mediator
+    pub fn websocket_endpoint(&self) -> String {
+        if let Some(endpoint) = &self.websocket_endpoint {
+            return endpoint.clone();
+        }
+
+        let endpoint = if let Some(endpoint) = self.rest_endpoint.strip_prefix("https://") {
+            ["wss://", endpoint].concat()
+        } else if let Some(endpoint) = self.rest_endpoint.strip_prefix("http://") {
+            ["ws://", endpoint].concat()
+        } else {
+            self.rest_endpoint.clone()
+        };
+        [&endpoint, "/ws"].concat()
+    }
+
+    /// POSTs a JSON body to the mediator REST API
+    /// - path: API path (e.g. `/authenticate/challenge`)
+    /// - body: JSON body (or a packed DIDComm message)
+    pub async fn post<T>(&self, path: &str, body: &str) -> Result<SuccessResponse<T>, ATMError>
+    where
+        T: GenericDataStruct,
+    {
+        let url = [&self.rest_endpoint, path].concat();
+
+        let init = RequestInit::new();
+        init.set_method("POST");
+        init.set_body(&JsValue::from_str(body));
+        let request = Request::new_with_str_and_init(&url, &init).map_err(transport_error)?;
+        let headers = request.headers();
+        headers
+            .set("Content-Type", "application/json")
+            .map_err(transport_error)?;
+        if let Some(access_token) = &self.access_token {
+            headers
+                .set("Authorization", &format!("Bearer {}", access_token))
+                .map_err(transport_error)?;
+        }
+
+        let response: Response = JsFuture::from(global_fetch(&request))
+            .await
+            .map_err(|err| {
+                ATMError::TransportError(format!(
+                    "HTTP POST failed ({}): {}",
+                    url,
+                    js_error_string(&err)
+                ))
+            })?
+            .dyn_into()
+            .map_err(transport_error)?;
+
+        let status = response.status();
+        let body = JsFuture::from(response.text().map_err(transport_error)?)
+            .await
+            .map_err(transport_error)?
+            .as_string()
+            .unwrap_or_default();
+        debug!("API response: status({})", status);
+
+        if status == 401 {
+            return Err(ATMError::ACLDenied("Authentication Denied".into()));
+        } else if !response.ok() {
+            return Err(ATMError::TransportError(format!(
+                "API returned an error: status({}), body({})",
+                status, body
+            )));
+        }
+
+        serde_json::from_str::<SuccessResponse<T>>(&body)
+            .map_err(|e| ATMError::TransportError(format!("Couldn't parse response: {:?}", e)))
+    }
+
+    /// Sends a packed DIDComm message to the mediator (`/inbound`)
+    pub async fn send_message(&self, message: &str) -> Result<InboundMessageResponse, ATMError> {
+        let response = self
+            .post::<InboundMessageResponse>("/inbound", message)
+            .await?;
+
+        Ok(response.data.unwrap_or(InboundMessageResponse::Empty))
+    }
+
+    /// Opens a WebSocket to the mediator, requires an access token
+    pub async fn websocket(&self) -> Result<BrowserWebSocket, ATMError> {
+        let Some(access_token) = &self.access_token else {
+            return Err(ATMError::AuthenticationError(
+                "An access token is required to open a WebSocket".into(),
+            ));
+        };
+
+        BrowserWebSocket::connect(&self.websocket_endpoint(), access_token).await
+    }
+}
+
+/// Events passed from the WebSocket callbacks to [BrowserWebSocket]
+enum WsEvent {
+    Open,
+    Message(String),
+    Error(String),
+    Closed(String),
+}
+
+/// WebSocket connection to the mediator
+/// Received messages are packed DIDComm messages
+pub struct BrowserWebSocket {
+    socket: WebSocket,
+    events: UnboundedReceiver<WsEvent>,
+    closed: bool,
+    _on_open: Closure<dyn FnMut(Event)>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_error: Closure<dyn FnMut(Event)>,
+    _on_close: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl BrowserWebSocket {
+    async fn connect(url: &str, access_token: &str) -> Result<Self, ATMError> {
+        let protocols = Array::of2(
+            &WEBSOCKET_PROTOCOL.into(),
+            &[WEBSOCKET_BEARER_PREFIX, access_token].concat().into(),
+        );
+        let socket = WebSocket::new_with_str_sequence(url, &protocols).map_err(transport_error)?;
+        socket.set_binary_type(BinaryType::Arraybuffer);
+
+        let (tx, events) = unbounded::<WsEvent>();
+
+        let _on_open = {
+            let tx = tx.clone();
+            Closure::<dyn FnMut(Event)>::new(move |_: Event| {
+                let _ = tx.unbounded_send(WsEvent::Open);
+            })
+        };
+        let _on_message = {
+            let tx = tx.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                let data = event.data();
+                let message = if let Some(text) = data.as_string() {
+                    text
+                } else if let Some(buffer) = data.dyn_ref::<ArrayBuffer>() {
+                    let bytes = Uint8Array::new(buffer).to_vec();
+                    match String::from_utf8(bytes) {
+                        Ok(text) => text,
+                        Err(err) => {
+                            warn!("Received binary message that isn't UTF-8: {}", err);
+                            return;
+                        }
+                    }
+                } else {
+                    warn!("Received unknown WebSocket message type");
+                    return;
+                };
+                let _ = tx.unbounded_send(WsEvent::Message(message));
+            })
+        };
+        let _on_error = {
+            let tx = tx.clone();
+            Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+                let _ = tx.unbounded_send(WsEvent::Error(event.type_()));
+            })
+        };
+        let _on_close = Closure::<dyn FnMut(CloseEvent)>::new(move |event: CloseEvent| {
+            let _ = tx.unbounded_send(WsEvent::Closed(format!(
+                "code({}) reason({})",
+                event.code(),
+                event.reason()
+            )));
+        });
+
+        socket.set_onopen(Some(_on_open.as_ref().unchecked_ref()));
+        socket.set_onmessage(Some(_on_message.as_ref().unchecked_ref()));
+        socket.set_onerror(Some(_on_error.as_ref().unchecked_ref()));
+        socket.set_onclose(Some(_on_close.as_ref().unchecked_ref()));
+
+        let mut ws = BrowserWebSocket {
+            socket,
+            events,
+            closed: false,
+            _on_open,
+            _on_message,
+            _on_error,
+            _on_close,
+        };
+
+        // Wait for the connection to open (or fail)
+        loop {
+            let event = ws.events.next().await;
+            match event {
+                Some(WsEvent::Open) => {
+                    debug!("WebSocket connected to ({})", url);
+                    return Ok(ws);
+                }
+                Some(WsEvent::Error(_)) => continue,
+                Some(WsEvent::Closed(reason)) => {
+                    ws.closed = true;
+                    return Err(ATMError::TransportError(format!(
+                        "WebSocket connection to ({}) failed: {}",
+                        url, reason
+                    )));
+                }
+                Some(WsEvent::Message(_)) => continue,
+                None => {
+                    ws.closed = true;
+                    return Err(ATMError::TransportError(format!(
+                        "WebSocket connection to ({}) failed",
+                        url
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Sends a packed DIDComm message
+    pub fn send(&self, message: &str) -> Result<(), ATMError> {
+        if self.closed {
+            return Err(ATMError::TransportError("WebSocket is closed".into()));
+        }
+        self.socket.send_with_str(message).map_err(|err| {
+            ATMError::TransportError(format!(
+                "Couldn't send WebSocket message: {}",
+                js_error_string(&err)
+            ))
+        })
+    }
+
+    /// Returns the next message received from the mediator
+    /// Returns None once the WebSocket is closed
+    pub async fn next(&mut self) -> Option<Result<String, ATMError>> {
+        if self.closed {
+            return None;
+        }
+
+        loop {
+            match self.events.next().await {
+                Some(WsEvent::Message(message)) => return Some(Ok(message)),
+                Some(WsEvent::Error(error)) => {
+                    return Some(Err(ATMError::TransportError(format!(
+                        "WebSocket error: {}",
+                        error
+                    ))));
+                }
+                Some(WsEvent::Closed(reason)) => {
+                    debug!("WebSocket closed: {}", reason);
+                    self.closed = true;
+                    return None;
+                }
+                Some(WsEvent::Open) => continue,
+                None => {
+                    self.closed = true;
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Closes the WebSocket
+    pub fn close(&mut self) {
+        if !self.closed {
+            let _ = self.socket.close();
+            self.closed = true;
+        }
+    }
+}
+
+impl Drop for BrowserWebSocket {
+    fn drop(&mut self) {
+        // The callbacks are freed with this struct, so they must be removed from the socket first
+        self.socket.set_onopen(None);
+        self.socket.set_onmessage(None);
+        self.socket.set_onerror(None);
+        self.socket.set_onclose(None);
+        self.close();
+    }
+}
+
+fn js_error_string(err: &JsValue) -> String {
+    if let Some(err) = err.dyn_ref::<js_sys::Error>() {
+        String::from(err.message())
+    } else if let Some(err) = err.as_string() {
+        err
+    } else {
+        format!("{:?}", err)
+    }
+}
+
+fn transport_error(err: JsValue) -> ATMError {
+    ATMError::TransportError(js_error_string(&err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::EmptyResponse;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn test_websocket_endpoint() {
+        assert_eq!(
+            BrowserTransport::new("https://localhost:7037/mediator/v1/").websocket_endpoint(),
+            "wss://localhost:7037/mediator/v1/ws"
+        );
+        assert_eq!(
+            BrowserTransport::new("http://localhost:7037/mediator/v1").websocket_endpoint(),
+            "ws://localhost:7037/mediator/v1/ws"
+        );
+        assert_eq!(
+            BrowserTransport::new("https://localhost:7037/mediator/v1")
+                .with_websocket_endpoint("wss://ws.example.com/ws")
+                .websocket_endpoint(),
+            "wss://ws.example.com/ws"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_post_unreachable() {
+        let transport = BrowserTransport::new("http://127.0.0.1:1/mediator/v1");
+
+        assert!(matches!(
+            transport.post::<EmptyResponse>("/inbound", "{}").await,
+            Err(ATMError::TransportError(_))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_websocket_requires_access_token() {
+        let transport = BrowserTransport::new("http://127.0.0.1:1/mediator/v1");
+
+        assert!(matches!(
+            transport.websocket().await,
+            Err(ATMError::AuthenticationError(_))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn test_websocket_unreachable() {
+        let mut transport = BrowserTransport::new("http://127.0.0.1:1/mediator/v1");
+        transport.set_access_token("token");
+
+        assert!(matches!(
+            transport.websocket().await,
+            Err(ATMError::TransportError(_))
+        ));
+    }
+}
@@ -1,17 +1,26 @@
+use crate::messages::GenericDataStruct;
+#[cfg(not(target_arch = "wasm32"))]
 use crate::{
     ATM,
     errors::ATMError,
-    messages::{GenericDataStruct, GetMessagesRequest, known::MessageType},
+    messages::{GetMessagesRequest, known::MessageType},
     profiles::ATMProfile,
     protocols::{message_pickup::MessagePickup, routing::Routing},
 };
 use affinidi_messaging_didcomm::Message;
 use serde_json::Value;
+#[cfg(not(target_arch = "wasm32"))]
 use sha256::digest;
+#[cfg(not(target_arch = "wasm32"))]
 use std::{sync::Arc, time::Duration};
+#[cfg(not(target_arch = "wasm32"))]
 use tracing::debug;
+#[cfg(not(target_arch = "wasm32"))]
 use websockets::ws_connection::WsConnectionCommands;
 
+#[cfg(all(feature = "browser", target_arch = "wasm32"))]
+pub mod browser;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod websockets;
 
 /// WebSocketSendResponse is the response from sending a message over a WebSocket connection
@@ -50,6 +59,7 @@ impl SendMessageResponse {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl ATM {
     /// Send a message to a mediator based on a given profile
     /// - profile: The profile to connect to the mediator with
@@ -169,7 +179,7 @@ impl ATM {
     /// - next_did: The DID of the next agent to forward the message to
     /// - expires_time: The time at which the message expires if not delivered
     /// - delay_milli: The time to wait before delivering the message
-    ///   NOTE: If negative, picks a random delay between 0 and the absolute value
+    ///   NOTE: If negative, the message is delivered no earlier than the absolute value
     /// - wait_for_response: Whether to wait for a message response from the mediator
     #[allow(clippy::too_many_arguments)]
     pub async fn forward_and_send_message(
@@ -5,20 +5,28 @@ These connections are managed by the `WsHandler` and are created and destroyed a
 
 Each WsConnection is a tokio parallel task, and responsible for unpacking incoming messages
 
+The access token is refreshed in-band over the websocket shortly before it expires, so the
+mediator doesn't disconnect the websocket when the original access token expires.
+
 */
 use super::SharedState;
 use crate::{
-    ATM, errors::ATMError, profiles::ATMProfile, protocols::Protocols,
+    ATM, authentication::AuthRefreshResponse, errors::ATMError,
+    messages::known::MessageType as SDKMessageType, profiles::ATMProfile, protocols::Protocols,
     transports::websockets::utils::connect,
 };
 use affinidi_messaging_didcomm::{Message as DidcommMessage, UnpackMetadata};
-use std::{pin::Pin, sync::Arc, time::Duration};
+use std::{
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use tokio::{
     io::{AsyncRead, AsyncWrite, BufReader},
     select,
     sync::mpsc::{Receiver, Sender},
     task::JoinHandle,
-    time::{interval_at, sleep},
+    time::{Instant, interval_at, sleep},
 };
 use tracing::{Instrument, debug, error, span, warn};
 use url::Url;
@@ -39,6 +47,11 @@ pub(crate) enum WsConnectionCommands {
     MessageReceived(Box<(DidcommMessage, UnpackMetadata)>),
 }
 
+/// Refresh the access token this many seconds before it expires
+const REFRESH_BEFORE_EXPIRY: u64 = 60;
+/// Minimum seconds between in-band refresh attempts
+const REFRESH_RETRY: u64 = 10;
+
 /// The following is to help with handling either TCP or TLS connections
 pub(crate) trait ReadWrite: AsyncRead + AsyncWrite + Send {}
 impl<T> ReadWrite for T where T: AsyncRead + AsyncWrite + Send {}
@@ -110,9 +123,18 @@ impl WsConnection {
             let mut direct_channel: Option<Sender<Box<(DidcommMessage, UnpackMetadata)>>> = None;
             let mut watchdog = interval_at(tokio::time::Instant::now()+Duration::from_secs(20), Duration::from_secs(20));
 
+            // Refreshes the access token before the mediator closes the websocket
+            let refresh_timer = sleep(self._refresh_delay().await);
+            tokio::pin!(refresh_timer);
+            let mut refresh_msg_id: Option<String> = None;
+
             let mut missed_pings = 0;
             loop {
                 select! {
+                    _ = &mut refresh_timer => {
+                        refresh_msg_id = self._refresh_in_band(&mut web_socket).await;
+                        refresh_timer.as_mut().reset(Instant::now() + self._refresh_delay().await);
+                    }
                     _ = watchdog.tick() => {
                         let _ = web_socket.send_ping(vec![]).await;
                         if missed_pings > 2 {
@@ -155,6 +177,17 @@ impl WsConnection {
                                                 continue;
                                             }
                                         };
+
+                                        // Handle our own refresh response, responses to other connections of this DID are ignored
+                                        if let Ok(SDKMessageType::AffinidiAuthenticateRefreshResponse) = unpack.0.type_.parse::<SDKMessageType>() {
+                                            if unpack.0.thid.is_some() && unpack.0.thid == refresh_msg_id {
+                                                refresh_msg_id = None;
+                                                self._update_access_token(&unpack.0).await;
+                                                refresh_timer.as_mut().reset(Instant::now() + self._refresh_delay().await);
+                                            }
+                                            continue;
+                                        }
+
                                         match &direct_channel {
                                             Some(sender) => {
                                                 let _ = sender.send(Box::new(unpack)).await;
@@ -253,6 +286,86 @@ impl WsConnection {
         Ok(())
     }
 
+    /// How long to wait before refreshing the access token in-band
+    async fn _refresh_delay(&self) -> Duration {
+        let Some(tokens) = &*self.profile.inner.authorization.lock().await else {
+            return Duration::from_secs(REFRESH_RETRY);
+        };
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Duration::from_secs(
+            tokens
+                .access_expires_at
+                .saturating_sub(now + REFRESH_BEFORE_EXPIRY)
+                .max(REFRESH_RETRY),
+        )
+    }
+
+    /// Sends a refresh request over the websocket if the access token is about to expire
+    /// Returns the ID of the refresh message if one was sent
+    async fn _refresh_in_band(
+        &self,
+        web_socket: &mut WebSocket<BufReader<Pin<Box<dyn ReadWrite>>>>,
+    ) -> Option<String> {
+        let tokens = self.profile.inner.authorization.lock().await.clone()?;
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        // Tokens may have already been refreshed (e.g. on reconnect)
+        if tokens.access_expires_at > now + REFRESH_BEFORE_EXPIRY {
+            return None;
+        }
+
+        // A new session is created when the websocket reconnects
+        if tokens.refresh_expires_at <= now {
+            warn!("Refresh token has expired, can't refresh the websocket session");
+            return None;
+        }
+
+        let (msg_id, msg) = match self
+            .profile
+            ._create_refresh_request(&tokens.refresh_token, &self.shared)
+            .await
+        {
+            Ok(refresh) => refresh,
+            Err(err) => {
+                error!("Error creating refresh request: {:?}", err);
+                return None;
+            }
+        };
+
+        debug!("Refreshing access token in-band");
+        match web_socket.send(msg.as_str()).await {
+            Ok(_) => Some(msg_id),
+            Err(err) => {
+                error!("Error sending refresh request: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /// Updates the profile with the new access token from a refresh response
+    async fn _update_access_token(&self, message: &DidcommMessage) {
+        let refreshed: AuthRefreshResponse = match serde_json::from_value(message.body.clone()) {
+            Ok(refreshed) => refreshed,
+            Err(err) => {
+                warn!("Couldn't parse refresh response. Reason: {}", err);
+                return;
+            }
+        };
+
+        if let Some(tokens) = self.profile.inner.authorization.lock().await.as_mut() {
+            tokens.access_token = refreshed.access_token;
+            tokens.access_expires_at = refreshed.access_expires_at;
+            debug!("Access token refreshed in-band");
+        }
+    }
+
     // Wrapper that handles all of the logic of setting up a connection to the mediator
     async fn _handle_connection(
         &mut self,